@@ -0,0 +1,233 @@
+//! Achievements, goals to go after across all of the runs of a save.
+//!
+//! The achievements are described in the TOML files of the `achievements` folder, so mods can
+//! bring their own:
+//!
+//! ```toml
+//! [[achievement]]
+//! id = "demolition"
+//! name = "Demolition Crab"
+//! description = "Blow up 100 barrels"
+//! counter = "barrels_exploded"
+//! goal = 100
+//! ```
+//!
+//! Every achievement counts one kind of thing happening, from the events of the level, and gets
+//! unlocked once it happened `goal` times. How far the player got is kept in their save.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{game::events::GameEvent, utils::AssetManager};
+
+/// The things that achievements count.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Counter {
+    BarrelsExploded,
+    Kills,
+    Headshots,
+    BossKills,
+    Pickups,
+    Checkpoints,
+    LevelsCompleted,
+    /// Levels beaten without firing a single shot.
+    LevelsWithoutFiring,
+}
+
+impl Counter {
+    /// The name the counter is kept under in the save.
+    fn name(self) -> &'static str {
+        match self {
+            Counter::BarrelsExploded => "barrels_exploded",
+            Counter::Kills => "kills",
+            Counter::Headshots => "headshots",
+            Counter::BossKills => "boss_kills",
+            Counter::Pickups => "pickups",
+            Counter::Checkpoints => "checkpoints",
+            Counter::LevelsCompleted => "levels_completed",
+            Counter::LevelsWithoutFiring => "levels_without_firing",
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub counter: Counter,
+    /// How often the counted thing has to happen.
+    #[serde(default = "one")]
+    pub goal: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// What an achievements file has in it.
+#[derive(Deserialize)]
+struct AchievementsFile {
+    #[serde(default)]
+    achievement: Vec<Achievement>,
+}
+
+/// How far the player got with the achievements, kept in their save.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    /// The ids of the achievements unlocked so far.
+    pub unlocked: Vec<String>,
+    /// How often each of the counted things happened, by the name of the counter.
+    pub counts: BTreeMap<String, u32>,
+}
+
+impl Progress {
+    pub fn count(&self, counter: Counter) -> u32 {
+        self.counts.get(counter.name()).copied().unwrap_or(0)
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.unlocked.contains(&achievement.id)
+    }
+}
+
+/// Keeps track of the achievements during a level.
+pub struct Achievements {
+    list: Vec<Achievement>,
+    /// Whether a shot was fired in the level so far.
+    fired: bool,
+}
+
+impl Achievements {
+    /// Read the achievements from every file in the `achievements` folder. Files that can't be
+    /// read are left out.
+    pub fn load(asset_manager: &AssetManager) -> Self {
+        let mut list = vec![];
+
+        for name in asset_manager.get_files("achievements") {
+            let file = asset_manager.get_file(&format!("/achievements/{}", name));
+
+            match toml::from_str::<AchievementsFile>(&file) {
+                Ok(file) => list.extend(file.achievement),
+                Err(error) => log::error!("Cannot read the achievements in {}: {}", name, error),
+            }
+        }
+
+        Self::new(list)
+    }
+
+    fn new(list: Vec<Achievement>) -> Self {
+        Self { list, fired: false }
+    }
+
+    pub fn list(&self) -> &[Achievement] {
+        &self.list
+    }
+
+    /// Count what happened towards the achievements, returning the ones it unlocked.
+    pub fn notify(&mut self, event: &GameEvent, progress: &mut Progress) -> Vec<&Achievement> {
+        let counters: &[Counter] = match *event {
+            GameEvent::EnemyKilled {
+                boss: Some(_),
+                headshot,
+            } => {
+                if headshot {
+                    &[Counter::Kills, Counter::BossKills, Counter::Headshots]
+                } else {
+                    &[Counter::Kills, Counter::BossKills]
+                }
+            }
+            GameEvent::EnemyKilled { headshot: true, .. } => &[Counter::Kills, Counter::Headshots],
+            GameEvent::EnemyKilled { .. } => &[Counter::Kills],
+            GameEvent::BarrelExploded { .. } => &[Counter::BarrelsExploded],
+            GameEvent::PickupCollected(_) => &[Counter::Pickups],
+            GameEvent::CheckpointReached => &[Counter::Checkpoints],
+            GameEvent::LevelCompleted if !self.fired => {
+                &[Counter::LevelsCompleted, Counter::LevelsWithoutFiring]
+            }
+            GameEvent::LevelCompleted => &[Counter::LevelsCompleted],
+            GameEvent::ShotFired => {
+                self.fired = true;
+
+                &[]
+            }
+            GameEvent::DamageDealt { .. } => &[],
+        };
+
+        for counter in counters {
+            *progress
+                .counts
+                .entry(counter.name().to_string())
+                .or_default() += 1;
+        }
+
+        let mut unlocked = vec![];
+
+        for achievement in &self.list {
+            if !progress.is_unlocked(achievement)
+                && progress.count(achievement.counter) >= achievement.goal
+            {
+                progress.unlocked.push(achievement.id.clone());
+                unlocked.push(achievement);
+            }
+        }
+
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_achievements() {
+        let file: AchievementsFile = toml::from_str(
+            r#"
+            [[achievement]]
+            id = "demolition"
+            name = "Demolition Crab"
+            description = "Blow up 2 barrels"
+            counter = "barrels_exploded"
+            goal = 2
+
+            [[achievement]]
+            id = "pacifist"
+            name = "Pacifist"
+            description = "Beat a level without firing"
+            counter = "levels_without_firing"
+            "#,
+        )
+        .unwrap();
+        let mut achievements = Achievements::new(file.achievement);
+        let mut progress = Progress::default();
+        let barrel = GameEvent::BarrelExploded { chain: 1 };
+
+        assert!(achievements.notify(&barrel, &mut progress).is_empty());
+        assert_eq!(
+            achievements.notify(&barrel, &mut progress)[0].id,
+            "demolition"
+        );
+
+        // Unlocked achievements don't get unlocked again.
+        assert!(achievements.notify(&barrel, &mut progress).is_empty());
+        assert_eq!(progress.count(Counter::BarrelsExploded), 3);
+
+        achievements.notify(&GameEvent::ShotFired, &mut progress);
+        assert!(achievements
+            .notify(&GameEvent::LevelCompleted, &mut progress)
+            .is_empty());
+        assert_eq!(progress.count(Counter::LevelsCompleted), 1);
+
+        let mut next_level = Achievements::new(achievements.list().to_vec());
+
+        assert_eq!(
+            next_level.notify(&GameEvent::LevelCompleted, &mut progress)[0].id,
+            "pacifist"
+        );
+        assert_eq!(progress.unlocked, vec!["demolition", "pacifist"]);
+    }
+}
@@ -0,0 +1,764 @@
+//! Sound effects and music.
+//!
+//! Every sound effect in the manifest (`Sfx::ALL`) gets loaded from `resources/audio` and decoded
+//! up front by the loading screen, so playing one later on costs next to nothing. Sounds that
+//! can't be loaded are all reported at once before the game starts. Sound effects get a little random variation in volume and pitch each time they
+//! play so hearing the same one over and over doesn't get old, and each play gets a sink of its own
+//! so sounds can overlap. Sound effects played at a spot in the world get panned and turned down
+//! based on where that spot is compared to the camera.
+//!
+//! Music tracks live in `resources/music` and loop until something else comes on. Switching tracks
+//! fades the old one out while the new one fades in.
+//!
+//! The music and the sound effects each have a volume of their own, which both get scaled by the
+//! master volume.
+
+use std::{
+    cell::{Cell, RefCell, RefMut},
+    collections::HashMap,
+    io::{Cursor, Read},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use ggez::{
+    audio::{SoundData, SoundSource, Source, SpatialSource},
+    filesystem, Context,
+};
+use nphysics2d::nalgebra as na;
+use rand::Rng;
+
+use crate::{
+    error::FerrisResult,
+    game::{
+        events::{GameEvent, Victim},
+        physics::Surface,
+    },
+};
+
+/// A volume control.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Channel {
+    Master,
+    Music,
+    Sfx,
+}
+
+/// The sound effects of the game.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Sfx {
+    Shoot,
+    Jump,
+    Hit,
+    Explosion,
+    Pickup,
+    Death,
+    Step(Surface),
+    Land(Surface),
+    /// The jetpack, loops while thrusting.
+    Thrust,
+}
+
+impl Sfx {
+    /// Every sound effect, the manifest of what has to be loaded.
+    pub const ALL: [Sfx; 11] = [
+        Sfx::Shoot,
+        Sfx::Jump,
+        Sfx::Hit,
+        Sfx::Explosion,
+        Sfx::Pickup,
+        Sfx::Death,
+        Sfx::Step(Surface::Dirt),
+        Sfx::Step(Surface::Metal),
+        Sfx::Land(Surface::Dirt),
+        Sfx::Land(Surface::Metal),
+        Sfx::Thrust,
+    ];
+
+    /// The file the sound effect is loaded from.
+    pub fn file(self) -> &'static str {
+        match self {
+            Sfx::Shoot => "Some(turbofish_shoot).mp3",
+            Sfx::Jump => "jump.wav",
+            Sfx::Hit => "hit.wav",
+            Sfx::Explosion => "Some(explode).mp3",
+            Sfx::Pickup => "pickup.wav",
+            Sfx::Death => "dead.mp3",
+            Sfx::Step(Surface::Dirt) => "step_dirt.wav",
+            Sfx::Step(Surface::Metal) => "step_metal.wav",
+            Sfx::Land(Surface::Dirt) => "land_dirt.wav",
+            Sfx::Land(Surface::Metal) => "land_metal.wav",
+            Sfx::Thrust => "thrust.wav",
+        }
+    }
+
+    /// How loud the sound effect is compared to the others.
+    fn volume(self) -> f32 {
+        match self {
+            Sfx::Jump => 0.4,
+            Sfx::Hit => 0.6,
+            Sfx::Pickup => 0.5,
+            Sfx::Step(_) => 0.35,
+            Sfx::Thrust => 0.3,
+            Sfx::Shoot | Sfx::Explosion | Sfx::Death | Sfx::Land(_) => 1.0,
+        }
+    }
+
+    /// How far the volume and pitch can stray from their usual values, as a fraction of them.
+    fn variation(self) -> (f32, f32) {
+        match self {
+            Sfx::Shoot => (0.1, 0.08),
+            Sfx::Jump => (0.1, 0.05),
+            Sfx::Hit => (0.15, 0.12),
+            Sfx::Explosion => (0.1, 0.1),
+            Sfx::Pickup => (0.0, 0.03),
+            Sfx::Step(_) => (0.2, 0.1),
+            Sfx::Land(_) => (0.1, 0.08),
+            Sfx::Death | Sfx::Thrust => (0.0, 0.0),
+        }
+    }
+
+    /// The least amount of time between two plays of the sound effect. Keeps held down keys and
+    /// a bunch of things happening on the same frame from stacking up into noise.
+    fn cooldown(self) -> Duration {
+        match self {
+            Sfx::Jump => Duration::from_millis(300),
+            Sfx::Land(_) => Duration::from_millis(150),
+            Sfx::Hit | Sfx::Explosion => Duration::from_millis(40),
+            Sfx::Shoot | Sfx::Pickup | Sfx::Death | Sfx::Step(_) | Sfx::Thrust => {
+                Duration::from_millis(0)
+            }
+        }
+    }
+}
+
+/// Where sound effects are heard from, usually the middle of the camera.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Listener {
+    pub position: na::Point2<f32>,
+    /// Half the width of the world the listener can see.
+    pub reach: f32,
+}
+
+impl Listener {
+    /// How far past the reach of the listener sounds fade out, in reaches.
+    const FALLOFF: f32 = 2.0;
+    /// How far to the side sounds can get panned, between 0.0 and 1.0.
+    const MAX_PAN: f32 = 0.8;
+
+    /// How loud and how far to the side (-1.0 to 1.0) a sound at `position` is. Sounds within
+    /// reach play at full volume, sounds further away are quieter.
+    fn hear(&self, position: na::Point2<f32>) -> (f32, f32) {
+        let offset = position - self.position;
+        let distance = offset.norm() / self.reach;
+
+        let volume = (1.0 - (distance - 1.0) / Self::FALLOFF).clamp(0.0, 1.0);
+        let pan = (offset.x / self.reach).clamp(-1.0, 1.0) * Self::MAX_PAN;
+
+        (volume, pan)
+    }
+}
+
+pub struct Audio {
+    sounds: HashMap<String, Mutex<SpatialSource>>,
+    /// The volume of every channel, between 0.0 and 1.0.
+    master_volume: Cell<f32>,
+    music_volume: Cell<f32>,
+    sfx_volume: Cell<f32>,
+    /// When each sound effect was last played.
+    played: RefCell<HashMap<Sfx, Instant>>,
+    listener: Cell<Option<Listener>>,
+    /// Whether the window has focus. Nothing new gets played while it doesn't.
+    focused: Cell<bool>,
+    music: RefCell<MusicController>,
+}
+
+impl Audio {
+    /// How far apart the ears are. Close enough for the spatial sink not to turn anything down
+    /// based on distance, that is done by the listener instead.
+    const EAR_DISTANCE: f32 = 1.0;
+    /// The spatial sink plays sounds right in front of the ears at three quarters of their volume.
+    const CENTER_GAIN: f32 = 0.75;
+
+    pub fn new() -> Self {
+        Self {
+            sounds: HashMap::new(),
+            master_volume: Cell::new(1.0),
+            music_volume: Cell::new(1.0),
+            sfx_volume: Cell::new(1.0),
+            played: RefCell::new(HashMap::new()),
+            listener: Cell::new(None),
+            focused: Cell::new(true),
+            music: RefCell::new(MusicController::new()),
+        }
+    }
+
+    /// Load and decode a sound effect from `resources/audio`.
+    pub fn load(&mut self, ctx: &mut Context, filename: &str) -> FerrisResult<()> {
+        let mut file = filesystem::open(ctx, format!("/audio/{}", filename))?;
+        let mut data = vec![];
+
+        file.read_to_end(&mut data)?;
+
+        let source = SpatialSource::from_data(ctx, decode(data)?)?;
+
+        self.sounds.insert(filename.to_string(), Mutex::new(source));
+
+        Ok(())
+    }
+
+    /// Quiet everything down while the window is in the background and bring it back once the
+    /// window gets focus again.
+    pub fn set_focused(&self, focused: bool) {
+        self.focused.set(focused);
+        self.music().duck(!focused);
+
+        if !focused {
+            self.stop_loops();
+        }
+    }
+
+    /// Move the listener, sounds played at a spot in the world are heard from there.
+    pub fn set_listener(&self, listener: Option<Listener>) {
+        self.listener.set(listener);
+    }
+
+    /// Play the sounds that go with the events of the level.
+    pub fn notify(&self, event: &GameEvent) {
+        match event {
+            GameEvent::DamageDealt {
+                victim: Victim::Player,
+                ..
+            } => self.play(Sfx::Hit),
+            GameEvent::PickupCollected(_) => self.play(Sfx::Pickup),
+            _ => {}
+        }
+    }
+
+    /// Play a sound effect with a bit of random variation.
+    pub fn play(&self, sfx: Sfx) {
+        self.play_sfx(sfx, 1.0, 0.0);
+    }
+
+    /// Play a sound effect scaled by a `volume` between 0.0 and 1.0.
+    pub fn play_at_volume(&self, sfx: Sfx, volume: f32) {
+        self.play_sfx(sfx, volume, 0.0);
+    }
+
+    /// Play a sound effect coming from a spot in the world.
+    pub fn play_at(&self, sfx: Sfx, position: na::Point2<f32>) {
+        match self.listener.get() {
+            Some(listener) => {
+                let (volume, pan) = listener.hear(position);
+
+                if volume > 0.0 {
+                    self.play_sfx(sfx, volume, pan);
+                }
+            }
+            None => self.play(sfx),
+        }
+    }
+
+    fn play_sfx(&self, sfx: Sfx, volume: f32, pan: f32) {
+        let now = Instant::now();
+
+        if let Some(played) = self.played.borrow().get(&sfx) {
+            if now.duration_since(*played) < sfx.cooldown() {
+                return;
+            }
+        }
+
+        self.played.borrow_mut().insert(sfx, now);
+
+        let rng = &mut rand::thread_rng();
+        let (volume_variation, pitch_variation) = sfx.variation();
+
+        self.play_with(
+            sfx.file(),
+            volume * sfx.volume() * (1.0 + rng.gen_range(-volume_variation..=volume_variation)),
+            1.0 + rng.gen_range(-pitch_variation..=pitch_variation),
+            pan,
+        );
+    }
+
+    /// Play the sound loaded from `filename` at the provided volume, pitch and pan (-1.0 is all
+    /// the way to the left, 1.0 all the way to the right). The volume gets scaled by the volume of
+    /// the sound effects.
+    pub fn play_with(&self, filename: &str, volume: f32, pitch: f32, pan: f32) {
+        if !self.focused.get() {
+            return;
+        }
+
+        let mut sound = match self.sound(filename) {
+            Some(sound) => sound,
+            None => return,
+        };
+
+        let ear = Self::EAR_DISTANCE / 2.0;
+
+        // The spatial sink turns the ear further away from the sound up, so the sound goes on the
+        // opposite side of where it should be heard.
+        sound.set_ears([-ear, 0.0, 0.0], [ear, 0.0, 0.0]);
+        sound.set_position([-pan * ear, 0.0, 0.0]);
+        sound.set_volume(volume * self.volume(Channel::Sfx) / Self::CENTER_GAIN);
+        sound.set_pitch(pitch);
+        if let Err(error) = sound.play_detached() {
+            log::warn!("Cannot play {}: {}", filename, error);
+        }
+    }
+
+    /// Keep a sound effect looping for as long as `on` is `true`.
+    pub fn set_looping(&self, sfx: Sfx, on: bool) {
+        let mut sound = match self.sound(sfx.file()) {
+            Some(sound) => sound,
+            None => return,
+        };
+
+        sound.set_volume(sfx.volume() * self.volume(Channel::Sfx) / Self::CENTER_GAIN);
+
+        if on && !sound.playing() && self.focused.get() {
+            sound.set_repeat(true);
+            if let Err(error) = sound.play() {
+                log::warn!("Cannot play {}: {}", sfx.file(), error);
+            }
+        } else if !on && sound.playing() {
+            sound.stop();
+        }
+    }
+
+    /// Stop every looping sound effect.
+    pub fn stop_loops(&self) {
+        for sound in self.sounds.values() {
+            let mut sound = sound.lock().unwrap();
+
+            if sound.repeat() {
+                sound.stop();
+            }
+        }
+    }
+
+    /// A loaded sound. Sounds that failed to load were already reported by the loading screen,
+    /// so they just stay quiet.
+    fn sound(&self, filename: &str) -> Option<MutexGuard<'_, SpatialSource>> {
+        self.sounds.get(filename).map(|sound| sound.lock().unwrap())
+    }
+
+    pub fn load_music(&mut self, ctx: &mut Context, filename: &str) -> FerrisResult<()> {
+        self.music.get_mut().load(ctx, filename)
+    }
+
+    pub fn music(&self) -> RefMut<'_, MusicController> {
+        self.music.borrow_mut()
+    }
+
+    /// Set the volume of a channel, between 0.0 and 1.0.
+    pub fn set_volume(&self, channel: Channel, volume: f32) {
+        match channel {
+            Channel::Master => self.master_volume.set(volume),
+            Channel::Music => self.music_volume.set(volume),
+            Channel::Sfx => self.sfx_volume.set(volume),
+        }
+
+        let music = self.volume(Channel::Music);
+
+        self.music().set_volume(music);
+    }
+
+    /// The volume a channel ends up playing at, after the master volume is applied.
+    pub fn volume(&self, channel: Channel) -> f32 {
+        let master = self.master_volume.get();
+
+        match channel {
+            Channel::Master => master,
+            Channel::Music => master * self.music_volume.get(),
+            Channel::Sfx => master * self.sfx_volume.get(),
+        }
+    }
+}
+
+/// A volume level moving towards a target at a steady pace.
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    level: f32,
+    target: f32,
+    /// How much the level changes in a second.
+    speed: f32,
+}
+
+impl Fade {
+    /// Start moving towards `target` so it is reached in `time` seconds.
+    /// Already moving towards `target` keeps the pace it had.
+    fn to(&mut self, target: f32, time: f32) {
+        if self.target == target {
+            return;
+        }
+
+        self.target = target;
+        self.speed = if time > 0.0 {
+            (target - self.level).abs() / time
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    fn update(&mut self, dt: f32) {
+        let step = self.speed * dt;
+
+        self.level = if self.level < self.target {
+            (self.level + step).min(self.target)
+        } else {
+            (self.level - step).max(self.target)
+        };
+    }
+}
+
+struct Track {
+    name: String,
+    fade: Fade,
+    /// How far each of the layers of the track has faded in.
+    layers: Vec<Fade>,
+}
+
+/// Plays the looping background music and crossfades between tracks.
+///
+/// A track can come with layers that play on top of it as things get tense, loaded from files
+/// named after the track with the number of the layer in front of the extension (`level.1.wav`
+/// is the first layer of `level.wav`). Every layer loops along with the track, fading in and out
+/// with the tension.
+pub struct MusicController {
+    tracks: HashMap<String, Source>,
+    /// The layers of every track that has any, in order.
+    layers: HashMap<String, Vec<String>>,
+    /// The track that is on, if any.
+    current: Option<Track>,
+    /// Tracks fading out, they get stopped once they are silent.
+    leaving: Vec<Track>,
+    /// How tense things are, between 0.0 and 1.0. Every layer needs a bit more to come in.
+    tension: f32,
+    /// Turns all of the music down while the window doesn't have focus.
+    duck: Fade,
+    /// The volume the music is played at, between 0.0 and 1.0.
+    volume: f32,
+}
+
+impl MusicController {
+    /// How long switching between tracks takes.
+    pub const CROSSFADE: f32 = 1.5;
+    /// How long a layer takes to fade all the way in or out.
+    const LAYER_FADE: f32 = 2.0;
+    /// How long ducking the music takes.
+    const DUCK_TIME: f32 = 0.25;
+
+    fn new() -> Self {
+        Self {
+            tracks: HashMap::new(),
+            layers: HashMap::new(),
+            current: None,
+            leaving: vec![],
+            tension: 0.0,
+            duck: Fade {
+                level: 1.0,
+                target: 1.0,
+                speed: 0.0,
+            },
+            volume: 1.0,
+        }
+    }
+
+    /// Load a track from `resources/music`. Music is decoded while it plays, there's too much of
+    /// it to keep decoded.
+    fn load(&mut self, ctx: &mut Context, filename: &str) -> FerrisResult<()> {
+        let mut source = Source::new(ctx, format!("/music/{}", filename))?;
+
+        source.set_repeat(true);
+
+        self.tracks.insert(filename.to_string(), source);
+
+        if let Some(track) = layer_of(filename) {
+            let layers = self.layers.entry(track).or_default();
+
+            layers.push(filename.to_string());
+            layers.sort();
+        }
+
+        Ok(())
+    }
+
+    /// Switch over to a track, fading it in over `time` seconds while the one before fades out.
+    /// Does nothing if the track is already on.
+    pub fn play(&mut self, name: &str, time: f32) {
+        if self.playing() == Some(name) {
+            return;
+        }
+
+        self.stop(time);
+
+        // Bring the track back if it is still on its way out, otherwise start it over.
+        let mut track = match self.leaving.iter().position(|track| track.name == name) {
+            Some(index) => self.leaving.remove(index),
+            None => {
+                let layers = self.layers.get(name).cloned().unwrap_or_default();
+
+                // The layers are started along with the track so they stay in time with it.
+                for source in std::iter::once(name).chain(layers.iter().map(String::as_str)) {
+                    match self.tracks.get_mut(source) {
+                        Some(source) => {
+                            source.stop();
+                            source.set_volume(0.0);
+
+                            if let Err(error) = source.play() {
+                                log::warn!("Cannot play {}: {}", name, error);
+                            }
+                        }
+                        // Carry on without music rather than stopping the game over it.
+                        None => log::warn!("Cannot find the {} music", source),
+                    }
+                }
+
+                let silent = Fade {
+                    level: 0.0,
+                    target: 0.0,
+                    speed: 0.0,
+                };
+
+                Track {
+                    name: name.to_string(),
+                    fade: silent,
+                    layers: vec![silent; layers.len()],
+                }
+            }
+        };
+
+        track.fade.to(1.0, time);
+        self.current = Some(track);
+    }
+
+    /// Fade the music out over `time` seconds.
+    pub fn stop(&mut self, time: f32) {
+        if let Some(mut track) = self.current.take() {
+            track.fade.to(0.0, time);
+            self.leaving.push(track);
+        }
+    }
+
+    /// Fade the track that is on to a `level` between 0.0 and 1.0 over `time` seconds, like
+    /// turning it down while the game is paused.
+    pub fn fade(&mut self, level: f32, time: f32) {
+        if let Some(track) = &mut self.current {
+            track.fade.to(level, time);
+        }
+    }
+
+    /// The name of the track that is on.
+    pub fn playing(&self) -> Option<&str> {
+        self.current.as_ref().map(|track| track.name.as_str())
+    }
+
+    /// Set how tense things are, between 0.0 and 1.0. The layers of the track that is on fade in
+    /// and out to match.
+    pub fn set_tension(&mut self, tension: f32) {
+        self.tension = tension.clamp(0.0, 1.0);
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    /// Turn all of the music down to nothing, or bring it back up.
+    pub fn duck(&mut self, on: bool) {
+        self.duck.to(if on { 0.0 } else { 1.0 }, Self::DUCK_TIME);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.duck.update(dt);
+
+        let volume = self.volume * self.duck.level;
+
+        if let Some(track) = &mut self.current {
+            let count = track.layers.len();
+
+            for (id, layer) in track.layers.iter_mut().enumerate() {
+                layer.target = layer_level(self.tension, id, count);
+                layer.speed = 1.0 / Self::LAYER_FADE;
+            }
+        }
+
+        for track in self.current.iter_mut().chain(self.leaving.iter_mut()) {
+            track.fade.update(dt);
+
+            if let Some(source) = self.tracks.get_mut(&track.name) {
+                source.set_volume(track.fade.level * volume);
+            }
+
+            if let Some(layers) = self.layers.get(&track.name) {
+                for (layer, name) in track.layers.iter_mut().zip(layers) {
+                    layer.update(dt);
+
+                    if let Some(source) = self.tracks.get_mut(name) {
+                        source.set_volume(layer.level * track.fade.level * volume);
+                    }
+                }
+            }
+        }
+
+        let tracks = &mut self.tracks;
+        let layers = &self.layers;
+
+        self.leaving.retain(|track| {
+            let silent = track.fade.level <= 0.0;
+
+            if silent {
+                let track_layers = layers.get(&track.name).into_iter().flatten();
+
+                for name in std::iter::once(&track.name).chain(track_layers) {
+                    if let Some(source) = tracks.get_mut(name) {
+                        source.stop();
+                    }
+                }
+            }
+
+            !silent
+        });
+    }
+}
+
+/// Decode a sound file into a WAV file, which takes next to no decoding when played.
+fn decode(data: Vec<u8>) -> FerrisResult<SoundData> {
+    use rodio::Source;
+
+    let decoder = rodio::Decoder::new(Cursor::new(data))
+        .map_err(|_| "Unrecognized sound format".to_string())?;
+    let channels = decoder.channels();
+    let sample_rate = decoder.sample_rate();
+    let samples = decoder.collect::<Vec<i16>>();
+
+    Ok(SoundData::from(wav(channels, sample_rate, &samples)))
+}
+
+/// Write 16 bit samples into a WAV file.
+fn wav(channels: u16, sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let block_align = channels * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
+
+/// The name of the track a music file is a layer of, if it is one.
+fn layer_of(filename: &str) -> Option<String> {
+    let parts = filename.split('.').collect::<Vec<_>>();
+
+    match parts.as_slice() {
+        [track, layer, extension] if layer.parse::<u32>().is_ok() => {
+            Some(format!("{}.{}", track, extension))
+        }
+        _ => None,
+    }
+}
+
+/// How far the layer `id` out of `count` layers is faded in at the provided tension. The layers
+/// come in one after another as the tension goes up.
+fn layer_level(tension: f32, id: usize, count: usize) -> f32 {
+    (tension * count as f32 - id as f32).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listener() {
+        let listener = Listener {
+            position: na::Point2::new(0.0, 0.0),
+            reach: 100.0,
+        };
+
+        // Sounds on the screen play at full volume.
+        assert_eq!(listener.hear(na::Point2::new(0.0, 50.0)), (1.0, 0.0));
+
+        // Off to the right, quieter and over on the right.
+        let (volume, pan) = listener.hear(na::Point2::new(200.0, 0.0));
+
+        assert_eq!(volume, 0.5);
+        assert_eq!(pan, Listener::MAX_PAN);
+
+        // Too far away to hear at all.
+        assert_eq!(listener.hear(na::Point2::new(-400.0, 0.0)).0, 0.0);
+    }
+
+    #[test]
+    fn test_layers() {
+        assert_eq!(layer_of("level.2.wav"), Some(String::from("level.wav")));
+        assert_eq!(layer_of("level.wav"), None);
+        assert_eq!(layer_of("Some(boss).wav"), None);
+
+        // With two layers, the first one is all the way in by the time the second one starts.
+        assert_eq!(layer_level(0.25, 0, 2), 0.5);
+        assert_eq!(layer_level(0.25, 1, 2), 0.0);
+        assert_eq!(layer_level(0.75, 0, 2), 1.0);
+        assert_eq!(layer_level(0.75, 1, 2), 0.5);
+    }
+
+    #[test]
+    fn test_decode() {
+        let samples = [0, 1000, -1000, i16::MAX];
+        let sound = decode(wav(1, 22050, &samples)).unwrap();
+
+        // Decoding a WAV file gives back the same WAV file.
+        assert_eq!(sound.as_ref(), wav(1, 22050, &samples).as_slice());
+    }
+
+    #[test]
+    fn test_channels() {
+        let audio = Audio::new();
+
+        audio.set_volume(Channel::Music, 0.5);
+        audio.set_volume(Channel::Master, 0.5);
+
+        assert_eq!(audio.volume(Channel::Music), 0.25);
+        assert_eq!(audio.volume(Channel::Sfx), 0.5);
+        assert_eq!(audio.music().volume, 0.25);
+    }
+
+    #[test]
+    fn test_fade() {
+        let mut fade = Fade {
+            level: 0.0,
+            target: 0.0,
+            speed: 0.0,
+        };
+
+        fade.to(1.0, 2.0);
+        fade.update(1.0);
+
+        assert_eq!(fade.level, 0.5);
+
+        fade.update(5.0);
+
+        assert_eq!(fade.level, 1.0);
+
+        // Fading without any time jumps straight to the target.
+        fade.to(0.0, 0.0);
+        fade.update(0.016);
+
+        assert_eq!(fade.level, 0.0);
+    }
+}
@@ -0,0 +1,302 @@
+//! The keys and controller buttons that do things in the game.
+//!
+//! Every action gets a key and a button, both of which can be changed in the settings. Bindings
+//! are stored in the config by name, like `key = "Space"` and `button = "LeftTrigger2"`. Pausing,
+//! moving with the stick and going through dialogue can't be rebound.
+
+use ggez::event::{Button, KeyCode};
+use serde::{Deserialize, Serialize};
+
+/// Something the player can do.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Fire,
+    Reload,
+    SwitchWeapon,
+    Map,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Fire,
+        Action::Reload,
+        Action::SwitchWeapon,
+        Action::Map,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Left => "MOVE LEFT",
+            Action::Right => "MOVE RIGHT",
+            Action::Jump => "JUMP",
+            Action::Fire => "FIRE",
+            Action::Reload => "RELOAD",
+            Action::SwitchWeapon => "SWITCH WEAPON",
+            Action::Map => "MAP",
+        }
+    }
+}
+
+/// The key and the button bound to an action.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Binding {
+    #[serde(with = "key_name")]
+    pub key: KeyCode,
+    #[serde(with = "button_name")]
+    pub button: Button,
+}
+
+impl Binding {
+    fn new(key: KeyCode, button: Button) -> Self {
+        Self { key, button }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub left: Binding,
+    pub right: Binding,
+    pub jump: Binding,
+    pub fire: Binding,
+    pub reload: Binding,
+    pub switch_weapon: Binding,
+    pub map: Binding,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            left: Binding::new(KeyCode::Left, Button::DPadLeft),
+            right: Binding::new(KeyCode::Right, Button::DPadRight),
+            jump: Binding::new(KeyCode::Space, Button::LeftTrigger2),
+            fire: Binding::new(KeyCode::S, Button::RightTrigger2),
+            reload: Binding::new(KeyCode::R, Button::West),
+            switch_weapon: Binding::new(KeyCode::Down, Button::RightTrigger),
+            map: Binding::new(KeyCode::M, Button::Select),
+        }
+    }
+}
+
+impl Bindings {
+    /// Keys that are already taken and can't be bound to anything.
+    pub const RESERVED_KEYS: [KeyCode; 4] =
+        [KeyCode::Escape, KeyCode::Up, KeyCode::Key7, KeyCode::Key8];
+    /// Buttons that are already taken and can't be bound to anything.
+    pub const RESERVED_BUTTONS: [Button; 1] = [Button::Start];
+
+    pub fn get(&self, action: Action) -> &Binding {
+        match action {
+            Action::Left => &self.left,
+            Action::Right => &self.right,
+            Action::Jump => &self.jump,
+            Action::Fire => &self.fire,
+            Action::Reload => &self.reload,
+            Action::SwitchWeapon => &self.switch_weapon,
+            Action::Map => &self.map,
+        }
+    }
+
+    fn get_mut(&mut self, action: Action) -> &mut Binding {
+        match action {
+            Action::Left => &mut self.left,
+            Action::Right => &mut self.right,
+            Action::Jump => &mut self.jump,
+            Action::Fire => &mut self.fire,
+            Action::Reload => &mut self.reload,
+            Action::SwitchWeapon => &mut self.switch_weapon,
+            Action::Map => &mut self.map,
+        }
+    }
+
+    /// The action the key is bound to, if any.
+    pub fn key_action(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| self.get(*action).key == key)
+    }
+
+    /// The action the button is bound to, if any.
+    pub fn button_action(&self, button: Button) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| self.get(*action).button == button)
+    }
+
+    /// Bind the key to the action. An action that already had the key gets the old key of this one
+    /// instead, so nothing is ever left without a key.
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) -> bool {
+        if Self::RESERVED_KEYS.contains(&key) || key_name::name(key).is_none() {
+            return false;
+        }
+
+        let old = self.get(action).key;
+
+        if let Some(other) = self.key_action(key) {
+            self.get_mut(other).key = old;
+        }
+
+        self.get_mut(action).key = key;
+
+        true
+    }
+
+    /// Same as `bind_key()`, for the controller.
+    pub fn bind_button(&mut self, action: Action, button: Button) -> bool {
+        if Self::RESERVED_BUTTONS.contains(&button) || button_name::name(button).is_none() {
+            return false;
+        }
+
+        let old = self.get(action).button;
+
+        if let Some(other) = self.button_action(button) {
+            self.get_mut(other).button = old;
+        }
+
+        self.get_mut(action).button = button;
+
+        true
+    }
+}
+
+/// Lists every supported variant along with its name in the config.
+macro_rules! names {
+    ($kind:ident: $($variant:ident),* $(,)?) => {
+        &[$(($kind::$variant, stringify!($variant))),*]
+    };
+}
+
+/// Keys by their name in the config.
+pub mod key_name {
+    use ggez::event::KeyCode;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const KEYS: &[(KeyCode, &str)] = names!(KeyCode:
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+        Left, Right, Up, Down, Space, Return, Tab, Back,
+        LShift, RShift, LControl, RControl, LAlt, RAlt,
+        Comma, Period, Slash, Semicolon, Apostrophe, LBracket, RBracket,
+        Escape,
+    );
+
+    pub fn name(key: KeyCode) -> Option<&'static str> {
+        KEYS.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+    }
+
+    pub fn parse(name: &str) -> Option<KeyCode> {
+        KEYS.iter().find(|(_, n)| *n == name).map(|(key, _)| *key)
+    }
+
+    /// How the key is called on screen.
+    pub fn label(key: KeyCode) -> String {
+        match key {
+            KeyCode::Return => String::from("ENTER"),
+            KeyCode::Back => String::from("BACKSPACE"),
+            _ => {
+                let name = name(key).unwrap_or("?");
+
+                name.strip_prefix("Key").unwrap_or(name).to_uppercase()
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(name(*key).unwrap_or_default())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        parse(&name).ok_or_else(|| D::Error::custom(format!("unknown key {}", name)))
+    }
+}
+
+/// Controller buttons by their name in the config.
+pub mod button_name {
+    use ggez::event::Button;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const BUTTONS: &[(Button, &str)] = names!(Button:
+        South, East, North, West, LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
+        Select, Start, LeftThumb, RightThumb, DPadUp, DPadDown, DPadLeft, DPadRight,
+    );
+
+    pub fn name(button: Button) -> Option<&'static str> {
+        BUTTONS
+            .iter()
+            .find(|(b, _)| *b == button)
+            .map(|(_, name)| *name)
+    }
+
+    pub fn parse(name: &str) -> Option<Button> {
+        BUTTONS
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(button, _)| *button)
+    }
+
+    /// How the button is called on screen.
+    pub fn label(button: Button) -> &'static str {
+        match button {
+            Button::South => "A",
+            Button::East => "B",
+            Button::West => "X",
+            Button::North => "Y",
+            Button::LeftTrigger => "LB",
+            Button::RightTrigger => "RB",
+            Button::LeftTrigger2 => "LT",
+            Button::RightTrigger2 => "RT",
+            Button::Select => "SELECT",
+            Button::Start => "START",
+            Button::LeftThumb => "LS",
+            Button::RightThumb => "RS",
+            Button::DPadUp => "D-PAD UP",
+            Button::DPadDown => "D-PAD DOWN",
+            Button::DPadLeft => "D-PAD LEFT",
+            Button::DPadRight => "D-PAD RIGHT",
+            _ => "?",
+        }
+    }
+
+    pub fn serialize<S: Serializer>(button: &Button, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(name(*button).unwrap_or_default())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Button, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        parse(&name).ok_or_else(|| D::Error::custom(format!("unknown button {}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebinding() {
+        let mut bindings = Bindings::default();
+
+        // Taking a key from another action hands that action the old key.
+        assert!(bindings.bind_key(Action::Jump, KeyCode::S));
+        assert_eq!(bindings.key_action(KeyCode::S), Some(Action::Jump));
+        assert_eq!(bindings.fire.key, KeyCode::Space);
+
+        assert!(!bindings.bind_key(Action::Jump, KeyCode::Escape));
+        assert!(!bindings.bind_button(Action::Fire, Button::Start));
+
+        let saved = toml::to_string(&bindings).unwrap();
+
+        assert_eq!(toml::from_str::<Bindings>(&saved).unwrap(), bindings);
+        assert_eq!(key_name::label(KeyCode::Key4), "4");
+    }
+}
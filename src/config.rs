@@ -0,0 +1,262 @@
+//! Player settings that are kept around between runs.
+//!
+//! The config gets stored as TOML in the user config directory. Anything missing from the file
+//! falls back to the defaults, and the first run writes the defaults out so there's a file to
+//! edit. The config gets loaded before the window is made, since the window needs to know about
+//! its size and vsync as it's being built.
+
+use std::{fs, path::PathBuf};
+
+use directories::ProjectDirs;
+use ggez::{conf::WindowMode, graphics, Context, GameResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::Channel,
+    bindings::{Action, Bindings},
+    display::{self, DisplayMode},
+    error::FerrisResult,
+    i18n::Localization,
+    palette::ColorBlindMode,
+    utils::{write_atomic, AssetManager},
+};
+
+/// Minimum width.
+pub const MIN_WIDTH: f32 = 1000.0;
+/// Minimum height.
+pub const MIN_HEIGHT: f32 = 600.0;
+
+/// The resolutions that can be picked in the settings.
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (1000.0, 600.0),
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+];
+
+/// The slowest the game can be set to go.
+pub const MIN_GAME_SPEED: f32 = 0.5;
+
+/// Tools for working on the game. They are all on when built with the `debug` feature.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugFlags {
+    /// Outline every collider.
+    pub colliders: bool,
+    /// Scroll to zoom the camera around and press F4 to switch to a split screen.
+    pub camera: bool,
+    /// Open the developer console with the key under escape.
+    pub console: bool,
+}
+
+impl Default for DebugFlags {
+    fn default() -> Self {
+        let debug = cfg!(feature = "debug");
+
+        Self {
+            colliders: debug,
+            camera: debug,
+            console: debug,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Volume of every sound in the game, between 0.0 and 1.0.
+    pub volume: f32,
+    /// Volume of the music, on top of `volume`.
+    pub music_volume: f32,
+    /// Volume of the sound effects, on top of `volume`.
+    pub sfx_volume: f32,
+
+    /// The size of the window, and of the screen the game draws to while letterboxed.
+    pub resolution: (f32, f32),
+    pub display: DisplayMode,
+    /// Whether to keep the screen at `resolution` and scale it to fit the window, with black bars
+    /// around it, instead of drawing onto the whole window.
+    pub letterbox: bool,
+    /// Whether to wait for the screen before showing a frame. Only read when the game starts.
+    pub vsync: bool,
+    /// The most frames to show a second, 0 for no limit.
+    pub fps_limit: u32,
+    /// Whether to show the frame rate in the corner of the screen.
+    pub show_fps: bool,
+    /// Whether to time the levels and show the splits and the inputs, for speedrunning.
+    pub speedrun: bool,
+
+    /// Multiplier for the screen shake. 0.0 turns it off.
+    pub screen_shake: f32,
+    /// Whether anything flashes, like the screen and sprites getting hurt, muzzle flashes and the
+    /// low ammo warning.
+    pub flashing: bool,
+    /// Whether getting hurt, low health and explosions show on the screen.
+    pub screen_effects: bool,
+    /// How fast the levels go, from `MIN_GAME_SPEED` up to 1.0 for full speed. Online games are
+    /// always played at full speed.
+    pub game_speed: f32,
+    /// Whether to outline the enemies and the bullets, so they stand out from the background.
+    pub high_contrast: bool,
+    /// Whether to make the game look like it's on an old TV.
+    pub crt_filter: bool,
+    /// Swaps the colors that mean something for ones color-blind players can tell apart.
+    pub color_blind: ColorBlindMode,
+    /// Whether to show the on-screen controls for touch screens.
+    pub touch_controls: bool,
+    /// Whether pressing fire once keeps firing until it's pressed again.
+    pub toggle_fire: bool,
+    /// Whether pressing jump once keeps the jetpack going until it's pressed again.
+    pub toggle_jetpack: bool,
+    /// The language the text is shown in, by the name of its file in the `lang` folder.
+    pub language: String,
+    /// The save slot being played.
+    pub save_slot: usize,
+    /// The online leaderboard runs get sent to, like `http://scores.example.com/ferris`. Left
+    /// empty, the high scores stay on this computer.
+    pub leaderboard: String,
+    /// Whether to show what's being played on the player's Discord profile, in builds with the
+    /// `discord` feature.
+    pub discord_presence: bool,
+    /// The id of the Discord application the game shows up as. Left empty, Discord doesn't hear
+    /// about the game.
+    pub discord_application: String,
+
+    /// What every key and button does. Kept with the other tables at the end, TOML wants its
+    /// tables after the plain values.
+    pub bindings: Bindings,
+    pub debug: DebugFlags,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+
+            resolution: RESOLUTIONS[0],
+            display: DisplayMode::Windowed,
+            letterbox: false,
+            vsync: true,
+            fps_limit: 0,
+            show_fps: false,
+            speedrun: false,
+
+            screen_shake: 1.0,
+            flashing: true,
+            screen_effects: true,
+            game_speed: 1.0,
+            high_contrast: false,
+            crt_filter: false,
+            color_blind: ColorBlindMode::Off,
+            touch_controls: false,
+            toggle_fire: false,
+            toggle_jetpack: false,
+            language: String::from(Localization::ENGLISH),
+            save_slot: 0,
+            leaderboard: String::new(),
+            discord_presence: true,
+            discord_application: String::new(),
+
+            bindings: Bindings::default(),
+            debug: DebugFlags::default(),
+        }
+    }
+}
+
+impl Config {
+    const FILE_NAME: &'static str = "config.toml";
+
+    /// Load the config from the user config directory. A broken config file leaves you with the
+    /// defaults, a missing one gets the defaults written to it.
+    pub fn load() -> Self {
+        let config = Self::path().and_then(|path| fs::read_to_string(path).ok());
+
+        match config {
+            Some(config) => toml::from_str(&config).unwrap_or_default(),
+            None => {
+                let config = Self::default();
+
+                if let Err(error) = config.save() {
+                    log::error!("Cannot save the config: {}", error);
+                }
+
+                config
+            }
+        }
+    }
+
+    /// Write the config to the user config directory.
+    pub fn save(&self) -> FerrisResult<()> {
+        let path = Self::path().ok_or("Cannot find the user config directory")?;
+
+        write_atomic(&path, &toml::to_string(self)?)
+    }
+
+    /// Make the window and the sounds match the config.
+    pub fn apply(&self, ctx: &mut Context, asset_manager: &AssetManager) -> GameResult {
+        graphics::set_mode(ctx, self.window_mode())?;
+        display::fit(ctx, self)?;
+
+        let audio = asset_manager.audio();
+
+        audio.set_volume(Channel::Master, self.volume);
+        audio.set_volume(Channel::Music, self.music_volume);
+        audio.set_volume(Channel::Sfx, self.sfx_volume);
+        asset_manager.set_language(&self.language);
+
+        Ok(())
+    }
+
+    /// The window the config asks for.
+    pub fn window_mode(&self) -> WindowMode {
+        let (width, height) = self.resolution;
+
+        WindowMode::default()
+            .dimensions(width, height)
+            .resizable(true)
+            .min_dimensions(MIN_WIDTH, MIN_HEIGHT)
+            .fullscreen_type(self.display.fullscreen_type())
+    }
+
+    /// The actions that get toggled by a press instead of being held down.
+    pub fn toggled(&self) -> Vec<Action> {
+        let mut toggled = vec![];
+
+        if self.toggle_fire {
+            toggled.push(Action::Fire);
+        }
+
+        if self.toggle_jetpack {
+            toggled.push(Action::Jump);
+        }
+
+        toggled
+    }
+
+    /// Where the config file goes. The same directory ggez uses as the user config directory,
+    /// which can't be asked for before there's a context.
+    fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", crate::AUTHOR, crate::GAME_ID)
+            .map(|dirs| dirs.config_dir().join(Self::FILE_NAME))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let config: Config = toml::from_str("volume = 0.5\nshow_fps = true").unwrap();
+
+        assert_eq!(config.volume, 0.5);
+        assert!(config.show_fps);
+        assert_eq!(config.resolution, Config::default().resolution);
+
+        let saved = toml::to_string(&config).unwrap();
+
+        assert_eq!(toml::from_str::<Config>(&saved).unwrap(), config);
+    }
+}
@@ -0,0 +1,299 @@
+//! Discord Rich Presence, showing what's being played on the player's Discord profile.
+//!
+//! Only built with the `discord` feature, and only turned on with the toggle in the settings and
+//! the id of a Discord application in the config. The Discord app listens on a local socket,
+//! `discord-ipc-0` in the runtime directory or the pipe going by that name on Windows. Everything
+//! sent over it is a frame of an opcode and a length, both little endian, followed by that much
+//! JSON. A handshake with the application id comes first, then every activity is a
+//! `SET_ACTIVITY` command.
+//!
+//! The socket is talked to in the background so the game never waits on it. Discord only takes so
+//! many activities at a time, so the newest one waits until the last one was sent a little while
+//! ago, and the ones in between get skipped. Whenever Discord isn't running the activities go
+//! nowhere, and the next one tries to get through again.
+
+use std::{
+    io::{self, Read, Write},
+    process,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use serde_json::json;
+
+/// What the player is up to, as shown on their profile.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Activity {
+    /// The first line, like the level being played.
+    pub details: String,
+    /// The second line, like how far along the level is.
+    pub state: String,
+    /// When the activity started, in seconds since 1970, for Discord to count the time from.
+    pub start: u64,
+    /// How many players are playing together and how many could, while playing together.
+    pub party: Option<(u32, u32)>,
+}
+
+impl Activity {
+    pub fn new(details: &str, state: &str) -> Self {
+        Self {
+            details: details.to_string(),
+            state: state.to_string(),
+            start: now(),
+            party: None,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut activity = json!({
+            "details": self.details,
+            "timestamps": { "start": self.start },
+        });
+
+        // Discord turns down an empty line.
+        if !self.state.is_empty() {
+            activity["state"] = json!(self.state);
+        }
+
+        if let Some((size, max)) = self.party {
+            activity["party"] = json!({ "size": [size, max] });
+        }
+
+        activity
+    }
+}
+
+/// The seconds since 1970.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Opcodes of the frames.
+const HANDSHAKE: u32 = 0;
+const FRAME: u32 = 1;
+
+/// Put a frame together.
+fn frame(opcode: u32, payload: &str) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + payload.len());
+
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload.as_bytes());
+
+    frame
+}
+
+/// Read a frame, with its opcode and its payload.
+fn read_frame(reader: &mut impl Read) -> io::Result<(u32, String)> {
+    let mut header = [0; 8];
+
+    reader.read_exact(&mut header)?;
+
+    let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let mut payload = vec![0; length as usize];
+
+    reader.read_exact(&mut payload)?;
+
+    String::from_utf8(payload)
+        .map(|payload| (opcode, payload))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+trait Socket: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Socket for T {}
+
+/// Where the Discord app could be listening, it takes the first free one of ten.
+fn socket_paths() -> Vec<String> {
+    if cfg!(windows) {
+        pipe_paths(r"\\?\pipe", '\\')
+    } else {
+        let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .unwrap_or_else(|| String::from("/tmp"));
+
+        pipe_paths(&dir, '/')
+    }
+}
+
+/// The ten places in `dir` the Discord app could be listening on. Named pipes on Windows go by
+/// `\\?\` paths, which are taken as they are, so they need their own `separator`.
+fn pipe_paths(dir: &str, separator: char) -> Vec<String> {
+    (0..10)
+        .map(|id| {
+            format!(
+                "{}{}discord-ipc-{}",
+                dir.trim_end_matches(separator),
+                separator,
+                id
+            )
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn open(path: &str) -> io::Result<Box<dyn Socket>> {
+    Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?))
+}
+
+#[cfg(not(unix))]
+fn open(path: &str) -> io::Result<Box<dyn Socket>> {
+    Ok(Box::new(
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?,
+    ))
+}
+
+/// A connection to the Discord app, past the handshake.
+struct Connection {
+    socket: Box<dyn Socket>,
+    /// Tells the answers to the commands apart.
+    nonce: u64,
+}
+
+impl Connection {
+    fn connect(application_id: &str) -> io::Result<Self> {
+        let mut socket = socket_paths()
+            .iter()
+            .find_map(|path| open(path).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Discord isn't running"))?;
+
+        let handshake = json!({ "v": 1, "client_id": application_id }).to_string();
+
+        socket.write_all(&frame(HANDSHAKE, &handshake))?;
+        read_frame(&mut socket)?;
+
+        Ok(Self { socket, nonce: 0 })
+    }
+
+    /// Show the activity on the profile, or take it off without one.
+    fn set_activity(&mut self, activity: Option<&Activity>) -> io::Result<()> {
+        self.nonce += 1;
+
+        let command = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": {
+                "pid": process::id(),
+                "activity": activity.map(Activity::to_json),
+            },
+            "nonce": self.nonce.to_string(),
+        });
+
+        self.socket.write_all(&frame(FRAME, &command.to_string()))?;
+        read_frame(&mut self.socket)?;
+
+        Ok(())
+    }
+}
+
+/// Sends what the player is up to over to Discord, in the background. Clones send to the same
+/// place.
+#[derive(Clone)]
+pub struct Presence {
+    sender: Sender<Option<Activity>>,
+}
+
+impl Presence {
+    /// How long an activity stays up before the next one gets sent.
+    const RATE: Duration = Duration::from_secs(4);
+
+    /// Start talking to Discord as the application going by `application_id`. The activity goes
+    /// away once every clone of the presence is dropped.
+    pub fn start(application_id: &str) -> Self {
+        let (sender, activities) = mpsc::channel();
+        let application_id = application_id.to_string();
+
+        thread::spawn(move || Self::run(&application_id, activities));
+
+        Self { sender }
+    }
+
+    pub fn set(&self, activity: Activity) {
+        let _ = self.sender.send(Some(activity));
+    }
+
+    pub fn clear(&self) {
+        let _ = self.sender.send(None);
+    }
+
+    fn run(application_id: &str, activities: Receiver<Option<Activity>>) {
+        let mut connection: Option<Connection> = None;
+
+        while let Ok(mut activity) = activities.recv() {
+            // Only the newest activity is worth sending.
+            while let Ok(newer) = activities.try_recv() {
+                activity = newer;
+            }
+
+            if connection.is_none() {
+                connection = Connection::connect(application_id)
+                    .map_err(|error| log::info!("Cannot reach Discord: {}", error))
+                    .ok();
+            }
+
+            if let Some(open) = &mut connection {
+                if let Err(error) = open.set_activity(activity.as_ref()) {
+                    log::info!("Lost Discord: {}", error);
+                    connection = None;
+                }
+            }
+
+            thread::sleep(Self::RATE);
+        }
+
+        // Everyone let go of the presence, so the game is done with it.
+        if let Some(open) = &mut connection {
+            let _ = open.set_activity(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let sent = frame(FRAME, "{\"cmd\":\"SET_ACTIVITY\"}");
+
+        assert_eq!(&sent[..4], &[1, 0, 0, 0]);
+        assert_eq!(
+            read_frame(&mut sent.as_slice()).unwrap(),
+            (FRAME, String::from("{\"cmd\":\"SET_ACTIVITY\"}"))
+        );
+
+        // A frame cut short doesn't get read.
+        assert!(read_frame(&mut &sent[..10]).is_err());
+    }
+
+    #[test]
+    fn test_pipe_paths() {
+        assert_eq!(pipe_paths(r"\\?\pipe", '\\')[0], r"\\?\pipe\discord-ipc-0");
+        assert_eq!(
+            pipe_paths("/run/user/1000/", '/')[9],
+            "/run/user/1000/discord-ipc-9"
+        );
+    }
+
+    #[test]
+    fn test_activity() {
+        let mut activity = Activity::new("Level 01", "3 of 12 gophers down");
+
+        assert!(activity.to_json().get("party").is_none());
+        assert!(Activity::new("In the menu", "")
+            .to_json()
+            .get("state")
+            .is_none());
+
+        activity.party = Some((2, 4));
+        assert_eq!(activity.to_json()["party"]["size"], json!([2, 4]));
+        assert_eq!(activity.to_json()["state"], "3 of 12 gophers down");
+    }
+}
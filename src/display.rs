@@ -0,0 +1,145 @@
+//! How the game fills the window.
+//!
+//! The window is either a normal window, a borderless one covering the whole desktop or takes over
+//! the screen at the picked resolution. By default the game draws onto the whole window at
+//! whatever size it is. With letterboxing on, the game always draws onto the picked resolution
+//! instead, which gets scaled to fit the window and has black bars put around it.
+//!
+//! Letterboxing works through the screen coordinates, so anything that needs the size of the
+//! screen or where the mouse is on it has to ask here instead of asking ggez for the size of the
+//! window.
+
+use ggez::{
+    conf::FullscreenType,
+    graphics::{self, DrawMode, MeshBuilder, Rect},
+    Context, GameResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    /// A window without borders covering the whole desktop.
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    pub fn fullscreen_type(&self) -> FullscreenType {
+        match self {
+            DisplayMode::Windowed => FullscreenType::Windowed,
+            DisplayMode::Borderless => FullscreenType::Desktop,
+            DisplayMode::Fullscreen => FullscreenType::True,
+        }
+    }
+}
+
+/// The screen coordinates that show `size` as big as it fits into a window of the provided size,
+/// centered between the bars.
+fn letterbox(window: (f32, f32), size: (f32, f32)) -> Rect {
+    let scale = (window.0 / size.0).min(window.1 / size.1);
+    let (width, height) = (window.0 / scale, window.1 / scale);
+
+    Rect::new(
+        -(width - size.0) / 2.0,
+        -(height - size.1) / 2.0,
+        width,
+        height,
+    )
+}
+
+/// Make the screen coordinates match the config and the size of the window.
+pub fn fit(ctx: &mut Context, config: &Config) -> GameResult {
+    let window = graphics::drawable_size(ctx);
+    let coordinates = if config.letterbox {
+        letterbox(window, config.resolution)
+    } else {
+        Rect::new(0.0, 0.0, window.0, window.1)
+    };
+
+    graphics::set_screen_coordinates(ctx, coordinates)
+}
+
+/// The size of the screen the game draws to.
+pub fn screen_size(ctx: &Context) -> (f32, f32) {
+    let coordinates = graphics::screen_coordinates(ctx);
+
+    // The bars are on both sides, and start where the screen ends.
+    (
+        coordinates.w + coordinates.x * 2.0,
+        coordinates.h + coordinates.y * 2.0,
+    )
+}
+
+/// Find the point on the screen under a point in the window, like the position of the mouse.
+pub fn to_screen(ctx: &Context, x: f32, y: f32) -> (f32, f32) {
+    let coordinates = graphics::screen_coordinates(ctx);
+    let (width, height) = graphics::drawable_size(ctx);
+
+    (
+        coordinates.x + x / width * coordinates.w,
+        coordinates.y + y / height * coordinates.h,
+    )
+}
+
+/// Cover up whatever got drawn past the edges of the screen and show the frame.
+pub fn present(ctx: &mut Context) -> GameResult {
+    let coordinates = graphics::screen_coordinates(ctx);
+
+    if coordinates.x < 0.0 || coordinates.y < 0.0 {
+        let (width, height) = screen_size(ctx);
+        let (left, top) = (coordinates.x, coordinates.y);
+        let bars = MeshBuilder::new()
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(left, top, -left, coordinates.h),
+                graphics::BLACK,
+            )
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(width, top, -left, coordinates.h),
+                graphics::BLACK,
+            )
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(left, top, coordinates.w, -top),
+                graphics::BLACK,
+            )
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(left, height, coordinates.w, -top),
+                graphics::BLACK,
+            )
+            .build(ctx)?;
+
+        graphics::draw(ctx, &bars, graphics::DrawParam::default())?;
+    }
+
+    graphics::present(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letterbox() {
+        // A wider window gets bars on the sides.
+        let coordinates = letterbox((2000.0, 600.0), (1000.0, 600.0));
+
+        assert_eq!(coordinates, Rect::new(-500.0, 0.0, 2000.0, 600.0));
+
+        // A taller one gets them above and below.
+        let coordinates = letterbox((1000.0, 1200.0), (2000.0, 1200.0));
+
+        assert_eq!(coordinates, Rect::new(0.0, -600.0, 2000.0, 2400.0));
+    }
+}
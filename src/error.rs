@@ -0,0 +1,168 @@
+//! What can go wrong while the game runs.
+//!
+//! Anything that can fail without it being a bug in the game returns a `FerrisResult`. Errors the
+//! game can carry on from, like a level that can't be built or a setting that can't be applied,
+//! get shown in a banner at the bottom of the screen for a bit instead of taking the game down.
+
+use std::io;
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, Mesh, Rect, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameError, GameResult,
+};
+use nphysics2d::object::DefaultBodyHandle;
+use thiserror::Error;
+
+use crate::{display, game::entities::EntityId, utils::AssetError};
+
+pub type FerrisResult<T> = Result<T, FerrisError>;
+
+#[derive(Debug, Error)]
+pub enum FerrisError {
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Cannot read the file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Cannot write the file: {0}")]
+    Write(#[from] toml::ser::Error),
+    #[error("Cannot read the file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+    #[error("Cannot write the image: {0}")]
+    Image(#[from] image::ImageError),
+    /// Something still held on to a body that has been taken out of the world.
+    #[error("The physics body {0:?} isn't there any more")]
+    MissingBody(DefaultBodyHandle),
+    /// Something still held on to an entity that has been despawned.
+    #[error("The entity {0:?} isn't there any more")]
+    MissingEntity(EntityId),
+    #[error("The level {level} is broken: {problem}")]
+    Level {
+        level: String,
+        problem: &'static str,
+    },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<String> for FerrisError {
+    fn from(message: String) -> Self {
+        FerrisError::Other(message)
+    }
+}
+
+impl From<&str> for FerrisError {
+    fn from(message: &str) -> Self {
+        FerrisError::Other(message.to_string())
+    }
+}
+
+/// Tells the player about an error the game carried on from.
+pub struct ErrorBanner {
+    message: Option<String>,
+    /// How long the banner still stays up.
+    time: f32,
+}
+
+impl ErrorBanner {
+    /// How long a banner stays up.
+    const DURATION: f32 = 6.0;
+
+    pub fn new() -> Self {
+        Self {
+            message: None,
+            time: 0.0,
+        }
+    }
+
+    /// Show an error, taking the place of the last one. It goes in the log too, to end up in bug
+    /// reports.
+    pub fn show(&mut self, error: &FerrisError) {
+        log::error!("{}", error);
+
+        self.message = Some(error.to_string());
+        self.time = Self::DURATION;
+    }
+
+    /// Show the error of a result, if it failed.
+    pub fn report<T>(&mut self, result: FerrisResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.show(&error);
+
+                None
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time -= dt;
+
+        if self.time <= 0.0 {
+            self.message = None;
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, font: Font) -> GameResult {
+        let message = match &self.message {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+
+        let (width, height) = display::screen_size(ctx);
+        let alpha = self.time.min(1.0);
+        let text = Text::new(
+            TextFragment::new(message.as_str())
+                .font(font)
+                .scale(Scale::uniform(18.0)),
+        );
+        let (text_width, text_height) = (text.width(ctx) as f32, text.height(ctx) as f32);
+        let banner = Rect::new(
+            (width - text_width) / 2.0 - 16.0,
+            height - text_height - 40.0,
+            text_width + 32.0,
+            text_height + 16.0,
+        );
+
+        let background = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            banner,
+            Color::new(0.55, 0.08, 0.05, 0.9 * alpha),
+        )?;
+
+        graphics::draw(ctx, &background, DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default()
+                .dest(Point2::new(banner.x + 16.0, banner.y + 8.0))
+                .color(Color::new(1.0, 1.0, 1.0, alpha)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_banner() {
+        let mut banner = ErrorBanner::new();
+
+        assert_eq!(banner.report::<u32>(Ok(3)), Some(3));
+        assert_eq!(
+            banner.report::<u32>(Err(FerrisError::Other(String::from("Oops")))),
+            None
+        );
+        assert_eq!(banner.message.as_deref(), Some("Oops"));
+
+        banner.update(ErrorBanner::DURATION);
+        assert_eq!(banner.message, None);
+    }
+}
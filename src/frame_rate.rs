@@ -0,0 +1,86 @@
+//! Keeping the frame rate in check.
+//!
+//! Without vsync, or on screens that refresh really fast, the game would draw as many frames as it
+//! can and keep a whole core busy doing so. The limiter sleeps away whatever is left of the frame
+//! once it's been drawn instead. The frame rate can also be shown in the corner of the screen.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use ggez::{
+    graphics::{self, DrawParam, Font, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    timer, Context, GameResult,
+};
+
+use crate::display;
+
+/// The frame rate limits that can be picked in the settings. 0 leaves the frame rate alone.
+pub const FPS_LIMITS: [u32; 6] = [0, 30, 60, 120, 144, 240];
+
+pub struct FrameLimiter {
+    /// When the last frame was shown.
+    last: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+        }
+    }
+
+    /// Wait out the rest of the frame, to show no more than `limit` frames a second.
+    pub fn wait(&mut self, limit: u32) {
+        if let Some(frame_time) = frame_time(limit) {
+            let elapsed = self.last.elapsed();
+
+            if elapsed < frame_time {
+                thread::sleep(frame_time - elapsed);
+            }
+        }
+
+        self.last = Instant::now();
+    }
+}
+
+/// How long a frame takes at the provided limit, if there's a limit.
+fn frame_time(limit: u32) -> Option<Duration> {
+    if limit == 0 {
+        None
+    } else {
+        Some(Duration::from_secs_f64(1.0 / limit as f64))
+    }
+}
+
+/// Show the frame rate in the top right corner of the screen.
+pub fn draw_counter(ctx: &mut Context, font: Font) -> GameResult {
+    let (width, _) = display::screen_size(ctx);
+    let counter = Text::new(
+        TextFragment::new(format!("{:.0} FPS", timer::fps(ctx)))
+            .font(font)
+            .scale(Scale::uniform(18.0)),
+    );
+    let counter_width = counter.width(ctx) as f32;
+
+    graphics::draw(
+        ctx,
+        &counter,
+        DrawParam::default()
+            .dest(Point2::new(width - counter_width - 10.0, 10.0))
+            .color(graphics::Color::from_rgb(120, 255, 120)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_time() {
+        assert_eq!(frame_time(0), None);
+        assert_eq!(frame_time(50), Some(Duration::from_millis(20)));
+    }
+}
@@ -0,0 +1,271 @@
+//! Headless runs, playing a level through without showing it, for benchmarks and regression tests.
+//!
+//! `--headless` plays the recording given with `--replay` as the input script, or the first of the
+//! demos without one, for up to `--frames` frames with the same time step every frame. Nothing
+//! gets drawn and the window stays hidden, though ggez still wants a display to load the sprites
+//! on, a virtual one like Xvfb does on CI. Once the level is over or the frames ran out, how long
+//! the parts of a frame took gets printed, along with a checksum of where everything ended up. The
+//! same recording on the same build comes out with the same checksum, so a checksum that changed
+//! means the gameplay did too.
+
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use ggez::Context;
+
+use crate::{
+    config::Config,
+    error::FerrisResult,
+    game::{self, recording::Recording},
+    profiler,
+    utils::AssetManager,
+};
+
+/// The time step of every frame, a frame at 60 FPS.
+const DT: f32 = 1.0 / 60.0;
+
+/// How many frames get played without `--frames`, a minute's worth.
+pub const FRAMES: u32 = 3600;
+
+/// The config headless runs are played with, the same on every computer so the runs come out the
+/// same, and without any sound.
+pub fn config() -> Config {
+    Config {
+        volume: 0.0,
+        ..Config::default()
+    }
+}
+
+/// How long every part of the game took over the frames played, in milliseconds.
+#[derive(Default)]
+struct Timings {
+    parts: Vec<(&'static str, Vec<f32>)>,
+}
+
+impl Timings {
+    fn add(&mut self, name: &'static str, time: Duration) {
+        let time = time.as_secs_f32() * 1000.0;
+
+        match self.parts.iter_mut().find(|(part, _)| *part == name) {
+            Some((_, times)) => times.push(time),
+            None => self.parts.push((name, vec![time])),
+        }
+    }
+
+    /// The average, the 95th percentile and the slowest of `times`.
+    fn summary(times: &[f32]) -> (f32, f32, f32) {
+        if times.is_empty() {
+            return (0.0, 0.0, 0.0);
+        }
+
+        let mut sorted = times.to_vec();
+
+        sorted.sort_by(f32::total_cmp);
+
+        let mean = sorted.iter().sum::<f32>() / sorted.len() as f32;
+        let p95 = sorted[(sorted.len() - 1) * 95 / 100];
+
+        (mean, p95, sorted[sorted.len() - 1])
+    }
+
+    fn print(&self) {
+        println!("{:<10}{:>10}{:>10}{:>10}", "PART", "MEAN", "P95", "MAX");
+
+        for (name, times) in &self.parts {
+            let (mean, p95, max) = Self::summary(times);
+
+            println!(
+                "{:<10}{:>7.3} MS{:>7.3} MS{:>7.3} MS",
+                name.to_uppercase(),
+                mean,
+                p95,
+                max
+            );
+        }
+    }
+}
+
+/// Set the level of `recording` up to be played through the way it was recorded.
+fn start(
+    ctx: &mut Context,
+    asset_manager: Rc<AssetManager>,
+    recording: Recording,
+) -> FerrisResult<game::Game> {
+    let config = config();
+
+    config.apply(ctx, &asset_manager)?;
+
+    let mut game = game::Game::create(
+        ctx,
+        asset_manager,
+        &recording.level,
+        &config,
+        recording.difficulty,
+    )?
+    .into_inner()
+    .expect("The game was just made, nothing had the chance to poison its lock");
+    let new_game_plus = recording.new_game_plus.clone();
+    let daily = recording.daily.clone();
+
+    // Played like a demo, the run doesn't count for the save.
+    game.play_demo(recording)?;
+
+    if let Some(new_game_plus) = new_game_plus {
+        game.play_new_game_plus(new_game_plus);
+    }
+
+    if let Some(daily) = daily {
+        game.play_daily(daily);
+    }
+
+    Ok(game)
+}
+
+/// Play `recording` for up to `frames` frames and print how it went.
+pub fn run(
+    ctx: &mut Context,
+    asset_manager: Rc<AssetManager>,
+    recording: Recording,
+    frames: u32,
+) -> FerrisResult<()> {
+    let level = recording.level.clone();
+    let mut game = start(ctx, asset_manager, recording)?;
+    let mut overlay = profiler::Overlay::new();
+    let mut timings = Timings::default();
+    let mut played = 0;
+    let start = Instant::now();
+
+    overlay.toggle();
+
+    while played < frames {
+        ctx.timer_context.tick();
+
+        let update = Instant::now();
+        let screen = game.update(ctx, DT)?;
+        let frame_time = update.elapsed();
+
+        timings.add("update", frame_time);
+
+        for (part, time) in profiler::this_frame() {
+            timings.add(part, time);
+        }
+
+        overlay.end_frame(frame_time);
+        played += 1;
+
+        // The demo is over once the level is or the recording ran out.
+        if screen.is_some() {
+            break;
+        }
+    }
+
+    let stats = game.stats();
+
+    println!(
+        "Played {} frames of {} in {:.2} s",
+        played,
+        level,
+        start.elapsed().as_secs_f32()
+    );
+    timings.print();
+    println!(
+        "Kills {}, score {}, time {:.2} s",
+        stats.kills, stats.score, stats.time
+    );
+    println!("Checksum {:016x}", game.checksum());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ggez::{graphics, ContextBuilder};
+
+    use super::*;
+    use crate::loading::Loading;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Timings::summary(&[]), (0.0, 0.0, 0.0));
+
+        let times: Vec<f32> = (1..=100).rev().map(|time| time as f32).collect();
+
+        assert_eq!(Timings::summary(&times), (50.5, 95.0, 100.0));
+    }
+
+    #[test]
+    fn test_timings() {
+        let mut timings = Timings::default();
+
+        timings.add("update", Duration::from_millis(2));
+        timings.add("physics", Duration::from_millis(1));
+        timings.add("update", Duration::from_millis(4));
+
+        assert_eq!(timings.parts[0], ("update", vec![2.0, 4.0]));
+        assert_eq!(timings.parts[1], ("physics", vec![1.0]));
+    }
+
+    /// Play `recording` through to its end, with as many updates every frame as `speed` says for
+    /// that frame, and come out with the checksum of the level.
+    fn play_through(
+        ctx: &mut Context,
+        asset_manager: &Rc<AssetManager>,
+        recording: &Recording,
+        speed: impl Fn(u32) -> u32,
+    ) -> u64 {
+        let mut game = start(ctx, Rc::clone(asset_manager), recording.clone()).unwrap();
+
+        for frame in 0..FRAMES {
+            game.set_playback_speed(speed(frame));
+            ctx.timer_context.tick();
+
+            if game.update(ctx, DT).unwrap().is_some() {
+                break;
+            }
+        }
+
+        game.checksum()
+    }
+
+    #[test]
+    #[ignore = "needs a display to load the sprites on, run it with --ignored under Xvfb"]
+    fn test_playback_speed() {
+        let dirs = vec![PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources")];
+        let (mut ctx, _event_loop) = ContextBuilder::new("call_of_ferris_test", "test")
+            .add_resource_path(&dirs[0])
+            .build()
+            .unwrap();
+
+        graphics::window(&ctx).hide();
+
+        let mut asset_manager = AssetManager::new(&mut ctx).unwrap();
+        let mut loading = Loading::assets(&mut ctx, &mut asset_manager, &dirs).unwrap();
+
+        while !loading.step(&mut ctx, &mut asset_manager) {}
+        loading.finish().unwrap();
+
+        let asset_manager = Rc::new(asset_manager);
+        let recording = game::recording::demos(&asset_manager).remove(0);
+        let normal = play_through(&mut ctx, &asset_manager, &recording, |_| 1);
+
+        // However the updates get bunched up into frames, the level comes out the same.
+        assert_eq!(
+            play_through(&mut ctx, &asset_manager, &recording, |_| 2),
+            normal
+        );
+        assert_eq!(
+            play_through(&mut ctx, &asset_manager, &recording, |frame| {
+                if frame % 7 < 3 {
+                    1
+                } else {
+                    2
+                }
+            }),
+            normal
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! The best runs of every level.
+//!
+//! High scores get stored as TOML in the user data directory, next to the save, with a table of the
+//! best few runs for every level:
+//!
+//! ```toml
+//! [[levels.01]]
+//! name = "FERRIS"
+//! score = 1200
+//! kills = 8
+//! time = 95.5
+//! ```
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use ggez::{filesystem, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::FerrisResult, utils::write_atomic};
+
+/// A run that made it into a table.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    /// What the player typed in when they got it.
+    pub name: String,
+    pub score: u32,
+    pub kills: i32,
+    /// Seconds the run lasted.
+    pub time: f32,
+}
+
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    /// The tables of every level by their ids, best run first.
+    levels: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+    const FILE_NAME: &'static str = "high_scores.toml";
+    /// How many runs every table keeps.
+    pub const KEPT: usize = 5;
+
+    /// Load the high scores from the user data directory. A missing or broken file leaves you
+    /// with empty tables.
+    pub fn load(ctx: &Context) -> Self {
+        fs::read_to_string(Self::path(ctx))
+            .ok()
+            .and_then(|high_scores| toml::from_str(&high_scores).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the high scores to the user data directory.
+    pub fn save(&self, ctx: &Context) -> FerrisResult<()> {
+        let path = Self::path(ctx);
+
+        write_atomic(&path, &toml::to_string(self)?)
+    }
+
+    /// The best runs of the level, best first.
+    pub fn table(&self, level: &str) -> &[HighScore] {
+        self.levels.get(level).map_or(&[], |table| table.as_slice())
+    }
+
+    /// Where a run with the score would land in the table of the level, if it makes it in at
+    /// all. A run with the same score as one already in the table goes after it.
+    pub fn rank(&self, level: &str, score: u32) -> Option<usize> {
+        let rank = self
+            .table(level)
+            .iter()
+            .position(|high_score| score > high_score.score)
+            .unwrap_or_else(|| self.table(level).len());
+
+        if score > 0 && rank < Self::KEPT {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+
+    /// Add the run to the table of the level if it's good enough. Returns where it landed.
+    pub fn insert(&mut self, level: &str, high_score: HighScore) -> Option<usize> {
+        let rank = self.rank(level, high_score.score)?;
+        let table = self.levels.entry(level.to_string()).or_default();
+
+        table.insert(rank, high_score);
+        table.truncate(Self::KEPT);
+
+        Some(rank)
+    }
+
+    fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_data_dir(ctx).join(Self::FILE_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: u32) -> HighScore {
+        HighScore {
+            name: String::from("FERRIS"),
+            score,
+            kills: 0,
+            time: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_tables() {
+        let mut high_scores = HighScores::default();
+
+        for score in 1..=HighScores::KEPT as u32 {
+            high_scores.insert("01", run(score * 100));
+        }
+
+        // The table is full, only runs better than the worst one get in.
+        assert_eq!(high_scores.rank("01", 50), None);
+        assert_eq!(high_scores.insert("01", run(250)), Some(3));
+        assert_eq!(high_scores.table("01").len(), HighScores::KEPT);
+        assert_eq!(high_scores.table("01")[0].score, 500);
+        assert!(high_scores.table("02").is_empty());
+
+        let saved = toml::to_string(&high_scores).unwrap();
+
+        assert_eq!(toml::from_str::<HighScores>(&saved).unwrap(), high_scores);
+    }
+}
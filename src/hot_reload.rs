@@ -0,0 +1,146 @@
+//! Reloading assets as their files change, built in with the `debug` feature.
+//!
+//! Every so often the resources directory gets checked for files that have changed since the last
+//! look. Changed sprites, atlases, levels, dialogues and scripts get loaded again, and the level being played
+//! gets built again from scratch so it picks up the new versions.
+
+use std::{collections::HashMap, fs, path::PathBuf, time::SystemTime};
+
+/// A file in the resources directory, as its folder and name.
+pub type ResourceFile = (&'static str, String);
+
+pub struct HotReload {
+    /// The resource directories of the game and the mods.
+    dirs: Vec<PathBuf>,
+    /// When every watched file was last changed.
+    files: HashMap<ResourceFile, SystemTime>,
+    /// Time until the next look at the files.
+    timer: f32,
+}
+
+impl HotReload {
+    /// The folders with the assets that can be reloaded.
+    const FOLDERS: [&'static str; 11] = [
+        "images",
+        "atlases",
+        "maps",
+        "arenas",
+        "dialogues",
+        "scripts",
+        "achievements",
+        "lang",
+        "cutscenes",
+        "remixes",
+        "demos",
+    ];
+    /// How long to wait between looking for changes, in seconds.
+    const INTERVAL: f32 = 0.5;
+
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self {
+            files: scan(&dirs),
+            dirs,
+            timer: Self::INTERVAL,
+        }
+    }
+
+    /// Look at the files every now and then. Returns the files that changed or showed up since the
+    /// last look.
+    pub fn update(&mut self, dt: f32) -> Vec<ResourceFile> {
+        self.timer -= dt;
+
+        if self.timer > 0.0 {
+            return vec![];
+        }
+
+        self.timer = Self::INTERVAL;
+
+        let files = scan(&self.dirs);
+        let mut changed = diff(&self.files, &files);
+
+        // The sprites of an atlas get cut out of its image by its description, so a changed atlas
+        // image means loading every description again.
+        if changed.iter().any(|(folder, _)| *folder == "atlases") {
+            changed.retain(|(folder, _)| *folder != "atlases");
+            changed.extend(
+                files
+                    .keys()
+                    .filter(|(folder, name)| *folder == "atlases" && name.ends_with(".toml"))
+                    .cloned(),
+            );
+        }
+
+        self.files = files;
+
+        changed
+    }
+}
+
+/// When every file in the watched folders was last changed, going by the newest one of the files
+/// with the same name in the resource directories. Files that can't be looked at are left out.
+fn scan(dirs: &[PathBuf]) -> HashMap<ResourceFile, SystemTime> {
+    let mut files: HashMap<ResourceFile, SystemTime> = HashMap::new();
+
+    for (dir, folder) in dirs
+        .iter()
+        .flat_map(|dir| HotReload::FOLDERS.iter().map(move |folder| (dir, *folder)))
+    {
+        let entries = match fs::read_dir(dir.join(folder)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                let file = (folder, entry.file_name().to_string_lossy().to_string());
+                let newest = files.entry(file).or_insert(modified);
+
+                *newest = (*newest).max(modified);
+            }
+        }
+    }
+
+    files
+}
+
+/// The files that are new or changed in `new`, sorted so they get reloaded in the same order.
+fn diff(
+    old: &HashMap<ResourceFile, SystemTime>,
+    new: &HashMap<ResourceFile, SystemTime>,
+) -> Vec<ResourceFile> {
+    let mut changed = new
+        .iter()
+        .filter(|(file, modified)| old.get(*file) != Some(*modified))
+        .map(|(file, _)| file.clone())
+        .collect::<Vec<_>>();
+
+    changed.sort();
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_diff() {
+        let then = SystemTime::UNIX_EPOCH;
+        let now = then + Duration::from_secs(1);
+        let file = |name: &str| ("maps", name.to_string());
+
+        let old = vec![(file("01.map"), then), (file("02.map"), then)]
+            .into_iter()
+            .collect();
+        let new = vec![
+            (file("01.map"), then),
+            (file("02.map"), now),
+            (file("03.map"), now),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(diff(&old, &new), vec![file("02.map"), file("03.map")]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Translations of the text of the game.
+//!
+//! The text is written in English in the code, and every other language is a TOML file of the
+//! `lang` folder named after its code, with the English text and what it becomes:
+//!
+//! ```toml
+//! name = "Deutsch"
+//!
+//! [strings]
+//! "START GAME" = "SPIEL STARTEN"
+//! "LEVEL {level}" = "LEVEL {level}"
+//! ```
+//!
+//! Bits of text like `{level}` get filled in as the text is shown. Anything a language doesn't
+//! have stays in English. Languages written in scripts Consolas doesn't have can bring a font of
+//! the `fonts` folder along with `font = "NotoSansJP.ttf"`, which all of the text gets drawn in.
+
+use std::{collections::HashMap, fmt::Display};
+
+use serde::Deserialize;
+
+use crate::utils::AssetManager;
+
+/// What a language file has in it.
+#[derive(Deserialize)]
+struct LanguageFile {
+    name: String,
+    #[serde(default)]
+    font: Option<String>,
+    #[serde(default)]
+    strings: HashMap<String, String>,
+}
+
+pub struct Language {
+    /// What the language goes by in the config, like `de`.
+    pub code: String,
+    /// What the language calls itself.
+    pub name: String,
+    /// The font to draw the language in, for scripts Consolas doesn't have.
+    pub font: Option<String>,
+    strings: HashMap<String, String>,
+}
+
+impl Language {
+    fn english() -> Self {
+        Self {
+            code: Localization::ENGLISH.to_string(),
+            name: String::from("English"),
+            font: None,
+            strings: HashMap::new(),
+        }
+    }
+}
+
+pub struct Localization {
+    /// English first, then the others by their code.
+    languages: Vec<Language>,
+    /// The language the text is shown in.
+    current: usize,
+}
+
+impl Localization {
+    pub const ENGLISH: &'static str = "en";
+
+    /// Read every language in the `lang` folder. Files that can't be read are left out.
+    pub fn load(asset_manager: &AssetManager) -> Self {
+        let mut languages = vec![];
+
+        for name in asset_manager.get_files("lang") {
+            let code = name.trim_end_matches(".toml");
+            let file = asset_manager.get_file(&format!("/lang/{}", name));
+
+            match toml::from_str::<LanguageFile>(&file) {
+                Ok(file) => languages.push(Language {
+                    code: code.to_string(),
+                    name: file.name,
+                    font: file.font,
+                    strings: file.strings,
+                }),
+                Err(error) => log::error!("Cannot read the language {}: {}", name, error),
+            }
+        }
+
+        Self::new(languages)
+    }
+
+    fn new(languages: Vec<Language>) -> Self {
+        let languages = std::iter::once(Language::english())
+            .chain(
+                languages
+                    .into_iter()
+                    .filter(|language| language.code != Self::ENGLISH),
+            )
+            .collect();
+
+        Self {
+            languages,
+            current: 0,
+        }
+    }
+
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.languages[self.current]
+    }
+
+    /// Show the text in the language with `code`, or in English if there's no such language.
+    pub fn set_language(&mut self, code: &str) {
+        self.current = self
+            .languages
+            .iter()
+            .position(|language| language.code == code)
+            .unwrap_or_else(|| {
+                log::warn!("There's no language {}, going with English", code);
+
+                0
+            });
+    }
+
+    /// The `english` text in the current language.
+    pub fn text<'a>(&'a self, english: &'a str) -> &'a str {
+        self.language()
+            .strings
+            .get(english)
+            .map_or(english, String::as_str)
+    }
+
+    /// The `english` text in the current language, with every `{name}` in it filled in with the
+    /// value going by that name.
+    pub fn text_with(&self, english: &str, values: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.text(english).to_string();
+
+        for (name, value) in values {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+
+        text
+    }
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback() {
+        let file: LanguageFile = toml::from_str(
+            r#"
+            name = "Deutsch"
+
+            [strings]
+            "START GAME" = "SPIEL STARTEN"
+            "LEVEL {level} DONE" = "LEVEL {level} GESCHAFFT"
+            "#,
+        )
+        .unwrap();
+        let mut localization = Localization::new(vec![Language {
+            code: String::from("de"),
+            name: file.name,
+            font: file.font,
+            strings: file.strings,
+        }]);
+
+        assert_eq!(localization.text("START GAME"), "START GAME");
+
+        localization.set_language("de");
+        assert_eq!(localization.text("START GAME"), "SPIEL STARTEN");
+        assert_eq!(
+            localization.text_with("LEVEL {level} DONE", &[("level", &"01")]),
+            "LEVEL 01 GESCHAFFT"
+        );
+
+        // Whatever German doesn't have stays in English.
+        assert_eq!(localization.text("QUIT"), "QUIT");
+
+        localization.set_language("xx");
+        assert_eq!(localization.language().code, Localization::ENGLISH);
+        assert_eq!(localization.languages().len(), 2);
+    }
+}
@@ -0,0 +1,239 @@
+//! The online leaderboard.
+//!
+//! When the config names a leaderboard, runs that make it into the high scores get sent there and
+//! the best runs of everyone get shown instead of only the ones on this computer. The leaderboard
+//! is a plain HTTP server, talked to in the background so the game never waits on it:
+//!
+//! - `POST <leaderboard>/scores/<level>` with a high score as JSON adds the run.
+//! - `GET <leaderboard>/scores/<level>` answers with the best runs as a JSON list, best first.
+//!
+//! The level names get percent-encoded to fit in the path. Whenever the leaderboard can't be
+//! reached the local high scores are all there is.
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::high_scores::HighScore;
+
+/// Where a leaderboard is, taken apart from its address.
+#[derive(PartialEq, Debug, Clone)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    /// The path the leaderboard lives under, without a trailing slash.
+    path: String,
+}
+
+impl Endpoint {
+    /// Read an address like `http://scores.example.com:8080/ferris`. There's no TLS to be had, so
+    /// only `http://` addresses work.
+    fn parse(address: &str) -> Result<Self, String> {
+        let rest = address
+            .trim()
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("{} isn't an http:// address", address))?;
+
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("{} isn't a valid port", port))?,
+            ),
+            None => (authority, 80),
+        };
+
+        if host.is_empty() {
+            return Err(format!("{} has no host", address));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Send the run to the leaderboard, if there's one, then get the best runs.
+    fn exchange(&self, path: &str, run: Option<&HighScore>) -> io::Result<Vec<HighScore>> {
+        if let Some(run) = run {
+            self.request("POST", path, &serde_json::to_string(run)?)?;
+        }
+
+        let body = self.request("GET", path, "")?;
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Send a request, returning the body of the answer if the server was happy with it.
+    fn request(&self, method: &str, path: &str, body: &str) -> io::Result<String> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cannot find the host"))?;
+
+        let mut stream = TcpStream::connect_timeout(&address, Leaderboard::TIMEOUT)?;
+
+        stream.set_read_timeout(Some(Leaderboard::TIMEOUT))?;
+        stream.set_write_timeout(Some(Leaderboard::TIMEOUT))?;
+
+        // HTTP/1.0 keeps the answer in one piece, and the connection closes after it.
+        write!(
+            stream,
+            "{} {}{} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            self.path,
+            path,
+            self.host,
+            body.len(),
+            body,
+        )?;
+
+        parse_answer(&read_answer(stream)?)
+    }
+}
+
+/// Read the whole answer, failing on ones too long to be a list of high scores.
+fn read_answer(stream: impl Read) -> io::Result<String> {
+    /// The most the leaderboard gets to answer with, in bytes.
+    const MAX_ANSWER: u64 = 1024 * 1024;
+
+    let mut answer = String::new();
+
+    // One more byte than is allowed tells an answer that's too long from one that just fits.
+    stream.take(MAX_ANSWER + 1).read_to_string(&mut answer)?;
+
+    if answer.len() as u64 > MAX_ANSWER {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The leaderboard answered with too much",
+        ));
+    }
+
+    Ok(answer)
+}
+
+/// Write `segment` down so it fits in a single segment of a path, everything but letters, digits
+/// and `-._~` percent-encoded.
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Pick the body out of an HTTP answer, or what went wrong.
+fn parse_answer(answer: &str) -> io::Result<String> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let status = answer
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid(String::from("Not an HTTP answer")))?;
+
+    if !(200..300).contains(&status) {
+        return Err(invalid(format!("The leaderboard answered {}", status)));
+    }
+
+    let body = answer.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+
+    Ok(body.to_string())
+}
+
+/// Sends a run to the leaderboard and gets the best runs back, in the background.
+pub struct Leaderboard {
+    answer: Receiver<io::Result<Vec<HighScore>>>,
+}
+
+impl Leaderboard {
+    /// How long the leaderboard gets to answer.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Ask the leaderboard at `address` for the best runs of the level, sending `run` there first
+    /// if there's one.
+    pub fn exchange(address: &str, level: &str, run: Option<HighScore>) -> Result<Self, String> {
+        let endpoint = Endpoint::parse(address)?;
+        let path = format!("/scores/{}", encode_segment(level));
+        let (sender, answer) = mpsc::channel();
+
+        thread::spawn(move || {
+            let _ = sender.send(endpoint.exchange(&path, run.as_ref()));
+        });
+
+        Ok(Self { answer })
+    }
+
+    /// The best runs, once the leaderboard answered. Fails when it couldn't be reached.
+    pub fn poll(&self) -> Option<io::Result<Vec<HighScore>>> {
+        match self.answer.try_recv() {
+            Ok(answer) => Some(answer),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(io::Error::other("The leaderboard went away")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            Endpoint::parse("http://scores.example.com:8080/ferris/"),
+            Ok(Endpoint {
+                host: String::from("scores.example.com"),
+                port: 8080,
+                path: String::from("/ferris"),
+            })
+        );
+        assert_eq!(Endpoint::parse("http://localhost").unwrap().port, 80);
+        assert!(Endpoint::parse("https://scores.example.com").is_err());
+        assert!(Endpoint::parse("http://:80").is_err());
+    }
+
+    #[test]
+    fn test_answer() {
+        let answer = "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n[]";
+
+        assert_eq!(parse_answer(answer).unwrap(), "[]");
+        assert!(parse_answer("HTTP/1.1 404 Not Found\r\n\r\n").is_err());
+        assert!(parse_answer("nonsense").is_err());
+    }
+
+    #[test]
+    fn test_long_answer() {
+        assert_eq!(
+            read_answer(&b"HTTP/1.0 200 OK\r\n\r\n[]"[..])
+                .unwrap()
+                .len(),
+            21
+        );
+        assert!(read_answer(io::repeat(b' ')).is_err());
+    }
+
+    #[test]
+    fn test_encode_segment() {
+        assert_eq!(encode_segment("boss_rush.map"), "boss_rush.map");
+        assert_eq!(encode_segment("../a b?c#d"), "..%2Fa%20b%3Fc%23d");
+        assert_eq!(encode_segment("ü"), "%C3%BC");
+    }
+}
@@ -0,0 +1,298 @@
+//! Playing in lockstep.
+//!
+//! Instead of telling each other where everything is, the players only tell each other what they
+//! are doing, and everyone simulates the level the same way from the same inputs. A frame only
+//! gets simulated once the inputs of every player for it are in, so the level waits for whoever
+//! is the slowest. What a player does gets scheduled a few frames ahead, the input delay, which
+//! gives it time to reach the others before they need it.
+//!
+//! After every frame everyone sends along a checksum of the state of the level. Checksums that
+//! don't match for the same frame mean the simulations went apart, which gets reported rather
+//! than fixed.
+
+use std::collections::BTreeMap;
+
+use crate::game::recording::InputFrame;
+
+pub struct Lockstep {
+    /// The player playing on this computer.
+    local: u8,
+    players: Vec<u8>,
+    /// How many frames ahead the inputs of the local player get scheduled.
+    delay: u32,
+    /// The next frame to simulate.
+    frame: u32,
+    /// The inputs of everyone, by the frame they're for and the player.
+    inputs: BTreeMap<(u32, u8), InputFrame>,
+    /// The checksums of the frames simulated here.
+    checksums: BTreeMap<u32, u64>,
+    /// The checksums of the others for frames that haven't been simulated here yet.
+    pending: Vec<(u8, u32, u64)>,
+    /// The first frame the checksums didn't match for, once they didn't.
+    desync: Option<u32>,
+}
+
+impl Lockstep {
+    /// How many frames ahead the inputs get scheduled.
+    pub const DELAY: u32 = 3;
+    /// How many frames of checksums are kept around to compare with the others.
+    const HISTORY: u32 = 120;
+
+    /// Start playing in lockstep as `local`, with everyone in `players`. Nobody does anything for
+    /// the first `delay` frames, so those can be simulated right away.
+    pub fn new(local: u8, players: &[u8], delay: u32) -> Self {
+        let mut inputs = BTreeMap::new();
+
+        for frame in 0..delay {
+            for player in players {
+                inputs.insert((frame, *player), InputFrame::default());
+            }
+        }
+
+        Self {
+            local,
+            players: players.to_vec(),
+            delay,
+            frame: 0,
+            inputs,
+            checksums: BTreeMap::new(),
+            pending: vec![],
+            desync: None,
+        }
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Whether the inputs of every player for the next frame are in.
+    pub fn is_ready(&self) -> bool {
+        self.players
+            .iter()
+            .all(|player| self.inputs.contains_key(&(self.frame, *player)))
+    }
+
+    /// Schedule what the local player is doing, and move on to the next frame. Returns the inputs
+    /// of every player for the frame to simulate, or `None` if they aren't all in yet.
+    pub fn advance(&mut self, local: InputFrame) -> Option<Vec<(u8, InputFrame)>> {
+        if !self.is_ready() {
+            return None;
+        }
+
+        self.inputs
+            .insert((self.frame + self.delay, self.local), local);
+
+        let frame = self.frame;
+        let inputs = self
+            .players
+            .iter()
+            .map(|player| (*player, self.inputs[&(frame, *player)].clone()))
+            .collect();
+
+        self.frame += 1;
+
+        // The others can be up to the input delay behind, so the local inputs they might still
+        // be waiting on stay around.
+        let oldest = self.frame.saturating_sub(self.delay);
+
+        self.inputs.retain(|(frame, _), _| *frame >= oldest);
+
+        Some(inputs)
+    }
+
+    /// The inputs of the local player the others might still be waiting on, to send them.
+    pub fn outgoing(&self) -> Vec<(u32, InputFrame)> {
+        self.inputs
+            .iter()
+            .filter(|((_, player), _)| *player == self.local)
+            .map(|((frame, _), input)| (*frame, input.clone()))
+            .collect()
+    }
+
+    /// Whether `frame` is further ahead than the others could get, which only a broken or hostile
+    /// player would send. Those get left out, so they can't pile up.
+    fn is_too_far(&self, frame: u32) -> bool {
+        frame > self.frame + self.delay + Self::HISTORY
+    }
+
+    /// Take in the inputs another player sent. The ones for frames already simulated or too far
+    /// ahead, or for players who aren't playing, are left out.
+    pub fn receive(&mut self, player: u8, inputs: Vec<(u32, InputFrame)>) {
+        if player == self.local || !self.players.contains(&player) {
+            return;
+        }
+
+        for (frame, input) in inputs {
+            if frame >= self.frame && !self.is_too_far(frame) {
+                self.inputs.entry((frame, player)).or_insert(input);
+            }
+        }
+    }
+
+    /// Write down the checksum of the state after simulating `frame`.
+    pub fn record(&mut self, frame: u32, checksum: u64) {
+        self.checksums.insert(frame, checksum);
+
+        let checked: Vec<(u8, u32, u64)> = std::mem::take(&mut self.pending);
+
+        for (player, frame, checksum) in checked {
+            self.compare(player, frame, checksum);
+        }
+
+        let oldest = frame.saturating_sub(Self::HISTORY);
+
+        self.checksums.retain(|frame, _| *frame >= oldest);
+    }
+
+    /// The frame simulated last and the checksum of the state after it, for the others.
+    pub fn last_checksum(&self) -> Option<(u32, u64)> {
+        self.checksums
+            .iter()
+            .next_back()
+            .map(|(frame, checksum)| (*frame, *checksum))
+    }
+
+    /// Check the checksum another player got after simulating `frame` against the one here,
+    /// or once it's been simulated here too.
+    pub fn compare(&mut self, player: u8, frame: u32, checksum: u64) {
+        match self.checksums.get(&frame) {
+            Some(ours) if *ours != checksum && self.desync.is_none() => self.desync = Some(frame),
+            Some(_) => {}
+            // Frames simulated here long ago can't be checked any more, and the ones too far ahead
+            // never will be. A checksum sent again only needs checking once.
+            None if frame < self.frame || self.is_too_far(frame) => {}
+            None if self
+                .pending
+                .iter()
+                .any(|(from, pending, _)| (*from, *pending) == (player, frame)) => {}
+            None => self.pending.push((player, frame, checksum)),
+        }
+    }
+
+    /// The first frame the simulations went apart at, if they did.
+    pub fn desync(&self) -> Option<u32> {
+        self.desync
+    }
+}
+
+/// Adds up the state of the level into a checksum that comes out the same on every computer.
+pub struct Checksum(u64);
+
+impl Checksum {
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Add some bytes, FNV-1a style.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::Action;
+
+    #[test]
+    fn test_waits_for_everyone() {
+        let mut host = Lockstep::new(0, &[0, 1], 2);
+        let mut client = Lockstep::new(1, &[0, 1], 2);
+        let jump = InputFrame::new(vec![Action::Jump], vec![Action::Jump]);
+
+        // The frames within the input delay go by without waiting.
+        assert!(host.advance(jump.clone()).is_some());
+        assert!(host.advance(InputFrame::default()).is_some());
+
+        // Then the host needs to hear from the client.
+        assert!(!host.is_ready());
+        assert!(host.advance(InputFrame::default()).is_none());
+
+        client.advance(InputFrame::default());
+        client.advance(InputFrame::default());
+        host.receive(1, client.outgoing());
+
+        // What the host did on the first frame comes in on the frame after the delay, for both.
+        client.receive(0, host.outgoing());
+
+        let on_host = host.advance(InputFrame::default()).unwrap();
+        let on_client = client.advance(InputFrame::default()).unwrap();
+
+        assert_eq!(on_host, on_client);
+        assert_eq!(on_host[0], (0, jump));
+        assert_eq!(host.frame(), 3);
+    }
+
+    #[test]
+    fn test_desync() {
+        let mut lockstep = Lockstep::new(0, &[0, 1], 2);
+
+        lockstep.advance(InputFrame::default());
+        lockstep.record(0, 42);
+
+        lockstep.compare(1, 0, 42);
+        assert_eq!(lockstep.desync(), None);
+
+        // A checksum from ahead gets checked once the frame has been simulated here.
+        lockstep.compare(1, 1, 7);
+        assert_eq!(lockstep.desync(), None);
+        lockstep.advance(InputFrame::default());
+        lockstep.record(1, 8);
+
+        assert_eq!(lockstep.desync(), Some(1));
+        assert_eq!(lockstep.last_checksum(), Some((1, 8)));
+    }
+
+    #[test]
+    fn test_far_ahead() {
+        let mut lockstep = Lockstep::new(0, &[0, 1], 2);
+        let far = 2 + Lockstep::HISTORY + 1;
+
+        lockstep.receive(
+            1,
+            vec![
+                (far, InputFrame::default()),
+                (far - 1, InputFrame::default()),
+            ],
+        );
+        assert!(!lockstep.inputs.contains_key(&(far, 1)));
+        assert!(lockstep.inputs.contains_key(&(far - 1, 1)));
+
+        lockstep.compare(1, far, 7);
+        lockstep.compare(1, 5, 7);
+        lockstep.compare(1, 5, 7);
+        assert_eq!(lockstep.pending, vec![(1, 5, 7)]);
+    }
+
+    #[test]
+    fn test_checksum() {
+        let sum = |values: &[f32]| {
+            let mut checksum = Checksum::new();
+
+            for value in values {
+                checksum.write_f32(*value);
+            }
+
+            checksum.finish()
+        };
+
+        assert_eq!(sum(&[1.0, 2.0]), sum(&[1.0, 2.0]));
+        assert_ne!(sum(&[1.0, 2.0]), sum(&[2.0, 1.0]));
+    }
+}
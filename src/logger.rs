@@ -0,0 +1,162 @@
+//! Writing down what the game is up to.
+//!
+//! Everything logged goes to stderr and to a log file in the user data directory, which starts
+//! over with every run. The game's own messages are kept from the `info` level up, or from `debug`
+//! up in debug builds, while the crates it's built on only get to tell about warnings and errors.
+//! When the game crashes it points at the log file, to be attached to the bug report.
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    fs::{self, File},
+    io::Write,
+    panic,
+    path::PathBuf,
+    sync::Mutex,
+    time::Instant,
+};
+
+use directories::ProjectDirs;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+struct Logger {
+    start: Instant,
+    file: Option<Mutex<File>>,
+}
+
+impl Logger {
+    /// Where the game's own messages come from.
+    const TARGET: &'static str = env!("CARGO_CRATE_NAME");
+
+    fn level(target: &str) -> Level {
+        if target.starts_with(Self::TARGET) {
+            if cfg!(feature = "debug") {
+                Level::Debug
+            } else {
+                Level::Info
+            }
+        } else {
+            Level::Warn
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Self::level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_line(
+            self.start.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            &record.args().to_string(),
+        );
+
+        eprintln!("{}", line);
+
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// A line of the log, with when it happened, how bad it is and the module it came from.
+fn format_line(time: f32, level: Level, target: &str, message: &str) -> String {
+    let module = target
+        .strip_prefix(Logger::TARGET)
+        .map(|module| module.trim_start_matches("::"))
+        .filter(|module| !module.is_empty())
+        .unwrap_or(target);
+
+    format!("[{:>9.3} {:<5} {}] {}", time, level, module, message)
+}
+
+/// Where the log file goes, the same directory ggez uses as the user data directory.
+fn path() -> Option<PathBuf> {
+    ProjectDirs::from("", crate::AUTHOR, crate::GAME_ID)
+        .map(|dirs| dirs.data_dir().join("call_of_ferris.log"))
+}
+
+/// Start logging, and point at the log file when the game crashes. Returns where the log file is,
+/// if it could be made.
+pub fn init() -> Option<PathBuf> {
+    let path = path();
+
+    if let Some(dir) = path.as_ref().and_then(|path| path.parent()) {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let file = path.as_ref().and_then(|path| File::create(path).ok());
+    let path = path.filter(|_| file.is_some());
+
+    let logger = Logger {
+        start: Instant::now(),
+        file: file.map(Mutex::new),
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return None;
+    }
+
+    log::set_max_level(LevelFilter::Trace);
+
+    let crash_path = path.clone();
+
+    // The panic goes in the log instead of only to stderr, with a backtrace when one is asked for
+    // with `RUST_BACKTRACE`.
+    panic::set_hook(Box::new(move |info| {
+        log::error!("{}", info);
+
+        let backtrace = Backtrace::capture();
+
+        if backtrace.status() == BacktraceStatus::Captured {
+            log::error!("{}", backtrace);
+        }
+
+        log::logger().flush();
+
+        if let Some(path) = &crash_path {
+            eprintln!(
+                "Call of Ferris crashed. Please attach {} to the bug report.",
+                path.display()
+            );
+        }
+    }));
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_line() {
+        let game = format!("{}::audio", Logger::TARGET);
+
+        assert_eq!(
+            format_line(1.5, Level::Warn, &game, "Cannot play menu.wav"),
+            "[    1.500 WARN  audio] Cannot play menu.wav"
+        );
+        assert_eq!(
+            format_line(0.0, Level::Info, "gfx_device_gl", "Hello"),
+            "[    0.000 INFO  gfx_device_gl] Hello"
+        );
+        assert_eq!(Logger::level("wgpu"), Level::Warn);
+    }
+}
@@ -5,88 +5,275 @@
 //!
 //! For a fuller outline, see the project's [README.md](https://github.com/Andy-Python-Programmer/CallOfFerris)
 
-use std::{fs, rc::Rc, sync::Mutex};
+use std::{env, path::PathBuf, rc::Rc};
 
+use config::Config;
+use devices::InputDevice;
+use error::{ErrorBanner, FerrisError, FerrisResult};
+use ggez::{
+    conf::WindowSetup,
+    event::{
+        self,
+        winit_event::{Event, WindowEvent},
+        EventHandler, EventsLoop,
+    },
+};
 use ggez::{
-    conf::WindowMode,
     event::KeyCode,
     event::KeyMods,
-    graphics::{set_screen_coordinates, Rect},
+    event::{Axis, Button, MouseButton},
+    graphics,
+    input::{gamepad::GamepadId, mouse},
     Context, ContextBuilder, GameResult,
 };
-use ggez::{
-    conf::WindowSetup,
-    event::{self, EventHandler},
-};
-use utils::{AssetManager, FerrisResult};
-
+use ggez_goodies::nalgebra_glm::Vec2;
+use scene::{SceneChange, SceneStack};
+use scenes::Shared;
+use transition::{Transition, TransitionKind};
+use utils::AssetManager;
+
+mod achievements;
+mod audio;
+mod bindings;
+mod config;
+#[cfg(feature = "discord")]
+mod discord;
+mod display;
+mod error;
+mod frame_rate;
+mod headless;
+mod high_scores;
+#[cfg(feature = "debug")]
+mod hot_reload;
+mod i18n;
+mod leaderboard;
+mod lockstep;
+mod logger;
+mod mods;
+mod net;
+mod palette;
+mod profiler;
+mod save;
+mod scene;
 mod screens;
+mod screenshot;
+mod transition;
 mod utils;
 
 pub use screens::*;
 
-/// Initial window width.
-const INIT_WIDTH: f32 = 1000.0;
-/// Initial window height.
-const INIT_HEIGHT: f32 = 600.0;
-
-/// Minimum width.
-const MIN_WIDTH: f32 = 1000.0;
-/// Minimum height.
-const MIN_HEIGHT: f32 = 600.0;
-
-fn init_assets(ctx: &mut Context) -> FerrisResult<AssetManager> {
-    let mut asset_manager = AssetManager::new();
+/// The id ggez knows the game by, it picks the user directories.
+const GAME_ID: &str = "Call of Ferris";
+/// The author ggez knows the game by, it picks the user directories.
+const AUTHOR: &str = "Borrow Checker";
+
+/// Options passed on the command line.
+#[derive(Default)]
+struct Options {
+    /// Where to write a recording of every run, with `--record <file>`.
+    record: Option<PathBuf>,
+    /// The recording to play back right away, with `--replay <file>`.
+    replay: Option<PathBuf>,
+    /// The port to host an online game on, with `--host <port>`.
+    host: Option<u16>,
+    /// The address of the online game to join, with `--join <address>`.
+    join: Option<String>,
+    /// Whether the hosted game gets played in lockstep, with `--lockstep`.
+    lockstep: bool,
+    /// The seed to play every run on, with `--seed <seed>`.
+    seed: Option<u64>,
+    /// Whether to play the replay through without showing it and print how it went, with
+    /// `--headless`.
+    headless: bool,
+    /// How many frames a headless run goes on for at most, with `--frames <count>`.
+    frames: Option<u32>,
+}
 
-    let images_dir = fs::read_dir("./resources/images/")?;
-    let fonts_dir = fs::read_dir("./resources/fonts/")?;
-    let audio_dir = fs::read_dir("./resources/audio/")?;
-    let maps_dir = fs::read_dir("./resources/maps/")?;
+impl Options {
+    /// Read the options from `args`, the arguments after the name of the program. A value that's
+    /// missing or doesn't make sense stops the game, rather than playing some other way than asked.
+    fn parse(args: impl Iterator<Item = String>) -> FerrisResult<Self> {
+        let mut options = Self::default();
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let mut value = |what: &str| {
+                args.next()
+                    .ok_or_else(|| FerrisError::from(format!("{} needs {}", arg, what)))
+            };
+
+            match arg.as_str() {
+                "--record" => options.record = Some(PathBuf::from(value("a file")?)),
+                "--replay" => options.replay = Some(PathBuf::from(value("a file")?)),
+                "--host" => {
+                    let port = value("a port")?;
+
+                    options.host = Some(
+                        port.parse()
+                            .map_err(|_| format!("{} isn't a port to host on", port))?,
+                    );
+                }
+                "--join" => options.join = Some(value("an address")?),
+                "--lockstep" => options.lockstep = true,
+                "--seed" => {
+                    let seed = value("a seed")?;
+
+                    options.seed = Some(
+                        game::rng::parse_seed(&seed)
+                            .ok_or_else(|| format!("{} isn't a seed", seed))?,
+                    );
+                }
+                "--headless" => options.headless = true,
+                "--frames" => {
+                    let frames = value("a frame count")?;
+
+                    options.frames = Some(
+                        frames
+                            .parse()
+                            .map_err(|_| format!("{} isn't a frame count", frames))?,
+                    );
+                }
+                _ => log::warn!("Unknown option {}", arg),
+            }
+        }
 
-    for image in images_dir {
-        asset_manager.load_image(ctx, image?.file_name().to_string_lossy());
+        Ok(options)
     }
+}
 
-    for font in fonts_dir {
-        asset_manager.load_font(ctx, font?.file_name().to_string_lossy());
-    }
+/// Load every asset while showing the loading screen, so the window keeps responding in the
+/// meantime.
+fn load_assets(
+    ctx: &mut Context,
+    event_loop: &mut EventsLoop,
+    dirs: &[PathBuf],
+) -> FerrisResult<AssetManager> {
+    let mut asset_manager = AssetManager::new(ctx)?;
+    let mut loading = loading::Loading::assets(ctx, &mut asset_manager, dirs)?;
+
+    while ctx.continuing && !loading.step(ctx, &mut asset_manager) {
+        // The same bookkeeping `event::run` does every frame.
+        ctx.timer_context.tick();
+        event_loop.poll_events(|event| {
+            ctx.process_event(&event);
+
+            if let Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } = event
+            {
+                event::quit(ctx);
+            }
+        });
 
-    for audio in audio_dir {
-        asset_manager.load_sound(ctx, audio?.file_name().to_string_lossy());
+        loading.update(ggez::timer::delta(ctx).as_secs_f32());
+        loading.draw(ctx, &asset_manager)?;
+        display::present(ctx)?;
     }
 
-    for map in maps_dir {
-        asset_manager.load_file(ctx, "maps", map?.file_name().to_string_lossy());
-    }
+    loading.finish()?;
 
     Ok(asset_manager)
 }
 
-fn main() -> FerrisResult<()> {
+fn main() {
+    let log_file = logger::init();
+
+    // Whatever stopped the game goes in the log, which gets pointed at for the bug report.
+    if let Err(error) = run() {
+        log::error!("{}", error);
+
+        if let Some(log_file) = log_file {
+            eprintln!(
+                "Call of Ferris stopped. Please attach {} to the bug report.",
+                log_file.display()
+            );
+        }
+
+        std::process::exit(1);
+    }
+}
+
+fn run() -> FerrisResult<()> {
+    let options = Options::parse(env::args().skip(1))?;
+    let replay = options
+        .replay
+        .as_deref()
+        .map(game::recording::Recording::load)
+        .transpose()?;
+    let session = match (options.host, options.join) {
+        (Some(port), _) => Some(net::Session::host(port, options.lockstep)?),
+        (None, Some(address)) => Some(net::Session::join(&address)?),
+        (None, None) => None,
+    };
+
     // The resources directory contains all of the assets.
-    // Including sprites and audio files.
-    let resource_dir = std::path::PathBuf::from("./resources");
+    // Including sprites and audio files. The mods come first, so their files win over the game's.
+    let mods = mods::active();
+    let resource_dirs = mods::resource_dirs(&mods);
+
+    // Headless runs come out the same on every computer, whatever the player's settings.
+    let config = if options.headless {
+        headless::config()
+    } else {
+        Config::load()
+    };
 
     // Make a Context and an EventLoop.
-    let (mut ctx, mut event_loop) = ContextBuilder::new("Call of Ferris", "Borrow Checker")
-        .add_resource_path(resource_dir)
-        .window_mode(
-            WindowMode::default()
-                .dimensions(INIT_WIDTH, INIT_HEIGHT)
-                .resizable(true)
-                .min_dimensions(MIN_WIDTH, MIN_HEIGHT),
-        )
+    let (mut ctx, mut event_loop) = resource_dirs
+        .iter()
+        .fold(ContextBuilder::new(GAME_ID, AUTHOR), |builder, dir| {
+            builder.add_resource_path(dir)
+        })
+        .window_mode(config.window_mode())
         .window_setup(
             WindowSetup::default()
                 .title("Call of Ferris")
-                .icon("/images/ferris_pacman_1.png"),
+                .icon("/images/ferris_pacman_1.png")
+                .vsync(config.vsync),
         )
         .build()?;
 
-    let asset_manager = init_assets(&mut ctx)?;
+    if options.headless {
+        graphics::window(&ctx).hide();
+    }
+
+    let asset_manager = load_assets(&mut ctx, &mut event_loop, &resource_dirs)?;
+
+    // The window got closed before everything was loaded.
+    if !ctx.continuing {
+        return Ok(());
+    }
+
+    if options.headless {
+        let replay = match replay {
+            Some(replay) => replay,
+            None => game::recording::demos(&asset_manager)
+                .into_iter()
+                .next()
+                .ok_or("There is no replay to play headless, pass one with --replay")?,
+        };
+
+        return headless::run(
+            &mut ctx,
+            Rc::new(asset_manager),
+            replay,
+            options.frames.unwrap_or(headless::FRAMES),
+        );
+    }
 
     // Create an instance of your event handler.
-    let mut game = Game::new(&mut ctx, asset_manager);
+    let mut game = Game::new(
+        &mut ctx,
+        asset_manager,
+        config,
+        mods,
+        options.record,
+        replay,
+        session,
+    )?;
+
+    game.shared.seed = options.seed;
 
     // Run!
     let exit = event::run(&mut ctx, &mut event_loop, &mut game);
@@ -104,80 +291,374 @@ fn main() -> FerrisResult<()> {
 }
 
 /// A enum specifying the current screen to show.
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Screen {
     /// The menu screen.
     Menu,
     /// The game screen.
     Play,
+    /// The pause menu over the level.
+    Pause,
     /// The death screen.
     Dead,
+    /// The settings screen.
+    Settings,
+    /// The loading screen shown while the level gets built.
+    Loading,
+    /// The screen where players pick what they play with.
+    Devices,
+    /// The save slot select screen.
+    Slots,
+    /// The lifetime statistics screen.
+    Stats,
+    /// The achievements screen.
+    Trophies,
+    /// A cutscene, like the intro or the credits.
+    Cutscene,
+    /// The results of a boss rush.
+    Results,
 }
 
 /// The current game state.
 pub struct Game {
-    /// The current screen,
-    screen: Screen,
-    /// Reference of the menu screen.
-    menu_screen: menu::Menu,
-    /// Mutable reference of the game screen.
-    game_screen: Mutex<game::Game>,
-    /// Reference of the death screen.
-    death_screen: dead::Death,
-    /// The asset manager.
-    asset_manager: Rc<AssetManager>,
+    /// The open screens, the one on top being the current one.
+    scenes: SceneStack<Shared>,
+    /// Covers up switching from one screen to the next.
+    transition: Transition,
+    /// Keeps the frame rate under the limit from the config.
+    frame_limiter: frame_rate::FrameLimiter,
+    /// Shows where the time goes, toggled with F3.
+    profiler: profiler::Overlay,
+    /// Takes screenshots with F12.
+    screenshots: screenshot::Screenshots,
+    /// Tells about things going on outside of the screens, like screenshots being saved.
+    toasts: game::toasts::Toasts,
+    /// Looks out for assets that change while the game is running.
+    #[cfg(feature = "debug")]
+    hot_reload: hot_reload::HotReload,
+    /// Everything the screens share.
+    shared: Shared,
 }
 
 impl Game {
-    pub fn new(ctx: &mut Context, asset_manager: AssetManager) -> Self {
+    pub fn new(
+        ctx: &mut Context,
+        asset_manager: AssetManager,
+        config: Config,
+        mods: Vec<String>,
+        record_to: Option<PathBuf>,
+        replay: Option<game::recording::Recording>,
+        session: Option<net::Session>,
+    ) -> FerrisResult<Self> {
         let asset_manager = Rc::new(asset_manager);
 
+        config.apply(ctx, &asset_manager)?;
+
+        #[cfg(feature = "debug")]
+        let hot_reload = hot_reload::HotReload::new(mods::resource_dirs(&mods));
+        let menu = menu::Menu::create(ctx, asset_manager.clone(), config.save_slot, mods);
+
         // Woah. We are cloning the asset manager. Yes that's why it's wrapped in Rc<>
         // Anything wrapped in a Rc<> and performs a clone it only clones its pointer, so it's fine to use clone here!
-        Self {
-            screen: Screen::Menu,
-
-            menu_screen: menu::Menu::create(ctx, asset_manager.clone()),
-            game_screen: game::Game::create(ctx, asset_manager.clone()),
-            death_screen: dead::Death::spawn(ctx, asset_manager.clone()),
+        let mut shared = Shared {
+            game: game::Game::create(
+                ctx,
+                asset_manager.clone(),
+                menu.level(),
+                &config,
+                Default::default(),
+            )?,
+            menu,
+            devices: devices::Devices::default(),
+            config,
+            errors: ErrorBanner::new(),
+            record_to,
+            replay,
+            demo: false,
+            demos_played: 0,
+            session,
+            seed: None,
+            cutscene: None,
+            #[cfg(feature = "discord")]
+            presence: None,
 
             asset_manager,
+        };
+
+        #[cfg(feature = "discord")]
+        shared.start_presence();
+
+        let menu = scenes::open(ctx, &mut shared, Screen::Menu, Screen::Menu);
+        let mut game = Self {
+            scenes: SceneStack::new(menu),
+            transition: Transition::new(),
+            frame_limiter: frame_rate::FrameLimiter::new(),
+            profiler: profiler::Overlay::new(),
+            screenshots: screenshot::Screenshots::new(),
+            toasts: game::toasts::Toasts::new(),
+            #[cfg(feature = "debug")]
+            hot_reload,
+            shared,
+        };
+
+        // Joining an online game goes right to the level the host is playing.
+        if let Some(session) = game
+            .shared
+            .session
+            .as_ref()
+            .filter(|session| !session.is_host())
+        {
+            game.shared.menu.select_level(session.level());
+            game.change_screen(ctx, Some(SceneChange::to(Screen::Play)));
+        }
+
+        // A replay skips the menu and goes right to its level.
+        if game.shared.replay.is_some() {
+            game.change_screen(ctx, Some(SceneChange::to(Screen::Play)));
         }
+
+        Ok(game)
     }
 }
 
-impl EventHandler for Game {
-    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        while ggez::timer::check_update_time(ctx, 60) {
-            match self.screen {
-                Screen::Menu => self.menu_screen.update(ctx)?,
-                Screen::Play => {
-                    let change = self.game_screen.lock().unwrap().update(ctx)?;
-
-                    if let Some(s) = change {
-                        self.screen = s;
-                    }
+impl Game {
+    /// Start making a change to the open screens, if there's one to make. Unless the change goes
+    /// by right away, it gets made once the current screen is covered up.
+    fn change_screen(&mut self, ctx: &mut Context, change: Option<SceneChange>) {
+        if let Some(change) = change.map(|change| self.cutscene_before(ctx, change)) {
+            let change = self.scenes.resolve(change);
+
+            match transition_kind(self.scenes.top(), self.scenes.target(change)) {
+                Some(kind) => self.transition.start(kind, change),
+                None => self.switch_screen(ctx, Some(change)),
+            }
+        }
+    }
+
+    /// Go over to `change` through a cutscene, if there's one to play on the way. A new game starts
+    /// with the intro, and beating the last level rolls the credits on the way to the menu.
+    fn cutscene_before(&mut self, ctx: &mut Context, change: SceneChange) -> SceneChange {
+        let from = self.scenes.top();
+        let shared = &mut self.shared;
+        let (cutscene, then) = match (from, change) {
+            (Screen::Menu, SceneChange::Switch(Screen::Loading)) if shared.menu.is_new_game() => {
+                (cutscene::CutsceneScreen::INTRO, Screen::Play)
+            }
+            (Screen::Play | Screen::Pause, SceneChange::Switch(Screen::Menu)) => {
+                let game = shared.game.lock().unwrap();
+
+                if !game.is_completed()
+                    || game.replaying().is_some()
+                    || shared.menu.levels().last().map(String::as_str) != Some(game.level())
+                {
+                    return change;
+                }
+
+                (cutscene::CutsceneScreen::CREDITS, Screen::Menu)
+            }
+            _ => return change,
+        };
+
+        if !cutscene::CutsceneScreen::create(ctx, shared.asset_manager.clone()).exists(cutscene) {
+            return change;
+        }
+
+        shared.cutscene = Some((cutscene, then));
+
+        SceneChange::Switch(Screen::Cutscene)
+    }
+
+    /// Make a change to the open screens right away.
+    fn switch_screen(&mut self, ctx: &mut Context, change: Option<SceneChange>) {
+        let change = match change {
+            Some(change) => change,
+            None => return,
+        };
+        let from = self.scenes.top();
+        let shared = &mut self.shared;
+
+        // Starting a replay over plays it again from the top.
+        if change == SceneChange::Switch(Screen::Loading)
+            && shared.replay.is_none()
+            && matches!(from, Screen::Play | Screen::Pause | Screen::Dead)
+        {
+            shared.replay = shared.game.lock().unwrap().replaying().cloned();
+        }
+
+        let was_playing = self.scenes.contains(Screen::Play);
+
+        self.scenes
+            .apply(change, |screen| scenes::open(ctx, shared, from, screen));
+
+        // Leaving the level writes down the recording of the run so far.
+        if was_playing && !self.scenes.contains(Screen::Play) {
+            shared.save_recording(ctx);
+            shared.game.lock().unwrap().write_save(ctx);
+        }
+
+        // The game hides the cursor behind its crosshair, every other screen wants it back.
+        // Nothing keeps the game's looping sounds going on other screens either.
+        if self.scenes.top() != Screen::Play {
+            mouse::set_cursor_hidden(ctx, false);
+            shared.asset_manager.audio().stop_loops();
+        }
+    }
+
+    /// Whether the device is playing the level right now, as player one or two.
+    fn plays(&self, device: InputDevice, player: usize) -> bool {
+        self.scenes.top() == Screen::Play && self.shared.devices.player(device) == Some(player)
+    }
+
+    /// Put everything that goes over the screens on top of the frame and show it.
+    fn present(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ggez::timer::delta(ctx).as_secs_f32();
+        let shared = &mut self.shared;
+
+        // Screenshots only show the screen, not what goes over it.
+        self.screenshots.capture(ctx)?;
+
+        for written in self.screenshots.finished() {
+            match written {
+                Ok(path) => {
+                    log::info!("Saved a screenshot to {}", path.display());
+
+                    self.toasts
+                        .push(format!("Screenshot saved to {}", path.display()));
+                }
+                Err(error) => {
+                    let error = format!("Cannot save the screenshot: {}", error);
+
+                    shared.errors.report(Err::<(), _>(FerrisError::from(error)));
                 }
-                Screen::Dead => self.death_screen.update(ctx)?,
             }
         }
 
+        self.transition.draw(ctx)?;
+        shared.errors.update(dt);
+        shared.errors.draw(ctx, shared.asset_manager.text_font())?;
+
+        let (width, height) = display::screen_size(ctx);
+        let layout = game::layout::Layout::new(Vec2::new(width, height));
+
+        self.toasts.update(dt);
+        graphics::push_transform(ctx, Some(layout.transform()));
+        graphics::apply_transformations(ctx)?;
+        self.toasts
+            .draw(ctx, &layout, shared.asset_manager.text_font())?;
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)?;
+
+        if shared.config.show_fps {
+            frame_rate::draw_counter(ctx, shared.asset_manager.text_font())?;
+        }
+
+        self.profiler.end_frame(ggez::timer::delta(ctx));
+        self.profiler.draw(ctx, shared.asset_manager.text_font())?;
+
+        display::present(ctx)?;
+        self.frame_limiter.wait(shared.config.fps_limit);
+
         Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        match self.screen {
-            Screen::Menu => self.menu_screen.draw(ctx),
-            Screen::Play => {
-                let change = self.game_screen.lock().unwrap().draw(ctx)?;
+    /// Load the assets that changed again, and build the level being played again on top of them.
+    #[cfg(feature = "debug")]
+    fn hot_reload(&mut self, ctx: &mut Context) {
+        let changed = self
+            .hot_reload
+            .update(ggez::timer::delta(ctx).as_secs_f32());
 
-                if let Some(s) = change {
-                    self.screen = s;
+        if changed.is_empty() {
+            return;
+        }
+
+        let shared = &mut self.shared;
+
+        for (folder, name) in &changed {
+            let reloaded = shared.asset_manager.reload(ctx, folder, name);
+
+            shared.errors.report(reloaded);
+        }
+
+        if changed.iter().any(|(folder, _)| *folder == "lang") {
+            shared.asset_manager.set_language(&shared.config.language);
+        }
+
+        if self.scenes.top() == Screen::Play {
+            self.change_screen(ctx, Some(SceneChange::to(Screen::Play)));
+        }
+    }
+}
+
+/// How many times a second the screens get updated.
+const UPDATES_PER_SECOND: u32 = 60;
+
+/// How to cover up going from one screen to another, if it gets covered up at all.
+fn transition_kind(from: Screen, to: Screen) -> Option<TransitionKind> {
+    match (from, to) {
+        // The level was built behind the loading screen, and the pause menu goes right over the
+        // level and away again.
+        (Screen::Loading, _) | (Screen::Play, Screen::Pause) | (Screen::Pause, Screen::Play) => {
+            None
+        }
+        (Screen::Play, Screen::Dead) | (Screen::Dead, Screen::Play) => {
+            Some(TransitionKind::Pixelate)
+        }
+        // Going from one run of a level to the next.
+        (Screen::Play | Screen::Pause | Screen::Dead, Screen::Loading) => {
+            Some(TransitionKind::Wipe)
+        }
+        _ => Some(TransitionKind::Fade),
+    }
+}
+
+impl EventHandler for Game {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.shared
+            .asset_manager
+            .audio()
+            .music()
+            .update(ggez::timer::delta(ctx).as_secs_f32());
+
+        let switch = self
+            .transition
+            .update(ggez::timer::delta(ctx).as_secs_f32());
+
+        self.switch_screen(ctx, switch);
+
+        #[cfg(feature = "debug")]
+        self.hot_reload(ctx);
+
+        // The game moves along in fixed steps, however long the frames take.
+        let dt = 1.0 / UPDATES_PER_SECOND as f32;
+
+        while ggez::timer::check_update_time(ctx, UPDATES_PER_SECOND) {
+            // Away from the level, the host still lets players join and keeps hearing from the
+            // ones who did.
+            if let Some(session) = &mut self.shared.session {
+                if !self.scenes.contains(Screen::Play) {
+                    session.receive();
                 }
+            }
 
-                Ok(())
+            // The screen being covered up is on its way out.
+            if self.transition.is_covering() {
+                continue;
             }
-            Screen::Dead => self.death_screen.draw(ctx),
+
+            let change = self.scenes.top_mut().update(ctx, &mut self.shared, dt)?;
+
+            self.change_screen(ctx, change);
         }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let change = self.scenes.top_mut().draw(ctx, &mut self.shared)?;
+
+        self.change_screen(ctx, change);
+        self.present(ctx)
     }
 
     fn key_down_event(
@@ -187,35 +668,154 @@ impl EventHandler for Game {
         _keymod: KeyMods,
         _repeat: bool,
     ) {
-        match self.screen {
-            Screen::Menu => {
-                let change = self.menu_screen.key_press(keycode);
+        match keycode {
+            KeyCode::F3 => self.profiler.toggle(),
+            KeyCode::F12 => self.screenshots.request(),
+            _ => {}
+        }
 
-                if let Some(s) = change {
-                    self.screen = s;
-                }
-            }
-            Screen::Play => {
-                let change = self.game_screen.lock().unwrap().key_press(keycode);
-
-                if let Some(s) = change {
-                    if let Screen::Menu = s {
-                        self.game_screen = game::Game::create(ctx, self.asset_manager.clone());
-                    }
-                    self.screen = s;
-                }
-            }
-            Screen::Dead => {}
+        if self.transition.is_covering() {
+            return;
+        }
+
+        let change = self
+            .scenes
+            .top_mut()
+            .key_press(ctx, &mut self.shared, keycode);
+
+        self.change_screen(ctx, change);
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        if self.transition.is_covering() {
+            return;
         }
+
+        let change = self
+            .scenes
+            .top_mut()
+            .gamepad_button_press(ctx, &mut self.shared, btn, id);
+
+        self.change_screen(ctx, change);
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        let mut game = self.shared.game.lock().unwrap();
+
+        if self.plays(InputDevice::Gamepad(id), 0) {
+            game.gamepad_button_release(btn)
+        } else if self.plays(InputDevice::Gamepad(id), 1) {
+            game.partner_button(btn, false)
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        let mut game = self.shared.game.lock().unwrap();
+
+        if self.plays(InputDevice::Gamepad(id), 0) {
+            game.gamepad_axis(axis, value)
+        } else if self.plays(InputDevice::Gamepad(id), 1) {
+            game.partner_axis(axis, value)
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, _button: MouseButton, x: f32, y: f32) {
+        if self.transition.is_covering() {
+            return;
+        }
+
+        let (x, y) = display::to_screen(ctx, x, y);
+        let change = self
+            .scenes
+            .top_mut()
+            .pointer_down(ctx, &mut self.shared, x, y);
+
+        self.change_screen(ctx, change);
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if self.plays(InputDevice::KeyboardMouse, 0) {
+            let (x, y) = display::to_screen(ctx, x, y);
+
+            self.shared.game.lock().unwrap().pointer_move(ctx, x, y)
+        }
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        if self.plays(InputDevice::KeyboardMouse, 0) {
+            self.shared.game.lock().unwrap().pointer_up()
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        self.scenes
+            .top_mut()
+            .text_input(&mut self.shared, character)
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods) {
-        if let Screen::Play = self.screen {
-            self.game_screen.lock().unwrap().key_up_event(keycode)
+        let mut game = self.shared.game.lock().unwrap();
+
+        if self.plays(InputDevice::KeyboardMouse, 0) {
+            game.key_up_event(keycode)
+        } else if self.plays(InputDevice::KeyboardMouse, 1) {
+            game.partner_key(keycode, false)
         }
     }
 
-    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
-        set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height)).unwrap();
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+        if self.plays(InputDevice::KeyboardMouse, 0) {
+            let mut game = self.shared.game.lock().unwrap();
+
+            if self.shared.config.debug.camera || game.in_photo_mode() {
+                game.mouse_wheel_event(y)
+            }
+        }
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) {
+        // Nobody is around to play while the window is in the background.
+        if !gained && self.scenes.top() == Screen::Play {
+            self.change_screen(ctx, Some(SceneChange::Push(Screen::Pause)));
+        }
+
+        self.shared.asset_manager.audio().set_focused(gained);
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, _width: f32, _height: f32) {
+        let fitted = display::fit(ctx, &self.shared.config);
+
+        self.shared.errors.report(fitted.map_err(FerrisError::from));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> FerrisResult<Options> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_options() {
+        let options = parse(&["--host", "4000", "--lockstep", "--seed", "1234"]).unwrap();
+
+        assert_eq!(options.host, Some(4000));
+        assert!(options.lockstep);
+        assert_eq!(options.seed, Some(1234));
+
+        // A typo doesn't quietly start some other game.
+        assert!(parse(&["--host", "40o0"]).is_err());
+        assert!(parse(&["--seed", "12e4"]).is_err());
+        assert!(parse(&["--join"]).is_err());
+        assert!(parse(&["--headless", "--frames", "36OO"]).is_err());
+        assert_eq!(parse(&["--frames", "600"]).unwrap().frames, Some(600));
     }
 }
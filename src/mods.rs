@@ -0,0 +1,89 @@
+//! Mods, found in the `mods` directory next to the resources.
+//!
+//! A mod is a folder laid out like `resources`. Its files take the place of the game's files with
+//! the same name, and any new ones get added, so a mod can change sprites, sounds, levels,
+//! dialogues and scripts or bring new ones along. `mods/load_order.txt` lists the mods to load,
+//! one per line, with the mods further down winning over the ones above them. Without a load
+//! order every mod gets loaded, in alphabetical order.
+
+use std::{fs, path::PathBuf};
+
+/// Where the mods are.
+const MODS_DIR: &str = "./mods";
+/// Where the game's own resources are.
+const RESOURCES_DIR: &str = "./resources";
+
+/// The names of the mods to load, in the order to load them in.
+pub fn active() -> Vec<String> {
+    let found = match fs::read_dir(MODS_DIR) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => return vec![],
+    };
+    let order = fs::read_to_string(format!("{}/load_order.txt", MODS_DIR)).ok();
+    let mods = load_order(order.as_deref(), found);
+
+    if !mods.is_empty() {
+        log::info!("Loading mods: {}", mods.join(", "));
+    }
+
+    mods
+}
+
+/// Every directory to look for resources in, the one that wins first. That's the last mod loaded
+/// down to the game's own resources.
+pub fn resource_dirs(mods: &[String]) -> Vec<PathBuf> {
+    mods.iter()
+        .rev()
+        .map(|name| PathBuf::from(MODS_DIR).join(name))
+        .chain(std::iter::once(PathBuf::from(RESOURCES_DIR)))
+        .collect()
+}
+
+/// The mods from `found` to load, going by the load order file. Mods listed in the file that
+/// aren't there get skipped, and lines starting with `#` are comments.
+fn load_order(order: Option<&str>, mut found: Vec<String>) -> Vec<String> {
+    match order {
+        Some(order) => order
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|name| found.iter().any(|found| found == name))
+            .map(String::from)
+            .collect(),
+        None => {
+            found.sort();
+            found
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_order() {
+        let found = vec![String::from("hats"), String::from("bigger_guns")];
+
+        assert_eq!(
+            load_order(None, found.clone()),
+            vec![String::from("bigger_guns"), String::from("hats")]
+        );
+        assert_eq!(
+            load_order(Some("# Hats go on last\nmissing\nhats\n"), found),
+            vec![String::from("hats")]
+        );
+        assert_eq!(
+            resource_dirs(&[String::from("a"), String::from("b")]),
+            vec![
+                PathBuf::from("./mods/b"),
+                PathBuf::from("./mods/a"),
+                PathBuf::from("./resources")
+            ]
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! Playing over the internet.
+//!
+//! One player hosts with `--host <port>` and the others join with `--join <address>`. The host is
+//! the server: everyone who joined sends the host where their crab is and how many shots it fired,
+//! and the host sends everyone a snapshot of all the crabs and the enemies still standing, which
+//! the host has the last word on. In versus the crabs also tell the host how often they went down,
+//! and the snapshots carry the frags of everyone. Messages are JSON, one to a UDP datagram. Nothing gets sent
+//! again when it's lost, every message has everything in it so the next one makes up for it.
+//! Players who haven't been heard from for a while get dropped.
+//!
+//! Other crabs get drawn a little behind time, between the two snapshots around that moment, so
+//! they move smoothly however the snapshots trickle in.
+//!
+//! Hosting with `--lockstep` as well, two players play in lockstep instead: they only trade their
+//! inputs and checksums, and both computers play the whole level from them.
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ggez_goodies::nalgebra_glm::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::game::recording::InputFrame;
+
+/// Bumped whenever the messages change, so different versions of the game don't try to play
+/// together.
+pub const PROTOCOL: u32 = 3;
+/// The most crabs in a game, the host's included.
+pub const MAX_PLAYERS: usize = 4;
+
+/// A crab as its player last reported it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CrabState {
+    pub id: u8,
+    pub x: f32,
+    pub y: f32,
+    pub health: f32,
+    /// How many shots the crab fired in the level so far.
+    pub shots: u32,
+    /// How often the crab went down in versus so far.
+    pub deaths: u32,
+    /// Who hit the crab last before it went down, in versus.
+    pub killer: Option<u8>,
+}
+
+/// How a player is doing in versus.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Frags {
+    pub id: u8,
+    pub frags: u32,
+    pub deaths: u32,
+}
+
+/// An enemy still standing on the host, by the id of its entity.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EnemyState {
+    pub id: u32,
+    pub health: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Someone wants to join the host's game.
+    Join { protocol: u32 },
+    /// The host let them in as player `id`, to play `level` seeded with `seed`, in lockstep if
+    /// `lockstep`.
+    Welcome {
+        id: u8,
+        level: String,
+        seed: u64,
+        #[serde(default)]
+        lockstep: bool,
+    },
+    /// The host can't let anyone else in.
+    Refused { reason: String },
+    /// A player telling the host about their crab.
+    Crab(CrabState),
+    /// The host telling everyone how the game is going.
+    Snapshot {
+        level: String,
+        seed: u64,
+        crabs: Vec<CrabState>,
+        enemies: Vec<EnemyState>,
+        /// The frags of everyone, in versus.
+        scores: Vec<Frags>,
+    },
+    /// Player `id` playing in lockstep, with their inputs by frame the others might not have yet,
+    /// and the checksum of the last frame they played.
+    Lockstep {
+        id: u8,
+        inputs: Vec<(u32, InputFrame)>,
+        checksum: Option<(u32, u64)>,
+    },
+    /// Player `id` is done playing, the host's going means the game is over.
+    Leave { id: u8 },
+}
+
+impl Message {
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+/// Someone who joined the host's game.
+struct Peer {
+    address: SocketAddr,
+    id: u8,
+    /// When the peer was last heard from.
+    heard: Instant,
+}
+
+enum Role {
+    Host {
+        peers: Vec<Peer>,
+    },
+    Client {
+        host: SocketAddr,
+        /// When the host was last heard from.
+        heard: Instant,
+    },
+}
+
+pub struct Session {
+    socket: UdpSocket,
+    role: Role,
+    /// Which player this is, the host is always player 0.
+    id: u8,
+    /// What the host is playing, which the players who join get to play too.
+    level: String,
+    seed: u64,
+    /// Whether the game is played in lockstep.
+    lockstep: bool,
+    /// How long until the next message goes out.
+    send_timer: f32,
+}
+
+impl Session {
+    /// How many messages go out a second.
+    const SEND_RATE: f32 = 20.0;
+    /// How long a player can go without being heard from before they're dropped.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    /// How long joining waits for the host to answer.
+    const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+    /// The biggest message that can come in.
+    const MAX_MESSAGE: usize = 64 * 1024;
+
+    /// Start hosting a game on `port`, to be played in lockstep if `lockstep`.
+    pub fn host(port: u16, lockstep: bool) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+
+        socket.set_nonblocking(true)?;
+
+        log::info!("Hosting on port {}", port);
+
+        Ok(Self {
+            socket,
+            role: Role::Host { peers: vec![] },
+            id: 0,
+            level: String::new(),
+            seed: 0,
+            lockstep,
+            send_timer: 0.0,
+        })
+    }
+
+    /// Join the game hosted at `address`, waiting for the host to let us in.
+    pub fn join(address: &str) -> io::Result<Self> {
+        let host = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Cannot find {}", address))
+        })?;
+
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+
+        socket.set_read_timeout(Some(Duration::from_millis(250)))?;
+
+        let join = Message::Join { protocol: PROTOCOL }.encode()?;
+        let start = Instant::now();
+        let mut buffer = vec![0; Self::MAX_MESSAGE];
+
+        // The join gets sent again every so often, in case it got lost on the way.
+        while start.elapsed() < Self::JOIN_TIMEOUT {
+            socket.send_to(&join, host)?;
+
+            let message = match socket.recv_from(&mut buffer) {
+                Ok((size, from)) if from == host => Message::decode(&buffer[..size]),
+                Ok(_) => None,
+                Err(error) if is_timeout(&error) => None,
+                Err(error) => return Err(error),
+            };
+
+            match message {
+                Some(Message::Welcome {
+                    id,
+                    level,
+                    seed,
+                    lockstep,
+                }) => {
+                    socket.set_read_timeout(None)?;
+                    socket.set_nonblocking(true)?;
+
+                    log::info!("Joined {} as player {}", host, id + 1);
+
+                    return Ok(Self {
+                        socket,
+                        role: Role::Client {
+                            host,
+                            heard: Instant::now(),
+                        },
+                        id,
+                        level,
+                        seed,
+                        lockstep,
+                        send_timer: 0.0,
+                    });
+                }
+                Some(Message::Refused { reason }) => {
+                    return Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason));
+                }
+                _ => thread::yield_now(),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("{} didn't answer", address),
+        ))
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host { .. })
+    }
+
+    pub fn is_lockstep(&self) -> bool {
+        self.lockstep
+    }
+
+    /// The level the host is playing, for the players who joined.
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Let everyone know what the host is playing now.
+    pub fn start_level(&mut self, level: &str, seed: u64) {
+        self.level = level.to_string();
+        self.seed = seed;
+    }
+
+    /// Whether it's time for the next message, `dt` after the last time this was asked.
+    pub fn should_send(&mut self, dt: f32) -> bool {
+        self.send_timer -= dt;
+
+        if self.send_timer > 0.0 {
+            return false;
+        }
+
+        self.send_timer += 1.0 / Self::SEND_RATE;
+        self.send_timer = self.send_timer.max(0.0);
+
+        true
+    }
+
+    /// Tell the host about our crab.
+    pub fn send_crab(&self, crab: CrabState) {
+        if let Role::Client { host, .. } = &self.role {
+            self.send(&Message::Crab(crab), *host);
+        }
+    }
+
+    /// Tell everyone who joined how the game is going.
+    pub fn send_snapshot(
+        &self,
+        crabs: Vec<CrabState>,
+        enemies: Vec<EnemyState>,
+        scores: Vec<Frags>,
+    ) {
+        if let Role::Host { peers, .. } = &self.role {
+            let snapshot = Message::Snapshot {
+                level: self.level.clone(),
+                seed: self.seed,
+                crabs,
+                enemies,
+                scores,
+            };
+
+            for peer in peers {
+                self.send(&snapshot, peer.address);
+            }
+        }
+    }
+
+    /// Send our inputs and the checksum of the last frame to the others, playing in lockstep.
+    pub fn send_inputs(&self, inputs: Vec<(u32, InputFrame)>, checksum: Option<(u32, u64)>) {
+        let message = Message::Lockstep {
+            id: self.id,
+            inputs,
+            checksum,
+        };
+
+        match &self.role {
+            Role::Host { peers } => {
+                for peer in peers {
+                    self.send(&message, peer.address);
+                }
+            }
+            Role::Client { host, .. } => self.send(&message, *host),
+        }
+    }
+
+    fn send(&self, message: &Message, to: SocketAddr) {
+        let sent = message
+            .encode()
+            .and_then(|bytes| self.socket.send_to(&bytes, to));
+
+        if let Err(error) = sent {
+            log::warn!("Cannot send to {}: {}", to, error);
+        }
+    }
+
+    /// Everything that came in since the last time. The host lets in the players who ask to join
+    /// by itself. Players who left or haven't been heard from in a while come back as `Leave`.
+    pub fn receive(&mut self) -> Vec<Message> {
+        let mut buffer = vec![0; Self::MAX_MESSAGE];
+        let mut received = vec![];
+
+        loop {
+            let (size, from) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(error) if is_timeout(&error) => break,
+                Err(error) => {
+                    // Windows reports a peer that went away as an error on the next read, the rest
+                    // of the messages get read with the next update.
+                    log::debug!("Cannot receive: {}", error);
+
+                    break;
+                }
+            };
+
+            if let Some(message) = Message::decode(&buffer[..size]) {
+                if let Some(message) = self.handle(message, from) {
+                    received.push(message);
+                }
+            }
+        }
+
+        received.extend(self.drop_silent());
+
+        received
+    }
+
+    /// Deal with a message from `from`, returning it if the game needs to know about it.
+    fn handle(&mut self, message: Message, from: SocketAddr) -> Option<Message> {
+        match &mut self.role {
+            Role::Host { peers } => {
+                if let Message::Join { protocol } = message {
+                    let answer =
+                        admit(peers, from, protocol, &self.level, self.seed, self.lockstep);
+
+                    self.send(&answer, from);
+
+                    return None;
+                }
+
+                let peer = peers.iter_mut().find(|peer| peer.address == from)?;
+
+                peer.heard = Instant::now();
+
+                match message {
+                    // Players only get to tell about their own crab.
+                    Message::Crab(crab) if crab.id == peer.id => Some(Message::Crab(crab)),
+                    Message::Lockstep { id, .. } if id == peer.id => Some(message),
+                    Message::Leave { .. } => {
+                        let id = peer.id;
+
+                        peers.retain(|peer| peer.address != from);
+                        log::info!("Player {} left", id + 1);
+
+                        Some(Message::Leave { id })
+                    }
+                    _ => None,
+                }
+            }
+            Role::Client { host, heard } => {
+                if from != *host {
+                    return None;
+                }
+
+                *heard = Instant::now();
+
+                if let Message::Snapshot { level, seed, .. } = &message {
+                    self.level = level.clone();
+                    self.seed = *seed;
+                }
+
+                Some(message)
+            }
+        }
+    }
+
+    /// Forget about the players who went quiet.
+    fn drop_silent(&mut self) -> Vec<Message> {
+        match &mut self.role {
+            Role::Host { peers, .. } => {
+                let mut left = vec![];
+
+                peers.retain(|peer| {
+                    let silent = peer.heard.elapsed() > Self::TIMEOUT;
+
+                    if silent {
+                        log::info!("Player {} timed out", peer.id + 1);
+                        left.push(Message::Leave { id: peer.id });
+                    }
+
+                    !silent
+                });
+
+                left
+            }
+            Role::Client { heard, .. } => {
+                if heard.elapsed() > Self::TIMEOUT {
+                    *heard = Instant::now();
+                    log::warn!("The host went quiet");
+
+                    vec![Message::Leave { id: 0 }]
+                } else {
+                    vec![]
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let leave = Message::Leave { id: self.id };
+
+        match &self.role {
+            Role::Host { peers, .. } => {
+                for peer in peers {
+                    self.send(&leave, peer.address);
+                }
+            }
+            Role::Client { host, .. } => self.send(&leave, *host),
+        }
+    }
+}
+
+/// Let the player at `address` in, or say why they can't be. Lockstep is for two players only.
+fn admit(
+    peers: &mut Vec<Peer>,
+    address: SocketAddr,
+    protocol: u32,
+    level: &str,
+    seed: u64,
+    lockstep: bool,
+) -> Message {
+    let max_players = if lockstep { 2 } else { MAX_PLAYERS };
+
+    if protocol != PROTOCOL {
+        return Message::Refused {
+            reason: String::from("The host plays a different version of the game"),
+        };
+    }
+
+    // A join sent again because the welcome got lost gets the same welcome.
+    let id = match peers.iter().find(|peer| peer.address == address) {
+        Some(peer) => peer.id,
+        None => {
+            let id = match (1..max_players as u8).find(|id| peers.iter().all(|peer| peer.id != *id))
+            {
+                Some(id) => id,
+                None => {
+                    return Message::Refused {
+                        reason: String::from("The game is full"),
+                    }
+                }
+            };
+
+            log::info!("Player {} joined from {}", id + 1, address);
+
+            peers.push(Peer {
+                address,
+                id,
+                heard: Instant::now(),
+            });
+
+            id
+        }
+    };
+
+    Message::Welcome {
+        id,
+        level: level.to_string(),
+        seed,
+        lockstep,
+    }
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Where another crab was over the latest snapshots, to draw it somewhere in between.
+pub struct Interpolator {
+    /// When the snapshots came in and where the crab was in them, oldest first.
+    snapshots: VecDeque<(f32, Vec2)>,
+}
+
+impl Interpolator {
+    /// How far behind the latest snapshot the crab gets drawn, a couple of snapshots.
+    pub const DELAY: f32 = 2.0 / Session::SEND_RATE;
+    /// How many snapshots are kept around.
+    const KEPT: usize = 8;
+
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(Self::KEPT),
+        }
+    }
+
+    /// The crab was at `position` as of `time`.
+    pub fn push(&mut self, time: f32, position: Vec2) {
+        if self.snapshots.len() >= Self::KEPT {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back((time, position));
+    }
+
+    /// Where to draw the crab at `time`. Before the first snapshot it's at the first one, after the
+    /// last one at the last one.
+    pub fn sample(&self, time: f32) -> Option<Vec2> {
+        let time = time - Self::DELAY;
+        let (first_time, first) = *self.snapshots.front()?;
+
+        if time <= first_time {
+            return Some(first);
+        }
+
+        for ((from_time, from), (to_time, to)) in
+            self.snapshots.iter().zip(self.snapshots.iter().skip(1))
+        {
+            if time <= *to_time {
+                let t = (time - from_time) / (to_time - from_time).max(f32::EPSILON);
+
+                return Some(from + (to - from) * t);
+            }
+        }
+
+        self.snapshots.back().map(|(_, position)| *position)
+    }
+}
+
+impl Default for Interpolator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let message = Message::Crab(CrabState {
+            id: 2,
+            x: 10.0,
+            y: -4.5,
+            health: 80.0,
+            shots: 3,
+            deaths: 1,
+            killer: Some(0),
+        });
+
+        let bytes = message.encode().unwrap();
+
+        assert_eq!(Message::decode(&bytes), Some(message));
+        assert_eq!(Message::decode(b"not a message"), None);
+    }
+
+    #[test]
+    fn test_admit() {
+        let mut peers = vec![];
+        let address = |port| SocketAddr::from(([127, 0, 0, 1], port));
+
+        for port in 1..=3 {
+            let welcome = admit(&mut peers, address(port), PROTOCOL, "01", 7, false);
+
+            assert_eq!(
+                welcome,
+                Message::Welcome {
+                    id: port as u8,
+                    level: String::from("01"),
+                    seed: 7,
+                    lockstep: false,
+                }
+            );
+        }
+
+        // Asking again gets the same welcome, but there's no room for a fifth crab.
+        assert!(matches!(
+            admit(&mut peers, address(2), PROTOCOL, "01", 7, false),
+            Message::Welcome { id: 2, .. }
+        ));
+        assert!(matches!(
+            admit(&mut peers, address(4), PROTOCOL, "01", 7, false),
+            Message::Refused { .. }
+        ));
+        assert!(matches!(
+            admit(&mut peers, address(5), 0, "01", 7, false),
+            Message::Refused { .. }
+        ));
+
+        // Only one other player gets to play in lockstep.
+        let mut peers = vec![];
+
+        assert!(matches!(
+            admit(&mut peers, address(1), PROTOCOL, "01", 7, true),
+            Message::Welcome { lockstep: true, .. }
+        ));
+        assert!(matches!(
+            admit(&mut peers, address(2), PROTOCOL, "01", 7, true),
+            Message::Refused { .. }
+        ));
+    }
+
+    #[test]
+    fn test_interpolator() {
+        let mut path = Interpolator::new();
+
+        assert_eq!(path.sample(1.0), None);
+
+        path.push(1.0, Vec2::new(0.0, 0.0));
+        path.push(2.0, Vec2::new(100.0, 50.0));
+
+        let at = |time: f32| path.sample(time + Interpolator::DELAY).unwrap();
+
+        assert!((at(0.5) - Vec2::new(0.0, 0.0)).norm() < 0.01);
+        assert!((at(1.5) - Vec2::new(50.0, 25.0)).norm() < 0.01);
+        assert!((at(3.0) - Vec2::new(100.0, 50.0)).norm() < 0.01);
+    }
+}
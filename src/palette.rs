@@ -0,0 +1,120 @@
+//! Colors that carry meaning, swapped out for color-blind players.
+//!
+//! Anything telling the player something through its color, like health against damage taken,
+//! friend against foe or a warning flashing on the HUD, takes its color from the palette of the
+//! color-blind mode picked in the settings instead of hardcoding it. The color-blind palettes lean
+//! on blues, oranges and yellows, which stay apart for players who have trouble telling red from
+//! green, and on reds and greens for the ones who mix up blue and yellow.
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ColorBlindMode {
+    Off,
+    /// Green looks like red.
+    Deuteranopia,
+    /// Red looks like green, and darker.
+    Protanopia,
+    /// Blue looks like green and yellow like pink.
+    Tritanopia,
+}
+
+impl ColorBlindMode {
+    pub const ALL: [ColorBlindMode; 4] = [
+        ColorBlindMode::Off,
+        ColorBlindMode::Deuteranopia,
+        ColorBlindMode::Protanopia,
+        ColorBlindMode::Tritanopia,
+    ];
+
+    pub fn palette(&self) -> &'static Palette {
+        match self {
+            ColorBlindMode::Off => &Palette::DEFAULT,
+            ColorBlindMode::Deuteranopia => &Palette::DEUTERANOPIA,
+            ColorBlindMode::Protanopia => &Palette::PROTANOPIA,
+            ColorBlindMode::Tritanopia => &Palette::TRITANOPIA,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Palette {
+    /// The player on the minimap.
+    pub friendly: Color,
+    /// Enemies on the minimap.
+    pub hostile: Color,
+    /// The health bar and the health pickup.
+    pub health: Color,
+    /// The part of the health bar that just got lost.
+    pub hurt: Color,
+    /// Damage numbers of critical hits.
+    pub critical: Color,
+    /// HUD warnings, like running low on ammo.
+    pub warning: Color,
+    /// The bullet time pickup.
+    pub bullet_time: Color,
+}
+
+impl Palette {
+    pub const DEFAULT: Palette = Palette {
+        friendly: rgb(247, 76, 0),
+        hostile: rgb(255, 80, 76),
+        health: rgb(34, 205, 124),
+        hurt: rgb(232, 76, 61),
+        critical: rgb(255, 196, 0),
+        warning: rgb(232, 76, 61),
+        bullet_time: rgb(90, 170, 255),
+    };
+
+    pub const DEUTERANOPIA: Palette = Palette {
+        friendly: rgb(86, 180, 233),
+        hostile: rgb(230, 159, 0),
+        health: rgb(0, 114, 178),
+        hurt: rgb(230, 159, 0),
+        critical: rgb(240, 228, 66),
+        warning: rgb(230, 159, 0),
+        bullet_time: rgb(204, 121, 167),
+    };
+
+    /// Reds look dark to protanopes, so the warm colors are brighter than for deuteranopes.
+    pub const PROTANOPIA: Palette = Palette {
+        friendly: rgb(86, 180, 233),
+        hostile: rgb(240, 190, 40),
+        health: rgb(0, 114, 178),
+        hurt: rgb(240, 190, 40),
+        critical: rgb(255, 245, 120),
+        warning: rgb(240, 190, 40),
+        bullet_time: rgb(220, 150, 200),
+    };
+
+    pub const TRITANOPIA: Palette = Palette {
+        friendly: rgb(0, 158, 115),
+        hostile: rgb(213, 94, 0),
+        health: rgb(0, 158, 115),
+        hurt: rgb(213, 40, 60),
+        critical: rgb(255, 110, 180),
+        warning: rgb(213, 40, 60),
+        bullet_time: rgb(160, 90, 220),
+    };
+}
+
+/// `Color::from_rgb` can't be used in constants.
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palettes_keep_meanings_apart() {
+        for mode in &ColorBlindMode::ALL {
+            let palette = mode.palette();
+
+            assert_ne!(palette.friendly, palette.hostile);
+            assert_ne!(palette.health, palette.hurt);
+        }
+    }
+}
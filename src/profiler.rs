@@ -0,0 +1,237 @@
+//! Timing how long the game spends where.
+//!
+//! The parts of the game worth keeping an eye on are wrapped in scoped timers, which add the time
+//! until they're dropped to the part they're named after. Every frame the totals get folded into
+//! smoothed averages, so the numbers can be read instead of flickering. The overlay shows them with
+//! the frame rate and a graph of how long the latest frames took.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, MeshBuilder, Rect, Text},
+    nalgebra::Point2,
+    timer, Context, GameResult,
+};
+
+use crate::utils::lerp;
+
+thread_local! {
+    static TIMINGS: RefCell<Timings> = RefCell::new(Timings::default());
+}
+
+#[derive(Default)]
+struct Timings {
+    /// Nothing gets timed while nobody is looking.
+    enabled: bool,
+    /// Every part that's been timed with what it took this frame and its average, in the order
+    /// they showed up.
+    parts: Vec<(&'static str, Duration, f32)>,
+}
+
+impl Timings {
+    /// How much of the average every new frame makes up.
+    const SMOOTHING: f32 = 0.1;
+
+    fn add(&mut self, name: &'static str, time: Duration) {
+        match self.parts.iter_mut().find(|(part, ..)| *part == name) {
+            Some((_, total, _)) => *total += time,
+            None => self.parts.push((name, time, time.as_secs_f32() * 1000.0)),
+        }
+    }
+
+    /// Fold this frame's totals into the averages and start the next frame over.
+    fn end_frame(&mut self) {
+        for (_, total, average) in &mut self.parts {
+            *average = lerp(*average, total.as_secs_f32() * 1000.0, Self::SMOOTHING);
+            *total = Duration::default();
+        }
+    }
+}
+
+/// Adds the time from when it's started until it's dropped to a part of the game.
+pub struct Scope {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let elapsed = start.elapsed();
+
+            TIMINGS.with(|timings| timings.borrow_mut().add(self.name, elapsed));
+        }
+    }
+}
+
+/// Time the part of the game called `name` until the returned timer gets dropped.
+pub fn scope(name: &'static str) -> Scope {
+    let enabled = TIMINGS.with(|timings| timings.borrow().enabled);
+
+    Scope {
+        name,
+        start: if enabled { Some(Instant::now()) } else { None },
+    }
+}
+
+/// What every part that's been timed took so far this frame, in the order they showed up.
+pub fn this_frame() -> Vec<(&'static str, Duration)> {
+    TIMINGS.with(|timings| {
+        timings
+            .borrow()
+            .parts
+            .iter()
+            .map(|(name, total, _)| (*name, *total))
+            .collect()
+    })
+}
+
+/// Shows what the timers found out, toggled with F3.
+pub struct Overlay {
+    /// How long the latest frames took in milliseconds, oldest first.
+    frames: VecDeque<f32>,
+}
+
+impl Overlay {
+    /// How many frames the graph goes back.
+    const HISTORY: usize = 120;
+    /// The frame time the graph's line is drawn at, a frame at 60 FPS.
+    const TARGET: f32 = 1000.0 / 60.0;
+    /// How many milliseconds the full height of the graph stands for.
+    const GRAPH_RANGE: f32 = Self::TARGET * 3.0;
+    const GRAPH_HEIGHT: f32 = 60.0;
+
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::with_capacity(Self::HISTORY),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        TIMINGS.with(|timings| timings.borrow().enabled)
+    }
+
+    pub fn toggle(&mut self) {
+        let enabled = !self.is_enabled();
+
+        TIMINGS.with(|timings| {
+            let mut timings = timings.borrow_mut();
+
+            timings.enabled = enabled;
+            timings.parts.clear();
+        });
+        self.frames.clear();
+    }
+
+    /// Wrap up the frame that took `frame_time`, ready for the next one.
+    pub fn end_frame(&mut self, frame_time: Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if self.frames.len() >= Self::HISTORY {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(frame_time.as_secs_f32() * 1000.0);
+        TIMINGS.with(|timings| timings.borrow_mut().end_frame());
+    }
+
+    pub fn draw(&self, ctx: &mut Context, font: Font) -> GameResult {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let width = Self::HISTORY as f32 * 2.0;
+        let last = self.frames.back().copied().unwrap_or(0.0);
+
+        let mut lines = vec![format!("{:.0} FPS  {:.1} MS", timer::fps(ctx), last)];
+
+        TIMINGS.with(|timings| {
+            for (name, _, average) in &timings.borrow().parts {
+                lines.push(format!("{:<10}{:>6.2} MS", name.to_uppercase(), average));
+            }
+        });
+
+        let text_height = lines.len() as f32 * 18.0;
+        let mut mesh = MeshBuilder::new();
+
+        mesh.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                0.0,
+                0.0,
+                width + 20.0,
+                Self::GRAPH_HEIGHT + text_height + 30.0,
+            ),
+            Color::from_rgba(20, 20, 20, 200),
+        );
+
+        // A bar for every frame, turning red when it took longer than it should have.
+        let bottom = Self::GRAPH_HEIGHT + 10.0;
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            let height = (frame / Self::GRAPH_RANGE).min(1.0) * Self::GRAPH_HEIGHT;
+            let color = if *frame > Self::TARGET * 1.1 {
+                Color::from_rgb(255, 90, 60)
+            } else {
+                Color::from_rgb(120, 255, 120)
+            };
+
+            mesh.rectangle(
+                DrawMode::fill(),
+                Rect::new(10.0 + index as f32 * 2.0, bottom - height, 2.0, height),
+                color,
+            );
+        }
+
+        let target = bottom - Self::TARGET / Self::GRAPH_RANGE * Self::GRAPH_HEIGHT;
+
+        mesh.rectangle(
+            DrawMode::fill(),
+            Rect::new(10.0, target, width, 1.0),
+            Color::new(1.0, 1.0, 1.0, 0.5),
+        );
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        for (index, line) in lines.into_iter().enumerate() {
+            let text = Text::new((line, font, 16.0));
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default().dest(Point2::new(10.0, bottom + 10.0 + index as f32 * 18.0)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let mut timings = Timings::default();
+
+        timings.add("physics", Duration::from_millis(2));
+        timings.add("draw", Duration::from_millis(4));
+        timings.add("physics", Duration::from_millis(2));
+        timings.end_frame();
+
+        // Parts timed more than once in a frame add up, and a new part starts out at its time.
+        assert_eq!(timings.parts[0].0, "physics");
+        assert!((timings.parts[0].2 - 2.2).abs() < 0.01);
+        assert!((timings.parts[1].2 - 4.0).abs() < 0.01);
+        assert_eq!(timings.parts[1].1, Duration::default());
+    }
+}
@@ -0,0 +1,455 @@
+//! Progress that is kept around between runs.
+//!
+//! Saves get stored as TOML in the user data directory, one file for each of the slots the player
+//! can pick from. Anything missing from the file (or the whole file, on the first run) starts out
+//! empty. The game writes it whenever something worth keeping happens, like reaching a checkpoint
+//! or finishing a level, and the menu reads it on startup. The difficulty gets picked once, when
+//! starting a new game in the slot. Saves get written through a temporary
+//! file, so a crash can't leave a broken one behind. The settings are kept in the config instead.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
+use ggez::{filesystem, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Progress,
+    error::FerrisResult,
+    game::{
+        components::bullet::WeaponType,
+        events::{GameEvent, Subscriber, Victim},
+    },
+    utils::write_atomic,
+};
+
+/// Everything the player has done over all of their runs.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    /// Turbofish shot, the grappling gun doesn't count.
+    pub shots_fired: u32,
+    /// Turbofish that hit an enemy.
+    pub shots_hit: u32,
+    pub deaths: u32,
+    /// How far Ferris went, in pixels.
+    pub distance: f32,
+    pub barrels_exploded: u32,
+    /// The enemies taken down, by the name of their kind.
+    pub kills: BTreeMap<String, u32>,
+}
+
+impl Stats {
+    /// How many of the shots hit, between 0.0 and 1.0.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            return 0.0;
+        }
+
+        (self.shots_hit as f32 / self.shots_fired as f32).min(1.0)
+    }
+
+    pub fn kill(&mut self, kind: &str) {
+        *self.kills.entry(kind.to_string()).or_default() += 1;
+    }
+}
+
+/// How hard the game is.
+#[derive(PartialEq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// For the crabs who know every level by heart.
+    Ferris,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Ferris,
+    ];
+
+    /// Multiplier for the damage the player takes.
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Ferris => 2.0,
+        }
+    }
+
+    /// Multiplier for the health of the enemies.
+    pub fn enemy_health(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Ferris => 1.5,
+        }
+    }
+
+    /// Every how many checkpoints of a level are kept, there are none at all for Ferris.
+    fn checkpoint_spacing(&self) -> Option<usize> {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => Some(1),
+            Difficulty::Hard => Some(2),
+            Difficulty::Ferris => None,
+        }
+    }
+
+    /// How many of the ten pickups a crate can hold are ammo. One of them is always bullet time.
+    pub fn ammo_drops(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 7,
+            Difficulty::Normal => 5,
+            Difficulty::Hard => 3,
+            Difficulty::Ferris => 2,
+        }
+    }
+
+    /// Keep the checkpoints of a level there are at this difficulty.
+    pub fn checkpoints<T>(&self, checkpoints: Vec<T>) -> Vec<T> {
+        match self.checkpoint_spacing() {
+            Some(spacing) => checkpoints
+                .into_iter()
+                .enumerate()
+                .filter(|(id, _)| (id + 1) % spacing == 0)
+                .map(|(_, checkpoint)| checkpoint)
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/// What Ferris had on them at the end of the last level, to carry over into New Game+.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Loadout {
+    pub weapon: WeaponType,
+    pub magazine: u32,
+    pub reserve: u32,
+}
+
+/// A run through the campaign after it was beaten, with remixed and tougher enemies.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewGamePlus {
+    /// How many times the campaign was beaten before.
+    pub prestige: u32,
+    /// What Ferris starts every level with, if they beat one yet.
+    pub loadout: Option<Loadout>,
+}
+
+impl NewGamePlus {
+    /// How much tougher the enemies get every time the campaign is beaten.
+    const TOUGHNESS: f32 = 0.5;
+
+    /// Multiplier for the health of the enemies, on top of the difficulty.
+    pub fn enemy_health(&self) -> f32 {
+        1.0 + Self::TOUGHNESS * self.prestige as f32
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Save {
+    /// What the player called the slot. Empty until they name it.
+    pub name: String,
+    /// Seconds spent playing levels.
+    pub playtime: f32,
+    /// The tutorial prompts the player has already been shown.
+    pub shown_prompts: Vec<String>,
+    /// The ids of the levels the player has beaten.
+    pub completed_levels: Vec<String>,
+    /// How hard the game is in this slot, once it was picked.
+    pub difficulty: Option<Difficulty>,
+    /// How many times the campaign was beaten and started over in New Game+.
+    pub prestige: u32,
+    /// Kept last, TOML wants its tables after the plain values.
+    pub loadout: Option<Loadout>,
+    pub stats: Stats,
+    pub achievements: Progress,
+
+    /// The slot the save belongs to.
+    #[serde(skip)]
+    slot: usize,
+}
+
+impl Save {
+    /// How many saves can be kept around at once.
+    pub const SLOTS: usize = 3;
+
+    /// Load the save in the slot. A missing or broken save leaves you with a fresh one.
+    pub fn load(ctx: &Context, slot: usize) -> Self {
+        Self::find(ctx, slot).unwrap_or(Self {
+            slot,
+            ..Self::default()
+        })
+    }
+
+    /// The save in the slot, if there is one.
+    pub fn find(ctx: &Context, slot: usize) -> Option<Self> {
+        Self::read(&Self::path(ctx, slot)).map(|save| Self { slot, ..save })
+    }
+
+    /// Read the save at `path`. A save that can't be read gets moved aside to a `.bak` file next to
+    /// it, so the next save written doesn't take the progress in it along.
+    fn read(path: &Path) -> Option<Self> {
+        let save = fs::read_to_string(path).ok()?;
+
+        match toml::from_str(&save) {
+            Ok(save) => Some(save),
+            Err(error) => {
+                let mut backup = path.as_os_str().to_owned();
+
+                backup.push(".bak");
+                log::error!("Cannot read the save {}: {}", path.display(), error);
+
+                if let Err(error) = fs::rename(path, &backup) {
+                    log::error!("Cannot move the broken save aside: {}", error);
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Write the save to its slot in the user data directory.
+    pub fn save(&self, ctx: &Context) -> FerrisResult<()> {
+        self.save_to(ctx, self.slot)
+    }
+
+    /// Write the save to another slot, over whatever was in there.
+    pub fn save_to(&self, ctx: &Context, slot: usize) -> FerrisResult<()> {
+        write_atomic(&Self::path(ctx, slot), &toml::to_string(self)?)
+    }
+
+    /// Write the save to its slot without waiting for the disk. The returned handle is done once
+    /// the save is written.
+    pub fn save_in_background(&self, ctx: &Context) -> FerrisResult<JoinHandle<()>> {
+        let path = Self::path(ctx, self.slot);
+        let save = toml::to_string(self)?;
+
+        Ok(thread::spawn(move || {
+            if let Err(error) = write_atomic(&path, &save) {
+                log::error!("Cannot save the game: {}", error);
+            }
+        }))
+    }
+
+    pub fn is_completed(&self, level: &str) -> bool {
+        self.completed_levels
+            .iter()
+            .any(|completed| completed == level)
+    }
+
+    /// Remember that the level was beaten. Returns false if it already was.
+    pub fn complete(&mut self, level: &str) -> bool {
+        if self.is_completed(level) {
+            return false;
+        }
+
+        self.completed_levels.push(level.to_string());
+
+        true
+    }
+
+    /// Whether every one of the levels has been beaten.
+    pub fn is_campaign_beaten(&self, levels: &[String]) -> bool {
+        !levels.is_empty() && self.level_reached(levels).is_none()
+    }
+
+    /// Whether boss rush can be played, which it can once the campaign was beaten, even if it was
+    /// started over since.
+    pub fn is_boss_rush_unlocked(&self, levels: &[String]) -> bool {
+        self.prestige > 0 || self.is_campaign_beaten(levels)
+    }
+
+    /// Start the campaign over in New Game+, keeping the loadout.
+    pub fn start_new_game_plus(&mut self) {
+        self.prestige += 1;
+        self.completed_levels.clear();
+    }
+
+    /// The New Game+ the slot is in, if the campaign was ever started over.
+    pub fn new_game_plus(&self) -> Option<NewGamePlus> {
+        if self.prestige == 0 {
+            return None;
+        }
+
+        Some(NewGamePlus {
+            prestige: self.prestige,
+            loadout: self.loadout.clone(),
+        })
+    }
+
+    /// Empty the slot.
+    pub fn delete(ctx: &Context, slot: usize) -> FerrisResult<()> {
+        let path = Self::path(ctx, slot);
+
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// How much of the levels have been beaten, between 0.0 and 1.0.
+    pub fn completion(&self, levels: &[String]) -> f32 {
+        if levels.is_empty() {
+            return 0.0;
+        }
+
+        let completed = levels
+            .iter()
+            .filter(|level| self.is_completed(level))
+            .count();
+
+        completed as f32 / levels.len() as f32
+    }
+
+    /// The first level that hasn't been beaten yet, if any.
+    pub fn level_reached<'a>(&self, levels: &'a [String]) -> Option<&'a str> {
+        levels
+            .iter()
+            .find(|level| !self.is_completed(level))
+            .map(String::as_str)
+    }
+
+    /// The first slot keeps the name saves had before there were slots.
+    fn path(ctx: &Context, slot: usize) -> PathBuf {
+        let file = match slot {
+            0 => String::from("save.toml"),
+            _ => format!("save_{}.toml", slot + 1),
+        };
+
+        filesystem::user_data_dir(ctx).join(file)
+    }
+}
+
+impl Subscriber for Stats {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::DamageDealt {
+                victim: Victim::Enemy,
+                hits,
+                ..
+            } => self.shots_hit += hits,
+            GameEvent::EnemyKilled { boss, .. } => self.kill(boss.unwrap_or("Gopher")),
+            GameEvent::BarrelExploded { .. } => self.barrels_exploded += 1,
+            GameEvent::ShotFired => self.shots_fired += 1,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broken_save() {
+        let dir = std::env::temp_dir().join(format!("ferris_save_{}", std::process::id()));
+        let path = dir.join("save.toml");
+
+        write_atomic(&path, "not = [a save").unwrap();
+        assert!(Save::read(&path).is_none());
+
+        // The broken save is kept around instead of getting written over.
+        assert!(!path.exists());
+        assert!(dir.join("save.toml.bak").exists());
+
+        write_atomic(&path, &toml::to_string(&Save::default()).unwrap()).unwrap();
+        assert!(Save::read(&path).is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_completed_levels() {
+        // Saves from before levels were tracked still load.
+        let mut save: Save = toml::from_str("shown_prompts = [\"move\"]").unwrap();
+
+        assert!(save.complete("01"));
+        assert!(!save.complete("01"));
+        assert!(save.is_completed("01"));
+
+        let levels = vec![String::from("01"), String::from("02")];
+
+        assert_eq!(save.completion(&levels), 0.5);
+        assert_eq!(save.level_reached(&levels), Some("02"));
+
+        save.stats.kill("Gopher");
+        save.stats.kill("Gopher");
+        save.stats.shots_fired = 4;
+        save.stats.shots_hit = 1;
+        save.achievements.unlocked.push(String::from("first_blood"));
+        save.achievements.counts.insert(String::from("kills"), 2);
+
+        assert_eq!(save.stats.kills["Gopher"], 2);
+        assert_eq!(save.stats.accuracy(), 0.25);
+
+        let saved = toml::to_string(&save).unwrap();
+
+        assert_eq!(toml::from_str::<Save>(&saved).unwrap(), save);
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let checkpoints = vec![1, 2, 3, 4, 5];
+
+        assert_eq!(
+            Difficulty::Normal.checkpoints(checkpoints.clone()),
+            checkpoints
+        );
+        assert_eq!(
+            Difficulty::Hard.checkpoints(checkpoints.clone()),
+            vec![2, 4]
+        );
+        assert!(Difficulty::Ferris.checkpoints(checkpoints).is_empty());
+
+        let save: Save = toml::from_str("difficulty = \"Ferris\"").unwrap();
+
+        assert_eq!(save.difficulty, Some(Difficulty::Ferris));
+        assert_eq!(toml::from_str::<Save>("").unwrap().difficulty, None);
+    }
+
+    #[test]
+    fn test_new_game_plus() {
+        let levels = vec![String::from("01"), String::from("02")];
+        let mut save = Save::default();
+
+        assert!(save.new_game_plus().is_none());
+        assert!(!save.is_boss_rush_unlocked(&levels));
+
+        save.complete("01");
+        save.complete("02");
+        save.loadout = Some(Loadout {
+            weapon: WeaponType::Grappling,
+            magazine: 3,
+            reserve: 12,
+        });
+        assert!(save.is_campaign_beaten(&levels));
+
+        save.start_new_game_plus();
+        assert!(!save.is_campaign_beaten(&levels));
+        assert!(save.is_boss_rush_unlocked(&levels));
+
+        let new_game_plus = save.new_game_plus().unwrap();
+
+        assert_eq!(new_game_plus.prestige, 1);
+        assert_eq!(new_game_plus.loadout, save.loadout);
+        assert_eq!(new_game_plus.enemy_health(), 1.5);
+
+        let saved = toml::to_string(&save).unwrap();
+
+        assert_eq!(toml::from_str::<Save>(&saved).unwrap(), save);
+    }
+}
@@ -0,0 +1,251 @@
+//! The screens that are open, stacked on top of each other.
+//!
+//! Every screen is a scene. Only the scene on top gets updated, drawn and handed the input, and
+//! it says where to go from there. Screens opened from another one, like the pause menu over the
+//! level or the settings from the pause menu, get pushed on top of it and popped off again once
+//! they're left, which brings back the screen underneath the way it was. Going anywhere else
+//! switches the whole stack over to the new screen.
+
+use ggez::{
+    event::{Button, KeyCode},
+    input::gamepad::GamepadId,
+    Context, GameResult,
+};
+
+use crate::Screen;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SceneChange {
+    /// Open a screen over the current one.
+    Push(Screen),
+    /// Leave the current screen, going back to the one underneath.
+    Pop,
+    /// Close every screen and open this one.
+    Switch(Screen),
+}
+
+impl SceneChange {
+    /// Go over to `screen`, opening it over the current screen if that's how it gets opened.
+    /// Starting the game always starts a fresh level, built behind the loading screen.
+    pub fn to(screen: Screen) -> Self {
+        match screen {
+            Screen::Play => SceneChange::Switch(Screen::Loading),
+            screen if Self::is_overlay(screen) => SceneChange::Push(screen),
+            screen => SceneChange::Switch(screen),
+        }
+    }
+
+    /// Whether a screen gets opened over the one it's opened from.
+    fn is_overlay(screen: Screen) -> bool {
+        matches!(
+            screen,
+            Screen::Pause | Screen::Settings | Screen::Devices | Screen::Slots | Screen::Stats
+        )
+    }
+}
+
+/// A screen of the game, `S` being what all of them share.
+pub trait Scene<S> {
+    /// Which screen the scene is.
+    fn screen(&self) -> Screen;
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut S,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>>;
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut S) -> GameResult<Option<SceneChange>>;
+
+    fn key_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut S,
+        keycode: KeyCode,
+    ) -> Option<SceneChange>;
+
+    fn gamepad_button_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut S,
+        button: Button,
+        id: GamepadId,
+    ) -> Option<SceneChange>;
+
+    /// The screen got touched or clicked at `x`, `y`.
+    fn pointer_down(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut S,
+        _x: f32,
+        _y: f32,
+    ) -> Option<SceneChange> {
+        None
+    }
+
+    fn text_input(&mut self, _shared: &mut S, _character: char) {}
+}
+
+pub struct SceneStack<S> {
+    scenes: Vec<Box<dyn Scene<S>>>,
+}
+
+impl<S> SceneStack<S> {
+    pub fn new(scene: Box<dyn Scene<S>>) -> Self {
+        Self {
+            scenes: vec![scene],
+        }
+    }
+
+    /// The screen on top, the one being played.
+    pub fn top(&self) -> Screen {
+        self.scenes.last().unwrap().screen()
+    }
+
+    /// The scene on top, the one being played.
+    pub fn top_mut(&mut self) -> &mut dyn Scene<S> {
+        self.scenes.last_mut().unwrap().as_mut()
+    }
+
+    /// The screen underneath the top one, if there's one.
+    fn below(&self) -> Option<Screen> {
+        self.scenes
+            .len()
+            .checked_sub(2)
+            .map(|id| self.scenes[id].screen())
+    }
+
+    /// How to make `change` from the current screen. Going to the screen underneath the top one
+    /// goes back to it.
+    pub fn resolve(&self, change: SceneChange) -> SceneChange {
+        match change {
+            SceneChange::Push(screen) | SceneChange::Switch(screen)
+                if self.below() == Some(screen) =>
+            {
+                SceneChange::Pop
+            }
+            SceneChange::Push(screen) if screen == self.top() => SceneChange::Switch(screen),
+            change => change,
+        }
+    }
+
+    /// How to go over to `screen` from the current one.
+    #[cfg(test)]
+    pub fn change_to(&self, screen: Screen) -> SceneChange {
+        self.resolve(SceneChange::to(screen))
+    }
+
+    /// The screen that ends up on top after `change`.
+    pub fn target(&self, change: SceneChange) -> Screen {
+        match change {
+            SceneChange::Push(screen) | SceneChange::Switch(screen) => screen,
+            SceneChange::Pop => self.below().unwrap_or_else(|| self.top()),
+        }
+    }
+
+    /// Make `change`, opening the screen it goes to with `open`.
+    pub fn apply(&mut self, change: SceneChange, open: impl FnOnce(Screen) -> Box<dyn Scene<S>>) {
+        match change {
+            SceneChange::Push(screen) => self.scenes.push(open(screen)),
+            // The last screen never gets popped, there would be nothing left to show.
+            SceneChange::Pop if self.scenes.len() > 1 => {
+                self.scenes.pop();
+            }
+            SceneChange::Pop => {}
+            SceneChange::Switch(screen) => {
+                let scene = open(screen);
+
+                self.scenes.clear();
+                self.scenes.push(scene);
+            }
+        }
+    }
+
+    /// Whether the screen is open, on top or underneath another one.
+    pub fn contains(&self, screen: Screen) -> bool {
+        self.scenes.iter().any(|scene| scene.screen() == screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scene that does nothing but be there.
+    struct Blank(Screen);
+
+    impl Scene<()> for Blank {
+        fn screen(&self) -> Screen {
+            self.0
+        }
+
+        fn update(
+            &mut self,
+            _: &mut Context,
+            _: &mut (),
+            _: f32,
+        ) -> GameResult<Option<SceneChange>> {
+            Ok(None)
+        }
+
+        fn draw(&mut self, _: &mut Context, _: &mut ()) -> GameResult<Option<SceneChange>> {
+            Ok(None)
+        }
+
+        fn key_press(&mut self, _: &mut Context, _: &mut (), _: KeyCode) -> Option<SceneChange> {
+            None
+        }
+
+        fn gamepad_button_press(
+            &mut self,
+            _: &mut Context,
+            _: &mut (),
+            _: Button,
+            _: GamepadId,
+        ) -> Option<SceneChange> {
+            None
+        }
+    }
+
+    fn blank(screen: Screen) -> Box<dyn Scene<()>> {
+        Box::new(Blank(screen))
+    }
+
+    #[test]
+    fn test_scene_stack() {
+        let mut scenes = SceneStack::new(blank(Screen::Play));
+
+        // The pause menu opens over the level, and the settings over the pause menu.
+        let change = scenes.change_to(Screen::Pause);
+        assert_eq!(change, SceneChange::Push(Screen::Pause));
+        scenes.apply(change, blank);
+
+        let change = scenes.change_to(Screen::Settings);
+        assert_eq!(change, SceneChange::Push(Screen::Settings));
+        scenes.apply(change, blank);
+        assert!(scenes.contains(Screen::Play));
+
+        // Leaving the settings goes back to the pause menu, and resuming back to the level.
+        let change = scenes.change_to(Screen::Pause);
+        assert_eq!(change, SceneChange::Pop);
+        assert_eq!(scenes.target(change), Screen::Pause);
+        scenes.apply(change, blank);
+        scenes.apply(SceneChange::Pop, blank);
+        assert_eq!(scenes.top(), Screen::Play);
+
+        // The last screen stays.
+        scenes.apply(SceneChange::Pop, blank);
+        assert_eq!(scenes.top(), Screen::Play);
+
+        // Starting the game again builds a fresh level.
+        assert_eq!(
+            scenes.change_to(Screen::Play),
+            SceneChange::Switch(Screen::Loading)
+        );
+
+        // Dying leaves the level behind.
+        scenes.apply(scenes.change_to(Screen::Dead), blank);
+        assert!(!scenes.contains(Screen::Play));
+    }
+}
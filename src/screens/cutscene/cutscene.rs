@@ -0,0 +1,385 @@
+//! Cutscenes, like the intro of a new game and the credits once the last level is beaten.
+//!
+//! A cutscene is a script in `resources/cutscenes` that puts text and pictures on the screen, one
+//! step after another, and waits in between. Nothing in a cutscene is played, any key or button
+//! skips it. A cutscene can be translated next to it as `name.<language>.scene`.
+//!
+//! # Script
+//! `.comment` => A comment \
+//! `title <text>` => Show a line of big text \
+//! `text [text]` => Show a line of text, an empty one without any \
+//! `image <file>` => Show a picture from `resources/images` \
+//! `wait <seconds>` => Keep the screen as it is for a while \
+//! `clear` => Take everything off the screen \
+//! `roll <speed>` => Roll what's on the screen up at `speed`, with new lines coming in from the
+//! bottom, until a `roll 0` \
+//! `music <track>` => Fade over to a track from `resources/music` \
+//! `music stop` => Fade the music out
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{audio::MusicController, display, utils::AssetManager, Screen};
+
+#[derive(PartialEq, Debug, Clone)]
+enum Step {
+    Title(String),
+    Text(String),
+    Image(String),
+    Wait(f32),
+    Clear,
+    Roll(f32),
+    Music(Option<String>),
+}
+
+/// Something a cutscene put on the screen.
+#[derive(PartialEq, Debug, Clone)]
+enum Element {
+    Title(String),
+    Text(String),
+    Image(String),
+}
+
+impl Element {
+    /// How much room the element takes up, going down the screen.
+    fn height(&self, asset_manager: &AssetManager) -> f32 {
+        match self {
+            Element::Title(_) => Scene::TITLE_SIZE + Scene::LINE_GAP,
+            Element::Text(_) => Scene::TEXT_SIZE + Scene::LINE_GAP,
+            Element::Image(image) => {
+                asset_manager.get_image(image).height() as f32 + Scene::LINE_GAP
+            }
+        }
+    }
+}
+
+pub struct Scene {
+    steps: Vec<Step>,
+    /// The step to take once the wait is over.
+    next: usize,
+    wait: f32,
+    /// What's on the screen, along with how long it's been there.
+    shown: Vec<(Element, f32)>,
+    /// How fast what's on the screen rolls up, nothing rolls at 0.
+    roll: f32,
+    /// How far what's on the screen rolled up.
+    rolled: f32,
+}
+
+impl Scene {
+    const TITLE_SIZE: f32 = 40.0;
+    const TEXT_SIZE: f32 = 20.0;
+    const LINE_GAP: f32 = 10.0;
+    /// How long it takes for something to fade in, in seconds.
+    const FADE: f32 = 0.6;
+
+    /// Parse a cutscene, failing with what's wrong with it.
+    pub fn parse(script: &str) -> Result<Self, &'static str> {
+        let mut steps = vec![];
+
+        for line in script.lines().map(str::trim) {
+            let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+            let number = || rest.parse().map_err(|_| "a cutscene is missing a number");
+
+            let step = match command {
+                "" | ".comment" => continue,
+                "title" => Step::Title(rest.to_string()),
+                "text" => Step::Text(rest.to_string()),
+                "image" if !rest.is_empty() => Step::Image(rest.to_string()),
+                "image" => return Err("a cutscene shows an image without saying which"),
+                "wait" => Step::Wait(number()?),
+                "clear" => Step::Clear,
+                "roll" => Step::Roll(number()?),
+                "music" if rest == "stop" => Step::Music(None),
+                "music" if !rest.is_empty() => Step::Music(Some(rest.to_string())),
+                "music" => return Err("a cutscene plays music without saying which"),
+                _ => return Err("a cutscene has a command that doesn't exist"),
+            };
+
+            steps.push(step);
+        }
+
+        Ok(Self {
+            steps,
+            next: 0,
+            wait: 0.0,
+            shown: vec![],
+            roll: 0.0,
+            rolled: 0.0,
+        })
+    }
+
+    /// The pictures the cutscene shows, to check they are there before it starts.
+    fn images(&self) -> impl Iterator<Item = &str> {
+        self.steps.iter().filter_map(|step| match step {
+            Step::Image(image) => Some(image.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Whether every step was taken and waited for.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.steps.len() && self.wait <= 0.0
+    }
+
+    /// Move the cutscene along, taking the steps up to the next wait. Returns the music cues of
+    /// the steps taken, `None` to fade the music out.
+    pub fn update(&mut self, dt: f32) -> Vec<Option<String>> {
+        for (_, age) in &mut self.shown {
+            *age += dt;
+        }
+
+        self.rolled += self.roll * dt;
+        self.wait -= dt;
+
+        let mut music = vec![];
+
+        while self.wait <= 0.0 && self.next < self.steps.len() {
+            match self.steps[self.next].clone() {
+                Step::Title(text) => self.shown.push((Element::Title(text), 0.0)),
+                Step::Text(text) => self.shown.push((Element::Text(text), 0.0)),
+                Step::Image(image) => self.shown.push((Element::Image(image), 0.0)),
+                Step::Wait(seconds) => self.wait += seconds,
+                Step::Clear => {
+                    self.shown.clear();
+                    self.rolled = 0.0;
+                }
+                Step::Roll(speed) => self.roll = speed,
+                Step::Music(track) => music.push(track),
+            }
+
+            self.next += 1;
+        }
+
+        music
+    }
+
+    pub fn draw(&self, ctx: &mut Context, asset_manager: &AssetManager) -> GameResult {
+        let (width, height) = display::screen_size(ctx);
+        let font = asset_manager.text_font();
+        let heights = self
+            .shown
+            .iter()
+            .map(|(element, _)| element.height(asset_manager))
+            .collect::<Vec<_>>();
+
+        // Rolling text comes in from the bottom, the rest sits in the middle of the screen.
+        let mut y = if self.roll > 0.0 || self.rolled > 0.0 {
+            height
+        } else {
+            (height - heights.iter().sum::<f32>()) / 2.0
+        } - self.rolled;
+
+        for ((element, age), element_height) in self.shown.iter().zip(heights) {
+            let color = Color::new(1.0, 1.0, 1.0, (age / Self::FADE).min(1.0));
+            let text = |text: &str, size: f32| {
+                Text::new(
+                    TextFragment::new(text)
+                        .font(font)
+                        .scale(Scale::uniform(size))
+                        .color(color),
+                )
+            };
+
+            match element {
+                Element::Title(title) => {
+                    let title = text(title, Self::TITLE_SIZE);
+                    let title_width = title.width(ctx) as f32;
+
+                    graphics::draw(
+                        ctx,
+                        &title,
+                        DrawParam::default().dest(Point2::new((width - title_width) / 2.0, y)),
+                    )?;
+                }
+                Element::Text(line) => {
+                    let line = text(line, Self::TEXT_SIZE);
+                    let line_width = line.width(ctx) as f32;
+
+                    graphics::draw(
+                        ctx,
+                        &line,
+                        DrawParam::default().dest(Point2::new((width - line_width) / 2.0, y)),
+                    )?;
+                }
+                Element::Image(image) => {
+                    let image = asset_manager.get_image(image);
+
+                    graphics::draw(
+                        ctx,
+                        &image,
+                        DrawParam::default()
+                            .dest(Point2::new((width - image.width() as f32) / 2.0, y))
+                            .color(color),
+                    )?;
+                }
+            }
+
+            y += element_height;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CutsceneScreen {
+    asset_manager: Rc<AssetManager>,
+
+    scene: Option<Scene>,
+    /// Where to go once the cutscene is over.
+    then: Screen,
+}
+
+impl CutsceneScreen {
+    /// The cutscene of a new game, before its first level.
+    pub const INTRO: &'static str = "intro.scene";
+    /// The cutscene once the last level is beaten.
+    pub const CREDITS: &'static str = "credits.scene";
+
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self {
+            asset_manager,
+
+            scene: None,
+            then: Screen::Menu,
+        }
+    }
+
+    /// Whether there's a cutscene called `name` to play.
+    pub fn exists(&self, name: &str) -> bool {
+        self.asset_manager
+            .get_files("cutscenes")
+            .iter()
+            .any(|file| file == name)
+    }
+
+    /// Play the cutscene called `name`, then go over to `then`. A cutscene that can't be played
+    /// goes over to `then` right away.
+    pub fn open(&mut self, name: &str, then: Screen) {
+        let script = self
+            .asset_manager
+            .get_translated_file(&format!("/cutscenes/{}", name));
+
+        self.then = then;
+        self.scene = Scene::parse(&script)
+            .and_then(|scene| {
+                let missing = scene
+                    .images()
+                    .any(|image| self.asset_manager.handle::<graphics::Image>(image).is_err());
+
+                if missing {
+                    Err("a cutscene shows an image that doesn't exist")
+                } else {
+                    Ok(scene)
+                }
+            })
+            .map_err(|error| log::error!("Cannot play the cutscene {}: {}", name, error))
+            .ok();
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, graphics::BLACK);
+
+        if let Some(scene) = &self.scene {
+            scene.draw(ctx, &self.asset_manager)?;
+        }
+
+        let (width, height) = display::screen_size(ctx);
+        let hint = Text::new(
+            TextFragment::new(self.asset_manager.text("PRESS ANY KEY TO SKIP"))
+                .font(self.asset_manager.text_font())
+                .scale(Scale::uniform(14.0))
+                .color(Color::new(1.0, 1.0, 1.0, 0.4)),
+        );
+        let hint_width = hint.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &hint,
+            DrawParam::default().dest(Point2::new(width - hint_width - 20.0, height - 34.0)),
+        )
+    }
+
+    /// Move the cutscene along. Returns where to go once it's over.
+    pub fn update(&mut self, dt: f32) -> Option<Screen> {
+        let scene = match &mut self.scene {
+            Some(scene) => scene,
+            None => return Some(self.then),
+        };
+
+        for track in scene.update(dt) {
+            let mut music = self.asset_manager.audio().music();
+
+            match track {
+                Some(track) => music.play(&track, MusicController::CROSSFADE),
+                None => music.stop(MusicController::CROSSFADE),
+            }
+        }
+
+        if scene.is_finished() {
+            self.scene = None;
+
+            return Some(self.then);
+        }
+
+        None
+    }
+
+    pub fn key_press(&mut self, _keycode: KeyCode) -> Option<Screen> {
+        self.skip()
+    }
+
+    pub fn gamepad_button_press(&mut self, _button: Button) -> Option<Screen> {
+        self.skip()
+    }
+
+    fn skip(&mut self) -> Option<Screen> {
+        self.scene.take().map(|_| self.then)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene() {
+        let mut scene = Scene::parse(
+            "
+            .comment The intro
+            music level.wav
+            title CALL OF FERRIS
+            text
+            wait 2
+            clear
+            text The end
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(scene.update(0.0), vec![Some(String::from("level.wav"))]);
+        assert_eq!(scene.shown.len(), 2);
+
+        // The screen stays as it is until the wait is over.
+        scene.update(1.5);
+        assert_eq!(scene.shown.len(), 2);
+        assert!(!scene.is_finished());
+
+        scene.update(1.0);
+        assert_eq!(
+            scene.shown,
+            vec![(Element::Text(String::from("The end")), 0.0)]
+        );
+        assert!(scene.is_finished());
+
+        assert!(Scene::parse("wait soon").is_err());
+        assert!(Scene::parse("dance").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! The high scores of the level, shown once a run is over.
+//!
+//! A run good enough to make it into the table asks for a name first. Typing it in goes through
+//! the text the window gets, so any keyboard layout works; a controller keeps the default name.
+//! With an online leaderboard set up, the table shows the best runs of everyone once it answers,
+//! and the ones on this computer until then or when it can't be reached.
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Font, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{
+    high_scores::{HighScore, HighScores},
+    leaderboard::Leaderboard,
+};
+
+use super::RunStats;
+
+/// How the online leaderboard is coming along.
+enum Online {
+    Waiting(Leaderboard),
+    /// The best runs of everyone.
+    Loaded(Vec<HighScore>),
+    /// The leaderboard couldn't be reached.
+    Offline,
+}
+
+pub struct HighScoreBoard {
+    high_scores: HighScores,
+    level: String,
+    /// The run that just ended, while its name is still being typed in.
+    entry: Option<HighScore>,
+    /// The address of the online leaderboard, empty without one.
+    leaderboard: String,
+    online: Option<Online>,
+}
+
+impl HighScoreBoard {
+    /// The most characters a name can have.
+    const NAME_LENGTH: usize = 10;
+    /// The name a new high score gets unless something else gets typed in.
+    const DEFAULT_NAME: &'static str = "FERRIS";
+
+    pub fn open(ctx: &Context, level: &str, stats: RunStats, leaderboard: &str) -> Self {
+        let high_scores = HighScores::load(ctx);
+        let entry = high_scores.rank(level, stats.score).map(|_| HighScore {
+            name: String::new(),
+            score: stats.score,
+            kills: stats.kills,
+            time: stats.time,
+        });
+
+        let mut board = Self {
+            high_scores,
+            level: level.to_string(),
+            entry,
+            leaderboard: leaderboard.to_string(),
+            online: None,
+        };
+
+        // A new high score goes to the leaderboard along with its name, once it's typed in.
+        if !board.is_entering() {
+            board.go_online(None);
+        }
+
+        board
+    }
+
+    /// Send the run to the online leaderboard, if there's one, and ask for the best runs.
+    fn go_online(&mut self, run: Option<HighScore>) {
+        if self.leaderboard.is_empty() {
+            return;
+        }
+
+        self.online = match Leaderboard::exchange(&self.leaderboard, &self.level, run) {
+            Ok(leaderboard) => Some(Online::Waiting(leaderboard)),
+            Err(error) => {
+                log::warn!("Cannot use the leaderboard: {}", error);
+
+                Some(Online::Offline)
+            }
+        };
+    }
+
+    /// Pick up what the online leaderboard answered, once it did.
+    fn poll(&mut self) {
+        let answer = match &self.online {
+            Some(Online::Waiting(leaderboard)) => leaderboard.poll(),
+            _ => None,
+        };
+
+        match answer {
+            Some(Ok(table)) => self.online = Some(Online::Loaded(table)),
+            Some(Err(error)) => {
+                log::warn!("Cannot reach the leaderboard: {}", error);
+
+                self.online = Some(Online::Offline);
+            }
+            None => {}
+        }
+    }
+
+    /// Whether a name is being typed in for a new high score.
+    pub fn is_entering(&self) -> bool {
+        self.entry.is_some()
+    }
+
+    pub fn text_input(&mut self, character: char) {
+        if let Some(entry) = &mut self.entry {
+            if character.is_alphanumeric() && entry.name.chars().count() < Self::NAME_LENGTH {
+                entry.name.extend(character.to_uppercase());
+            }
+        }
+    }
+
+    pub fn key_press(&mut self, ctx: &Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Return => self.confirm(ctx),
+            KeyCode::Back => {
+                if let Some(entry) = &mut self.entry {
+                    entry.name.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn gamepad_button_press(&mut self, ctx: &Context, button: Button) {
+        if let Button::South | Button::Start = button {
+            self.confirm(ctx);
+        }
+    }
+
+    /// Put the new high score in the table with the name typed in so far.
+    fn confirm(&mut self, ctx: &Context) {
+        if let Some(mut entry) = self.entry.take() {
+            if entry.name.is_empty() {
+                entry.name = String::from(Self::DEFAULT_NAME);
+            }
+
+            self.high_scores.insert(&self.level, entry.clone());
+
+            if let Err(error) = self.high_scores.save(ctx) {
+                log::error!("Cannot save the high scores: {}", error);
+            }
+
+            self.go_online(Some(entry));
+        }
+    }
+
+    /// Draw the table with its top left corner at `position`. The answer of the online
+    /// leaderboard gets picked up here as soon as it's in.
+    pub fn draw(&mut self, ctx: &mut Context, font: Font, position: Point2<f32>) -> GameResult<()> {
+        self.poll();
+
+        let header = match &self.online {
+            _ if self.is_entering() => String::from("NEW HIGH SCORE! TYPE YOUR NAME"),
+            Some(Online::Loaded(_)) => format!("ONLINE HIGH SCORES - LEVEL {}", self.level),
+            Some(Online::Waiting(_)) => format!("HIGH SCORES - LEVEL {} (CONNECTING)", self.level),
+            Some(Online::Offline) => format!("HIGH SCORES - LEVEL {} (OFFLINE)", self.level),
+            None => format!("HIGH SCORES - LEVEL {}", self.level),
+        };
+
+        let table = match &self.online {
+            Some(Online::Loaded(table)) => table.as_slice(),
+            _ => self.high_scores.table(&self.level),
+        };
+
+        let mut rows: Vec<(String, u32, bool)> = table
+            .iter()
+            .take(HighScores::KEPT)
+            .map(|high_score| (high_score.name.clone(), high_score.score, false))
+            .collect();
+
+        if let Some(entry) = &self.entry {
+            let rank = self.high_scores.rank(&self.level, entry.score).unwrap_or(0);
+
+            rows.insert(rank, (format!("{}_", entry.name), entry.score, true));
+            rows.truncate(HighScores::KEPT);
+        }
+
+        let header = Text::new(
+            TextFragment::new(header)
+                .font(font)
+                .scale(Scale::uniform(18.0))
+                .color(Color::from_rgb(247, 76, 0)),
+        );
+
+        graphics::draw(ctx, &header, DrawParam::default().dest(position))?;
+
+        for (id, (name, score, new)) in rows.into_iter().enumerate() {
+            let row = Text::new(
+                TextFragment::new(format!("{}. {:<11}{:06}", id + 1, name, score))
+                    .font(font)
+                    .scale(Scale::uniform(16.0))
+                    .color(if new {
+                        Color::from_rgb(247, 76, 0)
+                    } else {
+                        graphics::WHITE
+                    }),
+            );
+
+            graphics::draw(
+                ctx,
+                &row,
+                DrawParam::default().dest(Point2::new(
+                    position.x,
+                    position.y + 30.0 + id as f32 * 22.0,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+}
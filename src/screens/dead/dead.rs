@@ -1,6 +1,7 @@
 use std::rc::Rc;
 
 use ggez::{
+    event::{Button, KeyCode},
     graphics::Color,
     graphics::{self, Scale, Text, TextFragment},
     nalgebra::Point2,
@@ -8,27 +9,81 @@ use ggez::{
 };
 use graphics::DrawParam;
 
-use crate::utils::AssetManager;
+use crate::{
+    display,
+    menu::{draw_item, MenuInput},
+    utils::AssetManager,
+};
+
+use super::HighScoreBoard;
+
+/// How the run that just ended went.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunStats {
+    pub kills: i32,
+    /// Seconds the player stayed alive for.
+    pub time: f32,
+    pub score: u32,
+    /// What the dice of the run were seeded with, to play the same run again.
+    pub seed: u64,
+}
+
+/// What the player picked on the death screen.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DeathAction {
+    /// Carry on from the last checkpoint the player reached.
+    Retry,
+    /// Start the level over.
+    Restart,
+    MainMenu,
+}
 
 pub struct Death {
     asset_manager: Rc<AssetManager>,
+
+    /// The stats of the run that just ended.
+    stats: RunStats,
+    /// The highlighted item.
+    selected: usize,
+    /// The high scores of the level the run was in.
+    board: Option<HighScoreBoard>,
 }
 
 impl Death {
+    /// The items of the death screen, along with what they do.
+    const ITEMS: [(&'static str, DeathAction); 3] = [
+        ("RETRY CHECKPOINT", DeathAction::Retry),
+        ("RESTART LEVEL", DeathAction::Restart),
+        ("MAIN MENU", DeathAction::MainMenu),
+    ];
+
     pub fn spawn(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
-        Self { asset_manager }
+        Self {
+            asset_manager,
+
+            stats: RunStats::default(),
+            selected: 0,
+            board: None,
+        }
+    }
+
+    /// Show the death screen for a run of the level that just ended.
+    pub fn open(&mut self, ctx: &Context, level: &str, stats: RunStats, leaderboard: &str) {
+        self.stats = stats;
+        self.selected = 0;
+        self.board = Some(HighScoreBoard::open(ctx, level, stats, leaderboard));
     }
 
     pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let (width, _) = graphics::drawable_size(ctx);
+        let (width, _) = display::screen_size(ctx);
 
         graphics::clear(ctx, graphics::BLACK);
 
-        let consolas = self.asset_manager.get_font("Consolas.ttf");
+        let consolas = self.asset_manager.text_font();
         let ferris_planet = self.asset_manager.get_image("ferris_planet.png");
 
         let dead = Text::new(
-            TextFragment::new("YOU DEAD")
+            TextFragment::new(self.asset_manager.text("YOU DEAD"))
                 .scale(Scale::uniform(35.0))
                 .font(consolas)
                 .color(Color::from_rgb(255, 80, 76)),
@@ -98,10 +153,105 @@ impl Death {
             DrawParam::default().dest(Point2::new((width / 2.0) - 10.0, 240.0)),
         )?;
 
-        graphics::present(ctx)
+        let time = self.stats.time as u32;
+        let stats = Text::new(
+            TextFragment::new(self.asset_manager.text_with(
+                "KILLS {kills}   TIME {time}   SCORE {score}",
+                &[
+                    ("kills", &self.stats.kills),
+                    ("time", &format!("{:02}:{:02}", time / 60, time % 60)),
+                    ("score", &format!("{:06}", self.stats.score)),
+                ],
+            ))
+            .scale(Scale::uniform(20.0))
+            .font(consolas),
+        );
+
+        let stats_width = stats.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &stats,
+            DrawParam::default().dest(Point2::new((width - stats_width) / 2.0, 370.0)),
+        )?;
+
+        let seed = Text::new(
+            TextFragment::new(
+                self.asset_manager
+                    .text_with("SEED {seed}", &[("seed", &self.stats.seed)]),
+            )
+            .scale(Scale::uniform(14.0))
+            .font(consolas)
+            .color(Color::new(1.0, 1.0, 1.0, 0.6)),
+        );
+
+        let seed_width = seed.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &seed,
+            DrawParam::default().dest(Point2::new((width - seed_width) / 2.0, 396.0)),
+        )?;
+
+        if let Some(board) = &mut self.board {
+            board.draw(ctx, consolas, Point2::new(30.0, 120.0))?;
+        }
+
+        for (id, (label, _)) in Self::ITEMS.iter().enumerate() {
+            draw_item(
+                ctx,
+                consolas,
+                &self.asset_manager.text(label),
+                Point2::new(width / 2.0 - 110.0, 420.0 + id as f32 * 50.0),
+                id == self.selected,
+            )?;
+        }
+
+        Ok(())
     }
 
     pub fn update(&self, _ctx: &mut Context) -> GameResult {
         Ok(())
     }
+
+    pub fn key_press(&mut self, ctx: &Context, keycode: KeyCode) -> Option<DeathAction> {
+        // Typing in a name for a new high score comes first.
+        if let Some(board) = self.board.as_mut().filter(|board| board.is_entering()) {
+            board.key_press(ctx, keycode);
+
+            return None;
+        }
+
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input))
+    }
+
+    pub fn gamepad_button_press(&mut self, ctx: &Context, button: Button) -> Option<DeathAction> {
+        if let Some(board) = self.board.as_mut().filter(|board| board.is_entering()) {
+            board.gamepad_button_press(ctx, button);
+
+            return None;
+        }
+
+        MenuInput::from_button(button).and_then(|input| self.navigate(input))
+    }
+
+    pub fn text_input(&mut self, character: char) {
+        if let Some(board) = &mut self.board {
+            board.text_input(character);
+        }
+    }
+
+    fn navigate(&mut self, input: MenuInput) -> Option<DeathAction> {
+        let count = Self::ITEMS.len();
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Select => return Some(Self::ITEMS[self.selected].1),
+            MenuInput::Back => return Some(DeathAction::MainMenu),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
+    }
 }
@@ -1,3 +1,6 @@
+mod board;
+#[allow(clippy::module_inception)]
 mod dead;
 
+pub use board::*;
 pub use dead::*;
@@ -0,0 +1,214 @@
+//! Who plays with what.
+//!
+//! Every player claims a device by pressing ENTER on the keyboard or A on a controller, and gives it
+//! up again with ESCAPE or B. Until player one claims something, they play with whatever nobody
+//! else took, which is how a single player never has to come here at all. Everything the devices
+//! do only reaches the player they belong to.
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    input::gamepad::{self, GamepadId},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{display, utils::AssetManager, Screen};
+
+/// Something a player can play with.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum InputDevice {
+    KeyboardMouse,
+    Gamepad(GamepadId),
+}
+
+/// The device of every player.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Devices {
+    /// What each player claimed, if anything.
+    players: [Option<InputDevice>; Devices::PLAYERS],
+}
+
+impl Devices {
+    pub const PLAYERS: usize = 2;
+
+    /// The player the device belongs to, if any.
+    pub fn player(&self, device: InputDevice) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|claimed| *claimed == Some(device))
+            .or(if self.players[0].is_none() {
+                Some(0)
+            } else {
+                None
+            })
+    }
+
+    /// What the player claimed, if anything.
+    pub fn device(&self, player: usize) -> Option<InputDevice> {
+        self.players[player]
+    }
+
+    /// Give the device to the first player without one. Returns false if it's already taken or
+    /// everyone has a device.
+    pub fn claim(&mut self, device: InputDevice) -> bool {
+        if self.players.contains(&Some(device)) {
+            return false;
+        }
+
+        match self.players.iter_mut().find(|claimed| claimed.is_none()) {
+            Some(free) => {
+                *free = Some(device);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the device away from whoever has it. Returns false if nobody had it.
+    pub fn release(&mut self, device: InputDevice) -> bool {
+        match self
+            .players
+            .iter_mut()
+            .find(|claimed| **claimed == Some(device))
+        {
+            Some(claimed) => {
+                *claimed = None;
+
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct DeviceScreen {
+    asset_manager: Rc<AssetManager>,
+}
+
+impl DeviceScreen {
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self { asset_manager }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, devices: &Devices) -> GameResult<()> {
+        let (width, _) = display::screen_size(ctx);
+        let consolas = self.asset_manager.text_font();
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let title = Text::new(
+            TextFragment::new(self.asset_manager.text("PLAYERS"))
+                .font(consolas)
+                .scale(Scale::uniform(35.0))
+                .color(Color::from_rgb(247, 76, 0)),
+        );
+
+        draw_centered(ctx, &title, width, 40.0)?;
+
+        for player in 0..Devices::PLAYERS {
+            let device = match devices.device(player) {
+                Some(InputDevice::KeyboardMouse) => self.asset_manager.text("KEYBOARD & MOUSE"),
+                Some(InputDevice::Gamepad(id)) => gamepad::gamepad(ctx, id).name().to_uppercase(),
+                None if player == 0 => self.asset_manager.text("ANY DEVICE"),
+                None => self.asset_manager.text("PRESS ENTER OR A TO JOIN"),
+            };
+
+            let row = Text::new(
+                TextFragment::new(self.asset_manager.text_with(
+                    "PLAYER {player}   {device}",
+                    &[("player", &(player + 1)), ("device", &device)],
+                ))
+                .font(consolas)
+                .scale(Scale::uniform(22.0)),
+            );
+
+            draw_centered(ctx, &row, width, 160.0 + player as f32 * 60.0)?;
+        }
+
+        let hint = Text::new(
+            TextFragment::new(
+                self.asset_manager
+                    .text("ENTER / A TO JOIN   ESCAPE / B TO LEAVE OR GO BACK"),
+            )
+            .font(consolas)
+            .scale(Scale::uniform(16.0))
+            .color(Color::from_rgb(160, 160, 160)),
+        );
+
+        draw_centered(ctx, &hint, width, 340.0)?;
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(&mut self, keycode: KeyCode, devices: &mut Devices) -> Option<Screen> {
+        match keycode {
+            KeyCode::Return | KeyCode::Space => self.join(InputDevice::KeyboardMouse, devices),
+            KeyCode::Escape | KeyCode::Back => self.leave(InputDevice::KeyboardMouse, devices),
+            _ => None,
+        }
+    }
+
+    pub fn gamepad_button_press(
+        &mut self,
+        button: Button,
+        id: GamepadId,
+        devices: &mut Devices,
+    ) -> Option<Screen> {
+        match button {
+            Button::South | Button::Start => self.join(InputDevice::Gamepad(id), devices),
+            Button::East | Button::Select => self.leave(InputDevice::Gamepad(id), devices),
+            _ => None,
+        }
+    }
+
+    fn join(&self, device: InputDevice, devices: &mut Devices) -> Option<Screen> {
+        devices.claim(device);
+
+        None
+    }
+
+    /// Leaving with a device nobody claimed goes back to the menu.
+    fn leave(&self, device: InputDevice, devices: &mut Devices) -> Option<Screen> {
+        if devices.release(device) {
+            None
+        } else {
+            Some(Screen::Menu)
+        }
+    }
+}
+
+/// Draw the text in the middle of the screen, with its top at `y`.
+fn draw_centered(ctx: &mut Context, text: &Text, width: f32, y: f32) -> GameResult<()> {
+    let x = (width - text.width(ctx) as f32) / 2.0;
+
+    graphics::draw(ctx, text, DrawParam::default().dest(Point2::new(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_devices() {
+        let mut devices = Devices::default();
+
+        // Player one plays with anything until they pick something.
+        assert_eq!(devices.player(InputDevice::KeyboardMouse), Some(0));
+
+        assert!(devices.claim(InputDevice::KeyboardMouse));
+        assert!(!devices.claim(InputDevice::KeyboardMouse));
+        assert_eq!(devices.player(InputDevice::KeyboardMouse), Some(0));
+
+        assert!(devices.release(InputDevice::KeyboardMouse));
+        assert!(!devices.release(InputDevice::KeyboardMouse));
+        assert_eq!(devices.device(0), None);
+    }
+}
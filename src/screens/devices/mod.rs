@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod devices;
+
+pub use devices::*;
@@ -0,0 +1,227 @@
+//! Sprite sheets and the animations played on them.
+//!
+//! A sprite sheet cuts an image up into a grid of frames. An animation goes through some of those
+//! frames, showing each for a while, and either loops or stops on its last frame. Frames can carry
+//! an event, like a foot touching the ground, which gets handed back once the animation gets to
+//! them. Each thing that's animated keeps an animator with all of its animations by name.
+
+use ggez::{
+    graphics::{self, DrawParam, Image, Rect},
+    Context, GameResult,
+};
+
+use crate::utils::Sprite;
+
+/// A sprite cut up into a grid of frames.
+pub struct SpriteSheet {
+    image: Image,
+    /// The name of the image the sheet is on, sheets on the same image can share a batch.
+    texture: String,
+    /// Where every frame is on the image, row by row, as a part of the whole image.
+    frames: Vec<Rect>,
+}
+
+impl SpriteSheet {
+    /// The sprite can be a whole image or a region of an atlas.
+    pub fn new(sprite: Sprite, columns: usize, rows: usize) -> Self {
+        let src = sprite.src;
+        let (width, height) = (src.w / columns as f32, src.h / rows as f32);
+        let frames = (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    Rect::new(
+                        src.x + column as f32 * width,
+                        src.y + row as f32 * height,
+                        width,
+                        height,
+                    )
+                })
+            })
+            .collect();
+
+        Self {
+            image: sprite.image,
+            texture: sprite.texture,
+            frames,
+        }
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn texture(&self) -> &str {
+        &self.texture
+    }
+
+    /// Where the frame is on the image, as a part of the whole image.
+    pub fn src(&self, frame: usize) -> Rect {
+        self.frames[frame]
+    }
+
+    /// The size of a frame in pixels.
+    pub fn frame_size(&self) -> (f32, f32) {
+        let frame = self.frames[0];
+
+        (
+            self.image.width() as f32 * frame.w,
+            self.image.height() as f32 * frame.h,
+        )
+    }
+
+    pub fn draw(&self, ctx: &mut Context, frame: usize, param: DrawParam) -> GameResult {
+        graphics::draw(ctx, &self.image, param.src(self.src(frame)))
+    }
+}
+
+struct Frame {
+    /// The frame of the sprite sheet shown.
+    index: usize,
+    /// How long the frame is shown for.
+    duration: f32,
+    /// Handed back once the animation gets to the frame.
+    event: Option<&'static str>,
+}
+
+pub struct Animation {
+    frames: Vec<Frame>,
+    looping: bool,
+}
+
+impl Animation {
+    /// A looping animation starts over once it's done, others stay on their last frame.
+    pub fn new(looping: bool) -> Self {
+        Self {
+            frames: vec![],
+            looping,
+        }
+    }
+
+    /// Show the `index`th frame of the sprite sheet next, for `duration` seconds.
+    pub fn frame(mut self, index: usize, duration: f32) -> Self {
+        self.frames.push(Frame {
+            index,
+            duration,
+            event: None,
+        });
+
+        self
+    }
+
+    /// Hand back `event` once the animation gets to the last frame added.
+    pub fn event(mut self, event: &'static str) -> Self {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.event = Some(event);
+        }
+
+        self
+    }
+}
+
+/// Plays the animations of something, one at a time.
+pub struct Animator {
+    animations: Vec<(&'static str, Animation)>,
+    /// The animation being played.
+    current: usize,
+    /// The frame of the animation being shown and for how long it has been.
+    frame: usize,
+    time: f32,
+}
+
+impl Animator {
+    /// The first animation gets played right away.
+    pub fn new(animations: Vec<(&'static str, Animation)>) -> Self {
+        Self {
+            animations,
+            current: 0,
+            frame: 0,
+            time: 0.0,
+        }
+    }
+
+    /// Switch over to the animation with the provided name. Playing the animation that's already on
+    /// keeps it going instead of starting it over.
+    pub fn play(&mut self, name: &str) {
+        if let Some(index) = self.animations.iter().position(|(n, _)| *n == name) {
+            if index != self.current {
+                self.current = index;
+                self.frame = 0;
+                self.time = 0.0;
+            }
+        }
+    }
+
+    /// Move the animation along. Returns the events of the frames it got to.
+    pub fn update(&mut self, dt: f32) -> Vec<&'static str> {
+        let animation = &self.animations[self.current].1;
+        let mut events = vec![];
+
+        self.time += dt;
+
+        while let Some(frame) = animation.frames.get(self.frame) {
+            if self.time < frame.duration || frame.duration <= 0.0 {
+                break;
+            }
+
+            let next = if self.frame + 1 < animation.frames.len() {
+                self.frame + 1
+            } else if animation.looping {
+                0
+            } else {
+                // One shot animations stay on their last frame.
+                self.time = frame.duration;
+
+                break;
+            };
+
+            self.time -= frame.duration;
+            self.frame = next;
+            events.extend(animation.frames[next].event);
+        }
+
+        events
+    }
+
+    /// The frame of the sprite sheet to show.
+    pub fn frame(&self) -> usize {
+        self.animations[self.current]
+            .1
+            .frames
+            .get(self.frame)
+            .map_or(0, |frame| frame.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animator() {
+        let mut animator = Animator::new(vec![
+            ("idle", Animation::new(true).frame(0, 1.0)),
+            (
+                "walk",
+                Animation::new(true)
+                    .frame(1, 0.5)
+                    .frame(2, 0.5)
+                    .event("step"),
+            ),
+            ("die", Animation::new(false).frame(3, 0.2).frame(4, 0.2)),
+        ]);
+
+        assert!(animator.update(5.0).is_empty());
+        assert_eq!(animator.frame(), 0);
+
+        animator.play("walk");
+        assert_eq!(animator.update(0.6), vec!["step"]);
+        assert_eq!(animator.frame(), 2);
+
+        // Looping back around and getting to the step again.
+        assert_eq!(animator.update(1.0), vec!["step"]);
+
+        animator.play("die");
+        animator.update(10.0);
+        assert_eq!(animator.frame(), 4);
+    }
+}
@@ -0,0 +1,184 @@
+//! Drawing lots of the same sprite at once.
+//!
+//! Every image drawn on its own costs a draw call, which adds up quickly on big levels. The ground
+//! never moves, so its tiles get put into sprite batches a chunk at a time, one for every image
+//! their sprites are on, in world coordinates, and a chunk is only rebuilt once it changes. Sprites
+//! that move around, like the entities and the clouds, get batched again every frame instead, which
+//! still draws all of them in one go. Chunks out of view are skipped entirely.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use ggez::{
+    graphics::{self, spritebatch::SpriteBatch, DrawParam, Image, Rect},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+
+use crate::{
+    game::{components::tile::Tile, culling::is_visible, physics::Physics},
+    utils::{AssetManager, Sprite},
+};
+
+struct Chunk {
+    /// The tiles in the chunk, as indices into the ground.
+    tiles: Range<usize>,
+    /// A batch for every image the sprites of the tiles are on.
+    batches: BTreeMap<String, SpriteBatch>,
+    /// Whether the batches need to be rebuilt before being drawn.
+    dirty: bool,
+    /// The middle of the tiles in the world and how far they reach from it, found when the
+    /// batches get built.
+    center: Vec2,
+    radius: f32,
+}
+
+/// The ground of a level, drawn a chunk at a time.
+pub struct TileBatches {
+    chunks: Vec<Chunk>,
+}
+
+impl TileBatches {
+    /// How many tiles go into a chunk.
+    const CHUNK_SIZE: usize = 32;
+
+    pub fn new(tiles: usize) -> Self {
+        Self {
+            chunks: chunks(tiles, Self::CHUNK_SIZE)
+                .into_iter()
+                .map(|tiles| Chunk {
+                    tiles,
+                    batches: BTreeMap::new(),
+                    dirty: true,
+                    center: Vec2::zeros(),
+                    radius: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Draw the chunks that show up in `view`, the part of the world the camera sees.
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        camera: &Camera,
+        view: Rect,
+        tiles: &[Tile],
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+    ) -> GameResult {
+        // The batches are in world coordinates, so the camera only needs to move them around.
+        let origin = camera.calculate_dest_point(Vec2::zeros());
+
+        for chunk in &mut self.chunks {
+            if chunk.dirty {
+                for batch in chunk.batches.values_mut() {
+                    batch.clear();
+                }
+
+                let (mut min, mut max) = (Vec2::repeat(f32::MAX), Vec2::repeat(f32::MIN));
+
+                for tile in &tiles[chunk.tiles.clone()] {
+                    let position = tile.position(physics);
+                    let size = tile.dimensions();
+                    let half = Vec2::new(size.x, size.y) / 2.0;
+
+                    min = min.inf(&(Vec2::new(position.x, position.y) - half));
+                    max = max.sup(&(Vec2::new(position.x, position.y) + half));
+
+                    let Sprite {
+                        image,
+                        texture,
+                        src,
+                    } = asset_manager.get_sprite(tile.sprite_name());
+
+                    chunk
+                        .batches
+                        .entry(texture)
+                        .or_insert_with(|| SpriteBatch::new(image))
+                        .add(tile.sprite(physics).src(src));
+                }
+
+                chunk.center = (min + max) / 2.0;
+                chunk.radius = (max - min).max() / 2.0;
+                chunk.dirty = false;
+            }
+
+            if !is_visible(view, chunk.center, chunk.radius) {
+                continue;
+            }
+
+            for batch in chunk.batches.values() {
+                graphics::draw(
+                    ctx,
+                    batch,
+                    DrawParam::default().dest(Point2::new(origin.x, origin.y)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The sprites drawn this frame, with a batch for every image they're on. The batches get drawn
+/// in the order their images first came up, so what's held in front of a sprite stays in front.
+pub struct SpriteBatches {
+    batches: Vec<(String, SpriteBatch)>,
+}
+
+impl SpriteBatches {
+    pub fn new() -> Self {
+        Self { batches: vec![] }
+    }
+
+    /// Put a sprite on the `texture` image in its batch.
+    pub fn add(&mut self, texture: &str, image: &Image, param: DrawParam) {
+        let id = match self.batches.iter().position(|(name, _)| name == texture) {
+            Some(id) => id,
+            None => {
+                self.batches
+                    .push((texture.to_string(), SpriteBatch::new(image.clone())));
+                self.batches.len() - 1
+            }
+        };
+
+        self.batches[id].1.add(param);
+    }
+
+    /// Draw all the sprites added since the last time, and start over.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        for (_, batch) in &mut self.batches {
+            graphics::draw(ctx, batch, DrawParam::default())?;
+            batch.clear();
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for SpriteBatches {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split `count` things into chunks of at most `size`.
+fn chunks(count: usize, size: usize) -> Vec<Range<usize>> {
+    (0..count)
+        .step_by(size)
+        .map(|start| start..(start + size).min(count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(chunks(70, 32), vec![0..32, 32..64, 64..70]);
+        assert_eq!(chunks(64, 32), vec![0..32, 32..64]);
+        assert!(chunks(0, 32).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! The big health bar at the top of the screen during a boss fight.
+//!
+//! The bar slides in when the fight starts and back out once the boss goes down. It's split into
+//! segments, with a pip for every phase of the fight next to the name of the boss.
+
+use ggez::{
+    graphics::{
+        self, Color, DrawMode, DrawParam, Font, MeshBuilder, Rect, Scale, Text, TextFragment,
+    },
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use super::layout::{Anchor, Layout};
+
+pub struct BossBar {
+    /// The name of the boss being fought.
+    name: &'static str,
+    /// The health left, between 0.0 and 1.0.
+    fraction: f32,
+    /// The phase of the fight, starting at 1.
+    phase: u32,
+    /// The amount of phases in the fight.
+    phases: u32,
+
+    /// Whether a boss fight is going on.
+    active: bool,
+    /// How far the bar has slid in, between 0.0 and 1.0.
+    shown: f32,
+}
+
+impl BossBar {
+    /// The size of the bar.
+    const SIZE: (f32, f32) = (500.0, 18.0);
+    /// The amount of segments the bar is split into.
+    const SEGMENTS: u32 = 10;
+    /// How long sliding in and out takes.
+    const SLIDE_TIME: f32 = 0.4;
+    /// How far from the top of the screen the bar sits.
+    const TOP: f32 = 110.0;
+
+    const COLOR: Color = Color {
+        r: 200.0 / 255.0,
+        g: 40.0 / 255.0,
+        b: 40.0 / 255.0,
+        a: 1.0,
+    };
+
+    pub fn new() -> Self {
+        Self {
+            name: "",
+            fraction: 1.0,
+            phase: 1,
+            phases: 1,
+
+            active: false,
+            shown: 0.0,
+        }
+    }
+
+    /// Start showing the bar for a boss fight.
+    pub fn start(&mut self, name: &'static str, phases: u32) {
+        self.name = name;
+        self.phases = phases;
+        self.active = true;
+    }
+
+    /// The boss went down, slide the bar out.
+    pub fn end(&mut self) {
+        self.active = false;
+        self.fraction = 0.0;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn update(&mut self, fraction: f32, phase: u32, dt: f32) {
+        if self.active {
+            self.fraction = fraction;
+            self.phase = phase;
+        }
+
+        let target = if self.active { 1.0 } else { 0.0 };
+        let step = dt / Self::SLIDE_TIME;
+
+        self.shown = if self.shown < target {
+            (self.shown + step).min(target)
+        } else {
+            (self.shown - step).max(target)
+        };
+    }
+
+    pub fn draw(&self, ctx: &mut Context, layout: &Layout, font: Font) -> GameResult {
+        if self.shown <= 0.0 {
+            return Ok(());
+        }
+
+        // Ease the slide so it settles in softly.
+        let slide = 1.0 - (1.0 - self.shown).powi(2);
+        let position = layout.place(
+            Anchor::Top,
+            Vec2::new(-Self::SIZE.0 / 2.0, Self::TOP - (1.0 - slide) * 60.0),
+        );
+        let alpha = self.shown;
+
+        let name = Text::new(
+            TextFragment::new(self.name)
+                .font(font)
+                .scale(Scale::uniform(20.0))
+                .color(Color::new(1.0, 1.0, 1.0, alpha)),
+        );
+
+        let (_, name_height) = name.dimensions(ctx);
+
+        graphics::draw(
+            ctx,
+            &name,
+            DrawParam::default().dest(Point2::new(
+                position.x,
+                position.y - name_height as f32 - 6.0,
+            )),
+        )?;
+
+        let bar = Rect::new(position.x, position.y, Self::SIZE.0, Self::SIZE.1);
+        let mut mesh = MeshBuilder::new();
+
+        mesh.rectangle(
+            DrawMode::fill(),
+            bar,
+            Color::new(0.0, 0.0, 0.0, 0.6 * alpha),
+        );
+
+        if self.fraction > 0.0 {
+            mesh.rectangle(
+                DrawMode::fill(),
+                Rect::new(bar.x, bar.y, bar.w * self.fraction, bar.h),
+                Color {
+                    a: alpha,
+                    ..Self::COLOR
+                },
+            );
+        }
+
+        // Segments
+        for segment in 1..Self::SEGMENTS {
+            let x = bar.x + bar.w * segment as f32 / Self::SEGMENTS as f32;
+
+            mesh.line(
+                &[Point2::new(x, bar.y), Point2::new(x, bar.bottom())],
+                2.0,
+                Color::new(0.0, 0.0, 0.0, alpha),
+            )?;
+        }
+
+        mesh.rectangle(DrawMode::stroke(2.0), bar, Color::new(1.0, 1.0, 1.0, alpha));
+
+        // Phase pips, filled in for every phase that has been reached
+        for pip in 0..self.phases {
+            let center = Point2::new(
+                bar.right() - 6.0 - (self.phases - 1 - pip) as f32 * 16.0,
+                bar.y - 14.0,
+            );
+            let mode = if pip < self.phase {
+                DrawMode::fill()
+            } else {
+                DrawMode::stroke(2.0)
+            };
+
+            mesh.circle(
+                mode,
+                center,
+                5.0,
+                0.5,
+                Color {
+                    a: alpha,
+                    ..Self::COLOR
+                },
+            );
+        }
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slide() {
+        let mut bar = BossBar::new();
+
+        bar.start("BOSS", 3);
+        bar.update(0.5, 2, BossBar::SLIDE_TIME / 2.0);
+
+        assert_eq!(bar.shown, 0.5);
+
+        bar.update(0.5, 2, BossBar::SLIDE_TIME);
+
+        assert_eq!(bar.shown, 1.0);
+
+        // The bar keeps showing an empty bar while it slides out.
+        bar.end();
+        bar.update(1.0, 1, BossBar::SLIDE_TIME / 2.0);
+
+        assert_eq!(bar.fraction, 0.0);
+        assert_eq!(bar.shown, 0.5);
+    }
+}
@@ -0,0 +1,200 @@
+//! Boss rush, every boss of the game one after the other.
+//!
+//! Boss rush gets unlocked by beating the campaign. It's played on a map with `.boss_rush`, which
+//! starts out empty and gets the bosses dropped in one at a time. Every boss beaten gives a short
+//! break before the next, with a bit of health back and a shop of pickups to take one of. The rush
+//! is over once every boss is beaten or Ferris goes down, and the results show how far it went.
+
+/// Who comes in the rush, in order, with how much tougher they are than in the campaign.
+const ROSTER: [(&str, f32); 3] = [
+    ("THE GOPHER KING", 1.0),
+    ("THE GOPHER QUEEN", 1.5),
+    ("THE GOPHER EMPEROR", 2.0),
+];
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Stage {
+    /// Fighting the boss of the roster with this id.
+    Fight(usize),
+    /// Waiting for the boss of the roster with this id, or for the results once it's past the
+    /// last one.
+    Break {
+        next: usize,
+        left: f32,
+    },
+    Over,
+}
+
+/// What the game has to do for the rush.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RushEvent {
+    /// Drop in the boss with the name, this many times as tough as in the campaign.
+    Spawn(&'static str, f32),
+    /// A boss was beaten, with another one still to come.
+    Break,
+    /// Every boss was beaten.
+    Won,
+}
+
+/// How a boss rush went, for the results.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RushResults {
+    /// The bosses of the rush, with how long taking each of them down took if it was.
+    pub bosses: Vec<(&'static str, Option<f32>)>,
+    pub won: bool,
+    pub score: u32,
+}
+
+impl RushResults {
+    /// How long the bosses that were beaten took altogether.
+    pub fn total_time(&self) -> f32 {
+        self.bosses.iter().filter_map(|(_, time)| *time).sum()
+    }
+}
+
+pub struct BossRush {
+    stage: Stage,
+    /// How long taking down each of the bosses beaten so far took.
+    times: Vec<f32>,
+    /// How long the boss being fought has been fought for.
+    fighting: f32,
+}
+
+impl BossRush {
+    /// How long the break before the first boss is.
+    const START: f32 = 2.0;
+    /// How long the breaks between two bosses are.
+    pub const BREAK: f32 = 12.0;
+    /// How long the last boss gets to go up in flames before the results.
+    const FINISH: f32 = 2.0;
+
+    pub fn new() -> Self {
+        Self {
+            stage: Stage::Break {
+                next: 0,
+                left: Self::START,
+            },
+            times: vec![],
+            fighting: 0.0,
+        }
+    }
+
+    /// Whether the rush is between two bosses.
+    pub fn is_on_break(&self) -> bool {
+        matches!(self.stage, Stage::Break { next, .. } if next > 0 && next < ROSTER.len())
+    }
+
+    /// Move the rush along, with whether the boss being fought is still around.
+    pub fn update(&mut self, boss_alive: bool, dt: f32) -> Option<RushEvent> {
+        match &mut self.stage {
+            Stage::Fight(_) if boss_alive => {
+                self.fighting += dt;
+
+                None
+            }
+            Stage::Fight(boss) => {
+                let next = *boss + 1;
+
+                self.times.push(self.fighting);
+                self.fighting = 0.0;
+
+                if next < ROSTER.len() {
+                    self.stage = Stage::Break {
+                        next,
+                        left: Self::BREAK,
+                    };
+
+                    Some(RushEvent::Break)
+                } else {
+                    self.stage = Stage::Break {
+                        next,
+                        left: Self::FINISH,
+                    };
+
+                    None
+                }
+            }
+            Stage::Break { next, left } => {
+                *left -= dt;
+
+                if *left > 0.0 {
+                    return None;
+                }
+
+                match ROSTER.get(*next) {
+                    Some((name, toughness)) => {
+                        self.stage = Stage::Fight(*next);
+
+                        Some(RushEvent::Spawn(name, *toughness))
+                    }
+                    None => {
+                        self.stage = Stage::Over;
+
+                        Some(RushEvent::Won)
+                    }
+                }
+            }
+            Stage::Over => None,
+        }
+    }
+
+    /// How the rush went so far, with the score of the run.
+    pub fn results(&self, score: u32) -> RushResults {
+        RushResults {
+            bosses: ROSTER
+                .iter()
+                .enumerate()
+                .map(|(id, (name, _))| (*name, self.times.get(id).copied()))
+                .collect(),
+            won: self.stage == Stage::Over,
+            score,
+        }
+    }
+}
+
+impl Default for BossRush {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boss_rush() {
+        let mut rush = BossRush::new();
+
+        assert_eq!(rush.update(false, 1.0), None);
+        assert_eq!(
+            rush.update(false, 1.0),
+            Some(RushEvent::Spawn("THE GOPHER KING", 1.0))
+        );
+
+        rush.update(true, 5.0);
+        assert_eq!(rush.update(false, 0.0), Some(RushEvent::Break));
+        assert!(rush.is_on_break());
+
+        // The next boss waits for the break to be over.
+        assert_eq!(rush.update(false, BossRush::BREAK - 1.0), None);
+        assert_eq!(
+            rush.update(false, 1.0),
+            Some(RushEvent::Spawn("THE GOPHER QUEEN", 1.5))
+        );
+
+        let results = rush.results(300);
+
+        assert_eq!(results.bosses[0], ("THE GOPHER KING", Some(5.0)));
+        assert_eq!(results.bosses[1], ("THE GOPHER QUEEN", None));
+        assert_eq!(results.total_time(), 5.0);
+        assert!(!results.won);
+
+        rush.update(false, 0.0);
+        rush.update(false, BossRush::BREAK);
+        assert_eq!(rush.update(false, 0.0), None);
+        assert!(!rush.is_on_break());
+        assert_eq!(rush.update(false, BossRush::FINISH), Some(RushEvent::Won));
+        assert!(rush.results(0).won);
+    }
+}
@@ -0,0 +1,429 @@
+//! Camera controller that decides where the camera should be looking at.
+//!
+//! The ggez-goodies camera only does the coordinate transforms, so all of the actual camera
+//! movement (like smoothly following the player) lives in here.
+
+use ggez::nalgebra::{Matrix4, Vector3};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use rand::Rng;
+
+use crate::utils::{damp, lerp, smoothstep};
+
+pub struct CameraController {
+    /// The point the camera wants to be centered on.
+    target: Vec2,
+    /// The point the camera is currently centered on.
+    position: Vec2,
+
+    /// How fast the camera catches up with its target on the X and Y axis.
+    /// Higher values make the camera follow more tightly.
+    pub smoothing: Vec2,
+    /// Half of the width and height of the deadzone window around the target.
+    /// The camera only starts moving once the followed point leaves this window.
+    pub deadzone: Vec2,
+
+    /// How far ahead the camera looks in the direction the player is facing.
+    pub look_ahead: f32,
+    /// How much the camera leans towards the aim. 0.0 turns it off.
+    pub aim_look: f32,
+    /// The direction the player last moved in, -1.0 for left and 1.0 for right.
+    facing: f32,
+    /// The offset the camera wants to look ahead by.
+    look_target: Vec2,
+    /// The current look ahead offset, smoothly moving towards `look_target`.
+    look_offset: Vec2,
+
+    /// Screen shake applied on top of the camera position.
+    pub shake: ScreenShake,
+
+    /// The current zoom level and where it's heading.
+    zoom: Zoom,
+
+    /// The area of the world the camera is allowed to show.
+    bounds: Option<CameraBounds>,
+
+    /// A cinematic path that has taken control of the camera, along with the point it started from.
+    cinematic: Option<(CameraPath, Vec2)>,
+}
+
+impl CameraController {
+    /// Default horizontal smoothing rate.
+    const SMOOTHING_X: f32 = 8.0;
+    /// Default vertical smoothing rate. Lower than the horizontal one so jumps don't feel jarring.
+    const SMOOTHING_Y: f32 = 4.0;
+
+    /// Default half width of the deadzone window.
+    const DEADZONE_X: f32 = 60.0;
+    /// Default half height of the deadzone window.
+    const DEADZONE_Y: f32 = 40.0;
+
+    /// Default look ahead distance.
+    const LOOK_AHEAD: f32 = 150.0;
+    /// How fast the look ahead offset changes, kept low so flipping direction feels smooth.
+    const LOOK_SMOOTHING: f32 = 2.0;
+
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            target: position,
+            position,
+
+            smoothing: Vec2::new(Self::SMOOTHING_X, Self::SMOOTHING_Y),
+            deadzone: Vec2::new(Self::DEADZONE_X, Self::DEADZONE_Y),
+
+            look_ahead: Self::LOOK_AHEAD,
+            aim_look: 0.0,
+            facing: 1.0,
+            look_target: Vec2::new(0.0, 0.0),
+            look_offset: Vec2::new(0.0, 0.0),
+
+            shake: ScreenShake::new(),
+
+            zoom: Zoom::new(1.0),
+
+            bounds: None,
+
+            cinematic: None,
+        }
+    }
+
+    /// Take control of the camera and fly it along the provided path.
+    /// Once the path is done the camera flies back to whatever it was following.
+    pub fn play_path(&mut self, path: CameraPath) {
+        self.cinematic = Some((path, self.position));
+    }
+
+    /// Returns true while a cinematic path is in control of the camera.
+    pub fn is_cinematic(&self) -> bool {
+        self.cinematic.is_some()
+    }
+
+    /// Keep the view inside the provided area of the world.
+    pub fn set_bounds(&mut self, bounds: CameraBounds) {
+        self.bounds = Some(bounds);
+    }
+
+    /// Follow the provided point. The camera only moves once the point leaves the deadzone window.
+    pub fn follow(&mut self, point: Vec2) {
+        self.target.x = apply_deadzone(self.target.x, point.x, self.deadzone.x);
+        self.target.y = apply_deadzone(self.target.y, point.y, self.deadzone.y);
+    }
+
+    /// Look ahead in the direction the player is moving and towards where they are aiming.
+    /// `direction` is the horizontal movement, 0.0 keeps looking the way the player last moved.
+    /// `aim` is the aim offset from the center of the screen.
+    pub fn look(&mut self, direction: f32, aim: Vec2) {
+        if direction != 0.0 {
+            self.facing = direction.signum();
+        }
+
+        self.look_target = Vec2::new(self.facing * self.look_ahead, 0.0) + aim * self.aim_look;
+    }
+
+    /// The point the camera is currently centered on.
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Smoothly zoom to `target` over `duration` seconds. A zoom of 2.0 makes everything twice as big.
+    /// Calling this again with the same target does not restart the zoom.
+    pub fn set_zoom(&mut self, target: f32, duration: f32) {
+        let target = target.clamp(Zoom::MIN, Zoom::MAX);
+
+        if (target - self.zoom.to).abs() > f32::EPSILON {
+            self.zoom = Zoom {
+                from: self.zoom.current(),
+                to: target,
+                elapsed: 0.0,
+                duration,
+            };
+        }
+    }
+
+    /// The current zoom level.
+    pub fn zoom(&self) -> f32 {
+        self.zoom.current()
+    }
+
+    /// The zoom level the camera is currently zooming towards.
+    pub fn zoom_target(&self) -> f32 {
+        self.zoom.to
+    }
+
+    /// The transformation that applies the current zoom around the center of the screen.
+    /// Push it before drawing the world and pop it before drawing the UI.
+    pub fn zoom_transform(&self, screen_width: f32, screen_height: f32) -> Matrix4<f32> {
+        let zoom = self.zoom.current();
+        let center = Vector3::new(screen_width / 2.0, screen_height / 2.0, 0.0);
+
+        Matrix4::new_translation(&center)
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(zoom, zoom, 1.0))
+            * Matrix4::new_translation(&-center)
+    }
+
+    /// Move the camera towards its target and apply the result to the provided camera.
+    pub fn update(&mut self, camera: &mut Camera, screen_size: Vec2, rng: &mut impl Rng, dt: f32) {
+        self.look_offset.x = damp(
+            self.look_offset.x,
+            self.look_target.x,
+            Self::LOOK_SMOOTHING,
+            dt,
+        );
+        self.look_offset.y = damp(
+            self.look_offset.y,
+            self.look_target.y,
+            Self::LOOK_SMOOTHING,
+            dt,
+        );
+
+        let target = self.target + self.look_offset;
+
+        if let Some((path, start)) = &mut self.cinematic {
+            path.elapsed += dt;
+            self.position = path.sample(*start, target);
+
+            if path.is_finished() {
+                self.cinematic = None;
+            }
+        } else {
+            self.position.x = damp(self.position.x, target.x, self.smoothing.x, dt);
+            self.position.y = damp(self.position.y, target.y, self.smoothing.y, dt);
+        }
+
+        self.shake.update(dt);
+        self.zoom.elapsed += dt;
+
+        if let Some(bounds) = &self.bounds {
+            // The part of the world that is visible shrinks as we zoom in.
+            let half_view = screen_size / (2.0 * self.zoom.current());
+
+            self.position.x =
+                clamp_to_bounds(self.position.x, half_view.x, bounds.left, bounds.right);
+            self.position.y =
+                clamp_to_bounds(self.position.y, half_view.y, bounds.top, bounds.bottom);
+        }
+
+        camera.move_to(self.position + self.shake.offset(rng));
+    }
+}
+
+/// A list of waypoints the camera pans along, used for level intros and reveals.
+/// The camera eases from one waypoint to the next, holding still for a moment at each of them.
+pub struct CameraPath {
+    waypoints: Vec<Vec2>,
+
+    /// Time it takes to pan from one waypoint to the next.
+    leg_duration: f32,
+    /// Time the camera holds still at each waypoint.
+    hold: f32,
+
+    elapsed: f32,
+}
+
+impl CameraPath {
+    pub fn new(waypoints: Vec<Vec2>, leg_duration: f32, hold: f32) -> Self {
+        Self {
+            waypoints,
+            leg_duration,
+            hold,
+            elapsed: 0.0,
+        }
+    }
+
+    /// The amount of legs the path has, including the ones from `start` and back to `end`.
+    fn legs(&self) -> usize {
+        self.waypoints.len() + 1
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.legs() as f32 * (self.leg_duration + self.hold)
+    }
+
+    /// The position along the path, starting at `start` and ending at `end`.
+    fn sample(&self, start: Vec2, end: Vec2) -> Vec2 {
+        let mut points = vec![start];
+        points.extend_from_slice(&self.waypoints);
+        points.push(end);
+
+        let leg_time = self.leg_duration + self.hold;
+        let leg = ((self.elapsed / leg_time) as usize).min(self.legs() - 1);
+
+        let t = smoothstep((self.elapsed - leg as f32 * leg_time) / self.leg_duration);
+
+        points[leg] + (points[leg + 1] - points[leg]) * t
+    }
+}
+
+/// The edges of the world the camera should not scroll past.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraBounds {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+/// An eased transition between two zoom levels.
+struct Zoom {
+    from: f32,
+    to: f32,
+
+    elapsed: f32,
+    duration: f32,
+}
+
+impl Zoom {
+    /// The furthest the camera can zoom out.
+    const MIN: f32 = 0.25;
+    /// The furthest the camera can zoom in.
+    const MAX: f32 = 4.0;
+
+    fn new(zoom: f32) -> Self {
+        Self {
+            from: zoom,
+            to: zoom,
+            elapsed: 0.0,
+            duration: 0.0,
+        }
+    }
+
+    fn current(&self) -> f32 {
+        if self.elapsed >= self.duration {
+            self.to
+        } else {
+            lerp(self.from, self.to, smoothstep(self.elapsed / self.duration))
+        }
+    }
+}
+
+/// Trauma based screen shake. Anything can add trauma to it, which then decays over time.
+/// The actual shake is the square of the trauma, so small bumps are subtle while big explosions really rock.
+pub struct ScreenShake {
+    trauma: f32,
+
+    /// Global multiplier for the shake. 0.0 turns screen shake off completely.
+    pub intensity: f32,
+}
+
+impl ScreenShake {
+    /// Trauma caused by something big, like an exploding barrel.
+    pub const HEAVY: f32 = 0.6;
+    /// Trauma caused by something medium sized, like an enemy getting blown up.
+    pub const MEDIUM: f32 = 0.4;
+    /// Trauma caused by something small, like shooting a gun.
+    pub const LIGHT: f32 = 0.1;
+
+    /// The maximum distance the camera can be offset by.
+    const MAX_OFFSET: f32 = 20.0;
+    /// The amount of trauma removed every second.
+    const DECAY: f32 = 1.5;
+
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    /// Add some trauma. The total trauma is capped at 1.0.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.trauma = (self.trauma - Self::DECAY * dt).max(0.0);
+    }
+
+    /// The offset that should be added to the camera position this frame.
+    pub fn offset(&self, rng: &mut impl Rng) -> Vec2 {
+        let shake = self.trauma * self.trauma * self.intensity;
+
+        if shake <= 0.0 {
+            return Vec2::new(0.0, 0.0);
+        }
+
+        Vec2::new(
+            rng.gen_range(-1.0..=1.0) * Self::MAX_OFFSET * shake,
+            rng.gen_range(-1.0..=1.0) * Self::MAX_OFFSET * shake,
+        )
+    }
+}
+
+/// Drags `center` along with `point` so that `point` never ends up further than `half_size` away from it.
+fn apply_deadzone(center: f32, point: f32, half_size: f32) -> f32 {
+    let offset = point - center;
+
+    if offset > half_size {
+        point - half_size
+    } else if offset < -half_size {
+        point + half_size
+    } else {
+        center
+    }
+}
+
+/// Keeps the view around `center` within `min` and `max`.
+/// If the view is bigger than the bounds, it gets centered on the bounds instead.
+fn clamp_to_bounds(center: f32, half_view: f32, min: f32, max: f32) -> f32 {
+    if max - min < half_view * 2.0 {
+        (min + max) / 2.0
+    } else {
+        center.clamp(min + half_view, max - half_view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadzone() {
+        // Inside the window the camera stays put.
+        assert_eq!(apply_deadzone(0.0, 30.0, 60.0), 0.0);
+        assert_eq!(apply_deadzone(0.0, -60.0, 60.0), 0.0);
+
+        // Outside of it the window gets dragged along with the point.
+        assert_eq!(apply_deadzone(0.0, 100.0, 60.0), 40.0);
+        assert_eq!(apply_deadzone(0.0, -100.0, 60.0), -40.0);
+    }
+
+    #[test]
+    fn test_camera_path() {
+        let mut path = CameraPath::new(vec![Vec2::new(100.0, 0.0)], 1.0, 0.5);
+        let start = Vec2::new(0.0, 0.0);
+        let end = Vec2::new(0.0, 50.0);
+
+        assert_eq!(path.sample(start, end), start);
+
+        // Halfway through the first leg.
+        path.elapsed = 0.5;
+        assert_eq!(path.sample(start, end), Vec2::new(50.0, 0.0));
+
+        // Holding still at the waypoint.
+        path.elapsed = 1.25;
+        assert_eq!(path.sample(start, end), Vec2::new(100.0, 0.0));
+
+        // Back to the end point.
+        path.elapsed = 2.5;
+        assert_eq!(path.sample(start, end), end);
+        assert!(!path.is_finished());
+
+        path.elapsed = 3.0;
+        assert!(path.is_finished());
+    }
+
+    #[test]
+    fn test_clamp_to_bounds() {
+        assert_eq!(clamp_to_bounds(500.0, 100.0, 0.0, 1000.0), 500.0);
+        assert_eq!(clamp_to_bounds(50.0, 100.0, 0.0, 1000.0), 100.0);
+        assert_eq!(clamp_to_bounds(990.0, 100.0, 0.0, 1000.0), 900.0);
+
+        // A level smaller than the screen stays centered.
+        assert_eq!(clamp_to_bounds(50.0, 500.0, 0.0, 600.0), 300.0);
+
+        // Unbounded edges never clamp.
+        assert_eq!(
+            clamp_to_bounds(-1e6, 100.0, f32::NEG_INFINITY, 1000.0),
+            -1e6
+        );
+    }
+}
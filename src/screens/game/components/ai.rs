@@ -0,0 +1,68 @@
+use nphysics2d::nalgebra as na;
+
+/// Goes after the crabs that come close, and keeps after them for a while once they're gone.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Ai {
+    /// How close a crab has to get to be noticed.
+    range: f32,
+    /// How long a crab out of range is still gone after.
+    memory: f32,
+    /// How much longer the crabs are gone after.
+    aggro: f32,
+}
+
+impl Ai {
+    pub fn new(range: f32, memory: f32) -> Self {
+        Self {
+            range,
+            memory,
+            aggro: 0.0,
+        }
+    }
+
+    /// Look out for the `targets` from `position`.
+    pub fn update(&mut self, position: na::Point2<f32>, targets: &[na::Point2<f32>], dt: f32) {
+        self.aggro = (self.aggro - dt).max(0.0);
+
+        if targets
+            .iter()
+            .any(|target| na::distance(&position, target) < self.range)
+        {
+            self.provoke();
+        }
+    }
+
+    /// Go after the crabs, wherever they are, like after getting shot by one.
+    pub fn provoke(&mut self) {
+        self.aggro = self.memory;
+    }
+
+    pub fn is_aggroed(&self) -> bool {
+        self.aggro > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ai() {
+        let mut ai = Ai::new(100.0, 2.0);
+
+        ai.update(na::Point2::origin(), &[na::Point2::new(150.0, 0.0)], 0.1);
+        assert!(!ai.is_aggroed());
+
+        ai.update(na::Point2::origin(), &[na::Point2::new(50.0, 0.0)], 0.1);
+        assert!(ai.is_aggroed());
+
+        // It keeps after the crab for a while once it's out of range.
+        ai.update(na::Point2::origin(), &[], 1.5);
+        assert!(ai.is_aggroed());
+        ai.update(na::Point2::origin(), &[], 1.0);
+        assert!(!ai.is_aggroed());
+
+        ai.provoke();
+        assert!(ai.is_aggroed());
+    }
+}
@@ -0,0 +1,119 @@
+/// The turbofish ammo the player is carrying, split up into the loaded magazine and the reserve.
+#[derive(Debug, Clone, Copy)]
+pub struct Ammo {
+    /// Rounds in the magazine, ready to be shot.
+    pub magazine: u32,
+    /// Rounds carried around to reload the magazine with.
+    pub reserve: u32,
+
+    /// Time spent reloading so far, `None` while not reloading.
+    reloading: Option<f32>,
+}
+
+impl Ammo {
+    /// The amount of rounds that fit in a magazine.
+    pub const MAGAZINE_SIZE: u32 = 5;
+    /// The most rounds the player can keep in reserve.
+    pub const MAX_RESERVE: u32 = 20;
+    /// How long a reload takes in seconds.
+    pub const RELOAD_TIME: f32 = 1.2;
+    /// The magazine counts as low from this amount of rounds and below.
+    pub const LOW_AMMO: u32 = 1;
+
+    pub fn new(magazine: u32, reserve: u32) -> Self {
+        Self {
+            magazine: magazine.min(Self::MAGAZINE_SIZE),
+            reserve: reserve.min(Self::MAX_RESERVE),
+
+            reloading: None,
+        }
+    }
+
+    pub fn can_shoot(&self) -> bool {
+        self.magazine > 0 && self.reloading.is_none()
+    }
+
+    /// Take a round out of the magazine. Returns false if there was nothing to shoot.
+    pub fn use_round(&mut self) -> bool {
+        if !self.can_shoot() {
+            return false;
+        }
+
+        self.magazine -= 1;
+
+        // Reload right away once the magazine runs dry.
+        if self.magazine == 0 {
+            self.reload();
+        }
+
+        true
+    }
+
+    /// Start reloading, if there is anything to reload with.
+    pub fn reload(&mut self) {
+        if self.reloading.is_none() && self.magazine < Self::MAGAZINE_SIZE && self.reserve > 0 {
+            self.reloading = Some(0.0);
+        }
+    }
+
+    /// Put some rounds in the reserve.
+    pub fn add(&mut self, amount: u32) {
+        self.reserve = (self.reserve + amount).min(Self::MAX_RESERVE);
+
+        if self.magazine == 0 {
+            self.reload();
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(elapsed) = self.reloading {
+            let elapsed = elapsed + dt;
+
+            if elapsed >= Self::RELOAD_TIME {
+                let rounds = (Self::MAGAZINE_SIZE - self.magazine).min(self.reserve);
+
+                self.magazine += rounds;
+                self.reserve -= rounds;
+                self.reloading = None;
+            } else {
+                self.reloading = Some(elapsed);
+            }
+        }
+    }
+
+    /// How far along the reload is between 0.0 and 1.0, `None` while not reloading.
+    pub fn reload_progress(&self) -> Option<f32> {
+        self.reloading
+            .map(|elapsed| (elapsed / Self::RELOAD_TIME).min(1.0))
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.magazine <= Self::LOW_AMMO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload() {
+        let mut ammo = Ammo::new(1, 3);
+
+        assert!(ammo.use_round());
+
+        // Running dry starts a reload, no shooting until it's done.
+        assert!(!ammo.can_shoot());
+        assert_eq!(ammo.reload_progress(), Some(0.0));
+
+        ammo.update(Ammo::RELOAD_TIME);
+
+        assert_eq!(ammo.magazine, 3);
+        assert_eq!(ammo.reserve, 0);
+        assert_eq!(ammo.reload_progress(), None);
+
+        // Nothing left to reload with.
+        ammo.reload();
+        assert_eq!(ammo.reload_progress(), None);
+    }
+}
@@ -1,120 +1,184 @@
-use ggez::{audio::SoundSource, graphics, nalgebra::Point2, Context, GameResult};
-use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
-use graphics::DrawParam;
+use ggez::Context;
+use ggez_goodies::nalgebra_glm::Vec2;
+use rand::Rng;
 
 use crate::{
-    game::physics::{isometry_to_point, Physics},
-    play,
+    audio::Sfx,
+    display,
+    error::FerrisResult,
+    game::animation::SpriteSheet,
+    game::entities::EntityId,
+    game::objects::Objects,
+    game::particles::{Emitter, EmitterConfig, Particles},
+    game::physics::Physics,
     utils::{AssetManager, ParticleSystem},
 };
 
 use nphysics2d::{nalgebra as na, object::DefaultBodyHandle};
 
-use super::{bullet::PlayerWeapon, player::Player};
+use super::{bullet::PlayerWeapon, player::Player, sprite::Sprite};
 
 pub struct Barrel {
     body: DefaultBodyHandle,
+    /// Where the barrel is kept in the component store.
+    entity: EntityId,
+
+    /// Time left until the barrel goes boom after being caught in another explosion.
+    fuse: Option<f32>,
+    /// How many barrels in a row have gone off, including this one.
+    chain: u32,
+    /// The flames coming out of the barrel while its fuse burns.
+    fire: Emitter,
 }
 
 impl Barrel {
+    /// Anything within this distance of the barrel gets caught in the explosion.
+    pub const EXPLOSION_RADIUS: f32 = 250.0;
+    /// The damage dealt to things caught in the explosion.
+    pub const EXPLOSION_DAMAGE: f32 = 3.0;
+    /// The damage dealt to the player when caught in the explosion.
+    pub const PLAYER_DAMAGE: f32 = 30.0;
+    /// The delay between a barrel getting caught in an explosion and going off itself.
+    const FUSE_TIME: f32 = 0.15;
+
     pub fn new(
         ctx: &mut Context,
         pos_x: f32,
         physics: &mut Physics,
         asset_manager: &AssetManager,
     ) -> Self {
-        let (_, height) = graphics::drawable_size(ctx);
+        let (_, height) = display::screen_size(ctx);
 
-        let barrel = asset_manager.get_image("Some(barrel).png");
+        let sheet = SpriteSheet::new(asset_manager.get_sprite("Some(barrel).png"), 1, 1);
+        let (barrel_width, barrel_height) = sheet.frame_size();
 
-        let body = physics.create_barrel(
+        let (body, entity) = physics.create_barrel(
             na::Point2::new(pos_x, height / 2.0 - 155.0),
-            barrel.width(),
-            barrel.height(),
+            barrel_width as u16,
+            barrel_height as u16,
         );
 
-        Self { body }
-    }
-
-    pub fn draw(
-        &mut self,
-        ctx: &mut Context,
-        camera: &Camera,
-        physics: &mut Physics,
-        asset_manager: &AssetManager,
-    ) -> GameResult<()> {
-        let barrel = asset_manager.get_image("Some(barrel).png");
-
-        let barrel_position = self.position(physics);
-        let barrel_pos_camera =
-            camera.calculate_dest_point(Vec2::new(barrel_position.x, barrel_position.y));
+        physics
+            .entities_mut()
+            .insert_sprite(entity, Sprite::sheet(sheet, 1.0));
 
-        graphics::draw(
-            ctx,
-            &barrel,
-            DrawParam::default()
-                .dest(Point2::new(barrel_pos_camera.x, barrel_pos_camera.y))
-                .offset(Point2::new(0.5, 0.5)),
-        )?;
+        Self {
+            body,
+            entity,
 
-        Ok(())
+            fuse: None,
+            chain: 1,
+            fire: Emitter::new(&EmitterConfig::FIRE),
+        }
     }
 
+    /// Returns true if the barrel exploded, either from getting shot or from its fuse running out.
     pub fn update(
         &mut self,
         physics: &mut Physics,
         asset_manager: &AssetManager,
-        particles: &mut Vec<ParticleSystem>,
-        player: &mut Player,
+        objects: &mut Objects,
+        players: &mut [&mut Player],
+        rng: &mut impl Rng,
+        dt: f32,
     ) -> bool {
         let barrel = asset_manager.get_image("Some(barrel).png");
 
-        let position = self.position(physics);
-
-        for i in 0..player.weapons.len() {
-            match &mut player.weapons[i] {
-                PlayerWeapon::Turbofish(fish) => {
-                    if fish.is_touching(physics, self.handle()) {
-                        let explode_sound = asset_manager.get_sound("Some(explode).mp3");
-
-                        // FIXME
-                        particles.push(ParticleSystem::new(
-                            physics,
-                            100,
-                            na::Point2::new(
-                                position.x - (barrel.width() / 2) as f32,
-                                position.y - (barrel.height() / 2) as f32,
-                            ),
-                            na::Point2::new(
-                                position.x + (barrel.width() / 2) as f32,
-                                position.y + (barrel.height() / 2) as f32,
-                            ),
-                        ));
-
-                        play!(explode_sound);
-
-                        // Remove the enemy from the world
-                        self.destroy(physics);
-
-                        // Remove the weapon from the world
-                        fish.destroy(physics);
-                        player.weapons.remove(i);
-
-                        return true;
+        // The game drops barrels that are gone from the world.
+        let position = match self.position(physics) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+        let mut exploded = false;
+
+        for player in players.iter_mut() {
+            for i in 0..player.weapons.len() {
+                match &mut player.weapons[i] {
+                    PlayerWeapon::Turbofish(fish) => {
+                        if fish.is_touching(physics, self.handle()) {
+                            // Remove the weapon from the world
+                            fish.destroy(physics);
+                            player.weapons.remove(i);
+
+                            exploded = true;
+
+                            break;
+                        }
                     }
+                    PlayerWeapon::Grappling(_) => {}
                 }
-                PlayerWeapon::Grappling(_) => {}
             }
         }
 
-        false
+        if let Some(fuse) = &mut self.fuse {
+            *fuse -= dt;
+
+            if *fuse <= 0.0 {
+                exploded = true;
+            }
+        }
+
+        if exploded {
+            // FIXME
+            objects.spawn(ParticleSystem::new(
+                physics,
+                100,
+                na::Point2::new(
+                    position.x - (barrel.width() / 2) as f32,
+                    position.y - (barrel.height() / 2) as f32,
+                ),
+                na::Point2::new(
+                    position.x + (barrel.width() / 2) as f32,
+                    position.y + (barrel.height() / 2) as f32,
+                ),
+                rng,
+            ));
+
+            asset_manager.audio().play_at(Sfx::Explosion, position);
+
+            // Remove the barrel from the world
+            self.destroy(physics);
+        }
+
+        exploded
     }
 
-    pub fn position(&self, physics: &mut Physics) -> na::Point2<f32> {
-        let barrel_body = physics.get_rigid_body_mut(self.body);
-        let barrel_position = isometry_to_point(barrel_body.position());
+    /// Light the fuse of the barrel after it got caught in the explosion of the `chain`th barrel.
+    pub fn ignite(&mut self, chain: u32) {
+        if self.fuse.is_none() {
+            self.fuse = Some(Self::FUSE_TIME);
+            self.chain = chain + 1;
+        }
+    }
+
+    /// Keep the barrel burning while its fuse is lit.
+    pub fn burn(
+        &mut self,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+        effects: &mut Particles,
+        rng: &mut impl Rng,
+        dt: f32,
+    ) {
+        if let (Some(_), Ok(position)) = (self.fuse, self.position(physics)) {
+            let barrel = asset_manager.get_image("Some(barrel).png");
+
+            self.fire.emit(
+                effects,
+                Vec2::new(position.x, position.y - (barrel.height() / 2) as f32),
+                rng,
+                dt,
+            );
+        }
+    }
+
+    pub fn chain(&self) -> u32 {
+        self.chain
+    }
 
-        barrel_position
+    /// Where the barrel is, as the transform system last saw it.
+    pub fn position(&self, physics: &mut Physics) -> FerrisResult<na::Point2<f32>> {
+        Ok(physics.entities().transform(self.entity)?.position)
     }
 
     pub fn handle(&self) -> DefaultBodyHandle {
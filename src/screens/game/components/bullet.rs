@@ -6,9 +6,13 @@ use ggez::{
 use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
 
 use nphysics2d::{algebra::Velocity2, math::Velocity, nalgebra as na, object::DefaultBodyHandle};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::physics::{isometry_to_point, ObjectData, Physics},
+    error::FerrisResult,
+    game::entities::EntityId,
+    game::physics::{ObjectData, Physics},
+    game::trail::{Trail, TrailStyle},
     utils::AssetManager,
 };
 
@@ -17,6 +21,7 @@ pub enum PlayerWeapon {
     Grappling(Grappling),
 }
 
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum WeaponType {
     Turbofish,
     Grappling,
@@ -24,6 +29,9 @@ pub enum WeaponType {
 
 pub struct Turbofish {
     body: DefaultBodyHandle,
+    /// Where the bullet is kept in the component store.
+    entity: EntityId,
+    trail: Trail,
 }
 
 impl Turbofish {
@@ -34,16 +42,21 @@ impl Turbofish {
         asset_manager: &AssetManager,
     ) -> Self {
         let turbofish_bullet = asset_manager.get_image("Some(turbofish).png");
-        let body = physics.create_bullet(
+        let (body, entity) = physics.create_bullet(
             na::Point2::new(pos_x, pos_y),
             turbofish_bullet.width(),
             turbofish_bullet.height(),
         );
 
-        let bullet_body = physics.get_rigid_body_mut(body);
-        bullet_body.set_velocity(Velocity2::linear(1000.0, 0.0));
+        if let Ok(bullet_body) = physics.rigid_body_mut(body) {
+            bullet_body.set_velocity(Velocity2::linear(1000.0, 0.0));
+        }
 
-        Self { body }
+        Self {
+            body,
+            entity,
+            trail: Trail::new(TrailStyle::BULLET),
+        }
     }
 
     pub fn draw(
@@ -55,10 +68,15 @@ impl Turbofish {
     ) -> GameResult<()> {
         let turbofish_bullet = asset_manager.get_image("Some(turbofish).png");
 
-        let bullet_position = self.position(physics);
+        let bullet_position = match self.position(physics) {
+            Ok(position) => position,
+            Err(_) => return Ok(()),
+        };
         let turbofish_position =
             camera.calculate_dest_point(Vec2::new(bullet_position.x, bullet_position.y));
 
+        self.trail.draw(ctx, camera)?;
+
         graphics::draw(
             ctx,
             &turbofish_bullet,
@@ -70,9 +88,18 @@ impl Turbofish {
         Ok(())
     }
 
-    pub fn update(&mut self, physics: &mut Physics) -> bool {
+    /// Returns true once the bullet hits the ground, or once it's gone from the world already.
+    pub fn update(&mut self, physics: &mut Physics, dt: f32) -> bool {
+        let position = match self.position(physics) {
+            Ok(position) => position,
+            Err(_) => return true,
+        };
+
+        self.trail.update(dt);
+        self.trail.push(Vec2::new(position.x, position.y));
+
         for collision in physics.collisions(self.body) {
-            if collision.0 .1 == ObjectData::Ground {
+            if matches!(collision.0 .1, ObjectData::Ground(_)) {
                 return true;
             }
         }
@@ -90,15 +117,37 @@ impl Turbofish {
         false
     }
 
+    /// The enemy the bullet ran into, if it ran into one.
+    pub fn hit_enemy(&self, physics: &mut Physics) -> Option<EntityId> {
+        let body = physics
+            .collisions(self.body)
+            .into_iter()
+            .find(|collision| collision.0 .1 == ObjectData::Enemy)
+            .map(|collision| collision.1)?;
+
+        physics.entities().entity(body)
+    }
+
+    /// The crab out of `crabs` the bullet ran into, if it ran into one.
+    pub fn hit_crab(
+        &self,
+        physics: &mut Physics,
+        crabs: &[DefaultBodyHandle],
+    ) -> Option<DefaultBodyHandle> {
+        physics
+            .collisions(self.body)
+            .into_iter()
+            .find(|collision| collision.0 .1 == ObjectData::Player && crabs.contains(&collision.1))
+            .map(|collision| collision.1)
+    }
+
     pub fn destroy(&mut self, physics: &mut Physics) {
         physics.destroy_body(self.body);
     }
 
-    pub fn position(&self, physics: &mut Physics) -> na::Point2<f32> {
-        let bullet_body = physics.get_rigid_body_mut(self.body);
-        let bullet_position = isometry_to_point(bullet_body.position());
-
-        bullet_position
+    /// Where the bullet is, as the transform system last saw it.
+    pub fn position(&self, physics: &mut Physics) -> FerrisResult<na::Point2<f32>> {
+        Ok(physics.entities().transform(self.entity)?.position)
     }
 }
 
@@ -120,10 +169,11 @@ impl Grappling {
             for object in ray_cast {
                 if object.0 == ObjectData::Barrel {
                     let body = object.1.body();
-                    let body_pos = isometry_to_point(physics.get_rigid_body(body).position());
+                    let body_pos = physics.body_position(body).ok()?;
 
                     physics
-                        .get_rigid_body_mut(body)
+                        .rigid_body_mut(body)
+                        .ok()?
                         .set_velocity(Velocity::linear(pos_x - body_pos.x, pos_y - body_pos.y));
 
                     return Some(Self {
@@ -145,17 +195,21 @@ impl Grappling {
         camera: &Camera,
         physics: &mut Physics,
     ) -> GameResult<()> {
-        let player = isometry_to_point(physics.get_rigid_body(self.player_body).position());
+        let player = match physics.body_position(self.player_body) {
+            Ok(player) => player,
+            Err(_) => return Ok(()),
+        };
+
+        // The barrel is already gone, the next update lets go of it.
+        let length = match physics.distance(self.player_body, self.grapple_to) {
+            Ok(length) => length,
+            Err(_) => return Ok(()),
+        };
 
         let rect = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                0.0,
-                0.0,
-                physics.distance(self.player_body, self.grapple_to),
-                10.0,
-            ),
+            graphics::Rect::new(0.0, 0.0, length, 10.0),
             [1.0, 1.0, 1.0, 1.0].into(),
         )?;
 
@@ -170,14 +224,17 @@ impl Grappling {
         Ok(())
     }
 
-    pub fn update(&mut self, physics: &mut Physics) {
-        let player = isometry_to_point(physics.get_rigid_body(self.player_body).position());
-        let object = isometry_to_point(physics.get_rigid_body(self.grapple_to).position());
+    /// Pull the barrel in. Fails once the barrel has blown up and isn't there to pull any more.
+    pub fn update(&mut self, physics: &mut Physics) -> FerrisResult<()> {
+        let player = physics.body_position(self.player_body)?;
+        let object = physics.body_position(self.grapple_to)?;
 
-        if physics.distance(self.player_body, self.grapple_to) as i32 > 1 {
+        if physics.distance(self.player_body, self.grapple_to)? as i32 > 1 {
             physics
-                .get_rigid_body_mut(self.grapple_to)
+                .rigid_body_mut(self.grapple_to)?
                 .set_velocity(Velocity::linear(player.x - object.x, player.y - object.y));
         }
+
+        Ok(())
     }
 }
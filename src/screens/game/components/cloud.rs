@@ -1,14 +1,14 @@
 use ggez::{
     graphics,
     mint::{Point2, Vector2},
-    Context, GameResult,
+    Context,
 };
 use graphics::DrawParam;
 
-use crate::utils::AssetManager;
-
 use nphysics2d::nalgebra as na;
 
+use crate::display;
+
 pub struct Cloud {
     position: na::Point2<f32>,
 
@@ -27,32 +27,23 @@ impl Cloud {
         }
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, asset_manager: &AssetManager) -> GameResult<()> {
-        let cloud = asset_manager.get_image("Some(cloud).png");
-
-        graphics::draw(
-            ctx,
-            &cloud,
-            DrawParam::default()
-                .scale(Vector2 {
-                    x: self.scale,
-                    y: self.scale,
-                })
-                .dest(Point2 {
-                    x: self.position.x,
-                    y: self.position.y,
-                }),
-        )?;
-
-        Ok(())
+    /// How to draw the cloud into a batch.
+    pub fn sprite(&self) -> DrawParam {
+        DrawParam::default()
+            .scale(Vector2 {
+                x: self.scale,
+                y: self.scale,
+            })
+            .dest(Point2 {
+                x: self.position.x,
+                y: self.position.y,
+            })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) {
-        let (width, _) = graphics::drawable_size(ctx);
-
-        let delta_time = ggez::timer::delta(ctx).as_secs_f32();
+    pub fn update(&mut self, ctx: &mut Context, dt: f32) {
+        let (width, _) = display::screen_size(ctx);
 
-        self.position.x += delta_time * self.speed;
+        self.position.x += dt * self.speed;
 
         if self.position.x > width + 100. {
             self.position = na::Point2::new(-100., self.position.y);
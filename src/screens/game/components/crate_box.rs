@@ -0,0 +1,329 @@
+use ggez::{
+    graphics::{self, Color, DrawParam, Mesh},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use rand::Rng;
+
+use nphysics2d::{
+    algebra::Velocity2,
+    nalgebra as na,
+    ncollide2d::shape::{Cuboid, ShapeHandle},
+    object::{BodyPartHandle, ColliderDesc, DefaultBodyHandle, RigidBodyDesc},
+};
+
+use crate::{
+    display,
+    error::FerrisResult,
+    game::entities::EntityId,
+    game::objects::{GameObject, Objects},
+    game::physics::{isometry_to_point, point_to_isometry, ObjectData, Physics},
+    palette::Palette,
+    utils::AssetManager,
+};
+
+use super::{
+    bullet::PlayerWeapon,
+    health::Health,
+    pickup::{Pickup, PickupKind},
+    player::Player,
+    sprite::Sprite,
+};
+
+/// The colour of the crate planks.
+const CRATE_COLOR: Color = Color {
+    r: 156.0 / 255.0,
+    g: 102.0 / 255.0,
+    b: 51.0 / 255.0,
+    a: 1.0,
+};
+
+/// The colour of the crate frame.
+const CRATE_FRAME_COLOR: Color = Color {
+    r: 99.0 / 255.0,
+    g: 62.0 / 255.0,
+    b: 28.0 / 255.0,
+    a: 1.0,
+};
+
+/// A breakable wooden crate that drops a pickup when it's destroyed.
+pub struct Crate {
+    body: DefaultBodyHandle,
+    /// Where the crate and its health are kept in the component store.
+    entity: EntityId,
+
+    /// `None` means the crate will drop a random pickup.
+    contents: Option<PickupKind>,
+}
+
+impl Crate {
+    const SIZE: f32 = 56.0;
+    const HEALTH: f32 = 3.0;
+
+    /// Amount of damage a turbofish does to the crate.
+    const BULLET_DAMAGE: f32 = 1.0;
+
+    pub fn new(
+        ctx: &mut Context,
+        pos_x: f32,
+        physics: &mut Physics,
+        contents: Option<PickupKind>,
+    ) -> Self {
+        let (_, height) = display::screen_size(ctx);
+
+        let (body, entity) = physics.create_crate(
+            na::Point2::new(pos_x, height / 2.0 - 155.0),
+            Self::SIZE as u16,
+            Self::SIZE as u16,
+        );
+        let entities = physics.entities_mut();
+
+        entities.insert_health(entity, Health::new(Self::HEALTH));
+        entities.insert_sprite(entity, Sprite::shape(Self::mesh, Self::SIZE / 2.0));
+
+        Self {
+            body,
+            entity,
+            contents,
+        }
+    }
+
+    /// What the crate looks like, around its middle.
+    fn mesh(ctx: &mut Context, _palette: &Palette) -> GameResult<Mesh> {
+        let half = Self::SIZE / 2.0;
+
+        graphics::MeshBuilder::new()
+            .rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(-half, -half, Self::SIZE, Self::SIZE),
+                CRATE_COLOR,
+            )
+            .rectangle(
+                graphics::DrawMode::stroke(4.0),
+                graphics::Rect::new(-half, -half, Self::SIZE, Self::SIZE),
+                CRATE_FRAME_COLOR,
+            )
+            .line(
+                &[Point2::new(-half, -half), Point2::new(half, half)],
+                4.0,
+                CRATE_FRAME_COLOR,
+            )?
+            .line(
+                &[Point2::new(half, -half), Point2::new(-half, half)],
+                4.0,
+                CRATE_FRAME_COLOR,
+            )?
+            .build(ctx)
+    }
+
+    /// Checks if any of the players' bullets hit the crate. Returns true if the crate is broken.
+    pub fn update(&mut self, physics: &mut Physics, players: &mut [&mut Player]) -> bool {
+        for player in players.iter_mut() {
+            for i in 0..player.weapons.len() {
+                match &mut player.weapons[i] {
+                    PlayerWeapon::Turbofish(fish) => {
+                        if fish.is_touching(physics, self.handle()) {
+                            self.damage(physics, Self::BULLET_DAMAGE);
+
+                            // Remove the weapon from the world
+                            fish.destroy(physics);
+                            player.weapons.remove(i);
+
+                            break;
+                        }
+                    }
+                    PlayerWeapon::Grappling(_) => {}
+                }
+            }
+        }
+
+        self.is_broken(physics)
+    }
+
+    /// Deal damage to the crate. Can be called by anything that hurts, like bullets or explosions.
+    pub fn damage(&mut self, physics: &mut Physics, amount: f32) {
+        let entities = physics.entities_mut();
+        let wear = match entities.health_mut(self.entity) {
+            Ok(health) => {
+                health.damage(amount);
+
+                health.fraction()
+            }
+            Err(_) => return,
+        };
+
+        // Show some cracks by darkening the crate as it takes damage.
+        if let Ok(sprite) = entities.sprite_mut(self.entity) {
+            let shade = 0.5 + wear * 0.5;
+
+            sprite.color = Color::new(shade, shade, shade, 1.0);
+        }
+    }
+
+    /// Whether the crate is broken, which a crate gone from the world is too.
+    pub fn is_broken(&self, physics: &Physics) -> bool {
+        physics
+            .entities()
+            .health(self.entity)
+            .map_or(true, Health::is_dead)
+    }
+
+    /// Smash the crate into debris and return the pickup that was inside. Fails for a crate that's
+    /// gone from the world already.
+    pub fn break_apart(
+        &self,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+        objects: &mut Objects,
+        rng: &mut impl Rng,
+        ammo: u32,
+    ) -> FerrisResult<Pickup> {
+        let position = self.position(physics)?;
+        let half = Self::SIZE / 2.0;
+
+        objects.spawn(Debris::new(
+            physics,
+            6,
+            na::Point2::new(position.x - half, position.y - half),
+            na::Point2::new(position.x + half, position.y + half),
+            rng,
+        ));
+
+        self.destroy(physics);
+
+        Ok(Pickup::new(
+            position.x,
+            position.y,
+            self.contents
+                .unwrap_or_else(|| PickupKind::random(rng, ammo)),
+            physics,
+            asset_manager,
+        ))
+    }
+
+    /// Where the crate is, as the transform system last saw it.
+    pub fn position(&self, physics: &mut Physics) -> FerrisResult<na::Point2<f32>> {
+        Ok(physics.entities().transform(self.entity)?.position)
+    }
+
+    pub fn handle(&self) -> DefaultBodyHandle {
+        self.body
+    }
+
+    pub fn destroy(&self, physics: &mut Physics) {
+        physics.destroy_body(self.body);
+    }
+}
+
+/// A few broken planks flying around after a crate has been smashed.
+pub struct Debris {
+    pieces: Vec<DefaultBodyHandle>,
+    lifetime: f32,
+}
+
+impl Debris {
+    const DEBRIS_JUICE: f32 = 250.0;
+    const PIECE_WIDTH: f32 = 20.0;
+    const PIECE_HEIGHT: f32 = 6.0;
+
+    pub fn new(
+        physics: &mut Physics,
+        amount: usize,
+        min: na::Point2<f32>,
+        max: na::Point2<f32>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut pieces = vec![];
+
+        for _ in 0..amount {
+            let position =
+                na::Point2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y));
+            let mut body = RigidBodyDesc::new()
+                .mass(1.0)
+                .angular_inertia(1.0)
+                .position(point_to_isometry(position))
+                .build();
+
+            body.set_velocity(Velocity2::new(
+                na::Vector2::new(
+                    rng.gen_range(-Self::DEBRIS_JUICE..=Self::DEBRIS_JUICE),
+                    -rng.gen_range(0.0..=Self::DEBRIS_JUICE),
+                ),
+                rng.gen_range(-10.0..=10.0),
+            ));
+
+            let handle = physics.create_rigid_body(body);
+            let shape = ShapeHandle::new(Cuboid::new(na::Vector2::new(
+                Self::PIECE_WIDTH / 2.0,
+                Self::PIECE_HEIGHT / 2.0,
+            )));
+            let collider = ColliderDesc::new(shape)
+                .user_data(ObjectData::Debris)
+                .build(BodyPartHandle(handle, 0));
+
+            physics.create_collider(collider);
+
+            pieces.push(handle);
+        }
+
+        Self {
+            pieces,
+            lifetime: 1.5,
+        }
+    }
+}
+
+impl GameObject for Debris {
+    fn draw(&self, ctx: &mut Context, physics: &mut Physics, camera: &Camera) -> GameResult {
+        let color = Color::new(CRATE_COLOR.r, CRATE_COLOR.g, CRATE_COLOR.b, self.lifetime);
+
+        let piece_mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                -Self::PIECE_WIDTH / 2.0,
+                -Self::PIECE_HEIGHT / 2.0,
+                Self::PIECE_WIDTH,
+                Self::PIECE_HEIGHT,
+            ),
+            color,
+        )?;
+
+        for piece in &self.pieces {
+            let body = match physics.rigid_body(*piece) {
+                Ok(body) => body,
+                Err(_) => continue,
+            };
+            let position = isometry_to_point(body.position());
+            let rotation = body.position().rotation.angle();
+
+            let camera_pos = camera.calculate_dest_point(Vec2::new(position.x, position.y));
+
+            graphics::draw(
+                ctx,
+                &piece_mesh,
+                DrawParam::default()
+                    .dest(Point2::new(camera_pos.x, camera_pos.y))
+                    .rotation(rotation),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, _physics: &mut Physics, dt: f32) {
+        self.lifetime -= dt;
+    }
+
+    /// Whether the debris has faded away.
+    fn is_dead(&self) -> bool {
+        self.lifetime <= 0.0
+    }
+
+    fn destroy(&mut self, physics: &mut Physics) {
+        for piece in &self.pieces {
+            physics.destroy_body(*piece);
+        }
+    }
+}
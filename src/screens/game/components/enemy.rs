@@ -1,139 +1,291 @@
-use ggez::{
-    audio::SoundSource,
-    graphics::{self, DrawParam},
-    nalgebra::Point2,
-    Context, GameResult,
-};
-use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use ggez::{graphics, Context};
+use rand::Rng;
 
 use nphysics2d::{nalgebra as na, object::DefaultBodyHandle};
 
 use crate::{
-    game::physics::{isometry_to_point, Physics},
-    play,
+    audio::Sfx,
+    display,
+    error::FerrisResult,
+    game::animation::SpriteSheet,
+    game::entities::EntityId,
+    game::objects::Objects,
+    game::physics::Physics,
+    game::text_effects::TextEffects,
+    game::tint::HitFlash,
     utils::{AssetManager, ParticleSystem},
 };
 
-use super::{bullet::PlayerWeapon, player::Player};
+use super::{ai::Ai, health::Health, player::Player, sprite::Sprite, weapon::Weapon};
 
 pub struct Enemy {
     body: DefaultBodyHandle,
+    /// Where the enemy and its components are kept in the component store.
+    entity: EntityId,
+    /// The name of the enemy if it's a boss.
+    boss: Option<&'static str>,
+    /// How big the enemy is.
+    size: na::Vector2<f32>,
+
+    /// Whether the last hit the enemy took was a headshot.
+    headshot: bool,
+    /// How many times the enemy got hit.
+    hits: u32,
 }
 
 impl Enemy {
+    const HEALTH: f32 = 100.0;
+
+    /// The health of a boss.
+    const BOSS_HEALTH: f32 = 400.0;
+    /// How big a boss is compared to a normal gopher.
+    const BOSS_SCALE: f32 = 1.5;
+    /// The amount of phases a boss fight is split into.
+    pub const BOSS_PHASES: u32 = 3;
+
+    /// The range of damage a turbofish does to the enemy.
+    const BULLET_DAMAGE: (f32, f32) = (50.0, 70.0);
+    /// The chance of a turbofish landing a critical hit. Headshots are always critical.
+    const CRITICAL_CHANCE: f64 = 0.25;
+    /// Critical hits hurt this many times more.
+    const CRITICAL_MULTIPLIER: f32 = 2.0;
+
+    /// The enemy goes after the player once they get this close.
+    const AGGRO_RANGE: f32 = 800.0;
+    /// How long the enemy stays after the player once they are out of range.
+    const AGGRO_TIME: f32 = 5.0;
+    /// Where the gopher holds its gun.
+    const HOLD: (f32, f32) = (-50.0, 10.0);
+
     pub fn new(
         ctx: &mut Context,
         pos_x: f32,
         physics: &mut Physics,
         asset_manager: &AssetManager,
     ) -> Self {
-        let (_, height) = graphics::drawable_size(ctx);
+        Self::spawn(ctx, pos_x, physics, asset_manager, None)
+    }
 
-        let gopher = asset_manager.get_image("gopher.png");
+    /// Create a bigger, tougher gopher that gets its own health bar.
+    pub fn new_boss(
+        ctx: &mut Context,
+        pos_x: f32,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+        name: &'static str,
+    ) -> Self {
+        Self::spawn(ctx, pos_x, physics, asset_manager, Some(name))
+    }
 
-        let body = physics.create_enemy(
-            na::Point2::new(pos_x, height / 2.0 - 155.0),
-            gopher.width(),
-            gopher.height(),
-        );
+    /// Make the enemy `factor` times as tough, before it got hurt.
+    pub fn toughen(&mut self, physics: &mut Physics, factor: f32) {
+        let entities = physics.entities_mut();
 
-        Self { body }
+        if let Ok(health) = entities.health(self.entity).copied() {
+            entities.insert_health(self.entity, Health::new(health.max() * factor));
+        }
     }
 
-    pub fn draw(
-        &mut self,
+    fn spawn(
         ctx: &mut Context,
-        camera: &Camera,
+        pos_x: f32,
         physics: &mut Physics,
         asset_manager: &AssetManager,
-    ) -> GameResult<()> {
-        let gopher = asset_manager.get_image("gopher.png");
-        let gun = asset_manager.get_image("Some(gun).png");
-
-        let enemy_position = self.position(physics);
-        let gopher_position =
-            camera.calculate_dest_point(Vec2::new(enemy_position.x, enemy_position.y));
-
-        graphics::draw(
-            ctx,
-            &gopher,
-            DrawParam::default()
-                .dest(Point2::new(gopher_position.x, gopher_position.y))
-                .offset(Point2::new(0.5, 0.5)),
-        )?;
-
-        graphics::draw(
-            ctx,
-            &gun,
-            DrawParam::default()
-                .dest(Point2::new(
-                    gopher_position.x - 50.0,
-                    gopher_position.y + 10.0,
-                ))
-                .offset(Point2::new(0.5, 0.5)),
-        )?;
-
-        Ok(())
+        boss: Option<&'static str>,
+    ) -> Self {
+        let (_, height) = display::screen_size(ctx);
+
+        let sheet = SpriteSheet::new(asset_manager.get_sprite("gopher.png"), 1, 1);
+        let (gopher_width, gopher_height) = sheet.frame_size();
+
+        let (health, scale) = if boss.is_some() {
+            (Self::BOSS_HEALTH, Self::BOSS_SCALE)
+        } else {
+            (Self::HEALTH, 1.0)
+        };
+
+        let (body, entity) = physics.create_enemy(
+            na::Point2::new(pos_x, height / 2.0 - 155.0),
+            (gopher_width * scale) as u16,
+            (gopher_height * scale) as u16,
+        );
+
+        let entities = physics.entities_mut();
+        let (x, y) = Self::HOLD;
+
+        entities.insert_health(entity, Health::new(health));
+        entities.insert_sprite(entity, Sprite::sheet(sheet, scale));
+        entities.insert_weapon(
+            entity,
+            Weapon::new(
+                asset_manager.get_sprite("Some(gun).png"),
+                na::Vector2::new(x, y),
+            ),
+        );
+        entities.insert_ai(entity, Ai::new(Self::AGGRO_RANGE, Self::AGGRO_TIME));
+
+        Self {
+            body,
+            entity,
+            boss,
+            size: na::Vector2::new(gopher_width, gopher_height) * scale,
+
+            headshot: false,
+            hits: 0,
+        }
     }
 
+    /// Keep the enemy going, hurting it if a bullet hit it at `hit` this step. Returns true once
+    /// the enemy is dead.
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         physics: &mut Physics,
         asset_manager: &AssetManager,
-        particles: &mut Vec<ParticleSystem>,
+        objects: &mut Objects,
+        text_effects: &mut TextEffects,
         player: &mut Player,
+        hit: Option<na::Point2<f32>>,
+        rng: &mut impl Rng,
     ) -> bool {
-        let position = self.position(physics);
-
-        let gopher = asset_manager.get_image("gopher.png");
-        let explode_sound = asset_manager.get_sound("Some(explode).mp3");
-
-        for i in 0..player.weapons.len() {
-            match &mut player.weapons[i] {
-                PlayerWeapon::Turbofish(fish) => {
-                    if fish.is_touching(physics, self.handle()) {
-                        particles.push(ParticleSystem::new(
-                            physics,
-                            50,
-                            na::Point2::new(
-                                position.x - (gopher.width() / 2) as f32,
-                                position.y - (gopher.height() / 2) as f32,
-                            ),
-                            na::Point2::new(
-                                position.x + (gopher.width() / 2) as f32,
-                                position.y + (gopher.height() / 2) as f32,
-                            ),
-                        ));
-
-                        play!(explode_sound);
-
-                        // Remove the enemy from the world
-                        self.destroy(physics);
-
-                        // Remove the weapon from the world
-                        fish.destroy(physics);
-                        player.weapons.remove(i);
-
-                        return true;
-                    }
-                }
-                PlayerWeapon::Grappling(_) => {}
+        // The game drops enemies that are gone from the world.
+        let position = match self.position(physics) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+
+        let (gopher_width, gopher_height) = (self.size.x, self.size.y);
+
+        if let Some(hit_position) = hit {
+            // Anything hitting the top quarter of the gopher counts as a headshot.
+            self.headshot = hit_position.y < position.y - gopher_height / 4.0;
+
+            let critical = self.headshot || rng.gen_bool(Self::CRITICAL_CHANCE);
+
+            let mut damage = rng.gen_range(Self::BULLET_DAMAGE.0..=Self::BULLET_DAMAGE.1);
+
+            if critical {
+                damage *= Self::CRITICAL_MULTIPLIER;
+            }
+
+            let entities = physics.entities_mut();
+            let health = match entities.health_mut(self.entity) {
+                Ok(health) => health,
+                Err(_) => return false,
+            };
+
+            health.damage(damage);
+
+            let dead = health.is_dead();
+
+            if let Ok(sprite) = entities.sprite_mut(self.entity) {
+                sprite.flash.hit(HitFlash::HIT);
+            }
+
+            if let Ok(ai) = entities.ai_mut(self.entity) {
+                ai.provoke();
+            }
+
+            self.hits += 1;
+
+            if critical {
+                text_effects.spawn(
+                    format!("{:.0}!", damage),
+                    hit_position,
+                    text_effects.palette().critical,
+                    30.0,
+                );
+            } else {
+                text_effects.spawn(
+                    format!("{:.0}", damage),
+                    hit_position,
+                    graphics::WHITE,
+                    20.0,
+                );
+            }
+
+            if !dead {
+                asset_manager.audio().play_at(Sfx::Hit, position);
+            } else {
+                objects.spawn(ParticleSystem::new(
+                    physics,
+                    50,
+                    na::Point2::new(
+                        position.x - gopher_width / 2.0,
+                        position.y - gopher_height / 2.0,
+                    ),
+                    na::Point2::new(
+                        position.x + gopher_width / 2.0,
+                        position.y + gopher_height / 2.0,
+                    ),
+                    rng,
+                ));
+
+                asset_manager.audio().play_at(Sfx::Explosion, position);
+
+                // Remove the enemy from the world
+                self.destroy(physics);
+
+                return true;
             }
         }
 
         // Can the enemy see the player?
-        if physics.distance(self.handle(), player.handle()) < 300.0 {
+        if matches!(physics.distance(self.handle(), player.handle()), Ok(distance) if distance < 300.0)
+        {
             // TODO: The enemy shoots the player as soon as it sees the player.
         }
 
         false
     }
 
-    pub fn position(&self, physics: &mut Physics) -> na::Point2<f32> {
-        let enemy_body = physics.get_rigid_body_mut(self.body);
-        let enemy_position = isometry_to_point(enemy_body.position());
+    /// Whether the last hit the enemy took was a headshot.
+    pub fn headshot(&self) -> bool {
+        self.headshot
+    }
+
+    /// How many times the enemy got hit.
+    pub fn hits(&self) -> u32 {
+        self.hits
+    }
+
+    /// The name of the enemy if it's a boss.
+    pub fn boss(&self) -> Option<&'static str> {
+        self.boss
+    }
+
+    /// The health of the enemy, as long as it's still around.
+    pub fn health(&self, physics: &Physics) -> FerrisResult<Health> {
+        physics.entities().health(self.entity).copied()
+    }
+
+    /// Go along with the health the enemy has somewhere else, if it took more damage there.
+    pub fn sync_health(&mut self, physics: &mut Physics, health: f32) {
+        if let Ok(current) = physics.entities_mut().health_mut(self.entity) {
+            let lost = current.current() - health;
+
+            if lost > 0.0 {
+                current.damage(lost);
+            }
+        }
+    }
+
+    /// The phase of the boss fight the enemy is in, starting at 1. Every phase takes an equal
+    /// share of the health.
+    pub fn phase(&self, physics: &Physics) -> u32 {
+        let lost = 1.0 - self.health(physics).map_or(0.0, |health| health.fraction());
+
+        ((lost * Self::BOSS_PHASES as f32) as u32 + 1).min(Self::BOSS_PHASES)
+    }
+
+    /// How far the sprite of the enemy reaches around its position.
+    pub fn radius(&self) -> f32 {
+        self.size.max() / 2.0
+    }
 
-        enemy_position
+    /// Where the enemy is, as the transform system last saw it.
+    pub fn position(&self, physics: &mut Physics) -> FerrisResult<na::Point2<f32>> {
+        Ok(physics.entities().transform(self.entity)?.position)
     }
 
     pub fn handle(&mut self) -> DefaultBodyHandle {
@@ -0,0 +1,126 @@
+use ggez::{
+    graphics::{self, Color, DrawParam},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use nphysics2d::{nalgebra as na, object::DefaultBodyHandle};
+
+use crate::game::{
+    physics::{Physics, Surface},
+    prompts::Prompt,
+};
+
+/// The colour of a shut gate.
+const GATE_COLOR: Color = Color {
+    r: 150.0 / 255.0,
+    g: 170.0 / 255.0,
+    b: 190.0 / 255.0,
+    a: 1.0,
+};
+
+/// A wall of the tutorial that stays shut until the player did what the prompt before it teaches.
+pub struct Gate {
+    /// The wall, `None` once the gate is open.
+    body: Option<DefaultBodyHandle>,
+    /// The middle of the wall.
+    position: na::Point2<f32>,
+    /// What the gate waits on.
+    prompt: Prompt,
+    /// Where the prompt gets triggered. Doing what it teaches before getting there doesn't count.
+    trigger: f32,
+    /// How far the gate has faded away, between 0.0 and 1.0.
+    opened: f32,
+}
+
+impl Gate {
+    pub const WIDTH: f32 = 40.0;
+    /// Tall enough that the jetpack can't go over it.
+    pub const HEIGHT: f32 = 2000.0;
+    /// How long the gate takes to fade away once it opens.
+    const FADE_TIME: f32 = 0.5;
+
+    /// Put up a gate standing on the ground at `floor`, waiting on the prompt triggered at
+    /// `trigger`.
+    pub fn new(
+        floor: na::Point2<f32>,
+        physics: &mut Physics,
+        prompt: Prompt,
+        trigger: f32,
+    ) -> Self {
+        let position = na::Point2::new(floor.x, floor.y - Self::HEIGHT / 2.0);
+        let body = physics.create_tile(
+            position,
+            Self::WIDTH as u16,
+            Self::HEIGHT as u16,
+            Surface::Metal,
+        );
+
+        Self {
+            body: Some(body),
+            position,
+            prompt,
+            trigger,
+            opened: 0.0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.body.is_none()
+    }
+
+    pub fn prompt(&self) -> Prompt {
+        self.prompt
+    }
+
+    /// Whether doing what the prompt teaches with the player at `x` opens the gate.
+    pub fn is_waiting(&self, x: f32) -> bool {
+        !self.is_open() && x >= self.trigger
+    }
+
+    /// Open the gate, taking the wall out of the way.
+    pub fn open(&mut self, physics: &mut Physics) {
+        if let Some(body) = self.body.take() {
+            physics.destroy_body(body);
+        }
+    }
+
+    pub fn position(&self) -> na::Point2<f32> {
+        self.position
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.is_open() {
+            self.opened = (self.opened + dt / Self::FADE_TIME).min(1.0);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera) -> GameResult<()> {
+        if self.opened >= 1.0 {
+            return Ok(());
+        }
+
+        let position = camera.calculate_dest_point(Vec2::new(self.position.x, self.position.y));
+        let color = Color {
+            a: 1.0 - self.opened,
+            ..GATE_COLOR
+        };
+        let mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                -Self::WIDTH / 2.0,
+                -Self::HEIGHT / 2.0,
+                Self::WIDTH,
+                Self::HEIGHT,
+            ),
+            color,
+        )?;
+
+        graphics::draw(
+            ctx,
+            &mesh,
+            DrawParam::default().dest(Point2::new(position.x, position.y)),
+        )
+    }
+}
@@ -1,6 +1,14 @@
+pub mod ai;
+pub mod ammo;
 pub mod barrel;
 pub mod bullet;
 pub mod cloud;
+pub mod crate_box;
 pub mod enemy;
+pub mod gate;
+pub mod health;
+pub mod pickup;
 pub mod player;
+pub mod sprite;
 pub mod tile;
+pub mod weapon;
@@ -0,0 +1,159 @@
+use ggez::{
+    graphics::{self, Mesh},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use rand::Rng;
+
+use nphysics2d::{algebra::Velocity2, nalgebra as na, object::DefaultBodyHandle};
+
+use crate::{
+    error::FerrisResult,
+    game::animation::SpriteSheet,
+    game::entities::EntityId,
+    game::physics::{ObjectData, Physics},
+    palette::Palette,
+    utils::AssetManager,
+};
+
+use super::{player::Player, sprite::Sprite};
+
+/// The different kinds of goodies that can be picked up by the player.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PickupKind {
+    Ammo,
+    Health,
+    /// Slows everything but the player's reflexes down for a while.
+    BulletTime,
+}
+
+impl PickupKind {
+    /// Pick a random pickup kind, ammo `ammo` times out of ten.
+    pub fn random(rng: &mut impl Rng, ammo: u32) -> Self {
+        // Bullet time is the rarest of the bunch.
+        match rng.gen_range(0..10) {
+            0 => PickupKind::BulletTime,
+            roll if roll <= ammo => PickupKind::Ammo,
+            _ => PickupKind::Health,
+        }
+    }
+}
+
+pub struct Pickup {
+    pub kind: PickupKind,
+
+    body: DefaultBodyHandle,
+    /// Where the pickup is kept in the component store.
+    entity: EntityId,
+}
+
+impl Pickup {
+    /// The size of the health and bullet time pickups. The ammo pickup uses the size of its
+    /// sprite.
+    const HEALTH_SIZE: f32 = 24.0;
+    /// The amount of ammo an ammo pickup gives.
+    pub const AMMO_AMOUNT: u32 = 5;
+    /// The amount of health a health pickup gives.
+    pub const HEALTH_AMOUNT: f32 = 25.0;
+    /// The upwards velocity the pickup pops out with.
+    const POP_JUICE: f32 = 150.0;
+
+    pub fn new(
+        pos_x: f32,
+        pos_y: f32,
+        kind: PickupKind,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+    ) -> Self {
+        let ((width, height), sprite) = match kind {
+            PickupKind::Ammo => {
+                let sheet = SpriteSheet::new(asset_manager.get_sprite("Some(ammo).png"), 1, 1);
+
+                (sheet.frame_size(), Sprite::sheet(sheet, 1.0))
+            }
+            PickupKind::Health => (
+                (Self::HEALTH_SIZE, Self::HEALTH_SIZE),
+                Sprite::shape(Self::cross, Self::HEALTH_SIZE / 2.0),
+            ),
+            PickupKind::BulletTime => (
+                (Self::HEALTH_SIZE, Self::HEALTH_SIZE),
+                Sprite::shape(Self::clock, Self::HEALTH_SIZE / 2.0),
+            ),
+        };
+
+        let (body, entity) =
+            physics.create_pickup(na::Point2::new(pos_x, pos_y), width as u16, height as u16);
+
+        physics.entities_mut().insert_sprite(entity, sprite);
+
+        if let Ok(pickup_body) = physics.rigid_body_mut(body) {
+            pickup_body.set_velocity(Velocity2::linear(0.0, -Self::POP_JUICE));
+        }
+
+        Self { kind, body, entity }
+    }
+
+    /// A cross for the health pickup.
+    fn cross(ctx: &mut Context, palette: &Palette) -> GameResult<Mesh> {
+        let size = Self::HEALTH_SIZE;
+        let bar = size / 3.0;
+
+        graphics::MeshBuilder::new()
+            .rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(-size / 2.0, -bar / 2.0, size, bar),
+                palette.health,
+            )
+            .rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(-bar / 2.0, -size / 2.0, bar, size),
+                palette.health,
+            )
+            .build(ctx)
+    }
+
+    /// A clock face with its hands for the bullet time pickup.
+    fn clock(ctx: &mut Context, palette: &Palette) -> GameResult<Mesh> {
+        let radius = Self::HEALTH_SIZE / 2.0;
+        let color = palette.bullet_time;
+
+        graphics::MeshBuilder::new()
+            .circle(
+                graphics::DrawMode::stroke(3.0),
+                Point2::new(0.0, 0.0),
+                radius,
+                0.5,
+                color,
+            )
+            .line(
+                &[
+                    Point2::new(0.0, -radius * 0.6),
+                    Point2::new(0.0, 0.0),
+                    Point2::new(radius * 0.45, 0.0),
+                ],
+                2.5,
+                color,
+            )?
+            .build(ctx)
+    }
+
+    /// Returns true if the player has touched the pickup.
+    pub fn update(&mut self, physics: &mut Physics, player: &Player) -> bool {
+        for collision in physics.collisions(self.body) {
+            if collision.0 .1 == ObjectData::Player && collision.1 == player.handle() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Where the pickup is, as the transform system last saw it.
+    pub fn position(&self, physics: &mut Physics) -> FerrisResult<na::Point2<f32>> {
+        Ok(physics.entities().transform(self.entity)?.position)
+    }
+
+    pub fn destroy(&self, physics: &mut Physics) {
+        physics.destroy_body(self.body);
+    }
+}
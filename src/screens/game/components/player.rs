@@ -1,15 +1,28 @@
-use ggez::{event::KeyCode, graphics, input::keyboard, nalgebra::Point2, Context, GameResult};
+use ggez::graphics::DrawParam;
+use ggez::{nalgebra::Point2, Context, GameResult};
 use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
-use graphics::DrawParam;
-use nphysics2d::object::DefaultBodyHandle;
+use nphysics2d::object::{Body, BodyStatus, DefaultBodyHandle};
 use nphysics2d::{algebra::Velocity2, nalgebra as na};
 
 use crate::{
-    game::physics::{isometry_to_point, point_to_isometry, Physics},
+    bindings::Action,
+    display,
+    error::FerrisResult,
+    game::animation::{Animation, Animator, SpriteSheet},
+    game::entities::EntityId,
+    game::input::InputState,
+    game::physics::{isometry_to_point, point_to_isometry, ObjectData, Physics, Surface},
+    game::tint::{HitFlash, TintShader},
+    game::trail::{Trail, TrailStyle},
     utils::AssetManager,
 };
 
-use super::bullet::{Grappling, PlayerWeapon, Turbofish, WeaponType};
+use super::{
+    ammo::Ammo,
+    bullet::{Grappling, PlayerWeapon, Turbofish, WeaponType},
+    health::Health,
+    weapon::Weapon,
+};
 
 pub enum Direction {
     Left,
@@ -18,18 +31,50 @@ pub enum Direction {
 }
 
 pub struct Player {
-    pub ammo: f32,
-    pub health: i32,
+    pub ammo: Ammo,
 
     direction: Direction,
 
     body: DefaultBodyHandle,
+    /// Where the player, their health and their gun are kept in the component store.
+    entity: EntityId,
+    /// Where the player was last seen.
+    position: na::Point2<f32>,
     pub weapons: Vec<PlayerWeapon>,
+    /// Nothing hurts the player.
+    pub invincible: bool,
+    /// The player flies around through everything.
+    noclip: bool,
+
+    sheet: SpriteSheet,
+    animator: Animator,
+    flash: HitFlash,
+    /// Streaks behind the player while going fast.
+    trail: Trail,
 }
 
 impl Player {
-    const SHIFT_JUICE: f32 = 10.0;
-    const JUMP_JUICE: f32 = 20.0;
+    /// Where the sniper is held.
+    const HOLD: (f32, f32) = (30.0, 15.0);
+
+    /// How fast moving and jumping speed the player up, per second.
+    const SHIFT_JUICE: f32 = 600.0;
+    const JUMP_JUICE: f32 = 1200.0;
+
+    /// The health the player starts out with.
+    pub const MAX_HEALTH: f32 = 100.0;
+    /// The rounds the player starts out with in reserve.
+    const STARTING_RESERVE: u32 = 5;
+
+    /// How fast the player flies around without clipping.
+    const NOCLIP_SPEED: f32 = 500.0;
+
+    /// How far the player runs between two footsteps.
+    const STRIDE: f32 = 70.0;
+    /// Anything slower than this is standing still.
+    const WALK_SPEED: f32 = 20.0;
+    /// The player leaves a trail while going faster than this, like when flying with the jetpack.
+    const TRAIL_SPEED: f32 = 450.0;
 
     pub fn new(
         ctx: &mut Context,
@@ -37,26 +82,55 @@ impl Player {
         physics: &mut Physics,
         asset_manager: &AssetManager,
     ) -> Self {
-        let (_, height) = graphics::drawable_size(ctx);
+        let (_, height) = display::screen_size(ctx);
+
+        let sheet = SpriteSheet::new(asset_manager.get_sprite("Some(ferris).png"), 1, 1);
+        let (ferris_width, ferris_height) = sheet.frame_size();
 
-        let ferris = asset_manager.get_image("Some(ferris).png");
+        let position = na::Point2::new(pos_x, height / 2.0 - 155.);
+        let (body, entity) =
+            physics.create_player(position, ferris_width as u16, ferris_height as u16);
 
-        let body = physics.create_player(
-            na::Point2::new(pos_x, height / 2.0 - 155.),
-            ferris.width(),
-            ferris.height(),
+        let entities = physics.entities_mut();
+
+        entities.insert_health(entity, Health::new(Self::MAX_HEALTH));
+        entities.insert_weapon(
+            entity,
+            Weapon::new(
+                asset_manager.get_sprite("Some(sniper).png"),
+                na::Vector2::new(Self::HOLD.0, Self::HOLD.1),
+            ),
         );
 
         let weapons = vec![];
 
         Self {
-            ammo: 10.0,
-            health: 100,
+            ammo: Ammo::new(Ammo::MAGAZINE_SIZE, Self::STARTING_RESERVE),
 
             direction: Direction::None,
 
             body,
+            entity,
+            position,
             weapons,
+            invincible: false,
+            noclip: false,
+
+            sheet,
+            // The run animation goes by strides rather than seconds. The first step after standing
+            // still comes quickly.
+            animator: Animator::new(vec![
+                ("idle", Animation::new(true).frame(0, 1.0)),
+                (
+                    "run",
+                    Animation::new(true)
+                        .frame(0, 0.5)
+                        .frame(0, 0.5)
+                        .event("step"),
+                ),
+            ]),
+            flash: HitFlash::new(),
+            trail: Trail::new(TrailStyle::PLAYER),
         }
     }
 
@@ -70,33 +144,28 @@ impl Player {
         camera: &Camera,
         physics: &mut Physics,
         asset_manager: &AssetManager,
+        tint: &TintShader,
     ) -> GameResult<()> {
-        let ferris = asset_manager.get_image("Some(ferris).png");
-        let turbofish_sniper = asset_manager.get_image("Some(sniper).png");
-
         let player_position = self.position(physics);
         let ferris_position =
             camera.calculate_dest_point(Vec2::new(player_position.x, player_position.y));
 
+        self.trail.draw(ctx, camera)?;
+
         // Draw the player
-        graphics::draw(
-            ctx,
-            &ferris,
-            DrawParam::default()
-                .dest(Point2::new(ferris_position.x, ferris_position.y))
-                .offset(Point2::new(0.5, 0.5)),
-        )?;
-
-        graphics::draw(
-            ctx,
-            &turbofish_sniper,
-            DrawParam::default()
-                .dest(Point2::new(
-                    ferris_position.x + 30.0,
-                    ferris_position.y + 15.0,
-                ))
-                .offset(Point2::new(0.5, 0.5)),
-        )?;
+        tint.draw(ctx, &self.flash, |ctx| {
+            self.sheet.draw(
+                ctx,
+                self.animator.frame(),
+                DrawParam::default()
+                    .dest(Point2::new(ferris_position.x, ferris_position.y))
+                    .offset(Point2::new(0.5, 0.5)),
+            )
+        })?;
+
+        if let Ok(weapon) = physics.entities().weapon(self.entity) {
+            weapon.draw(ctx, Point2::new(ferris_position.x, ferris_position.y), 1.0)?;
+        }
 
         // Draw the player weapon
         for weapon in &mut self.weapons {
@@ -113,42 +182,254 @@ impl Player {
         Ok(())
     }
 
+    /// Play the animation that fits what the player is doing. Returns the events of the frames
+    /// that were gotten to, like `"step"` whenever a foot touches the ground.
+    pub fn animate(&mut self, physics: &mut Physics, dt: f32) -> Vec<&'static str> {
+        self.flash.update(dt);
+        self.trail.update(dt);
+
+        if self.velocity(physics).norm() > Self::TRAIL_SPEED {
+            let position = self.position(physics);
+
+            self.trail.push(Vec2::new(position.x, position.y));
+        }
+
+        let speed = self.velocity(physics).x.abs();
+
+        if self.ground(physics).is_some() && speed > Self::WALK_SPEED {
+            self.animator.play("run");
+            self.animator.update(speed / Self::STRIDE * dt)
+        } else {
+            self.animator.play("idle");
+            self.animator.update(dt)
+        }
+    }
+
+    /// The health of the player, as kept in the component store.
+    pub fn health(&self, physics: &Physics) -> FerrisResult<Health> {
+        physics.entities().health(self.entity).copied()
+    }
+
+    /// Start over with `health`.
+    pub fn set_health(&mut self, physics: &mut Physics, health: Health) {
+        physics.entities_mut().insert_health(self.entity, health);
+    }
+
+    pub fn heal(&mut self, physics: &mut Physics, amount: f32) {
+        if let Ok(health) = physics.entities_mut().health_mut(self.entity) {
+            health.heal(amount);
+        }
+    }
+
+    /// Get hurt by something, which flashes the player.
+    pub fn damage(&mut self, physics: &mut Physics, amount: f32) {
+        if self.invincible {
+            return;
+        }
+
+        if let Ok(health) = physics.entities_mut().health_mut(self.entity) {
+            health.damage(amount);
+        }
+
+        self.flash.hit(HitFlash::HURT);
+    }
+
+    pub fn is_noclip(&self) -> bool {
+        self.noclip
+    }
+
+    /// Let the player fly through everything, or go back to bumping into things. Gravity and
+    /// whatever is in the way leave the player alone in the meantime.
+    pub fn set_noclip(&mut self, physics: &mut Physics, noclip: bool) {
+        if let Ok(body) = physics.rigid_body_mut(self.body) {
+            body.set_status(if noclip {
+                BodyStatus::Kinematic
+            } else {
+                BodyStatus::Dynamic
+            });
+            body.set_velocity(Velocity2::zero());
+        }
+
+        self.noclip = noclip;
+    }
+
+    /// Fly around without clipping, going up while jumping and sinking otherwise.
+    fn fly(&mut self, physics: &mut Physics, input: &InputState) {
+        let horizontal = match (input.is_held(Action::Left), input.is_held(Action::Right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let vertical = if input.is_held(Action::Jump) {
+            -1.0
+        } else {
+            0.5
+        };
+
+        if let Ok(body) = physics.rigid_body_mut(self.body) {
+            body.set_velocity(Velocity2::linear(
+                horizontal * Self::NOCLIP_SPEED,
+                vertical * Self::NOCLIP_SPEED,
+            ));
+        }
+    }
+
     pub fn init(&mut self, physics: &mut Physics) {
-        let player_body = physics.get_rigid_body_mut(self.body);
+        let player_body = match physics.rigid_body_mut(self.body) {
+            Ok(body) => body,
+            Err(_) => return,
+        };
         let player_position = isometry_to_point(player_body.position());
 
         let updated_position =
             point_to_isometry(na::Point2::new(player_position.x, player_position.y - 40.0));
 
         player_body.set_position(updated_position);
+        physics.sync_transforms();
+    }
+
+    /// Bring the player back to life at `position` with full health.
+    pub fn respawn(&mut self, physics: &mut Physics, position: na::Point2<f32>) {
+        self.place(physics, position);
+
+        let max = self
+            .health(physics)
+            .map_or(Self::MAX_HEALTH, |health| health.max());
+
+        self.set_health(physics, Health::new(max));
     }
 
-    pub fn update(&mut self, ctx: &mut Context, physics: &mut Physics) {
-        if keyboard::is_key_pressed(ctx, KeyCode::Left) {
-            self.shift(physics, Direction::Left);
+    /// Put the player down at `position`, standing still.
+    pub fn place(&mut self, physics: &mut Physics, position: na::Point2<f32>) {
+        if let Ok(player_body) = physics.rigid_body_mut(self.body) {
+            player_body.set_position(point_to_isometry(position));
+            player_body.set_velocity(Velocity2::zero());
+            physics.sync_transforms();
+        }
+    }
+
+    /// Where the player's bullets in the air are.
+    pub fn bullet_positions(&self, physics: &mut Physics) -> Vec<na::Point2<f32>> {
+        self.weapons
+            .iter()
+            .filter_map(|weapon| match weapon {
+                PlayerWeapon::Turbofish(fish) => fish.position(physics).ok(),
+                PlayerWeapon::Grappling(_) => None,
+            })
+            .collect()
+    }
+
+    /// Take the bullets that ran into enemies out of the world. Returns the enemies they hit, with
+    /// where they hit them.
+    pub fn bullet_hits(&mut self, physics: &mut Physics) -> Vec<(EntityId, na::Point2<f32>)> {
+        let mut hits = vec![];
+
+        self.weapons.retain_mut(|weapon| {
+            let fish = match weapon {
+                PlayerWeapon::Turbofish(fish) => fish,
+                PlayerWeapon::Grappling(_) => return true,
+            };
+
+            match fish.hit_enemy(physics) {
+                Some(enemy) => {
+                    if let Ok(position) = fish.position(physics) {
+                        hits.push((enemy, position));
+                    }
+
+                    fish.destroy(physics);
+
+                    false
+                }
+                None => true,
+            }
+        });
+
+        hits
+    }
+
+    /// The crabs out of `crabs` the player's bullets ran into, in versus. The bullets that hit go
+    /// away, and they never hit the player that fired them.
+    pub fn crab_hits(
+        &mut self,
+        physics: &mut Physics,
+        crabs: &[DefaultBodyHandle],
+    ) -> Vec<DefaultBodyHandle> {
+        let others: Vec<DefaultBodyHandle> = crabs
+            .iter()
+            .copied()
+            .filter(|crab| *crab != self.body)
+            .collect();
+        let mut hits = vec![];
+
+        self.weapons.retain_mut(|weapon| {
+            let fish = match weapon {
+                PlayerWeapon::Turbofish(fish) => fish,
+                PlayerWeapon::Grappling(_) => return true,
+            };
+
+            match fish.hit_crab(physics, &others) {
+                Some(crab) => {
+                    hits.push(crab);
+                    fish.destroy(physics);
+
+                    false
+                }
+                None => true,
+            }
+        });
+
+        hits
+    }
+
+    /// Move the player around and keep its weapons going. Returns where the player's bullets hit
+    /// the ground.
+    pub fn update(
+        &mut self,
+        physics: &mut Physics,
+        input: &InputState,
+        dt: f32,
+    ) -> Vec<na::Point2<f32>> {
+        let held = |action| input.is_held(action);
+
+        if self.noclip {
+            self.fly(physics, input);
+        } else if held(Action::Left) {
+            self.shift(physics, Direction::Left, dt);
             self.set_direction(Direction::Left);
-        } else if keyboard::is_key_pressed(ctx, KeyCode::Right) {
-            self.shift(physics, Direction::Right);
+        } else if held(Action::Right) {
+            self.shift(physics, Direction::Right, dt);
             self.set_direction(Direction::Right);
+        } else if input.just_released(Action::Left) || input.just_released(Action::Right) {
+            self.set_direction(Direction::None);
         }
 
         // We are not adding Space key pressed in an else if statement as we want to jump while we are also moving to a specific direction in the x axis.
-        if keyboard::is_key_pressed(ctx, KeyCode::Space) {
-            self.go_boom(physics);
+        if held(Action::Jump) && !self.noclip {
+            self.go_boom(physics, dt);
             self.set_direction(Direction::None);
         }
 
         // Same as the previous if statement. We want to shoot while moving and jumping around :)
-        if keyboard::is_key_pressed(ctx, KeyCode::S) {
+        if held(Action::Fire) {
             // TODO: Move the shoot logic from game struct to this if statement
         }
 
+        if held(Action::Reload) {
+            self.ammo.reload();
+        }
+
+        self.ammo.update(dt);
+
+        let mut impacts = vec![];
+
         for i in 0..self.weapons.len() {
             let weapon = &mut self.weapons[i];
 
             match weapon {
                 PlayerWeapon::Turbofish(fish) => {
-                    if fish.update(physics) {
+                    if fish.update(physics, dt) {
+                        // A bullet that vanished from the world doesn't leave an impact.
+                        impacts.extend(fish.position(physics).ok());
                         fish.destroy(physics);
                         self.weapons.remove(i);
 
@@ -156,15 +437,16 @@ impl Player {
                     }
                 }
                 PlayerWeapon::Grappling(grapple) => {
-                    if keyboard::is_key_pressed(ctx, KeyCode::S) {
-                        grapple.update(physics);
-                    } else {
+                    // Let go once the fire button is, or once the barrel is gone.
+                    if !held(Action::Fire) || grapple.update(physics).is_err() {
                         self.weapons.remove(i);
                         break;
                     }
                 }
             }
         }
+
+        impacts
     }
 
     pub fn shoot(
@@ -175,46 +457,74 @@ impl Player {
     ) -> Option<PlayerWeapon> {
         let player_position = self.position(physics);
 
-        if self.ammo > 0.0 {
-            match gun {
-                WeaponType::Turbofish => Some(PlayerWeapon::Turbofish(Turbofish::new(
-                    player_position.x + 140.0,
-                    player_position.y,
-                    physics,
-                    asset_manager,
-                ))),
-
-                WeaponType::Grappling => {
-                    let gun = Grappling::new(
+        match gun {
+            WeaponType::Turbofish => {
+                if self.ammo.use_round() {
+                    Some(PlayerWeapon::Turbofish(Turbofish::new(
                         player_position.x + 140.0,
                         player_position.y,
                         physics,
-                        self.handle(),
-                    );
-
-                    gun.map(PlayerWeapon::Grappling)
+                        asset_manager,
+                    )))
+                } else {
+                    None
                 }
             }
-        } else {
-            None
+
+            WeaponType::Grappling => {
+                let gun = Grappling::new(
+                    player_position.x + 140.0,
+                    player_position.y,
+                    physics,
+                    self.handle(),
+                );
+
+                gun.map(PlayerWeapon::Grappling)
+            }
         }
     }
 
+    /// Where the player is as the transform system last saw it, or was last seen should the body
+    /// have gone missing from the world.
     pub fn position(&mut self, physics: &mut Physics) -> na::Point2<f32> {
-        let player_body = physics.get_rigid_body_mut(self.body);
-        let player_position = isometry_to_point(player_body.position());
+        if let Ok(transform) = physics.entities().transform(self.entity) {
+            self.position = transform.position;
+        }
 
-        player_position
+        self.position
+    }
+
+    /// How fast the player is going, standing still without a body in the world.
+    pub fn velocity(&mut self, physics: &mut Physics) -> na::Vector2<f32> {
+        physics
+            .body_velocity(self.body)
+            .unwrap_or_else(|_| na::Vector2::zeros())
+    }
+
+    /// What the player is standing on, if anything.
+    pub fn ground(&self, physics: &mut Physics) -> Option<Surface> {
+        physics
+            .collisions(self.body)
+            .into_iter()
+            .find_map(|(objects, _, _)| match objects {
+                (ObjectData::Ground(surface), _) | (_, ObjectData::Ground(surface)) => {
+                    Some(surface)
+                }
+                _ => None,
+            })
     }
 
-    pub fn go_boom(&mut self, physics: &mut Physics) {
-        let player_body = physics.get_rigid_body_mut(self.body);
+    pub fn go_boom(&mut self, physics: &mut Physics, dt: f32) {
+        let player_body = match physics.rigid_body_mut(self.body) {
+            Ok(body) => body,
+            Err(_) => return,
+        };
         let player_velocity = player_body.velocity();
 
         let new_velocity = Velocity2::new(
             na::Vector2::new(
                 player_velocity.linear.x,
-                player_velocity.linear.y - Self::JUMP_JUICE,
+                player_velocity.linear.y - Self::JUMP_JUICE * dt,
             ),
             player_velocity.angular,
         );
@@ -222,15 +532,18 @@ impl Player {
         player_body.set_velocity(new_velocity);
     }
 
-    fn shift(&mut self, physics: &mut Physics, direction: Direction) {
-        let player_body = physics.get_rigid_body_mut(self.body);
+    fn shift(&mut self, physics: &mut Physics, direction: Direction, dt: f32) {
+        let player_body = match physics.rigid_body_mut(self.body) {
+            Ok(body) => body,
+            Err(_) => return,
+        };
         let player_velocity = player_body.velocity();
 
         match direction {
             Direction::Left => {
                 let new_velocity = Velocity2::new(
                     na::Vector2::new(
-                        player_velocity.linear.x - Self::SHIFT_JUICE,
+                        player_velocity.linear.x - Self::SHIFT_JUICE * dt,
                         player_velocity.linear.y,
                     ),
                     player_velocity.angular,
@@ -241,7 +554,7 @@ impl Player {
             Direction::Right => {
                 let new_velocity = Velocity2::new(
                     na::Vector2::new(
-                        player_velocity.linear.x + Self::SHIFT_JUICE,
+                        player_velocity.linear.x + Self::SHIFT_JUICE * dt,
                         player_velocity.linear.y,
                     ),
                     player_velocity.angular,
@@ -0,0 +1,94 @@
+use ggez::{
+    graphics::{self, Color, DrawParam, Mesh},
+    nalgebra::{Point2, Vector2},
+    Context, GameResult,
+};
+
+use crate::{
+    game::animation::SpriteSheet,
+    game::tint::{HitFlash, TintShader},
+    palette::Palette,
+};
+
+/// What an entity looks like.
+pub enum Look {
+    /// A frame of a sprite sheet, drawn with its middle where the entity is.
+    Sheet(SpriteSheet),
+    /// A shape around the origin, built on the spot in the colours of the palette.
+    Shape(fn(&mut Context, &Palette) -> GameResult<Mesh>),
+}
+
+/// How an entity gets drawn by the sprite system, where its transform is.
+pub struct Sprite {
+    pub look: Look,
+    /// The frame of the sprite sheet shown.
+    pub frame: usize,
+    pub scale: f32,
+    /// Multiplies the colours of the sprite, like a crate darkening as it takes damage.
+    pub color: Color,
+    /// Lights the sprite up for a moment after a hit.
+    pub flash: HitFlash,
+    /// How far the sprite reaches around the entity.
+    radius: f32,
+}
+
+impl Sprite {
+    pub fn sheet(sheet: SpriteSheet, scale: f32) -> Self {
+        let (width, height) = sheet.frame_size();
+
+        Self::new(Look::Sheet(sheet), scale, width.max(height) * scale / 2.0)
+    }
+
+    /// A shape reaching `radius` around the entity.
+    pub fn shape(build: fn(&mut Context, &Palette) -> GameResult<Mesh>, radius: f32) -> Self {
+        Self::new(Look::Shape(build), 1.0, radius)
+    }
+
+    fn new(look: Look, scale: f32, radius: f32) -> Self {
+        Self {
+            look,
+            frame: 0,
+            scale,
+            color: graphics::WHITE,
+            flash: HitFlash::new(),
+            radius,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// How to draw the sprite with its middle at `dest` on the screen.
+    pub fn param(&self, dest: Point2<f32>) -> DrawParam {
+        let param = DrawParam::default()
+            .dest(dest)
+            .scale(Vector2::new(self.scale, self.scale))
+            .color(self.color);
+
+        match &self.look {
+            Look::Sheet(sheet) => param
+                .offset(Point2::new(0.5, 0.5))
+                .src(sheet.src(self.frame)),
+            Look::Shape(_) => param,
+        }
+    }
+
+    /// Draw the sprite on its own, with its hit flash mixed in.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        dest: Point2<f32>,
+        tint: &TintShader,
+        palette: &Palette,
+    ) -> GameResult {
+        tint.draw(ctx, &self.flash, |ctx| match &self.look {
+            Look::Sheet(sheet) => graphics::draw(ctx, sheet.image(), self.param(dest)),
+            Look::Shape(build) => {
+                let mesh = build(ctx, palette)?;
+
+                graphics::draw(ctx, &mesh, self.param(dest))
+            }
+        })
+    }
+}
@@ -1,11 +1,11 @@
-use ggez::{graphics, nalgebra::Point2, Context, GameResult};
-use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use ggez::{graphics, nalgebra::Point2, Context};
 use graphics::DrawParam;
 use nphysics2d::nalgebra as na;
 use nphysics2d::object::DefaultBodyHandle;
 
 use crate::{
-    game::physics::{isometry_to_point, Physics},
+    display,
+    game::physics::{Physics, Surface},
     utils::AssetManager,
 };
 
@@ -20,110 +20,81 @@ pub struct Tile {
     height: f32,
 
     body: DefaultBodyHandle,
+    /// Where the tile was put down, tiles never move.
+    position: na::Point2<f32>,
     tile_type: TileType,
+    surface: Surface,
 }
 
 impl Tile {
+    /// Metal tiles are drawn as tinted dirt tiles.
+    const METAL_TINT: graphics::Color = graphics::Color {
+        r: 150.0 / 255.0,
+        g: 170.0 / 255.0,
+        b: 190.0 / 255.0,
+        a: 1.0,
+    };
+
     pub fn new(
         ctx: &mut Context,
         pos_x: f32,
         physics: &mut Physics,
         asset_manager: &AssetManager,
         tile_type: TileType,
+        surface: Surface,
     ) -> Self {
-        let (_, height) = graphics::drawable_size(ctx);
-
-        let tile_width;
-        let tile_height;
+        let (_, height) = display::screen_size(ctx);
 
         let pos_y = height / 2.0 - 64.0;
 
-        match tile_type {
-            TileType::Left => {
-                let ground_left = asset_manager.get_image("ground_left.png");
-
-                tile_width = ground_left.width();
-                tile_height = ground_left.height();
-            }
-            TileType::Center => {
-                let ground_centre = asset_manager.get_image("ground_centre.png");
-
-                tile_width = ground_centre.width();
-                tile_height = ground_centre.height();
-            }
-            TileType::Right => {
-                let ground_right = asset_manager.get_image("ground_right.png");
-
-                tile_width = ground_right.width();
-                tile_height = ground_right.height();
-            }
-        }
+        let (tile_width, tile_height) = asset_manager
+            .get_sprite(Self::sprite_name_of(&tile_type))
+            .size();
 
-        let body = physics.create_tile(na::Point2::new(pos_x, pos_y), tile_width, tile_height);
+        let position = na::Point2::new(pos_x, pos_y);
+        let body = physics.create_tile(position, tile_width as u16, tile_height as u16, surface);
 
         Self {
             tile_type,
+            surface,
             body,
+            position,
 
-            width: tile_width as f32,
-            height: tile_height as f32,
+            width: tile_width,
+            height: tile_height,
         }
     }
 
-    pub fn draw(
-        &mut self,
-        ctx: &mut Context,
-        camera: &Camera,
-        physics: &mut Physics,
-        asset_manager: &AssetManager,
-    ) -> GameResult<()> {
-        let ground_left = asset_manager.get_image("ground_left.png");
-        let ground_centre = asset_manager.get_image("ground_centre.png");
-        let ground_right = asset_manager.get_image("ground_right.png");
+    /// The sprite the tile is drawn with.
+    pub fn sprite_name(&self) -> &'static str {
+        Self::sprite_name_of(&self.tile_type)
+    }
 
-        let ground_position = self.position(physics);
-        let tile_position =
-            camera.calculate_dest_point(Vec2::new(ground_position.x, ground_position.y));
-
-        match self.tile_type {
-            TileType::Left => {
-                graphics::draw(
-                    ctx,
-                    &ground_left,
-                    DrawParam::default()
-                        .dest(Point2::new(tile_position.x, tile_position.y))
-                        .offset(Point2::new(0.5, 0.5)),
-                )?;
-            }
-
-            TileType::Center => {
-                graphics::draw(
-                    ctx,
-                    &ground_centre,
-                    DrawParam::default()
-                        .dest(Point2::new(tile_position.x, tile_position.y))
-                        .offset(Point2::new(0.5, 0.5)),
-                )?;
-            }
-            TileType::Right => {
-                graphics::draw(
-                    ctx,
-                    &ground_right,
-                    DrawParam::default()
-                        .dest(Point2::new(tile_position.x, tile_position.y))
-                        .offset(Point2::new(0.5, 0.5)),
-                )?;
-            }
+    fn sprite_name_of(tile_type: &TileType) -> &'static str {
+        match tile_type {
+            TileType::Left => "ground_left",
+            TileType::Center => "ground_centre",
+            TileType::Right => "ground_right",
         }
+    }
 
-        Ok(())
+    /// How to draw the tile into a batch, in world coordinates.
+    pub fn sprite(&self, physics: &mut Physics) -> DrawParam {
+        let ground_position = self.position(physics);
+
+        let color = match self.surface {
+            Surface::Dirt => graphics::WHITE,
+            Surface::Metal => Self::METAL_TINT,
+        };
+
+        DrawParam::default()
+            .dest(Point2::new(ground_position.x, ground_position.y))
+            .offset(Point2::new(0.5, 0.5))
+            .color(color)
     }
 
     pub fn position(&self, physics: &mut Physics) -> na::Point2<f32> {
-        let ground_body = physics.get_rigid_body(self.body);
-        let ground_position = isometry_to_point(ground_body.position());
-
-        ground_position
+        physics.body_position(self.body).unwrap_or(self.position)
     }
 
     pub fn dimensions(&self) -> na::Point2<f32> {
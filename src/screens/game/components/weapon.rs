@@ -0,0 +1,39 @@
+use ggez::{
+    graphics::{self, DrawParam},
+    nalgebra::{Point2, Vector2},
+    Context, GameResult,
+};
+
+use nphysics2d::nalgebra as na;
+
+use crate::utils::Sprite;
+
+/// The gun an entity is holding.
+pub struct Weapon {
+    pub sprite: Sprite,
+    /// Where the gun is held, from the middle of whoever holds it.
+    hold: na::Vector2<f32>,
+}
+
+impl Weapon {
+    pub fn new(sprite: Sprite, hold: na::Vector2<f32>) -> Self {
+        Self { sprite, hold }
+    }
+
+    /// How to draw the gun in the hands of something `scale` times as big as usual, with its
+    /// middle at `dest` on the screen.
+    pub fn param(&self, dest: Point2<f32>, scale: f32) -> DrawParam {
+        DrawParam::default()
+            .src(self.sprite.src)
+            .dest(Point2::new(
+                dest.x + self.hold.x * scale,
+                dest.y + self.hold.y * scale,
+            ))
+            .offset(Point2::new(0.5, 0.5))
+            .scale(Vector2::new(scale, scale))
+    }
+
+    pub fn draw(&self, ctx: &mut Context, dest: Point2<f32>, scale: f32) -> GameResult {
+        graphics::draw(ctx, &self.sprite.image, self.param(dest, scale))
+    }
+}
@@ -0,0 +1,292 @@
+//! A console dropping down from the top of the screen to type commands into.
+//!
+//! The console doesn't know any commands but `help` and `clear` by itself. Whatever it runs
+//! commands on registers them with a name and a usage line, and gets handed the words typed after
+//! the name when one is entered. A command answers with a line to show, or with what went wrong.
+
+use std::collections::VecDeque;
+
+use ggez::{
+    event::KeyCode,
+    graphics::{self, Color, DrawMode, DrawParam, Font, Rect, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::display;
+
+/// Runs a command on `T` with the words typed after its name.
+pub type Run<T> = fn(&mut T, &[&str]) -> Result<String, String>;
+
+struct ConsoleCommand<T> {
+    name: &'static str,
+    /// How the command gets typed, shown by `help`.
+    usage: &'static str,
+    run: Run<T>,
+}
+
+pub struct Console<T> {
+    open: bool,
+    /// What's been typed so far.
+    line: String,
+    /// The lines typed in and what came back, oldest first.
+    log: VecDeque<String>,
+    /// The lines typed in, to go back through with the arrow keys.
+    history: Vec<String>,
+    /// How far back through the history the typed line is, `None` for a line of its own.
+    recalled: Option<usize>,
+    commands: Vec<ConsoleCommand<T>>,
+}
+
+impl<T> Console<T> {
+    /// How many lines of the log are kept.
+    const MAX_LOG: usize = 100;
+    /// How much of the screen the open console covers.
+    const HEIGHT: f32 = 0.4;
+    const LINE_HEIGHT: f32 = 18.0;
+
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            line: String::new(),
+            log: VecDeque::new(),
+            history: vec![],
+            recalled: None,
+            commands: vec![],
+        }
+    }
+
+    /// Make a command available, over any other command going by the same name.
+    pub fn register(&mut self, name: &'static str, usage: &'static str, run: Run<T>) {
+        self.commands.retain(|command| command.name != name);
+        self.commands.push(ConsoleCommand { name, usage, run });
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Type a character into the console. The key opening the console doesn't get typed in.
+    pub fn text_input(&mut self, character: char) {
+        if self.open && !character.is_control() && !matches!(character, '`' | '~') {
+            self.line.push(character);
+        }
+    }
+
+    /// Edit the typed line. Returns the line once it's entered.
+    pub fn key_press(&mut self, keycode: KeyCode) -> Option<String> {
+        match keycode {
+            KeyCode::Escape => self.open = false,
+            KeyCode::Back => {
+                self.line.pop();
+            }
+            KeyCode::Up => self.recall(1),
+            KeyCode::Down => self.recall(-1),
+            KeyCode::Return | KeyCode::NumpadEnter => {
+                let line = std::mem::take(&mut self.line);
+
+                self.recalled = None;
+
+                if !line.trim().is_empty() {
+                    self.history.push(line.clone());
+
+                    return Some(line);
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Go `steps` lines further back through the history, or forward with negative steps.
+    fn recall(&mut self, steps: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let back = self.recalled.map_or(0, |back| back as isize + 1) + steps - 1;
+
+        if back < 0 {
+            self.recalled = None;
+            self.line.clear();
+
+            return;
+        }
+
+        let back = (back as usize).min(self.history.len() - 1);
+
+        self.recalled = Some(back);
+        self.line = self.history[self.history.len() - 1 - back].clone();
+    }
+
+    /// Find the command a line asks for, to be run on `T` with the words that came after its name.
+    /// The console answers the lines that don't ask for a registered command itself.
+    pub fn command<'a>(&mut self, line: &'a str) -> Option<(Run<T>, Vec<&'a str>)> {
+        self.print(format!("> {}", line));
+
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+
+        match name {
+            "help" => {
+                let usages: Vec<String> = self
+                    .commands
+                    .iter()
+                    .map(|command| command.usage.to_string())
+                    .collect();
+
+                for usage in usages {
+                    self.print(usage);
+                }
+
+                None
+            }
+            "clear" => {
+                self.log.clear();
+
+                None
+            }
+            _ => match self.commands.iter().find(|command| command.name == name) {
+                Some(command) => Some((command.run, words.collect())),
+                None => {
+                    self.print(format!("Unknown command {}, try help", name));
+
+                    None
+                }
+            },
+        }
+    }
+
+    /// Show what came of running a command.
+    pub fn answer(&mut self, answer: Result<String, String>) {
+        match answer {
+            Ok(line) if line.is_empty() => {}
+            Ok(line) => self.print(line),
+            Err(error) => self.print(format!("Error: {}", error)),
+        }
+    }
+
+    fn print(&mut self, line: String) {
+        if self.log.len() >= Self::MAX_LOG {
+            self.log.pop_front();
+        }
+
+        self.log.push_back(line);
+    }
+
+    pub fn draw(&self, ctx: &mut Context, font: Font) -> GameResult {
+        if !self.open {
+            return Ok(());
+        }
+
+        let (width, height) = display::screen_size(ctx);
+        let console_height = height * Self::HEIGHT;
+
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0.0, 0.0, width, console_height),
+            Color::from_rgba(20, 20, 20, 220),
+        )?;
+
+        graphics::draw(ctx, &background, DrawParam::default())?;
+
+        let text = |line: &str, color: Color| {
+            Text::new(
+                TextFragment::new(line)
+                    .font(font)
+                    .scale(Scale::uniform(16.0))
+                    .color(color),
+            )
+        };
+
+        let mut y = console_height - Self::LINE_HEIGHT - 10.0;
+
+        graphics::draw(
+            ctx,
+            &text(&format!("> {}_", self.line), Color::from_rgb(247, 76, 0)),
+            DrawParam::default().dest(Point2::new(10.0, y)),
+        )?;
+
+        // The newest lines go right above the typed one, as many as there's room for.
+        for line in self.log.iter().rev() {
+            y -= Self::LINE_HEIGHT;
+
+            if y < 0.0 {
+                break;
+            }
+
+            graphics::draw(
+                ctx,
+                &text(line, graphics::WHITE),
+                DrawParam::default().dest(Point2::new(10.0, y)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Default for Console<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read the word at `index` as a number, saying what's wrong with it if it isn't one.
+pub fn number<N: std::str::FromStr>(args: &[&str], index: usize, name: &str) -> Result<N, String> {
+    let word = args
+        .get(index)
+        .ok_or_else(|| format!("Missing the {}", name))?;
+
+    word.parse()
+        .map_err(|_| format!("{} isn't a valid {}", word, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(total: &mut i32, args: &[&str]) -> Result<String, String> {
+        *total += number::<i32>(args, 0, "amount")?;
+
+        Ok(format!("{}", total))
+    }
+
+    #[test]
+    fn test_console() {
+        let mut console = Console::new();
+        let mut total = 0;
+
+        console.register("add", "add <amount>", add);
+
+        for line in &["add 2", "add x", "subtract 1", "help"] {
+            if let Some((run, args)) = console.command(line) {
+                let answer = run(&mut total, &args);
+
+                console.answer(answer);
+            }
+        }
+
+        assert_eq!(total, 2);
+        assert_eq!(console.log[1], "2");
+        assert_eq!(console.log[3], "Error: x isn't a valid amount");
+        assert_eq!(console.log[5], "Unknown command subtract, try help");
+        assert_eq!(console.log[7], "add <amount>");
+
+        console.history = vec![String::from("add 2"), String::from("add 3")];
+        console.recall(1);
+        assert_eq!(console.line, "add 3");
+        console.recall(1);
+        console.recall(1);
+        assert_eq!(console.line, "add 2");
+        console.recall(-1);
+        console.recall(-1);
+        assert!(console.line.is_empty());
+    }
+}
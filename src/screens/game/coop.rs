@@ -0,0 +1,146 @@
+//! Playing the level together.
+//!
+//! Player two joins as a second crab when a second device was claimed on the devices screen. The
+//! second crab has its own input, health, ammo and weapon, while both share the camera, the score
+//! and the level. The camera frames both crabs, zooming out as they drift apart. Player two going
+//! down doesn't end the run, they come back next to Ferris after a moment instead.
+
+use ggez::{graphics::Color, Context};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::utils::AssetManager;
+
+use super::{
+    components::{bullet::WeaponType, player::Player},
+    input::InputState,
+    online::Remote,
+    physics::Physics,
+};
+
+/// The colour player two is told apart by.
+pub const PARTNER_COLOR: Color = Color {
+    r: 120.0 / 255.0,
+    g: 200.0 / 255.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+pub struct Partner {
+    pub player: Player,
+    pub input: InputState,
+    /// The weapon player two has in hand.
+    pub weapon: WeaponType,
+    /// How long until player two comes back, while they're down.
+    pub down: Option<f32>,
+}
+
+impl Partner {
+    /// How long player two stays down before coming back.
+    pub const RESPAWN_TIME: f32 = 3.0;
+    /// How far behind Ferris player two joins and comes back.
+    pub const OFFSET: f32 = 80.0;
+
+    pub fn new(
+        ctx: &mut Context,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+        ferris: f32,
+    ) -> Self {
+        let mut player = Player::new(ctx, ferris - Self::OFFSET, physics, asset_manager);
+
+        player.init(physics);
+
+        Self {
+            player,
+            input: InputState::new(),
+            weapon: WeaponType::Turbofish,
+            down: None,
+        }
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.down.is_some()
+    }
+
+    /// Put away the weapon in hand and take out the other one.
+    pub fn switch_weapon(&mut self) {
+        self.weapon = match self.weapon {
+            WeaponType::Turbofish => WeaponType::Grappling,
+            WeaponType::Grappling => WeaponType::Turbofish,
+        };
+    }
+}
+
+/// All of the crabs, for whatever their bullets can hit, with the crabs of the players online.
+/// Player two's bullets keep flying while they're down.
+pub fn crabs<'a>(
+    ferris: &'a mut Player,
+    partner: &'a mut Option<Partner>,
+    remotes: &'a mut [Remote],
+) -> Vec<&'a mut Player> {
+    let mut crabs = vec![ferris];
+
+    crabs.extend(partner.as_mut().map(|partner| &mut partner.player));
+    crabs.extend(remotes.iter_mut().map(|remote| &mut remote.player));
+
+    crabs
+}
+
+/// Where a camera of `view` size looks and how far it zooms out, to keep both crabs in sight.
+/// Neither crab gets closer than `margin` to the edges, unless that would take zooming out further
+/// than `min_zoom`.
+pub fn framing(ferris: Vec2, partner: Vec2, view: Vec2, margin: f32, min_zoom: f32) -> (Vec2, f32) {
+    let center = (ferris + partner) / 2.0;
+    let apart = (ferris - partner).abs();
+    let room = (view - Vec2::new(margin, margin) * 2.0).map(|room| room.max(1.0));
+    let needed = (apart.x / room.x).max(apart.y / room.y);
+
+    let zoom = if needed > 1.0 {
+        (1.0 / needed).max(min_zoom)
+    } else {
+        1.0
+    };
+
+    (center, zoom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framing() {
+        let view = Vec2::new(1000.0, 600.0);
+
+        // Close together, the camera looks between them without zooming out.
+        let (center, zoom) = framing(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(200.0, 100.0),
+            view,
+            100.0,
+            0.5,
+        );
+        assert_eq!(center, Vec2::new(100.0, 50.0));
+        assert_eq!(zoom, 1.0);
+
+        // Twice as far apart as fits, so the camera zooms out by half.
+        let (_, zoom) = framing(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1600.0, 0.0),
+            view,
+            100.0,
+            0.4,
+        );
+        assert!((zoom - 0.5).abs() < 0.001);
+
+        // But never further than allowed.
+        let (_, zoom) = framing(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(8000.0, 0.0),
+            view,
+            100.0,
+            0.4,
+        );
+        assert_eq!(zoom, 0.4);
+    }
+}
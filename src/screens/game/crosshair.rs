@@ -0,0 +1,109 @@
+//! The crosshair that replaces the mouse cursor while playing.
+//!
+//! Every shot kicks the lines of the crosshair apart, and they settle back in over time. The
+//! crosshair turns red while it is over an enemy within range.
+
+use ggez::{
+    graphics::{self, Color, DrawParam, MeshBuilder},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use super::components::bullet::WeaponType;
+
+pub struct Crosshair {
+    /// How far the lines are kicked apart by recent shots.
+    recoil: f32,
+}
+
+impl Crosshair {
+    /// The gap between the lines and the center without any recoil.
+    const GAP: f32 = 6.0;
+    /// The length of each line.
+    const LENGTH: f32 = 10.0;
+    /// The biggest the recoil can get.
+    const MAX_RECOIL: f32 = 30.0;
+    /// How fast the recoil settles down in pixels per second.
+    const RECOVERY: f32 = 60.0;
+    /// Color of the crosshair while it is over an enemy.
+    const TARGET_COLOR: Color = Color {
+        r: 1.0,
+        g: 0.31,
+        b: 0.3,
+        a: 1.0,
+    };
+
+    pub fn new() -> Self {
+        Self { recoil: 0.0 }
+    }
+
+    /// Kick the lines apart after shooting the provided weapon.
+    pub fn shoot(&mut self, weapon: WeaponType) {
+        let kick = match weapon {
+            WeaponType::Turbofish => 14.0,
+            WeaponType::Grappling => 6.0,
+        };
+
+        self.recoil = (self.recoil + kick).min(Self::MAX_RECOIL);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.recoil = (self.recoil - Self::RECOVERY * dt).max(0.0);
+    }
+
+    /// The gap between the lines and the center.
+    pub fn gap(&self) -> f32 {
+        Self::GAP + self.recoil
+    }
+
+    /// Draw the crosshair centered on `position`.
+    pub fn draw(&self, ctx: &mut Context, position: Point2<f32>, on_target: bool) -> GameResult {
+        let color = if on_target {
+            Self::TARGET_COLOR
+        } else {
+            graphics::WHITE
+        };
+
+        let gap = self.gap();
+        let end = gap + Self::LENGTH;
+        let mut mesh = MeshBuilder::new();
+
+        for (x, y) in &[(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            mesh.line(
+                &[
+                    Point2::new(position.x + x * gap, position.y + y * gap),
+                    Point2::new(position.x + x * end, position.y + y * end),
+                ],
+                2.0,
+                color,
+            )?;
+        }
+
+        mesh.circle(graphics::DrawMode::fill(), position, 1.5, 0.5, color);
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recoil_settles() {
+        let mut crosshair = Crosshair::new();
+
+        crosshair.shoot(WeaponType::Turbofish);
+        crosshair.shoot(WeaponType::Turbofish);
+        crosshair.shoot(WeaponType::Turbofish);
+
+        // The gap can't grow forever.
+        assert_eq!(crosshair.gap(), Crosshair::GAP + Crosshair::MAX_RECOIL);
+
+        crosshair.update(1.0);
+
+        assert_eq!(crosshair.gap(), Crosshair::GAP);
+    }
+}
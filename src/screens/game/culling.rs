@@ -0,0 +1,91 @@
+//! Skipping work for things nobody can see.
+//!
+//! Every viewport sees a rectangle of the world. Anything outside of all of them, with a margin so
+//! nothing pops in at the edges, doesn't get drawn. Things far away from every view are left alone
+//! most frames and get caught up with a bigger step every few frames instead, staggered so they
+//! don't all update on the same frame.
+
+use ggez::graphics::Rect;
+use ggez_goodies::nalgebra_glm::Vec2;
+
+/// The parts of the world the viewports see this frame.
+pub struct Culling {
+    views: Vec<Rect>,
+    /// The update the level is on, picks whose turn it is to update.
+    frame: usize,
+}
+
+impl Culling {
+    /// How far past the edge of a view things still get drawn.
+    const MARGIN: f32 = 150.0;
+    /// Things further away than this from every view update at a reduced rate.
+    const FAR: f32 = 1200.0;
+    /// Far away things update once every this many frames.
+    pub const FAR_INTERVAL: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            views: vec![],
+            frame: 0,
+        }
+    }
+
+    /// Start the `frame`th update of the level with the world rectangles the viewports see.
+    pub fn update(&mut self, views: Vec<Rect>, frame: usize) {
+        self.views = views;
+        self.frame = frame;
+    }
+
+    /// The step to update the `index`th thing of its kind at `position` with, if it's its turn.
+    pub fn step(&self, index: usize, position: Vec2, dt: f32) -> Option<f32> {
+        if self.views.is_empty()
+            || self
+                .views
+                .iter()
+                .any(|view| is_visible(*view, position, Self::FAR))
+        {
+            Some(dt)
+        } else if (self.frame + index).is_multiple_of(Self::FAR_INTERVAL) {
+            Some(dt * Self::FAR_INTERVAL as f32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether something at `position` reaching `radius` around it shows up in the view.
+pub fn is_visible(view: Rect, position: Vec2, radius: f32) -> bool {
+    let radius = radius + Culling::MARGIN;
+
+    position.x + radius >= view.x
+        && position.x - radius <= view.x + view.w
+        && position.y + radius >= view.y
+        && position.y - radius <= view.y + view.h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_culling() {
+        let view = Rect::new(0.0, 0.0, 1000.0, 600.0);
+
+        assert!(is_visible(view, Vec2::new(500.0, 300.0), 0.0));
+        assert!(is_visible(view, Vec2::new(-100.0, 300.0), 0.0));
+        assert!(!is_visible(view, Vec2::new(2000.0, 300.0), 100.0));
+
+        let mut culling = Culling::new();
+
+        culling.update(vec![view], 0);
+        assert_eq!(culling.step(0, Vec2::new(500.0, 300.0), 0.1), Some(0.1));
+
+        // Far away things take turns.
+        let far = Vec2::new(10_000.0, 300.0);
+        let updates = (0..Culling::FAR_INTERVAL)
+            .filter(|index| culling.step(*index, far, 0.1).is_some())
+            .count();
+
+        assert_eq!(updates, 1);
+    }
+}
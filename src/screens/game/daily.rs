@@ -0,0 +1,144 @@
+//! The daily challenge, the same run for everyone on the same day.
+//!
+//! The date picks the seed of the run, and the seed picks one of the levels along with a couple of
+//! modifiers changing how it plays. Everything else gets rolled with that seed too, so everyone
+//! playing on the same day gets the same run. The challenge is always played on normal, and its
+//! runs go into a high score table of the day instead of the one of the level.
+
+use std::time::SystemTime;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Something changing how a daily challenge plays.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Modifier {
+    /// Everything falls at half the speed.
+    LowGravity,
+    /// The grappling gun stays put away.
+    TurbofishOnly,
+    /// Ferris goes down in half as many hits.
+    GlassCannon,
+    /// The gophers have twice the health.
+    ToughGophers,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [
+        Modifier::LowGravity,
+        Modifier::TurbofishOnly,
+        Modifier::GlassCannon,
+        Modifier::ToughGophers,
+    ];
+
+    /// What the modifier is called on the screen.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::LowGravity => "LOW GRAVITY",
+            Modifier::TurbofishOnly => "TURBOFISH ONLY",
+            Modifier::GlassCannon => "GLASS CANNON",
+            Modifier::ToughGophers => "TOUGH GOPHERS",
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct DailyChallenge {
+    /// The day of the challenge, like `2026-10-14`.
+    pub date: String,
+    pub seed: u64,
+    pub level: String,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl DailyChallenge {
+    /// How many modifiers every challenge has.
+    const MODIFIERS: usize = 2;
+    /// Mixed into the day, so the seeds of days next to each other aren't next to each other.
+    const SALT: u64 = 0x5eed_f0f0_da11_1e55;
+
+    /// The challenge of today, in UTC, on one of the `levels`.
+    pub fn today(levels: &[String]) -> Option<Self> {
+        let days = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() / 86_400);
+
+        Self::for_day(days, levels)
+    }
+
+    /// The challenge of the day `days` after 1970-01-01 on one of the `levels`, `None` without
+    /// any levels.
+    pub fn for_day(days: u64, levels: &[String]) -> Option<Self> {
+        let mut rng = StdRng::seed_from_u64(days ^ Self::SALT);
+        let seed = rng.gen();
+        let level = levels.choose(&mut rng)?.clone();
+        let modifiers = Modifier::ALL
+            .choose_multiple(&mut rng, Self::MODIFIERS)
+            .copied()
+            .collect();
+
+        Some(Self {
+            date: date(days),
+            seed,
+            level,
+            modifiers,
+        })
+    }
+
+    pub fn has(&self, modifier: Modifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    /// The high score table the runs of the challenge go into.
+    pub fn table(&self) -> String {
+        format!("daily-{}", self.date)
+    }
+}
+
+/// The date `days` after 1970-01-01, as `year-month-day`.
+fn date(days: u64) -> String {
+    // Counted in eras of 400 years from 0000-03-01, which makes leap days the last of a year.
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_challenge() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_740), "2026-10-14");
+
+        let levels = vec![String::from("01"), String::from("02")];
+        let challenge = DailyChallenge::for_day(20_740, &levels).unwrap();
+
+        // Everyone gets the same challenge on the same day.
+        assert_eq!(
+            DailyChallenge::for_day(20_740, &levels),
+            Some(challenge.clone())
+        );
+        assert_ne!(
+            DailyChallenge::for_day(20_741, &levels).unwrap().seed,
+            challenge.seed
+        );
+
+        assert_eq!(challenge.modifiers.len(), DailyChallenge::MODIFIERS);
+        assert_ne!(challenge.modifiers[0], challenge.modifiers[1]);
+        assert_eq!(challenge.table(), "daily-2026-10-14");
+
+        assert_eq!(DailyChallenge::for_day(20_740, &[]), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Marks fights leave behind on the level.
+//!
+//! Bullets that hit the ground leave a hole and explosions scorch what's around them. The marks
+//! stay for the rest of the run, up to a limit after which the oldest ones make room for new ones.
+
+use std::collections::VecDeque;
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, MeshBuilder, Rect},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use rand::Rng;
+
+use super::culling::is_visible;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DecalKind {
+    BulletHole,
+    Scorch,
+}
+
+impl DecalKind {
+    /// How big the mark is, give or take a bit.
+    fn radius(&self) -> f32 {
+        match self {
+            DecalKind::BulletHole => 4.0,
+            DecalKind::Scorch => 70.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            DecalKind::BulletHole => Color::new(0.08, 0.06, 0.05, 0.9),
+            DecalKind::Scorch => Color::new(0.05, 0.04, 0.03, 0.55),
+        }
+    }
+}
+
+struct Decal {
+    kind: DecalKind,
+    position: Vec2,
+    /// Marks aren't all the same size, so they don't look like stamps.
+    scale: f32,
+}
+
+pub struct Decals {
+    decals: VecDeque<Decal>,
+}
+
+impl Decals {
+    /// How many marks stay on the level at once.
+    const MAX_DECALS: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            decals: VecDeque::with_capacity(Self::MAX_DECALS),
+        }
+    }
+
+    /// Leave a mark at a position in the world, making room by dropping the oldest mark if there
+    /// are too many.
+    pub fn stamp(&mut self, kind: DecalKind, position: Vec2, rng: &mut impl Rng) {
+        if self.decals.len() >= Self::MAX_DECALS {
+            self.decals.pop_front();
+        }
+
+        self.decals.push_back(Decal {
+            kind,
+            position,
+            scale: rng.gen_range(0.8..1.2),
+        });
+    }
+
+    /// Draw the marks showing up in `view`, the part of the world the camera sees.
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera, view: Rect) -> GameResult {
+        let mut mesh = MeshBuilder::new();
+        let mut empty = true;
+
+        for decal in &self.decals {
+            let radius = decal.kind.radius() * decal.scale;
+
+            if !is_visible(view, decal.position, radius) {
+                continue;
+            }
+
+            let position = camera.calculate_dest_point(decal.position);
+
+            // Scorch marks spread out along the ground more than they go up.
+            match decal.kind {
+                DecalKind::BulletHole => mesh.circle(
+                    DrawMode::fill(),
+                    Point2::new(position.x, position.y),
+                    radius,
+                    0.5,
+                    decal.kind.color(),
+                ),
+                DecalKind::Scorch => mesh.ellipse(
+                    DrawMode::fill(),
+                    Point2::new(position.x, position.y),
+                    radius,
+                    radius * 0.4,
+                    0.5,
+                    decal.kind.color(),
+                ),
+            };
+
+            empty = false;
+        }
+
+        if empty {
+            return Ok(());
+        }
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.decals.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_oldest_decals_go_first() {
+        let rng = &mut StdRng::seed_from_u64(0);
+        let mut decals = Decals::new();
+
+        for x in 0..Decals::MAX_DECALS + 10 {
+            decals.stamp(DecalKind::BulletHole, Vec2::new(x as f32, 0.0), rng);
+        }
+
+        assert_eq!(decals.len(), Decals::MAX_DECALS);
+        assert_eq!(decals.decals[0].position.x, 10.0);
+    }
+}
@@ -0,0 +1,243 @@
+//! Dialogue boxes for story beats and chatter.
+//!
+//! Dialogues are written as scripts in `resources/dialogues` and get started by the dialogue
+//! triggers of a level. The text of every line is typed out a few characters at a time. A script
+//! can be translated next to it as `name.<language>.dlg`, like `01_intro.de.dlg`.
+//!
+//! # Script
+//! `.comment` => A comment \
+//! `.speaker <name> <portrait>` => Set who is talking for the following lines \
+//! Every other line that is not empty is a line of dialogue.
+
+use ggez::{
+    graphics::{
+        self, Align, Color, DrawMode, DrawParam, Font, Mesh, Rect, Scale, Text, TextFragment,
+    },
+    nalgebra::{Point2, Vector2},
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::utils::AssetManager;
+
+struct Line {
+    speaker: String,
+    portrait: String,
+    text: String,
+}
+
+pub struct Dialogue {
+    lines: Vec<Line>,
+    /// The line currently being shown.
+    line: usize,
+    /// How many characters of the current line have been typed out so far.
+    typed: f32,
+}
+
+impl Dialogue {
+    /// How many characters get typed out per second.
+    const TYPING_SPEED: f32 = 40.0;
+    /// The height of the dialogue box.
+    const HEIGHT: f32 = 140.0;
+    /// The size of the portrait next to the text.
+    const PORTRAIT_SIZE: f32 = 100.0;
+
+    /// Parse a dialogue script.
+    pub fn parse(script: &str) -> Self {
+        let mut lines = vec![];
+
+        let mut speaker = String::new();
+        let mut portrait = String::new();
+
+        for line in script
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let exp = line.split(' ').collect::<Vec<_>>();
+
+            if exp[0].starts_with(".speaker") {
+                speaker = exp.get(1).unwrap_or(&"").to_string();
+                portrait = exp[2..].join(" ");
+            } else if exp[0].starts_with(".comment") {
+                // Do nothing. ¯\_(ツ)_/¯
+            } else {
+                lines.push(Line {
+                    speaker: speaker.clone(),
+                    portrait: portrait.clone(),
+                    text: line.to_string(),
+                });
+            }
+        }
+
+        Self {
+            lines,
+            line: 0,
+            typed: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.typed += Self::TYPING_SPEED * dt;
+    }
+
+    /// Move the dialogue along. Finishes typing out the current line first, then moves on to the
+    /// next one. Returns `true` once there are no lines left.
+    pub fn advance(&mut self) -> bool {
+        let length = self.current().map_or(0, |line| line.text.chars().count());
+
+        if (self.typed as usize) < length {
+            self.typed = length as f32;
+        } else {
+            self.line += 1;
+            self.typed = 0.0;
+        }
+
+        self.is_done()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.line >= self.lines.len()
+    }
+
+    /// Draw the dialogue box at the bottom of a screen of the provided size.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        size: Vec2,
+        font: Font,
+        asset_manager: &AssetManager,
+    ) -> GameResult {
+        let line = match self.current() {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+
+        let area = Rect::new(
+            40.0,
+            size.y - Self::HEIGHT - 20.0,
+            size.x - 80.0,
+            Self::HEIGHT,
+        );
+
+        let background =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), area, Color::new(0.0, 0.0, 0.0, 0.8))?;
+        let border = Mesh::new_rectangle(
+            ctx,
+            DrawMode::stroke(2.0),
+            area,
+            Color::from_rgb(247, 76, 0),
+        )?;
+
+        graphics::draw(ctx, &background, DrawParam::default())?;
+        graphics::draw(ctx, &border, DrawParam::default())?;
+
+        let padding = (Self::HEIGHT - Self::PORTRAIT_SIZE) / 2.0;
+        let mut text_left = area.x + padding;
+
+        if !line.portrait.is_empty() {
+            let portrait = asset_manager.get_image(&line.portrait);
+
+            graphics::draw(
+                ctx,
+                &portrait,
+                DrawParam::default()
+                    .dest(Point2::new(text_left, area.y + padding))
+                    .scale(Vector2::new(
+                        Self::PORTRAIT_SIZE / portrait.width() as f32,
+                        Self::PORTRAIT_SIZE / portrait.height() as f32,
+                    )),
+            )?;
+
+            text_left += Self::PORTRAIT_SIZE + padding;
+        }
+
+        let speaker = Text::new(
+            TextFragment::new(line.speaker.as_str())
+                .font(font)
+                .scale(Scale::uniform(22.0))
+                .color(Color::from_rgb(247, 76, 0)),
+        );
+
+        graphics::draw(
+            ctx,
+            &speaker,
+            DrawParam::default().dest(Point2::new(text_left, area.y + padding)),
+        )?;
+
+        let typed = line
+            .text
+            .chars()
+            .take(self.typed as usize)
+            .collect::<String>();
+        let mut text = Text::new(
+            TextFragment::new(typed)
+                .font(font)
+                .scale(Scale::uniform(18.0)),
+        );
+
+        text.set_bounds(
+            Point2::new(area.right() - text_left - padding, f32::INFINITY),
+            Align::Left,
+        );
+
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default().dest(Point2::new(text_left, area.y + padding + 32.0)),
+        )?;
+
+        // Let the player know the line is done once it has been typed out.
+        if self.typed as usize >= line.text.chars().count() {
+            let prompt = Text::new(
+                TextFragment::new("[ENTER]")
+                    .font(font)
+                    .scale(Scale::uniform(14.0))
+                    .color(Color::new(1.0, 1.0, 1.0, 0.6)),
+            );
+
+            let (width, height) = prompt.dimensions(ctx);
+
+            graphics::draw(
+                ctx,
+                &prompt,
+                DrawParam::default().dest(Point2::new(
+                    area.right() - width as f32 - 12.0,
+                    area.bottom() - height as f32 - 8.0,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn current(&self) -> Option<&Line> {
+        self.lines.get(self.line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        let mut dialogue = Dialogue::parse(
+            ".comment Who is talking\n.speaker Ferris Some(profile).png\nHello\n\n.speaker Gopher gopher.png\nBye\n",
+        );
+
+        assert_eq!(dialogue.lines.len(), 2);
+        assert_eq!(dialogue.lines[1].speaker, "Gopher");
+        assert_eq!(dialogue.lines[1].portrait, "gopher.png");
+
+        // The first press finishes typing out the line, the second one moves on.
+        assert!(!dialogue.advance());
+        assert_eq!(dialogue.line, 0);
+        assert!(!dialogue.advance());
+        assert_eq!(dialogue.line, 1);
+
+        dialogue.update(1.0);
+
+        assert!(dialogue.advance());
+    }
+}
@@ -0,0 +1,416 @@
+//! The entities of a level, and the components they're made of.
+//!
+//! Collisions and ray casts only hand out the bodies involved, while the game logic lives on the
+//! enemies, bullets and barrels those bodies belong to. Every entity gets an id when its body is
+//! created and loses it when the body is destroyed, so a body can be traced back to its entity. An
+//! id is never handed out twice, unlike body handles whose slots get reused. The physics world
+//! keeps the store along with the bodies for that reason, but everything about an entity besides
+//! its body is reached through the store.
+//!
+//! What an entity is made of is kept here by its id, one store per component. Every entity has a
+//! `PhysicsBody` and a `Transform`. The ones that can get hurt have a `Health`, the ones the
+//! sprite system draws a `Sprite`, the ones holding a gun a `Weapon` and the ones going after the
+//! crabs an `Ai`. A new kind of entity is whatever components it gets spawned with.
+//!
+//! Systems are passes over the component stores. The transform system runs after every physics
+//! step and copies where the bodies ended up into their transforms, so everything else reads the
+//! position from there instead of asking the physics world. An entity whose body went missing gets
+//! despawned by it. The AI system has every `Ai` look out for the crabs, the sprite system keeps
+//! the hit flashes going and draws every `Sprite` where its transform is, along with the `Weapon`
+//! it holds.
+
+use std::collections::{BTreeMap, HashMap};
+
+use ggez::{graphics::Rect, nalgebra::Point2, Context, GameResult};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use nphysics2d::{
+    nalgebra as na,
+    object::{DefaultBodyHandle, DefaultBodySet},
+};
+
+use crate::{
+    error::{FerrisError, FerrisResult},
+    game::batch::SpriteBatches,
+    game::components::{
+        ai::Ai,
+        health::Health,
+        sprite::{Look, Sprite},
+        weapon::Weapon,
+    },
+    game::culling::is_visible,
+    game::physics::isometry_to_point,
+    game::tint::TintShader,
+    palette::Palette,
+};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct EntityId(u32);
+
+impl EntityId {
+    /// The number behind the id. The entities a level starts out with get the same numbers every
+    /// time the level is built, wherever it's played.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// Where an entity is, as of the last time the transform system ran.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Transform {
+    pub position: na::Point2<f32>,
+}
+
+/// The body an entity moves around with in the physics world.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct PhysicsBody(pub DefaultBodyHandle);
+
+pub struct Entities {
+    next: u32,
+    entities: HashMap<DefaultBodyHandle, EntityId>,
+    bodies: HashMap<EntityId, PhysicsBody>,
+    transforms: HashMap<EntityId, Transform>,
+    healths: HashMap<EntityId, Health>,
+    /// By id, so the sprites get drawn in the order their entities were spawned in.
+    sprites: BTreeMap<EntityId, Sprite>,
+    weapons: HashMap<EntityId, Weapon>,
+    ais: HashMap<EntityId, Ai>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self {
+            next: 0,
+            entities: HashMap::new(),
+            bodies: HashMap::new(),
+            transforms: HashMap::new(),
+            healths: HashMap::new(),
+            sprites: BTreeMap::new(),
+            weapons: HashMap::new(),
+            ais: HashMap::new(),
+        }
+    }
+
+    /// Give the entity the body belongs to an id, starting out at `position`. `kind` tells what
+    /// sort of entity it is in the log.
+    pub fn spawn(
+        &mut self,
+        body: DefaultBodyHandle,
+        position: na::Point2<f32>,
+        kind: &str,
+    ) -> EntityId {
+        let entity = EntityId(self.next);
+
+        log::debug!("Spawned {} {:?}", kind, entity);
+
+        self.next += 1;
+        self.entities.insert(body, entity);
+        self.bodies.insert(entity, PhysicsBody(body));
+        self.transforms.insert(entity, Transform { position });
+
+        entity
+    }
+
+    /// Forget about the entity of a body that's being destroyed, along with all of its components.
+    pub fn despawn(&mut self, body: DefaultBodyHandle) {
+        if let Some(entity) = self.entities.remove(&body) {
+            log::debug!("Despawned {:?}", entity);
+
+            self.bodies.remove(&entity);
+            self.transforms.remove(&entity);
+            self.healths.remove(&entity);
+            self.sprites.remove(&entity);
+            self.weapons.remove(&entity);
+            self.ais.remove(&entity);
+        }
+    }
+
+    /// The entity the body belongs to.
+    pub fn entity(&self, body: DefaultBodyHandle) -> Option<EntityId> {
+        self.entities.get(&body).copied()
+    }
+
+    /// The body of the entity, as long as it's still around.
+    pub fn body(&self, entity: EntityId) -> Option<DefaultBodyHandle> {
+        self.bodies.get(&entity).map(|body| body.0)
+    }
+
+    /// Whether the entity is still around to be given components.
+    fn is_spawned(&self, entity: EntityId) -> bool {
+        self.bodies.contains_key(&entity)
+    }
+
+    pub fn transform(&self, entity: EntityId) -> FerrisResult<&Transform> {
+        self.transforms
+            .get(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    /// Let the entity get hurt, with `health` to begin with. An entity that has health already
+    /// starts over with the new one.
+    pub fn insert_health(&mut self, entity: EntityId, health: Health) {
+        if self.is_spawned(entity) {
+            self.healths.insert(entity, health);
+        }
+    }
+
+    pub fn health(&self, entity: EntityId) -> FerrisResult<&Health> {
+        self.healths
+            .get(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    pub fn health_mut(&mut self, entity: EntityId) -> FerrisResult<&mut Health> {
+        self.healths
+            .get_mut(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    /// Have the sprite system draw the entity.
+    pub fn insert_sprite(&mut self, entity: EntityId, sprite: Sprite) {
+        if self.is_spawned(entity) {
+            self.sprites.insert(entity, sprite);
+        }
+    }
+
+    pub fn sprite_mut(&mut self, entity: EntityId) -> FerrisResult<&mut Sprite> {
+        self.sprites
+            .get_mut(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    /// Put a gun in the hands of the entity.
+    pub fn insert_weapon(&mut self, entity: EntityId, weapon: Weapon) {
+        if self.is_spawned(entity) {
+            self.weapons.insert(entity, weapon);
+        }
+    }
+
+    pub fn weapon(&self, entity: EntityId) -> FerrisResult<&Weapon> {
+        self.weapons
+            .get(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    /// Have the entity go after the crabs.
+    pub fn insert_ai(&mut self, entity: EntityId, ai: Ai) {
+        if self.is_spawned(entity) {
+            self.ais.insert(entity, ai);
+        }
+    }
+
+    pub fn ai(&self, entity: EntityId) -> FerrisResult<&Ai> {
+        self.ais.get(&entity).ok_or_else(|| missing_entity(entity))
+    }
+
+    pub fn ai_mut(&mut self, entity: EntityId) -> FerrisResult<&mut Ai> {
+        self.ais
+            .get_mut(&entity)
+            .ok_or_else(|| missing_entity(entity))
+    }
+
+    /// The transform system. Moves every transform to where the body of its entity is in `bodies`.
+    /// Entities whose body isn't there any more are despawned.
+    pub fn sync_transforms(&mut self, bodies: &DefaultBodySet<f32>) {
+        let mut missing = vec![];
+
+        for (entity, body) in &self.bodies {
+            match bodies.rigid_body(body.0) {
+                Some(rigid_body) => {
+                    let position = isometry_to_point(rigid_body.position());
+
+                    self.transforms.insert(*entity, Transform { position });
+                }
+                None => missing.push(body.0),
+            }
+        }
+
+        for body in missing {
+            log::warn!("{:?} went missing from the physics world", body);
+
+            self.despawn(body);
+        }
+    }
+
+    /// The AI system. Every entity with an `Ai` looks out for the crabs at `targets`.
+    pub fn update_ai(&mut self, targets: &[na::Point2<f32>], dt: f32) {
+        for (entity, ai) in &mut self.ais {
+            if let Some(transform) = self.transforms.get(entity) {
+                ai.update(transform.position, targets, dt);
+            }
+        }
+    }
+
+    /// How many entities are going after the crabs.
+    pub fn aggroed(&self) -> usize {
+        self.ais.values().filter(|ai| ai.is_aggroed()).count()
+    }
+
+    /// The update half of the sprite system, which fades the hit flashes out.
+    pub fn update_sprites(&mut self, dt: f32) {
+        for sprite in self.sprites.values_mut() {
+            sprite.flash.update(dt);
+        }
+    }
+
+    /// The draw half of the sprite system. Draws every sprite that shows up in `view`, the part of
+    /// the world the camera sees, along with the gun its entity holds. The sprite sheets go into
+    /// `batches`, apart from the flashing ones which need the tint shader to themselves, and the
+    /// shapes get drawn over them.
+    pub fn draw_sprites(
+        &self,
+        ctx: &mut Context,
+        camera: &Camera,
+        view: Rect,
+        batches: &mut SpriteBatches,
+        tint: &TintShader,
+        palette: &Palette,
+    ) -> GameResult {
+        let mut alone = vec![];
+
+        for (entity, sprite) in &self.sprites {
+            let position = match self.transforms.get(entity) {
+                Some(transform) => Vec2::new(transform.position.x, transform.position.y),
+                None => continue,
+            };
+
+            if !is_visible(view, position, sprite.radius()) {
+                continue;
+            }
+
+            let dest = camera.calculate_dest_point(position);
+            let dest = Point2::new(dest.x, dest.y);
+            let weapon = self.weapons.get(entity);
+
+            match &sprite.look {
+                Look::Sheet(sheet) if !sprite.flash.is_flashing() => {
+                    batches.add(sheet.texture(), sheet.image(), sprite.param(dest));
+
+                    if let Some(weapon) = weapon {
+                        batches.add(
+                            &weapon.sprite.texture,
+                            &weapon.sprite.image,
+                            weapon.param(dest, sprite.scale),
+                        );
+                    }
+                }
+                _ => alone.push((sprite, weapon, dest)),
+            }
+        }
+
+        batches.draw(ctx)?;
+
+        for (sprite, weapon, dest) in alone {
+            sprite.draw(ctx, dest, tint, palette)?;
+
+            if let Some(weapon) = weapon {
+                weapon.draw(ctx, dest, sprite.scale)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error for an entity that has been despawned, or never had the component asked for.
+fn missing_entity(entity: EntityId) -> FerrisError {
+    log::warn!("{:?} is not in the component store", entity);
+
+    FerrisError::MissingEntity(entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nphysics2d::object::RigidBodyDesc;
+
+    use crate::game::physics::point_to_isometry;
+
+    #[test]
+    fn test_entities() {
+        let mut bodies = DefaultBodySet::<f32>::new();
+        let mut entities = Entities::new();
+
+        let first = bodies.insert(RigidBodyDesc::new().build());
+        let second = bodies.insert(RigidBodyDesc::new().build());
+        let (a, b) = (
+            entities.spawn(first, na::Point2::origin(), "test"),
+            entities.spawn(second, na::Point2::origin(), "test"),
+        );
+
+        assert_ne!(a, b);
+        assert_eq!(entities.entity(second), Some(b));
+        assert_eq!(entities.body(a), Some(first));
+
+        entities.insert_health(a, Health::new(100.0));
+        entities.health_mut(a).unwrap().damage(30.0);
+        assert_eq!(entities.health(a).unwrap().current(), 70.0);
+        assert!(entities.health(b).is_err());
+
+        entities.despawn(first);
+        assert_eq!(entities.entity(first), None);
+        assert_eq!(entities.body(a), None);
+        assert!(entities.transform(a).is_err());
+        assert!(entities.health(a).is_err());
+
+        // A despawned entity doesn't get its health back.
+        entities.insert_health(a, Health::new(100.0));
+        assert!(entities.health(a).is_err());
+    }
+
+    #[test]
+    fn test_transform_system() {
+        let mut bodies = DefaultBodySet::<f32>::new();
+        let mut entities = Entities::new();
+
+        let body = bodies.insert(RigidBodyDesc::new().build());
+        let gone = bodies.insert(RigidBodyDesc::new().build());
+        let (entity, lost) = (
+            entities.spawn(body, na::Point2::origin(), "test"),
+            entities.spawn(gone, na::Point2::origin(), "test"),
+        );
+
+        bodies
+            .rigid_body_mut(body)
+            .unwrap()
+            .set_position(point_to_isometry(na::Point2::new(40.0, -20.0)));
+        bodies.remove(gone);
+        entities.sync_transforms(&bodies);
+
+        assert_eq!(
+            entities.transform(entity).ok(),
+            Some(&Transform {
+                position: na::Point2::new(40.0, -20.0)
+            })
+        );
+        assert_eq!(entities.entity(gone), None);
+        assert!(entities.transform(lost).is_err());
+    }
+
+    #[test]
+    fn test_ai_system() {
+        let mut bodies = DefaultBodySet::<f32>::new();
+        let mut entities = Entities::new();
+
+        let (near, far) = (
+            bodies.insert(RigidBodyDesc::new().build()),
+            bodies.insert(RigidBodyDesc::new().build()),
+        );
+        let near = entities.spawn(near, na::Point2::new(100.0, 0.0), "test");
+        let far = entities.spawn(far, na::Point2::new(5000.0, 0.0), "test");
+
+        entities.insert_ai(near, Ai::new(800.0, 5.0));
+        entities.insert_ai(far, Ai::new(800.0, 5.0));
+        entities.update_ai(&[na::Point2::origin()], 1.0 / 60.0);
+
+        assert!(entities.ai(near).unwrap().is_aggroed());
+        assert!(!entities.ai(far).unwrap().is_aggroed());
+        assert_eq!(entities.aggroed(), 1);
+    }
+}
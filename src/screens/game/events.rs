@@ -0,0 +1,91 @@
+//! Things that happen during a level, for whoever wants to know about them.
+//!
+//! Gameplay code publishes an event whenever something happens, instead of telling the score, the
+//! stats, the HUD and the audio about it one by one. The bus holds on to the events until the end
+//! of the update, where every subscriber gets handed each of them in the order they happened.
+
+use super::components::pickup::PickupKind;
+
+/// Who got hurt.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Victim {
+    Player,
+    Enemy,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GameEvent {
+    /// Someone lost `amount` health over `hits` hits.
+    DamageDealt {
+        victim: Victim,
+        amount: f32,
+        hits: u32,
+    },
+    EnemyKilled {
+        /// The name of the boss, if the enemy was one.
+        boss: Option<&'static str>,
+        headshot: bool,
+    },
+    PickupCollected(PickupKind),
+    CheckpointReached,
+    /// A crab fired a turbofish, the grappling gun doesn't count.
+    ShotFired,
+    /// Every enemy of the level got taken down.
+    LevelCompleted,
+    /// A barrel went off as the `chain`th one of a chain explosion.
+    BarrelExploded {
+        chain: u32,
+    },
+}
+
+/// Something that reacts to the events of the level.
+pub trait Subscriber {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+pub struct EventBus {
+    events: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self { events: vec![] }
+    }
+
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Take the events published since the last time, oldest first.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus() {
+        let mut bus = EventBus::new();
+
+        bus.publish(GameEvent::CheckpointReached);
+        bus.publish(GameEvent::BarrelExploded { chain: 2 });
+
+        assert_eq!(
+            bus.drain(),
+            vec![
+                GameEvent::CheckpointReached,
+                GameEvent::BarrelExploded { chain: 2 }
+            ]
+        );
+        assert!(bus.drain().is_empty());
+    }
+}
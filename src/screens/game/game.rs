@@ -1,30 +1,89 @@
-use std::{collections::HashMap, process::exit, rc::Rc, sync::Mutex};
+use std::{collections::HashMap, process::exit, rc::Rc, sync::Mutex, thread::JoinHandle};
 
 use ggez::{
-    audio::SoundSource,
-    event::KeyCode,
-    graphics::{self, Color, DrawParam, Drawable, Shader, Text},
-    mint,
+    event::{Axis, Button, KeyCode},
+    graphics::{self, spritebatch::SpriteBatch, Canvas, DrawParam, Drawable, Shader, Text},
     nalgebra::Point2,
     timer, Context, GameResult,
 };
-use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use ggez_goodies::nalgebra_glm::Vec2;
 use graphics::{GlBackendSpec, Scale, ShaderGeneric, TextFragment};
-use mint::Vector2;
+use nphysics2d::{nalgebra as na, object::DefaultBodyHandle};
 use rand::Rng;
 
 use crate::{
+    achievements::Achievements,
+    audio::{Listener, MusicController, Sfx},
+    bindings::Action,
+    config::{Config, MIN_GAME_SPEED},
+    dead::{HighScoreBoard, RunStats},
+    display,
+    error::{FerrisError, FerrisResult},
+    game::batch::{SpriteBatches, TileBatches},
+    game::boss_bar::BossBar,
+    game::boss_rush::{BossRush, RushEvent, RushResults},
+    game::camera::{CameraPath, ScreenShake},
     game::components::{
+        ammo::Ammo,
+        barrel::Barrel,
         bullet::{PlayerWeapon, WeaponType},
         cloud::Cloud,
-        player::Direction,
+        crate_box::Crate,
+        enemy::Enemy,
+        gate::Gate,
+        health::Health,
+        pickup::{Pickup, PickupKind},
+        player::Player,
     },
+    game::console::{self, Console},
+    game::coop::{self, Partner},
+    game::crosshair::Crosshair,
+    game::culling::{is_visible, Culling},
+    game::daily::{DailyChallenge, Modifier},
+    game::decals::{DecalKind, Decals},
+    game::dialogue::Dialogue,
+    game::events::{EventBus, GameEvent, Subscriber, Victim},
+    game::hud::Hud,
+    game::input::{Device, InputState},
+    game::kill_feed::KillFeed,
+    game::layout::{Anchor, Layout},
+    game::lighting::Lighting,
     game::map::Map,
+    game::minimap::Minimap,
+    game::movement_sounds::MovementSounds,
+    game::objective::{Objective, ObjectiveMarker, OBJECTIVE_COLOR},
+    game::objects::Objects,
+    game::online::Remote,
+    game::outline,
+    game::particles::{EmitterConfig, Particles},
+    game::photo::{PhotoAction, PhotoMode},
     game::physics::Physics,
-    play,
-    utils::{lerp, remap, AssetManager, ParticleSystem},
+    game::post::{Filter, PostEffect, PostProcessing},
+    game::prompts::{Prompt, Prompts},
+    game::recording::{Playback, Recording},
+    game::remix::RemixTable,
+    game::rng::{self, GameRng},
+    game::score::Score,
+    game::script::{Command, Script, Spawn},
+    game::spectator::Spectator,
+    game::speedrun::{self, Speedrun},
+    game::text_effects::TextEffects,
+    game::time_scale::{SlowMotion, TimeScale},
+    game::tint::TintShader,
+    game::toasts::Toasts,
+    game::touch::{TouchButton, TouchControls},
+    game::versus::{self, Versus},
+    game::viewport::Viewport,
+    lockstep::{Checksum, Lockstep},
+    net::{CrabState, EnemyState, Message, Session},
+    profiler,
+    save::{Difficulty, Loadout, NewGamePlus, Save},
+    screenshot::Screenshot,
+    utils::{damp, AssetManager},
     Screen,
 };
+#[cfg(feature = "discord")]
+use crate::{discord::Presence, game::presence::LevelPresence};
 
 use gfx::*;
 
@@ -39,37 +98,263 @@ pub struct Game {
     map: Map,
     /// Physics system for the game.
     physics: Physics,
-    /// Camera to see the world.
-    camera: Camera,
+    /// The views into the world. There is more than one when the screen is split.
+    viewports: Vec<Viewport>,
+    /// The health bar and friends drawn on top of the world.
+    hud: Hud,
+    /// Points and combo of the player.
+    score: Score,
+    /// Reports the latest kills and explosions.
+    kill_feed: KillFeed,
+    /// Lets the player know about everything else that happens.
+    toasts: Toasts,
+    /// Map of the level in the corner of the screen.
+    minimap: Minimap,
+    /// Replaces the mouse cursor while playing.
+    crosshair: Crosshair,
+    /// Footsteps, landings and the jetpack.
+    movement_sounds: MovementSounds,
+    /// How tense the fighting is, between 0.0 and 1.0. Brings in the layers of the music.
+    tension: f32,
+    /// The zoom picked with the scroll wheel while debugging the camera, which the camera keeps
+    /// instead of zooming on its own.
+    manual_zoom: Option<f32>,
+    /// Points the player towards what they should be doing.
+    objective: ObjectiveMarker,
+    /// Health of the boss, shown during a boss fight.
+    boss_bar: BossBar,
+    /// Teaches the player the controls.
+    prompts: Prompts,
+    input: InputState,
+    touch: TouchControls,
+    /// Player two, while playing together.
+    partner: Option<Partner>,
+    /// The crabs of the other players, while playing online.
+    remotes: Vec<Remote>,
+    /// Which player this is, while playing online.
+    player_id: Option<u8>,
+    /// The deathmatch, in the arenas for versus.
+    versus: Option<Versus>,
+    /// Where the crabs come back after going down in versus.
+    spawns: Vec<na::Point2<f32>>,
+    /// How many shots Ferris fired in the level, for the other players online to fire them too.
+    shots: u32,
+    difficulty: Difficulty,
+    /// The New Game+ the level is played in, if it is.
+    new_game_plus: Option<NewGamePlus>,
+    /// The daily challenge being played, if it is.
+    daily: Option<DailyChallenge>,
+    /// `Some` while playing the boss rush.
+    boss_rush: Option<BossRush>,
+    /// Times the level, in speedrun mode.
+    speedrun: Option<Speedrun>,
+    /// Counts towards the achievements.
+    achievements: Achievements,
+    /// The inputs of both crabs, while playing in lockstep online.
+    lockstep: Option<Lockstep>,
+    /// What the player is doing, while it's being recorded.
+    recording: Option<Recording>,
+    /// The recording being played back instead of what the player is doing.
+    playback: Option<Playback>,
+    /// The camera controls of whoever watches the recording being played back.
+    spectator: Option<Spectator>,
+    /// Whether the recording being played back is a demo, played by the menu while it's left
+    /// alone.
+    demo: bool,
+    /// Shows the level on the player's Discord profile.
+    #[cfg(feature = "discord")]
+    presence: Option<LevelPresence>,
+    /// The dice of the run. Recordings keep its seed to roll the same way when played back.
+    rng: GameRng,
+    /// The id of the level being played.
+    level: String,
+    /// The high scores of the level, shown once it's won.
+    board: Option<HighScoreBoard>,
+    /// The player's progress, remembering the prompts that were already shown and the levels
+    /// that were beaten.
+    save: Save,
+    /// Whether the save changed since it was last written.
+    save_dirty: bool,
+    /// The save being written in the background, and how long the saving indicator has been up.
+    autosave: Option<(JoinHandle<()>, f32)>,
+    /// Whether the player asked for the pause menu, it gets opened over the level.
+    wants_pause: bool,
+    /// `Some` while taking photos, which keeps the game frozen.
+    photo: Option<PhotoMode>,
+    /// The dialogue being shown, the player can't move around until it is over.
+    dialogue: Option<Dialogue>,
+    /// The script of the level, if it has one.
+    script: Option<Script>,
+    console: Console<Self>,
+    /// What the console asked for, carried out with the next update.
+    queued: Vec<Command>,
+    /// The level the console asked to go over to.
+    level_request: Option<String>,
+    /// The seed the console asked to play every run on from now on, `Some(None)` to go back to
+    /// a seed of their own for every run.
+    seed_request: Option<Option<u64>>,
+    /// Where the player comes back after dying.
+    checkpoint: na::Point2<f32>,
+    /// Seconds the player has been playing the level for.
+    time: f32,
+
+    /// The ground, batched a chunk at a time.
+    ground_batches: TileBatches,
+    /// The sprites of the entities, batched by texture again every frame.
+    sprite_batches: SpriteBatches,
+    /// Clouds show up many times, so they get batched again every frame too.
+    cloud_batch: SpriteBatch,
 
     // TODO: Refactor the rest of the fields
     clouds: Vec<Cloud>,
 
     /// Reference to the asset manager.
     asset_manager: Rc<AssetManager>,
+    /// The player's settings.
+    config: Config,
 
-    elapsed_shake: Option<(f32, Vec2, f32)>,
     tics: Option<i32>,
-    particles: Vec<ParticleSystem>,
+    /// Particle bursts, debris and whatever else looks after itself.
+    objects: Objects,
+    /// Explosions, muzzle flashes, dust and fire.
+    effects: Particles,
+    /// Bullet holes and scorch marks.
+    decals: Decals,
+    culling: Culling,
+    /// How many updates the level went through, the same however often frames get drawn.
+    updates: usize,
+    events: EventBus,
+    time_scale: TimeScale,
+    /// The darkness over dark levels.
+    lighting: Option<Lighting>,
+    /// Screen flashes and the like, drawn over everything.
+    post: PostProcessing,
+    /// Flashes sprites when they get hurt.
+    tint: TintShader,
+    /// Damage numbers and other text popping up in the world.
+    text_effects: TextEffects,
+    pickups: Vec<Pickup>,
     ui_lerp: HashMap<String, f32>,
 
     dim_shader: ShaderGeneric<GlBackendSpec, Dim>,
     dim_constant: Dim,
 
-    draw_end_text: (bool, Option<usize>, bool, bool), // Thread Sleeped?, Current Iters, Done?, Win?
+    draw_end_text: (bool, Option<f32>, bool, bool), // Thread Sleeped?, Seconds Waited, Done?, Win?
     can_die: bool,
 }
 
 impl Game {
-    pub fn create(ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Mutex<Self> {
-        let (width, height) = graphics::drawable_size(ctx);
+    /// The fall speed after which landing shakes the camera.
+    const LANDING_SPEED: f32 = 200.0;
+    /// Cap on how many times a light shake a landing can cause.
+    const MAX_LANDING_SHAKE: f32 = 3.0;
+    /// The fall speed after which landing kicks up dust.
+    const DUST_SPEED: f32 = 120.0;
+    /// How far ahead of the player bullets leave the gun.
+    const MUZZLE_OFFSET: f32 = 140.0;
+
+    /// The speed after which the camera zooms out.
+    const HIGH_SPEED: f32 = 400.0;
+    /// The zoom used while the player is moving fast.
+    const HIGH_SPEED_ZOOM: f32 = 0.85;
+    /// The zoom used during boss fights, to keep the boss and its attacks in sight.
+    const BOSS_ZOOM: f32 = 0.8;
+    /// The zoom used while a dialogue is being shown.
+    const DIALOGUE_ZOOM: f32 = 1.2;
+    /// How long it takes the camera to zoom in and out.
+    const ZOOM_DURATION: f32 = 0.8;
+
+    /// Time the intro camera takes to pan between two waypoints.
+    const INTRO_LEG_DURATION: f32 = 1.5;
+    /// Time the intro camera holds still at each waypoint.
+    const INTRO_HOLD: f32 = 0.5;
+
+    /// The amount of viewports the screen gets split into.
+    const SPLIT_VIEWPORTS: usize = 2;
+
+    /// How close to the edges of the screen the camera lets the crabs get while playing together.
+    const COOP_MARGIN: f32 = 150.0;
+    /// How far the camera zooms out at most to keep both crabs in sight.
+    const COOP_MIN_ZOOM: f32 = 0.6;
+
+    /// The speed the player needs to be moving at for the camera to look ahead in that direction.
+    const LOOK_AHEAD_SPEED: f32 = 50.0;
+    /// How long the saving indicator stays up at least, so it doesn't just flicker.
+    const SAVING_TIME: f32 = 1.0;
+
+    /// How close the player needs to get to a boss for the boss fight to start.
+    const BOSS_RANGE: f32 = 900.0;
+    /// The track played during boss fights.
+    const BOSS_MUSIC: &'static str = "boss.wav";
+    /// How much tension every gopher going after the player adds.
+    const TENSION_PER_ENEMY: f32 = 0.35;
+    /// How fast the tension goes up and down, per second.
+    const TENSION_RISE: f32 = 1.0;
+    const TENSION_FALL: f32 = 0.15;
+    /// How loud the music is while the game is paused.
+    pub const PAUSED_MUSIC: f32 = 0.4;
+    /// How long turning the music up or down takes.
+    pub const MUSIC_FADE: f32 = 0.3;
+
+    /// How fast the screen dims and brightens again.
+    const DIM_RATE: f32 = 6.0;
+    /// How fast the name of the weapon in use fades out.
+    const USING_FADE: f32 = 3.0;
+    /// How long the level waits after the last enemy before it shows the end text.
+    const END_TEXT_DELAY: f32 = 0.5;
+
+    /// What the modifiers of the daily challenge multiply gravity, the health of Ferris and the
+    /// health of the gophers by.
+    const LOW_GRAVITY: f32 = 0.5;
+    const GLASS_CANNON: f32 = 0.5;
+    const TOUGH_GOPHERS: f32 = 2.0;
+    /// How far ahead of the player the bosses of the boss rush get dropped in.
+    const RUSH_SPAWN_DISTANCE: f32 = 600.0;
+    /// How much health a break of the boss rush gives back.
+    const BREAK_HEAL: f32 = 50.0;
+    /// Where the pickups of the shop go, ahead of and above the player.
+    const SHOP_DISTANCE: f32 = 200.0;
+    const SHOP_SPACING: f32 = 120.0;
+    const SHOP_HEIGHT: f32 = 100.0;
+    /// How far apart what a gopher gets remixed into spawns.
+    const REMIX_SPREAD: f32 = 80.0;
+
+    /// How close to an enemy the crosshair needs to be to lock onto it.
+    const TARGET_RADIUS: f32 = 60.0;
+    /// Enemies further away from the player than this are out of range of the crosshair.
+    const TARGET_RANGE: f32 = 1200.0;
+
+    /// Build a level to play at `difficulty`. Fails if the level can't be read, which leaves the
+    /// screen that was there before as it was.
+    pub fn create(
+        ctx: &mut Context,
+        asset_manager: Rc<AssetManager>,
+        level: &str,
+        config: &Config,
+        difficulty: Difficulty,
+    ) -> FerrisResult<Mutex<Self>> {
+        let (width, height) = display::screen_size(ctx);
+
+        let mut rng = GameRng::random();
 
-        let mut camera = Camera::new(width as u32, height as u32, width, height);
+        let mut physics = Physics::new();
+        let mut map = Map::parse(ctx, level, &mut physics, &asset_manager)?;
 
-        let mut rng = rand::thread_rng();
+        log::info!(
+            "Playing level {} with seed {} on {:?}",
+            level,
+            rng.seed(),
+            difficulty
+        );
 
-        let mut physics = Physics::new();
-        let mut map = Map::parse(ctx, "01", &mut physics, &asset_manager);
+        for enemy in &mut map.enemies {
+            enemy.toughen(&mut physics, difficulty.enemy_health());
+        }
+
+        // Arenas keep their checkpoints to spawn at.
+        if map.versus.is_none() {
+            map.checkpoints = difficulty.checkpoints(std::mem::take(&mut map.checkpoints));
+        }
 
         let mut clouds = vec![];
 
@@ -82,63 +367,191 @@ impl Game {
             dim_constant,
             "Dim",
             None,
-        )
-        .unwrap();
+        )?;
 
         let mut ui_lerp = HashMap::new();
 
-        ui_lerp.insert(String::from("ammo"), map.player.ammo as f32);
-        ui_lerp.insert(String::from("health"), map.player.health as f32);
-        ui_lerp.insert(String::from("using"), map.using.as_ref().unwrap().1);
+        ui_lerp.insert(String::from("using"), map.using.1);
 
         map.player.init(&mut physics);
 
-        camera.move_to(Vec2::new(
-            map.player.position(&mut physics).x,
-            map.player.position(&mut physics).y,
-        ));
+        let mut hud = Hud::new(&map.player, &physics, map.weapon, &asset_manager);
+
+        hud.set_flashing(config.flashing);
+        let minimap = Minimap::new(&map, &mut physics);
+
+        let player_position = map.player.position(&mut physics);
+
+        // The checkpoints of an arena are where the crabs spawn, along with where the player starts.
+        let mut spawns = vec![];
+        let versus = map.versus.map(|frag_limit| {
+            let mut versus = Versus::new(frag_limit);
+
+            spawns = std::mem::take(&mut map.checkpoints);
+            spawns.push(player_position);
+            versus.join(0);
+
+            versus
+        });
+
+        let mut viewport = Viewport::new(
+            ctx,
+            graphics::Rect::new(0.0, 0.0, width, height),
+            Vec2::new(player_position.x, player_position.y),
+            map.camera_bounds(&mut physics),
+            false,
+        )?;
+
+        viewport.controller.shake.intensity = config.screen_shake;
+
+        if !map.intro.is_empty() {
+            viewport.controller.play_path(CameraPath::new(
+                map.intro
+                    .iter()
+                    .map(|waypoint| Vec2::new(waypoint.x, waypoint.y))
+                    .collect(),
+                Self::INTRO_LEG_DURATION,
+                Self::INTRO_HOLD,
+            ));
+        }
+
+        let script = match &map.script {
+            Some(name) => Some(Self::load_script(&asset_manager, level, name)?),
+            None => None,
+        };
+
+        let mut input = InputState::new();
+
+        input.set_toggled(config.toggled());
+
+        let sky = rng.effects();
 
-        for _ in 0..rng.gen_range(5..=7) {
+        for _ in 0..sky.gen_range(5..=7) {
             clouds.push(Cloud::new(
-                rng.gen_range(0. ..=width),
-                rng.gen_range(10. ..=40.),
-                rng.gen_range(0.1..=0.3),
-                rng.gen_range(10. ..=35.),
+                sky.gen_range(0. ..=width),
+                sky.gen_range(10. ..=40.),
+                sky.gen_range(0.1..=0.3),
+                sky.gen_range(10. ..=35.),
             ));
         }
 
-        Mutex::new(Self {
+        let lighting = if map.darkness > 0.0 {
+            Some(Lighting::new(map.darkness))
+        } else {
+            None
+        };
+        let achievements = Achievements::load(&asset_manager);
+        let boss_rush = map.boss_rush.then(BossRush::new);
+
+        Ok(Mutex::new(Self {
+            ground_batches: TileBatches::new(map.ground.len()),
+            sprite_batches: SpriteBatches::new(),
+            cloud_batch: SpriteBatch::new(asset_manager.get_image("Some(cloud).png")),
+
             map,
             physics,
 
             clouds,
 
             asset_manager,
+            config: config.clone(),
+
+            viewports: vec![viewport],
+            hud,
+            score: Score::new(),
+            kill_feed: KillFeed::new(),
+            toasts: Toasts::new(),
+            minimap,
+            crosshair: Crosshair::new(),
+            movement_sounds: MovementSounds::new(),
+            tension: 0.0,
+            manual_zoom: None,
+            objective: ObjectiveMarker::new(),
+            boss_bar: BossBar::new(),
+            prompts: Prompts::new(),
+            input,
+            touch: TouchControls::new(),
+            partner: None,
+            remotes: vec![],
+            player_id: None,
+            versus,
+            spawns,
+            shots: 0,
+            difficulty,
+            new_game_plus: None,
+            daily: None,
+            boss_rush,
+            speedrun: config.speedrun.then(|| Speedrun::start(ctx, level)),
+            achievements,
+            lockstep: None,
+            recording: None,
+            playback: None,
+            demo: false,
+            #[cfg(feature = "discord")]
+            presence: None,
+            spectator: None,
+            rng,
+            level: level.to_string(),
+            board: None,
+            save: Save::load(ctx, config.save_slot),
+            save_dirty: false,
+            autosave: None,
+            wants_pause: false,
+            photo: None,
+            dialogue: None,
+            script,
+            console: Self::console(),
+            queued: vec![],
+            level_request: None,
+            seed_request: None,
+            checkpoint: player_position,
+            time: 0.0,
 
-            camera,
-
-            elapsed_shake: None,
             tics: None,
-            particles: vec![],
+            objects: Objects::new(),
+            effects: Particles::new(),
+            decals: Decals::new(),
+            culling: Culling::new(),
+            updates: 0,
+            events: EventBus::new(),
+            time_scale: TimeScale::new(),
+            lighting,
+            post: PostProcessing::new(ctx, config)?,
+            tint: TintShader::new(ctx, config)?,
+            text_effects: TextEffects::new(config.color_blind.palette()),
+            pickups: vec![],
             ui_lerp,
 
             dim_shader,
             dim_constant,
             draw_end_text: (false, None, false, false),
             can_die: true,
-        })
+        }))
     }
 
     pub fn draw(&mut self, ctx: &mut Context) -> GameResult<Option<Screen>> {
-        let (width, height) = graphics::drawable_size(ctx);
+        self.draw_frame(ctx, false)
+    }
+
+    /// Draw the level frozen in place, for the pause menu to go over.
+    pub fn draw_paused(&mut self, ctx: &mut Context) -> GameResult {
+        self.draw_frame(ctx, true).map(|_| ())
+    }
+
+    fn draw_frame(&mut self, ctx: &mut Context, paused: bool) -> GameResult<Option<Screen>> {
+        let _timer = profiler::scope("draw");
+        let (width, height) = display::screen_size(ctx);
 
-        let consolas = self.asset_manager.get_font("Consolas.ttf");
+        let consolas = self.asset_manager.text_font();
+
+        // The crosshair takes over from the cursor, unless there is a menu to click around in.
+        ggez::input::mouse::set_cursor_hidden(ctx, !paused && self.tics.is_none());
 
         if let Some(_t) = self.tics {
             {
                 let _lock = graphics::use_shader(ctx, &self.dim_shader);
 
-                self.inner_draw(ctx)?;
+                self.inner_draw(ctx, paused)?;
             }
 
             if self.draw_end_text.0 && self.draw_end_text.3 {
@@ -162,6 +575,32 @@ impl Game {
                     )),
                 )?;
 
+                // The seed, to take the same run on again
+                let seed_frag = &Text::new(
+                    TextFragment::new(
+                        self.asset_manager
+                            .text_with("SEED {seed}", &[("seed", &self.rng.seed())]),
+                    )
+                    .font(consolas)
+                    .scale(Scale::uniform(14.))
+                    .color(graphics::Color::new(1.0, 1.0, 1.0, 0.6)),
+                );
+
+                let seed_dimensions = seed_frag.dimensions(ctx);
+
+                graphics::draw(
+                    ctx,
+                    seed_frag,
+                    DrawParam::default().dest(Point2::new(
+                        (width / 2.0) - (seed_dimensions.0 / 2) as f32,
+                        50.0 + end_dimensions.1 as f32 + 6.0,
+                    )),
+                )?;
+
+                if let Some(board) = &mut self.board {
+                    board.draw(ctx, consolas, Point2::new(30.0, 120.0))?;
+                }
+
                 // End quote
                 for line in self
                     .map
@@ -271,138 +710,327 @@ impl Game {
                 )?;
             }
         } else {
-            self.inner_draw(ctx)?;
+            self.inner_draw(ctx, paused)?;
+        }
+
+        // The photo gets taken before anything of photo mode goes on top.
+        if let Some(photo) = &mut self.photo {
+            if photo.take_capture() {
+                match Screenshot::capture(ctx).map(|screenshot| screenshot.save(ctx, "photo")) {
+                    Ok(Ok(path)) => {
+                        log::info!("Saved a photo to {}", path.display());
+
+                        photo.saved(path.display().to_string());
+                    }
+                    Ok(Err(error)) => log::error!("Cannot save the photo: {}", error),
+                    Err(error) => log::error!("Cannot take the photo: {}", error),
+                }
+            }
+        }
+
+        if let Some(photo) = &self.photo {
+            photo.draw(ctx, consolas, Vec2::new(width, height))?;
+        }
+
+        if self.demo {
+            self.draw_demo(ctx, width)?;
+        } else if let Some(playback) = &self.playback {
+            playback.draw(ctx, consolas)?;
         }
 
-        graphics::present(ctx)?;
+        self.console.draw(ctx, consolas)?;
 
         Ok(None)
     }
 
-    fn inner_draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+    fn inner_draw(&mut self, ctx: &mut Context, paused: bool) -> GameResult<()> {
         graphics::clear(ctx, graphics::BLACK);
 
+        // The game goes through the post shader when any of its effects are showing.
+        let screen = self.post.begin(ctx)?;
+        let coordinates = graphics::screen_coordinates(ctx);
+
+        for view in 0..self.viewports.len() {
+            self.viewports[view].begin(ctx)?;
+            self.draw_view(ctx, view, screen.as_ref(), paused)?;
+            self.viewports[view].end(ctx, screen.as_ref(), coordinates)?;
+        }
+
+        self.post.end(ctx, screen)
+    }
+
+    /// Draw the world and the UI as seen through the provided viewport.
+    fn draw_view(
+        &mut self,
+        ctx: &mut Context,
+        view: usize,
+        screen: Option<&Canvas>,
+        paused: bool,
+    ) -> GameResult<()> {
+        let viewport = &self.viewports[view];
+        let camera = &viewport.camera;
+        let size = viewport.size();
+        // Anything out of the part of the world the viewport sees doesn't get drawn.
+        let world = viewport.world_rect();
+
         // Clouds
-        for cloud in &mut self.clouds {
-            cloud.draw(ctx, &self.asset_manager)?;
+        self.cloud_batch.clear();
+
+        for cloud in &self.clouds {
+            self.cloud_batch.add(cloud.sprite());
         }
 
+        graphics::draw(ctx, &self.cloud_batch, DrawParam::default())?;
+
+        // Everything in the world gets zoomed, the UI does not.
+        graphics::push_transform(
+            ctx,
+            Some(viewport.controller.zoom_transform(size.x, size.y)),
+        );
+        graphics::apply_transformations(ctx)?;
+
         // Ground
-        for tile in &mut self.map.ground {
-            tile.draw(ctx, &self.camera, &mut self.physics, &self.asset_manager)?;
+        self.ground_batches.draw(
+            ctx,
+            camera,
+            world,
+            &self.map.ground,
+            &mut self.physics,
+            &self.asset_manager,
+        )?;
+
+        self.decals.draw(ctx, camera, world)?;
+
+        // Enemies, barrels, crates and pickups
+        self.physics.entities().draw_sprites(
+            ctx,
+            camera,
+            world,
+            &mut self.sprite_batches,
+            &self.tint,
+            self.config.color_blind.palette(),
+        )?;
+
+        // Gates
+        for gate in &self.map.gates {
+            let position = gate.position();
+
+            if is_visible(world, Vec2::new(position.x, position.y), Gate::HEIGHT) {
+                gate.draw(ctx, camera)?;
+            }
         }
 
-        // Enemies
-        for enemy in &mut self.map.enemies {
-            enemy.draw(ctx, &self.camera, &mut self.physics, &self.asset_manager)?;
+        // Player
+        self.map.player.draw(
+            ctx,
+            camera,
+            &mut self.physics,
+            &self.asset_manager,
+            &self.tint,
+        )?;
+
+        if let Some(partner) = self.partner.as_mut().filter(|partner| !partner.is_down()) {
+            partner.player.draw(
+                ctx,
+                camera,
+                &mut self.physics,
+                &self.asset_manager,
+                &self.tint,
+            )?;
+
+            // Tell the two crabs apart.
+            let position = partner.player.position(&mut self.physics);
+            let label = camera.calculate_dest_point(Vec2::new(position.x, position.y));
+            let text = Text::new(
+                TextFragment::new("P2")
+                    .font(self.asset_manager.text_font())
+                    .scale(Scale::uniform(20.0)),
+            );
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default()
+                    .dest(Point2::new(label.x - 10.0, label.y - 70.0))
+                    .color(coop::PARTNER_COLOR),
+            )?;
+        }
+
+        for remote in &mut self.remotes {
+            remote.player.draw(
+                ctx,
+                camera,
+                &mut self.physics,
+                &self.asset_manager,
+                &self.tint,
+            )?;
+
+            let position = remote.player.position(&mut self.physics);
+            let label = camera.calculate_dest_point(Vec2::new(position.x, position.y));
+            let text = Text::new(
+                TextFragment::new(format!("P{}", remote.id() + 1))
+                    .font(self.asset_manager.text_font())
+                    .scale(Scale::uniform(20.0)),
+            );
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default().dest(Point2::new(label.x - 10.0, label.y - 70.0)),
+            )?;
         }
 
-        // Barrel
-        for boom in &mut self.map.barrels {
-            boom.draw(ctx, &self.camera, &mut self.physics, &self.asset_manager)?;
+        if self.config.high_contrast {
+            let crabs = std::iter::once(&self.map.player)
+                .chain(self.partner.iter().map(|partner| &partner.player))
+                .chain(self.remotes.iter().map(|remote| &remote.player));
+            let targets = outline::targets(&mut self.physics, &self.map.enemies, crabs, world);
+
+            outline::draw(ctx, camera, &targets)?;
         }
 
-        // Player
-        self.map
-            .player
-            .draw(ctx, &self.camera, &mut self.physics, &self.asset_manager)?;
+        // Particles and debris
+        self.objects.draw(ctx, &mut self.physics, camera)?;
+        self.effects.draw(ctx, camera)?;
 
-        // Particles
-        for sys in &mut self.particles {
-            sys.draw(ctx, &mut self.physics, &mut self.camera)?;
+        // Damage numbers
+        self.text_effects
+            .draw(ctx, camera, self.asset_manager.text_font())?;
+
+        if self.config.debug.colliders {
+            self.physics.draw_colliders(ctx, camera)?;
         }
 
-        // User Profile, etc..
-        self.draw_ui(ctx)?;
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)?;
 
-        #[cfg(feature = "debug")]
-        self.physics.draw_colliders(ctx, &self.camera)?;
+        if let Some(lighting) = &mut self.lighting {
+            let lamp = self.map.player.position(&mut self.physics);
+
+            lighting.draw(ctx, viewport, screen, Vec2::new(lamp.x, lamp.y))?;
+        }
+
+        // User Profile, etc..
+        if self.photo.as_ref().is_none_or(PhotoMode::shows_hud) {
+            self.draw_ui(ctx, size, paused)?;
+        }
 
         Ok(())
     }
 
-    fn draw_ui(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let (width, _) = graphics::drawable_size(ctx);
+    fn draw_ui(&mut self, ctx: &mut Context, size: Vec2, paused: bool) -> GameResult<()> {
+        let consolas = self.asset_manager.text_font();
 
-        let profile = self.asset_manager.get_image("Some(profile).png");
-        let fish = self.asset_manager.get_image("Some(fish).png");
+        // The UI is drawn onto the virtual screen of the layout, except for the crosshair which
+        // has to stay on the actual cursor.
+        let layout = Layout::new(size);
+        let size = layout.size();
 
-        let consolas = self.asset_manager.get_font("Consolas.ttf");
+        graphics::push_transform(ctx, Some(layout.transform()));
+        graphics::apply_transformations(ctx)?;
 
-        graphics::draw(
+        self.hud.draw(
             ctx,
-            &profile,
-            DrawParam::default()
-                .dest(Point2::new(10.0, 10.0))
-                .scale(Vector2 { x: 0.5, y: 0.5 }),
+            &layout,
+            &self.map.player,
+            &self.physics,
+            &self.score,
+            &self.asset_manager,
+            self.config.color_blind.palette(),
         )?;
 
-        let ammo_rect = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                ((profile.width() / 2) + 10) as f32,
-                (profile.height() / 3) as f32,
-                150.,
-                15.,
-            ),
-            Color::from_rgb(54, 50, 49),
-        )?;
+        if let Some(partner) = &self.partner {
+            self.hud.draw_partner(
+                ctx,
+                &layout,
+                &partner.player,
+                &self.physics,
+                &self.asset_manager,
+                self.config.color_blind.palette(),
+            )?;
+        }
 
-        let hp_rect = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                ((profile.width() / 2) + 10) as f32,
-                (profile.height() / 5) as f32,
-                150.,
-                15.,
-            ),
-            Color::from_rgb(54, 50, 49),
-        )?;
+        if let Some(versus) = &self.versus {
+            versus.draw(ctx, consolas, size)?;
+        }
 
-        let cur_ammo_rect = graphics::Mesh::new_rectangle(
+        let player_position = self.map.player.position(&mut self.physics);
+        let physics = &mut self.physics;
+        let enemies = self
+            .map
+            .enemies
+            .iter()
+            .filter_map(|enemy| enemy.position(physics).ok())
+            .collect::<Vec<_>>();
+
+        let objective = self
+            .objective
+            .objective()
+            .map(|objective| objective.position);
+
+        self.minimap.draw(
             ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                ((profile.width() / 2) + 10) as f32,
-                (profile.height() / 3) as f32,
-                remap(self.map.player.ammo as f32, 0., 10., 0., 150.),
-                15.,
-            ),
-            Color::from_rgb(21, 156, 228),
+            size,
+            player_position,
+            &enemies,
+            objective,
+            self.config.color_blind.palette(),
         )?;
 
-        let cur_hp_rect = graphics::Mesh::new_rectangle(
+        self.boss_bar.draw(ctx, &layout, consolas)?;
+        self.prompts.draw(
             ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                ((profile.width() / 2) + 10) as f32,
-                (profile.height() / 5) as f32,
-                remap(self.map.player.health as f32, 0., 100., 0., 150.),
-                15.,
-            ),
-            Color::from_rgb(34, 205, 124),
+            &layout,
+            consolas,
+            self.input.device,
+            &self.config.bindings,
+            &self.asset_manager.localization(),
         )?;
+        self.toasts.draw(ctx, &layout, consolas)?;
 
-        graphics::draw(ctx, &ammo_rect, DrawParam::default())?;
+        if let Some(speedrun) = &self.speedrun {
+            speedrun.draw(ctx, &layout, consolas)?;
+            speedrun::draw_inputs(ctx, &layout, consolas, &self.input)?;
+        }
 
-        graphics::draw(ctx, &hp_rect, DrawParam::default())?;
+        if self.config.touch_controls && !paused {
+            self.touch.draw(ctx, &layout, consolas)?;
+        }
 
-        graphics::draw(ctx, &cur_ammo_rect, DrawParam::default())?;
+        if let Some(objective) = self.objective.objective() {
+            let label = Text::new(
+                TextFragment::new(format!("OBJECTIVE: {}", objective.label))
+                    .font(consolas)
+                    .scale(Scale::uniform(16.0))
+                    .color(OBJECTIVE_COLOR),
+            );
+
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(layout.place(Anchor::TopLeft, Vec2::new(20.0, 120.0))),
+            )?;
+        }
 
-        graphics::draw(ctx, &cur_hp_rect, DrawParam::default())?;
+        if let Some(dialogue) = &self.dialogue {
+            dialogue.draw(ctx, size, consolas, &self.asset_manager)?;
+        }
 
-        graphics::draw(
-            ctx,
-            &fish,
-            DrawParam::default()
-                .dest(Point2::new(
-                    ((profile.width() / 2) - 10) as f32,
-                    (profile.height() / 3) as f32,
-                ))
-                .scale(Vector2 { x: 0.7, y: 0.7 }),
-        )?;
+        if self.autosave.is_some() {
+            let saving = Text::new(
+                TextFragment::new("SAVING...")
+                    .font(consolas)
+                    .scale(Scale::uniform(16.0)),
+            );
+            let saving_width = saving.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &saving,
+                DrawParam::default()
+                    .dest(layout.place(Anchor::Bottom, Vec2::new(-saving_width / 2.0, -40.0))),
+            )?;
+        }
 
         let evildoers = &Text::new(
             TextFragment::new(format!(
@@ -419,13 +1047,19 @@ impl Game {
         graphics::draw(
             ctx,
             evildoers,
-            DrawParam::default().dest(Point2::new((width - evildoers_dim.0 as f32) - 40., 20.)),
+            DrawParam::default().dest(layout.place(
+                Anchor::TopRight,
+                Vec2::new(-40.0 - evildoers_dim.0 as f32, 20.0),
+            )),
         )?;
 
+        self.kill_feed
+            .draw(ctx, size, 30. + evildoers_dim.1 as f32, consolas)?;
+
         let info = &Text::new(
-            TextFragment::new(format!("Using {}", self.map.using.as_ref().unwrap().0))
+            TextFragment::new(format!("Using {}", self.map.using.0))
                 .font(consolas)
-                .color([1.0, 1.0, 1.0, self.map.using.as_ref().unwrap().1].into()),
+                .color([1.0, 1.0, 1.0, self.map.using.1].into()),
         );
 
         let info_dim = info.dimensions(ctx);
@@ -433,224 +1067,2834 @@ impl Game {
         graphics::draw(
             ctx,
             info,
-            DrawParam::default().dest(Point2::new((width / 2.) - (info_dim.0 / 2) as f32, 150.)),
+            DrawParam::default()
+                .dest(layout.place(Anchor::Top, Vec2::new(-((info_dim.0 / 2) as f32), 150.0))),
         )?;
 
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)?;
+
+        // The objective marker follows the world, so it's drawn in actual pixels.
+        if let Some(objective) = objective {
+            let target = self.viewports[0].world_to_screen(Vec2::new(objective.x, objective.y));
+
+            self.objective
+                .draw(ctx, layout.screen_size(), target, player_position, consolas)?;
+        }
+
+        if !paused && self.photo.is_none() && self.tics.is_none() {
+            let cursor = self.cursor(ctx);
+            let aim = self.viewports[0].screen_to_world(cursor);
+            let aim = na::Point2::new(aim.x, aim.y);
+
+            let on_target = enemies.iter().any(|enemy| {
+                na::distance(enemy, &aim) < Self::TARGET_RADIUS
+                    && na::distance(enemy, &player_position) < Self::TARGET_RANGE
+            });
+
+            self.crosshair
+                .draw(ctx, Point2::new(cursor.x, cursor.y), on_target)?;
+        }
+
         Ok(())
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<crate::Screen>> {
+    /// Move the level along by `dt`, the length of one fixed update step.
+    pub fn update(&mut self, ctx: &mut Context, dt: f32) -> GameResult<Option<crate::Screen>> {
+        // Everything stays frozen in place while the game or the replay is paused.
+        let replay_paused = self.playback.as_ref().is_some_and(Playback::is_paused);
+        let frozen = replay_paused || self.photo.is_some();
+
+        self.time_scale.set_speed(self.game_speed());
+        self.time_scale.set_paused(frozen);
+
+        let zoom = self.viewports[0].controller.zoom();
+
+        if let Some(spectator) = &mut self.spectator {
+            spectator.update(zoom, dt);
+        }
+
+        if let Some(photo) = &mut self.photo {
+            photo.update(zoom, dt);
+        }
+
+        if frozen {
+            self.asset_manager.audio().stop_loops();
+
+            // The free camera still gets around while the replay is paused or photos are taken.
+            let free = match &self.photo {
+                Some(photo) => Some(photo.camera()),
+                None => self
+                    .spectator
+                    .as_ref()
+                    .filter(|spectator| spectator.is_free()),
+            };
+
+            if let Some(spectator) = free {
+                let viewport = &mut self.viewports[0];
+                let size = viewport.size();
+
+                viewport
+                    .controller
+                    .follow(spectator.target(viewport.controller.position()));
+                viewport
+                    .controller
+                    .update(&mut viewport.camera, size, self.rng.effects(), dt);
+            }
+
+            return Ok(None);
+        }
+
+        // The arena stops once someone won the deathmatch.
+        let won = self
+            .versus
+            .as_ref()
+            .is_some_and(|versus| versus.winner().is_some());
+
+        if won {
+            self.asset_manager
+                .audio()
+                .music()
+                .fade(Self::PAUSED_MUSIC, Self::MUSIC_FADE);
+            self.asset_manager.audio().stop_loops();
+
+            return Ok(None);
+        }
+
+        // Boss fights come with a music of their own.
+        let track = if self.boss_bar.is_active() {
+            Self::BOSS_MUSIC
+        } else {
+            self.map.music.as_str()
+        };
+        let audio = self.asset_manager.audio();
+
+        audio.music().play(track, MusicController::CROSSFADE);
+        audio.music().fade(1.0, Self::MUSIC_FADE);
+
         if let Some(t) = self.tics {
             if self.tics.is_some() && self.dim_constant.rate != 0.5 {
-                self.dim_constant.rate = lerp(self.dim_constant.rate, 0.5, 0.1);
+                self.dim_constant.rate = damp(self.dim_constant.rate, 0.5, Self::DIM_RATE, dt);
                 self.dim_shader.send(ctx, self.dim_constant)?;
             }
 
-            if timer::ticks(ctx) % t as usize == 0 {
-                return self.inner_update(ctx);
+            if timer::ticks(ctx).is_multiple_of(t as usize) {
+                return self.step(ctx, dt);
             }
         } else {
-            return self.inner_update(ctx);
+            return self.step(ctx, dt);
         }
 
         Ok(None)
     }
 
-    fn inner_update(&mut self, ctx: &mut Context) -> GameResult<Option<crate::Screen>> {
-        let (_, height) = graphics::drawable_size(ctx);
+    /// Move the level along by a step, or a few of them while a replay is sped up.
+    fn step(&mut self, ctx: &mut Context, dt: f32) -> GameResult<Option<crate::Screen>> {
+        let steps = self.playback.as_ref().map_or(1, Playback::speed);
 
-        // Take a time step in our physics world!
-        self.physics.step();
+        for _ in 0..steps {
+            // Playing in lockstep, the level waits until the inputs of the other player are in.
+            if self
+                .lockstep
+                .as_ref()
+                .is_some_and(|lockstep| !lockstep.is_ready())
+            {
+                return Ok(None);
+            }
 
-        // Update our player
-        self.map.player.update(ctx, &mut self.physics);
-        self.camera.move_to(Vec2::new(
-            self.map.player.position(&mut self.physics).x,
-            self.map.player.position(&mut self.physics).y,
-        ));
+            let screen = self.inner_update(ctx, dt)?;
 
-        // Update our lovely clouds
-        for cloud in &mut self.clouds {
-            cloud.update(ctx);
+            self.record_checksum();
+
+            // The demo goes back to the menu once it's over, however it ended.
+            if self.demo && (screen.is_some() || self.playback.is_none()) {
+                return Ok(Some(crate::Screen::Menu));
+            }
+
+            if let Some(screen) = screen {
+                return Ok(Some(screen));
+            }
         }
 
-        if self.map.enemies.is_empty() {
-            self.draw_end_text.3 = true;
-            self.can_die = false;
+        Ok(None)
+    }
 
-            if self.draw_end_text.1.is_none() {
-                self.draw_end_text.1 = Some(timer::ticks(ctx));
-            } else if !self.draw_end_text.2 {
-                if timer::ticks(ctx) - self.draw_end_text.1.unwrap() > 30 {
-                    self.draw_end_text.0 = true;
-                    self.draw_end_text.2 = true;
-                }
-            } else {
-                self.tics = Some(1);
+    fn inner_update(
+        &mut self,
+        ctx: &mut Context,
+        real_dt: f32,
+    ) -> GameResult<Option<crate::Screen>> {
+        let (_, height) = display::screen_size(ctx);
 
-                if self.dim_constant.rate != 0.0 {
-                    self.dim_constant.rate = lerp(self.dim_constant.rate, 0.0, 0.1);
-                    self.dim_shader.send(ctx, self.dim_constant)?;
-                }
-            }
+        self.updates += 1;
+
+        let fall_speed = self.map.player.velocity(&mut self.physics).y;
+
+        // The world moves along by the scaled step, the camera and the interface by the real one.
+        self.time_scale.update(real_dt);
+
+        if let Some(speedrun) = &mut self.speedrun {
+            speedrun.update(real_dt);
         }
 
-        if self.map.player.position(&mut self.physics).y > height && self.can_die {
-            return Ok(Some(Screen::Dead));
+        let time_scale = self.time_scale.scale();
+        let dt = real_dt * time_scale;
+
+        // Take a time step in our physics world!
+        {
+            let _timer = profiler::scope("physics");
+
+            self.physics.step(time_scale);
         }
 
-        for id in 0..self.map.enemies.len() {
-            let enemy = &mut self.map.enemies[id];
+        let views = self
+            .viewports
+            .iter()
+            .map(|viewport| viewport.world_rect())
+            .collect();
 
-            if enemy.update(
-                &mut self.physics,
-                &self.asset_manager,
-                &mut self.particles,
-                &mut self.map.player,
-            ) {
-                self.map.enemies.remove(id);
-                let cam_loc = self.camera.location();
-                let org_pos = cam_loc.data.as_slice();
+        self.culling.update(views, self.updates);
 
-                self.elapsed_shake = Some((0., Vec2::new(org_pos[0], org_pos[1]), 3.));
-                self.camera_shakeke();
+        let landed = self.map.player.velocity(&mut self.physics).y <= 0.0;
 
-                break;
-            };
+        if fall_speed > Self::DUST_SPEED && landed {
+            let ferris = self.asset_manager.get_image("Some(ferris).png");
+            let position = self.map.player.position(&mut self.physics);
+
+            self.effects.burst(
+                &EmitterConfig::DUST,
+                Vec2::new(position.x, position.y + (ferris.height() / 2) as f32),
+                self.rng.effects(),
+            );
         }
 
-        for id in 0..self.map.barrels.len() {
-            if self.map.barrels[id].update(
-                &mut self.physics,
-                &self.asset_manager,
-                &mut self.particles,
-                &mut self.map.player,
-            ) {
-                self.map.barrels.remove(id);
-                let cam_loc = self.camera.location();
-                let org_pos = cam_loc.data.as_slice();
+        // Give the camera a little bump when the player lands after a big fall.
+        if fall_speed > Self::LANDING_SPEED && landed {
+            self.add_trauma(
+                ScreenShake::LIGHT
+                    * (fall_speed / Self::LANDING_SPEED).min(Self::MAX_LANDING_SHAKE),
+            );
+        }
 
-                self.elapsed_shake = Some((0., Vec2::new(org_pos[0], org_pos[1]), 5.));
-                self.camera_shakeke();
+        // Playing in lockstep, both crabs go by the inputs everyone has for this frame, the host's
+        // playing Ferris. A recording being played back takes over from the player until it's over.
+        if let Some(lockstep) = &mut self.lockstep {
+            let local = self.input.sample(&self.config.bindings, real_dt);
+
+            for (player, frame) in lockstep.advance(local).unwrap_or_default() {
+                match (player, &mut self.partner) {
+                    (0, _) => self.input.play(&frame, real_dt),
+                    (_, Some(partner)) => partner.input.play(&frame, real_dt),
+                    (_, None) => {}
+                }
+            }
+        } else {
+            match self
+                .playback
+                .as_mut()
+                .and_then(|playback| playback.next_frame())
+            {
+                Some(frame) => self.input.play(frame, real_dt),
+                None => {
+                    self.playback = None;
+                    self.spectator = None;
+                    self.input.update(&self.config.bindings, real_dt);
+                }
             }
         }
 
-        if let Some(s) = self.elapsed_shake {
-            if s.0 < 1. {
-                self.camera_shakeke();
-            } else {
-                self.camera.move_to(s.1);
-                self.elapsed_shake = None;
-            }
+        if let Some(recording) = &mut self.recording {
+            recording.push(self.input.frame());
         }
 
-        for id in 0..self.particles.len() {
-            let sys = &mut self.particles[id];
+        for action in Action::ALL.iter().copied() {
+            if self.input.just_pressed(action) {
+                if let Some(prompt) = self.prompts.done(action) {
+                    self.save.shown_prompts.push(prompt.name().to_string());
+                    self.save_dirty = true;
+                }
 
-            if sys.update(ctx, &mut self.physics) {
-                self.particles.remove(id);
+                self.open_gates(|prompt| prompt.is_done_by(action));
+            }
 
-                break;
+            if self.input.is_buffered(action) && self.action(action) {
+                self.input.consume(action);
             }
         }
 
-        for v in &mut self.ui_lerp {
-            match v.0.as_str() {
-                "ammo" => {
-                    if self.map.player.ammo <= 0.0 {
-                        self.map.player.ammo = 0.0;
-                    } else {
-                        self.map.player.ammo = lerp(self.map.player.ammo, *v.1, 0.3);
-                    }
-                }
-
-                "health" => {
-                    // TODO: Health lerping
-                }
+        // Update our player. The player can't move around while the camera is busy with a cinematic
+        // or someone is talking, or while down in versus.
+        if !self.is_cinematic() && self.dialogue.is_none() {
+            let idle = InputState::new();
+            let input = if self.is_down() { &idle } else { &self.input };
+            let impacts = self.map.player.update(&mut self.physics, input, dt);
+
+            for impact in impacts {
+                self.decals.stamp(
+                    DecalKind::BulletHole,
+                    Vec2::new(impact.x, impact.y),
+                    self.rng.effects(),
+                );
+            }
+        }
 
-                "using" => {
-                    self.map.using.as_mut().unwrap().1 =
-                        lerp(self.map.using.as_mut().unwrap().1, 0.0, 0.05);
-                }
+        self.update_partner(ctx, real_dt, dt);
 
-                _ => panic!(),
+        for remote in &mut self.remotes {
+            for impact in remote.update(&mut self.physics, self.time, dt) {
+                self.decals.stamp(
+                    DecalKind::BulletHole,
+                    Vec2::new(impact.x, impact.y),
+                    self.rng.effects(),
+                );
             }
         }
 
-        Ok(None)
-    }
+        let player_position = self.map.player.position(&mut self.physics);
+        let player_velocity = self.map.player.velocity(&mut self.physics);
+        let player_speed = player_velocity.norm();
 
-    pub fn key_press(&mut self, keycode: KeyCode) -> Option<crate::Screen> {
-        match keycode {
-            KeyCode::S => {
-                let ui_lerp = self.ui_lerp.clone();
-                let turbofish_shoot = self.asset_manager.get_sound("Some(turbofish_shoot).mp3");
+        let thrusting =
+            self.input.is_held(Action::Jump) && !self.is_cinematic() && self.dialogue.is_none();
+        let ground = self.map.player.ground(&mut self.physics);
+        let stepped = self
+            .map
+            .player
+            .animate(&mut self.physics, dt)
+            .contains(&"step");
+
+        self.movement_sounds.update(
+            self.asset_manager.audio(),
+            ground,
+            player_velocity,
+            thrusting,
+            stepped,
+        );
 
-                if let Some(bullet) =
-                    self.map
-                        .player
-                        .shoot(&mut self.physics, &self.asset_manager, &self.map.weapon)
-                {
-                    play!(turbofish_shoot);
+        let cursor = self.cursor(ctx);
+        let moving = if player_velocity.x.abs() > Self::LOOK_AHEAD_SPEED {
+            player_velocity.x
+        } else {
+            0.0
+        };
 
-                    if let PlayerWeapon::Turbofish(_fish) = &bullet {
-                        let cur_ammo = ui_lerp.get("ammo").unwrap();
-                        self.ui_lerp.insert(String::from("ammo"), *cur_ammo - 1.);
-                    }
+        let partner_position = self.partner_position();
+        let split = self.viewports.len() > 1;
 
-                    self.map.player.weapons.push(bullet);
-                }
+        if !self.config.debug.camera {
+            self.manual_zoom = None;
+        }
+
+        let manual_zoom = self.manual_zoom;
+        let boss_fight = self.boss_bar.is_active();
+        let talking = self.dialogue.is_some();
+        let spectator = self
+            .spectator
+            .as_ref()
+            .filter(|spectator| spectator.is_free());
+
+        for (view, viewport) in self.viewports.iter_mut().enumerate() {
+            let size = viewport.size();
+            let controller = &mut viewport.controller;
+
+            // The free camera of a replay goes wherever it's moved instead of looking ahead.
+            if spectator.is_some() {
+                controller.look(0.0, Vec2::new(0.0, 0.0));
+            } else {
+                controller.look(moving, cursor - size / 2.0);
             }
-            KeyCode::Up => {
-                self.tics = Some(6);
+
+            // Zoom out a little while moving fast so the player can see what's coming, and a bit
+            // more during boss fights.
+            let mut zoom = if player_speed > Self::HIGH_SPEED {
+                Self::HIGH_SPEED_ZOOM
+            } else {
+                1.0
+            };
+
+            if boss_fight {
+                zoom = zoom.min(Self::BOSS_ZOOM);
             }
-            KeyCode::Key7 => {
-                return Some(Screen::Menu);
+            let mut target = Vec2::new(player_position.x, player_position.y);
+
+            // Playing together, the split screen gives player two a view of their own. Otherwise
+            // the camera keeps both of the crabs in sight.
+            if let Some(partner) = partner_position {
+                let partner = Vec2::new(partner.x, partner.y);
+
+                if !split {
+                    let (center, framed) = coop::framing(
+                        target,
+                        partner,
+                        size,
+                        Self::COOP_MARGIN,
+                        Self::COOP_MIN_ZOOM,
+                    );
+
+                    target = center;
+                    zoom = zoom.min(framed);
+                } else if view == 1 {
+                    target = partner;
+                }
             }
-            KeyCode::Key8 => {
-                exit(0);
+
+            if let Some(spectator) = spectator {
+                target = spectator.target(target);
             }
-            KeyCode::Down => match self.map.using.as_ref().unwrap().0.as_str() {
-                "Turbofish Gun" => {
-                    self.map.using = Some((String::from("Grappling Gun"), 1.0));
-                    self.map.weapon = WeaponType::Grappling;
-                }
 
-                "Grappling Gun" => {
-                    self.map.using = Some((String::from("Turbofish Gun"), 1.0));
-                    self.map.weapon = WeaponType::Turbofish;
-                }
+            // Dialogues get a closer look at who's talking, and the zoom picked while debugging the
+            // camera wins over everything.
+            if talking {
+                zoom = Self::DIALOGUE_ZOOM;
+            }
 
-                _ => {
-                    panic!()
-                }
-            },
-            _ => (),
+            match manual_zoom {
+                Some(manual) => controller.set_zoom(manual, 0.1),
+                None => controller.set_zoom(zoom, Self::ZOOM_DURATION),
+            }
+
+            controller.follow(target);
+            controller.update(&mut viewport.camera, size, self.rng.effects(), real_dt);
         }
 
-        None
-    }
+        // Sounds are heard from the first viewport, even when the screen is split.
+        let viewport = &self.viewports[0];
+        let listener = viewport.camera.location();
 
-    pub fn key_up_event(&mut self, keycode: KeyCode) {
-        if keycode == KeyCode::Up {
-            self.tics = None;
-            self.dim_constant.rate = 1.0;
-        }
-        self.map.player.set_direction(Direction::None);
-    }
+        self.asset_manager.audio().set_listener(Some(Listener {
+            position: na::Point2::new(listener.x, listener.y),
+            reach: viewport.size().x / 2.0 / viewport.controller.zoom(),
+        }));
 
-    /// Give the camera a shakey shakey.
-    fn camera_shakeke(&mut self) {
-        let mut rng = rand::thread_rng();
+        self.minimap.update(player_position);
 
-        let elapsed = self.elapsed_shake.unwrap();
-        let magnitude = elapsed.2;
+        self.time += real_dt;
 
-        let x = rng.gen_range(-1.0..=1.0) * magnitude;
-        let y = rng.gen_range(-1.0..=1.0) * magnitude;
+        if let Some((autosave, shown)) = &mut self.autosave {
+            *shown += real_dt;
 
-        self.camera.move_by(Vec2::new(x, y));
+            if autosave.is_finished() && *shown >= Self::SAVING_TIME {
+                self.autosave = None;
+            }
+        }
+        self.save.playtime += real_dt;
+        self.save.stats.distance += player_speed * dt;
+
+        if let Some(id) = self
+            .map
+            .checkpoints
+            .iter()
+            .position(|checkpoint| player_position.x >= checkpoint.x)
+        {
+            self.checkpoint = self.map.checkpoints.remove(id);
+            self.events.publish(GameEvent::CheckpointReached);
+            self.autosave(ctx);
+        }
+
+        // Head for the next checkpoint, then take out whatever evildoers are left.
+        let objective = if let Some(checkpoint) = self.map.checkpoints.first() {
+            Some(Objective {
+                label: "Reach the checkpoint",
+                position: *checkpoint,
+            })
+        } else {
+            let physics = &mut self.physics;
+
+            self.map
+                .enemies
+                .iter()
+                .filter_map(|enemy| enemy.position(physics).ok())
+                .min_by(|a, b| {
+                    na::distance(a, &player_position).total_cmp(&na::distance(b, &player_position))
+                })
+                .map(|position| Objective {
+                    label: "Take out the evildoers",
+                    position,
+                })
+        };
+
+        if self.objective.set(objective) {
+            let label = self.objective.objective().unwrap().label;
+
+            self.toasts.push(format!("New objective: {}", label));
+        }
+
+        self.objective.update(real_dt);
+
+        // The boss fight starts once the player gets close enough to the boss.
+        let physics = &mut self.physics;
+
+        match self.map.enemies.iter().find(|enemy| enemy.boss().is_some()) {
+            Some(boss) => {
+                if !self.boss_bar.is_active()
+                    && boss.position(physics).is_ok_and(|position| {
+                        na::distance(&position, &player_position) < Self::BOSS_RANGE
+                    })
+                {
+                    let name = boss.boss().unwrap();
+
+                    self.boss_bar.start(name, Enemy::BOSS_PHASES);
+                    self.toasts.push(format!("{} appears!", name));
+                }
+
+                let fraction = boss.health(physics).map_or(0.0, |health| health.fraction());
+
+                self.boss_bar.update(fraction, boss.phase(physics), real_dt);
+            }
+            None => {
+                if self.boss_bar.is_active() {
+                    self.boss_bar.end();
+                }
+
+                self.boss_bar.update(0.0, Enemy::BOSS_PHASES, real_dt);
+            }
+        }
+
+        // Start the dialogue of the first trigger the player got past.
+        if self.dialogue.is_none() {
+            if let Some(id) = self
+                .map
+                .dialogues
+                .iter()
+                .position(|(x, _)| player_position.x >= *x)
+            {
+                let (_, script) = self.map.dialogues.remove(id);
+
+                self.dialogue = Some(Dialogue::parse(
+                    &self
+                        .asset_manager
+                        .get_translated_file(&format!("/dialogues/{}", script)),
+                ));
+            }
+        }
+
+        if let Some(dialogue) = &mut self.dialogue {
+            dialogue.update(real_dt);
+        }
+
+        if let Some(script) = &mut self.script {
+            let commands = script.update(player_position.x);
+
+            self.run_script(ctx, commands);
+        }
+
+        let queued = std::mem::take(&mut self.queued);
+
+        self.run_script(ctx, queued);
+
+        // Show the prompt of the first trigger the player got past, unless it has been seen before.
+        if !self.prompts.is_showing() {
+            if let Some(id) = self
+                .map
+                .prompts
+                .iter()
+                .position(|(x, _)| player_position.x >= *x)
+            {
+                let (_, prompt) = self.map.prompts.remove(id);
+                let seen = self
+                    .save
+                    .shown_prompts
+                    .iter()
+                    .any(|name| name == prompt.name());
+
+                // A gate waiting on the prompt needs it to be done again, so it's shown again.
+                if !seen || self.map.gates.iter().any(|gate| gate.prompt() == prompt) {
+                    self.prompts.show(prompt);
+                }
+            }
+        }
+
+        self.prompts.update(real_dt);
+
+        if self.save_dirty {
+            self.write_save(ctx);
+        }
+
+        // Update our lovely clouds
+        for cloud in &mut self.clouds {
+            cloud.update(ctx, dt);
+        }
+
+        for gate in &mut self.map.gates {
+            gate.update(dt);
+        }
+
+        // The boss rush drops its bosses in one at a time, it isn't over once they're gone.
+        let boss_alive = !self.map.enemies.is_empty();
+
+        match self
+            .boss_rush
+            .as_mut()
+            .and_then(|rush| rush.update(boss_alive, dt))
+        {
+            Some(RushEvent::Spawn(name, toughness)) => self.spawn_rush_boss(ctx, name, toughness),
+            Some(RushEvent::Break) => self.open_shop(),
+            Some(RushEvent::Won) => {
+                self.dispatch_events();
+
+                return Ok(Some(Screen::Results));
+            }
+            None => {}
+        }
+
+        // Arenas are won by frags, not by taking out the evildoers.
+        if self.map.enemies.is_empty() && self.versus.is_none() && self.boss_rush.is_none() {
+            if !self.draw_end_text.3 {
+                self.board = Some(HighScoreBoard::open(
+                    ctx,
+                    &self.high_score_table(),
+                    self.stats(),
+                    &self.config.leaderboard,
+                ));
+
+                // The daily challenge doesn't count towards the campaign.
+                if self.daily.is_none() {
+                    self.save.complete(&self.level);
+                    self.save.loadout = Some(Loadout {
+                        weapon: self.map.weapon,
+                        magazine: self.map.player.ammo.magazine,
+                        reserve: self.map.player.ammo.reserve,
+                    });
+                }
+                self.events.publish(GameEvent::LevelCompleted);
+
+                if let Some(speedrun) = &mut self.speedrun {
+                    if speedrun.finish(ctx) {
+                        self.toasts.push(String::from("New personal best!"));
+                    }
+                }
+                self.autosave(ctx);
+            }
+
+            self.draw_end_text.3 = true;
+            self.can_die = false;
+
+            if let Some(waited) = &mut self.draw_end_text.1 {
+                if !self.draw_end_text.2 {
+                    *waited += real_dt;
+
+                    if *waited > Self::END_TEXT_DELAY {
+                        self.draw_end_text.0 = true;
+                        self.draw_end_text.2 = true;
+                    }
+                } else {
+                    self.tics = Some(1);
+
+                    if self.dim_constant.rate != 0.0 {
+                        self.dim_constant.rate =
+                            damp(self.dim_constant.rate, 0.0, Self::DIM_RATE, real_dt);
+                        self.dim_shader.send(ctx, self.dim_constant)?;
+                    }
+                }
+            } else {
+                self.draw_end_text.1 = Some(0.0);
+            }
+        }
+
+        if self.versus.is_some() {
+            self.update_versus(height, dt);
+        } else if (self.map.player.position(&mut self.physics).y > height
+            || self
+                .map
+                .player
+                .health(&self.physics)
+                .map_or(true, |health| health.is_dead()))
+            && self.can_die
+            && !self.map.player.invincible
+        {
+            self.asset_manager.audio().play(Sfx::Death);
+            self.save.stats.deaths += 1;
+            self.dispatch_events();
+
+            // Going down ends the boss rush, there are no checkpoints to go back to.
+            if self.boss_rush.is_some() {
+                return Ok(Some(Screen::Results));
+            }
+
+            return Ok(Some(Screen::Dead));
+        }
+
+        if self.versus.is_some() {
+            self.friendly_fire();
+        }
+
+        // Hand the bullets that hit something over to the enemies they hit.
+        let mut bullet_hits = self.map.player.bullet_hits(&mut self.physics);
+
+        if let Some(partner) = &mut self.partner {
+            bullet_hits.extend(partner.player.bullet_hits(&mut self.physics));
+        }
+
+        for remote in &mut self.remotes {
+            bullet_hits.extend(remote.player.bullet_hits(&mut self.physics));
+        }
+        let ai_timer = profiler::scope("ai");
+
+        // Enemies get provoked by whichever crab comes close.
+        let targets: Vec<_> = std::iter::once(player_position)
+            .chain(partner_position)
+            .collect();
+        let entities = self.physics.entities_mut();
+
+        entities.update_ai(&targets, dt);
+        entities.update_sprites(dt);
+
+        for id in 0..self.map.enemies.len() {
+            let enemy = &mut self.map.enemies[id];
+            let hits = enemy.hits();
+            let position = match enemy.position(&mut self.physics) {
+                Ok(position) => position,
+                Err(_) => {
+                    // The body went missing, there's nothing left of the enemy to fight.
+                    self.map.enemies.remove(id);
+
+                    break;
+                }
+            };
+            let health = enemy
+                .health(&self.physics)
+                .map_or(0.0, |health| health.current());
+            let entity = self.physics.entities().entity(enemy.handle());
+            let hit = bullet_hits
+                .iter()
+                .find(|(hit, _)| Some(*hit) == entity)
+                .map(|(_, position)| *position);
+
+            // Far away enemies still take their hits right away.
+            if self
+                .culling
+                .step(id, Vec2::new(position.x, position.y), dt)
+                .is_none()
+                && hit.is_none()
+            {
+                continue;
+            }
+
+            // Enemies go after whichever crab is closest.
+            let partner_closer = partner_position.is_some_and(|partner| {
+                na::distance(&position, &partner) < na::distance(&position, &player_position)
+            });
+            let target = match &mut self.partner {
+                Some(partner) if partner_closer => &mut partner.player,
+                _ => &mut self.map.player,
+            };
+
+            let killed = enemy.update(
+                &mut self.physics,
+                &self.asset_manager,
+                &mut self.objects,
+                &mut self.text_effects,
+                target,
+                hit,
+                self.rng.run(),
+            );
+
+            if enemy.hits() > hits {
+                // A killed enemy is despawned already, along with its health.
+                let left = if killed {
+                    0.0
+                } else {
+                    enemy
+                        .health(&self.physics)
+                        .map_or(0.0, |health| health.current())
+                };
+
+                self.events.publish(GameEvent::DamageDealt {
+                    victim: Victim::Enemy,
+                    amount: health - left,
+                    hits: enemy.hits() - hits,
+                });
+            }
+
+            if killed {
+                let enemy = self.map.enemies.remove(id);
+
+                self.effects.burst(
+                    &EmitterConfig::EXPLOSION,
+                    Vec2::new(position.x, position.y),
+                    self.rng.effects(),
+                );
+                self.decals.stamp(
+                    DecalKind::Scorch,
+                    Vec2::new(position.x, position.y),
+                    self.rng.effects(),
+                );
+                self.flash(Vec2::new(position.x, position.y), Lighting::EXPLOSION);
+                self.post.play(PostEffect::Aberration(0.5));
+                self.add_trauma(ScreenShake::MEDIUM);
+                self.events.publish(GameEvent::EnemyKilled {
+                    boss: enemy.boss(),
+                    headshot: enemy.headshot(),
+                });
+
+                break;
+            }
+        }
+
+        drop(ai_timer);
+
+        // The more gophers are after Ferris the tenser the music gets. It calms down slowly once
+        // the fighting is over.
+        let aggroed = self.physics.entities().aggroed();
+        let tension = (aggroed as f32 * Self::TENSION_PER_ENEMY).min(1.0);
+        self.tension = if tension > self.tension {
+            (self.tension + Self::TENSION_RISE * real_dt).min(tension)
+        } else {
+            (self.tension - Self::TENSION_FALL * real_dt).max(tension)
+        };
+
+        self.asset_manager.audio().music().set_tension(self.tension);
+
+        for id in 0..self.map.barrels.len() {
+            let barrel_position = match self.map.barrels[id].position(&mut self.physics) {
+                Ok(position) => position,
+                Err(_) => {
+                    self.map.barrels.remove(id);
+
+                    break;
+                }
+            };
+
+            self.map.barrels[id].burn(
+                &mut self.physics,
+                &self.asset_manager,
+                &mut self.effects,
+                self.rng.effects(),
+                dt,
+            );
+
+            if self.map.barrels[id].update(
+                &mut self.physics,
+                &self.asset_manager,
+                &mut self.objects,
+                &mut coop::crabs(&mut self.map.player, &mut self.partner, &mut self.remotes),
+                self.rng.run(),
+                dt,
+            ) {
+                let chain = self.map.barrels.remove(id).chain();
+
+                self.effects.burst(
+                    &EmitterConfig::EXPLOSION,
+                    Vec2::new(barrel_position.x, barrel_position.y),
+                    self.rng.effects(),
+                );
+                self.decals.stamp(
+                    DecalKind::Scorch,
+                    Vec2::new(barrel_position.x, barrel_position.y),
+                    self.rng.effects(),
+                );
+                self.flash(
+                    Vec2::new(barrel_position.x, barrel_position.y),
+                    Lighting::EXPLOSION,
+                );
+                self.post.play(PostEffect::Aberration(1.0));
+                self.add_trauma(ScreenShake::HEAVY);
+
+                self.events.publish(GameEvent::BarrelExploded { chain });
+
+                // The blast sets off the barrels around it.
+                for barrel in &mut self.map.barrels {
+                    let caught = barrel.position(&mut self.physics).is_ok_and(|position| {
+                        na::distance(&position, &barrel_position) < Barrel::EXPLOSION_RADIUS
+                    });
+
+                    if caught {
+                        barrel.ignite(chain);
+                    }
+                }
+
+                // The blast smashes the crates around the barrel.
+                for crate_box in &mut self.map.crates {
+                    let caught = crate_box.position(&mut self.physics).is_ok_and(|position| {
+                        na::distance(&position, &barrel_position) < Barrel::EXPLOSION_RADIUS
+                    });
+
+                    if caught {
+                        crate_box.damage(&mut self.physics, Barrel::EXPLOSION_DAMAGE);
+                    }
+                }
+
+                // And hurts the player if they're standing too close.
+                if na::distance(&player_position, &barrel_position) < Barrel::EXPLOSION_RADIUS {
+                    let amount = Barrel::PLAYER_DAMAGE * self.difficulty.damage_multiplier();
+
+                    self.map.player.damage(&mut self.physics, amount);
+                    self.events.publish(GameEvent::DamageDealt {
+                        victim: Victim::Player,
+                        amount,
+                        hits: 1,
+                    });
+                }
+
+                let partner_caught = partner_position.is_some_and(|partner| {
+                    na::distance(&partner, &barrel_position) < Barrel::EXPLOSION_RADIUS
+                });
+
+                if let Some(partner) = self.partner.as_mut().filter(|_| partner_caught) {
+                    let amount = Barrel::PLAYER_DAMAGE * self.difficulty.damage_multiplier();
+
+                    partner.player.damage(&mut self.physics, amount);
+                    self.events.publish(GameEvent::DamageDealt {
+                        victim: Victim::Player,
+                        amount,
+                        hits: 1,
+                    });
+                }
+
+                break;
+            }
+        }
+
+        for id in 0..self.map.crates.len() {
+            if self.map.crates[id].update(
+                &mut self.physics,
+                &mut coop::crabs(&mut self.map.player, &mut self.partner, &mut self.remotes),
+            ) {
+                let broken = self.map.crates.remove(id);
+                let pickup = broken.break_apart(
+                    &mut self.physics,
+                    &self.asset_manager,
+                    &mut self.objects,
+                    self.rng.run(),
+                    self.difficulty.ammo_drops(),
+                );
+
+                // A crate without a body has nothing left to break into.
+                if let Ok(pickup) = pickup {
+                    self.pickups.push(pickup);
+                }
+
+                break;
+            }
+        }
+
+        for id in 0..self.pickups.len() {
+            let position = match self.pickups[id].position(&mut self.physics) {
+                Ok(position) => position,
+                Err(_) => {
+                    self.pickups.remove(id);
+
+                    break;
+                }
+            };
+            if self
+                .culling
+                .step(id, Vec2::new(position.x, position.y), dt)
+                .is_none()
+            {
+                continue;
+            }
+
+            let by_partner = match &self.partner {
+                Some(partner) if !partner.is_down() => {
+                    self.pickups[id].update(&mut self.physics, &partner.player)
+                }
+                _ => false,
+            };
+
+            if by_partner || self.pickups[id].update(&mut self.physics, &self.map.player) {
+                let pickup = self.pickups.remove(id);
+
+                pickup.destroy(&mut self.physics);
+                self.collect_pickup(pickup.kind, by_partner);
+
+                // Only one thing can be taken from the shop of the boss rush.
+                if self.boss_rush.as_ref().is_some_and(BossRush::is_on_break) {
+                    self.close_shop();
+                }
+
+                break;
+            }
+        }
+
+        let particles_timer = profiler::scope("particles");
+
+        self.objects.update(&mut self.physics, dt);
+        self.effects.update(dt);
+
+        drop(particles_timer);
+
+        if let Some(lighting) = &mut self.lighting {
+            lighting.update(dt);
+        }
+
+        self.post.set_health(
+            self.map
+                .player
+                .health(&self.physics)
+                .map_or(0.0, |health| health.fraction()),
+        );
+        self.post.update(real_dt);
+
+        self.text_effects.update(dt);
+        self.kill_feed.update(real_dt);
+        self.toasts.update(real_dt);
+        self.score.update(real_dt);
+        self.crosshair.update(real_dt);
+
+        self.dispatch_events();
+        self.hud.update(
+            &self.map.player,
+            &self.physics,
+            self.map.weapon,
+            &self.score,
+            real_dt,
+        );
+
+        for v in &mut self.ui_lerp {
+            match v.0.as_str() {
+                "using" => {
+                    self.map.using.1 = damp(self.map.using.1, 0.0, Self::USING_FADE, real_dt);
+                }
+
+                _ => panic!(),
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn key_press(&mut self, ctx: &Context, keycode: KeyCode) -> Option<crate::Screen> {
+        if self.config.debug.console && keycode == KeyCode::Grave {
+            self.console.toggle();
+
+            return None;
+        }
+
+        // The open console gets everything typed.
+        if self.console.is_open() {
+            if let Some(line) = self.console.key_press(keycode) {
+                self.run_console(&line);
+            }
+
+            return None;
+        }
+
+        // Typing in a name for a new high score comes first.
+        if let Some(board) = self.board.as_mut().filter(|board| board.is_entering()) {
+            board.key_press(ctx, keycode);
+
+            return None;
+        }
+
+        if let Some(photo) = &mut self.photo {
+            match photo.key(keycode, true) {
+                Some(PhotoAction::Zoom(factor)) => {
+                    let controller = &mut self.viewports[0].controller;
+
+                    controller.set_zoom(controller.zoom_target() * factor, 0.1);
+                }
+                Some(PhotoAction::Filter(filter)) => self.post.set_filter(filter),
+                Some(PhotoAction::Leave) => self.leave_photo_mode(),
+                None => {}
+            }
+
+            return None;
+        }
+
+        if self.dialogue.is_some() && matches!(keycode, KeyCode::Return | KeyCode::Space) {
+            self.advance_dialogue();
+
+            return None;
+        }
+
+        // Watching a replay comes with controls of its own.
+        if let Some(playback) = &mut self.playback {
+            match keycode {
+                KeyCode::Space => {
+                    playback.toggle_pause();
+
+                    return None;
+                }
+                KeyCode::Tab => {
+                    playback.toggle_speed();
+
+                    return None;
+                }
+                KeyCode::F => {
+                    if let Some(spectator) = &mut self.spectator {
+                        spectator.toggle(self.viewports[0].camera.location());
+                    }
+
+                    return None;
+                }
+                KeyCode::E => {
+                    match playback.recording().share(ctx) {
+                        Ok(path) => {
+                            log::info!("Shared the replay to {}", path.display());
+
+                            self.toasts
+                                .push(format!("Replay shared to {}", path.display()));
+                        }
+                        Err(error) => log::error!("Cannot share the replay: {}", error),
+                    }
+
+                    return None;
+                }
+                // Switching to the game screen starts the level over, and the replay with it.
+                KeyCode::R => return Some(Screen::Play),
+                _ => {}
+            }
+
+            if let Some(spectator) = &mut self.spectator {
+                if spectator.key(keycode, true) {
+                    return None;
+                }
+            }
+        }
+
+        // The scoreboard of versus stays open while Tab is held, and once someone won the arena
+        // is left with Enter.
+        if let Some(versus) = &mut self.versus {
+            match keycode {
+                KeyCode::Tab => {
+                    versus.showing = true;
+
+                    return None;
+                }
+                KeyCode::Return if versus.winner().is_some() => return Some(Screen::Menu),
+                _ => {}
+            }
+        }
+
+        match keycode {
+            KeyCode::Escape => self.pause(),
+            KeyCode::Up => {
+                self.tics = Some(6);
+            }
+
+            KeyCode::Key7 => {
+                return Some(Screen::Menu);
+            }
+            KeyCode::Key8 => {
+                exit(0);
+            }
+            _ => self.input.key_down(keycode, &self.config.bindings),
+        }
+
+        None
+    }
+
+    pub fn gamepad_button_press(&mut self, ctx: &Context, button: Button) -> Option<crate::Screen> {
+        if let Some(board) = self.board.as_mut().filter(|board| board.is_entering()) {
+            board.gamepad_button_press(ctx, button);
+
+            return None;
+        }
+
+        let won = self
+            .versus
+            .as_ref()
+            .is_some_and(|versus| versus.winner().is_some());
+
+        match button {
+            Button::Start | Button::South if won => return Some(crate::Screen::Menu),
+            Button::Start => self.pause(),
+            Button::South if self.dialogue.is_some() => self.advance_dialogue(),
+            _ => self.input.button_down(button, &self.config.bindings),
+        }
+
+        None
+    }
+
+    pub fn text_input(&mut self, character: char) {
+        if self.console.is_open() {
+            self.console.text_input(character);
+
+            return;
+        }
+
+        if let Some(board) = &mut self.board {
+            board.text_input(character);
+        }
+    }
+
+    pub fn gamepad_button_release(&mut self, button: Button) {
+        self.input.button_up(button);
+    }
+
+    pub fn gamepad_axis(&mut self, axis: Axis, value: f32) {
+        self.input.axis_event(axis, value);
+    }
+
+    /// Let player two join in with a crab of their own, next to Ferris.
+    pub fn add_partner(&mut self, ctx: &mut Context) {
+        let ferris = self.map.player.position(&mut self.physics);
+        let mut partner = Partner::new(ctx, &mut self.physics, &self.asset_manager, ferris.x);
+
+        partner.input.set_toggled(self.config.toggled());
+        self.hud.add_partner(
+            &partner.player,
+            &self.physics,
+            partner.weapon,
+            &self.asset_manager,
+        );
+        self.partner = Some(partner);
+
+        let id = self.partner_id();
+
+        if let Some((versus, id)) = self.versus.as_mut().zip(id) {
+            versus.join(id);
+        }
+    }
+
+    /// A key on player two's keyboard went down or up. Either player can pause.
+    pub fn partner_key(&mut self, keycode: KeyCode, down: bool) {
+        let partner = match &mut self.partner {
+            Some(partner) => partner,
+            None => return,
+        };
+
+        if !down {
+            partner.input.key_up(keycode);
+        } else if keycode == KeyCode::Escape {
+            self.pause();
+        } else {
+            partner.input.key_down(keycode, &self.config.bindings);
+        }
+    }
+
+    /// A button on player two's controller went down or up.
+    pub fn partner_button(&mut self, button: Button, down: bool) {
+        let partner = match &mut self.partner {
+            Some(partner) => partner,
+            None => return,
+        };
+
+        if !down {
+            partner.input.button_up(button);
+        } else if button == Button::Start {
+            self.pause();
+        } else {
+            partner.input.button_down(button, &self.config.bindings);
+        }
+    }
+
+    pub fn partner_axis(&mut self, axis: Axis, value: f32) {
+        if let Some(partner) = &mut self.partner {
+            partner.input.axis_event(axis, value);
+        }
+    }
+
+    /// The screen got touched, or clicked while the touch controls are on.
+    pub fn pointer_down(&mut self, ctx: &Context, x: f32, y: f32) {
+        if !self.config.touch_controls {
+            return;
+        }
+
+        if self.dialogue.is_some() {
+            self.advance_dialogue();
+
+            return;
+        }
+
+        let layout = Self::touch_layout(ctx);
+
+        if self
+            .touch
+            .press(&layout, layout.to_virtual(Vec2::new(x, y)))
+            == Some(TouchButton::Pause)
+        {
+            self.pause();
+        }
+
+        self.input.touch(self.touch.held());
+    }
+
+    pub fn pointer_move(&mut self, ctx: &Context, x: f32, y: f32) {
+        let layout = Self::touch_layout(ctx);
+
+        if self.config.touch_controls && self.touch.drag(layout.to_virtual(Vec2::new(x, y))) {
+            self.input.touch(self.touch.held());
+        }
+    }
+
+    pub fn pointer_up(&mut self) {
+        if self.config.touch_controls {
+            self.touch.release();
+            self.input.touch(vec![]);
+        }
+    }
+
+    /// The layout the touch controls are drawn with.
+    fn touch_layout(ctx: &Context) -> Layout {
+        let (width, height) = display::screen_size(ctx);
+
+        Layout::new(Vec2::new(width, height))
+    }
+
+    /// Do what was just pressed. Anything that is held down instead is up to the player. Returns
+    /// false if it can't be done yet, so the press stays buffered.
+    fn action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Fire => self.shoot(),
+            Action::SwitchWeapon => {
+                if !self.has_modifier(Modifier::TurbofishOnly) {
+                    self.switch_weapon();
+                }
+
+                true
+            }
+            Action::Map => {
+                self.minimap.expanded = !self.minimap.expanded;
+
+                true
+            }
+            Action::Left | Action::Right | Action::Jump | Action::Reload => true,
+        }
+    }
+
+    /// Light up a dark level for a moment.
+    fn flash(&mut self, position: Vec2, flash: (f32, graphics::Color, f32)) {
+        if !self.config.flashing {
+            return;
+        }
+
+        if let Some(lighting) = &mut self.lighting {
+            lighting.flash(position, flash);
+        }
+    }
+
+    /// Fire the weapon in hand. Returns false if there was nothing to fire.
+    fn shoot(&mut self) -> bool {
+        if self.is_cinematic() || self.dialogue.is_some() || self.is_down() {
+            return false;
+        }
+
+        if let Some(bullet) =
+            self.map
+                .player
+                .shoot(&mut self.physics, &self.asset_manager, &self.map.weapon)
+        {
+            let position = self.map.player.position(&mut self.physics);
+
+            self.muzzle_flash(position);
+            self.crosshair.shoot(self.map.weapon);
+
+            if let PlayerWeapon::Turbofish(_) = bullet {
+                self.events.publish(GameEvent::ShotFired);
+                self.shots += 1;
+            }
+
+            self.map.player.weapons.push(bullet);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fire the weapon player two has in hand. Returns false if there was nothing to fire.
+    fn shoot_partner(&mut self) -> bool {
+        let partner = match &mut self.partner {
+            Some(partner) => partner,
+            None => return false,
+        };
+
+        let bullet =
+            match partner
+                .player
+                .shoot(&mut self.physics, &self.asset_manager, &partner.weapon)
+            {
+                Some(bullet) => bullet,
+                None => return false,
+            };
+
+        let position = partner.player.position(&mut self.physics);
+
+        if let PlayerWeapon::Turbofish(_) = bullet {
+            self.events.publish(GameEvent::ShotFired);
+        }
+
+        partner.player.weapons.push(bullet);
+        self.muzzle_flash(position);
+
+        true
+    }
+
+    /// The bang of a weapon going off in the hands of a crab at `position`.
+    fn muzzle_flash(&mut self, position: na::Point2<f32>) {
+        let muzzle = Vec2::new(position.x + Self::MUZZLE_OFFSET, position.y);
+
+        self.asset_manager.audio().play(Sfx::Shoot);
+        self.effects
+            .burst(&EmitterConfig::MUZZLE_FLASH, muzzle, self.rng.effects());
+        self.flash(muzzle, Lighting::MUZZLE_FLASH);
+        self.add_trauma(ScreenShake::LIGHT);
+    }
+
+    /// Whether Ferris is down in versus, waiting to come back.
+    fn is_down(&self) -> bool {
+        self.versus
+            .as_ref()
+            .is_some_and(|versus| versus.down.is_some())
+    }
+
+    /// The player id of Ferris in versus.
+    fn ferris_id(&self) -> u8 {
+        self.player_id.unwrap_or(0)
+    }
+
+    /// The player id of player two in versus. Player two only makes it onto the scoreboard when
+    /// not playing online, where the ids are handed out by the host.
+    fn partner_id(&self) -> Option<u8> {
+        match (&self.partner, self.player_id) {
+            (Some(_), None) => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Where a crab coming back in versus spawns, as far away from the others as it gets. Ferris
+    /// is one of the others unless it's Ferris coming back.
+    fn spawn_point(&mut self, ferris: bool) -> na::Point2<f32> {
+        let physics = &mut self.physics;
+        let mut others: Vec<na::Point2<f32>> = self
+            .remotes
+            .iter_mut()
+            .map(|remote| remote.player.position(physics))
+            .collect();
+
+        if ferris {
+            others.extend(self.partner_position());
+        } else {
+            others.push(self.map.player.position(&mut self.physics));
+        }
+
+        versus::spawn_point(&self.spawns, &others).unwrap_or(self.checkpoint)
+    }
+
+    /// Knock Ferris down in versus instead of ending the run, and bring Ferris back at a spawn
+    /// point after a moment.
+    fn update_versus(&mut self, height: f32, dt: f32) {
+        let id = self.ferris_id();
+        let versus = match &mut self.versus {
+            Some(versus) => versus,
+            None => return,
+        };
+
+        if let Some(time) = &mut versus.down {
+            *time -= dt;
+
+            if *time <= 0.0 {
+                versus.down = None;
+
+                let position = self.spawn_point(true);
+
+                self.map.player.respawn(&mut self.physics, position);
+            }
+
+            return;
+        }
+
+        let fell = self.map.player.position(&mut self.physics).y > height;
+
+        if (fell
+            || self
+                .map
+                .player
+                .health(&self.physics)
+                .map_or(true, |health| health.is_dead()))
+            && !self.map.player.invincible
+        {
+            if let Some(versus) = &mut self.versus {
+                versus.down = Some(Versus::RESPAWN_TIME);
+                versus.fallen += 1;
+                versus.fallen_to = versus.died(id);
+            }
+
+            self.asset_manager.audio().play(Sfx::Death);
+            self.save.stats.deaths += 1;
+        }
+    }
+
+    /// Let the bullets of every crab hurt the other crabs, in versus. The crabs of the players
+    /// online only get hurt on their own computers, their bullets just go away here.
+    fn friendly_fire(&mut self) {
+        let ferris = self.map.player.handle();
+        let ferris_id = self.ferris_id();
+        let partner_id = self.partner_id();
+        let partner_body = self.partner.as_ref().map(|partner| partner.player.handle());
+
+        let mut crabs = vec![ferris];
+
+        crabs.extend(partner_body);
+        crabs.extend(self.remotes.iter().map(|remote| remote.player.handle()));
+
+        // Who fired each bullet that hit, and the crab it hit.
+        let mut hits: Vec<(Option<u8>, DefaultBodyHandle)> = self
+            .map
+            .player
+            .crab_hits(&mut self.physics, &crabs)
+            .into_iter()
+            .map(|victim| (Some(ferris_id), victim))
+            .collect();
+
+        if let Some(partner) = &mut self.partner {
+            hits.extend(
+                partner
+                    .player
+                    .crab_hits(&mut self.physics, &crabs)
+                    .into_iter()
+                    .map(|victim| (partner_id, victim)),
+            );
+        }
+
+        for remote in &mut self.remotes {
+            let id = remote.id();
+
+            hits.extend(
+                remote
+                    .player
+                    .crab_hits(&mut self.physics, &crabs)
+                    .into_iter()
+                    .map(|victim| (Some(id), victim)),
+            );
+        }
+
+        let ferris_down = self.is_down();
+
+        for (shooter, victim) in hits {
+            let (player, id) = if victim == ferris && !ferris_down {
+                (&mut self.map.player, Some(ferris_id))
+            } else {
+                match &mut self.partner {
+                    Some(partner) if Some(victim) == partner_body && !partner.is_down() => {
+                        (&mut partner.player, partner_id)
+                    }
+                    _ => continue,
+                }
+            };
+
+            player.damage(&mut self.physics, Versus::DAMAGE);
+            self.asset_manager.audio().play(Sfx::Hit);
+
+            if let Some((versus, (victim, shooter))) = self.versus.as_mut().zip(id.zip(shooter)) {
+                versus.hit(victim, shooter);
+            }
+        }
+    }
+
+    /// Move player two along, or bring them back next to Ferris once they've been down for long
+    /// enough. In versus they come back at a spawn point instead.
+    fn update_partner(&mut self, ctx: &Context, real_dt: f32, dt: f32) {
+        let (_, height) = display::screen_size(ctx);
+        let frozen = self.is_cinematic() || self.dialogue.is_some();
+        let ferris = self.map.player.position(&mut self.physics);
+        let comeback = if self.versus.is_some() && self.partner.is_some() {
+            self.spawn_point(false)
+        } else {
+            na::Point2::new(ferris.x - Partner::OFFSET, ferris.y)
+        };
+        let scored = self.partner_id();
+
+        let partner = match &mut self.partner {
+            Some(partner) => partner,
+            None => return,
+        };
+
+        // Playing in lockstep, player two's inputs come in over the internet.
+        if self.lockstep.is_none() {
+            partner.input.update(&self.config.bindings, real_dt);
+        }
+
+        self.hud
+            .update_partner(&partner.player, &self.physics, partner.weapon, real_dt);
+
+        if let Some(down) = &mut partner.down {
+            *down -= dt;
+
+            if *down <= 0.0 {
+                partner.player.respawn(&mut self.physics, comeback);
+                partner.down = None;
+            }
+
+            return;
+        }
+
+        let position = partner.player.position(&mut self.physics);
+
+        if (position.y > height
+            || partner
+                .player
+                .health(&self.physics)
+                .map_or(true, |health| health.is_dead()))
+            && !partner.player.invincible
+        {
+            partner.down = Some(Partner::RESPAWN_TIME);
+            self.asset_manager.audio().play(Sfx::Death);
+
+            if let Some((versus, id)) = self.versus.as_mut().zip(scored) {
+                versus.died(id);
+            }
+
+            return;
+        }
+
+        if frozen {
+            return;
+        }
+
+        if partner.input.is_buffered(Action::SwitchWeapon) {
+            partner.input.consume(Action::SwitchWeapon);
+            partner.switch_weapon();
+        }
+
+        let impacts = partner.player.update(&mut self.physics, &partner.input, dt);
+
+        partner.player.animate(&mut self.physics, dt);
+
+        for impact in impacts {
+            self.decals.stamp(
+                DecalKind::BulletHole,
+                Vec2::new(impact.x, impact.y),
+                self.rng.effects(),
+            );
+        }
+
+        let fire = partner.input.is_buffered(Action::Fire);
+
+        if fire && self.shoot_partner() {
+            if let Some(partner) = &mut self.partner {
+                partner.input.consume(Action::Fire);
+            }
+        }
+    }
+
+    /// Trade what happened with the other players of an online game. The host tells everyone
+    /// about every crab and the enemies left, the others only tell the host about their own crab.
+    pub fn sync(&mut self, ctx: &mut Context, session: &mut Session, dt: f32) {
+        if session.is_host() && session.level() != self.level {
+            session.start_level(&self.level, self.rng.seed());
+        }
+
+        if self.player_id.is_none() {
+            if let Some(versus) = &mut self.versus {
+                versus.join(session.id());
+            }
+
+            self.player_id = Some(session.id());
+        }
+
+        for message in session.receive() {
+            match message {
+                // The host keeps count of the frags of everyone.
+                Message::Crab(crab) => {
+                    if let Some(versus) = &mut self.versus {
+                        versus.report(crab.id, crab.deaths, crab.killer);
+                    }
+
+                    self.remote_crab(ctx, crab)
+                }
+                Message::Snapshot {
+                    level,
+                    crabs,
+                    enemies,
+                    scores,
+                    ..
+                } => {
+                    // Everyone plays the level the host is playing.
+                    if level != self.level {
+                        self.level_request = Some(level);
+
+                        continue;
+                    }
+
+                    for remote in &mut self.remotes {
+                        if !crabs.iter().any(|crab| crab.id == remote.id()) {
+                            remote.destroy(&mut self.physics);
+                        }
+                    }
+
+                    self.remotes
+                        .retain(|remote| crabs.iter().any(|crab| crab.id == remote.id()));
+
+                    for crab in crabs.into_iter().filter(|crab| crab.id != session.id()) {
+                        self.remote_crab(ctx, crab);
+                    }
+
+                    self.sync_enemies(&enemies);
+
+                    if let Some(versus) = &mut self.versus {
+                        versus.set_scores(scores);
+                    }
+                }
+                Message::Leave { id } => {
+                    // Without the host there's nobody left to play with.
+                    let gone: Vec<u8> = if session.is_host() {
+                        vec![id]
+                    } else {
+                        self.remotes.iter().map(Remote::id).collect()
+                    };
+
+                    for remote in &mut self.remotes {
+                        if gone.contains(&remote.id()) {
+                            remote.destroy(&mut self.physics);
+                        }
+                    }
+
+                    self.remotes.retain(|remote| !gone.contains(&remote.id()));
+                }
+                _ => {}
+            }
+        }
+
+        if !session.should_send(dt) {
+            return;
+        }
+
+        let position = self.map.player.position(&mut self.physics);
+        let ferris = CrabState {
+            id: session.id(),
+            x: position.x,
+            y: position.y,
+            health: self
+                .map
+                .player
+                .health(&self.physics)
+                .map_or(0.0, |health| health.current()),
+            shots: self.shots,
+            deaths: self.versus.as_ref().map_or(0, |versus| versus.fallen),
+            killer: self.versus.as_ref().and_then(|versus| versus.fallen_to),
+        };
+
+        if session.is_host() {
+            let mut crabs = vec![ferris];
+
+            crabs.extend(self.remotes.iter().map(Remote::state));
+
+            let physics = &self.physics;
+            let enemies = self
+                .map
+                .enemies
+                .iter_mut()
+                .filter_map(|enemy| {
+                    let health = enemy.health(physics).ok()?.current();
+
+                    physics
+                        .entities()
+                        .entity(enemy.handle())
+                        .map(|entity| EnemyState {
+                            id: entity.index(),
+                            health,
+                        })
+                })
+                .collect();
+
+            let scores = self
+                .versus
+                .as_ref()
+                .map_or(vec![], |versus| versus.scores().to_vec());
+
+            session.send_snapshot(crabs, enemies, scores);
+        } else {
+            session.send_crab(ferris);
+        }
+    }
+
+    /// Start playing in lockstep online as player `id`. The other player gets to be player two,
+    /// playing the same level on both computers from the inputs they trade.
+    pub fn start_lockstep(&mut self, ctx: &mut Context, id: u8) {
+        log::info!("Playing in lockstep as player {}", id + 1);
+
+        self.add_partner(ctx);
+        self.lockstep = Some(Lockstep::new(id, &[0, 1], Lockstep::DELAY));
+    }
+
+    /// Trade inputs and checksums with the other player, while playing in lockstep.
+    pub fn lockstep_sync(&mut self, session: &mut Session) {
+        let lockstep = match &mut self.lockstep {
+            Some(lockstep) => lockstep,
+            None => return,
+        };
+        let in_sync = lockstep.desync().is_none();
+
+        for message in session.receive() {
+            match message {
+                Message::Lockstep {
+                    id,
+                    inputs,
+                    checksum,
+                } => {
+                    lockstep.receive(id, inputs);
+
+                    if let Some((frame, checksum)) = checksum {
+                        lockstep.compare(id, frame, checksum);
+                    }
+                }
+                Message::Leave { .. } => {
+                    log::warn!("The other player is gone, playing on alone");
+
+                    self.lockstep = None;
+
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        // There's no putting the levels back together, but everyone gets to know.
+        if let Some(frame) = lockstep.desync().filter(|_| in_sync) {
+            log::error!("Out of sync with the other player as of frame {}", frame);
+            self.toasts.push(String::from("Out of sync!"));
+        }
+
+        // The inputs go out with every frame, the level can't move on without them.
+        session.send_inputs(lockstep.outgoing(), lockstep.last_checksum());
+    }
+
+    /// Sum up the level after a frame played in lockstep, for the other player to check theirs
+    /// against.
+    fn record_checksum(&mut self) {
+        if self.lockstep.is_none() {
+            return;
+        }
+
+        let checksum = self.checksum();
+
+        if let Some(lockstep) = &mut self.lockstep {
+            let frame = lockstep.frame().saturating_sub(1);
+
+            lockstep.record(frame, checksum);
+        }
+    }
+
+    /// Sum up the level as it is. Where the crabs and the enemies are and how much health they have
+    /// left is enough to tell the levels apart once they went different ways.
+    pub fn checksum(&mut self) -> u64 {
+        let physics = &mut self.physics;
+        let mut checksum = Checksum::new();
+        let mut crab = |player: &mut Player, checksum: &mut Checksum| {
+            let position = player.position(physics);
+            let velocity = player.velocity(physics);
+
+            for value in &[position.x, position.y, velocity.x, velocity.y] {
+                checksum.write_f32(*value);
+            }
+
+            checksum.write_f32(
+                player
+                    .health(physics)
+                    .map_or(0.0, |health| health.current()),
+            );
+        };
+
+        crab(&mut self.map.player, &mut checksum);
+
+        if let Some(partner) = &mut self.partner {
+            crab(&mut partner.player, &mut checksum);
+        }
+
+        for enemy in &mut self.map.enemies {
+            let position = match enemy.position(physics) {
+                Ok(position) => position,
+                Err(_) => continue,
+            };
+
+            checksum.write_f32(position.x);
+            checksum.write_f32(position.y);
+            checksum.write_f32(enemy.health(physics).map_or(0.0, |health| health.current()));
+        }
+
+        checksum.finish()
+    }
+
+    /// Catch up with what another player online reported about their crab.
+    fn remote_crab(&mut self, ctx: &mut Context, crab: CrabState) {
+        let id = match self
+            .remotes
+            .iter()
+            .position(|remote| remote.id() == crab.id)
+        {
+            Some(id) => id,
+            None => {
+                log::info!("Player {} is in the level", crab.id + 1);
+
+                let remote = Remote::new(ctx, &mut self.physics, &self.asset_manager, crab.clone());
+
+                self.remotes.push(remote);
+                self.remotes.len() - 1
+            }
+        };
+
+        let fired = self.remotes[id].report(&mut self.physics, crab, self.time);
+
+        for _ in 0..fired {
+            if self.remotes[id].shoot(&mut self.physics, &self.asset_manager) {
+                let position = self.remotes[id].player.position(&mut self.physics);
+
+                self.muzzle_flash(position);
+            }
+        }
+    }
+
+    /// Go along with the host about the enemies. Whatever the host took out goes here too, and no
+    /// enemy gets to be healthier here than on the host.
+    fn sync_enemies(&mut self, enemies: &[EnemyState]) {
+        let mut id = 0;
+
+        while id < self.map.enemies.len() {
+            let enemy = &mut self.map.enemies[id];
+            let state = self
+                .physics
+                .entities()
+                .entity(enemy.handle())
+                .and_then(|entity| enemies.iter().find(|state| state.id == entity.index()));
+
+            if let Some(state) = state {
+                enemy.sync_health(&mut self.physics, state.health);
+                id += 1;
+
+                continue;
+            }
+
+            let position = enemy.position(&mut self.physics);
+
+            enemy.destroy(&mut self.physics);
+            self.map.enemies.remove(id);
+
+            if let Ok(position) = position {
+                self.effects.burst(
+                    &EmitterConfig::EXPLOSION,
+                    Vec2::new(position.x, position.y),
+                    self.rng.effects(),
+                );
+            }
+        }
+    }
+
+    /// Where player two is, unless they're down.
+    fn partner_position(&mut self) -> Option<na::Point2<f32>> {
+        match &mut self.partner {
+            Some(partner) if !partner.is_down() => Some(partner.player.position(&mut self.physics)),
+            _ => None,
+        }
+    }
+
+    /// Put away the weapon in hand and take out the other one.
+    fn switch_weapon(&mut self) {
+        match self.map.weapon {
+            WeaponType::Turbofish => {
+                self.map.using = (String::from("Grappling Gun"), 1.0);
+                self.map.weapon = WeaponType::Grappling;
+            }
+
+            WeaponType::Grappling => {
+                self.map.using = (String::from("Turbofish Gun"), 1.0);
+                self.map.weapon = WeaponType::Turbofish;
+            }
+        }
+    }
+
+    /// Where the player is aiming on the screen. The right stick aims from the middle of the first
+    /// viewport while playing with a controller, touch controls aim straight at the middle.
+    fn cursor(&self, ctx: &Context) -> Vec2 {
+        match self.input.device {
+            Device::Keyboard => {
+                let cursor = ggez::input::mouse::position(ctx);
+                let (x, y) = display::to_screen(ctx, cursor.x, cursor.y);
+
+                Vec2::new(x, y)
+            }
+            Device::Gamepad | Device::Touch => {
+                let viewport = &self.viewports[0];
+
+                viewport.world_to_screen(viewport.camera.location())
+                    + self.input.aim() * InputState::AIM_REACH
+            }
+        }
+    }
+
+    /// Start recording what the player does in the level.
+    pub fn record(&mut self, level: &str) {
+        // Recordings only keep what player one does, so the runs played together can't be.
+        if self.partner.is_some() {
+            return;
+        }
+
+        match Map::source(&self.asset_manager, level) {
+            Ok(map) => {
+                let mut recording = Recording::new(level, self.rng.seed(), &map);
+
+                recording.difficulty = self.difficulty;
+                recording.new_game_plus = self.new_game_plus.clone();
+                recording.daily = self.daily.clone();
+                recording.game_speed = self.config.game_speed.clamp(MIN_GAME_SPEED, 1.0);
+                self.recording = Some(recording);
+            }
+            Err(error) => log::error!("Cannot record the run: {}", error),
+        }
+    }
+
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    /// How fast the level goes. Runs go at the speed they were started at, so they play back the
+    /// same way, and online games go at full speed for everyone.
+    fn game_speed(&self) -> f32 {
+        if self.player_id.is_some() || self.lockstep.is_some() {
+            return 1.0;
+        }
+
+        match (&self.playback, &self.recording) {
+            (Some(playback), _) => playback.recording().game_speed,
+            (None, Some(recording)) => recording.game_speed,
+            (None, None) => self.config.game_speed.clamp(MIN_GAME_SPEED, 1.0),
+        }
+    }
+
+    /// Play the recording back instead of letting the player play, rolling the dice the way they
+    /// were rolled when it was recorded. Fails if it wouldn't play back the same way here.
+    pub fn play_back(&mut self, recording: Recording) -> FerrisResult<()> {
+        recording.check(&Map::source(&self.asset_manager, &self.level)?)?;
+        self.start_playback(recording);
+
+        Ok(())
+    }
+
+    /// Play a demo back, which nobody watching gets to control. It doesn't count for the save.
+    /// Demos come with the game, so they aren't held to the version and level they were made on.
+    pub fn play_demo(&mut self, recording: Recording) -> FerrisResult<()> {
+        recording.check_format()?;
+        self.start_playback(recording);
+        self.spectator = None;
+        self.demo = true;
+
+        Ok(())
+    }
+
+    fn start_playback(&mut self, recording: Recording) {
+        log::info!("Playing back a replay with seed {}", recording.seed);
+
+        self.reseed(recording.seed);
+        self.playback = Some(Playback::new(recording));
+        // Watching a run isn't running it.
+        self.speedrun = None;
+        self.spectator = Some(Spectator::new());
+    }
+
+    pub fn is_demo(&self) -> bool {
+        self.demo
+    }
+
+    /// Play the replay back at `speed` updates a step, if it's one of the speeds there are.
+    #[cfg(test)]
+    pub fn set_playback_speed(&mut self, speed: u32) {
+        if let Some(playback) = &mut self.playback {
+            while playback.speed() != speed {
+                playback.toggle_speed();
+
+                if playback.speed() == 1 {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Show the level on the player's Discord profile, and keep it up with how far it got.
+    #[cfg(feature = "discord")]
+    pub fn report_to(&mut self, presence: Presence) {
+        self.presence = Some(LevelPresence::new(presence));
+    }
+
+    /// The level with the mode it's played in, and how far it got, for the Discord profile.
+    #[cfg(feature = "discord")]
+    fn describe(&self) -> (String, String) {
+        let mode = if self.playback.is_some() {
+            "Watching a replay"
+        } else if self.versus.is_some() {
+            "Versus"
+        } else if self.boss_rush.is_some() {
+            "Boss rush"
+        } else if !self.map.gates.is_empty() {
+            "Tutorial"
+        } else if self.daily.is_some() {
+            "Daily challenge"
+        } else if self.player_id.is_some() {
+            "Online"
+        } else if self.new_game_plus.is_some() {
+            "New Game+"
+        } else {
+            "Campaign"
+        };
+        let details = match mode {
+            "Versus" | "Boss rush" | "Tutorial" => mode.to_string(),
+            _ => format!("{} - Level {}", mode, self.level),
+        };
+        let state = if self.is_completed() {
+            String::from("Level complete")
+        } else if self.versus.is_some() || self.boss_rush.is_some() {
+            format!("{} points", self.score.points)
+        } else {
+            format!(
+                "{} of {} evildoers down",
+                self.stats().kills,
+                self.map.total_enemies
+            )
+        };
+
+        (details, state)
+    }
+
+    /// How many are playing together and how many could, for the Discord profile. Nobody plays
+    /// together on their own.
+    #[cfg(feature = "discord")]
+    fn party(&self) -> Option<(u32, u32)> {
+        if self.lockstep.is_some() || self.partner.is_some() {
+            Some((2, 2))
+        } else if self.player_id.is_some() {
+            Some((
+                1 + self.remotes.len() as u32,
+                crate::net::MAX_PLAYERS as u32,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Tell whoever walks by that the game is playing itself.
+    fn draw_demo(&self, ctx: &mut Context, width: f32) -> GameResult {
+        let consolas = self.asset_manager.text_font();
+        let demo = Text::new(
+            TextFragment::new(self.asset_manager.text("DEMO"))
+                .font(consolas)
+                .scale(Scale::uniform(50.0)),
+        );
+        let hint = Text::new(
+            TextFragment::new(self.asset_manager.text("PRESS ANY KEY"))
+                .font(consolas)
+                .scale(Scale::uniform(20.0)),
+        );
+
+        let demo_width = demo.width(ctx) as f32;
+        let hint_width = hint.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &demo,
+            DrawParam::default()
+                .dest(Point2::new((width - demo_width) / 2.0, 80.0))
+                .color(graphics::Color::from_rgb(247, 76, 0)),
+        )?;
+        graphics::draw(
+            ctx,
+            &hint,
+            DrawParam::default().dest(Point2::new((width - hint_width) / 2.0, 140.0)),
+        )
+    }
+
+    /// Play the level in New Game+. Ferris starts out with the loadout and the gophers get remixed
+    /// with the dice of the run, so this comes after the run is seeded.
+    pub fn play_new_game_plus(&mut self, new_game_plus: NewGamePlus) {
+        if let Some(loadout) = &new_game_plus.loadout {
+            if loadout.weapon != self.map.weapon {
+                self.switch_weapon();
+            }
+
+            self.map.player.ammo = Ammo::new(loadout.magazine, loadout.reserve);
+        }
+
+        let table = RemixTable::load(&self.asset_manager, &self.level);
+        let mut remixed = vec![];
+
+        for mut enemy in std::mem::take(&mut self.map.enemies) {
+            if enemy.boss().is_some() {
+                enemy.toughen(&mut self.physics, new_game_plus.enemy_health());
+                remixed.push(enemy);
+
+                continue;
+            }
+
+            let position = enemy.position(&mut self.physics);
+
+            enemy.destroy(&mut self.physics);
+            self.map.total_enemies -= 1;
+
+            // An enemy without a body leaves nowhere to spawn the new ones.
+            let x = match position {
+                Ok(position) => position.x,
+                Err(_) => continue,
+            };
+
+            // Spread out, so they don't land on top of each other.
+            for (i, spawn) in table.roll(self.rng.run()).into_iter().enumerate() {
+                self.queued
+                    .push(Command::Spawn(spawn, x + i as f32 * Self::REMIX_SPREAD));
+            }
+        }
+
+        self.map.enemies = remixed;
+
+        if let Some(recording) = &mut self.recording {
+            recording.new_game_plus = Some(new_game_plus.clone());
+        }
+
+        self.new_game_plus = Some(new_game_plus);
+    }
+
+    /// Play the level as the daily challenge, with its modifiers. The run has to be seeded with
+    /// the seed of the challenge already.
+    pub fn play_daily(&mut self, daily: DailyChallenge) {
+        self.toasts.push(
+            self.asset_manager
+                .text_with("DAILY CHALLENGE {date}", &[("date", &daily.date)]),
+        );
+
+        for modifier in &daily.modifiers {
+            self.toasts.push(self.asset_manager.text(modifier.name()));
+
+            match modifier {
+                Modifier::LowGravity => {
+                    let gravity = self.physics.gravity() * Self::LOW_GRAVITY;
+
+                    self.physics.set_gravity(gravity);
+                }
+                Modifier::TurbofishOnly if self.map.weapon != WeaponType::Turbofish => {
+                    self.switch_weapon()
+                }
+                Modifier::TurbofishOnly => {}
+                Modifier::GlassCannon => {
+                    let player = &mut self.map.player;
+
+                    let max = player
+                        .health(&self.physics)
+                        .map_or(Player::MAX_HEALTH, |health| health.max());
+
+                    player.set_health(&mut self.physics, Health::new(max * Self::GLASS_CANNON));
+                }
+                Modifier::ToughGophers => {
+                    for enemy in &mut self.map.enemies {
+                        enemy.toughen(&mut self.physics, Self::TOUGH_GOPHERS);
+                    }
+                }
+            }
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.daily = Some(daily.clone());
+        }
+
+        self.daily = Some(daily);
+    }
+
+    fn has_modifier(&self, modifier: Modifier) -> bool {
+        self.daily.as_ref().is_some_and(|daily| daily.has(modifier))
+    }
+
+    /// The high score table the run goes into, the one of the day for the daily challenge.
+    pub fn high_score_table(&self) -> String {
+        match &self.daily {
+            Some(daily) => daily.table(),
+            None => self.level.clone(),
+        }
+    }
+
+    /// Drop the next boss of the boss rush in, a bit ahead of the player.
+    fn spawn_rush_boss(&mut self, ctx: &mut Context, name: &'static str, toughness: f32) {
+        self.close_shop();
+
+        // In front of the player, unless that's off the end of the ground.
+        let player = self.map.player.position(&mut self.physics).x;
+        let bounds = self.map.camera_bounds(&mut self.physics);
+        let x = if player + Self::RUSH_SPAWN_DISTANCE < bounds.right {
+            player + Self::RUSH_SPAWN_DISTANCE
+        } else {
+            player - Self::RUSH_SPAWN_DISTANCE
+        };
+        let mut boss = Enemy::new_boss(ctx, x, &mut self.physics, &self.asset_manager, name);
+
+        let toughness = self.enemy_health() * toughness;
+
+        boss.toughen(&mut self.physics, toughness);
+        self.map.enemies.push(boss);
+        self.map.total_enemies += 1;
+    }
+
+    /// Give some health back and put out the shop for a break of the boss rush.
+    fn open_shop(&mut self) {
+        let position = self.map.player.position(&mut self.physics);
+
+        self.map.player.heal(&mut self.physics, Self::BREAK_HEAL);
+
+        for (id, kind) in [PickupKind::Health, PickupKind::Ammo, PickupKind::BulletTime]
+            .iter()
+            .enumerate()
+        {
+            self.pickups.push(Pickup::new(
+                position.x + Self::SHOP_DISTANCE + id as f32 * Self::SHOP_SPACING,
+                position.y - Self::SHOP_HEIGHT,
+                *kind,
+                &mut self.physics,
+                &self.asset_manager,
+            ));
+        }
+
+        self.toasts.push(
+            self.asset_manager
+                .text("Take one thing from the shop before the next boss"),
+        );
+    }
+
+    /// Take away what's left of the shop.
+    fn close_shop(&mut self) {
+        for pickup in self.pickups.drain(..) {
+            pickup.destroy(&mut self.physics);
+        }
+    }
+
+    /// How the boss rush went, if this is the boss rush.
+    pub fn boss_rush_results(&self) -> Option<RushResults> {
+        self.boss_rush
+            .as_ref()
+            .map(|rush| rush.results(self.score.points))
+    }
+
+    /// Multiplier for the health of the enemies, with the difficulty, New Game+ and the daily
+    /// challenge.
+    fn enemy_health(&self) -> f32 {
+        let tough = if self.has_modifier(Modifier::ToughGophers) {
+            Self::TOUGH_GOPHERS
+        } else {
+            1.0
+        };
+
+        self.difficulty.enemy_health()
+            * self
+                .new_game_plus
+                .as_ref()
+                .map_or(1.0, NewGamePlus::enemy_health)
+            * tough
+    }
+
+    /// Roll the dice deciding how the run goes from `seed` instead.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = GameRng::new(seed);
+    }
+
+    /// The recording being played back, if there's one.
+    pub fn replaying(&self) -> Option<&Recording> {
+        self.playback.as_ref().map(Playback::recording)
+    }
+
+    /// Write down the progress made so far without holding up the game, showing the saving
+    /// indicator in the meantime.
+    fn autosave(&mut self, ctx: &Context) {
+        if self.demo {
+            return;
+        }
+
+        // Let the last autosave finish first, they'd be writing to the same file.
+        if let Some((autosave, _)) = self.autosave.take() {
+            let _ = autosave.join();
+        }
+
+        match self.save.save_in_background(ctx) {
+            Ok(autosave) => {
+                self.autosave = Some((autosave, 0.0));
+                self.save_dirty = false;
+            }
+            Err(error) => log::error!("Cannot save the game: {}", error),
+        }
+    }
+
+    /// Write down the progress made so far, including the time played.
+    pub fn write_save(&mut self, ctx: &Context) {
+        if self.demo {
+            return;
+        }
+
+        if let Some((autosave, _)) = self.autosave.take() {
+            let _ = autosave.join();
+        }
+
+        if let Err(error) = self.save.save(ctx) {
+            log::error!("Cannot save the game: {}", error);
+        }
+
+        self.save_dirty = false;
+    }
+
+    /// Whether the last evildoer of the level went down. Arenas never get completed.
+    pub fn is_completed(&self) -> bool {
+        self.draw_end_text.3
+    }
+
+    /// The id of the level being played.
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// How the run has been going so far.
+    pub fn stats(&self) -> RunStats {
+        RunStats {
+            kills: self.map.total_enemies - self.map.enemies.len() as i32,
+            time: self.time,
+            score: self.score.points,
+            seed: self.rng.seed(),
+        }
+    }
+
+    /// Ask for the pause menu to be opened over the level.
+    pub fn pause(&mut self) {
+        self.wants_pause = true;
+    }
+
+    /// Whether the player asked for the pause menu since the last time this was asked.
+    pub fn take_pause(&mut self) -> bool {
+        std::mem::take(&mut self.wants_pause)
+    }
+
+    /// Bring the player back to the last checkpoint they reached. The rest of the level stays the
+    /// way it was.
+    pub fn retry(&mut self) {
+        self.map.player.respawn(&mut self.physics, self.checkpoint);
+
+        if let Some(partner) = &mut self.partner {
+            let position = na::Point2::new(self.checkpoint.x - Partner::OFFSET, self.checkpoint.y);
+
+            partner.player.respawn(&mut self.physics, position);
+            partner.down = None;
+        }
+
+        self.dialogue = None;
+    }
+
+    /// Move the current dialogue along and get rid of it once it is over.
+    fn advance_dialogue(&mut self) {
+        if let Some(dialogue) = &mut self.dialogue {
+            if dialogue.advance() {
+                self.dialogue = None;
+            }
+        }
+    }
+
+    /// Start taking photos of the level, which keeps it frozen.
+    pub fn enter_photo_mode(&mut self) {
+        self.photo = Some(PhotoMode::new(self.viewports[0].camera.location()));
+    }
+
+    pub fn in_photo_mode(&self) -> bool {
+        self.photo.is_some()
+    }
+
+    /// Go back to playing from photo mode, taking the filter off.
+    fn leave_photo_mode(&mut self) {
+        self.photo = None;
+        self.post.set_filter(Filter::None);
+    }
+
+    /// Scroll to zoom the camera around while debugging or taking photos. While debugging, the
+    /// camera stays at the zoom picked until the camera debugging is turned off.
+    pub fn mouse_wheel_event(&mut self, y: f32) {
+        let zoom = self.viewports[0].controller.zoom_target() * (1.0 + y * 0.1);
+
+        if self.config.debug.camera {
+            self.manual_zoom = Some(zoom);
+        }
+
+        for viewport in &mut self.viewports {
+            viewport.controller.set_zoom(zoom, 0.1);
+        }
+    }
+
+    pub fn key_up_event(&mut self, keycode: KeyCode) {
+        if let Some(versus) = self.versus.as_mut().filter(|_| keycode == KeyCode::Tab) {
+            versus.showing = false;
+        }
+
+        if let Some(spectator) = &mut self.spectator {
+            spectator.key(keycode, false);
+        }
+
+        if let Some(photo) = &mut self.photo {
+            photo.key(keycode, false);
+        }
+
+        if keycode == KeyCode::Up {
+            self.tics = None;
+            self.dim_constant.rate = 1.0;
+        }
+
+        self.input.key_up(keycode);
+    }
+
+    /// Shake all of the cameras.
+    fn add_trauma(&mut self, amount: f32) {
+        for viewport in &mut self.viewports {
+            viewport.controller.shake.add_trauma(amount);
+        }
+    }
+
+    /// Returns true while a cinematic is in control of any of the cameras.
+    fn is_cinematic(&self) -> bool {
+        self.viewports
+            .iter()
+            .any(|viewport| viewport.controller.is_cinematic())
+    }
+
+    /// Split the screen into a viewport for every player, or go back to a single viewport.
+    pub fn set_split_screen(&mut self, ctx: &mut Context, split: bool) -> GameResult {
+        let (width, height) = display::screen_size(ctx);
+        let count = if split { Self::SPLIT_VIEWPORTS } else { 1 };
+        let view_width = width / count as f32;
+
+        let player_position = self.map.player.position(&mut self.physics);
+
+        self.viewports.clear();
+
+        for view in 0..count {
+            let mut viewport = Viewport::new(
+                ctx,
+                graphics::Rect::new(view as f32 * view_width, 0.0, view_width, height),
+                Vec2::new(player_position.x, player_position.y),
+                self.map.camera_bounds(&mut self.physics),
+                split,
+            )?;
+
+            viewport.controller.shake.intensity = self.config.screen_shake;
+
+            self.viewports.push(viewport);
+        }
+
+        Ok(())
+    }
+
+    /// Pick up changes the player made in the settings.
+    pub fn apply_config(&mut self, ctx: &mut Context, config: &Config) -> GameResult {
+        let resized = config.resolution != self.config.resolution
+            || config.display != self.config.display
+            || config.letterbox != self.config.letterbox;
+
+        self.config = config.clone();
+        self.input.set_toggled(config.toggled());
+
+        if let Some(partner) = &mut self.partner {
+            partner.input.set_toggled(config.toggled());
+        }
+        self.post.apply_config(config);
+        self.tint.apply_config(config);
+        self.hud.set_flashing(config.flashing);
+        self.text_effects.set_palette(config.color_blind.palette());
+
+        #[cfg(feature = "discord")]
+        if !config.discord_presence {
+            self.presence = None;
+        }
+
+        if resized {
+            // The viewports have to be rebuilt to fit the new window size.
+            let split = self.viewports.len() > 1;
+
+            self.set_split_screen(ctx, split)?;
+        } else {
+            for viewport in &mut self.viewports {
+                viewport.controller.shake.intensity = config.screen_shake;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Switch between a single and a split screen while debugging.
+    pub fn toggle_split_screen(&mut self, ctx: &mut Context) -> GameResult {
+        let split = self.viewports.len() == 1;
+
+        self.set_split_screen(ctx, split)
+    }
+
+    /// Apply the goodies of a pickup that Ferris, or player two, just walked into.
+    fn collect_pickup(&mut self, kind: PickupKind, by_partner: bool) {
+        let player = match &mut self.partner {
+            Some(partner) if by_partner => &mut partner.player,
+            _ => &mut self.map.player,
+        };
+
+        match kind {
+            PickupKind::Ammo => player.ammo.add(Pickup::AMMO_AMOUNT),
+            PickupKind::Health => player.heal(&mut self.physics, Pickup::HEALTH_AMOUNT),
+            PickupKind::BulletTime => self.time_scale.slow(SlowMotion::BULLET_TIME),
+        }
+
+        self.events.publish(GameEvent::PickupCollected(kind));
+    }
+
+    /// Hand the events of this update to everything that reacts to them.
+    fn dispatch_events(&mut self) {
+        for event in self.events.drain() {
+            self.score.notify(&event);
+            self.kill_feed.notify(&event);
+            self.toasts.notify(&event);
+            self.time_scale.notify(&event);
+            self.save.stats.notify(&event);
+
+            for achievement in self
+                .achievements
+                .notify(&event, &mut self.save.achievements)
+            {
+                log::info!("Unlocked the {} achievement", achievement.id);
+                self.toasts
+                    .push(format!("Achievement unlocked: {}", achievement.name));
+                self.save_dirty = true;
+            }
+            self.asset_manager.audio().notify(&event);
+
+            if let Some(script) = &mut self.script {
+                script.notify(&event);
+            }
+
+            if let Some(prompt) = self.prompts.done_by_event(&event) {
+                self.save.shown_prompts.push(prompt.name().to_string());
+                self.save_dirty = true;
+            }
+
+            self.open_gates(|prompt| prompt.is_done_by_event(&event));
+
+            #[cfg(feature = "discord")]
+            if let Some(presence) = &mut self.presence {
+                presence.notify(&event);
+            }
+        }
+
+        #[cfg(feature = "discord")]
+        if let Some(mut presence) = self.presence.take() {
+            presence.update(self.party(), || self.describe());
+            self.presence = Some(presence);
+        }
+    }
+
+    /// Open the gates of the tutorial waiting on what was just done, if the player got to where
+    /// it's taught.
+    fn open_gates(&mut self, done: impl Fn(Prompt) -> bool) {
+        let x = self.map.player.position(&mut self.physics).x;
+
+        for gate in &mut self.map.gates {
+            if gate.is_waiting(x) && done(gate.prompt()) {
+                gate.open(&mut self.physics);
+                self.asset_manager.audio().play(Sfx::Pickup);
+            }
+        }
+    }
+
+    /// Read the script of a level, checking the dialogues it starts are there.
+    fn load_script(asset_manager: &AssetManager, level: &str, name: &str) -> FerrisResult<Script> {
+        let broken = |problem| FerrisError::Level {
+            level: level.to_string(),
+            problem,
+        };
+        let script: String =
+            asset_manager.get(asset_manager.handle(&format!("/scripts/{}", name))?);
+        let script = Script::parse(&script).map_err(broken)?;
+
+        for dialogue in script.dialogues() {
+            asset_manager.handle::<String>(&format!("/dialogues/{}", dialogue))?;
+        }
+
+        Ok(script)
+    }
+
+    /// Carry out what the level script asks for.
+    fn run_script(&mut self, ctx: &mut Context, commands: Vec<Command>) {
+        for command in commands {
+            match command {
+                Command::Spawn(Spawn::Enemy, x) => {
+                    let mut enemy = Enemy::new(ctx, x, &mut self.physics, &self.asset_manager);
+
+                    let toughness = self.enemy_health();
+
+                    enemy.toughen(&mut self.physics, toughness);
+                    self.map.enemies.push(enemy);
+                    self.map.total_enemies += 1;
+                }
+                Command::Spawn(Spawn::Barrel, x) => self.map.barrels.push(Barrel::new(
+                    ctx,
+                    x,
+                    &mut self.physics,
+                    &self.asset_manager,
+                )),
+                Command::Spawn(Spawn::Crate, x) => {
+                    self.map
+                        .crates
+                        .push(Crate::new(ctx, x, &mut self.physics, None))
+                }
+                Command::Damage(amount) => {
+                    self.map.player.damage(&mut self.physics, amount);
+                    self.events.publish(GameEvent::DamageDealt {
+                        victim: Victim::Player,
+                        amount,
+                        hits: 1,
+                    });
+                }
+                Command::Heal(amount) => self.map.player.heal(&mut self.physics, amount),
+                Command::Sound(sfx) => self.asset_manager.audio().play(sfx),
+                Command::Dialogue(script) => {
+                    self.dialogue = Some(Dialogue::parse(
+                        &self
+                            .asset_manager
+                            .get_translated_file(&format!("/dialogues/{}", script)),
+                    ));
+                }
+                Command::Toast(text) => self.toasts.push(text),
+            }
+        }
+    }
+
+    /// The developer console with every command it knows about the level.
+    fn console() -> Console<Self> {
+        let mut console = Console::new();
+
+        console.register(
+            "spawn",
+            "spawn <enemy|barrel|crate> [count]",
+            Self::spawn_command,
+        );
+        console.register(
+            "give",
+            "give <turbofish|grappling|ammo|health>",
+            Self::give_command,
+        );
+        console.register("god", "god: nothing hurts the player", Self::god_command);
+        console.register(
+            "noclip",
+            "noclip: fly through everything",
+            Self::noclip_command,
+        );
+        console.register("set", "set gravity <amount>", Self::set_command);
+        console.register("loadlevel", "loadlevel <level>", Self::load_level_command);
+        console.register("seed", "seed [<seed>|random]", Self::seed_command);
+
+        console
+    }
+
+    /// Run a line entered into the console.
+    fn run_console(&mut self, line: &str) {
+        if let Some((run, args)) = self.console.command(line) {
+            let answer = run(self, &args);
+
+            self.console.answer(answer);
+        }
+    }
+
+    fn spawn_command(&mut self, args: &[&str]) -> Result<String, String> {
+        let name = args.first().ok_or("Spawn what?")?;
+        let spawn =
+            Spawn::parse(name).ok_or_else(|| format!("Nothing called {} to spawn", name))?;
+        let count = match args.get(1) {
+            Some(_) => console::number(args, 1, "count")?,
+            None => 1,
+        };
+
+        // Out in front of the player, spread out so they don't land on top of each other.
+        let x = self.map.player.position(&mut self.physics).x + 300.0;
+
+        self.queued
+            .extend((0..count).map(|i| Command::Spawn(spawn, x + i as f32 * 80.0)));
+
+        Ok(format!("Spawned {} {}", count, name))
+    }
+
+    fn give_command(&mut self, args: &[&str]) -> Result<String, String> {
+        let player = &mut self.map.player;
+
+        match args.first().copied() {
+            Some("turbofish") => self.map.weapon = WeaponType::Turbofish,
+            Some("grappling") => self.map.weapon = WeaponType::Grappling,
+            Some("ammo") => player.ammo.add(Pickup::AMMO_AMOUNT * 5),
+            Some("health") => {
+                let max = player
+                    .health(&self.physics)
+                    .map_or(Player::MAX_HEALTH, |health| health.max());
+
+                player.heal(&mut self.physics, max);
+            }
+            Some(name) => return Err(format!("Nothing called {} to give", name)),
+            None => return Err(String::from("Give what?")),
+        }
+
+        Ok(String::new())
+    }
+
+    fn god_command(&mut self, _args: &[&str]) -> Result<String, String> {
+        let player = &mut self.map.player;
+
+        player.invincible = !player.invincible;
+
+        Ok(format!("God mode {}", on_off(player.invincible)))
+    }
+
+    fn noclip_command(&mut self, _args: &[&str]) -> Result<String, String> {
+        let player = &mut self.map.player;
+        let noclip = !player.is_noclip();
+
+        player.set_noclip(&mut self.physics, noclip);
+
+        Ok(format!("Noclip {}", on_off(noclip)))
+    }
+
+    fn set_command(&mut self, args: &[&str]) -> Result<String, String> {
+        match args.first().copied() {
+            Some("gravity") => {
+                let gravity = console::number(args, 1, "gravity")?;
+
+                self.physics.set_gravity(gravity);
+
+                Ok(format!("Gravity is {}", self.physics.gravity()))
+            }
+            Some(name) => Err(format!("Nothing called {} to set", name)),
+            None => Err(String::from("Set what?")),
+        }
+    }
+
+    fn load_level_command(&mut self, args: &[&str]) -> Result<String, String> {
+        let level = args.first().ok_or("Load which level?")?;
+        let levels = self.asset_manager.get_files("maps");
+
+        // Levels are numbered from 01, so 2 is good enough for 02.
+        let candidates = [level.to_string(), format!("{:0>2}", level)];
+        let level = candidates
+            .iter()
+            .find(|level| levels.contains(&format!("{}.map", level)))
+            .ok_or_else(|| format!("There's no level {}", level))?;
+
+        self.level_request = Some(level.clone());
+
+        Ok(format!("Loading level {}", level))
+    }
+
+    /// Show the seed of the run, or play the level over on another seed along with every run
+    /// after it.
+    fn seed_command(&mut self, args: &[&str]) -> Result<String, String> {
+        let seed = match args.first().copied() {
+            None => return Ok(format!("Seed {}", self.rng.seed())),
+            Some("random") => None,
+            Some(seed) => {
+                Some(rng::parse_seed(seed).ok_or_else(|| format!("{} is not a seed", seed))?)
+            }
+        };
+
+        self.seed_request = Some(seed);
+        self.level_request = Some(self.level.clone());
+
+        Ok(match seed {
+            Some(seed) => format!("Playing on seed {}", seed),
+            None => String::from("Playing on a new seed every run"),
+        })
+    }
+
+    /// The level the console asked to go over to, if it just did.
+    pub fn take_level_request(&mut self) -> Option<String> {
+        self.level_request.take()
+    }
+
+    /// The seed the console asked to play on, if it just did.
+    pub fn take_seed_request(&mut self) -> Option<Option<u64>> {
+        self.seed_request.take()
+    }
+}
 
-        self.elapsed_shake = Some((elapsed.0 + 0.1, elapsed.1, magnitude));
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
     }
 }
@@ -0,0 +1,647 @@
+//! The heads-up display drawn on top of the world.
+//!
+//! Everything in here is anchored to the screen instead of the world, so it's drawn without going
+//! through the camera. Positions are in the virtual pixels of the HUD layout.
+
+use std::f32::consts::PI;
+
+use ggez::{
+    graphics::{self, Color, DrawParam, Image, Scale, Text, TextFragment},
+    mint::Vector2,
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::{
+    game::components::{ammo::Ammo, bullet::WeaponType, health::Health, player::Player},
+    game::coop::PARTNER_COLOR,
+    game::layout::{Anchor, Layout},
+    game::physics::Physics,
+    game::score::Score,
+    palette::Palette,
+    utils::{damp, lerp, remap, AssetManager, Handle},
+};
+
+/// The colour of the empty part of the bars.
+const BAR_BACKGROUND: Color = Color {
+    r: 54.0 / 255.0,
+    g: 50.0 / 255.0,
+    b: 49.0 / 255.0,
+    a: 1.0,
+};
+
+/// The colour of the combo multiplier.
+const COMBO_COLOR: Color = Color {
+    r: 247.0 / 255.0,
+    g: 76.0 / 255.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+pub struct Hud {
+    health_bar: HealthBar,
+    ammo_widget: AmmoWidget,
+    score_counter: ScoreCounter,
+    profile: Handle<Image>,
+    /// The health and ammo of player two, in the bottom right corner while playing together.
+    partner: Option<(HealthBar, AmmoWidget)>,
+    /// Whether the ammo flashes when it's running low, instead of just turning the warning color.
+    flashing: bool,
+}
+
+impl Hud {
+    /// The width of the health bar.
+    const BAR_WIDTH: f32 = 150.0;
+    /// The height of the health bar.
+    const BAR_HEIGHT: f32 = 15.0;
+    /// How far from the right edge player two's health and ammo start.
+    const PARTNER_WIDTH: f32 = 320.0;
+
+    pub fn new(
+        player: &Player,
+        physics: &Physics,
+        weapon: WeaponType,
+        asset_manager: &AssetManager,
+    ) -> Self {
+        Self {
+            health_bar: HealthBar::of(player, physics),
+            ammo_widget: AmmoWidget::new(weapon, asset_manager),
+            score_counter: ScoreCounter::new(),
+            profile: asset_manager.expect_handle("Some(profile).png"),
+            partner: None,
+            flashing: true,
+        }
+    }
+
+    pub fn set_flashing(&mut self, flashing: bool) {
+        self.flashing = flashing;
+    }
+
+    /// Start showing the health and ammo of player two.
+    pub fn add_partner(
+        &mut self,
+        player: &Player,
+        physics: &Physics,
+        weapon: WeaponType,
+        asset_manager: &AssetManager,
+    ) {
+        self.partner = Some((
+            HealthBar::of(player, physics),
+            AmmoWidget::new(weapon, asset_manager),
+        ));
+    }
+
+    pub fn update_partner(
+        &mut self,
+        player: &Player,
+        physics: &Physics,
+        weapon: WeaponType,
+        dt: f32,
+    ) {
+        if let Some((health_bar, ammo_widget)) = &mut self.partner {
+            if let Ok(health) = player.health(physics) {
+                health_bar.update(&health, dt);
+            }
+
+            ammo_widget.update(weapon, dt);
+        }
+    }
+
+    /// Draw the health and ammo of player two, if they're playing.
+    pub fn draw_partner(
+        &self,
+        ctx: &mut Context,
+        layout: &Layout,
+        player: &Player,
+        physics: &Physics,
+        asset_manager: &AssetManager,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let (health_bar, ammo_widget) = match &self.partner {
+            Some(partner) => partner,
+            None => return Ok(()),
+        };
+
+        let corner = layout.place(Anchor::BottomRight, Vec2::new(-Self::PARTNER_WIDTH, -20.0));
+        let label = Text::new(
+            TextFragment::new("P2")
+                .font(asset_manager.text_font())
+                .scale(Scale::uniform(20.0)),
+        );
+
+        graphics::draw(
+            ctx,
+            &label,
+            DrawParam::default()
+                .dest(Point2::new(corner.x, corner.y - 100.0))
+                .color(PARTNER_COLOR),
+        )?;
+
+        if let Ok(health) = player.health(physics) {
+            health_bar.draw(
+                ctx,
+                &health,
+                graphics::Rect::new(
+                    corner.x + 40.0,
+                    corner.y - 98.0,
+                    Self::BAR_WIDTH,
+                    Self::BAR_HEIGHT,
+                ),
+                palette,
+            )?;
+        }
+
+        ammo_widget.draw(
+            ctx,
+            corner,
+            &player.ammo,
+            asset_manager,
+            palette.warning,
+            self.flashing,
+        )
+    }
+
+    pub fn update(
+        &mut self,
+        player: &Player,
+        physics: &Physics,
+        weapon: WeaponType,
+        score: &Score,
+        dt: f32,
+    ) {
+        if let Ok(health) = player.health(physics) {
+            self.health_bar.update(&health, dt);
+        }
+
+        self.ammo_widget.update(weapon, dt);
+        self.score_counter.update(score, dt);
+    }
+
+    /// Draw the HUD onto the virtual screen of the layout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        layout: &Layout,
+        player: &Player,
+        physics: &Physics,
+        score: &Score,
+        asset_manager: &AssetManager,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let profile = asset_manager.get(self.profile);
+
+        graphics::draw(
+            ctx,
+            &profile,
+            DrawParam::default()
+                .dest(layout.place(Anchor::TopLeft, Vec2::new(10.0, 10.0)))
+                .scale(Vector2 { x: 0.5, y: 0.5 }),
+        )?;
+
+        // Health
+        let health_position = layout.place(
+            Anchor::TopLeft,
+            Vec2::new(
+                ((profile.width() / 2) + 10) as f32,
+                (profile.height() / 5) as f32,
+            ),
+        );
+
+        if let Ok(health) = player.health(physics) {
+            self.health_bar.draw(
+                ctx,
+                &health,
+                graphics::Rect::new(
+                    health_position.x,
+                    health_position.y,
+                    Self::BAR_WIDTH,
+                    Self::BAR_HEIGHT,
+                ),
+                palette,
+            )?;
+        }
+
+        // Weapon and ammo in the bottom left corner
+        self.ammo_widget.draw(
+            ctx,
+            layout.place(Anchor::BottomLeft, Vec2::new(20.0, -20.0)),
+            &player.ammo,
+            asset_manager,
+            palette.warning,
+            self.flashing,
+        )?;
+
+        // Score at the top of the screen
+        self.score_counter.draw(
+            ctx,
+            layout.place(Anchor::Top, Vec2::new(0.0, 20.0)),
+            score,
+            asset_manager,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Counts the score shown on the screen up to the actual score.
+struct ScoreCounter {
+    /// The score currently shown.
+    shown: f32,
+    /// The combo multiplier during the last update, to notice the combo going up.
+    multiplier: u32,
+    /// Makes the combo pop when it goes up, counts down to 0.0.
+    pop: f32,
+}
+
+impl ScoreCounter {
+    /// How fast the shown score catches up with the actual score.
+    const COUNT_RATE: f32 = 8.0;
+    /// How long the combo pops for when it goes up.
+    const POP_TIME: f32 = 0.25;
+    /// The width of the bar showing the time left on the combo.
+    const COMBO_BAR_WIDTH: f32 = 120.0;
+
+    fn new() -> Self {
+        Self {
+            shown: 0.0,
+            multiplier: 1,
+            pop: 0.0,
+        }
+    }
+
+    fn update(&mut self, score: &Score, dt: f32) {
+        let points = score.points as f32;
+
+        self.shown = damp(self.shown, points, Self::COUNT_RATE, dt);
+
+        if (points - self.shown).abs() < 1.0 {
+            self.shown = points;
+        }
+
+        if score.multiplier() > self.multiplier {
+            self.pop = Self::POP_TIME;
+        }
+
+        self.multiplier = score.multiplier();
+        self.pop = (self.pop - dt).max(0.0);
+    }
+
+    /// Draw the counter centered below `top`.
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        top: Point2<f32>,
+        score: &Score,
+        asset_manager: &AssetManager,
+    ) -> GameResult<()> {
+        let font = asset_manager.text_font();
+        let points = Text::new(
+            TextFragment::new(asset_manager.text_with(
+                "SCORE {score}",
+                &[("score", &format!("{:06}", self.shown.round() as u32))],
+            ))
+            .font(font)
+            .scale(Scale::uniform(28.0)),
+        );
+
+        let (points_width, points_height) = points.dimensions(ctx);
+
+        graphics::draw(
+            ctx,
+            &points,
+            DrawParam::default().dest(Point2::new(top.x - points_width as f32 / 2.0, top.y)),
+        )?;
+
+        if score.multiplier() <= 1 {
+            return Ok(());
+        }
+
+        let combo_top = top.y + 5.0 + points_height as f32;
+        let scale = 20.0 * (1.0 + (self.pop / Self::POP_TIME) * 0.5);
+
+        let combo = Text::new(
+            TextFragment::new(format!("x{} COMBO", score.multiplier()))
+                .font(font)
+                .scale(Scale::uniform(scale))
+                .color(COMBO_COLOR),
+        );
+
+        let (combo_width, combo_height) = combo.dimensions(ctx);
+
+        graphics::draw(
+            ctx,
+            &combo,
+            DrawParam::default().dest(Point2::new(top.x - combo_width as f32 / 2.0, combo_top)),
+        )?;
+
+        // Time left until the combo falls apart
+        let bar = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                top.x - Self::COMBO_BAR_WIDTH / 2.0,
+                combo_top + combo_height as f32 + 4.0,
+                Self::COMBO_BAR_WIDTH * (score.combo_timer() / Score::COMBO_TIME),
+                3.0,
+            ),
+            COMBO_COLOR,
+        )?;
+
+        graphics::draw(ctx, &bar, DrawParam::default())
+    }
+}
+
+/// Shows the weapon in use along with its ammo.
+///
+/// The loaded magazine is drawn as a row of rounds next to the weapon icon, followed by the
+/// reserve and a ring that fills up while reloading.
+struct AmmoWidget {
+    /// The weapon being shown.
+    weapon: WeaponType,
+    /// Makes the weapon icon pop when switching weapons, counts down to 0.0.
+    switch_pop: f32,
+    /// Keeps the time for the low ammo flashing.
+    elapsed: f32,
+
+    sniper: Handle<Image>,
+    gun: Handle<Image>,
+    round: Handle<Image>,
+    empty: Handle<Image>,
+}
+
+impl AmmoWidget {
+    /// How long the weapon icon pops for after a weapon switch.
+    const SWITCH_POP: f32 = 0.3;
+    /// How many times a second the ammo flashes when it's running low.
+    const FLASH_RATE: f32 = 3.0;
+    /// The radius of the reload ring.
+    const RING_RADIUS: f32 = 14.0;
+    /// The space between the rounds in the magazine.
+    const ROUND_SPACING: f32 = 4.0;
+
+    fn new(weapon: WeaponType, asset_manager: &AssetManager) -> Self {
+        Self {
+            weapon,
+            switch_pop: 0.0,
+            elapsed: 0.0,
+
+            sniper: asset_manager.expect_handle("Some(sniper).png"),
+            gun: asset_manager.expect_handle("Some(gun).png"),
+            round: asset_manager.expect_handle("Some(ammo).png"),
+            empty: asset_manager.expect_handle("Some(nil).png"),
+        }
+    }
+
+    fn update(&mut self, weapon: WeaponType, dt: f32) {
+        if weapon != self.weapon {
+            self.weapon = weapon;
+            self.switch_pop = Self::SWITCH_POP;
+        }
+
+        self.switch_pop = (self.switch_pop - dt).max(0.0);
+        self.elapsed += dt;
+    }
+
+    /// Draw the widget with its bottom left corner at `corner`.
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        corner: Point2<f32>,
+        ammo: &Ammo,
+        asset_manager: &AssetManager,
+        warning: Color,
+        flashing: bool,
+    ) -> GameResult<()> {
+        let icon = match self.weapon {
+            WeaponType::Turbofish => asset_manager.get(self.sniper),
+            WeaponType::Grappling => asset_manager.get(self.gun),
+        };
+
+        let pop = 1.0 + (self.switch_pop / Self::SWITCH_POP) * 0.3;
+        let icon_scale = 0.5 * pop;
+
+        graphics::draw(
+            ctx,
+            &icon,
+            DrawParam::default()
+                .dest(Point2::new(
+                    corner.x + icon.width() as f32 * 0.25,
+                    corner.y - icon.height() as f32 * 0.25,
+                ))
+                .offset(Point2::new(0.5, 0.5))
+                .scale(Vector2 {
+                    x: icon_scale,
+                    y: icon_scale,
+                }),
+        )?;
+
+        // The grappling gun doesn't use up any ammo.
+        if self.weapon == WeaponType::Grappling {
+            return Ok(());
+        }
+
+        let color = if ammo.is_low() && !flashing {
+            warning
+        } else if ammo.is_low() {
+            let flash = ((self.elapsed * Self::FLASH_RATE * 2.0 * PI).sin() + 1.0) / 2.0;
+
+            Color::new(
+                lerp(1.0, warning.r, flash),
+                lerp(1.0, warning.g, flash),
+                lerp(1.0, warning.b, flash),
+                1.0,
+            )
+        } else {
+            graphics::WHITE
+        };
+
+        // Magazine
+        let round = asset_manager.get(self.round);
+        let empty = asset_manager.get(self.empty);
+
+        let mut x = corner.x + icon.width() as f32 * 0.5 + 10.0;
+
+        for slot in 0..Ammo::MAGAZINE_SIZE {
+            let image = if slot < ammo.magazine { &round } else { &empty };
+
+            graphics::draw(
+                ctx,
+                image,
+                DrawParam::default()
+                    .dest(Point2::new(x, corner.y - image.height() as f32))
+                    .color(color),
+            )?;
+
+            x += image.width() as f32 + Self::ROUND_SPACING;
+        }
+
+        // Reserve
+        let consolas = asset_manager.text_font();
+        let reserve = Text::new(
+            TextFragment::new(format!("/ {}", ammo.reserve))
+                .font(consolas)
+                .scale(Scale::uniform(20.0))
+                .color(color),
+        );
+
+        graphics::draw(
+            ctx,
+            &reserve,
+            DrawParam::default().dest(Point2::new(x + 6.0, corner.y - 20.0)),
+        )?;
+
+        x += reserve.width(ctx) as f32 + 6.0;
+
+        // Reload ring
+        if let Some(progress) = ammo.reload_progress() {
+            let center = Point2::new(x + 10.0 + Self::RING_RADIUS, corner.y - Self::RING_RADIUS);
+            let segments = 32;
+
+            let mut ring = graphics::MeshBuilder::new();
+
+            ring.circle(
+                graphics::DrawMode::stroke(4.0),
+                center,
+                Self::RING_RADIUS,
+                0.5,
+                BAR_BACKGROUND,
+            );
+
+            // A polyline needs at least two points, so skip the ring until it has some progress.
+            let filled = (progress * segments as f32) as usize;
+
+            if filled > 0 {
+                let points = (0..=filled)
+                    .map(|i| {
+                        let angle = -PI / 2.0 + (i as f32 / segments as f32) * 2.0 * PI;
+
+                        Point2::new(
+                            center.x + angle.cos() * Self::RING_RADIUS,
+                            center.y + angle.sin() * Self::RING_RADIUS,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                ring.polyline(graphics::DrawMode::stroke(4.0), &points, graphics::WHITE)?;
+            }
+
+            let ring = ring.build(ctx)?;
+
+            graphics::draw(ctx, &ring, DrawParam::default())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Health bar with a "damage ghost" segment that shows how much health was just lost.
+///
+/// The ghost hangs around for a moment after taking damage before draining down to the
+/// current health.
+struct HealthBar {
+    /// The health value the ghost segment reaches up to.
+    ghost: f32,
+    /// The health during the last update, to notice new damage.
+    last: f32,
+    /// Time left until the ghost starts draining.
+    delay: f32,
+}
+
+impl HealthBar {
+    /// How long the ghost waits after the last hit before draining.
+    const GHOST_DELAY: f32 = 0.6;
+    /// How fast the ghost drains down to the current health.
+    const GHOST_RATE: f32 = 6.0;
+
+    fn new(health: &Health) -> Self {
+        Self {
+            ghost: health.current(),
+            last: health.current(),
+            delay: 0.0,
+        }
+    }
+
+    /// A bar for the health of `player`, full if the player has none to show yet.
+    fn of(player: &Player, physics: &Physics) -> Self {
+        match player.health(physics) {
+            Ok(health) => Self::new(&health),
+            Err(_) => Self::new(&Health::new(Player::MAX_HEALTH)),
+        }
+    }
+
+    fn update(&mut self, health: &Health, dt: f32) {
+        let current = health.current();
+
+        if current < self.last {
+            self.delay = Self::GHOST_DELAY;
+        }
+
+        self.last = current;
+
+        if current >= self.ghost {
+            // Healing fills the bar right away.
+            self.ghost = current;
+        } else if self.delay > 0.0 {
+            self.delay -= dt;
+        } else {
+            self.ghost = damp(self.ghost, current, Self::GHOST_RATE, dt);
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        health: &Health,
+        rect: graphics::Rect,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let width = |value: f32| remap(value, 0., health.max(), 0., rect.w);
+
+        let bar = graphics::MeshBuilder::new()
+            .rectangle(graphics::DrawMode::fill(), rect, BAR_BACKGROUND)
+            .rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(rect.x, rect.y, width(self.ghost), rect.h),
+                palette.hurt,
+            )
+            .rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(rect.x, rect.y, width(health.current()), rect.h),
+                palette.health,
+            )
+            .build(ctx)?;
+
+        graphics::draw(ctx, &bar, DrawParam::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_ghost() {
+        let mut health = Health::new(100.0);
+        let mut bar = HealthBar::new(&health);
+
+        health.damage(40.0);
+        bar.update(&health, 0.1);
+
+        // The ghost waits a bit before draining.
+        assert_eq!(bar.ghost, 100.0);
+
+        for _ in 0..100 {
+            bar.update(&health, 0.1);
+        }
+
+        assert!((bar.ghost - 60.0).abs() < 0.01);
+
+        // Healing doesn't leave a ghost behind.
+        health.heal(20.0);
+        bar.update(&health, 0.1);
+
+        assert_eq!(bar.ghost, 80.0);
+    }
+}
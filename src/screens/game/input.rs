@@ -0,0 +1,442 @@
+//! What the player is asking the game to do.
+//!
+//! Keys, controller buttons and the touch controls come in as events and get turned into actions through the bindings,
+//! so the rest of the game only ever asks about actions: whether one is held down, was just pressed
+//! or was just released, and where the player is aiming. ggez only tells about the controller
+//! through its events, so the sticks are put back together from them here too. Pushing the left
+//! stick far enough counts as holding left or right.
+//!
+//! Presses are buffered for a moment, so something pressed a little too early (like firing right
+//! before a reload is done) still happens as soon as it can.
+//!
+//! Some actions can be toggled instead, for players who'd rather not hold them down: a press starts
+//! holding them and the next one lets go. Firing keeps getting pressed while it's toggled on, so
+//! the weapon goes off on its own.
+//!
+//! Whichever of the keyboard and the controller was used last decides what the prompts tell the
+//! player to press.
+
+use std::mem;
+
+use ggez::event::{Axis, Button, KeyCode};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::bindings::{Action, Bindings};
+
+use super::recording::InputFrame;
+
+/// What the player is playing with.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Device {
+    Keyboard,
+    Gamepad,
+    Touch,
+}
+
+pub struct InputState {
+    /// The keys being held down right now.
+    keys: Vec<KeyCode>,
+    /// The buttons being held down right now.
+    buttons: Vec<Button>,
+    left_stick: Vec2,
+    right_stick: Vec2,
+    /// The actions held down through the touch controls.
+    touch: Vec<Action>,
+    /// The actions that get toggled by a press instead of being held down.
+    toggled: Vec<Action>,
+    /// The toggled actions that are on.
+    toggled_on: Vec<Action>,
+    /// The actions that were down as of the last update, toggled or not.
+    down: Vec<Action>,
+    /// How long it's been since fire was last pressed on its own.
+    auto_fire: f32,
+
+    /// The actions held down as of the last update.
+    held: Vec<Action>,
+    /// The actions that started being held with the last update.
+    pressed: Vec<Action>,
+    /// The actions that stopped being held with the last update.
+    released: Vec<Action>,
+    /// The actions pressed since the last update. Kept so a tap that is over before the next
+    /// update still counts.
+    tapped: Vec<Action>,
+    /// The presses that haven't been used up yet, with how long ago they happened.
+    buffered: Vec<(Action, f32)>,
+
+    /// What the player used last.
+    pub device: Device,
+}
+
+impl InputState {
+    /// Sticks pushed less than this far are resting.
+    const DEADZONE: f32 = 0.2;
+    /// How far the left stick needs to be pushed to count as holding left or right.
+    const STICK_PRESS: f32 = 0.3;
+    /// How far from the middle of the screen the right stick aims when pushed all the way.
+    pub const AIM_REACH: f32 = 300.0;
+    /// How long a press waits around to be used before it's forgotten.
+    const BUFFER_TIME: f32 = 0.15;
+    /// How often fire gets pressed while it's toggled on.
+    const AUTO_FIRE_TIME: f32 = 0.2;
+
+    pub fn new() -> Self {
+        Self {
+            keys: vec![],
+            buttons: vec![],
+            left_stick: Vec2::zeros(),
+            right_stick: Vec2::zeros(),
+            touch: vec![],
+            toggled: vec![],
+            toggled_on: vec![],
+            down: vec![],
+            auto_fire: 0.0,
+
+            held: vec![],
+            pressed: vec![],
+            released: vec![],
+            tapped: vec![],
+            buffered: vec![],
+
+            device: Device::Keyboard,
+        }
+    }
+
+    pub fn key_down(&mut self, key: KeyCode, bindings: &Bindings) {
+        if !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+
+        self.tapped.extend(bindings.key_action(key));
+        self.device = Device::Keyboard;
+    }
+
+    pub fn key_up(&mut self, key: KeyCode) {
+        self.keys.retain(|held| *held != key);
+    }
+
+    pub fn button_down(&mut self, button: Button, bindings: &Bindings) {
+        if !self.buttons.contains(&button) {
+            self.buttons.push(button);
+        }
+
+        self.tapped.extend(bindings.button_action(button));
+        self.device = Device::Gamepad;
+    }
+
+    pub fn button_up(&mut self, button: Button) {
+        self.buttons.retain(|held| *held != button);
+    }
+
+    pub fn axis_event(&mut self, axis: Axis, value: f32) {
+        // Up is positive on the controller but negative on the screen.
+        match axis {
+            Axis::LeftStickX => self.left_stick.x = value,
+            Axis::LeftStickY => self.left_stick.y = -value,
+            Axis::RightStickX => self.right_stick.x = value,
+            Axis::RightStickY => self.right_stick.y = -value,
+            _ => return,
+        }
+
+        if value.abs() > Self::DEADZONE {
+            self.device = Device::Gamepad;
+        }
+    }
+
+    /// The touch controls changed, these are the actions they hold down now.
+    pub fn touch(&mut self, held: Vec<Action>) {
+        for action in &held {
+            if !self.touch.contains(action) {
+                self.tapped.push(*action);
+            }
+        }
+
+        self.touch = held;
+        self.device = Device::Touch;
+    }
+
+    /// Have the actions get toggled by a press instead of being held down.
+    pub fn set_toggled(&mut self, toggled: Vec<Action>) {
+        self.toggled_on.retain(|action| toggled.contains(action));
+        self.toggled = toggled;
+    }
+
+    /// Work out what changed since the last update. Call it once before every step of the game,
+    /// unless playing a recording back.
+    pub fn update(&mut self, bindings: &Bindings, dt: f32) {
+        let frame = self.sample(bindings, dt);
+
+        self.advance(frame.held, frame.pressed, dt);
+    }
+
+    /// Work out what the player is doing, without going along with it yet. Playing in lockstep,
+    /// it only gets played a few frames later, once the others know about it too.
+    pub fn sample(&mut self, bindings: &Bindings, dt: f32) -> InputFrame {
+        let down: Vec<Action> = Action::ALL
+            .iter()
+            .copied()
+            .filter(|action| self.is_down(bindings, *action))
+            .collect();
+        let tapped = mem::take(&mut self.tapped);
+        let mut pushed = vec![];
+
+        for action in down
+            .iter()
+            .copied()
+            .filter(|action| !self.down.contains(action))
+            .chain(tapped)
+        {
+            if !pushed.contains(&action) {
+                pushed.push(action);
+            }
+        }
+
+        for action in &pushed {
+            if !self.toggled.contains(action) {
+                continue;
+            }
+
+            if self.toggled_on.contains(action) {
+                self.toggled_on.retain(|on| on != action);
+            } else {
+                self.toggled_on.push(*action);
+            }
+        }
+
+        let held: Vec<Action> = Action::ALL
+            .iter()
+            .copied()
+            .filter(|action| {
+                if self.toggled.contains(action) {
+                    self.toggled_on.contains(action)
+                } else {
+                    down.contains(action)
+                }
+            })
+            .collect();
+        let mut pressed: Vec<Action> = pushed
+            .into_iter()
+            .filter(|action| !self.toggled.contains(action))
+            .chain(
+                held.iter()
+                    .copied()
+                    .filter(|action| self.toggled.contains(action) && !self.held.contains(action)),
+            )
+            .collect();
+
+        if self.toggled_on.contains(&Action::Fire) {
+            self.auto_fire += dt;
+
+            if self.auto_fire >= Self::AUTO_FIRE_TIME && !pressed.contains(&Action::Fire) {
+                pressed.push(Action::Fire);
+            }
+        }
+
+        if pressed.contains(&Action::Fire) {
+            self.auto_fire = 0.0;
+        }
+
+        self.down = down;
+
+        InputFrame::new(held, pressed)
+    }
+
+    /// What was held and pressed with the last update, for recording it.
+    pub fn frame(&self) -> InputFrame {
+        InputFrame::new(self.held.clone(), self.pressed.clone())
+    }
+
+    /// Update with a recorded frame instead of what the player is doing.
+    pub fn play(&mut self, frame: &InputFrame, dt: f32) {
+        self.tapped.clear();
+        self.advance(frame.held.clone(), frame.pressed.clone(), dt);
+    }
+
+    fn advance(&mut self, held: Vec<Action>, pressed: Vec<Action>, dt: f32) {
+        self.pressed = pressed;
+        self.released = self
+            .held
+            .iter()
+            .copied()
+            .filter(|action| !held.contains(action))
+            .collect();
+        self.held = held;
+
+        for (_, age) in &mut self.buffered {
+            *age += dt;
+        }
+
+        self.buffered.retain(|(_, age)| *age <= Self::BUFFER_TIME);
+
+        for action in &self.pressed {
+            // Pressing again starts the wait over.
+            self.buffered.retain(|(buffered, _)| buffered != action);
+            self.buffered.push((*action, 0.0));
+        }
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.released.contains(&action)
+    }
+
+    /// Whether the action was pressed a moment ago and hasn't been used up yet.
+    pub fn is_buffered(&self, action: Action) -> bool {
+        self.buffered
+            .iter()
+            .any(|(buffered, _)| *buffered == action)
+    }
+
+    /// The press did what it was meant to, stop buffering it.
+    pub fn consume(&mut self, action: Action) {
+        self.buffered.retain(|(buffered, _)| *buffered != action);
+    }
+
+    /// Where the right stick is aiming, with y pointing down like on the screen.
+    pub fn aim(&self) -> Vec2 {
+        deadzone(self.right_stick)
+    }
+
+    fn is_down(&self, bindings: &Bindings, action: Action) -> bool {
+        let binding = bindings.get(action);
+        let stick = deadzone(self.left_stick).x;
+
+        self.keys.contains(&binding.key)
+            || self.buttons.contains(&binding.button)
+            || self.touch.contains(&action)
+            || match action {
+                Action::Left => stick < -Self::STICK_PRESS,
+                Action::Right => stick > Self::STICK_PRESS,
+                _ => false,
+            }
+    }
+}
+
+/// Ignore the stick while it's resting, and have it start from nothing right past the deadzone
+/// instead of jumping ahead.
+fn deadzone(stick: Vec2) -> Vec2 {
+    let length = stick.norm();
+
+    if length <= InputState::DEADZONE {
+        return Vec2::zeros();
+    }
+
+    let scaled = ((length - InputState::DEADZONE) / (1.0 - InputState::DEADZONE)).min(1.0);
+
+    stick * (scaled / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions() {
+        let bindings = Bindings::default();
+        let mut input = InputState::new();
+
+        input.key_down(KeyCode::Space, &bindings);
+        input.update(&bindings, 0.0);
+
+        assert!(input.is_held(Action::Jump));
+        assert!(input.just_pressed(Action::Jump));
+
+        input.update(&bindings, 0.0);
+
+        assert!(input.is_held(Action::Jump));
+        assert!(!input.just_pressed(Action::Jump));
+
+        input.key_up(KeyCode::Space);
+        input.update(&bindings, 0.0);
+
+        assert!(input.just_released(Action::Jump));
+
+        // A tap in between two updates still gets pressed.
+        input.key_down(KeyCode::S, &bindings);
+        input.key_up(KeyCode::S);
+        input.update(&bindings, 0.0);
+
+        assert!(input.just_pressed(Action::Fire));
+        assert!(!input.is_held(Action::Fire));
+    }
+
+    #[test]
+    fn test_buffer() {
+        let bindings = Bindings::default();
+        let mut input = InputState::new();
+
+        input.key_down(KeyCode::S, &bindings);
+        input.update(&bindings, 0.0);
+        input.update(&bindings, InputState::BUFFER_TIME / 2.0);
+
+        // Still waiting to be used a little later.
+        assert!(input.is_buffered(Action::Fire));
+
+        input.consume(Action::Fire);
+        assert!(!input.is_buffered(Action::Fire));
+
+        input.key_down(KeyCode::M, &bindings);
+        input.update(&bindings, 0.0);
+        input.update(&bindings, InputState::BUFFER_TIME * 2.0);
+
+        assert!(!input.is_buffered(Action::Map));
+    }
+
+    #[test]
+    fn test_sticks() {
+        let bindings = Bindings::default();
+        let mut input = InputState::new();
+
+        // Resting sticks drift a little, that doesn't count as using the controller.
+        input.axis_event(Axis::LeftStickX, 0.1);
+        input.update(&bindings, 0.0);
+
+        assert!(!input.is_held(Action::Right));
+        assert_eq!(input.device, Device::Keyboard);
+
+        input.axis_event(Axis::LeftStickX, -1.0);
+        input.update(&bindings, 0.0);
+
+        assert!(input.is_held(Action::Left));
+        assert_eq!(input.device, Device::Gamepad);
+
+        input.axis_event(Axis::RightStickY, 1.0);
+        assert!(input.aim().y < -0.9);
+    }
+
+    #[test]
+    fn test_toggles() {
+        let bindings = Bindings::default();
+        let mut input = InputState::new();
+
+        input.set_toggled(vec![Action::Fire]);
+
+        // A tap keeps fire held down, and fire keeps getting pressed on its own.
+        input.key_down(KeyCode::S, &bindings);
+        input.key_up(KeyCode::S);
+        input.update(&bindings, 0.0);
+
+        assert!(input.just_pressed(Action::Fire));
+
+        input.update(&bindings, InputState::AUTO_FIRE_TIME / 2.0);
+
+        assert!(input.is_held(Action::Fire));
+        assert!(!input.just_pressed(Action::Fire));
+
+        input.update(&bindings, InputState::AUTO_FIRE_TIME);
+
+        assert!(input.just_pressed(Action::Fire));
+
+        // The next tap lets go.
+        input.key_down(KeyCode::S, &bindings);
+        input.update(&bindings, 0.0);
+
+        assert!(input.just_released(Action::Fire));
+    }
+}
@@ -0,0 +1,124 @@
+//! A feed in the corner of the screen reporting what just happened.
+
+use std::collections::VecDeque;
+
+use ggez::{
+    graphics::{self, Color, DrawParam, Font, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use super::events::{GameEvent, Subscriber};
+
+struct Entry {
+    text: String,
+    /// Time since the entry was added.
+    age: f32,
+}
+
+pub struct KillFeed {
+    /// The entries in the feed, newest first.
+    entries: VecDeque<Entry>,
+}
+
+impl KillFeed {
+    /// The most entries shown at once. Older ones get pushed out.
+    const MAX_ENTRIES: usize = 5;
+    /// How long an entry is shown for in seconds.
+    const LIFETIME: f32 = 4.0;
+    /// How long an entry takes to fade out at the end of its lifetime.
+    const FADE_TIME: f32 = 1.0;
+    /// The space between two entries.
+    const LINE_HEIGHT: f32 = 22.0;
+
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(Self::MAX_ENTRIES),
+        }
+    }
+
+    /// Report something that just happened.
+    pub fn push(&mut self, text: String) {
+        self.entries.push_front(Entry { text, age: 0.0 });
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for entry in &mut self.entries {
+            entry.age += dt;
+        }
+
+        self.entries.retain(|entry| entry.age < Self::LIFETIME);
+    }
+
+    /// Draw the feed in the top right corner of a screen of the provided size, starting at `top`.
+    pub fn draw(&self, ctx: &mut Context, size: Vec2, top: f32, font: Font) -> GameResult<()> {
+        for (line, entry) in self.entries.iter().enumerate() {
+            let alpha = ((Self::LIFETIME - entry.age) / Self::FADE_TIME).min(1.0);
+
+            let text = Text::new(
+                TextFragment::new(entry.text.as_str())
+                    .font(font)
+                    .scale(Scale::uniform(16.0))
+                    .color(Color::new(1.0, 1.0, 1.0, alpha)),
+            );
+
+            let width = text.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default().dest(Point2::new(
+                    size.x - width - 40.0,
+                    top + line as f32 * Self::LINE_HEIGHT,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Subscriber for KillFeed {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::EnemyKilled {
+                boss: Some(name), ..
+            } => self.push(format!("Ferris took down {}", name)),
+            GameEvent::EnemyKilled { headshot: true, .. } => self.push(String::from(
+                "Ferris headshot a Gopher with the Turbofish Gun",
+            )),
+            GameEvent::EnemyKilled { .. } => self.push(String::from(
+                "Ferris blasted a Gopher with the Turbofish Gun",
+            )),
+            GameEvent::BarrelExploded { chain } if chain > 1 => {
+                self.push(format!("Barrel chain x{}", chain))
+            }
+            GameEvent::BarrelExploded { .. } => self.push(String::from("Ferris blew up a barrel")),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed() {
+        let mut feed = KillFeed::new();
+
+        for i in 0..10 {
+            feed.push(i.to_string());
+        }
+
+        // Only the newest entries are kept around.
+        assert_eq!(feed.entries.len(), KillFeed::MAX_ENTRIES);
+        assert_eq!(feed.entries[0].text, "9");
+
+        feed.update(KillFeed::LIFETIME);
+
+        assert!(feed.entries.is_empty());
+    }
+}
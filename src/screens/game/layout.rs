@@ -0,0 +1,110 @@
+//! Lays out the HUD the same way on screens of any size.
+//!
+//! The HUD is drawn onto a virtual screen that is scaled up or down to fit the actual one, so it
+//! keeps its proportions from 720p to 4K. Screens with a different aspect ratio get a virtual screen
+//! that is wider or taller instead of stretching, and elements pinned to an anchor stay in their
+//! corner of it.
+
+use ggez::nalgebra::{Matrix4, Point2, Vector3};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+/// The spot on the screen an element is pinned to.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+pub struct Layout {
+    /// The size of the virtual screen.
+    size: Vec2,
+    /// How many actual pixels a virtual pixel takes up.
+    scale: f32,
+}
+
+impl Layout {
+    /// The resolution the HUD is designed at.
+    const VIRTUAL_SIZE: (f32, f32) = (1280.0, 720.0);
+
+    /// Fit the virtual screen onto an actual screen of the provided size.
+    pub fn new(screen: Vec2) -> Self {
+        let scale = (screen.x / Self::VIRTUAL_SIZE.0).min(screen.y / Self::VIRTUAL_SIZE.1);
+
+        Self {
+            size: screen / scale,
+            scale,
+        }
+    }
+
+    /// The size of the virtual screen.
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// The size of the actual screen.
+    pub fn screen_size(&self) -> Vec2 {
+        self.size * self.scale
+    }
+
+    /// The point `offset` away from the anchor on the virtual screen.
+    pub fn place(&self, anchor: Anchor, offset: Vec2) -> Point2<f32> {
+        let (x, y) = match anchor {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (self.size.x / 2.0, 0.0),
+            Anchor::TopRight => (self.size.x, 0.0),
+            Anchor::BottomLeft => (0.0, self.size.y),
+            Anchor::Bottom => (self.size.x / 2.0, self.size.y),
+            Anchor::BottomRight => (self.size.x, self.size.y),
+        };
+
+        Point2::new(x + offset.x, y + offset.y)
+    }
+
+    /// The point on the virtual screen under a point on the actual one.
+    pub fn to_virtual(&self, point: Vec2) -> Vec2 {
+        point / self.scale
+    }
+
+    /// The transformation from the virtual screen onto the actual one.
+    /// Push it before drawing the HUD and pop it afterwards.
+    pub fn transform(&self) -> Matrix4<f32> {
+        Matrix4::new_nonuniform_scaling(&Vector3::new(self.scale, self.scale, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        // 4K has the same aspect ratio, so the virtual screen just gets scaled up.
+        let layout = Layout::new(Vec2::new(3840.0, 2160.0));
+
+        assert_eq!(layout.size(), Vec2::new(1280.0, 720.0));
+        assert_eq!(
+            layout.place(Anchor::BottomLeft, Vec2::new(20.0, -20.0)),
+            Point2::new(20.0, 700.0)
+        );
+
+        // A wider screen gets more room on the sides.
+        let layout = Layout::new(Vec2::new(2560.0, 720.0));
+
+        assert_eq!(layout.size(), Vec2::new(2560.0, 720.0));
+        assert_eq!(
+            layout.place(Anchor::Top, Vec2::new(0.0, 20.0)),
+            Point2::new(1280.0, 20.0)
+        );
+
+        let layout = Layout::new(Vec2::new(2560.0, 1440.0));
+
+        assert_eq!(
+            layout.to_virtual(Vec2::new(2560.0, 720.0)),
+            Vec2::new(1280.0, 360.0)
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Darkness and the lights cutting through it.
+//!
+//! Dark levels get a light map drawn over them: a canvas cleared to how dark the level is, with
+//! every light added on top as a glow that fades out towards its edge. The light map is then
+//! multiplied over the scene, so anything outside of the lights stays dark. Ferris always carries a
+//! lamp, while muzzle flashes and explosions light things up for a moment.
+
+use std::f32::consts::PI;
+
+use ggez::{
+    conf::NumSamples,
+    graphics::{self, BlendMode, Canvas, Color, DrawParam, Drawable, MeshBuilder, Rect, Vertex},
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use super::viewport::Viewport;
+
+/// A light that fades out after a while.
+struct Flash {
+    position: Vec2,
+    radius: f32,
+    color: Color,
+    /// How long the flash lasts and how much of that is left.
+    duration: f32,
+    time: f32,
+}
+
+pub struct Lighting {
+    /// The light everything gets without any lights around.
+    ambient: Color,
+    flashes: Vec<Flash>,
+    /// The light map, made again whenever the size of the viewport changes.
+    canvas: Option<Canvas>,
+}
+
+impl Lighting {
+    /// How far the lamp of the player reaches.
+    const LAMP_RADIUS: f32 = 380.0;
+    const LAMP_COLOR: Color = Color::new(1.0, 0.92, 0.75, 1.0);
+    /// How many sides the glow of a light has.
+    const SEGMENTS: u32 = 32;
+
+    pub const MUZZLE_FLASH: (f32, Color, f32) = (220.0, Color::new(1.0, 0.8, 0.4, 1.0), 0.1);
+    pub const EXPLOSION: (f32, Color, f32) = (600.0, Color::new(1.0, 0.6, 0.2, 1.0), 0.6);
+
+    /// `darkness` goes from 0.0 for a level that's fully lit to 1.0 for one that's pitch black.
+    pub fn new(darkness: f32) -> Self {
+        let light = 1.0 - darkness.clamp(0.0, 1.0);
+
+        Self {
+            ambient: Color::new(light * 0.85, light * 0.9, light, 1.0),
+            flashes: vec![],
+            canvas: None,
+        }
+    }
+
+    /// Light up the area around `position` for a moment. The flash is a radius, color and duration
+    /// like `Lighting::EXPLOSION`.
+    pub fn flash(&mut self, position: Vec2, (radius, color, duration): (f32, Color, f32)) {
+        self.flashes.push(Flash {
+            position,
+            radius,
+            color,
+            duration,
+            time: duration,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for flash in &mut self.flashes {
+            flash.time -= dt;
+        }
+
+        self.flashes.retain(|flash| flash.time > 0.0);
+    }
+
+    /// Darken everything drawn into the viewport so far, except for what's around the lights.
+    /// Viewports without a canvas of their own are drawn into `screen`, if there is one.
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        viewport: &Viewport,
+        screen: Option<&Canvas>,
+        lamp: Vec2,
+    ) -> GameResult {
+        let size = viewport.size();
+        let coordinates = Rect::new(0.0, 0.0, size.x, size.y);
+        // The screen coordinates to go back to, which might be letterboxed.
+        let outer = graphics::screen_coordinates(ctx);
+
+        let resized = match &self.canvas {
+            Some(canvas) => {
+                let image = canvas.image();
+
+                image.width() != size.x as u16 || image.height() != size.y as u16
+            }
+            None => true,
+        };
+
+        if resized {
+            self.canvas = Some(Canvas::new(
+                ctx,
+                size.x as u16,
+                size.y as u16,
+                NumSamples::One,
+            )?);
+        }
+
+        let canvas = self.canvas.as_mut().unwrap();
+        let mut mesh = MeshBuilder::new();
+
+        glow(
+            &mut mesh,
+            viewport.world_to_screen(lamp) - viewport.offset(),
+            Self::LAMP_RADIUS * viewport.controller.zoom(),
+            Self::LAMP_COLOR,
+        );
+
+        for flash in &self.flashes {
+            let strength = flash.time / flash.duration;
+
+            glow(
+                &mut mesh,
+                viewport.world_to_screen(flash.position) - viewport.offset(),
+                flash.radius * viewport.controller.zoom(),
+                Color::new(
+                    flash.color.r * strength,
+                    flash.color.g * strength,
+                    flash.color.b * strength,
+                    1.0,
+                ),
+            );
+        }
+
+        let mut mesh = mesh.build(ctx)?;
+
+        mesh.set_blend_mode(Some(BlendMode::Add));
+
+        graphics::set_canvas(ctx, Some(canvas));
+        graphics::set_screen_coordinates(ctx, coordinates)?;
+        graphics::clear(ctx, self.ambient);
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        // Go back to drawing into the viewport and put the light map over it.
+        graphics::set_canvas(ctx, viewport.canvas().or(screen));
+        graphics::set_screen_coordinates(ctx, outer)?;
+
+        canvas.set_blend_mode(Some(BlendMode::Multiply));
+        graphics::draw(ctx, canvas, DrawParam::default())
+    }
+}
+
+/// Add a glow to the mesh that's `color` in the middle and fades out to nothing at `radius`.
+fn glow(mesh: &mut MeshBuilder, center: Vec2, radius: f32, color: Color) {
+    let vertex = |position: Vec2, color: Color| Vertex {
+        pos: [position.x, position.y],
+        uv: [0.0, 0.0],
+        color: color.into(),
+    };
+
+    let mut vertices = vec![vertex(center, color)];
+    let mut indices = vec![];
+
+    for segment in 0..Lighting::SEGMENTS {
+        let angle = segment as f32 / Lighting::SEGMENTS as f32 * 2.0 * PI;
+
+        vertices.push(vertex(
+            center + Vec2::new(angle.cos(), angle.sin()) * radius,
+            graphics::BLACK,
+        ));
+        indices.extend(&[0, segment + 1, (segment + 1) % Lighting::SEGMENTS + 1]);
+    }
+
+    mesh.raw(&vertices, &indices, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flashes() {
+        let mut lighting = Lighting::new(0.8);
+
+        lighting.flash(Vec2::zeros(), Lighting::MUZZLE_FLASH);
+        lighting.flash(Vec2::zeros(), Lighting::EXPLOSION);
+
+        // The muzzle flash is over long before the explosion is.
+        lighting.update(0.2);
+        assert_eq!(lighting.flashes.len(), 1);
+
+        lighting.update(1.0);
+        assert!(lighting.flashes.is_empty());
+    }
+}
@@ -3,28 +3,56 @@
 //! # Map
 //! `[` => Create left tile \
 //! `-` => Create center tile \
+//! `=` => Create metal center tile \
 //! `]` => Create right tile \
 //! `_` => Increase draw x by 100.0 \
 //! `8` => Push a tile with a enemy \
+//! `B` => Push a tile with a boss \
 //! `4` => Create a tile with the player \
 //! `*` => Create a tile with a barrel \
+//! `#` => Create a tile with a crate holding a random pickup \
+//! `$` => Create a tile with a crate holding ammo \
+//! `+` => Create a tile with a crate holding health \
+//! `%` => Create a tile with a crate holding bullet time \
+//! `@` => Create a tile with a waypoint for the intro camera path \
+//! `|` => Create a tile with a checkpoint \
+//! `^` => Create a tile with a tutorial prompt trigger, the nth one shows the nth `.prompt` \
+//! `?` => Create a tile with a dialogue trigger, the nth one starts the nth `.dialogue` \
+//! `!` => Create a tile with a gate, which stays shut until the player did what the last prompt
+//! trigger before it teaches \
 //!
 //! # Setter Syntax
 //! `.comment` => A comment \
 //! `.using_weapon` => Set the current weapon \
-//! `.end` => The end quote displayed on the win screen
+//! `.end` => The end quote displayed on the win screen \
+//! `.dialogue` => Add a dialogue script from `resources/dialogues` for the next dialogue trigger \
+//! `.prompt` => Add a tutorial prompt (`move`, `jump`, `shoot`, `map` or `explode`) for the next
+//! prompt trigger \
+//! `.music` => The track from `resources/music` played during the level
+//! `.darkness` => How dark the level is, from 0.0 for fully lit to 1.0 for pitch black \
+//! `.script` => The script from `resources/scripts` running during the level \
+//! `.versus` => Make the level an arena for versus, played up to this many frags. The player and
+//! the checkpoints are where the crabs spawn \
+//! `.boss_rush` => Make the level the boss rush, where the bosses get dropped in one at a time
 
 use ggez::Context;
+use nphysics2d::nalgebra as na;
 
 use crate::{
+    error::{FerrisError, FerrisResult},
+    game::camera::CameraBounds,
     game::components::{
         barrel::Barrel,
         bullet::WeaponType,
+        crate_box::Crate,
         enemy::Enemy,
+        gate::Gate,
+        pickup::PickupKind,
         player::Player,
         tile::{Tile, TileType},
     },
-    game::physics::Physics,
+    game::physics::{Physics, Surface},
+    game::prompts::Prompt,
     utils::AssetManager,
 };
 
@@ -32,24 +60,95 @@ pub struct Map {
     pub ground: Vec<Tile>,
     pub enemies: Vec<Enemy>,
     pub barrels: Vec<Barrel>,
+    pub crates: Vec<Crate>,
+    /// The points the camera pans along when the level starts.
+    pub intro: Vec<na::Point2<f32>>,
+    /// The points the player comes back to after dying, once they have been reached.
+    pub checkpoints: Vec<na::Point2<f32>>,
+    /// Tutorial prompt triggers, showing their prompt once the player gets past them.
+    pub prompts: Vec<(f32, Prompt)>,
+    /// Dialogue triggers, starting their dialogue script once the player gets past them.
+    pub dialogues: Vec<(f32, String)>,
+    /// The gates of the tutorial.
+    pub gates: Vec<Gate>,
     pub player: Player,
 
     pub total_enemies: i32,
 
     pub end: Option<String>,
-    pub using: Option<(String, f32)>,
+    /// The script from `resources/scripts` running during the level, if there's one.
+    pub script: Option<String>,
+    /// The track played during the level.
+    pub music: String,
+    /// How dark the level is, lit levels have none.
+    pub darkness: f32,
+    /// The frag limit of an arena for versus.
+    pub versus: Option<u32>,
+    /// Whether the level is the boss rush.
+    pub boss_rush: bool,
+    /// The name of the weapon in hand and how visible its label still is. Levels that don't pick
+    /// one start out with the turbofish gun.
+    pub using: (String, f32),
 
     pub weapon: WeaponType,
 }
 
 impl Map {
+    /// The track played during levels that don't pick one.
+    const DEFAULT_MUSIC: &'static str = "level.wav";
+    /// How far above the ground the intro camera path goes.
+    const INTRO_HEIGHT: f32 = 150.0;
+    /// How far above the ground the player comes back at a checkpoint.
+    const SPAWN_HEIGHT: f32 = 100.0;
+
+    /// The area the camera can show. Spans all of the ground horizontally and
+    /// stops at the bottom of the ground, the sky is left open.
+    pub fn camera_bounds(&self, physics: &mut Physics) -> CameraBounds {
+        let mut left = f32::MAX;
+        let mut right = f32::MIN;
+        let mut bottom = f32::MIN;
+
+        for tile in &self.ground {
+            let position = tile.position(physics);
+            let dimensions = tile.dimensions();
+
+            left = left.min(position.x - dimensions.x / 2.0);
+            right = right.max(position.x + dimensions.x / 2.0);
+            bottom = bottom.max(position.y + dimensions.y / 2.0);
+        }
+
+        CameraBounds {
+            left,
+            right,
+            top: f32::NEG_INFINITY,
+            bottom,
+        }
+    }
+
+    /// What the level going by `map_id` is built from. Arenas for versus go by their ids too.
+    pub fn source(asset_manager: &AssetManager, map_id: &str) -> FerrisResult<String> {
+        let handle = asset_manager
+            .handle(&format!("/maps/{}.map", map_id))
+            .or_else(|error| {
+                asset_manager
+                    .handle(&format!("/arenas/{}.map", map_id))
+                    .map_err(|_| error)
+            })?;
+
+        Ok(asset_manager.get(handle))
+    }
+
     pub fn parse(
         ctx: &mut Context,
         map_id: &str,
         physics: &mut Physics,
         asset_manager: &AssetManager,
-    ) -> Self {
-        let map = asset_manager.get_file(format!("/maps/{}.map", map_id).as_str());
+    ) -> FerrisResult<Self> {
+        let broken = |problem| FerrisError::Level {
+            level: map_id.to_string(),
+            problem,
+        };
+        let map = Self::source(asset_manager, map_id)?;
 
         let mut draw_pos = 0.;
 
@@ -60,11 +159,24 @@ impl Map {
         let mut enemies = vec![];
         let mut total_enemies = 0;
         let mut barrels = vec![];
+        let mut crates = vec![];
+        let mut intro = vec![];
+        let mut checkpoints = vec![];
+        let mut prompt_names = vec![];
+        let mut prompts = vec![];
+        let mut scripts = vec![];
+        let mut dialogues = vec![];
+        let mut gates = vec![];
 
         let mut player = None;
 
         let mut end = None;
-        let mut using = None;
+        let mut using = (String::from("Turbofish Gun"), 1.0);
+        let mut script = None;
+        let mut music = String::from(Self::DEFAULT_MUSIC);
+        let mut darkness = 0.0;
+        let mut versus = None;
+        let mut boss_rush = false;
 
         let mut weapon = WeaponType::Turbofish;
 
@@ -79,18 +191,53 @@ impl Map {
                 weapon = match using_weapon.0.as_str() {
                     "Turbofish Gun" => WeaponType::Turbofish,
                     "Grappling Gun" => WeaponType::Grappling,
-                    _ => panic!(""),
+                    _ => return Err(broken("it uses a weapon that doesn't exist")),
                 };
 
-                using = Some(using_weapon);
+                using = using_weapon;
+            } else if exp[0].starts_with(".prompt") {
+                let name = exp[1..].join(" ").trim().to_string();
+
+                prompt_names.push(
+                    Prompt::parse(&name)
+                        .ok_or_else(|| broken("it has a prompt that doesn't exist"))?,
+                );
+            } else if exp[0].starts_with(".dialogue") {
+                scripts.push(exp[1..].join(" ").trim().to_string());
+            } else if exp[0].starts_with(".script") {
+                script = Some(exp[1..].join(" ").trim().to_string());
+            } else if exp[0].starts_with(".music") {
+                music = exp[1..].join(" ").trim().to_string();
+            } else if exp[0].starts_with(".darkness") {
+                darkness = exp[1..]
+                    .join(" ")
+                    .trim()
+                    .parse()
+                    .map_err(|_| broken("its darkness isn't a number"))?;
+            } else if exp[0].starts_with(".versus") {
+                versus = Some(
+                    exp[1..]
+                        .join(" ")
+                        .trim()
+                        .parse()
+                        .map_err(|_| broken("its frag limit isn't a number"))?,
+                );
+            } else if exp[0].starts_with(".boss_rush") {
+                boss_rush = true;
             } else if exp[0].starts_with(".comment") {
                 // Do nothing. ¯\_(ツ)_/¯
             } else {
                 for id in line.chars() {
                     match id {
                         '[' => {
-                            let tile =
-                                Tile::new(ctx, draw_pos, physics, asset_manager, TileType::Left);
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Left,
+                                Surface::Dirt,
+                            );
 
                             draw_inc = (tile.dimensions().x / 2.0) + 32.0;
                             draw_pos += draw_inc;
@@ -99,8 +246,30 @@ impl Map {
                         }
 
                         '-' => {
-                            let tile =
-                                Tile::new(ctx, draw_pos, physics, asset_manager, TileType::Center);
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            draw_inc = (tile.dimensions().x / 2.0) + 32.0;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
+                        '=' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Metal,
+                            );
 
                             draw_inc = (tile.dimensions().x / 2.0) + 32.0;
                             draw_pos += draw_inc;
@@ -115,6 +284,7 @@ impl Map {
                                 physics,
                                 asset_manager,
                                 TileType::Right,
+                                Surface::Dirt,
                             );
 
                             draw_inc = (tile.dimensions().x / 2.0) + 32.0;
@@ -129,8 +299,14 @@ impl Map {
                         }
 
                         '8' => {
-                            let tile =
-                                Tile::new(ctx, draw_pos, physics, asset_manager, TileType::Center);
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
 
                             draw_inc = (tile.dimensions().x / 2.0) + 32.0;
 
@@ -141,9 +317,40 @@ impl Map {
                             total_enemies += 1;
                         }
 
+                        'B' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            draw_inc = (tile.dimensions().x / 2.0) + 32.0;
+
+                            ground.push(tile);
+                            enemies.push(Enemy::new_boss(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                "THE GOPHER KING",
+                            ));
+
+                            draw_pos += draw_inc;
+                            total_enemies += 1;
+                        }
+
                         '4' => {
-                            let tile =
-                                Tile::new(ctx, draw_pos, physics, asset_manager, TileType::Center);
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
 
                             player = Some(Player::new(ctx, draw_pos, physics, asset_manager));
 
@@ -154,8 +361,14 @@ impl Map {
                         }
 
                         '*' => {
-                            let tile =
-                                Tile::new(ctx, draw_pos, physics, asset_manager, TileType::Center);
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
 
                             draw_inc = tile.dimensions().x;
 
@@ -165,23 +378,171 @@ impl Map {
                             draw_pos += draw_inc;
                         }
 
+                        '#' | '$' | '+' | '%' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            let contents = match id {
+                                '$' => Some(PickupKind::Ammo),
+                                '+' => Some(PickupKind::Health),
+                                '%' => Some(PickupKind::BulletTime),
+                                _ => None,
+                            };
+
+                            draw_inc = tile.dimensions().x;
+
+                            ground.push(tile);
+                            crates.push(Crate::new(ctx, draw_pos, physics, contents));
+
+                            draw_pos += draw_inc;
+                        }
+
+                        '@' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            intro.push(na::Point2::new(
+                                draw_pos,
+                                tile.position(physics).y - Self::INTRO_HEIGHT,
+                            ));
+
+                            draw_inc = tile.dimensions().x;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
+                        '|' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            checkpoints.push(na::Point2::new(
+                                draw_pos,
+                                tile.position(physics).y - Self::SPAWN_HEIGHT,
+                            ));
+
+                            draw_inc = tile.dimensions().x;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
+                        '^' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            let prompt = prompt_names
+                                .get(prompts.len())
+                                .ok_or_else(|| broken("a prompt trigger is missing its .prompt"))?;
+
+                            prompts.push((draw_pos, *prompt));
+
+                            draw_inc = tile.dimensions().x;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
+                        '?' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Dirt,
+                            );
+
+                            let script = scripts.get(dialogues.len()).ok_or_else(|| {
+                                broken("a dialogue trigger is missing its .dialogue script")
+                            })?;
+
+                            dialogues.push((draw_pos, script.clone()));
+
+                            draw_inc = tile.dimensions().x;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
+                        '!' => {
+                            let tile = Tile::new(
+                                ctx,
+                                draw_pos,
+                                physics,
+                                asset_manager,
+                                TileType::Center,
+                                Surface::Metal,
+                            );
+
+                            let (trigger, prompt) = prompts
+                                .last()
+                                .ok_or_else(|| broken("a gate has no prompt trigger before it"))?;
+                            let floor = na::Point2::new(
+                                draw_pos,
+                                tile.position(physics).y - tile.dimensions().y / 2.0,
+                            );
+
+                            gates.push(Gate::new(floor, physics, *prompt, *trigger));
+
+                            draw_inc = tile.dimensions().x;
+                            draw_pos += draw_inc;
+
+                            ground.push(tile);
+                        }
+
                         _ => {}
                     }
                 }
             }
         }
 
-        let player = player.unwrap();
+        let player = player.ok_or_else(|| broken("there's no player in it"))?;
 
-        Self {
+        Ok(Self {
             ground,
             enemies,
             barrels,
+            crates,
+            intro,
+            checkpoints,
+            prompts,
+            dialogues,
+            gates,
             player,
             total_enemies,
             end,
+            script,
+            music,
+            darkness,
+            versus,
+            boss_rush,
             using,
             weapon,
-        }
+        })
     }
 }
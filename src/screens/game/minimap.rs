@@ -0,0 +1,240 @@
+//! A small map of the level in the corner of the screen.
+//!
+//! The minimap is built from the ground tiles of the level and only shows the parts the player
+//! has already been close to. It can be expanded to show the whole level.
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, MeshBuilder, Rect},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+use nphysics2d::nalgebra as na;
+
+use crate::palette::Palette;
+
+use super::{map::Map, objective::OBJECTIVE_COLOR, physics::Physics};
+
+pub struct Minimap {
+    /// The ground tiles of the level in world coordinates.
+    tiles: Vec<Rect>,
+    /// Whether the player has been close enough to each of the tiles to see them.
+    discovered: Vec<bool>,
+    /// The part of the world the whole level takes up.
+    level: Rect,
+
+    /// Shows the map of the whole level over the screen instead of the corner.
+    pub expanded: bool,
+}
+
+impl Minimap {
+    /// The size of the minimap in the corner.
+    const SIZE: (f32, f32) = (240.0, 100.0);
+    /// How much of the world the minimap in the corner shows around the player.
+    const VIEW: (f32, f32) = (3000.0, 1250.0);
+    /// The player discovers the tiles within this distance.
+    const DISCOVER_RADIUS: f32 = 700.0;
+    /// Enemies only show up on the minimap when they are within this distance of the player.
+    const ENEMY_RADIUS: f32 = 1500.0;
+    /// How much room to leave above the ground, so the player can still be seen while jumping.
+    const SKY_HEIGHT: f32 = 900.0;
+
+    pub fn new(map: &Map, physics: &mut Physics) -> Self {
+        let tiles = map
+            .ground
+            .iter()
+            .map(|tile| {
+                let position = tile.position(physics);
+                let dimensions = tile.dimensions();
+
+                Rect::new(
+                    position.x - dimensions.x / 2.0,
+                    position.y - dimensions.y / 2.0,
+                    dimensions.x,
+                    dimensions.y,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let bounds = map.camera_bounds(physics);
+        let ground_top = tiles.iter().map(|tile| tile.y).fold(f32::MAX, f32::min);
+        let top = ground_top - Self::SKY_HEIGHT;
+
+        Self {
+            discovered: vec![false; tiles.len()],
+            tiles,
+            level: Rect::new(
+                bounds.left,
+                top,
+                bounds.right - bounds.left,
+                bounds.bottom - top,
+            ),
+
+            expanded: false,
+        }
+    }
+
+    /// Discover the tiles around the player.
+    pub fn update(&mut self, player: na::Point2<f32>) {
+        for (tile, discovered) in self.tiles.iter().zip(&mut self.discovered) {
+            let closest = tile.x.max(player.x).min(tile.x + tile.w);
+
+            if (closest - player.x).abs() < Self::DISCOVER_RADIUS {
+                *discovered = true;
+            }
+        }
+    }
+
+    /// Draw the minimap onto a screen of the provided size.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        size: Vec2,
+        player: na::Point2<f32>,
+        enemies: &[na::Point2<f32>],
+        objective: Option<na::Point2<f32>>,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let (area, screen) = if self.expanded {
+            (
+                self.level,
+                Rect::new(40.0, size.y / 4.0, size.x - 80.0, size.y / 2.0),
+            )
+        } else {
+            (
+                Rect::new(
+                    player.x - Self::VIEW.0 / 2.0,
+                    player.y - Self::VIEW.1 / 2.0,
+                    Self::VIEW.0,
+                    Self::VIEW.1,
+                ),
+                Rect::new(
+                    size.x - Self::SIZE.0 - 20.0,
+                    size.y - Self::SIZE.1 - 20.0,
+                    Self::SIZE.0,
+                    Self::SIZE.1,
+                ),
+            )
+        };
+
+        let mut mesh = MeshBuilder::new();
+
+        mesh.rectangle(DrawMode::fill(), screen, Color::new(0.0, 0.0, 0.0, 0.6));
+        mesh.rectangle(DrawMode::stroke(2.0), screen, Color::from_rgb(54, 50, 49));
+
+        // Ground
+        for (tile, _) in self
+            .tiles
+            .iter()
+            .zip(&self.discovered)
+            .filter(|(_, discovered)| **discovered)
+        {
+            if let Some(tile) = intersect(tile, &area) {
+                let top_left = to_screen(na::Point2::new(tile.x, tile.y), &area, &screen);
+                let bottom_right =
+                    to_screen(na::Point2::new(tile.right(), tile.bottom()), &area, &screen);
+
+                mesh.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(
+                        top_left.x,
+                        top_left.y,
+                        (bottom_right.x - top_left.x).max(1.0),
+                        (bottom_right.y - top_left.y).max(1.0),
+                    ),
+                    Color::from_rgb(150, 150, 150),
+                );
+            }
+        }
+
+        // Enemies
+        for enemy in enemies {
+            if na::distance(enemy, &player) < Self::ENEMY_RADIUS
+                && area.contains(Point2::new(enemy.x, enemy.y))
+            {
+                mesh.circle(
+                    DrawMode::fill(),
+                    to_screen(*enemy, &area, &screen),
+                    3.0,
+                    0.5,
+                    palette.hostile,
+                );
+            }
+        }
+
+        // Objective, stuck to the edge of the minimap when it's out of view
+        if let Some(objective) = objective {
+            let position = to_screen(objective, &area, &screen);
+            let position = Point2::new(
+                position.x.clamp(screen.left() + 4.0, screen.right() - 4.0),
+                position.y.clamp(screen.top() + 4.0, screen.bottom() - 4.0),
+            );
+
+            mesh.polygon(
+                DrawMode::fill(),
+                &[
+                    Point2::new(position.x, position.y - 5.0),
+                    Point2::new(position.x + 4.0, position.y),
+                    Point2::new(position.x, position.y + 5.0),
+                    Point2::new(position.x - 4.0, position.y),
+                ],
+                OBJECTIVE_COLOR,
+            )?;
+        }
+
+        // Player
+        mesh.circle(
+            DrawMode::fill(),
+            to_screen(player, &area, &screen),
+            4.0,
+            0.5,
+            palette.friendly,
+        );
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+}
+
+/// The overlapping part of two rectangles, if they overlap at all.
+fn intersect(a: &Rect, b: &Rect) -> Option<Rect> {
+    let left = a.left().max(b.left());
+    let right = a.right().min(b.right());
+    let top = a.top().max(b.top());
+    let bottom = a.bottom().min(b.bottom());
+
+    if left < right && top < bottom {
+        Some(Rect::new(left, top, right - left, bottom - top))
+    } else {
+        None
+    }
+}
+
+/// Map a point in the `area` of the world onto the `screen` rectangle.
+fn to_screen(point: na::Point2<f32>, area: &Rect, screen: &Rect) -> Point2<f32> {
+    Point2::new(
+        screen.x + (point.x - area.x) / area.w * screen.w,
+        screen.y + (point.y - area.y) / area.h * screen.h,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_screen() {
+        let area = Rect::new(100.0, 0.0, 1000.0, 500.0);
+        let screen = Rect::new(10.0, 10.0, 100.0, 50.0);
+
+        assert_eq!(
+            to_screen(na::Point2::new(100.0, 0.0), &area, &screen),
+            Point2::new(10.0, 10.0)
+        );
+        assert_eq!(
+            to_screen(na::Point2::new(600.0, 250.0), &area, &screen),
+            Point2::new(60.0, 35.0)
+        );
+    }
+}
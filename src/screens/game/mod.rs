@@ -1,6 +1,54 @@
-mod components;
+mod animation;
+mod batch;
+mod boss_bar;
+pub mod boss_rush;
+mod camera;
+pub mod components;
+mod console;
+mod coop;
+mod crosshair;
+mod culling;
+pub mod daily;
+mod decals;
+mod dialogue;
+pub mod entities;
+pub mod events;
+#[allow(clippy::module_inception)]
 mod game;
+mod hud;
+mod input;
+mod kill_feed;
+pub mod layout;
+mod lighting;
 mod map;
+mod minimap;
+mod movement_sounds;
+mod objective;
+pub mod objects;
+mod online;
+mod outline;
+mod particles;
+pub mod pause;
+mod photo;
 pub mod physics;
+mod post;
+#[cfg(feature = "discord")]
+mod presence;
+mod prompts;
+pub mod recording;
+mod remix;
+pub mod rng;
+mod score;
+mod script;
+mod spectator;
+mod speedrun;
+mod text_effects;
+mod time_scale;
+mod tint;
+pub mod toasts;
+mod touch;
+mod trail;
+mod versus;
+mod viewport;
 
 pub use game::*;
@@ -0,0 +1,90 @@
+//! The sounds Ferris makes moving around.
+//!
+//! Footsteps come whenever the run animation of Ferris puts a foot down, landings thump louder the faster
+//! Ferris was falling and the jetpack keeps hissing while it's thrusting. Steps and landings sound
+//! different depending on what the ground is made of.
+
+use nphysics2d::nalgebra as na;
+
+use crate::{
+    audio::{Audio, Sfx},
+    game::physics::Surface,
+};
+
+pub struct MovementSounds {
+    /// Whether the player was standing on something last frame.
+    grounded: bool,
+    /// How fast the player was falling last frame.
+    fall_speed: f32,
+    /// Whether the jetpack was thrusting last frame.
+    thrusting: bool,
+}
+
+impl MovementSounds {
+    /// Landings slower than this don't make a sound.
+    const SOFT_LANDING: f32 = 60.0;
+    /// Landings this fast or faster thump at full volume.
+    const HARD_LANDING: f32 = 400.0;
+
+    pub fn new() -> Self {
+        Self {
+            grounded: true,
+            fall_speed: 0.0,
+            thrusting: false,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        audio: &Audio,
+        ground: Option<Surface>,
+        velocity: na::Vector2<f32>,
+        thrusting: bool,
+        stepped: bool,
+    ) {
+        if thrusting && !self.thrusting {
+            audio.play(Sfx::Jump);
+        }
+
+        audio.set_looping(Sfx::Thrust, thrusting);
+
+        if let Some(surface) = ground {
+            if !self.grounded {
+                if let Some(volume) = landing_volume(self.fall_speed) {
+                    audio.play_at_volume(Sfx::Land(surface), volume);
+                }
+            } else if stepped {
+                audio.play(Sfx::Step(surface));
+            }
+        }
+
+        self.grounded = ground.is_some();
+        self.fall_speed = velocity.y.max(0.0);
+        self.thrusting = thrusting;
+    }
+}
+
+/// How loud landing at the provided fall speed is, if it can be heard at all.
+fn landing_volume(fall_speed: f32) -> Option<f32> {
+    if fall_speed < MovementSounds::SOFT_LANDING {
+        return None;
+    }
+
+    Some(
+        ((fall_speed - MovementSounds::SOFT_LANDING)
+            / (MovementSounds::HARD_LANDING - MovementSounds::SOFT_LANDING))
+            .clamp(0.2, 1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_landing_volume() {
+        assert_eq!(landing_volume(30.0), None);
+        assert_eq!(landing_volume(MovementSounds::SOFT_LANDING), Some(0.2));
+        assert_eq!(landing_volume(1000.0), Some(1.0));
+    }
+}
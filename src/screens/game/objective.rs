@@ -0,0 +1,199 @@
+//! The current goal of the level and the marker pointing the player towards it.
+//!
+//! The marker sits on top of the goal while it is on the screen. Once it goes off the screen the
+//! marker sticks to the edge of the screen and turns into an arrow pointing the way.
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, MeshBuilder, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+use nphysics2d::nalgebra as na;
+
+/// The colour of everything objective related.
+pub const OBJECTIVE_COLOR: Color = Color {
+    r: 1.0,
+    g: 196.0 / 255.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Objective {
+    /// What the player has to do.
+    pub label: &'static str,
+    /// Where in the world it has to be done.
+    pub position: na::Point2<f32>,
+}
+
+pub struct ObjectiveMarker {
+    objective: Option<Objective>,
+    /// Time since the objective last changed, makes the marker pulse when a new one comes in.
+    age: f32,
+}
+
+impl ObjectiveMarker {
+    /// How far from the edge of the screen the marker stays.
+    const MARGIN: f32 = 40.0;
+    /// The size of the marker.
+    const SIZE: f32 = 12.0;
+    /// How long the marker pulses for after the objective changes.
+    const PULSE_TIME: f32 = 1.0;
+    /// The amount of world units in a meter of the distance shown.
+    const METER: f32 = 100.0;
+
+    pub fn new() -> Self {
+        Self {
+            objective: None,
+            age: 0.0,
+        }
+    }
+
+    /// Change the objective. Returns `true` when the player has something new to do.
+    pub fn set(&mut self, objective: Option<Objective>) -> bool {
+        let changed = objective.as_ref().map(|objective| objective.label)
+            != self.objective.as_ref().map(|objective| objective.label);
+
+        if changed {
+            self.age = 0.0;
+        }
+
+        self.objective = objective;
+
+        changed && self.objective.is_some()
+    }
+
+    pub fn objective(&self) -> Option<&Objective> {
+        self.objective.as_ref()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.age += dt;
+    }
+
+    /// Draw the marker onto a screen of the provided size. `target` is where the objective shows
+    /// up on the screen.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        size: Vec2,
+        target: Vec2,
+        player: na::Point2<f32>,
+        font: Font,
+    ) -> GameResult {
+        let objective = match &self.objective {
+            Some(objective) => objective,
+            None => return Ok(()),
+        };
+
+        let (position, direction) = edge_point(target, size, Self::MARGIN);
+        let pulse = 1.0 + (1.0 - (self.age / Self::PULSE_TIME).min(1.0)) * 0.5;
+        let marker_size = Self::SIZE * pulse;
+
+        let mut mesh = MeshBuilder::new();
+
+        match direction {
+            // Off the screen, point the way.
+            Some(direction) => {
+                let side = Vec2::new(-direction.y, direction.x);
+                let tip = position + direction * marker_size;
+                let back = position - direction * marker_size * 0.5;
+
+                mesh.polygon(
+                    DrawMode::fill(),
+                    &[
+                        Point2::new(tip.x, tip.y),
+                        Point2::new(
+                            back.x + side.x * marker_size * 0.7,
+                            back.y + side.y * marker_size * 0.7,
+                        ),
+                        Point2::new(
+                            back.x - side.x * marker_size * 0.7,
+                            back.y - side.y * marker_size * 0.7,
+                        ),
+                    ],
+                    OBJECTIVE_COLOR,
+                )?;
+            }
+            // On the screen, hover a diamond above it.
+            None => {
+                mesh.polygon(
+                    DrawMode::fill(),
+                    &[
+                        Point2::new(position.x, position.y - marker_size),
+                        Point2::new(position.x + marker_size * 0.7, position.y),
+                        Point2::new(position.x, position.y + marker_size),
+                        Point2::new(position.x - marker_size * 0.7, position.y),
+                    ],
+                    OBJECTIVE_COLOR,
+                )?;
+            }
+        }
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let distance = Text::new(
+            TextFragment::new(format!(
+                "{:.0}m",
+                na::distance(&player, &objective.position) / Self::METER
+            ))
+            .font(font)
+            .scale(Scale::uniform(16.0))
+            .color(OBJECTIVE_COLOR),
+        );
+
+        let (width, _) = distance.dimensions(ctx);
+
+        graphics::draw(
+            ctx,
+            &distance,
+            DrawParam::default().dest(Point2::new(
+                position.x - width as f32 / 2.0,
+                position.y + marker_size + 4.0,
+            )),
+        )
+    }
+}
+
+/// Keep a point on a screen of the provided size, at least `margin` away from its edges. Points
+/// that had to be pulled onto the screen come with the direction they are in.
+fn edge_point(point: Vec2, size: Vec2, margin: f32) -> (Vec2, Option<Vec2>) {
+    let clamped = Vec2::new(
+        point.x.clamp(margin, size.x - margin),
+        point.y.clamp(margin, size.y - margin),
+    );
+
+    if clamped == point {
+        (point, None)
+    } else {
+        let center = size / 2.0;
+        let direction = (point - center).normalize();
+
+        (clamped, Some(direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_point() {
+        let size = Vec2::new(1000.0, 600.0);
+
+        // Points on the screen stay where they are.
+        let (point, direction) = edge_point(Vec2::new(500.0, 300.0), size, 40.0);
+
+        assert_eq!(point, Vec2::new(500.0, 300.0));
+        assert_eq!(direction, None);
+
+        // Points off to the right stick to the right edge and point that way.
+        let (point, direction) = edge_point(Vec2::new(3000.0, 300.0), size, 40.0);
+
+        assert_eq!(point, Vec2::new(960.0, 300.0));
+        assert_eq!(direction, Some(Vec2::new(1.0, 0.0)));
+    }
+}
@@ -0,0 +1,138 @@
+//! Things in the level that look after themselves.
+//!
+//! Anything that only needs the physics world to get by can be a game object: it moves itself
+//! along, draws itself, hears about what its bodies run into and says when it's done. The level
+//! keeps every object in one list and takes care of the rest, so a new kind of object doesn't need
+//! a list, an update loop and a draw loop of its own in the game screen.
+
+use ggez::{Context, GameResult};
+use ggez_goodies::camera::Camera;
+use nphysics2d::object::DefaultBodyHandle;
+
+use super::physics::{ObjectData, Physics};
+
+pub trait GameObject {
+    fn update(&mut self, physics: &mut Physics, dt: f32);
+
+    fn draw(&self, ctx: &mut Context, physics: &mut Physics, camera: &Camera) -> GameResult;
+
+    /// The bodies to hear about the collisions of. Objects that don't care about what they run
+    /// into don't need to list any.
+    fn bodies(&self) -> &[DefaultBodyHandle] {
+        &[]
+    }
+
+    /// One of the object's bodies ran into something.
+    fn on_collision(
+        &mut self,
+        _physics: &mut Physics,
+        _body: DefaultBodyHandle,
+        _other: ObjectData,
+    ) {
+    }
+
+    /// Whether the object is done and can be taken out of the level.
+    fn is_dead(&self) -> bool;
+
+    /// Take the object's bodies out of the world, once it's dead.
+    fn destroy(&mut self, physics: &mut Physics);
+}
+
+pub struct Objects {
+    objects: Vec<Box<dyn GameObject>>,
+}
+
+impl Objects {
+    pub fn new() -> Self {
+        Self { objects: vec![] }
+    }
+
+    /// Add an object to the level.
+    pub fn spawn(&mut self, object: impl GameObject + 'static) {
+        self.objects.push(Box::new(object));
+    }
+
+    /// Move every object along and tell them about their collisions. Objects that are done get
+    /// taken out of the world.
+    pub fn update(&mut self, physics: &mut Physics, dt: f32) {
+        for object in &mut self.objects {
+            object.update(physics, dt);
+
+            let bodies = object.bodies().to_vec();
+
+            for body in bodies {
+                let others: Vec<ObjectData> = physics
+                    .collisions(body)
+                    .into_iter()
+                    .map(|((_, other), ..)| other)
+                    .collect();
+
+                for other in others {
+                    object.on_collision(physics, body, other);
+                }
+            }
+        }
+
+        self.objects.retain_mut(|object| {
+            if object.is_dead() {
+                object.destroy(physics);
+
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn draw(&self, ctx: &mut Context, physics: &mut Physics, camera: &Camera) -> GameResult {
+        for object in &self.objects {
+            object.draw(ctx, physics, camera)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Objects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Timer(f32);
+
+    impl GameObject for Timer {
+        fn update(&mut self, _physics: &mut Physics, dt: f32) {
+            self.0 -= dt;
+        }
+
+        fn draw(&self, _ctx: &mut Context, _physics: &mut Physics, _camera: &Camera) -> GameResult {
+            Ok(())
+        }
+
+        fn is_dead(&self) -> bool {
+            self.0 <= 0.0
+        }
+
+        fn destroy(&mut self, _physics: &mut Physics) {}
+    }
+
+    #[test]
+    fn test_dead_objects_go() {
+        let mut physics = Physics::new();
+        let mut objects = Objects::new();
+
+        objects.spawn(Timer(1.0));
+        objects.spawn(Timer(3.0));
+
+        objects.update(&mut physics, 2.0);
+        assert_eq!(objects.objects.len(), 1);
+
+        objects.update(&mut physics, 2.0);
+        assert!(objects.objects.is_empty());
+    }
+}
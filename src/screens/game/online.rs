@@ -0,0 +1,189 @@
+//! The crabs of the other players in a game played over the internet.
+//!
+//! The other crabs don't move by themselves here, they get put where their players said they were
+//! and fire whenever their players fired. Their bullets are real ones though, so they hit the
+//! enemies, barrels and crates here just like over there.
+
+use ggez::Context;
+use ggez_goodies::nalgebra_glm::Vec2;
+use nphysics2d::nalgebra as na;
+
+use crate::{
+    net::{CrabState, Interpolator},
+    utils::AssetManager,
+};
+
+use super::{
+    components::{
+        ammo::Ammo,
+        bullet::{PlayerWeapon, WeaponType},
+        health::Health,
+        player::Player,
+    },
+    input::InputState,
+    physics::Physics,
+};
+
+pub struct Remote {
+    pub player: Player,
+    /// What the crab's player reported last.
+    state: CrabState,
+    path: Interpolator,
+}
+
+impl Remote {
+    pub fn new(
+        ctx: &mut Context,
+        physics: &mut Physics,
+        asset_manager: &AssetManager,
+        state: CrabState,
+    ) -> Self {
+        // The first report has nothing before it, a fresh crab in the corner of the level is as
+        // good as anything.
+        let fresh = CrabState {
+            x: 0.0,
+            y: 0.0,
+            health: Player::MAX_HEALTH,
+            ..state.clone()
+        };
+        let (state, _) = sanitize(&fresh, state);
+        let mut player = Player::new(ctx, state.x, physics, asset_manager);
+
+        // The crab goes wherever it's put, without gravity or anything else pushing it around.
+        player.set_noclip(physics, true);
+        player.place(physics, na::Point2::new(state.x, state.y));
+
+        Self {
+            player,
+            state,
+            path: Interpolator::new(),
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        self.state.id
+    }
+
+    pub fn state(&self) -> CrabState {
+        self.state.clone()
+    }
+
+    /// Take in what the crab's player reported as of `time`. Returns how many shots the crab
+    /// fired since the last report.
+    pub fn report(&mut self, physics: &mut Physics, state: CrabState, time: f32) -> u32 {
+        let (state, fired) = sanitize(&self.state, state);
+        let mut health = Health::new(Player::MAX_HEALTH);
+
+        health.damage(Player::MAX_HEALTH - state.health);
+        self.path.push(time, Vec2::new(state.x, state.y));
+        self.player.set_health(physics, health);
+        self.state = state;
+
+        fired
+    }
+
+    /// Fire a shot like the crab's player did. The crab never runs out, its player keeps count of
+    /// the ammo.
+    pub fn shoot(&mut self, physics: &mut Physics, asset_manager: &AssetManager) -> bool {
+        self.player.ammo = Ammo::new(Ammo::MAGAZINE_SIZE, 0);
+
+        match self
+            .player
+            .shoot(physics, asset_manager, &WeaponType::Turbofish)
+        {
+            Some(bullet) => {
+                self.player.weapons.push(bullet);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keep the crab's bullets flying and put the crab where it was at `time`. Returns where the
+    /// bullets hit the ground.
+    pub fn update(&mut self, physics: &mut Physics, time: f32, dt: f32) -> Vec<na::Point2<f32>> {
+        let impacts = self.player.update(physics, &InputState::new(), dt);
+
+        if let Some(position) = self.path.sample(time) {
+            self.player
+                .place(physics, na::Point2::new(position.x, position.y));
+        }
+
+        impacts
+    }
+
+    /// Take the crab and its bullets out of the world.
+    pub fn destroy(&mut self, physics: &mut Physics) {
+        for weapon in self.player.weapons.drain(..) {
+            if let PlayerWeapon::Turbofish(mut fish) = weapon {
+                fish.destroy(physics);
+            }
+        }
+
+        physics.destroy_body(self.player.handle());
+    }
+}
+
+/// Make what a crab's player reported safe to take in after `last`, whatever the player sent.
+/// Health that isn't a number keeps what it was and the rest stays between none and full, a
+/// position that isn't one stays where it was, and no more shots get fired at once than a
+/// magazine holds. Returns the state along with how many shots were fired.
+fn sanitize(last: &CrabState, mut state: CrabState) -> (CrabState, u32) {
+    let fired = state
+        .shots
+        .saturating_sub(last.shots)
+        .min(Ammo::MAGAZINE_SIZE);
+
+    state.health = if state.health.is_finite() {
+        state.health.clamp(0.0, Player::MAX_HEALTH)
+    } else {
+        last.health
+    };
+
+    if !state.x.is_finite() || !state.y.is_finite() {
+        state.x = last.x;
+        state.y = last.y;
+    }
+
+    (state, fired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crab(health: f32, shots: u32) -> CrabState {
+        CrabState {
+            id: 1,
+            x: 10.0,
+            y: 20.0,
+            health,
+            shots,
+            deaths: 0,
+            killer: None,
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let last = crab(80.0, 3);
+
+        assert_eq!(sanitize(&last, crab(70.0, 5)), (crab(70.0, 5), 2));
+
+        // A hostile player doesn't get to fire forever or be more than alive.
+        let hostile = CrabState {
+            x: f32::NAN,
+            y: f32::INFINITY,
+            ..crab(f32::MAX, u32::MAX)
+        };
+        let (state, fired) = sanitize(&last, hostile);
+
+        assert_eq!(fired, Ammo::MAGAZINE_SIZE);
+        assert_eq!(state.health, Player::MAX_HEALTH);
+        assert_eq!((state.x, state.y), (10.0, 20.0));
+
+        assert_eq!(sanitize(&last, crab(f32::NAN, 3)).0.health, 80.0);
+        assert_eq!(sanitize(&last, crab(-50.0, 0)), (crab(0.0, 0), 0));
+    }
+}
@@ -0,0 +1,87 @@
+//! High contrast outlines, for spotting what's dangerous against busy backgrounds.
+//!
+//! With high contrast on in the settings, the enemies and the bullets in the air get a ring drawn
+//! around them. The ring is white inside of black, so it stands out on light and dark backgrounds
+//! alike.
+
+use ggez::{
+    graphics::{self, DrawMode, DrawParam, MeshBuilder},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use nphysics2d::nalgebra as na;
+
+use crate::game::{
+    components::{enemy::Enemy, player::Player},
+    culling::is_visible,
+    physics::Physics,
+};
+
+/// How thick each of the two rings is.
+const THICKNESS: f32 = 3.0;
+
+/// How far out the bullets get their rings.
+const BULLET_RADIUS: f32 = 20.0;
+
+/// What gets outlined in the part of the world that's in view: the enemies and the bullets of
+/// every one of the `crabs`, with how far out their rings go.
+pub fn targets<'a>(
+    physics: &mut Physics,
+    enemies: &[Enemy],
+    crabs: impl Iterator<Item = &'a Player>,
+    view: graphics::Rect,
+) -> Vec<(na::Point2<f32>, f32)> {
+    let mut targets = vec![];
+
+    for enemy in enemies {
+        if let Ok(position) = enemy.position(physics) {
+            targets.push((position, enemy.radius()));
+        }
+    }
+
+    for crab in crabs {
+        for bullet in crab.bullet_positions(physics) {
+            targets.push((bullet, BULLET_RADIUS));
+        }
+    }
+
+    targets
+        .retain(|(position, radius)| is_visible(view, Vec2::new(position.x, position.y), *radius));
+
+    targets
+}
+
+/// Outline everything in `targets`, given as where they are in the world and how far out to draw
+/// the ring.
+pub fn draw(ctx: &mut Context, camera: &Camera, targets: &[(na::Point2<f32>, f32)]) -> GameResult {
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let mut mesh = MeshBuilder::new();
+
+    for (position, radius) in targets {
+        let center = camera.calculate_dest_point(Vec2::new(position.x, position.y));
+        let center = Point2::new(center.x, center.y);
+
+        mesh.circle(
+            DrawMode::stroke(THICKNESS),
+            center,
+            radius + THICKNESS,
+            0.5,
+            graphics::BLACK,
+        );
+        mesh.circle(
+            DrawMode::stroke(THICKNESS),
+            center,
+            *radius,
+            0.5,
+            graphics::WHITE,
+        );
+    }
+
+    let mesh = mesh.build(ctx)?;
+
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
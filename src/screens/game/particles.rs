@@ -0,0 +1,298 @@
+//! Sparks, smoke, dust and fire.
+//!
+//! Every effect is described by an emitter config, which says how many particles it gives off, how
+//! long they live, which way and how fast they fly, how hard gravity pulls on them and how their
+//! color and size change as they get older. Effects either go off all at once, like explosions, or
+//! keep going through an emitter, like a burning barrel. All of the particles share one pool that
+//! gets allocated along with the level, so nothing is allocated while playing. Once the pool is
+//! full new particles are dropped until old ones die out.
+
+use std::f32::consts::PI;
+
+use ggez::{
+    graphics::{self, BlendMode, Color, DrawMode, DrawParam, Drawable, MeshBuilder},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use rand::Rng;
+
+use crate::utils::lerp;
+
+/// What an effect looks like.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct EmitterConfig {
+    /// How many particles a running emitter gives off every second.
+    pub rate: f32,
+    /// How many particles the effect gives off when it goes off all at once.
+    pub burst: usize,
+    /// The shortest and the longest a particle lives, in seconds.
+    pub lifetime: (f32, f32),
+    /// The slowest and the fastest a particle starts off flying.
+    pub speed: (f32, f32),
+    /// The direction the particles fly off in, in radians. Zero is to the right.
+    pub direction: f32,
+    /// How far the particles can stray from the direction on either side, in radians.
+    pub spread: f32,
+    /// How hard the particles get pulled down. Negative values make them rise.
+    pub gravity: f32,
+    /// The color of a particle when it's born and when it dies.
+    pub color: (Color, Color),
+    /// The radius of a particle when it's born and when it dies.
+    pub size: (f32, f32),
+    pub blend: BlendMode,
+}
+
+impl EmitterConfig {
+    pub const EXPLOSION: EmitterConfig = EmitterConfig {
+        rate: 0.0,
+        burst: 80,
+        lifetime: (0.3, 0.8),
+        speed: (80.0, 420.0),
+        direction: 0.0,
+        spread: PI,
+        gravity: 150.0,
+        color: (
+            Color::new(1.0, 0.9, 0.4, 1.0),
+            Color::new(0.6, 0.1, 0.0, 0.0),
+        ),
+        size: (7.0, 2.0),
+        blend: BlendMode::Add,
+    };
+
+    pub const MUZZLE_FLASH: EmitterConfig = EmitterConfig {
+        rate: 0.0,
+        burst: 12,
+        lifetime: (0.05, 0.15),
+        speed: (150.0, 400.0),
+        direction: 0.0,
+        spread: 0.35,
+        gravity: 0.0,
+        color: (
+            Color::new(1.0, 1.0, 0.7, 1.0),
+            Color::new(1.0, 0.5, 0.0, 0.0),
+        ),
+        size: (5.0, 1.0),
+        blend: BlendMode::Add,
+    };
+
+    pub const DUST: EmitterConfig = EmitterConfig {
+        rate: 0.0,
+        burst: 14,
+        lifetime: (0.3, 0.6),
+        speed: (40.0, 140.0),
+        direction: -PI / 2.0,
+        spread: 1.3,
+        gravity: 250.0,
+        color: (
+            Color::new(0.8, 0.75, 0.7, 0.7),
+            Color::new(0.8, 0.75, 0.7, 0.0),
+        ),
+        size: (3.0, 8.0),
+        blend: BlendMode::Alpha,
+    };
+
+    pub const FIRE: EmitterConfig = EmitterConfig {
+        rate: 45.0,
+        burst: 0,
+        lifetime: (0.3, 0.7),
+        speed: (20.0, 70.0),
+        direction: -PI / 2.0,
+        spread: 0.4,
+        gravity: -120.0,
+        color: (
+            Color::new(1.0, 0.8, 0.2, 0.9),
+            Color::new(0.8, 0.1, 0.0, 0.0),
+        ),
+        size: (6.0, 2.0),
+        blend: BlendMode::Add,
+    };
+}
+
+struct Particle {
+    config: &'static EmitterConfig,
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+}
+
+impl Particle {
+    /// How far along its life the particle is, from zero to one.
+    fn life(&self) -> f32 {
+        (self.age / self.lifetime).min(1.0)
+    }
+
+    fn color(&self) -> Color {
+        let (from, to) = self.config.color;
+        let t = self.life();
+
+        Color::new(
+            lerp(from.r, to.r, t),
+            lerp(from.g, to.g, t),
+            lerp(from.b, to.b, t),
+            lerp(from.a, to.a, t),
+        )
+    }
+
+    fn size(&self) -> f32 {
+        lerp(self.config.size.0, self.config.size.1, self.life())
+    }
+}
+
+/// Every particle in the level.
+pub struct Particles {
+    pool: Vec<Particle>,
+}
+
+impl Particles {
+    /// The most particles there can be at once.
+    const CAPACITY: usize = 2048;
+    /// The blend modes the configs can use, in the order they get drawn.
+    const BLEND_MODES: [BlendMode; 2] = [BlendMode::Alpha, BlendMode::Add];
+
+    pub fn new() -> Self {
+        Self {
+            pool: Vec::with_capacity(Self::CAPACITY),
+        }
+    }
+
+    /// Set the effect off all at once at the provided point.
+    pub fn burst(&mut self, config: &'static EmitterConfig, position: Vec2, rng: &mut impl Rng) {
+        self.spawn(config, position, config.burst, rng);
+    }
+
+    fn spawn(
+        &mut self,
+        config: &'static EmitterConfig,
+        position: Vec2,
+        amount: usize,
+        rng: &mut impl Rng,
+    ) {
+        let amount = amount.min(Self::CAPACITY - self.pool.len());
+
+        for _ in 0..amount {
+            let angle = config.direction + rng.gen_range(-config.spread..=config.spread);
+            let speed = rng.gen_range(config.speed.0..=config.speed.1);
+
+            self.pool.push(Particle {
+                config,
+                position,
+                velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                age: 0.0,
+                lifetime: rng.gen_range(config.lifetime.0..=config.lifetime.1),
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.pool {
+            particle.velocity.y += particle.config.gravity * dt;
+            particle.position += particle.velocity * dt;
+            particle.age += dt;
+        }
+
+        self.pool
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera) -> GameResult {
+        for blend in Self::BLEND_MODES.iter() {
+            let mut mesh = MeshBuilder::new();
+            let mut empty = true;
+
+            for particle in self.pool.iter().filter(|p| p.config.blend == *blend) {
+                let position = camera.calculate_dest_point(particle.position);
+
+                mesh.circle(
+                    DrawMode::fill(),
+                    Point2::new(position.x, position.y),
+                    particle.size(),
+                    0.5,
+                    particle.color(),
+                );
+                empty = false;
+            }
+
+            // Building a mesh out of nothing fails.
+            if !empty {
+                let mut mesh = mesh.build(ctx)?;
+
+                mesh.set_blend_mode(Some(*blend));
+                graphics::draw(ctx, &mesh, DrawParam::default())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.pool.len()
+    }
+}
+
+/// Keeps an effect going for as long as it gets updated.
+pub struct Emitter {
+    config: &'static EmitterConfig,
+    /// The part of a particle left over from the last update.
+    carry: f32,
+}
+
+impl Emitter {
+    pub fn new(config: &'static EmitterConfig) -> Self {
+        Self { config, carry: 0.0 }
+    }
+
+    pub fn emit(&mut self, particles: &mut Particles, position: Vec2, rng: &mut impl Rng, dt: f32) {
+        self.carry += self.config.rate * dt;
+
+        let amount = self.carry.floor();
+
+        self.carry -= amount;
+        particles.spawn(self.config, position, amount as usize, rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_particles() {
+        let rng = &mut StdRng::seed_from_u64(0);
+        let mut particles = Particles::new();
+        let mut fire = Emitter::new(&EmitterConfig::FIRE);
+
+        particles.burst(&EmitterConfig::EXPLOSION, Vec2::zeros(), rng);
+        assert_eq!(particles.len(), EmitterConfig::EXPLOSION.burst);
+
+        // Everything from the explosion dies out eventually.
+        particles.update(1.0);
+        assert_eq!(particles.len(), 0);
+
+        // A running emitter keeps the leftovers for the next update.
+        fire.emit(
+            &mut particles,
+            Vec2::zeros(),
+            rng,
+            0.6 / EmitterConfig::FIRE.rate,
+        );
+        assert_eq!(particles.len(), 0);
+        fire.emit(
+            &mut particles,
+            Vec2::zeros(),
+            rng,
+            0.6 / EmitterConfig::FIRE.rate,
+        );
+        assert_eq!(particles.len(), 1);
+
+        // The pool never grows.
+        for _ in 0..100 {
+            particles.burst(&EmitterConfig::EXPLOSION, Vec2::zeros(), rng);
+        }
+        assert_eq!(particles.len(), Particles::CAPACITY);
+    }
+}
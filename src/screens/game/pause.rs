@@ -0,0 +1,101 @@
+//! The pause menu shown on top of the frozen game.
+
+use ggez::{
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{
+    display,
+    menu::{draw_item, MenuInput},
+    utils::AssetManager,
+};
+
+/// What the player picked in the pause menu.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    PhotoMode,
+    Settings,
+    QuitToMenu,
+}
+
+#[derive(Default)]
+pub struct PauseMenu {
+    /// The highlighted item.
+    selected: usize,
+}
+
+impl PauseMenu {
+    /// The items of the pause menu, along with what they do.
+    const ITEMS: [(&'static str, PauseAction); 5] = [
+        ("RESUME", PauseAction::Resume),
+        ("RESTART LEVEL", PauseAction::Restart),
+        ("PHOTO MODE", PauseAction::PhotoMode),
+        ("SETTINGS", PauseAction::Settings),
+        ("QUIT TO MENU", PauseAction::QuitToMenu),
+    ];
+
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, asset_manager: &AssetManager) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+
+        let consolas = asset_manager.text_font();
+
+        // Dim everything behind the menu.
+        let dim = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, width, height),
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        )?;
+
+        graphics::draw(ctx, &dim, DrawParam::default())?;
+
+        let paused = Text::new(
+            TextFragment::new(asset_manager.text("PAUSED"))
+                .font(consolas)
+                .scale(Scale::uniform(50.0)),
+        );
+
+        let paused_width = paused.width(ctx) as f32;
+        let top = height / 2.0 - (Self::ITEMS.len() as f32 * 50.0) / 2.0;
+
+        graphics::draw(
+            ctx,
+            &paused,
+            DrawParam::default().dest(Point2::new((width - paused_width) / 2.0, top - 80.0)),
+        )?;
+
+        for (id, (label, _)) in Self::ITEMS.iter().enumerate() {
+            draw_item(
+                ctx,
+                consolas,
+                &asset_manager.text(label),
+                Point2::new(width / 2.0 - 110.0, top + id as f32 * 50.0),
+                id == self.selected,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn navigate(&mut self, input: MenuInput) -> Option<PauseAction> {
+        let count = Self::ITEMS.len();
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Back => return Some(PauseAction::Resume),
+            MenuInput::Select => return Some(Self::ITEMS[self.selected].1),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
+    }
+}
@@ -0,0 +1,167 @@
+//! Photo mode, for action shots of Ferris.
+//!
+//! Photo mode is opened from the pause menu and keeps the level frozen while the camera gets moved
+//! around with the arrow keys and zoomed with `+` and `-` or the mouse wheel. The HUD can be
+//! hidden and a filter put over the screen before the photo is saved to the `screenshots` folder
+//! of the user data directory. The controls of photo mode never make it into the photo.
+
+use ggez::{
+    event::KeyCode,
+    graphics::{self, Color, DrawParam, Font, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use super::{post::Filter, spectator::Spectator};
+
+/// What a key did in photo mode, for the game to take care of.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PhotoAction {
+    /// Zoom the camera in or out by a factor.
+    Zoom(f32),
+    /// Put a filter over the screen.
+    Filter(Filter),
+    Leave,
+}
+
+pub struct PhotoMode {
+    camera: Spectator,
+    hud: bool,
+    filter: Filter,
+    /// Whether a photo is to be taken the next time the game gets drawn.
+    capture: bool,
+    /// Where the last photo went.
+    saved: Option<String>,
+}
+
+impl PhotoMode {
+    /// How much a press of `+` or `-` zooms the camera.
+    const ZOOM_STEP: f32 = 1.25;
+
+    /// Start taking photos with the camera at `from`.
+    pub fn new(from: Vec2) -> Self {
+        let mut camera = Spectator::new();
+
+        camera.toggle(from);
+
+        Self {
+            camera,
+            hud: true,
+            filter: Filter::None,
+            capture: false,
+            saved: None,
+        }
+    }
+
+    pub fn camera(&self) -> &Spectator {
+        &self.camera
+    }
+
+    /// Whether the HUD shows in the photos.
+    pub fn shows_hud(&self) -> bool {
+        self.hud
+    }
+
+    /// Whether a photo is waiting to be taken, which is forgotten about once asked.
+    pub fn take_capture(&mut self) -> bool {
+        std::mem::take(&mut self.capture)
+    }
+
+    pub fn saved(&mut self, path: String) {
+        self.saved = Some(path);
+    }
+
+    pub fn key(&mut self, keycode: KeyCode, down: bool) -> Option<PhotoAction> {
+        if self.camera.key(keycode, down) || !down {
+            return None;
+        }
+
+        match keycode {
+            KeyCode::Equals | KeyCode::Add => Some(PhotoAction::Zoom(Self::ZOOM_STEP)),
+            KeyCode::Minus | KeyCode::Subtract => Some(PhotoAction::Zoom(1.0 / Self::ZOOM_STEP)),
+            KeyCode::H => {
+                self.hud = !self.hud;
+
+                None
+            }
+            KeyCode::F => {
+                self.filter = self.filter.next();
+
+                Some(PhotoAction::Filter(self.filter))
+            }
+            KeyCode::Space | KeyCode::Return => {
+                self.capture = true;
+
+                None
+            }
+            KeyCode::Escape | KeyCode::P => Some(PhotoAction::Leave),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, zoom: f32, dt: f32) {
+        self.camera.update(zoom, dt);
+    }
+
+    /// Show the controls at the bottom of a screen of `size`.
+    pub fn draw(&self, ctx: &mut Context, font: Font, size: Vec2) -> GameResult {
+        let controls = format!(
+            "PHOTO MODE   ARROWS MOVE   +/- ZOOM   H HUD   F FILTER: {}   SPACE SAVE   ESC BACK",
+            self.filter.name()
+        );
+        let lines = std::iter::once(controls)
+            .chain(self.saved.as_ref().map(|path| format!("SAVED TO {}", path)));
+        let mut y = size.y - if self.saved.is_some() { 64.0 } else { 40.0 };
+
+        for line in lines {
+            let text = Text::new(
+                TextFragment::new(line)
+                    .font(font)
+                    .scale(Scale::uniform(18.0)),
+            );
+            let width = text.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default()
+                    .dest(Point2::new((size.x - width) / 2.0, y))
+                    .color(Color::new(1.0, 1.0, 1.0, 0.8)),
+            )?;
+
+            y += 24.0;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_photo_mode() {
+        let mut photo = PhotoMode::new(Vec2::new(0.0, 0.0));
+
+        // The arrow keys move the camera rather than doing anything else.
+        assert_eq!(photo.key(KeyCode::Right, true), None);
+        photo.update(1.0, 1.0);
+        assert!(photo.camera().target(Vec2::new(0.0, 0.0)).x > 0.0);
+
+        assert_eq!(
+            photo.key(KeyCode::F, true),
+            Some(PhotoAction::Filter(Filter::Grayscale))
+        );
+
+        photo.key(KeyCode::H, true);
+        assert!(!photo.shows_hud());
+
+        photo.key(KeyCode::Space, true);
+        assert!(photo.take_capture());
+        assert!(!photo.take_capture());
+
+        assert_eq!(photo.key(KeyCode::Escape, true), Some(PhotoAction::Leave));
+    }
+}
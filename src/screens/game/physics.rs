@@ -1,14 +1,10 @@
 //! This file contains a helper physics struct and a bunch of helper conversion methods.
 
-use ggez::graphics::Color;
-#[cfg(feature = "debug")]
 use ggez::{
-    graphics::{self, DrawParam, Rect},
+    graphics::{self, Color, DrawParam, Rect},
     nalgebra::Point2,
     Context, GameResult,
 };
-
-#[cfg(feature = "debug")]
 use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
 
 use ncollide2d::{pipeline::CollisionGroups, query::RayIntersection};
@@ -29,24 +25,40 @@ use nphysics2d::{
 use nphysics2d::nalgebra as na;
 use object::Collider;
 
+use crate::{
+    error::{FerrisError, FerrisResult},
+    game::entities::{Entities, EntityId},
+};
+
 type N = f32;
 
+/// What a piece of ground is made of, changes how walking on it sounds.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Surface {
+    Dirt,
+    Metal,
+}
+
 /// Enum that is made for each physics object's identity
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ObjectData {
-    Ground,
+    Ground(Surface),
     Player,
     Enemy,
     Bullet,
     Barrel,
+    Crate,
+    Pickup,
+    Debris,
     Particle(Color),
 }
 
 impl ObjectData {
-    pub fn get_particle_data(&self) -> Color {
+    /// The color of a particle, `None` for anything else.
+    pub fn particle_color(&self) -> Option<Color> {
         match *self {
-            ObjectData::Particle(particle) => particle,
-            _ => unreachable!(),
+            ObjectData::Particle(particle) => Some(particle),
+            _ => None,
         }
     }
 }
@@ -59,6 +71,8 @@ pub struct Physics {
     collider_set: object::DefaultColliderSet<N>,
     joint_constraint_set: nphysics2d::joint::DefaultJointConstraintSet<N>,
     force_generator_set: nphysics2d::force_generator::DefaultForceGeneratorSet<N>,
+    /// The entities the bodies belong to, and their components.
+    entities: Entities,
 }
 
 impl Physics {
@@ -66,6 +80,8 @@ impl Physics {
 
     /// The amount of gravity for the Y axis in the physics world.
     const GRAVITY: N = 300.0;
+    /// How much time a step takes at normal speed.
+    const TIMESTEP: N = 1.0 / 60.0;
 
     /// Create a new physics struct object.
     pub fn new() -> Self {
@@ -87,11 +103,28 @@ impl Physics {
             collider_set,
             joint_constraint_set,
             force_generator_set,
+            entities: Entities::new(),
         }
     }
 
-    /// Step the physics world.
-    pub fn step(&mut self) {
+    /// How strongly everything gets pulled down.
+    pub fn gravity(&self) -> f32 {
+        self.mechanical_world.gravity.y
+    }
+
+    pub fn set_gravity(&mut self, gravity: f32) {
+        self.mechanical_world.gravity = Vector2::new(0.0, gravity);
+    }
+
+    /// Step the physics world, `time_scale` times as far as at normal speed. Nothing moves while
+    /// the time is stopped.
+    pub fn step(&mut self, time_scale: f32) {
+        if time_scale <= 0.0 {
+            return;
+        }
+
+        self.mechanical_world
+            .set_timestep(Self::TIMESTEP * time_scale);
         self.mechanical_world.step(
             &mut self.geometrical_world,
             &mut self.body_set,
@@ -99,6 +132,13 @@ impl Physics {
             &mut self.joint_constraint_set,
             &mut self.force_generator_set,
         );
+        self.sync_transforms();
+    }
+
+    /// Run the transform system, so the transforms are where the bodies are. Every step does, and
+    /// so does anything putting a body somewhere else in between.
+    pub fn sync_transforms(&mut self) {
+        self.entities.sync_transforms(&self.body_set);
     }
 
     // Creates a new tile body.
@@ -107,6 +147,7 @@ impl Physics {
         pos: na::Point2<f32>,
         width: u16,
         height: u16,
+        surface: Surface,
     ) -> DefaultBodyHandle {
         let width = width as f32;
         let height = height as f32;
@@ -125,7 +166,7 @@ impl Physics {
             .material(material::MaterialHandle::new(material::BasicMaterial::new(
                 0.0, 0.0,
             )))
-            .user_data(ObjectData::Ground)
+            .user_data(ObjectData::Ground(surface))
             .build(BodyPartHandle(ground_handle, 0));
 
         self.collider_set.insert(collider);
@@ -139,7 +180,7 @@ impl Physics {
         pos: na::Point2<f32>,
         width: u16,
         height: u16,
-    ) -> DefaultBodyHandle {
+    ) -> (DefaultBodyHandle, EntityId) {
         let width = width as f32;
         let height = height as f32;
 
@@ -164,7 +205,9 @@ impl Physics {
 
         self.collider_set.insert(collider);
 
-        player_handle
+        let entity = self.entities.spawn(player_handle, pos, "player");
+
+        (player_handle, entity)
     }
 
     /// Create a new enemy body.
@@ -173,7 +216,7 @@ impl Physics {
         pos: na::Point2<f32>,
         width: u16,
         height: u16,
-    ) -> DefaultBodyHandle {
+    ) -> (DefaultBodyHandle, EntityId) {
         let width = width as f32;
         let height = height as f32;
 
@@ -198,7 +241,9 @@ impl Physics {
 
         self.collider_set.insert(collider);
 
-        enemy_handle
+        let entity = self.entities.spawn(enemy_handle, pos, "enemy");
+
+        (enemy_handle, entity)
     }
 
     /// Create a new enemy body.
@@ -207,7 +252,7 @@ impl Physics {
         pos: na::Point2<f32>,
         width: u16,
         height: u16,
-    ) -> DefaultBodyHandle {
+    ) -> (DefaultBodyHandle, EntityId) {
         let width = width as f32;
         let height = height as f32;
 
@@ -232,7 +277,81 @@ impl Physics {
 
         self.collider_set.insert(collider);
 
-        barrel_handle
+        let entity = self.entities.spawn(barrel_handle, pos, "barrel");
+
+        (barrel_handle, entity)
+    }
+
+    /// Create a new crate body.
+    pub fn create_crate(
+        &mut self,
+        pos: na::Point2<f32>,
+        width: u16,
+        height: u16,
+    ) -> (DefaultBodyHandle, EntityId) {
+        let width = width as f32;
+        let height = height as f32;
+
+        let crate_body = RigidBodyDesc::new()
+            .position(point_to_isometry(pos))
+            .mass(10.0)
+            .linear_damping(1.0)
+            .status(BodyStatus::Dynamic)
+            .build();
+        let crate_handle = self.body_set.insert(crate_body);
+
+        let shape = ShapeHandle::new(Cuboid::new(Vector2::new(
+            width / 2.0 - 0.01,
+            height / 2.0 - 0.01,
+        )));
+        let collider = ColliderDesc::new(shape)
+            .material(material::MaterialHandle::new(material::BasicMaterial::new(
+                0.0, 0.0,
+            )))
+            .user_data(ObjectData::Crate)
+            .build(BodyPartHandle(crate_handle, 0));
+
+        self.collider_set.insert(collider);
+
+        let entity = self.entities.spawn(crate_handle, pos, "crate");
+
+        (crate_handle, entity)
+    }
+
+    /// Create a new pickup body.
+    pub fn create_pickup(
+        &mut self,
+        pos: na::Point2<f32>,
+        width: u16,
+        height: u16,
+    ) -> (DefaultBodyHandle, EntityId) {
+        let width = width as f32;
+        let height = height as f32;
+
+        let pickup = RigidBodyDesc::new()
+            .position(point_to_isometry(pos))
+            .mass(1.0)
+            .linear_damping(1.0)
+            .status(BodyStatus::Dynamic)
+            .build();
+        let pickup_handle = self.body_set.insert(pickup);
+
+        let shape = ShapeHandle::new(Cuboid::new(Vector2::new(
+            width / 2.0 - 0.01,
+            height / 2.0 - 0.01,
+        )));
+        let collider = ColliderDesc::new(shape)
+            .material(material::MaterialHandle::new(material::BasicMaterial::new(
+                0.0, 0.0,
+            )))
+            .user_data(ObjectData::Pickup)
+            .build(BodyPartHandle(pickup_handle, 0));
+
+        self.collider_set.insert(collider);
+
+        let entity = self.entities.spawn(pickup_handle, pos, "pickup");
+
+        (pickup_handle, entity)
     }
 
     /// Create a new bullet. Can be any included in crate::components::bullet::PlayerWeapon enum
@@ -241,7 +360,7 @@ impl Physics {
         pos: na::Point2<f32>,
         width: u16,
         height: u16,
-    ) -> DefaultBodyHandle {
+    ) -> (DefaultBodyHandle, EntityId) {
         let width = width as f32;
         let height = height as f32;
 
@@ -266,14 +385,14 @@ impl Physics {
 
         self.collider_set.insert(collider);
 
-        bullet_handle
+        let entity = self.entities.spawn(bullet_handle, pos, "bullet");
+
+        (bullet_handle, entity)
     }
 
     /// Create a new rigid body
     pub fn create_rigid_body(&mut self, body: RigidBody<f32>) -> DefaultBodyHandle {
-        let handle = self.body_set.insert(body);
-
-        handle
+        self.body_set.insert(body)
     }
 
     /// Create a new collider
@@ -281,26 +400,38 @@ impl Physics {
         self.collider_set.insert(collider);
     }
 
-    /// Returns a immutable body from the handle provided by the above helper functions.
-    pub fn get_rigid_body(&mut self, handle: DefaultBodyHandle) -> &RigidBody<f32> {
-        let body = self.body_set.rigid_body(handle).expect("Body not found!");
+    /// Where a body is in the world, as long as it's still in there.
+    pub fn body_position(&self, handle: DefaultBodyHandle) -> FerrisResult<na::Point2<f32>> {
+        Ok(isometry_to_point(self.rigid_body(handle)?.position()))
+    }
 
-        body
+    /// How fast a body is going, as long as it's still in the world.
+    pub fn body_velocity(&self, handle: DefaultBodyHandle) -> FerrisResult<na::Vector2<f32>> {
+        Ok(self.rigid_body(handle)?.velocity().linear)
     }
 
-    /// Returns a mutable body from the handle provided by the above helper functions.
-    pub fn get_rigid_body_mut(&mut self, handle: DefaultBodyHandle) -> &mut RigidBody<f32> {
-        let body = self
-            .body_set
-            .rigid_body_mut(handle)
-            .expect("Body not found!");
+    /// Returns a body from the handle provided by the above helper functions. Bodies get taken out
+    /// of the world when what they belong to goes away, so a handle kept around for too long
+    /// doesn't find one.
+    pub fn rigid_body(&self, handle: DefaultBodyHandle) -> FerrisResult<&RigidBody<f32>> {
+        self.body_set
+            .rigid_body(handle)
+            .ok_or_else(|| missing_body(handle))
+    }
 
-        body
+    /// The mutable version of `rigid_body`.
+    pub fn rigid_body_mut(
+        &mut self,
+        handle: DefaultBodyHandle,
+    ) -> FerrisResult<&mut RigidBody<f32>> {
+        self.body_set
+            .rigid_body_mut(handle)
+            .ok_or_else(|| missing_body(handle))
     }
 
     /// Simple helper function that allows you to see the colliders.
-    /// To be able to show the colliders run Call of Ferris by `cargo run --features=["debug"]`
-    #[cfg(feature = "debug")]
+    /// To be able to show the colliders turn on `colliders` in the `[debug]` table of the config,
+    /// or run Call of Ferris by `cargo run --features=["debug"]`
     pub fn draw_colliders(&self, ctx: &mut Context, camera: &Camera) -> GameResult {
         for (_, collider) in self.collider_set.iter() {
             let shape = collider.shape().aabb(collider.position());
@@ -338,40 +469,56 @@ impl Physics {
             .contacts_with(&self.collider_set, object, true)
             .into_iter()
             .flatten()
-            .map(|(handle1, _, handle2, _, _, manifold)| {
-                (
-                    (self.get_user_data(handle1), self.get_user_data(handle2)),
+            .filter_map(|(handle1, _, handle2, _, _, manifold)| {
+                Some((
+                    (self.get_user_data(handle1)?, self.get_user_data(handle2)?),
                     handle2,
                     manifold,
-                )
+                ))
             })
             .collect()
     }
 
-    /// Gets the user data of the 2 handles provided in the collisions function.
-    pub fn get_user_data(&self, object: DefaultBodyHandle) -> ObjectData {
-        let collider = self.collider_set.get(object).unwrap();
+    /// The entities the bodies belong to, and their components.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
 
-        let data = *collider
-            .user_data()
-            .unwrap()
-            .downcast_ref::<ObjectData>()
-            .expect("Invalid types");
+    pub fn entities_mut(&mut self) -> &mut Entities {
+        &mut self.entities
+    }
 
-        data
+    /// Gets the user data of the 2 handles provided in the collisions function. Every collider the
+    /// helpers above create has some, so only a collider that's gone doesn't.
+    pub fn get_user_data(&self, object: DefaultBodyHandle) -> Option<ObjectData> {
+        self.collider_set
+            .get(object)?
+            .user_data()?
+            .downcast_ref::<ObjectData>()
+            .copied()
     }
 
     /// Get the distance between a object
-    pub fn distance(&mut self, object1: DefaultBodyHandle, object2: DefaultBodyHandle) -> f32 {
-        let pos_1 = self.collider_set.get(object1).unwrap();
-        let pos_2 = self.collider_set.get(object2).unwrap();
-
-        ncollide2d::query::distance(
+    pub fn distance(
+        &mut self,
+        object1: DefaultBodyHandle,
+        object2: DefaultBodyHandle,
+    ) -> FerrisResult<f32> {
+        let pos_1 = self
+            .collider_set
+            .get(object1)
+            .ok_or(FerrisError::MissingBody(object1))?;
+        let pos_2 = self
+            .collider_set
+            .get(object2)
+            .ok_or(FerrisError::MissingBody(object2))?;
+
+        Ok(ncollide2d::query::distance(
             pos_1.position(),
             pos_1.shape(),
             pos_2.position(),
             pos_2.shape(),
-        )
+        ))
     }
 
     /// Perform a raycast
@@ -393,18 +540,36 @@ impl Physics {
                 f32::MAX,
                 &CollisionGroups::default(),
             )
-            .map(|(handle, collider, intersection)| {
-                (self.get_user_data(handle), collider, intersection)
+            .filter_map(|(handle, collider, intersection)| {
+                Some((self.get_user_data(handle)?, collider, intersection))
             })
             .collect()
     }
 
     pub fn destroy_body(&mut self, handle: DefaultBodyHandle) {
+        if self.body_set.rigid_body(handle).is_none() {
+            log::warn!("Destroying {:?}, which is already gone", handle);
+        }
+
+        self.entities.despawn(handle);
         self.body_set.remove(handle);
         self.collider_set.remove(handle);
     }
 }
 
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error for a body that's not in the world anymore, which shouldn't happen often.
+fn missing_body(handle: DefaultBodyHandle) -> FerrisError {
+    log::warn!("{:?} is not in the physics world", handle);
+
+    FerrisError::MissingBody(handle)
+}
+
 /// Converts isometry to point
 pub fn isometry_to_point<N: na::RealField + Copy + na::Scalar>(
     isometry: &Isometry2<N>,
@@ -0,0 +1,305 @@
+//! Effects applied to the whole screen once everything has been drawn.
+//!
+//! When any of the effects are on, the game gets drawn into a canvas first, which then goes onto the
+//! screen through the post shader. Getting hurt flashes the screen red and reddens its edges for a
+//! moment, low health closes in on the middle of the screen with a vignette and explosions split
+//! the colors apart for a moment. The CRT filter makes the whole thing look like an old TV.
+//! Everything but the CRT filter goes away with the screen effects setting. The filters of photo
+//! mode go on top of it all.
+
+use ggez::{
+    conf::NumSamples,
+    graphics::{self, Canvas, Color, DrawParam, GlBackendSpec, Shader, ShaderGeneric},
+    nalgebra::{Point2, Vector2},
+    Context, GameResult,
+};
+
+use crate::config::Config;
+
+use gfx::*;
+
+gfx_defines! {
+    constant Post {
+        flash: [f32; 4] = "u_Flash",
+        resolution: [f32; 2] = "u_Resolution",
+        vignette: f32 = "u_Vignette",
+        aberration: f32 = "u_Aberration",
+        crt: f32 = "u_Crt",
+        filter: f32 = "u_Filter",
+    }
+}
+
+/// Something the post shader does to the screen.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PostEffect {
+    /// Flash the screen with a color.
+    Flash(Color),
+    /// Split the colors apart by the provided strength, between 0.0 and 1.0.
+    Aberration(f32),
+}
+
+/// A look for the whole screen, to take photos with.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Filter {
+    None,
+    Grayscale,
+    Sepia,
+    Noir,
+    Vivid,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 5] = [
+        Filter::None,
+        Filter::Grayscale,
+        Filter::Sepia,
+        Filter::Noir,
+        Filter::Vivid,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::None => "NONE",
+            Filter::Grayscale => "GRAYSCALE",
+            Filter::Sepia => "SEPIA",
+            Filter::Noir => "NOIR",
+            Filter::Vivid => "VIVID",
+        }
+    }
+
+    /// The filter after this one, going around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|filter| *filter == self)
+            .unwrap_or(0);
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// What the post shader knows the filter as.
+    fn shader_id(self) -> f32 {
+        Self::ALL
+            .iter()
+            .position(|filter| *filter == self)
+            .unwrap_or(0) as f32
+    }
+}
+
+pub struct PostProcessing {
+    shader: ShaderGeneric<GlBackendSpec, Post>,
+    /// The game gets drawn into this before going onto the screen. Made again whenever the size of
+    /// the window changes, and lent out while the game is being drawn.
+    canvas: Option<Canvas>,
+
+    /// Whether getting hurt, low health and explosions show on the screen.
+    effects: bool,
+    /// Whether the screen gets flashed.
+    flashing: bool,
+    crt: bool,
+    filter: Filter,
+
+    /// The color of the flash and how strong it still is.
+    flash: (Color, f32),
+    aberration: f32,
+    /// How far the red edge from the last hit still reaches in.
+    hurt: f32,
+    /// How much of their health the player had last frame.
+    health: f32,
+}
+
+impl PostProcessing {
+    /// How long it takes for a flash to fade out.
+    const FLASH_TIME: f32 = 0.25;
+    /// How strong a flash starts out.
+    const FLASH_STRENGTH: f32 = 0.45;
+    /// How long it takes for the colors to come back together.
+    const ABERRATION_TIME: f32 = 0.4;
+    /// The vignette creeps in once the player has less than this much of their health left.
+    const LOW_HEALTH: f32 = 0.35;
+    /// How long the red edge from getting hurt takes to go away, and how far it reaches in.
+    const HURT_TIME: f32 = 0.5;
+    const HURT_VIGNETTE: f32 = 0.6;
+    pub const DAMAGE_COLOR: Color = Color::new(0.8, 0.0, 0.0, 1.0);
+
+    pub fn new(ctx: &mut Context, config: &Config) -> GameResult<Self> {
+        let constant = Post {
+            flash: [0.0; 4],
+            resolution: [1.0, 1.0],
+            vignette: 0.0,
+            aberration: 0.0,
+            crt: 0.0,
+            filter: 0.0,
+        };
+
+        let shader = Shader::new(
+            ctx,
+            "/shaders/dim.basic.glslf",
+            "/shaders/post.glslf",
+            constant,
+            "Post",
+            None,
+        )?;
+
+        Ok(Self {
+            shader,
+            canvas: None,
+
+            effects: config.screen_effects,
+            flashing: config.flashing,
+            crt: config.crt_filter,
+            filter: Filter::None,
+
+            flash: (graphics::BLACK, 0.0),
+            aberration: 0.0,
+            hurt: 0.0,
+            health: 1.0,
+        })
+    }
+
+    pub fn apply_config(&mut self, config: &Config) {
+        self.effects = config.screen_effects;
+        self.flashing = config.flashing;
+        self.crt = config.crt_filter;
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    pub fn play(&mut self, effect: PostEffect) {
+        match effect {
+            PostEffect::Flash(_) if !self.flashing => {}
+            PostEffect::Flash(color) => self.flash = (color, 1.0),
+            PostEffect::Aberration(strength) => self.aberration = self.aberration.max(strength),
+        }
+    }
+
+    /// Keep track of how much of their health the player has left, as a part of their full health.
+    /// Losing some flashes the screen and reddens its edges for a moment.
+    pub fn set_health(&mut self, health: f32) {
+        if health < self.health {
+            self.play(PostEffect::Flash(Self::DAMAGE_COLOR));
+            self.hurt = 1.0;
+        }
+
+        self.health = health;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.flash.1 = (self.flash.1 - dt / Self::FLASH_TIME).max(0.0);
+        self.aberration = (self.aberration - dt / Self::ABERRATION_TIME).max(0.0);
+        self.hurt = (self.hurt - dt / Self::HURT_TIME).max(0.0);
+    }
+
+    /// How far the vignette closes in, from low health or a hit just taken.
+    fn vignette(&self) -> f32 {
+        vignette(self.health).max(self.hurt * Self::HURT_VIGNETTE)
+    }
+
+    /// Whether the game has to go through the post shader at all.
+    fn is_active(&self) -> bool {
+        self.crt
+            || self.filter != Filter::None
+            || self.effects
+                && (self.flash.1 > 0.0 || self.aberration > 0.0 || self.vignette() > 0.0)
+    }
+
+    /// Start drawing the game. Returns the canvas it's being drawn into, if it has to go through
+    /// the post shader, which has to be handed back to `end()` once the game is drawn.
+    pub fn begin(&mut self, ctx: &mut Context) -> GameResult<Option<Canvas>> {
+        if !self.is_active() {
+            return Ok(None);
+        }
+
+        let (width, height) = graphics::drawable_size(ctx);
+        let canvas = match self.canvas.take() {
+            Some(canvas)
+                if canvas.image().width() == width as u16
+                    && canvas.image().height() == height as u16 =>
+            {
+                canvas
+            }
+            _ => Canvas::new(ctx, width as u16, height as u16, NumSamples::One)?,
+        };
+
+        graphics::set_canvas(ctx, Some(&canvas));
+        graphics::clear(ctx, graphics::BLACK);
+
+        Ok(Some(canvas))
+    }
+
+    /// Put the game onto the screen through the post shader.
+    pub fn end(&mut self, ctx: &mut Context, canvas: Option<Canvas>) -> GameResult {
+        let canvas = match canvas {
+            Some(canvas) => canvas,
+            None => return Ok(()),
+        };
+
+        let (width, height) = graphics::drawable_size(ctx);
+        let coordinates = graphics::screen_coordinates(ctx);
+        let effects = if self.effects { 1.0 } else { 0.0 };
+        let (color, flash) = self.flash;
+
+        graphics::set_canvas(ctx, None);
+
+        self.shader.send(
+            ctx,
+            Post {
+                flash: [
+                    color.r,
+                    color.g,
+                    color.b,
+                    flash * Self::FLASH_STRENGTH * effects,
+                ],
+                resolution: [width, height],
+                vignette: self.vignette() * effects,
+                aberration: self.aberration * effects,
+                crt: if self.crt { 1.0 } else { 0.0 },
+                filter: self.filter.shader_id(),
+            },
+        )?;
+
+        {
+            let _lock = graphics::use_shader(ctx, &self.shader);
+
+            // The canvas is as big as the window, the screen coordinates might be letterboxed.
+            graphics::draw(
+                ctx,
+                &canvas,
+                DrawParam::default()
+                    .dest(Point2::new(coordinates.x, coordinates.y))
+                    .scale(Vector2::new(coordinates.w / width, coordinates.h / height)),
+            )?;
+        }
+
+        self.canvas = Some(canvas);
+
+        Ok(())
+    }
+}
+
+/// How far the vignette closes in with the provided part of their health left.
+fn vignette(health: f32) -> f32 {
+    ((PostProcessing::LOW_HEALTH - health) / PostProcessing::LOW_HEALTH).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vignette() {
+        assert_eq!(vignette(1.0), 0.0);
+        assert_eq!(vignette(PostProcessing::LOW_HEALTH), 0.0);
+        assert_eq!(vignette(0.0), 1.0);
+        assert!(vignette(PostProcessing::LOW_HEALTH / 2.0) > 0.0);
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(Filter::None.next(), Filter::Grayscale);
+        assert_eq!(Filter::Vivid.next(), Filter::None);
+        assert_eq!(Filter::Sepia.shader_id(), 2.0);
+    }
+}
@@ -0,0 +1,64 @@
+//! Keeps the Discord Rich Presence up with the level being played.
+//!
+//! Tells Discord about the level and the mode it's played in once it starts, and about how far it
+//! got every time something worth telling happens on the event bus. The time played gets counted
+//! by Discord from when the level started.
+
+use crate::{
+    discord::{self, Activity, Presence},
+    game::events::{GameEvent, Subscriber},
+};
+
+pub struct LevelPresence {
+    presence: Presence,
+    /// When the level started.
+    start: u64,
+    /// Whether there's news since Discord was last told.
+    dirty: bool,
+    /// How many are playing together and how many could, while playing together.
+    party: Option<(u32, u32)>,
+}
+
+impl LevelPresence {
+    pub fn new(presence: Presence) -> Self {
+        Self {
+            presence,
+            start: discord::now(),
+            dirty: true,
+            party: None,
+        }
+    }
+
+    /// Tell Discord about the level and how far it got, if there's news or the party changed.
+    pub fn update(
+        &mut self,
+        party: Option<(u32, u32)>,
+        describe: impl FnOnce() -> (String, String),
+    ) {
+        if !self.dirty && party == self.party {
+            return;
+        }
+
+        let (details, state) = describe();
+
+        self.dirty = false;
+        self.party = party;
+        self.presence.set(Activity {
+            details,
+            state,
+            start: self.start,
+            party,
+        });
+    }
+}
+
+impl Subscriber for LevelPresence {
+    fn notify(&mut self, event: &GameEvent) {
+        if let GameEvent::EnemyKilled { .. }
+        | GameEvent::CheckpointReached
+        | GameEvent::LevelCompleted = event
+        {
+            self.dirty = true;
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Key hints that teach the player the controls.
+//!
+//! Prompts get triggered by the level the first time the player runs into something new and stay
+//! up until the player does what they say. Every prompt is only ever shown once, the save keeps
+//! track of the ones that have been seen. Prompts name whatever the action is bound to, and show
+//! buttons of the controller instead of keys while the player is playing with one.
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, Mesh, Rect, Scale, Text, TextFragment},
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::{
+    bindings::{button_name, key_name, Action, Bindings},
+    i18n::Localization,
+};
+
+use super::{
+    events::GameEvent,
+    input::Device,
+    layout::{Anchor, Layout},
+};
+
+/// Something the player can be taught.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Prompt {
+    Move,
+    Jump,
+    Shoot,
+    Map,
+    /// Shooting a barrel, which only counts once it goes off.
+    Explode,
+}
+
+impl Prompt {
+    /// Find a prompt by its name in the level.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "move" => Some(Prompt::Move),
+            "jump" => Some(Prompt::Jump),
+            "shoot" => Some(Prompt::Shoot),
+            "map" => Some(Prompt::Map),
+            "explode" => Some(Prompt::Explode),
+            _ => None,
+        }
+    }
+
+    /// The name of the prompt, used in the level and the save.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prompt::Move => "move",
+            Prompt::Jump => "jump",
+            Prompt::Shoot => "shoot",
+            Prompt::Map => "map",
+            Prompt::Explode => "explode",
+        }
+    }
+
+    fn text(&self, device: Device, bindings: &Bindings, localization: &Localization) -> String {
+        let key = |action| match (device, action) {
+            (Device::Gamepad, _) => button_name::label(bindings.get(action).button).to_string(),
+            (Device::Touch, Action::Jump) => localization.text("JUMP").to_string(),
+            (Device::Touch, Action::Fire) => localization.text("FIRE").to_string(),
+            // There is no touch button for everything.
+            (Device::Keyboard, _) | (Device::Touch, _) => key_name::label(bindings.get(action).key),
+        };
+
+        match self {
+            Prompt::Move if device == Device::Gamepad => {
+                localization.text("Push the LEFT STICK to move").to_string()
+            }
+            Prompt::Move if device == Device::Touch => localization
+                .text("Drag the stick on the left to move")
+                .to_string(),
+            Prompt::Move => localization.text_with(
+                "Press {left} and {right} to move",
+                &[("left", &key(Action::Left)), ("right", &key(Action::Right))],
+            ),
+            Prompt::Jump => {
+                localization.text_with("Hold {key} to jump", &[("key", &key(Action::Jump))])
+            }
+            Prompt::Shoot => {
+                localization.text_with("Press {key} to shoot", &[("key", &key(Action::Fire))])
+            }
+            Prompt::Map => localization.text_with(
+                "Press {key} to see the whole map",
+                &[("key", &key(Action::Map))],
+            ),
+            Prompt::Explode => localization.text_with(
+                "Shoot a barrel with {key} to blow it up",
+                &[("key", &key(Action::Fire))],
+            ),
+        }
+    }
+
+    /// The actions that count as doing what the prompt says.
+    fn actions(&self) -> &'static [Action] {
+        match self {
+            Prompt::Move => &[Action::Left, Action::Right],
+            Prompt::Jump => &[Action::Jump],
+            Prompt::Shoot => &[Action::Fire],
+            Prompt::Map => &[Action::Map],
+            Prompt::Explode => &[],
+        }
+    }
+
+    /// Whether the action is what the prompt says.
+    pub fn is_done_by(&self, action: Action) -> bool {
+        self.actions().contains(&action)
+    }
+
+    /// Whether what happened is what the prompt says, for the prompts that aren't done with an
+    /// action.
+    pub fn is_done_by_event(&self, event: &GameEvent) -> bool {
+        matches!(
+            (self, event),
+            (Prompt::Explode, GameEvent::BarrelExploded { .. })
+        )
+    }
+}
+
+pub struct Prompts {
+    /// The prompt being shown.
+    current: Option<Prompt>,
+    /// How far the prompt has faded in, between 0.0 and 1.0.
+    shown: f32,
+    /// The prompt that was last shown, kept around while it fades out.
+    fading: Option<Prompt>,
+}
+
+impl Prompts {
+    /// How long fading in and out takes.
+    const FADE_TIME: f32 = 0.3;
+
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            shown: 0.0,
+            fading: None,
+        }
+    }
+
+    pub fn is_showing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn show(&mut self, prompt: Prompt) {
+        self.current = Some(prompt);
+        self.fading = Some(prompt);
+    }
+
+    /// Take the prompt down if the action is what it says. Returns the prompt that was done.
+    pub fn done(&mut self, action: Action) -> Option<Prompt> {
+        let prompt = self.current?;
+
+        if prompt.is_done_by(action) {
+            self.current = None;
+
+            Some(prompt)
+        } else {
+            None
+        }
+    }
+
+    /// Take the prompt down if what happened is what it says. Returns the prompt that was done.
+    pub fn done_by_event(&mut self, event: &GameEvent) -> Option<Prompt> {
+        let prompt = self.current?;
+
+        if prompt.is_done_by_event(event) {
+            self.current = None;
+
+            Some(prompt)
+        } else {
+            None
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let step = dt / Self::FADE_TIME;
+
+        if self.current.is_some() {
+            self.shown = (self.shown + step).min(1.0);
+        } else {
+            self.shown = (self.shown - step).max(0.0);
+        }
+    }
+
+    /// Draw the prompt above the bottom of the virtual screen, in terms of the device being used.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        layout: &Layout,
+        font: Font,
+        device: Device,
+        bindings: &Bindings,
+        localization: &Localization,
+    ) -> GameResult {
+        let prompt = match self.fading {
+            Some(prompt) if self.shown > 0.0 => prompt,
+            _ => return Ok(()),
+        };
+
+        let text = Text::new(
+            TextFragment::new(prompt.text(device, bindings, localization))
+                .font(font)
+                .scale(Scale::uniform(20.0))
+                .color(Color::new(1.0, 1.0, 1.0, self.shown)),
+        );
+
+        let (width, height) = text.dimensions(ctx);
+        let (width, height) = (width as f32, height as f32);
+        let center = layout.place(Anchor::Bottom, Vec2::new(0.0, -180.0));
+
+        let background = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                center.x - width / 2.0 - 16.0,
+                center.y - height / 2.0 - 10.0,
+                width + 32.0,
+                height + 20.0,
+            ),
+            Color::new(0.0, 0.0, 0.0, 0.6 * self.shown),
+        )?;
+
+        graphics::draw(ctx, &background, DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default().dest([center.x - width / 2.0, center.y - height / 2.0]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dismiss() {
+        let mut prompts = Prompts::new();
+
+        prompts.show(Prompt::Jump);
+
+        // Only doing what the prompt says takes it down.
+        assert_eq!(prompts.done(Action::Fire), None);
+        assert_eq!(prompts.done(Action::Jump), Some(Prompt::Jump));
+        assert!(!prompts.is_showing());
+
+        assert_eq!(Prompt::parse(Prompt::Map.name()), Some(Prompt::Map));
+
+        // Blowing something up can't be done with a press alone.
+        prompts.show(Prompt::Explode);
+        assert_eq!(prompts.done(Action::Fire), None);
+        assert_eq!(
+            prompts.done_by_event(&GameEvent::BarrelExploded { chain: 1 }),
+            Some(Prompt::Explode)
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! Recording what the player does and playing it back.
+//!
+//! A recording keeps the actions held and pressed with every update of a level, so feeding them
+//! back into the input state plays the run over the same way. The seed the run's rolls came from
+//! goes along with them, so the dice come up the same way too. Updates that look the same in a row
+//! are stored once along with how many there were, which keeps the files small. Recordings are
+//! stored as TOML:
+//!
+//! ```toml
+//! level = "01.map"
+//! seed = 1234
+//! format = 1
+//! game = "0.1.0"
+//! map = 3735928559
+//!
+//! [[frames]]
+//! held = ["Right"]
+//! pressed = ["Right"]
+//!
+//! [[frames]]
+//! held = ["Right"]
+//! updates = 40
+//! ```
+//!
+//! Replays can be shared with others too, as one line of JSON in a `.replay` file. A replay only
+//! plays back the same way on the same version of the game and the same level, so both get
+//! written down and checked before a replay is watched.
+//!
+//! The demos the menu plays when it's left alone are recordings too, bundled in
+//! `resources/demos`. They leave out the version of the game and the checksum of the level, so
+//! they keep playing after either changes, and only get checked for being laid out in a format
+//! the game knows.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ggez::{
+    filesystem,
+    graphics::{self, Color, DrawMode, DrawParam, Font, Rect, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::Action,
+    display,
+    error::FerrisResult,
+    game::daily::DailyChallenge,
+    save::{Difficulty, NewGamePlus},
+    utils::AssetManager,
+};
+
+/// What the player was doing during one or more updates in a row.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputFrame {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub held: Vec<Action>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pressed: Vec<Action>,
+    /// How many updates in a row this lasted.
+    #[serde(skip_serializing_if = "is_one")]
+    pub updates: u32,
+}
+
+fn is_one(updates: &u32) -> bool {
+    *updates == 1
+}
+
+impl Default for InputFrame {
+    fn default() -> Self {
+        Self {
+            held: vec![],
+            pressed: vec![],
+            updates: 1,
+        }
+    }
+}
+
+impl InputFrame {
+    pub fn new(held: Vec<Action>, pressed: Vec<Action>) -> Self {
+        Self {
+            held,
+            pressed,
+            updates: 1,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// The level that was played.
+    pub level: String,
+    /// What the run's random rolls were seeded with.
+    #[serde(default)]
+    pub seed: u64,
+    /// How the recording is laid out. Recordings from before it was written down are laid out
+    /// like the first format.
+    #[serde(default)]
+    format: u32,
+    /// The version of the game the run was played on, recordings from before it was written
+    /// down leave it out.
+    #[serde(default)]
+    game: String,
+    /// The checksum of the level the run was played on, left out like the version.
+    #[serde(default)]
+    map: u32,
+    /// How hard the game was, the level gets built the same way to play it back.
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// The New Game+ the run was played in, if it was.
+    #[serde(default)]
+    pub new_game_plus: Option<NewGamePlus>,
+    /// The daily challenge the run was, if it was.
+    #[serde(default)]
+    pub daily: Option<DailyChallenge>,
+    /// How fast the game went, it goes that fast again to play the run back.
+    #[serde(default = "full_speed")]
+    pub game_speed: f32,
+    frames: Vec<InputFrame>,
+}
+
+fn full_speed() -> f32 {
+    1.0
+}
+
+impl Recording {
+    /// How many replays are kept around, the oldest ones make room for new runs.
+    const MAX_REPLAYS: usize = 20;
+    /// The newest layout of recordings there is.
+    const FORMAT: u32 = 1;
+    /// What the files of shared replays end in.
+    const SHARED: &'static str = "replay";
+
+    /// Start recording a run of `level`, where `map` is what the level is built from.
+    pub fn new(level: &str, seed: u64, map: &str) -> Self {
+        Self {
+            level: level.to_string(),
+            seed,
+            format: Self::FORMAT,
+            game: env!("CARGO_PKG_VERSION").to_string(),
+            map: checksum(map),
+            difficulty: Difficulty::Normal,
+            new_game_plus: None,
+            daily: None,
+            game_speed: full_speed(),
+            frames: vec![],
+        }
+    }
+
+    /// Read a recording, a shared one if the file ends in `.replay`.
+    pub fn load(path: &Path) -> FerrisResult<Self> {
+        let text = fs::read_to_string(path)?;
+
+        if path.extension().is_some_and(|ext| ext == Self::SHARED) {
+            Ok(serde_json::from_str(&text)?)
+        } else {
+            Self::parse(&text)
+        }
+    }
+
+    /// Read a recording written down as TOML.
+    pub fn parse(text: &str) -> FerrisResult<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Make sure the recording plays back the way it was played, on this version of the game and
+    /// on `map`, what its level is built from here.
+    pub fn check(&self, map: &str) -> Result<(), String> {
+        self.check_format()?;
+
+        // Recordings from before the format was written down didn't know the version or the
+        // level either, there's nothing to check them against.
+        if self.format == 0 {
+            return Ok(());
+        }
+
+        if self.game != env!("CARGO_PKG_VERSION") {
+            return Err(format!(
+                "The replay is from version {} of the game",
+                self.game
+            ));
+        }
+
+        if self.map != checksum(map) {
+            return Err(format!(
+                "The replay was played on another version of level {}",
+                self.level
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Make sure the recording is laid out in a format this version of the game can read.
+    pub fn check_format(&self) -> Result<(), String> {
+        if self.format > Self::FORMAT {
+            return Err(String::from(
+                "The replay is from a newer version of the game",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Write the recording down to be shared with others, as small as it gets. Returns where it
+    /// went.
+    pub fn share(&self, ctx: &Context) -> FerrisResult<PathBuf> {
+        let dir = shared_dir(ctx);
+        let path = dir.join(format!("{}.{}", self.name(), Self::SHARED));
+
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, serde_json::to_string(self)?)?;
+
+        Ok(path)
+    }
+
+    /// A name for the recording, going by the level and the millisecond it's saved in.
+    fn name(&self) -> String {
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+
+        format!("{}-{}", self.level.trim_end_matches(".map"), time)
+    }
+
+    pub fn save(&self, path: &Path) -> FerrisResult<()> {
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Keep the recording with the other replays, named after the level and when it was played.
+    pub fn save_replay(&self, ctx: &Context) -> FerrisResult<()> {
+        let dir = replays_dir(ctx);
+
+        fs::create_dir_all(&dir)?;
+        self.save(&dir.join(format!("{}.toml", self.name())))?;
+
+        for old in saved_replays(ctx).iter().skip(Self::MAX_REPLAYS) {
+            fs::remove_file(old)?;
+        }
+
+        Ok(())
+    }
+
+    /// How many updates the recording lasts.
+    pub fn len(&self) -> u64 {
+        self.frames
+            .iter()
+            .map(|frame| u64::from(frame.updates))
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Add what the player did during an update.
+    pub fn push(&mut self, frame: InputFrame) {
+        match self.frames.last_mut() {
+            // A frame that can't count any more updates makes way for a new one.
+            Some(last)
+                if last.held == frame.held
+                    && last.pressed == frame.pressed
+                    && last.updates.checked_add(frame.updates).is_some() =>
+            {
+                last.updates += frame.updates;
+            }
+            _ => self.frames.push(frame),
+        }
+    }
+}
+
+/// Where the replays of past runs are kept.
+fn replays_dir(ctx: &Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join("replays")
+}
+
+/// Where shared replays are written to, and where the ones shared by others can be put to be
+/// watched.
+fn shared_dir(ctx: &Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join("shared")
+}
+
+/// The files in `dir` ending in `extension`.
+fn files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The replays of past runs, newest first.
+fn saved_replays(ctx: &Context) -> Vec<PathBuf> {
+    let mut replays = files(&replays_dir(ctx), "toml");
+
+    sort_replays(&mut replays);
+
+    replays
+}
+
+/// The replays of past runs and the shared ones, newest first.
+pub fn watchable_replays(ctx: &Context) -> Vec<PathBuf> {
+    let mut replays = files(&replays_dir(ctx), "toml");
+
+    replays.extend(files(&shared_dir(ctx), Recording::SHARED));
+    sort_replays(&mut replays);
+
+    replays
+}
+
+/// The demos bundled with the game, the ones that can't be read left out.
+pub fn demos(asset_manager: &AssetManager) -> Vec<Recording> {
+    asset_manager
+        .get_files("demos")
+        .iter()
+        .filter(|file| file.ends_with(".toml"))
+        .filter_map(|file| {
+            Recording::parse(&asset_manager.get_file(&format!("/demos/{}", file)))
+                .map_err(|error| log::error!("Cannot read the demo {}: {}", file, error))
+                .ok()
+        })
+        .collect()
+}
+
+/// A checksum of what a level is built from, the same on every computer and every run.
+/// It's 32 bits long, TOML can't hold all of the 64 bit numbers.
+fn checksum(map: &str) -> u32 {
+    // FNV-1a, since the hashers of the standard library can change between versions of Rust.
+    map.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Put the newest replays first, going by when they were played going by their names.
+fn sort_replays(replays: &mut [PathBuf]) {
+    replays.sort_by_key(|path| {
+        let played = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit('-').next())
+            .and_then(|time| time.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        std::cmp::Reverse(played)
+    });
+}
+
+/// Goes through a recording one update at a time.
+pub struct Playback {
+    recording: Recording,
+    /// The frame being played.
+    frame: usize,
+    /// How many updates of that frame have been played.
+    played: u32,
+    /// How many updates have been played in total.
+    elapsed: u64,
+    paused: bool,
+    /// How many updates get played with every step of the game.
+    speed: u32,
+}
+
+impl Playback {
+    /// The speeds the playback can go at.
+    const SPEEDS: [u32; 2] = [1, 2];
+
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            played: 0,
+            elapsed: 0,
+            paused: false,
+            speed: 1,
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// Go over to the next speed, coming back around to the normal one.
+    pub fn toggle_speed(&mut self) {
+        let current = Self::SPEEDS.iter().position(|speed| *speed == self.speed);
+
+        self.speed = Self::SPEEDS[current.map_or(0, |current| (current + 1) % Self::SPEEDS.len())];
+    }
+
+    /// How far along the playback is, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        self.elapsed as f32 / self.recording.len().max(1) as f32
+    }
+
+    /// Show how far along the replay is and how to control it at the top of the screen.
+    pub fn draw(&self, ctx: &mut Context, font: Font) -> GameResult {
+        const WIDTH: f32 = 300.0;
+
+        let (width, _) = display::screen_size(ctx);
+        let left = (width - WIDTH) / 2.0;
+
+        let state = if self.paused {
+            String::from("REPLAY  PAUSED")
+        } else {
+            format!("REPLAY  {}X", self.speed)
+        };
+        let state = Text::new(
+            TextFragment::new(state)
+                .font(font)
+                .scale(Scale::uniform(20.0)),
+        );
+        let controls = Text::new(
+            TextFragment::new("SPACE PAUSE   TAB SPEED   R RESTART   F FREE CAMERA   E SHARE")
+                .font(font)
+                .scale(Scale::uniform(14.0)),
+        );
+
+        let bar = graphics::MeshBuilder::new()
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(left, 40.0, WIDTH, 6.0),
+                Color::from_rgba(36, 36, 36, 200),
+            )
+            .rectangle(
+                DrawMode::fill(),
+                Rect::new(left, 40.0, WIDTH * self.progress().min(1.0), 6.0),
+                Color::from_rgb(247, 76, 0),
+            )
+            .build(ctx)?;
+
+        let state_width = state.width(ctx) as f32;
+        let controls_width = controls.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &state,
+            DrawParam::default().dest(Point2::new((width - state_width) / 2.0, 12.0)),
+        )?;
+        graphics::draw(ctx, &bar, DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &controls,
+            DrawParam::default()
+                .dest(Point2::new((width - controls_width) / 2.0, 54.0))
+                .color(Color::new(1.0, 1.0, 1.0, 0.7)),
+        )
+    }
+
+    /// What the player did during the next update, or `None` once the recording is over.
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        loop {
+            let frame = self.recording.frames.get(self.frame)?;
+
+            if self.played < frame.updates {
+                self.played += 1;
+                self.elapsed += 1;
+
+                return self.recording.frames.get(self.frame);
+            }
+
+            self.frame += 1;
+            self.played = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording() {
+        let mut recording = Recording::new("01.map", 7, "");
+
+        recording.push(InputFrame::new(vec![Action::Right], vec![Action::Right]));
+        recording.push(InputFrame::new(vec![Action::Right], vec![]));
+        recording.push(InputFrame::new(vec![Action::Right], vec![]));
+
+        // Updates that are the same get stored once.
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.frames[1].updates, 2);
+        assert_eq!(recording.len(), 3);
+
+        let saved = toml::to_string(&recording).unwrap();
+        let mut playback = Playback::new(toml::from_str(&saved).unwrap());
+
+        assert_eq!(playback.recording().seed, 7);
+
+        assert_eq!(playback.next_frame().unwrap().pressed, vec![Action::Right]);
+        assert!(playback.next_frame().unwrap().pressed.is_empty());
+        assert!(playback.next_frame().is_some());
+        assert!(playback.next_frame().is_none());
+        assert_eq!(playback.progress(), 1.0);
+
+        playback.toggle_speed();
+        assert_eq!(playback.speed(), 2);
+        playback.toggle_speed();
+        assert_eq!(playback.speed(), 1);
+    }
+
+    #[test]
+    fn test_check() {
+        let recording = Recording::new("01", 7, "[ground]");
+
+        assert!(recording.check("[ground]").is_ok());
+        assert!(recording.check("[ground][enemy]").is_err());
+
+        // Shared replays come back the way they went out.
+        let shared: Recording =
+            serde_json::from_str(&serde_json::to_string(&recording).unwrap()).unwrap();
+        assert_eq!(shared, recording);
+
+        let newer = Recording {
+            format: Recording::FORMAT + 1,
+            ..recording.clone()
+        };
+        assert!(newer.check("[ground]").is_err());
+
+        let other_game = Recording {
+            game: String::from("0.0.1"),
+            ..recording.clone()
+        };
+        assert!(other_game.check("[ground]").is_err());
+
+        // Leaving the version and the level out doesn't get a recording past the checks.
+        let unknown = Recording {
+            game: String::new(),
+            map: 0,
+            ..recording.clone()
+        };
+        assert!(unknown.check("[ground]").is_err());
+        assert!(unknown.check_format().is_ok());
+
+        // Recordings from before any of it was written down play anywhere.
+        let old: Recording = toml::from_str("level = \"01\"\nframes = []").unwrap();
+        assert!(old.check("[ground][enemy]").is_ok());
+    }
+
+    #[test]
+    fn test_long_recording() {
+        let mut recording = Recording::new("01", 7, "[ground]");
+
+        recording.push(InputFrame {
+            updates: u32::MAX,
+            ..InputFrame::default()
+        });
+        recording.push(InputFrame::default());
+        recording.push(InputFrame::default());
+
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.len(), u64::from(u32::MAX) + 2);
+    }
+
+    #[test]
+    fn test_newest_replays_first() {
+        let mut replays = vec![
+            PathBuf::from("01-100.toml"),
+            PathBuf::from("boss-arena-300.toml"),
+            PathBuf::from("02-200.toml"),
+        ];
+
+        sort_replays(&mut replays);
+
+        assert_eq!(replays[0], PathBuf::from("boss-arena-300.toml"));
+        assert_eq!(replays[2], PathBuf::from("01-100.toml"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Remix tables, for the levels of New Game+.
+//!
+//! In New Game+ every gopher of a level gets swapped for what its remix table rolls, so the
+//! levels don't play out the way they did the first time around. A level picks the table in
+//! `resources/remixes` named after it, and the other levels go with `default.toml`. A table is a
+//! list of what a gopher can turn into, and how likely it is to:
+//!
+//! ```toml
+//! [[gopher]]
+//! spawns = ["enemy", "enemy"]
+//! weight = 2
+//!
+//! [[gopher]]
+//! spawns = ["enemy", "barrel"]
+//! ```
+//!
+//! Bosses stay who they are. Everything in `spawns` is something a level script can `spawn`.
+
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::utils::AssetManager;
+
+use super::script::Spawn;
+
+#[derive(Deserialize)]
+struct RemixFile {
+    #[serde(default)]
+    gopher: Vec<RemixEntry>,
+}
+
+#[derive(Deserialize)]
+struct RemixEntry {
+    spawns: Vec<String>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RemixTable {
+    /// What a gopher can turn into, with how likely it is to.
+    gopher: Vec<(Vec<Spawn>, u32)>,
+}
+
+impl RemixTable {
+    /// The table the levels without one of their own go with.
+    const DEFAULT: &'static str = "default.toml";
+
+    /// Read the remix table of `level`. A level without one keeps its gophers as they are.
+    pub fn load(asset_manager: &AssetManager, level: &str) -> Self {
+        let files = asset_manager.get_files("remixes");
+        let name = match vec![format!("{}.toml", level), Self::DEFAULT.to_string()]
+            .into_iter()
+            .find(|name| files.contains(name))
+        {
+            Some(name) => name,
+            None => return Self::default(),
+        };
+
+        Self::parse(&asset_manager.get_file(&format!("/remixes/{}", name))).unwrap_or_else(
+            |error| {
+                log::error!("Cannot read the remix table {}: {}", name, error);
+
+                Self::default()
+            },
+        )
+    }
+
+    /// Parse a remix table, failing with what's wrong with it.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file = toml::from_str::<RemixFile>(source).map_err(|error| error.to_string())?;
+        let mut gopher = vec![];
+
+        for entry in file.gopher {
+            let spawns = entry
+                .spawns
+                .iter()
+                .map(|name| {
+                    Spawn::parse(name).ok_or_else(|| format!("Nothing called {} to spawn", name))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if entry.weight > 0 {
+                gopher.push((spawns, entry.weight));
+            }
+        }
+
+        Ok(Self { gopher })
+    }
+
+    /// Roll what a gopher turns into.
+    pub fn roll(&self, rng: &mut impl Rng) -> Vec<Spawn> {
+        let total = self.gopher.iter().map(|(_, weight)| weight).sum::<u32>();
+
+        if total == 0 {
+            return vec![Spawn::Enemy];
+        }
+
+        let mut roll = rng.gen_range(0..total);
+
+        for (spawns, weight) in &self.gopher {
+            if roll < *weight {
+                return spawns.clone();
+            }
+
+            roll -= weight;
+        }
+
+        vec![Spawn::Enemy]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_remix_table() {
+        let table = RemixTable::parse(
+            r#"
+            [[gopher]]
+            spawns = ["enemy", "barrel"]
+            weight = 3
+
+            [[gopher]]
+            spawns = ["crate"]
+            weight = 0
+            "#,
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // Nothing with no weight ever gets rolled.
+        for _ in 0..10 {
+            assert_eq!(table.roll(&mut rng), vec![Spawn::Enemy, Spawn::Barrel]);
+        }
+
+        // Gophers stay gophers without a table.
+        assert_eq!(RemixTable::default().roll(&mut rng), vec![Spawn::Enemy]);
+
+        assert!(RemixTable::parse("[[gopher]]\nspawns = [\"dragon\"]").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! The dice of a run.
+//!
+//! Everything random in a level gets rolled with the `GameRng` of the run, seeded as the run
+//! starts: what a hit does, what crates drop, how debris flies and where particles go. Playing a
+//! level again with the same seed rolls everything the same way, which is how replays and online
+//! games stay in step, and how challenge runs have everyone play the same run. The seed shows once
+//! the run is over, and a run can be started on a seed of its own with `--seed <seed>` or the
+//! `seed` console command.
+//!
+//! What only gets drawn, like particles, decals, clouds and the shaking of the camera, rolls
+//! dice of its own from the same seed, so turning the effects down never changes how a run goes.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+pub struct GameRng {
+    seed: u64,
+    /// For the rolls deciding how the run goes.
+    run: StdRng,
+    /// For the rolls of what only gets drawn.
+    effects: StdRng,
+}
+
+impl GameRng {
+    /// Mixed into the seed of the effects, so they don't roll the same numbers as the run.
+    const EFFECTS: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            run: StdRng::seed_from_u64(seed),
+            effects: StdRng::seed_from_u64(seed ^ Self::EFFECTS),
+        }
+    }
+
+    /// A run on a seed nobody picked.
+    pub fn random() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The dice deciding how the run goes.
+    pub fn run(&mut self) -> &mut StdRng {
+        &mut self.run
+    }
+
+    /// The dice of what only gets drawn.
+    pub fn effects(&mut self) -> &mut StdRng {
+        &mut self.effects
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+/// Read a seed typed in for a challenge run.
+pub fn parse_seed(seed: &str) -> Option<u64> {
+    seed.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_rng() {
+        let mut first = GameRng::new(1234);
+        let mut second = GameRng::new(1234);
+
+        // Rolling effects in between doesn't change what the run rolls.
+        let _: f32 = second.effects().gen();
+
+        for _ in 0..10 {
+            assert_eq!(
+                first.run().gen_range(0..100),
+                second.run().gen_range(0..100)
+            );
+        }
+
+        assert_eq!(parse_seed(" 1234 "), Some(1234));
+        assert_eq!(parse_seed("ferris"), None);
+    }
+}
@@ -0,0 +1,120 @@
+//! Points for blowing things up.
+//!
+//! Kills in quick succession build up a combo which multiplies the points of every kill. The combo
+//! falls apart when the player goes too long without a kill.
+
+use super::events::{GameEvent, Subscriber};
+
+pub struct Score {
+    /// The total amount of points.
+    pub points: u32,
+
+    /// The amount of kills in the current combo.
+    combo: u32,
+    /// Time left until the combo falls apart.
+    combo_timer: f32,
+}
+
+impl Score {
+    /// Points for killing an enemy.
+    const KILL_POINTS: u32 = 100;
+    /// Extra points for killing an enemy with a headshot.
+    const HEADSHOT_BONUS: u32 = 50;
+    /// Extra points for every barrel in a chain explosion after the first.
+    const CHAIN_BONUS: u32 = 75;
+
+    /// How long the combo lasts without a kill.
+    pub const COMBO_TIME: f32 = 3.0;
+    /// The biggest the combo multiplier can get.
+    const MAX_COMBO: u32 = 8;
+
+    pub fn new() -> Self {
+        Self {
+            points: 0,
+
+            combo: 0,
+            combo_timer: 0.0,
+        }
+    }
+
+    /// Score a kill. Returns the amount of points it was worth.
+    pub fn kill(&mut self, headshot: bool) -> u32 {
+        self.combo = (self.combo + 1).min(Self::MAX_COMBO);
+        self.combo_timer = Self::COMBO_TIME;
+
+        let points = if headshot {
+            Self::KILL_POINTS + Self::HEADSHOT_BONUS
+        } else {
+            Self::KILL_POINTS
+        };
+
+        self.add(points)
+    }
+
+    /// Score a barrel going off as the `chain`th one of a chain explosion. Returns the amount of
+    /// points it was worth.
+    pub fn explosion(&mut self, chain: u32) -> u32 {
+        self.add(Self::CHAIN_BONUS * chain.saturating_sub(1))
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.combo_timer = (self.combo_timer - dt).max(0.0);
+
+        if self.combo_timer <= 0.0 {
+            self.combo = 0;
+        }
+    }
+
+    /// The multiplier applied to every score.
+    pub fn multiplier(&self) -> u32 {
+        self.combo.max(1)
+    }
+
+    /// Time left until the combo falls apart.
+    pub fn combo_timer(&self) -> f32 {
+        self.combo_timer
+    }
+
+    fn add(&mut self, points: u32) -> u32 {
+        let points = points * self.multiplier();
+
+        self.points += points;
+
+        points
+    }
+}
+
+impl Subscriber for Score {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::EnemyKilled { headshot, .. } => {
+                self.kill(headshot);
+            }
+            GameEvent::BarrelExploded { chain } => {
+                self.explosion(chain);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combo() {
+        let mut score = Score::new();
+
+        assert_eq!(score.kill(false), 100);
+        assert_eq!(score.kill(true), 300);
+        assert_eq!(score.explosion(3), 300);
+        assert_eq!(score.points, 700);
+
+        // The combo falls apart without any kills.
+        score.update(Score::COMBO_TIME);
+
+        assert_eq!(score.multiplier(), 1);
+        assert_eq!(score.kill(false), 100);
+    }
+}
@@ -0,0 +1,237 @@
+//! Level scripts, for scripted moments that don't need changes to the game itself.
+//!
+//! A level picks a script from `resources/scripts` with `.script`. A script is a list of
+//! handlers, each a trigger followed by the commands to run once it goes off, and every handler
+//! only runs once. The commands are all a script can do, so a script can't get the game into a
+//! state it couldn't get into on its own.
+//!
+//! # Script
+//! `.comment` => A comment \
+//! `.on start` => Run the following commands as soon as the level starts \
+//! `.on reach <x>` => Run them once the player gets past `x` \
+//! `.on kills <amount>` => Run them once `amount` enemies are dead \
+//! `.on checkpoint` => Run them once the player reaches a checkpoint \
+//! `spawn <enemy|barrel|crate> <x>` => Drop something into the level at `x` \
+//! `damage <amount>` => Hurt the player \
+//! `heal <amount>` => Heal the player \
+//! `sound <shoot|jump|hit|explosion|pickup|death>` => Play a sound effect \
+//! `dialogue <script>` => Start a dialogue from `resources/dialogues` \
+//! `toast <text>` => Show a message in the corner of the screen
+
+use crate::audio::Sfx;
+
+use super::events::{GameEvent, Subscriber};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Trigger {
+    Start,
+    Reach(f32),
+    Kills(u32),
+    Checkpoint,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Spawn {
+    Enemy,
+    Barrel,
+    Crate,
+}
+
+impl Spawn {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "enemy" => Some(Spawn::Enemy),
+            "barrel" => Some(Spawn::Barrel),
+            "crate" => Some(Spawn::Crate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Command {
+    Spawn(Spawn, f32),
+    Damage(f32),
+    Heal(f32),
+    Sound(Sfx),
+    Dialogue(String),
+    Toast(String),
+}
+
+pub struct Script {
+    /// The handlers that haven't gone off yet.
+    handlers: Vec<(Trigger, Vec<Command>)>,
+    kills: u32,
+    checkpoints: u32,
+}
+
+impl Script {
+    /// Parse a script, failing with what's wrong with it.
+    pub fn parse(script: &str) -> Result<Self, &'static str> {
+        let mut handlers: Vec<(Trigger, Vec<Command>)> = vec![];
+
+        for line in script
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let exp = line.split(' ').collect::<Vec<_>>();
+            let number = |index: usize| {
+                exp.get(index)
+                    .and_then(|number| number.parse().ok())
+                    .ok_or("a script is missing a number")
+            };
+
+            if exp[0].starts_with(".comment") {
+                // Do nothing. ¯\_(ツ)_/¯
+            } else if exp[0].starts_with(".on") {
+                let trigger = match exp.get(1) {
+                    Some(&"start") => Trigger::Start,
+                    Some(&"reach") => Trigger::Reach(number(2)?),
+                    Some(&"kills") => Trigger::Kills(number(2)? as u32),
+                    Some(&"checkpoint") => Trigger::Checkpoint,
+                    _ => return Err("a script waits for something that never happens"),
+                };
+
+                handlers.push((trigger, vec![]));
+            } else {
+                let command = match exp[0] {
+                    "spawn" => {
+                        let spawn = match exp.get(1).and_then(|name| Spawn::parse(name)) {
+                            Some(spawn) => spawn,
+                            None => return Err("a script spawns something that doesn't exist"),
+                        };
+
+                        Command::Spawn(spawn, number(2)?)
+                    }
+                    "damage" => Command::Damage(number(1)?),
+                    "heal" => Command::Heal(number(1)?),
+                    "sound" => Command::Sound(
+                        exp.get(1)
+                            .and_then(|name| sound(name))
+                            .ok_or("a script plays a sound that doesn't exist")?,
+                    ),
+                    "dialogue" => Command::Dialogue(exp[1..].join(" ")),
+                    "toast" => Command::Toast(exp[1..].join(" ")),
+                    _ => return Err("a script has a command that doesn't exist"),
+                };
+
+                handlers
+                    .last_mut()
+                    .ok_or("a script has a command before its first .on")?
+                    .1
+                    .push(command);
+            }
+        }
+
+        Ok(Self {
+            handlers,
+            kills: 0,
+            checkpoints: 0,
+        })
+    }
+
+    /// The dialogues the script starts, to check they are there before the level starts.
+    pub fn dialogues(&self) -> impl Iterator<Item = &str> {
+        self.handlers
+            .iter()
+            .flat_map(|(_, commands)| commands)
+            .filter_map(|command| match command {
+                Command::Dialogue(script) => Some(script.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Take the commands of the handlers that went off, with the player at `player_x`.
+    pub fn update(&mut self, player_x: f32) -> Vec<Command> {
+        let (kills, checkpoints) = (self.kills, self.checkpoints);
+        let went_off = |trigger: &Trigger| match *trigger {
+            Trigger::Start => true,
+            Trigger::Reach(x) => player_x >= x,
+            Trigger::Kills(amount) => kills >= amount,
+            Trigger::Checkpoint => checkpoints > 0,
+        };
+
+        let mut commands = vec![];
+
+        self.handlers.retain(|(trigger, handler)| {
+            if went_off(trigger) {
+                commands.extend(handler.iter().cloned());
+
+                false
+            } else {
+                true
+            }
+        });
+
+        commands
+    }
+}
+
+impl Subscriber for Script {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::EnemyKilled { .. } => self.kills += 1,
+            GameEvent::CheckpointReached => self.checkpoints += 1,
+            _ => {}
+        }
+    }
+}
+
+/// The sound effect a script calls `name`.
+fn sound(name: &str) -> Option<Sfx> {
+    match name {
+        "shoot" => Some(Sfx::Shoot),
+        "jump" => Some(Sfx::Jump),
+        "hit" => Some(Sfx::Hit),
+        "explosion" => Some(Sfx::Explosion),
+        "pickup" => Some(Sfx::Pickup),
+        "death" => Some(Sfx::Death),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        let mut script = Script::parse(
+            ".comment Backup for the king
+            .on start
+            toast HERE THEY COME
+            .on kills 2
+            spawn enemy 1200
+            sound explosion",
+        )
+        .unwrap();
+
+        assert_eq!(
+            script.update(0.0),
+            vec![Command::Toast(String::from("HERE THEY COME"))]
+        );
+
+        script.notify(&GameEvent::EnemyKilled {
+            boss: None,
+            headshot: false,
+        });
+        assert_eq!(script.update(0.0), vec![]);
+
+        script.notify(&GameEvent::EnemyKilled {
+            boss: None,
+            headshot: false,
+        });
+        assert_eq!(
+            script.update(0.0),
+            vec![
+                Command::Spawn(Spawn::Enemy, 1200.0),
+                Command::Sound(Sfx::Explosion)
+            ]
+        );
+        assert_eq!(script.update(0.0), vec![]);
+
+        assert!(Script::parse("toast TOO EARLY").is_err());
+        assert!(Script::parse(".on start\nopen door").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Watching a replay.
+//!
+//! The camera follows Ferris around while a replay plays, the way it did during the run. Letting
+//! go of Ferris frees the camera up to be moved around the level with the arrow keys, to see what
+//! goes on elsewhere, and following Ferris again brings it back.
+
+use ggez::event::KeyCode;
+use ggez_goodies::nalgebra_glm::Vec2;
+
+pub struct Spectator {
+    /// Where the camera is while it's free, it follows Ferris otherwise.
+    free: Option<Vec2>,
+    /// The directions the camera is being moved in while it's free.
+    pan: Vec2,
+}
+
+impl Spectator {
+    /// How fast the free camera moves, in pixels per second.
+    const SPEED: f32 = 900.0;
+
+    pub fn new() -> Self {
+        Self {
+            free: None,
+            pan: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.free.is_some()
+    }
+
+    /// Let go of Ferris, leaving the camera at `from`, or go back to following Ferris.
+    pub fn toggle(&mut self, from: Vec2) {
+        self.free = match self.free {
+            Some(_) => None,
+            None => Some(from),
+        };
+        self.pan = Vec2::new(0.0, 0.0);
+    }
+
+    /// Start or stop moving the free camera with the arrow keys. Returns whether the key was one
+    /// of them.
+    pub fn key(&mut self, keycode: KeyCode, down: bool) -> bool {
+        let direction = match keycode {
+            KeyCode::Left => Vec2::new(-1.0, 0.0),
+            KeyCode::Right => Vec2::new(1.0, 0.0),
+            KeyCode::Up => Vec2::new(0.0, 1.0),
+            KeyCode::Down => Vec2::new(0.0, -1.0),
+            _ => return false,
+        };
+
+        // The camera only takes the arrow keys while it's free, the slow motion has Up otherwise.
+        if !self.is_free() {
+            return false;
+        }
+
+        let axis = if direction.x != 0.0 {
+            &mut self.pan.x
+        } else {
+            &mut self.pan.y
+        };
+        let amount = direction.x + direction.y;
+
+        if down {
+            *axis = amount;
+        } else if *axis == amount {
+            *axis = 0.0;
+        }
+
+        true
+    }
+
+    /// Move the free camera along, slower when zoomed in so it covers the same part of the screen.
+    pub fn update(&mut self, zoom: f32, dt: f32) {
+        if let Some(position) = &mut self.free {
+            *position += self.pan * Self::SPEED / zoom.max(0.1) * dt;
+        }
+    }
+
+    /// Where the camera looks, which is at `ferris` unless it's free.
+    pub fn target(&self, ferris: Vec2) -> Vec2 {
+        self.free.unwrap_or(ferris)
+    }
+}
+
+impl Default for Spectator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_camera() {
+        let mut spectator = Spectator::new();
+        let ferris = Vec2::new(100.0, 0.0);
+
+        // Following Ferris, the arrow keys are left alone.
+        assert!(!spectator.key(KeyCode::Right, true));
+        assert_eq!(spectator.target(ferris), ferris);
+
+        spectator.toggle(Vec2::new(0.0, 0.0));
+        assert!(spectator.key(KeyCode::Right, true));
+
+        spectator.update(1.0, 1.0);
+        assert_eq!(spectator.target(ferris), Vec2::new(Spectator::SPEED, 0.0));
+
+        // Letting go of the other way doesn't stop the camera.
+        spectator.key(KeyCode::Left, false);
+        spectator.update(1.0, 1.0);
+        assert_eq!(spectator.target(ferris).x, Spectator::SPEED * 2.0);
+
+        spectator.toggle(Vec2::new(0.0, 0.0));
+        assert_eq!(spectator.target(ferris), ferris);
+    }
+}
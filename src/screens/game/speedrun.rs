@@ -0,0 +1,345 @@
+//! Speedrun mode.
+//!
+//! With speedrun mode on, a timer runs from the moment a level starts until its last evildoer goes
+//! down, and every level beaten makes a split of the run. The timer gets compared with the
+//! personal best of the level as it goes, and a display of the actions being held shows how the
+//! run was played. Running a level again that the run already has starts a new run.
+//!
+//! The splits get written to `splits.toml` in the user data directory after every level, with the
+//! personal bests for all of the levels.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use ggez::{
+    filesystem,
+    graphics::{self, Color, DrawMode, DrawParam, Font, Rect, Scale, Text, TextFragment},
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{bindings::Action, error::FerrisResult, utils::write_atomic};
+
+use super::{
+    input::InputState,
+    layout::{Anchor, Layout},
+};
+
+/// How long a level of the run took.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Split {
+    pub level: String,
+    /// In seconds.
+    pub time: f32,
+}
+
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Splits {
+    /// The personal best of every level, by its id.
+    pub best: BTreeMap<String, f32>,
+    /// The levels of the run so far, in the order they were beaten.
+    pub run: Vec<Split>,
+}
+
+impl Splits {
+    /// What the splits file is called in the user data directory.
+    const FILE: &'static str = "splits.toml";
+
+    /// The splits written down last. Splits that can't be read start over.
+    pub fn load(ctx: &Context) -> Self {
+        fs::read_to_string(Self::path(ctx))
+            .ok()
+            .and_then(|splits| toml::from_str(&splits).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, ctx: &Context) -> FerrisResult<PathBuf> {
+        let path = Self::path(ctx);
+
+        write_atomic(&path, &toml::to_string(self)?)?;
+
+        Ok(path)
+    }
+
+    fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_data_dir(ctx).join(Self::FILE)
+    }
+
+    /// Get going on `level`, starting a new run if the run already has it.
+    pub fn start(&mut self, level: &str) {
+        if self.run.iter().any(|split| split.level == level) {
+            self.run.clear();
+        }
+    }
+
+    /// `level` was beaten in `time`. Returns whether that's a new personal best.
+    pub fn finish(&mut self, level: &str, time: f32) -> bool {
+        self.run.push(Split {
+            level: level.to_string(),
+            time,
+        });
+
+        let best = self.best.entry(level.to_string()).or_insert(f32::INFINITY);
+        let record = time < *best;
+
+        *best = best.min(time);
+
+        record
+    }
+
+    /// How long the run took so far.
+    pub fn total(&self) -> f32 {
+        self.run.iter().map(|split| split.time).sum()
+    }
+}
+
+pub struct Speedrun {
+    level: String,
+    splits: Splits,
+    /// The personal best of the level from before this run.
+    best: Option<f32>,
+    time: f32,
+    /// Whether the level was beaten, which stops the timer.
+    finished: bool,
+}
+
+impl Speedrun {
+    /// How many of the splits of the run are shown.
+    const SHOWN_SPLITS: usize = 5;
+    const WIDTH: f32 = 240.0;
+    const LINE_HEIGHT: f32 = 22.0;
+
+    /// Start the timer as `level` starts.
+    pub fn start(ctx: &Context, level: &str) -> Self {
+        let mut splits = Splits::load(ctx);
+
+        splits.start(level);
+
+        Self {
+            level: level.to_string(),
+            best: splits.best.get(level).copied(),
+            splits,
+            time: 0.0,
+            finished: false,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if !self.finished {
+            self.time += dt;
+        }
+    }
+
+    /// Stop the timer as the level is beaten, and write the split down. Returns whether it was a
+    /// new personal best.
+    pub fn finish(&mut self, ctx: &Context) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        self.finished = true;
+
+        let record = self.splits.finish(&self.level, self.time);
+
+        match self.splits.save(ctx) {
+            Ok(path) => log::info!("Wrote the splits to {}", path.display()),
+            Err(error) => log::error!("Cannot write the splits: {}", error),
+        }
+
+        record
+    }
+
+    /// Draw the timer and the splits of the run on the right of the screen.
+    pub fn draw(&self, ctx: &mut Context, layout: &Layout, font: Font) -> GameResult {
+        let text = |text: String, size: f32, color: Color| {
+            Text::new(
+                TextFragment::new(text)
+                    .font(font)
+                    .scale(Scale::uniform(size))
+                    .color(color),
+            )
+        };
+        let dim = Color::new(1.0, 1.0, 1.0, 0.7);
+        let origin = layout.place(
+            Anchor::TopRight,
+            Vec2::new(-Self::WIDTH - 20.0, layout.size().y / 2.0 - 120.0),
+        );
+        let mut y = origin.y;
+
+        let mut line = |ctx: &mut Context, left: Text, right: Option<Text>| -> GameResult {
+            graphics::draw(ctx, &left, DrawParam::default().dest([origin.x, y]))?;
+
+            if let Some(right) = right {
+                let width = right.width(ctx) as f32;
+
+                graphics::draw(
+                    ctx,
+                    &right,
+                    DrawParam::default().dest([origin.x + Self::WIDTH - width, y]),
+                )?;
+            }
+
+            y += Self::LINE_HEIGHT;
+
+            Ok(())
+        };
+
+        let skipped = self.splits.run.len().saturating_sub(Self::SHOWN_SPLITS);
+
+        for split in self.splits.run.iter().skip(skipped) {
+            // The split of the level being played is the current time.
+            if self.finished && split.level == self.level {
+                continue;
+            }
+
+            line(
+                ctx,
+                text(format!("LEVEL {}", split.level), 16.0, dim),
+                Some(text(format_time(split.time), 16.0, dim)),
+            )?;
+        }
+
+        let color = match self.best {
+            Some(best) if self.time > best => Color::from_rgb(230, 60, 60),
+            Some(_) => Color::from_rgb(80, 220, 100),
+            None => graphics::WHITE,
+        };
+
+        line(
+            ctx,
+            text(format!("LEVEL {}", self.level), 16.0, graphics::WHITE),
+            Some(text(format_time(self.time), 16.0, color)),
+        )?;
+
+        if let Some(best) = self.best {
+            line(
+                ctx,
+                text(String::from("PB"), 16.0, dim),
+                Some(text(format_delta(self.time - best), 16.0, color)),
+            )?;
+        }
+
+        let total = self.splits.total() + if self.finished { 0.0 } else { self.time };
+
+        line(
+            ctx,
+            text(String::from("RUN"), 24.0, graphics::WHITE),
+            Some(text(format_time(total), 24.0, graphics::WHITE)),
+        )
+    }
+}
+
+/// Show which of the actions are being held at the bottom of the screen, like the input display
+/// of a speedrun.
+pub fn draw_inputs(
+    ctx: &mut Context,
+    layout: &Layout,
+    font: Font,
+    input: &InputState,
+) -> GameResult {
+    const KEYS: [(Action, &str); 6] = [
+        (Action::Left, "<"),
+        (Action::Right, ">"),
+        (Action::Jump, "JUMP"),
+        (Action::Fire, "FIRE"),
+        (Action::Reload, "RELOAD"),
+        (Action::SwitchWeapon, "SWITCH"),
+    ];
+    const KEY_WIDTH: f32 = 70.0;
+    const KEY_HEIGHT: f32 = 30.0;
+    const GAP: f32 = 6.0;
+
+    let width = KEYS.len() as f32 * (KEY_WIDTH + GAP) - GAP;
+    let origin = layout.place(Anchor::Bottom, Vec2::new(-width / 2.0, -110.0));
+
+    for (id, (action, label)) in KEYS.iter().enumerate() {
+        let held = input.is_held(*action);
+        let x = origin.x + id as f32 * (KEY_WIDTH + GAP);
+        let key = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(x, origin.y, KEY_WIDTH, KEY_HEIGHT),
+            if held {
+                Color::from_rgba(247, 76, 0, 220)
+            } else {
+                Color::from_rgba(36, 36, 36, 160)
+            },
+        )?;
+        let label = Text::new(
+            TextFragment::new(*label)
+                .font(font)
+                .scale(Scale::uniform(14.0)),
+        );
+        let (label_width, label_height) = label.dimensions(ctx);
+
+        graphics::draw(ctx, &key, DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &label,
+            DrawParam::default().dest([
+                x + (KEY_WIDTH - label_width as f32) / 2.0,
+                origin.y + (KEY_HEIGHT - label_height as f32) / 2.0,
+            ]),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A time like `1:02.34`.
+fn format_time(time: f32) -> String {
+    let hundredths = (time * 100.0).round() as u32;
+
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// How far ahead or behind a time is, like `-0.52` or `+1:02.34`.
+fn format_delta(delta: f32) -> String {
+    let sign = if delta > 0.0 { '+' } else { '-' };
+
+    if delta.abs() < 60.0 {
+        format!("{}{:.2}", sign, delta.abs())
+    } else {
+        format!("{}{}", sign, format_time(delta.abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splits() {
+        let mut splits = Splits::default();
+
+        splits.start("01");
+        assert!(splits.finish("01", 30.0));
+        splits.start("02");
+        assert!(splits.finish("02", 45.5));
+        assert_eq!(splits.total(), 75.5);
+
+        // Playing the first level again starts a new run, and a slower time keeps the best.
+        splits.start("01");
+        assert!(splits.run.is_empty());
+        assert!(!splits.finish("01", 31.0));
+        assert_eq!(splits.best["01"], 30.0);
+
+        let saved = toml::to_string(&splits).unwrap();
+
+        assert_eq!(toml::from_str::<Splits>(&saved).unwrap(), splits);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_time(62.345), "1:02.35");
+        assert_eq!(format_time(5.0), "0:05.00");
+        assert_eq!(format_delta(-0.52), "-0.52");
+        assert_eq!(format_delta(62.0), "+1:02.00");
+    }
+}
@@ -0,0 +1,160 @@
+//! Little bits of text that pop up in the world, like damage numbers.
+//!
+//! Effects live in a pool that gets reused as they fade away, so spawning a bunch of them every
+//! frame doesn't keep allocating.
+
+use ggez::{
+    graphics::{self, Color, DrawParam, Font, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+use nphysics2d::nalgebra as na;
+
+use crate::palette::Palette;
+
+struct TextEffect {
+    text: String,
+    /// Where the effect was spawned in the world.
+    position: na::Point2<f32>,
+    color: Color,
+    scale: f32,
+
+    /// Time since the effect was spawned.
+    age: f32,
+    /// Inactive effects are free to be reused.
+    active: bool,
+}
+
+pub struct TextEffects {
+    pool: Vec<TextEffect>,
+    /// Where the colors of the damage numbers come from.
+    palette: &'static Palette,
+}
+
+impl TextEffects {
+    /// The most effects that can be shown at once. Once the pool is full the oldest one gets reused.
+    const POOL_SIZE: usize = 32;
+    /// How long an effect is shown for in seconds.
+    const LIFETIME: f32 = 0.8;
+    /// How fast the effects rise up in pixels per second.
+    const RISE_SPEED: f32 = 60.0;
+
+    pub fn new(palette: &'static Palette) -> Self {
+        Self {
+            pool: Vec::with_capacity(Self::POOL_SIZE),
+            palette,
+        }
+    }
+
+    pub fn palette(&self) -> &'static Palette {
+        self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: &'static Palette) {
+        self.palette = palette;
+    }
+
+    /// Show a bit of text rising up from `position` in the world.
+    pub fn spawn(&mut self, text: String, position: na::Point2<f32>, color: Color, scale: f32) {
+        let effect = TextEffect {
+            text,
+            position,
+            color,
+            scale,
+
+            age: 0.0,
+            active: true,
+        };
+
+        if let Some(free) = self.pool.iter_mut().find(|effect| !effect.active) {
+            *free = effect;
+        } else if self.pool.len() < Self::POOL_SIZE {
+            self.pool.push(effect);
+        } else if let Some(oldest) = self.pool.iter_mut().max_by(|a, b| a.age.total_cmp(&b.age)) {
+            *oldest = effect;
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for effect in self.pool.iter_mut().filter(|effect| effect.active) {
+            effect.age += dt;
+
+            if effect.age >= Self::LIFETIME {
+                effect.active = false;
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera, font: Font) -> GameResult<()> {
+        for effect in self.pool.iter().filter(|effect| effect.active) {
+            let progress = effect.age / Self::LIFETIME;
+            let position = camera.calculate_dest_point(Vec2::new(
+                effect.position.x,
+                effect.position.y - effect.age * Self::RISE_SPEED,
+            ));
+
+            let text = Text::new(
+                TextFragment::new(effect.text.as_str())
+                    .font(font)
+                    .scale(Scale::uniform(effect.scale))
+                    .color(Color {
+                        a: 1.0 - progress,
+                        ..effect.color
+                    }),
+            );
+
+            let (width, height) = text.dimensions(ctx);
+
+            graphics::draw(
+                ctx,
+                &text,
+                DrawParam::default().dest(Point2::new(
+                    position.x - width as f32 / 2.0,
+                    position.y - height as f32 / 2.0,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuse() {
+        let mut effects = TextEffects::new(&Palette::DEFAULT);
+
+        effects.spawn(
+            String::from("1"),
+            na::Point2::origin(),
+            graphics::WHITE,
+            20.0,
+        );
+        effects.update(TextEffects::LIFETIME);
+
+        // The faded effect gets reused instead of growing the pool.
+        effects.spawn(
+            String::from("2"),
+            na::Point2::origin(),
+            graphics::WHITE,
+            20.0,
+        );
+        assert_eq!(effects.pool.len(), 1);
+        assert_eq!(effects.pool[0].text, "2");
+
+        for _ in 0..TextEffects::POOL_SIZE * 2 {
+            effects.spawn(
+                String::from("3"),
+                na::Point2::origin(),
+                graphics::WHITE,
+                20.0,
+            );
+        }
+
+        assert_eq!(effects.pool.len(), TextEffects::POOL_SIZE);
+    }
+}
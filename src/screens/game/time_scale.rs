@@ -0,0 +1,138 @@
+//! Slowing the game down.
+//!
+//! Everything in a level moves along by the frame time scaled by the time scale: the physics, the
+//! enemies, the particles and so on. Slow motion effects like bullet time or the short slow down
+//! after a boss dies pull the scale down for a while, and pausing stops it altogether. The scale
+//! ramps towards where it should be instead of jumping there, except when pausing. The game speed
+//! picked in the settings slows everything down on top of that.
+
+use crate::utils::damp;
+
+use super::events::{GameEvent, Subscriber};
+
+/// A slow motion effect, how slow it goes and for how long.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SlowMotion {
+    pub scale: f32,
+    pub duration: f32,
+}
+
+impl SlowMotion {
+    /// Picked up from a bullet time pickup.
+    pub const BULLET_TIME: SlowMotion = SlowMotion {
+        scale: 0.35,
+        duration: 5.0,
+    };
+
+    /// Played when a boss goes down.
+    pub const BOSS_KILL: SlowMotion = SlowMotion {
+        scale: 0.2,
+        duration: 1.2,
+    };
+}
+
+pub struct TimeScale {
+    scale: f32,
+    /// The slow motion effects going on, with how long they still go on for.
+    effects: Vec<(f32, f32)>,
+    /// How fast the game goes without any effects.
+    speed: f32,
+    paused: bool,
+}
+
+impl TimeScale {
+    /// How fast the scale ramps towards where it should be.
+    const RAMP: f32 = 8.0;
+
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            effects: vec![],
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Start a slow motion effect. The slowest effect going on wins.
+    pub fn slow(&mut self, effect: SlowMotion) {
+        self.effects.push((effect.scale, effect.duration));
+    }
+
+    /// Set how fast the game goes without any effects, 1.0 being normal speed.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Stop time right away while paused, it ramps back up once unpaused.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused {
+            self.scale = 0.0;
+        }
+
+        self.paused = paused;
+    }
+
+    /// Move the scale along. The effects run on real time, so they don't last longer the slower
+    /// they make the game go.
+    pub fn update(&mut self, real_dt: f32) {
+        if self.paused {
+            return;
+        }
+
+        for (_, time) in &mut self.effects {
+            *time -= real_dt;
+        }
+
+        self.effects.retain(|(_, time)| *time > 0.0);
+
+        let target = self
+            .effects
+            .iter()
+            .map(|(scale, _)| *scale)
+            .fold(1.0, f32::min)
+            * self.speed;
+
+        self.scale = damp(self.scale, target, Self::RAMP, real_dt);
+    }
+
+    /// How fast the game goes, 1.0 being normal speed and 0.0 being stopped.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+}
+
+impl Subscriber for TimeScale {
+    fn notify(&mut self, event: &GameEvent) {
+        if let GameEvent::EnemyKilled { boss: Some(_), .. } = event {
+            self.slow(SlowMotion::BOSS_KILL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_scale() {
+        let mut time_scale = TimeScale::new();
+
+        time_scale.slow(SlowMotion::BULLET_TIME);
+        time_scale.slow(SlowMotion::BOSS_KILL);
+        time_scale.update(0.5);
+        assert!(time_scale.scale() < 1.0 && time_scale.scale() > SlowMotion::BOSS_KILL.scale);
+
+        time_scale.set_paused(true);
+        time_scale.update(10.0);
+        assert_eq!(time_scale.scale(), 0.0);
+
+        // Once the effects are over it ramps back up.
+        time_scale.set_paused(false);
+        time_scale.update(10.0);
+        assert!((time_scale.scale() - 1.0).abs() < 0.01);
+
+        time_scale.set_speed(0.8);
+        time_scale.update(10.0);
+        assert!((time_scale.scale() - 0.8).abs() < 0.01);
+    }
+}
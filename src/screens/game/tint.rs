@@ -0,0 +1,134 @@
+//! Flashing sprites a color when they get hurt.
+//!
+//! Anything that can get hurt keeps a hit flash around, which gets set off by the hit and fades
+//! out over a few frames. While it's going the sprite gets drawn through the tint shader, which
+//! swaps its colors out for the color of the flash without touching its shape. With flashing
+//! effects turned off in the settings the sprites get drawn as they are.
+
+use ggez::{
+    graphics::{self, Color, GlBackendSpec, Shader, ShaderGeneric},
+    Context, GameResult,
+};
+
+use gfx::*;
+
+use crate::config::Config;
+
+gfx_defines! {
+    constant Tint {
+        color: [f32; 4] = "u_Tint",
+    }
+}
+
+pub struct HitFlash {
+    color: Color,
+    /// How long the flash still goes on for.
+    time: f32,
+}
+
+impl HitFlash {
+    /// How long a flash lasts, a handful of frames.
+    const DURATION: f32 = 0.12;
+
+    /// Flashed by anything the player hits.
+    pub const HIT: Color = graphics::WHITE;
+    /// Flashed by the player getting hurt.
+    pub const HURT: Color = Color::new(1.0, 0.15, 0.1, 1.0);
+
+    pub fn new() -> Self {
+        Self {
+            color: Self::HIT,
+            time: 0.0,
+        }
+    }
+
+    pub fn hit(&mut self, color: Color) {
+        self.color = color;
+        self.time = Self::DURATION;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time = (self.time - dt).max(0.0);
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.time > 0.0
+    }
+
+    /// The color of the flash, with how strong it still is as the alpha.
+    fn tint(&self) -> [f32; 4] {
+        let Color { r, g, b, .. } = self.color;
+
+        [r, g, b, self.time / Self::DURATION]
+    }
+}
+
+pub struct TintShader {
+    shader: ShaderGeneric<GlBackendSpec, Tint>,
+    /// Whether the flashes get drawn at all.
+    flashing: bool,
+}
+
+impl TintShader {
+    pub fn new(ctx: &mut Context, config: &Config) -> GameResult<Self> {
+        let shader = Shader::new(
+            ctx,
+            "/shaders/dim.basic.glslf",
+            "/shaders/tint.glslf",
+            Tint { color: [0.0; 4] },
+            "Tint",
+            None,
+        )?;
+
+        Ok(Self {
+            shader,
+            flashing: config.flashing,
+        })
+    }
+
+    pub fn apply_config(&mut self, config: &Config) {
+        self.flashing = config.flashing;
+    }
+
+    /// Draw something with its hit flash mixed in. Things that aren't flashing are drawn as usual.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        flash: &HitFlash,
+        draw: impl FnOnce(&mut Context) -> GameResult,
+    ) -> GameResult {
+        if !flash.is_flashing() || !self.flashing {
+            return draw(ctx);
+        }
+
+        self.shader.send(
+            ctx,
+            Tint {
+                color: flash.tint(),
+            },
+        )?;
+
+        let _lock = graphics::use_shader(ctx, &self.shader);
+
+        draw(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_flash() {
+        let mut flash = HitFlash::new();
+
+        flash.hit(HitFlash::HURT);
+        assert_eq!(flash.tint()[3], 1.0);
+
+        flash.update(HitFlash::DURATION / 2.0);
+        assert!(flash.tint()[3] > 0.0 && flash.tint()[3] < 1.0);
+
+        flash.update(1.0);
+        assert_eq!(flash.tint()[3], 0.0);
+    }
+}
@@ -0,0 +1,170 @@
+//! Short notifications that slide in on the side of the screen.
+//!
+//! Only one toast is shown at a time. Toasts that come in while another one is up wait in line
+//! and get their turn once it fades out.
+
+use std::collections::VecDeque;
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, Mesh, Rect, Scale, Text, TextFragment},
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use super::{
+    components::pickup::{Pickup, PickupKind},
+    events::{GameEvent, Subscriber},
+    layout::{Anchor, Layout},
+};
+
+struct Toast {
+    text: String,
+    /// Time since the toast started showing.
+    age: f32,
+}
+
+pub struct Toasts {
+    /// The toast being shown.
+    current: Option<Toast>,
+    /// Toasts waiting for their turn, oldest first.
+    queue: VecDeque<String>,
+}
+
+impl Toasts {
+    /// How long sliding in takes.
+    const SLIDE_TIME: f32 = 0.3;
+    /// How long a toast stays up after sliding in.
+    const HOLD_TIME: f32 = 1.8;
+    /// How long fading out takes.
+    const FADE_TIME: f32 = 0.5;
+    /// The most toasts that can wait in line. Any more than that get dropped.
+    const MAX_QUEUE: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            queue: VecDeque::with_capacity(Self::MAX_QUEUE),
+        }
+    }
+
+    /// Let the player know about something.
+    pub fn push(&mut self, text: String) {
+        if self.queue.len() < Self::MAX_QUEUE {
+            self.queue.push_back(text);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(toast) = &mut self.current {
+            toast.age += dt;
+
+            if toast.age >= Self::lifetime() {
+                self.current = None;
+            }
+        }
+
+        if self.current.is_none() {
+            self.current = self.queue.pop_front().map(|text| Toast { text, age: 0.0 });
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, layout: &Layout, font: Font) -> GameResult {
+        let toast = match &self.current {
+            Some(toast) => toast,
+            None => return Ok(()),
+        };
+
+        let slide = (toast.age / Self::SLIDE_TIME).min(1.0);
+        let slide = 1.0 - (1.0 - slide).powi(2);
+        let alpha = ((Self::lifetime() - toast.age) / Self::FADE_TIME).min(1.0);
+
+        let text = Text::new(
+            TextFragment::new(toast.text.as_str())
+                .font(font)
+                .scale(Scale::uniform(18.0))
+                .color(Color::new(1.0, 1.0, 1.0, alpha)),
+        );
+
+        let (width, height) = text.dimensions(ctx);
+        let area_width = width as f32 + 28.0;
+        let area = Rect::new(0.0, 0.0, area_width, height as f32 + 16.0);
+
+        // Slide in from past the left edge of the screen.
+        let position = layout.place(
+            Anchor::TopLeft,
+            Vec2::new(-area_width + slide * (area_width + 20.0), 160.0),
+        );
+
+        let background = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            area,
+            Color::new(0.0, 0.0, 0.0, 0.7 * alpha),
+        )?;
+        let accent = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0.0, 0.0, 4.0, area.h),
+            Color::new(247.0 / 255.0, 76.0 / 255.0, 0.0, alpha),
+        )?;
+
+        graphics::draw(ctx, &background, DrawParam::default().dest(position))?;
+        graphics::draw(ctx, &accent, DrawParam::default().dest(position))?;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default().dest([position.x + 16.0, position.y + 8.0]),
+        )
+    }
+
+    fn lifetime() -> f32 {
+        Self::SLIDE_TIME + Self::HOLD_TIME + Self::FADE_TIME
+    }
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Subscriber for Toasts {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PickupCollected(PickupKind::Ammo) => {
+                self.push(format!("Ammo +{}", Pickup::AMMO_AMOUNT))
+            }
+            GameEvent::PickupCollected(PickupKind::Health) => {
+                self.push(format!("Health +{:.0}", Pickup::HEALTH_AMOUNT))
+            }
+            GameEvent::PickupCollected(PickupKind::BulletTime) => {
+                self.push(String::from("Bullet time!"))
+            }
+            GameEvent::CheckpointReached => self.push(String::from("Checkpoint reached")),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue() {
+        let mut toasts = Toasts::new();
+
+        toasts.push(String::from("first"));
+        toasts.push(String::from("second"));
+        toasts.update(0.0);
+
+        assert_eq!(toasts.current.as_ref().unwrap().text, "first");
+
+        // The next toast only shows up once the first one is gone.
+        toasts.update(Toasts::lifetime());
+        assert_eq!(toasts.current.as_ref().unwrap().text, "second");
+
+        toasts.update(Toasts::lifetime());
+        assert!(toasts.current.is_none());
+    }
+}
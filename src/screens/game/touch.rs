@@ -0,0 +1,249 @@
+//! On-screen controls for playing on a touch screen.
+//!
+//! A virtual stick shows up wherever the left half of the screen gets touched and moves Ferris
+//! around as it's dragged, while the buttons on the right jump and fire. ggez doesn't tell about
+//! touches themselves, so the controls follow the pointer the touch screen drives, one finger at a
+//! time. Everything here works on the virtual screen of the layout.
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, MeshBuilder, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+
+use crate::bindings::Action;
+
+use super::layout::{Anchor, Layout};
+
+/// A button drawn on the screen.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TouchButton {
+    Jump,
+    Fire,
+    Pause,
+}
+
+impl TouchButton {
+    const ALL: [TouchButton; 3] = [TouchButton::Jump, TouchButton::Fire, TouchButton::Pause];
+
+    fn label(&self) -> &'static str {
+        match self {
+            TouchButton::Jump => "JUMP",
+            TouchButton::Fire => "FIRE",
+            TouchButton::Pause => "II",
+        }
+    }
+
+    /// The middle of the button and its radius. The buttons sit right above the minimap.
+    fn area(&self, layout: &Layout) -> (Point2<f32>, f32) {
+        match self {
+            TouchButton::Jump => (
+                layout.place(Anchor::BottomRight, Vec2::new(-90.0, -210.0)),
+                55.0,
+            ),
+            TouchButton::Fire => (
+                layout.place(Anchor::BottomRight, Vec2::new(-220.0, -170.0)),
+                48.0,
+            ),
+            TouchButton::Pause => (layout.place(Anchor::Top, Vec2::new(220.0, 40.0)), 26.0),
+        }
+    }
+
+    fn action(&self) -> Option<Action> {
+        match self {
+            TouchButton::Jump => Some(Action::Jump),
+            TouchButton::Fire => Some(Action::Fire),
+            TouchButton::Pause => None,
+        }
+    }
+}
+
+pub struct TouchControls {
+    /// Where the stick was put down and where it's dragged to, while it's being held.
+    stick: Option<(Vec2, Vec2)>,
+    /// The button being held down.
+    button: Option<TouchButton>,
+}
+
+impl TouchControls {
+    /// How far the stick can be dragged from where it was put down.
+    const STICK_RADIUS: f32 = 70.0;
+    /// How far the stick needs to be dragged to move, as a part of its radius.
+    const STICK_PRESS: f32 = 0.3;
+
+    pub fn new() -> Self {
+        Self {
+            stick: None,
+            button: None,
+        }
+    }
+
+    /// The screen got touched at the provided point. Returns the button that was touched, if any.
+    pub fn press(&mut self, layout: &Layout, point: Vec2) -> Option<TouchButton> {
+        let button = TouchButton::ALL.iter().copied().find(|button| {
+            let (center, radius) = button.area(layout);
+
+            (point - Vec2::new(center.x, center.y)).norm() <= radius
+        });
+
+        if button.is_some() {
+            self.button = button;
+        } else if point.x < layout.size().x / 2.0 {
+            self.stick = Some((point, point));
+        }
+
+        button
+    }
+
+    /// The touch moved to the provided point. Returns false if it isn't dragging the stick.
+    pub fn drag(&mut self, point: Vec2) -> bool {
+        match &mut self.stick {
+            Some((_, current)) => {
+                *current = point;
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.stick = None;
+        self.button = None;
+    }
+
+    /// The actions being held down through the controls.
+    pub fn held(&self) -> Vec<Action> {
+        let stick = self.stick_offset().x / Self::STICK_RADIUS;
+        let mut held: Vec<Action> = self
+            .button
+            .and_then(|button| button.action())
+            .into_iter()
+            .collect();
+
+        if stick < -Self::STICK_PRESS {
+            held.push(Action::Left);
+        } else if stick > Self::STICK_PRESS {
+            held.push(Action::Right);
+        }
+
+        held
+    }
+
+    pub fn draw(&self, ctx: &mut Context, layout: &Layout, font: Font) -> GameResult {
+        let mut mesh = MeshBuilder::new();
+
+        if let Some((origin, _)) = self.stick {
+            let knob = origin + self.stick_offset();
+
+            mesh.circle(
+                DrawMode::fill(),
+                Point2::new(origin.x, origin.y),
+                Self::STICK_RADIUS,
+                0.5,
+                Color::new(0.0, 0.0, 0.0, 0.4),
+            );
+            mesh.circle(
+                DrawMode::fill(),
+                Point2::new(knob.x, knob.y),
+                30.0,
+                0.5,
+                Color::new(1.0, 1.0, 1.0, 0.5),
+            );
+        }
+
+        for button in TouchButton::ALL.iter() {
+            let (center, radius) = button.area(layout);
+            let alpha = if self.button == Some(*button) {
+                0.8
+            } else {
+                0.4
+            };
+
+            mesh.circle(
+                DrawMode::fill(),
+                center,
+                radius,
+                0.5,
+                Color::new(0.0, 0.0, 0.0, alpha),
+            );
+            mesh.circle(
+                DrawMode::stroke(2.0),
+                center,
+                radius,
+                0.5,
+                Color::new(1.0, 1.0, 1.0, alpha),
+            );
+        }
+
+        let mesh = mesh.build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        for button in TouchButton::ALL.iter() {
+            let (center, _) = button.area(layout);
+            let label = Text::new(
+                TextFragment::new(button.label())
+                    .font(font)
+                    .scale(Scale::uniform(18.0)),
+            );
+            let (width, height) = label.dimensions(ctx);
+
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(Point2::new(
+                    center.x - width as f32 / 2.0,
+                    center.y - height as f32 / 2.0,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// How far the stick is dragged, kept within its radius.
+    fn stick_offset(&self) -> Vec2 {
+        match self.stick {
+            Some((origin, current)) => {
+                let offset = current - origin;
+                let length = offset.norm();
+
+                if length > Self::STICK_RADIUS {
+                    offset * (Self::STICK_RADIUS / length)
+                } else {
+                    offset
+                }
+            }
+            None => Vec2::zeros(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_controls() {
+        let layout = Layout::new(Vec2::new(1280.0, 720.0));
+        let mut touch = TouchControls::new();
+
+        // Touching the left half puts the stick down, dragging it moves.
+        assert_eq!(touch.press(&layout, Vec2::new(200.0, 500.0)), None);
+        touch.drag(Vec2::new(400.0, 500.0));
+
+        assert_eq!(touch.held(), vec![Action::Right]);
+
+        touch.release();
+
+        let (jump, _) = TouchButton::Jump.area(&layout);
+
+        assert_eq!(
+            touch.press(&layout, Vec2::new(jump.x, jump.y)),
+            Some(TouchButton::Jump)
+        );
+        assert_eq!(touch.held(), vec![Action::Jump]);
+    }
+}
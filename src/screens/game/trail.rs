@@ -0,0 +1,172 @@
+//! Fading ribbons left behind by fast things.
+//!
+//! A trail remembers where the thing it's attached to has been lately and draws a ribbon through
+//! those points. The ribbon thins out and fades towards its tail, going from the head color to the
+//! tail color, and the oldest points drop off once they've been around for the trail's lifetime.
+
+use std::collections::VecDeque;
+
+use ggez::{
+    graphics::{self, Color, DrawParam, MeshBuilder, Vertex},
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+
+use crate::utils::lerp;
+
+/// How a trail looks.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TrailStyle {
+    /// How wide the ribbon is at its head.
+    pub width: f32,
+    /// How long a point stays on the trail.
+    pub lifetime: f32,
+    /// The color at the head and at the tail.
+    pub colors: (Color, Color),
+}
+
+impl TrailStyle {
+    pub const BULLET: TrailStyle = TrailStyle {
+        width: 6.0,
+        lifetime: 0.12,
+        colors: (
+            Color::new(1.0, 0.85, 0.5, 0.8),
+            Color::new(1.0, 0.4, 0.1, 0.0),
+        ),
+    };
+
+    pub const PLAYER: TrailStyle = TrailStyle {
+        width: 30.0,
+        lifetime: 0.25,
+        colors: (
+            Color::new(0.95, 0.45, 0.2, 0.35),
+            Color::new(0.95, 0.2, 0.1, 0.0),
+        ),
+    };
+}
+
+pub struct Trail {
+    style: TrailStyle,
+    /// Where the thing has been, newest first, with how long ago.
+    points: VecDeque<(Vec2, f32)>,
+}
+
+impl Trail {
+    /// Points closer than this to the last one don't get added, so a trail doesn't bunch up while
+    /// its thing moves slowly.
+    const SPACING: f32 = 4.0;
+
+    pub fn new(style: TrailStyle) -> Self {
+        Self {
+            style,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// Add where the thing is now to the head of the trail.
+    pub fn push(&mut self, position: Vec2) {
+        match self.points.front() {
+            Some((head, _)) if (position - head).norm() < Self::SPACING => {}
+            _ => self.points.push_front((position, 0.0)),
+        }
+    }
+
+    /// Age the trail, dropping the points that have been around for too long.
+    pub fn update(&mut self, dt: f32) {
+        for (_, age) in &mut self.points {
+            *age += dt;
+        }
+
+        while let Some((_, age)) = self.points.back() {
+            if *age < self.style.lifetime {
+                break;
+            }
+
+            self.points.pop_back();
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera) -> GameResult {
+        if self.points.len() < 2 {
+            return Ok(());
+        }
+
+        let points: Vec<Vec2> = self
+            .points
+            .iter()
+            .map(|(position, _)| camera.calculate_dest_point(*position))
+            .collect();
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+
+        for (index, (point, (_, age))) in points.iter().zip(&self.points).enumerate() {
+            // The ribbon goes across the direction the trail goes in, the tail goes the way the
+            // point before it does.
+            let direction = match points.get(index + 1) {
+                Some(next) => next - point,
+                None => point - points[index - 1],
+            };
+            let across = Vec2::new(-direction.y, direction.x).normalize();
+
+            let life = (age / self.style.lifetime).min(1.0);
+            let width = self.style.width * (1.0 - life) / 2.0;
+            let (head, tail) = self.style.colors;
+            let color: [f32; 4] = Color::new(
+                lerp(head.r, tail.r, life),
+                lerp(head.g, tail.g, life),
+                lerp(head.b, tail.b, life),
+                lerp(head.a, tail.a, life),
+            )
+            .into();
+
+            for side in &[across * width, -across * width] {
+                let position = point + side;
+
+                vertices.push(Vertex {
+                    pos: [position.x, position.y],
+                    uv: [0.0, 0.0],
+                    color,
+                });
+            }
+        }
+
+        for segment in 0..points.len() as u32 - 1 {
+            let start = segment * 2;
+
+            indices.extend(&[start, start + 1, start + 2, start + 1, start + 3, start + 2]);
+        }
+
+        let mesh = MeshBuilder::new()
+            .raw(&vertices, &indices, None)
+            .build(ctx)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trail() {
+        let mut trail = Trail::new(TrailStyle::BULLET);
+
+        trail.push(Vec2::new(0.0, 0.0));
+        // Too close to the last point to be added.
+        trail.push(Vec2::new(1.0, 0.0));
+        trail.push(Vec2::new(10.0, 0.0));
+        assert_eq!(trail.len(), 2);
+
+        trail.update(TrailStyle::BULLET.lifetime * 0.6);
+        trail.push(Vec2::new(20.0, 0.0));
+        trail.update(TrailStyle::BULLET.lifetime * 0.6);
+        assert_eq!(trail.len(), 1);
+    }
+}
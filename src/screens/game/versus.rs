@@ -0,0 +1,315 @@
+//! Versus, every crab for themselves.
+//!
+//! Arena maps set a frag limit with `.versus`, which turns the level into a deathmatch between the
+//! crabs of everyone playing, on this computer or online. Bullets hurt every crab but the one
+//! that fired them, crabs that go down come back at the spawn point furthest from everyone else
+//! after a moment, and the first crab to reach the frag limit wins.
+//!
+//! Every computer has the last word on its own crabs getting hit. Playing online, everyone tells
+//! the host how often their crab went down and who got it last, and the host keeps the frags of
+//! everyone and sends them around.
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Font, Rect, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::nalgebra_glm::Vec2;
+use nphysics2d::nalgebra as na;
+
+use crate::net::Frags;
+
+pub struct Versus {
+    frag_limit: u32,
+    /// The frags of every crab, by their player ids.
+    scores: Vec<Frags>,
+    /// Who hit each crab last, victim first, to get the frag when it goes down.
+    last_hit: Vec<(u8, u8)>,
+    /// How often the crabs online were known to have gone down, for the host to tell when they
+    /// go down again.
+    reported: Vec<(u8, u32)>,
+    /// How long until Ferris comes back, while Ferris is down.
+    pub down: Option<f32>,
+    /// How often Ferris went down here, for the host to keep count online.
+    pub fallen: u32,
+    /// Who got Ferris the last time Ferris went down.
+    pub fallen_to: Option<u8>,
+    /// Whether the scoreboard is being held open.
+    pub showing: bool,
+}
+
+impl Versus {
+    /// How much health a bullet takes off a crab.
+    pub const DAMAGE: f32 = 25.0;
+    /// How long a crab stays down before coming back.
+    pub const RESPAWN_TIME: f32 = 3.0;
+
+    pub fn new(frag_limit: u32) -> Self {
+        Self {
+            frag_limit,
+            scores: vec![],
+            last_hit: vec![],
+            reported: vec![],
+            down: None,
+            fallen: 0,
+            fallen_to: None,
+            showing: false,
+        }
+    }
+
+    fn entry(&mut self, id: u8) -> &mut Frags {
+        match self.scores.iter().position(|score| score.id == id) {
+            Some(index) => &mut self.scores[index],
+            None => {
+                self.scores.push(Frags {
+                    id,
+                    frags: 0,
+                    deaths: 0,
+                });
+                self.scores.sort_by_key(|score| score.id);
+
+                self.entry(id)
+            }
+        }
+    }
+
+    /// Put a crab on the scoreboard, if it isn't on it yet.
+    pub fn join(&mut self, id: u8) {
+        self.entry(id);
+    }
+
+    /// A bullet of `shooter` hit `victim`.
+    pub fn hit(&mut self, victim: u8, shooter: u8) {
+        self.last_hit.retain(|(hit, _)| *hit != victim);
+        self.last_hit.push((victim, shooter));
+    }
+
+    /// Who hit a crab last, if anyone did since it came back.
+    pub fn killer(&self, victim: u8) -> Option<u8> {
+        self.last_hit
+            .iter()
+            .find(|(hit, _)| *hit == victim)
+            .map(|(_, shooter)| *shooter)
+    }
+
+    /// A crab went down. Whoever hit it last gets the frag, unless the crab did it to itself.
+    pub fn died(&mut self, victim: u8) -> Option<u8> {
+        let killer = self.killer(victim).filter(|killer| *killer != victim);
+
+        self.last_hit.retain(|(hit, _)| *hit != victim);
+        self.entry(victim).deaths += 1;
+
+        if let Some(killer) = killer {
+            self.entry(killer).frags += 1;
+        }
+
+        killer
+    }
+
+    /// Take in what a crab online said about going down, on the host.
+    pub fn report(&mut self, id: u8, deaths: u32, killer: Option<u8>) {
+        let known = match self.reported.iter_mut().find(|(crab, _)| *crab == id) {
+            Some((_, known)) => known,
+            None => {
+                self.reported.push((id, deaths));
+                self.join(id);
+
+                return;
+            }
+        };
+        let new = deaths.saturating_sub(*known);
+
+        *known = deaths;
+
+        for _ in 0..new {
+            if let Some(killer) = killer {
+                self.hit(id, killer);
+            }
+
+            self.died(id);
+        }
+    }
+
+    /// Go along with the frags the host keeps.
+    pub fn set_scores(&mut self, scores: Vec<Frags>) {
+        self.scores = scores;
+    }
+
+    pub fn scores(&self) -> &[Frags] {
+        &self.scores
+    }
+
+    /// The crab that reached the frag limit, once one did.
+    pub fn winner(&self) -> Option<u8> {
+        self.scores
+            .iter()
+            .find(|score| score.frags >= self.frag_limit)
+            .map(|score| score.id)
+    }
+
+    /// Show how many frags everyone has at the top of the screen, or the whole scoreboard while
+    /// it's held open or once someone won, on a screen of `size`.
+    pub fn draw(&self, ctx: &mut Context, font: Font, size: Vec2) -> GameResult {
+        let (width, height) = (size.x, size.y);
+        let text = |text: String, size: f32| {
+            Text::new(
+                TextFragment::new(text)
+                    .font(font)
+                    .scale(Scale::uniform(size)),
+            )
+        };
+
+        let winner = self.winner();
+
+        if !self.showing && winner.is_none() {
+            let line = self
+                .scores
+                .iter()
+                .map(|score| format!("P{} {}", score.id + 1, score.frags))
+                .collect::<Vec<_>>()
+                .join("   ");
+            let line = text(format!("{}   FIRST TO {}", line, self.frag_limit), 20.0);
+            let line_width = line.width(ctx) as f32;
+
+            return graphics::draw(
+                ctx,
+                &line,
+                DrawParam::default().dest(Point2::new((width - line_width) / 2.0, 12.0)),
+            );
+        }
+
+        const WIDTH: f32 = 420.0;
+        const ROW: f32 = 36.0;
+
+        let rows = self.scores.len() as f32 + 2.0;
+        let top = (height - rows * ROW) / 2.0;
+        let left = (width - WIDTH) / 2.0;
+
+        let backdrop = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(left - 20.0, top - 20.0, WIDTH + 40.0, rows * ROW + 40.0),
+            Color::from_rgba(0, 0, 0, 200),
+        )?;
+
+        graphics::draw(ctx, &backdrop, DrawParam::default())?;
+
+        let header = match winner {
+            Some(winner) => format!("P{} WINS!   PRESS ENTER", winner + 1),
+            None => String::from("SCOREBOARD"),
+        };
+
+        graphics::draw(
+            ctx,
+            &text(header, 26.0),
+            DrawParam::default().dest(Point2::new(left, top)),
+        )?;
+        graphics::draw(
+            ctx,
+            &text(
+                format!("{:<10}{:>8}{:>8}", "PLAYER", "FRAGS", "DEATHS"),
+                20.0,
+            ),
+            DrawParam::default()
+                .dest(Point2::new(left, top + ROW))
+                .color(Color::new(1.0, 1.0, 1.0, 0.7)),
+        )?;
+
+        let mut scores = self.scores.clone();
+
+        scores.sort_by_key(|score| std::cmp::Reverse(score.frags));
+
+        for (row, score) in scores.iter().enumerate() {
+            let line = format!(
+                "{:<10}{:>8}{:>8}",
+                format!("P{}", score.id + 1),
+                score.frags,
+                score.deaths
+            );
+
+            graphics::draw(
+                ctx,
+                &text(line, 20.0),
+                DrawParam::default().dest(Point2::new(left, top + ROW * (row as f32 + 2.0))),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The spawn point furthest away from the closest of the `others` crabs, to come back at.
+pub fn spawn_point(
+    spawns: &[na::Point2<f32>],
+    others: &[na::Point2<f32>],
+) -> Option<na::Point2<f32>> {
+    let room = |spawn: &na::Point2<f32>| {
+        others
+            .iter()
+            .map(|other| na::distance(spawn, other))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    spawns
+        .iter()
+        .max_by(|a, b| room(a).total_cmp(&room(b)))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frags() {
+        let mut versus = Versus::new(2);
+        let deaths = |versus: &Versus, id| {
+            versus
+                .scores()
+                .iter()
+                .find(|score| score.id == id)
+                .map_or(0, |score| score.deaths)
+        };
+
+        versus.join(0);
+        versus.hit(1, 0);
+        assert_eq!(versus.died(1), Some(0));
+
+        // Going down by yourself gets nobody a frag.
+        versus.hit(0, 0);
+        assert_eq!(versus.died(0), None);
+        assert_eq!(versus.died(1), None);
+
+        assert_eq!(versus.scores()[0].frags, 1);
+        assert_eq!(deaths(&versus, 1), 2);
+        assert_eq!(versus.winner(), None);
+
+        // The host hears about a crab online going down twice at once.
+        versus.report(2, 0, None);
+        versus.report(2, 2, Some(0));
+        assert_eq!(deaths(&versus, 2), 2);
+        assert_eq!(versus.winner(), Some(0));
+    }
+
+    #[test]
+    fn test_spawn_point() {
+        let spawns = [
+            na::Point2::new(0.0, 0.0),
+            na::Point2::new(500.0, 0.0),
+            na::Point2::new(1000.0, 0.0),
+        ];
+
+        assert_eq!(
+            spawn_point(&spawns, &[na::Point2::new(100.0, 0.0)]),
+            Some(spawns[2])
+        );
+        assert_eq!(
+            spawn_point(
+                &spawns,
+                &[na::Point2::new(0.0, 0.0), na::Point2::new(1000.0, 0.0)]
+            ),
+            Some(spawns[1])
+        );
+        assert_eq!(spawn_point(&[], &[]), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! A view into the world with its own camera.
+//!
+//! Normally a single viewport covers the whole screen, but the screen can be split up between
+//! several of them (for local co-op). Split viewports are rendered into their own canvas which
+//! then gets drawn onto its part of the screen.
+
+use ggez::{
+    conf::NumSamples,
+    graphics::{self, Canvas, DrawParam, Rect},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+
+use super::camera::{CameraBounds, CameraController};
+
+pub struct Viewport {
+    /// Camera to see the world through this viewport.
+    pub camera: Camera,
+    /// Moves the camera of this viewport around smoothly.
+    pub controller: CameraController,
+
+    /// The part of the screen the viewport is drawn to.
+    rect: Rect,
+    /// The canvas the viewport is rendered into when it does not cover the whole screen.
+    canvas: Option<Canvas>,
+}
+
+impl Viewport {
+    pub fn new(
+        ctx: &mut Context,
+        rect: Rect,
+        position: Vec2,
+        bounds: CameraBounds,
+        split: bool,
+    ) -> GameResult<Self> {
+        let mut camera = Camera::new(rect.w as u32, rect.h as u32, rect.w, rect.h);
+        let mut controller = CameraController::new(position);
+
+        controller.set_bounds(bounds);
+        camera.move_to(controller.position());
+
+        let canvas = if split {
+            Some(Canvas::new(
+                ctx,
+                rect.w as u16,
+                rect.h as u16,
+                NumSamples::One,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            camera,
+            controller,
+
+            rect,
+            canvas,
+        })
+    }
+
+    /// The size of the viewport in pixels.
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.rect.w, self.rect.h)
+    }
+
+    /// Find the point in the world under a point on the screen, taking the zoom into account.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let size = self.size();
+        let center = size / 2.0;
+        let local = Vec2::new(point.x - self.rect.x, point.y - self.rect.y);
+        let unzoomed = center + (local - center) / self.controller.zoom();
+
+        // The camera shows one world unit per pixel.
+        self.camera.location() + unzoomed - center
+    }
+
+    /// Find the point on the screen a point in the world shows up at, taking the zoom into account.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let center = self.size() / 2.0;
+        let unzoomed = point - self.camera.location() + center;
+        let local = center + (unzoomed - center) * self.controller.zoom();
+
+        Vec2::new(local.x + self.rect.x, local.y + self.rect.y)
+    }
+
+    /// The part of the world the viewport sees, taking the zoom into account.
+    pub fn world_rect(&self) -> Rect {
+        let start = self.screen_to_world(self.offset());
+        let end = self.screen_to_world(self.offset() + self.size());
+
+        Rect::new(start.x, start.y, end.x - start.x, end.y - start.y)
+    }
+
+    /// Where the viewport is on the screen.
+    pub fn offset(&self) -> Vec2 {
+        Vec2::new(self.rect.x, self.rect.y)
+    }
+
+    /// The canvas the viewport is rendered into, if it has one.
+    pub fn canvas(&self) -> Option<&Canvas> {
+        self.canvas.as_ref()
+    }
+
+    /// Start drawing into the viewport. Everything drawn until `end()` uses the viewport's coordinates.
+    pub fn begin(&self, ctx: &mut Context) -> GameResult {
+        if let Some(canvas) = &self.canvas {
+            graphics::set_canvas(ctx, Some(canvas));
+            graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.rect.w, self.rect.h))?;
+            graphics::clear(ctx, graphics::BLACK);
+        }
+
+        Ok(())
+    }
+
+    /// Stop drawing into the viewport and put it on its part of the screen, which is drawn into
+    /// `screen` if there is one. `coordinates` are the screen coordinates from before `begin()`.
+    pub fn end(&self, ctx: &mut Context, screen: Option<&Canvas>, coordinates: Rect) -> GameResult {
+        if let Some(canvas) = &self.canvas {
+            graphics::set_canvas(ctx, screen);
+            graphics::set_screen_coordinates(ctx, coordinates)?;
+
+            graphics::draw(
+                ctx,
+                canvas,
+                DrawParam::default().dest(Point2::new(self.rect.x, self.rect.y)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+//! The loading screen.
+//!
+//! Assets get loaded a few at a time, between frames, so the window keeps responding and the progress
+//! bar can move along. Tips about the game are shown while waiting. Sounds and atlases that can't be
+//! loaded don't stop the loading, they all get reported together once it's done.
+
+use std::{collections::VecDeque, fs, path::PathBuf, time::Duration, time::Instant};
+
+use ggez::{
+    graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+use rand::Rng;
+
+use crate::{audio::Sfx, display, error::FerrisResult, utils::AssetManager};
+
+/// The kind of an asset waiting to be loaded.
+#[derive(PartialEq, Clone, Copy)]
+enum AssetKind {
+    Image,
+    /// The description of an atlas, which loads its image along with it.
+    Atlas,
+    Sound,
+    Music,
+    /// A text file from the provided folder.
+    File(&'static str),
+}
+
+pub struct Loading {
+    /// The assets left to load.
+    queue: VecDeque<(AssetKind, String)>,
+    /// The amount of assets there were to load in the first place.
+    total: usize,
+    /// What is being loaded, shown above the progress bar.
+    label: &'static str,
+    /// The sounds and atlases that couldn't be loaded and why.
+    failed: Vec<String>,
+
+    /// The tip being shown.
+    tip: usize,
+    /// Time until the next tip is shown.
+    tip_timer: f32,
+}
+
+impl Loading {
+    /// How long loading can take each frame before the loading screen gets drawn again.
+    const FRAME_BUDGET: Duration = Duration::from_millis(12);
+    /// How long each tip is shown for in seconds.
+    const TIP_TIME: f32 = 4.0;
+    /// The size of the progress bar.
+    const BAR_SIZE: (f32, f32) = (400.0, 16.0);
+
+    const TIPS: [&'static str; 6] = [
+        "Shoot a barrel to blow up everything around it, including other barrels.",
+        "Hitting a gopher on the head is always a critical hit.",
+        "Kills in quick succession build up a combo multiplier.",
+        "Crates can hold ammo and health. Shoot them open!",
+        "Press R to reload before you run dry.",
+        "Press M to see the map of the whole level.",
+    ];
+
+    /// Start loading every asset from the resource directories, the game's own and the ones of the
+    /// mods. The fonts get loaded right away so the loading screen has something to write with.
+    pub fn assets(
+        ctx: &mut Context,
+        asset_manager: &mut AssetManager,
+        dirs: &[PathBuf],
+    ) -> FerrisResult<Self> {
+        for font in files(dirs, "fonts")? {
+            asset_manager.load_font(ctx, font.into())?;
+        }
+
+        let mut queue = VecDeque::new();
+
+        // Only the sound effects in the manifest get loaded, there's no point in decoding anything
+        // else.
+        for sfx in Sfx::ALL {
+            let sound = (AssetKind::Sound, sfx.file().to_string());
+
+            if !queue.contains(&sound) {
+                queue.push_back(sound);
+            }
+        }
+
+        let folders = [
+            ("images", AssetKind::Image),
+            ("music", AssetKind::Music),
+            ("maps", AssetKind::File("maps")),
+            ("arenas", AssetKind::File("arenas")),
+            ("dialogues", AssetKind::File("dialogues")),
+            ("scripts", AssetKind::File("scripts")),
+            ("achievements", AssetKind::File("achievements")),
+            ("lang", AssetKind::File("lang")),
+            ("cutscenes", AssetKind::File("cutscenes")),
+            ("remixes", AssetKind::File("remixes")),
+            ("demos", AssetKind::File("demos")),
+        ];
+
+        for (folder, kind) in folders {
+            for file in files(dirs, folder)? {
+                queue.push_back((kind, file));
+            }
+        }
+
+        // The images of the atlases get loaded along with their descriptions.
+        for name in files(dirs, "atlases")? {
+            if name.ends_with(".toml") {
+                queue.push_back((AssetKind::Atlas, name));
+            }
+        }
+
+        Ok(Self::new(queue, "LOADING ASSETS"))
+    }
+
+    /// A loading screen for building a level, which happens all at once.
+    pub fn level() -> Self {
+        Self::new(VecDeque::new(), "BUILDING LEVEL")
+    }
+
+    fn new(queue: VecDeque<(AssetKind, String)>, label: &'static str) -> Self {
+        Self {
+            total: queue.len(),
+            queue,
+            label,
+            failed: vec![],
+
+            tip: rand::thread_rng().gen_range(0..Self::TIPS.len()),
+            tip_timer: Self::TIP_TIME,
+        }
+    }
+
+    /// Load as many of the assets left as fit in a frame. Returns `true` once everything is loaded.
+    pub fn step(&mut self, ctx: &mut Context, asset_manager: &mut AssetManager) -> bool {
+        let start = Instant::now();
+
+        while start.elapsed() < Self::FRAME_BUDGET {
+            match self.queue.pop_front() {
+                Some((AssetKind::Image, name)) => {
+                    if let Err(error) = asset_manager.load_image(ctx, name.as_str().into()) {
+                        log::warn!("Cannot load {}: {}", name, error);
+                        self.failed.push(format!("{} ({})", name, error));
+                    }
+                }
+                Some((AssetKind::Atlas, name)) => {
+                    if let Err(error) = asset_manager.load_atlas(ctx, name.as_str().into()) {
+                        log::warn!("Cannot load {}: {}", name, error);
+                        self.failed.push(format!("{} ({})", name, error));
+                    }
+                }
+                Some((AssetKind::Sound, name)) => {
+                    if let Err(error) = asset_manager.load_sound(ctx, name.as_str().into()) {
+                        log::warn!("Cannot load {}: {}", name, error);
+                        self.failed.push(format!("{} ({})", name, error));
+                    }
+                }
+                Some((AssetKind::Music, name)) => {
+                    if let Err(error) = asset_manager.load_music(ctx, name.as_str().into()) {
+                        log::warn!("Cannot load {}: {}", name, error);
+                        self.failed.push(format!("{} ({})", name, error));
+                    }
+                }
+                Some((AssetKind::File(folder), name)) => {
+                    if let Err(error) = asset_manager.load_file(ctx, folder, name.as_str().into()) {
+                        log::warn!("Cannot load {}: {}", name, error);
+                        self.failed.push(format!("{} ({})", name, error));
+                    }
+                }
+                None => break,
+            }
+        }
+
+        self.queue.is_empty()
+    }
+
+    /// Check that everything got loaded, failing with every asset that didn't.
+    pub fn finish(&self) -> FerrisResult<()> {
+        if self.failed.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Cannot load these assets: {}", self.failed.join(", ")).into())
+        }
+    }
+
+    /// How much has been loaded, between 0.0 and 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            1.0 - self.queue.len() as f32 / self.total as f32
+        }
+    }
+
+    /// Rotate through the tips.
+    pub fn update(&mut self, dt: f32) {
+        self.tip_timer -= dt;
+
+        if self.tip_timer <= 0.0 {
+            self.tip = (self.tip + 1) % Self::TIPS.len();
+            self.tip_timer = Self::TIP_TIME;
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, asset_manager: &AssetManager) -> GameResult {
+        let (width, height) = display::screen_size(ctx);
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let consolas = asset_manager.text_font();
+
+        let label = Text::new(
+            TextFragment::new(asset_manager.text(self.label))
+                .font(consolas)
+                .scale(Scale::uniform(30.0)),
+        );
+
+        let label_width = label.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &label,
+            DrawParam::default().dest(Point2::new(
+                (width - label_width) / 2.0,
+                height / 2.0 - 60.0,
+            )),
+        )?;
+
+        let bar = Rect::new(
+            (width - Self::BAR_SIZE.0) / 2.0,
+            height / 2.0,
+            Self::BAR_SIZE.0,
+            Self::BAR_SIZE.1,
+        );
+
+        let background =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), bar, Color::from_rgb(54, 50, 49))?;
+
+        graphics::draw(ctx, &background, DrawParam::default())?;
+
+        if self.progress() > 0.0 {
+            let fill = Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(bar.x, bar.y, bar.w * self.progress(), bar.h),
+                Color::from_rgb(247, 76, 0),
+            )?;
+
+            graphics::draw(ctx, &fill, DrawParam::default())?;
+        }
+
+        let tip = Text::new(
+            TextFragment::new(asset_manager.text_with(
+                "TIP: {tip}",
+                &[("tip", &asset_manager.text(Self::TIPS[self.tip]))],
+            ))
+            .font(consolas)
+            .scale(Scale::uniform(18.0))
+            .color(Color::new(1.0, 1.0, 1.0, 0.7)),
+        );
+
+        let tip_width = tip.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &tip,
+            DrawParam::default().dest(Point2::new((width - tip_width) / 2.0, height / 2.0 + 60.0)),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The names of the files in a folder of any of the resource directories, each name once. The
+/// directories don't all need to have the folder.
+fn files(dirs: &[PathBuf], folder: &str) -> FerrisResult<Vec<String>> {
+    let mut files = vec![];
+
+    for dir in dirs {
+        let entries = match fs::read_dir(dir.join(folder)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries {
+            let name = entry?.file_name().to_string_lossy().to_string();
+
+            if !files.contains(&name) {
+                files.push(name);
+            }
+        }
+    }
+
+    Ok(files)
+}
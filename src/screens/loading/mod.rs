@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod loading;
+
+pub use loading::*;
@@ -1,32 +1,360 @@
 use ggez::{
-    event::KeyCode,
-    graphics::{self, Scale, Text, TextFragment},
+    event::{Button, KeyCode},
+    graphics::{self, Font, Scale, Text, TextFragment},
     nalgebra::{Point2, Vector2},
     Context, GameResult,
 };
 use graphics::{Color, DrawParam};
-use std::{process::exit, rc::Rc};
+use std::{path::PathBuf, process::exit, rc::Rc};
 
 use crate::utils::AssetManager;
-use crate::Screen;
+use crate::{
+    display,
+    game::recording,
+    save::{Difficulty, Save},
+    Screen,
+};
+
+/// The things the menu can be asked to do, no matter if it was a key or a gamepad button.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+impl MenuInput {
+    pub fn from_key(keycode: KeyCode) -> Option<Self> {
+        match keycode {
+            KeyCode::Up | KeyCode::W => Some(MenuInput::Up),
+            KeyCode::Down | KeyCode::S => Some(MenuInput::Down),
+            KeyCode::Left | KeyCode::A => Some(MenuInput::Left),
+            KeyCode::Right | KeyCode::D => Some(MenuInput::Right),
+            KeyCode::Return | KeyCode::Space => Some(MenuInput::Select),
+            KeyCode::Escape | KeyCode::Back => Some(MenuInput::Back),
+            _ => None,
+        }
+    }
+
+    pub fn from_button(button: Button) -> Option<Self> {
+        match button {
+            Button::DPadUp => Some(MenuInput::Up),
+            Button::DPadDown => Some(MenuInput::Down),
+            Button::DPadLeft => Some(MenuInput::Left),
+            Button::DPadRight => Some(MenuInput::Right),
+            Button::South | Button::Start => Some(MenuInput::Select),
+            Button::East | Button::Select => Some(MenuInput::Back),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum MenuItem {
+    StartGame,
+    Tutorial,
+    Daily,
+    BossRush,
+    LevelSelect,
+    Versus,
+    Players,
+    SaveSlots,
+    Statistics,
+    Trophies,
+    Replays,
+    Mods,
+    Settings,
+    Quit,
+}
+
+impl MenuItem {
+    const ALL: [MenuItem; 14] = [
+        MenuItem::StartGame,
+        MenuItem::Tutorial,
+        MenuItem::Daily,
+        MenuItem::BossRush,
+        MenuItem::LevelSelect,
+        MenuItem::Versus,
+        MenuItem::Players,
+        MenuItem::SaveSlots,
+        MenuItem::Statistics,
+        MenuItem::Trophies,
+        MenuItem::Replays,
+        MenuItem::Mods,
+        MenuItem::Settings,
+        MenuItem::Quit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MenuItem::StartGame => "START GAME",
+            MenuItem::Tutorial => "TUTORIAL",
+            MenuItem::Daily => "DAILY CHALLENGE",
+            MenuItem::BossRush => "BOSS RUSH",
+            MenuItem::LevelSelect => "LEVEL SELECT",
+            MenuItem::Versus => "VERSUS",
+            MenuItem::Players => "PLAYERS",
+            MenuItem::SaveSlots => "SAVE SLOTS",
+            MenuItem::Statistics => "STATISTICS",
+            MenuItem::Trophies => "TROPHIES",
+            MenuItem::Replays => "REPLAYS",
+            MenuItem::Mods => "MODS",
+            MenuItem::Settings => "SETTINGS",
+            MenuItem::Quit => "QUIT",
+        }
+    }
+}
+
+/// Draw a menu item with its top left corner at `position`.
+pub fn draw_item(
+    ctx: &mut Context,
+    font: Font,
+    label: &str,
+    position: Point2<f32>,
+    selected: bool,
+) -> GameResult<()> {
+    let item_rect = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        graphics::Rect::new(position.x, position.y, 220.0, 40.0),
+        if selected {
+            Color::from_rgba(36, 36, 36, 230)
+        } else {
+            Color::from_rgba(36, 36, 36, 128)
+        },
+    )?;
+
+    let color = if selected {
+        Color::from_rgb(247, 76, 0)
+    } else {
+        graphics::WHITE
+    };
+
+    let label = TextFragment::new(if selected {
+        format!("> {}", label)
+    } else {
+        label.to_string()
+    })
+    .font(font)
+    .scale(Scale::uniform(20.0))
+    .color(color);
+
+    graphics::draw(ctx, &item_rect, DrawParam::default())?;
+    graphics::draw(
+        ctx,
+        &Text::new(label),
+        DrawParam::default().dest(Point2::new(position.x + 20.0, position.y + 10.0)),
+    )
+}
+
+/// The list of items currently shown by the menu.
+enum Page {
+    Main,
+    LevelSelect,
+    /// The arenas for versus.
+    Arenas,
+    /// The replays of past runs, newest first.
+    Replays,
+    /// The mods that got loaded, in their load order.
+    Mods,
+    /// How hard the game gets, picked when starting a new game.
+    Difficulty,
+}
 
 pub struct Menu {
     asset_manager: Rc<AssetManager>,
+
+    page: Page,
+    /// The highlighted item on the current page.
+    selected: usize,
+
+    /// The ids of all of the maps that can be played.
+    levels: Vec<String>,
+    /// The level that gets played when starting the game.
+    level: usize,
+    /// The ids of the arenas for versus.
+    arenas: Vec<String>,
+    /// The arena that gets played instead of the level, once one is picked.
+    arena: Option<usize>,
+    /// The ids of the levels the player has beaten.
+    completed: Vec<String>,
+    /// The names of the mods that got loaded.
+    mods: Vec<String>,
+    /// The replays that can be watched.
+    replays: Vec<PathBuf>,
+    /// The replay picked to be watched, until the game picks it up.
+    replay: Option<PathBuf>,
+    /// How hard the game is in the save, once it was picked.
+    difficulty: Option<Difficulty>,
+    /// The difficulty picked for a new game, until the game picks it up.
+    picked: Option<Difficulty>,
+    /// Whether every level has been beaten, which makes the next game a New Game+.
+    campaign_beaten: bool,
+    /// Whether a New Game+ was started, until the game picks it up.
+    new_game_plus: bool,
+    /// Whether the daily challenge was picked, until the game picks it up.
+    daily: bool,
+    /// Whether boss rush can be played yet.
+    boss_rush_unlocked: bool,
+    /// The map played instead of the level, the tutorial or boss rush.
+    special: Option<&'static str>,
+    /// How long nothing was pressed on the menu for.
+    idle: f32,
+    /// Whether the menu was left alone long enough to play a demo, until the game picks it up.
+    demo: bool,
 }
 
 impl Menu {
-    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
-        Self { asset_manager }
+    /// The track played on the menu.
+    pub const MUSIC: &'static str = "menu.wav";
+    /// The level teaching the controls, which isn't part of the campaign.
+    const TUTORIAL: &'static str = "tutorial";
+    /// The map boss rush is played on, which isn't part of the campaign either.
+    const BOSS_RUSH: &'static str = "boss_rush";
+    /// How long the menu has to be left alone for before it plays a demo.
+    const IDLE_TIME: f32 = 30.0;
+
+    pub fn create(
+        ctx: &mut Context,
+        asset_manager: Rc<AssetManager>,
+        slot: usize,
+        mods: Vec<String>,
+    ) -> Self {
+        let maps = |folder| -> Vec<String> {
+            asset_manager
+                .get_files(folder)
+                .iter()
+                .filter_map(|file| file.strip_suffix(".map"))
+                .filter(|map| ![Self::TUTORIAL, Self::BOSS_RUSH].contains(map))
+                .map(String::from)
+                .collect()
+        };
+        let levels = maps("maps");
+        let arenas = maps("arenas");
+
+        let mut menu = Self {
+            asset_manager,
+
+            page: Page::Main,
+            selected: 0,
+
+            levels,
+            level: 0,
+            arenas,
+            arena: None,
+            completed: vec![],
+            mods,
+            replays: vec![],
+            replay: None,
+            difficulty: None,
+            picked: None,
+            campaign_beaten: false,
+            new_game_plus: false,
+            daily: false,
+            boss_rush_unlocked: false,
+            special: None,
+            idle: 0.0,
+            demo: false,
+        };
+
+        menu.load_progress(ctx, slot);
+        menu
+    }
+
+    /// Catch up with the levels beaten since the menu was last shown, or with another save. Carries
+    /// on with the first level that hasn't been beaten yet.
+    pub fn load_progress(&mut self, ctx: &Context, slot: usize) {
+        let save = Save::load(ctx, slot);
+
+        self.level = save
+            .level_reached(&self.levels)
+            .and_then(|reached| self.levels.iter().position(|level| level == reached))
+            .unwrap_or(0);
+        self.campaign_beaten = save.is_campaign_beaten(&self.levels);
+        self.boss_rush_unlocked = save.is_boss_rush_unlocked(&self.levels);
+        self.completed = save.completed_levels;
+        self.difficulty = save.difficulty;
+        self.replays = recording::watchable_replays(ctx);
+        // Coming back to the menu isn't leaving it alone.
+        self.idle = 0.0;
+    }
+
+    /// The replay that was picked to be watched, if one just was.
+    pub fn take_replay(&mut self) -> Option<PathBuf> {
+        self.replay.take()
+    }
+
+    /// The difficulty that was picked for a new game, if one just was.
+    pub fn take_difficulty(&mut self) -> Option<Difficulty> {
+        self.picked.take()
+    }
+
+    /// Whether the daily challenge was just picked.
+    pub fn take_daily(&mut self) -> bool {
+        std::mem::take(&mut self.daily)
+    }
+
+    /// Whether the menu was just left alone long enough to play a demo.
+    pub fn take_demo(&mut self) -> bool {
+        std::mem::take(&mut self.demo)
+    }
+
+    /// Whether a New Game+ was just started.
+    pub fn take_new_game_plus(&mut self) -> bool {
+        std::mem::take(&mut self.new_game_plus)
+    }
+
+    /// Whether a new game is about to start, with the difficulty just picked for it or as a New
+    /// Game+.
+    pub fn is_new_game(&self) -> bool {
+        self.picked.is_some() || self.new_game_plus
+    }
+
+    /// The ids of all of the maps that can be played.
+    pub fn levels(&self) -> &[String] {
+        &self.levels
+    }
+
+    /// Play `level` when starting the game, if there's a level or an arena going by that id.
+    pub fn select_level(&mut self, level: &str) {
+        if let Some(id) = self.levels.iter().position(|id| id == level) {
+            self.level = id;
+            self.arena = None;
+            self.special = None;
+        } else if let Some(id) = self.arenas.iter().position(|id| id == level) {
+            self.arena = Some(id);
+            self.special = None;
+        } else if let Some(special) = [Self::TUTORIAL, Self::BOSS_RUSH]
+            .iter()
+            .find(|special| **special == level)
+        {
+            self.special = Some(special);
+        }
+    }
+
+    /// The id of the map to play.
+    pub fn level(&self) -> &str {
+        if let Some(special) = self.special {
+            return special;
+        }
+
+        match self.arena {
+            Some(arena) => &self.arenas[arena],
+            None => &self.levels[self.level],
+        }
     }
 
     pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
-        let (width, height) = graphics::drawable_size(ctx);
+        let (width, height) = display::screen_size(ctx);
 
         let logo = self.asset_manager.get_image("logo.png");
         let ferris_ninja = self.asset_manager.get_image("ferris_ninja.png");
         let menu_bg = self.asset_manager.get_image("menu_bg.png");
 
-        let consolas = self.asset_manager.get_font("Consolas.ttf");
+        let consolas = self.asset_manager.text_font();
 
         // Clear the screen
         graphics::clear(ctx, graphics::BLACK);
@@ -52,99 +380,257 @@ impl Menu {
             )),
         )?;
 
-        let press_and_to = TextFragment::new("Press & to")
-            .font(consolas)
-            .scale(Scale::uniform(15.0));
+        let items = self.items();
+        // Longer pages start higher up so they still fit on the screen.
+        let top = (height - (ferris_ninja.height() + 70) as f32)
+            .min(height - items.len() as f32 * 50.0 - 10.0);
 
-        let press_pointer_to = TextFragment::new("Press * to")
-            .font(consolas)
-            .scale(Scale::uniform(15.0));
+        for (id, label) in items.iter().enumerate() {
+            draw_item(
+                ctx,
+                consolas,
+                label,
+                Point2::new(width - 240.0, top + id as f32 * 50.0),
+                id == self.selected,
+            )?;
+        }
 
-        graphics::draw(
-            ctx,
-            &Text::new(press_and_to),
-            DrawParam::default().dest(Point2::new(
-                width - 200.0,
-                height - (ferris_ninja.height() + 30) as f32,
-            )),
-        )?;
+        Ok(())
+    }
 
-        let play_rect = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                width - 200.0,
-                height - (ferris_ninja.height() + 10) as f32,
-                220.0,
-                40.0,
-            ),
-            Color::from_rgba(36, 36, 36, 128),
-        )?;
+    pub fn update(&mut self, _ctx: &mut Context, dt: f32) -> GameResult {
+        self.idle += dt;
 
-        let quit_rect = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(
-                width - 200.0,
-                height - (ferris_ninja.height() - 70) as f32,
-                220.0,
-                40.0,
-            ),
-            Color::from_rgba(36, 36, 36, 128),
-        )?;
+        if self.idle >= Self::IDLE_TIME {
+            self.idle = 0.0;
+            self.demo = true;
+        }
 
-        let play_text = TextFragment::new("PLAY")
-            .font(consolas)
-            .scale(Scale::uniform(20.0));
+        Ok(())
+    }
 
-        let quit_text = TextFragment::new("QUIT")
-            .font(consolas)
-            .scale(Scale::uniform(20.0));
+    pub fn key_press(&mut self, keycode: KeyCode) -> Option<Screen> {
+        self.idle = 0.0;
 
-        graphics::draw(ctx, &play_rect, DrawParam::default())?;
-        graphics::draw(ctx, &quit_rect, DrawParam::default())?;
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input))
+    }
 
-        graphics::draw(
-            ctx,
-            &Text::new(play_text),
-            DrawParam::default().dest(Point2::new(
-                width - 170.0,
-                height - ferris_ninja.height() as f32,
-            )),
-        )?;
+    pub fn gamepad_button_press(&mut self, button: Button) -> Option<Screen> {
+        self.idle = 0.0;
 
-        graphics::draw(
-            ctx,
-            &Text::new(press_pointer_to),
-            DrawParam::default().dest(Point2::new(
-                width - 200.0,
-                height - (ferris_ninja.height() - 50) as f32,
-            )),
-        )?;
+        MenuInput::from_button(button).and_then(|input| self.navigate(input))
+    }
 
-        graphics::draw(
-            ctx,
-            &Text::new(quit_text),
-            DrawParam::default().dest(Point2::new(
-                width - 170.0,
-                height - (ferris_ninja.height() - 80) as f32,
-            )),
-        )?;
+    /// The labels of the items on the current page.
+    fn items(&self) -> Vec<String> {
+        let text = |english: &str| self.asset_manager.text(english);
+        let back = text("BACK");
+
+        match self.page {
+            Page::Main => MenuItem::ALL
+                .iter()
+                .map(|item| match item {
+                    MenuItem::StartGame if self.campaign_beaten => text("NEW GAME+"),
+                    MenuItem::BossRush if !self.boss_rush_unlocked => text("BOSS RUSH LOCKED"),
+                    _ => text(item.label()),
+                })
+                .collect(),
+
+            Page::LevelSelect => self
+                .levels
+                .iter()
+                .map(|level| {
+                    let label = if self.completed.contains(level) {
+                        "LEVEL {level} DONE"
+                    } else {
+                        "LEVEL {level}"
+                    };
 
-        graphics::present(ctx)
+                    self.asset_manager.text_with(label, &[("level", level)])
+                })
+                .chain(std::iter::once(back))
+                .collect(),
+
+            Page::Arenas => self
+                .arenas
+                .iter()
+                .map(|arena| {
+                    self.asset_manager
+                        .text_with("ARENA {arena}", &[("arena", &arena.to_uppercase())])
+                })
+                .chain(std::iter::once(back))
+                .collect(),
+
+            Page::Replays if self.replays.is_empty() => vec![text("NO REPLAYS"), back],
+            Page::Replays => self
+                .replays
+                .iter()
+                .map(|replay| {
+                    replay
+                        .file_stem()
+                        .map_or(String::new(), |name| name.to_string_lossy().to_uppercase())
+                })
+                .chain(std::iter::once(back))
+                .collect(),
+
+            Page::Mods if self.mods.is_empty() => vec![text("NO MODS"), back],
+            Page::Mods => self
+                .mods
+                .iter()
+                .map(|name| name.to_uppercase())
+                .chain(std::iter::once(back))
+                .collect(),
+
+            Page::Difficulty => Difficulty::ALL
+                .iter()
+                .map(|difficulty| text(&format!("{:?}", difficulty).to_uppercase()))
+                .chain(std::iter::once(back))
+                .collect(),
+        }
     }
 
-    pub fn update(&self, _ctx: &mut Context) -> GameResult {
-        Ok(())
+    fn navigate(&mut self, input: MenuInput) -> Option<Screen> {
+        let count = self.items().len();
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Back => self.open(Page::Main),
+            MenuInput::Select => return self.select(),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
     }
 
-    pub fn key_press(&self, keycode: KeyCode) -> Option<Screen> {
-        if keycode == KeyCode::Key7 {
-            return Some(Screen::Play);
-        } else if keycode == KeyCode::Key8 {
-            exit(0);
+    fn select(&mut self) -> Option<Screen> {
+        match self.page {
+            Page::Main => match MenuItem::ALL[self.selected] {
+                // A new game starts with picking how hard it gets.
+                MenuItem::StartGame if self.difficulty.is_none() => {
+                    self.open(Page::Difficulty);
+                    self.selected = 1;
+                }
+                // Once every level is beaten, the campaign starts over from the first one.
+                MenuItem::StartGame if self.campaign_beaten => {
+                    self.campaign_beaten = false;
+                    self.new_game_plus = true;
+                    self.completed.clear();
+                    self.level = 0;
+                    self.arena = None;
+                    self.special = None;
+
+                    return Some(Screen::Play);
+                }
+                MenuItem::StartGame => {
+                    self.arena = None;
+                    self.special = None;
+
+                    return Some(Screen::Play);
+                }
+                MenuItem::Tutorial => {
+                    self.arena = None;
+                    self.special = Some(Self::TUTORIAL);
+
+                    return Some(Screen::Play);
+                }
+                MenuItem::Daily => {
+                    self.daily = true;
+                    self.arena = None;
+                    self.special = None;
+
+                    return Some(Screen::Play);
+                }
+                // Boss rush waits for the campaign to be beaten.
+                MenuItem::BossRush if !self.boss_rush_unlocked => {}
+                MenuItem::BossRush => {
+                    self.arena = None;
+                    self.special = Some(Self::BOSS_RUSH);
+
+                    return Some(Screen::Play);
+                }
+                MenuItem::Versus => self.open(Page::Arenas),
+                MenuItem::LevelSelect => {
+                    self.open(Page::LevelSelect);
+                    self.selected = self.level;
+                }
+                MenuItem::Players => return Some(Screen::Devices),
+                MenuItem::SaveSlots => return Some(Screen::Slots),
+                MenuItem::Statistics => return Some(Screen::Stats),
+                MenuItem::Trophies => return Some(Screen::Trophies),
+                MenuItem::Replays => self.open(Page::Replays),
+                MenuItem::Mods => self.open(Page::Mods),
+                MenuItem::Settings => return Some(Screen::Settings),
+                MenuItem::Quit => exit(0),
+            },
+
+            Page::LevelSelect => {
+                if self.selected < self.levels.len() {
+                    self.level = self.selected;
+                    self.arena = None;
+                    self.special = None;
+                    self.open(Page::Main);
+
+                    return Some(Screen::Play);
+                }
+
+                // Back
+                self.open(Page::Main);
+            }
+
+            Page::Arenas => {
+                if self.selected < self.arenas.len() {
+                    self.arena = Some(self.selected);
+                    self.special = None;
+                    self.open(Page::Main);
+
+                    return Some(Screen::Play);
+                }
+
+                // Back
+                self.open(Page::Main);
+            }
+
+            Page::Replays => {
+                if let Some(replay) = self.replays.get(self.selected) {
+                    self.replay = Some(replay.clone());
+                    self.open(Page::Main);
+
+                    return Some(Screen::Play);
+                }
+
+                // Back
+                self.open(Page::Main);
+            }
+
+            Page::Difficulty => {
+                if let Some(difficulty) = Difficulty::ALL.get(self.selected) {
+                    self.difficulty = Some(*difficulty);
+                    self.picked = Some(*difficulty);
+                    self.arena = None;
+                    self.special = None;
+                    self.open(Page::Main);
+
+                    return Some(Screen::Play);
+                }
+
+                // Back
+                self.open(Page::Main);
+            }
+
+            Page::Mods => {
+                // Only the last item, going back, does anything.
+                if self.selected + 1 == self.items().len() {
+                    self.open(Page::Main);
+                }
+            }
         }
 
         None
     }
+
+    fn open(&mut self, page: Page) {
+        self.page = page;
+        self.selected = 0;
+    }
 }
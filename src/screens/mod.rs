@@ -1,3 +1,12 @@
+pub mod cutscene;
 pub mod dead;
+pub mod devices;
 pub mod game;
+pub mod loading;
 pub mod menu;
+pub mod results;
+pub mod scenes;
+pub mod settings;
+pub mod slots;
+pub mod stats;
+pub mod trophies;
@@ -0,0 +1,131 @@
+//! The results of a boss rush, once it's over.
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{display, game::boss_rush::RushResults, menu::MenuInput, utils::AssetManager, Screen};
+
+pub struct ResultsScreen {
+    asset_manager: Rc<AssetManager>,
+
+    results: RushResults,
+}
+
+impl ResultsScreen {
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self {
+            asset_manager,
+
+            results: RushResults::default(),
+        }
+    }
+
+    /// Show how the boss rush that just ended went.
+    pub fn open(&mut self, results: RushResults) {
+        self.results = results;
+    }
+
+    /// The results as they're shown, with their labels.
+    fn rows(&self) -> Vec<(String, String)> {
+        let text = |english: &str| self.asset_manager.text(english);
+        let time = |seconds: f32| format!("{}:{:04.1}", seconds as u32 / 60, seconds % 60.0);
+
+        self.results
+            .bosses
+            .iter()
+            .map(|(name, beaten)| {
+                let beaten = match beaten {
+                    Some(seconds) => time(*seconds),
+                    None => text("NOT BEATEN"),
+                };
+
+                (text(name), beaten)
+            })
+            .chain(vec![
+                (text("TOTAL TIME"), time(self.results.total_time())),
+                (text("SCORE"), self.results.score.to_string()),
+            ])
+            .collect()
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+        let consolas = self.asset_manager.text_font();
+        let rows = self.rows();
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let title = if self.results.won {
+            "BOSS RUSH COMPLETE"
+        } else {
+            "BOSS RUSH OVER"
+        };
+        let title = Text::new(
+            TextFragment::new(self.asset_manager.text(title))
+                .font(consolas)
+                .scale(Scale::uniform(50.0)),
+        );
+        let title_width = title.width(ctx) as f32;
+        let top = height / 2.0 - (rows.len() as f32 * 40.0) / 2.0;
+
+        graphics::draw(
+            ctx,
+            &title,
+            DrawParam::default().dest(Point2::new((width - title_width) / 2.0, top - 80.0)),
+        )?;
+
+        for (id, (label, value)) in rows.iter().enumerate() {
+            let y = top + id as f32 * 40.0;
+            let label = Text::new(
+                TextFragment::new(label.as_str())
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0)),
+            );
+            let value = Text::new(
+                TextFragment::new(value.as_str())
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(Color::from_rgb(247, 76, 0)),
+            );
+            let value_width = value.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(Point2::new(width / 2.0 - 250.0, y)),
+            )?;
+            graphics::draw(
+                ctx,
+                &value,
+                DrawParam::default().dest(Point2::new(width / 2.0 + 250.0 - value_width, y)),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(&mut self, keycode: KeyCode) -> Option<Screen> {
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input))
+    }
+
+    pub fn gamepad_button_press(&mut self, button: Button) -> Option<Screen> {
+        MenuInput::from_button(button).and_then(|input| self.navigate(input))
+    }
+
+    fn navigate(&mut self, input: MenuInput) -> Option<Screen> {
+        match input {
+            MenuInput::Select | MenuInput::Back => Some(Screen::Menu),
+            _ => None,
+        }
+    }
+}
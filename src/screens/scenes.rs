@@ -0,0 +1,1169 @@
+//! The scenes the screens get played through, and what they share.
+//!
+//! Every screen comes with a scene taking the updates, the drawing and the input of the game to
+//! it. Scenes get opened fresh every time their screen is gone to, apart from the menu and the
+//! level, which carry on in between: going back to the menu comes back to the level picked last,
+//! and a level that was left can still be looked at on the death screen.
+
+use std::{path::PathBuf, rc::Rc, sync::Mutex};
+
+use ggez::{
+    event::{Button, KeyCode},
+    input::gamepad::GamepadId,
+    Context, GameError, GameResult,
+};
+
+use crate::{
+    audio::MusicController,
+    config::Config,
+    cutscene::CutsceneScreen,
+    dead::{Death, DeathAction},
+    devices::{DeviceScreen, Devices, InputDevice},
+    error::{ErrorBanner, FerrisError},
+    game::{
+        self,
+        daily::DailyChallenge,
+        pause::{PauseAction, PauseMenu},
+        recording::Recording,
+    },
+    loading::Loading,
+    menu::{Menu, MenuInput},
+    net,
+    results::ResultsScreen,
+    save::{Difficulty, Save},
+    scene::{Scene, SceneChange},
+    settings::Settings,
+    slots::SaveSlots,
+    stats::StatsScreen,
+    trophies::TrophiesScreen,
+    utils::AssetManager,
+    Screen,
+};
+
+#[cfg(feature = "discord")]
+use crate::discord;
+
+/// Everything the screens share.
+pub struct Shared {
+    /// The menu, which remembers the level picked on it.
+    pub menu: Menu,
+    /// The level being played, or the one played last.
+    pub game: Mutex<game::Game>,
+    /// What every player plays with.
+    pub devices: Devices,
+    /// The player's settings.
+    pub config: Config,
+    /// Tells about the errors the game carried on from.
+    pub errors: ErrorBanner,
+    /// Where to write a recording of every run.
+    pub record_to: Option<PathBuf>,
+    /// The recording to play back once its level is loaded.
+    pub replay: Option<Recording>,
+    /// Whether the recording to play back is a demo.
+    pub demo: bool,
+    /// How many demos were played, they take turns.
+    pub demos_played: usize,
+    /// The online game being played, if there's one.
+    pub session: Option<net::Session>,
+    /// The seed every run gets played on, for challenge runs. Without one every run gets a seed
+    /// of its own.
+    pub seed: Option<u64>,
+    /// The cutscene the cutscene screen plays next, and where it goes after.
+    pub cutscene: Option<(&'static str, Screen)>,
+    /// Shows what's being played on the player's Discord profile, while it's turned on.
+    #[cfg(feature = "discord")]
+    pub presence: Option<discord::Presence>,
+    pub asset_manager: Rc<AssetManager>,
+}
+
+impl Shared {
+    /// Apply the changes made in the settings screen and write them to the config file. Settings
+    /// that can't be applied or saved get reported, the rest of them still apply.
+    pub fn apply_config(&mut self, ctx: &mut Context, playing: bool) {
+        let applied = self
+            .config
+            .apply(ctx, &self.asset_manager)
+            .and_then(|_| self.game.lock().unwrap().apply_config(ctx, &self.config));
+
+        self.errors.report(applied.map_err(FerrisError::from));
+        self.errors.report(self.config.save());
+
+        #[cfg(feature = "discord")]
+        if self.config.discord_presence != self.presence.is_some() {
+            self.start_presence();
+
+            // Turned on from the pause menu, the level being played shows up right away.
+            let mut game = self.game.lock().unwrap();
+
+            match &self.presence {
+                Some(presence) if playing && !game.is_demo() => game.report_to(presence.clone()),
+                _ => {}
+            }
+        }
+
+        #[cfg(not(feature = "discord"))]
+        let _ = playing;
+    }
+
+    /// Start showing what's being played on Discord if it's turned on, or stop if it's not.
+    /// Without an application in the config Discord doesn't hear about the game either way.
+    #[cfg(feature = "discord")]
+    pub fn start_presence(&mut self) {
+        if let Some(presence) = self.presence.take() {
+            presence.clear();
+        }
+
+        if self.config.discord_presence && !self.config.discord_application.is_empty() {
+            let presence = discord::Presence::start(&self.config.discord_application);
+
+            presence.set(discord::Activity::new("In the menu", ""));
+            self.presence = Some(presence);
+        }
+    }
+
+    /// Play the next of the demos bundled with the game, if there are any.
+    fn play_demo(&mut self) -> Option<SceneChange> {
+        let demos = game::recording::demos(&self.asset_manager);
+
+        if demos.is_empty() {
+            return None;
+        }
+
+        self.replay = Some(demos[self.demos_played % demos.len()].clone());
+        self.demos_played += 1;
+        self.demo = true;
+
+        Some(SceneChange::to(Screen::Play))
+    }
+
+    /// Get the replay picked on the menu ready to be watched. Returns false if it can't be read, in
+    /// which case the menu stays.
+    fn pick_replay(&mut self) -> bool {
+        let path = match self.menu.take_replay() {
+            Some(path) => path,
+            None => return true,
+        };
+
+        match Recording::load(&path) {
+            Ok(replay) => {
+                self.replay = Some(replay);
+
+                true
+            }
+            Err(error) => {
+                self.errors.show(&error);
+
+                false
+            }
+        }
+    }
+
+    /// Write down the recording of the run so far, if there is one.
+    pub fn save_recording(&self, ctx: &Context) {
+        let game = self.game.lock().unwrap();
+
+        let recording = match game.recording() {
+            Some(recording) if !recording.is_empty() => recording,
+            _ => return,
+        };
+
+        if let Some(path) = &self.record_to {
+            if let Err(error) = recording.save(path) {
+                log::error!("Cannot save the recording: {}", error);
+            }
+        }
+
+        if let Err(error) = recording.save_replay(ctx) {
+            log::error!("Cannot save the replay: {}", error);
+        }
+    }
+
+    /// Keep hearing from the other players online, and tell them how the level is going.
+    fn sync(&mut self, ctx: &mut Context, dt: f32) {
+        if let Some(session) = &mut self.session {
+            let mut game = self.game.lock().unwrap();
+
+            if session.is_lockstep() {
+                game.lockstep_sync(session);
+            } else {
+                game.sync(ctx, session, dt);
+            }
+        }
+    }
+
+    /// Leave a level that ran into an error for the menu, instead of taking the whole game down.
+    fn leave_level(&mut self, error: GameError) -> SceneChange {
+        self.errors.show(&FerrisError::from(error));
+
+        SceneChange::to(Screen::Menu)
+    }
+
+    /// Build the level picked, the way it's going to be played.
+    fn build_level(&mut self, ctx: &mut Context) -> Result<(), FerrisError> {
+        let replay = self.replay.take();
+        let demo = std::mem::take(&mut self.demo);
+        let daily = match &replay {
+            Some(replay) => replay.daily.clone(),
+            None if self.menu.take_daily() => DailyChallenge::today(self.menu.levels()),
+            None => None,
+        };
+        let level = match (&replay, &daily) {
+            (Some(replay), _) => replay.level.clone(),
+            (None, Some(daily)) => daily.level.clone(),
+            (None, None) => self.menu.level().to_string(),
+        };
+
+        // A new game keeps the difficulty picked for it in the save, and a New Game+ starts
+        // the campaign over. Replays get played the way they were played.
+        let mut save = Save::load(ctx, self.config.save_slot);
+        let difficulty = self.menu.take_difficulty();
+        let new_game_plus = self.menu.take_new_game_plus();
+
+        if let Some(difficulty) = difficulty {
+            save.difficulty = Some(difficulty);
+        }
+
+        if new_game_plus {
+            save.start_new_game_plus();
+        }
+
+        if difficulty.is_some() || new_game_plus {
+            if let Err(error) = save.save(ctx) {
+                self.errors.show(&error);
+            }
+        }
+
+        // Everyone plays the daily challenge the same way.
+        let (difficulty, new_game_plus) = match &replay {
+            Some(replay) => (replay.difficulty, replay.new_game_plus.clone()),
+            None if daily.is_some() => (Difficulty::Normal, None),
+            None => (save.difficulty.unwrap_or_default(), save.new_game_plus()),
+        };
+
+        self.game = game::Game::create(
+            ctx,
+            self.asset_manager.clone(),
+            &level,
+            &self.config,
+            difficulty,
+        )?;
+
+        let mut game = self.game.lock().unwrap();
+
+        // Player two joins in when they claimed a device, except in replays. Playing in
+        // lockstep, player two is the other player online.
+        match self
+            .session
+            .as_ref()
+            .filter(|session| session.is_lockstep())
+        {
+            Some(session) => game.start_lockstep(ctx, session.id()),
+            None if replay.is_none() && self.devices.device(1).is_some() => game.add_partner(ctx),
+            None => {}
+        }
+
+        if let Some(seed) = self.seed {
+            game.reseed(seed);
+        }
+
+        if let Some(daily) = &daily {
+            game.reseed(daily.seed);
+        }
+
+        // Everyone online rolls the dice the way the host does.
+        if let Some(session) = self.session.as_ref().filter(|session| !session.is_host()) {
+            game.reseed(session.seed());
+        }
+
+        // Every run gets recorded to be watched again, apart from the replays. The other players
+        // online don't make it into the recording, so online runs don't get recorded either.
+        let watching = match replay {
+            Some(replay) if demo => game.play_demo(replay),
+            Some(replay) => game.play_back(replay),
+            None if self.session.is_none() => {
+                game.record(&level);
+
+                Ok(())
+            }
+            None => Ok(()),
+        };
+
+        // Remixing the level rolls the dice, so it waits for the run to be seeded.
+        if let Some(new_game_plus) = new_game_plus {
+            game.play_new_game_plus(new_game_plus);
+        }
+
+        if let Some(daily) = daily {
+            game.play_daily(daily);
+        }
+
+        // Nobody is playing the demos.
+        #[cfg(feature = "discord")]
+        match &self.presence {
+            Some(presence) if !demo => game.report_to(presence.clone()),
+            _ => {}
+        }
+
+        watching
+    }
+}
+
+/// Open the scene of `screen`, gone to from the `from` screen.
+pub fn open(
+    ctx: &mut Context,
+    shared: &mut Shared,
+    from: Screen,
+    screen: Screen,
+) -> Box<dyn Scene<Shared>> {
+    let asset_manager = shared.asset_manager.clone();
+
+    match screen {
+        Screen::Menu => {
+            shared.menu.load_progress(ctx, shared.config.save_slot);
+
+            #[cfg(feature = "discord")]
+            if let Some(presence) = &shared.presence {
+                presence.set(discord::Activity::new("In the menu", ""));
+            }
+
+            asset_manager
+                .audio()
+                .music()
+                .play(Menu::MUSIC, MusicController::CROSSFADE);
+
+            Box::new(MenuScene)
+        }
+        Screen::Play => Box::new(PlayScene),
+        Screen::Pause => Box::new(PauseScene(PauseMenu::new())),
+        Screen::Loading => Box::new(LoadingScene {
+            screen: Loading::level(),
+            shown: false,
+        }),
+        Screen::Dead => {
+            let mut death = Death::spawn(ctx, asset_manager.clone());
+            let game = shared.game.lock().unwrap();
+
+            death.open(
+                ctx,
+                &game.high_score_table(),
+                game.stats(),
+                &shared.config.leaderboard,
+            );
+            asset_manager
+                .audio()
+                .music()
+                .stop(MusicController::CROSSFADE);
+
+            Box::new(DeadScene(death))
+        }
+        Screen::Settings => {
+            let mut settings = Settings::create(ctx, asset_manager);
+
+            settings.open(from);
+
+            Box::new(SettingsScene(settings))
+        }
+        Screen::Devices => Box::new(DevicesScene(DeviceScreen::create(ctx, asset_manager))),
+        Screen::Slots => {
+            let mut slots = SaveSlots::create(ctx, asset_manager, shared.menu.levels());
+
+            slots.open(ctx, &shared.config);
+
+            Box::new(SlotsScene(slots))
+        }
+        Screen::Stats => {
+            let mut stats = StatsScreen::create(ctx, asset_manager);
+            let save = Save::load(ctx, shared.config.save_slot);
+
+            stats.open(&save.stats, save.playtime);
+
+            Box::new(StatsScene(stats))
+        }
+        Screen::Trophies => {
+            let mut trophies = TrophiesScreen::create(ctx, asset_manager);
+            let save = Save::load(ctx, shared.config.save_slot);
+
+            trophies.open(&save.achievements);
+
+            Box::new(TrophiesScene(trophies))
+        }
+        Screen::Cutscene => {
+            let mut cutscene = CutsceneScreen::create(ctx, asset_manager);
+
+            if let Some((name, then)) = shared.cutscene.take() {
+                cutscene.open(name, then);
+            }
+
+            Box::new(CutsceneScene(cutscene))
+        }
+        Screen::Results => {
+            let mut results = ResultsScreen::create(ctx, asset_manager);
+
+            if let Some(boss_rush) = shared.game.lock().unwrap().boss_rush_results() {
+                results.open(boss_rush);
+            }
+
+            Box::new(ResultsScene(results))
+        }
+    }
+}
+
+/// Where to go from a screen that says which screen to go to.
+fn go(screen: Option<Screen>) -> Option<SceneChange> {
+    screen.map(SceneChange::to)
+}
+
+struct MenuScene;
+
+impl Scene<Shared> for MenuScene {
+    fn screen(&self) -> Screen {
+        Screen::Menu
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        shared.menu.update(ctx, dt)?;
+
+        // Left alone, the menu plays one of the demos. Not while hosting a game online though,
+        // the other players are waiting to join.
+        if shared.menu.take_demo() && shared.session.is_none() {
+            return Ok(shared.play_demo());
+        }
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        shared.menu.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(shared
+            .menu
+            .key_press(keycode)
+            .filter(|_| shared.pick_replay()))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(shared
+            .menu
+            .gamepad_button_press(button)
+            .filter(|_| shared.pick_replay()))
+    }
+}
+
+/// The level being played, the one in `Shared`.
+struct PlayScene;
+
+impl PlayScene {
+    /// Open the pause menu over the level, if a player asked for it.
+    fn pause(shared: &Shared) -> Option<SceneChange> {
+        shared
+            .game
+            .lock()
+            .unwrap()
+            .take_pause()
+            .then_some(SceneChange::Push(Screen::Pause))
+    }
+}
+
+impl Scene<Shared> for PlayScene {
+    fn screen(&self) -> Screen {
+        Screen::Play
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        let change = shared.game.lock().unwrap().update(ctx, dt);
+
+        shared.sync(ctx, dt);
+
+        // The console can send the player over to another level.
+        let (level, seed) = {
+            let mut game = shared.game.lock().unwrap();
+
+            (game.take_level_request(), game.take_seed_request())
+        };
+
+        if let Some(seed) = seed {
+            shared.seed = seed;
+        }
+
+        if let Some(level) = level {
+            shared.menu.select_level(&level);
+
+            return Ok(Some(SceneChange::to(Screen::Play)));
+        }
+
+        match change {
+            Ok(change) => Ok(go(change).or_else(|| Self::pause(shared))),
+            Err(error) => Ok(Some(shared.leave_level(error))),
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        let change = shared.game.lock().unwrap().draw(ctx);
+
+        match change {
+            Ok(change) => Ok(go(change)),
+            Err(error) => Ok(Some(shared.leave_level(error))),
+        }
+    }
+
+    fn key_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        let mut game = shared.game.lock().unwrap();
+
+        // Any key stops the demo.
+        if game.is_demo() {
+            return Some(SceneChange::to(Screen::Menu));
+        }
+
+        let change = match shared.devices.player(InputDevice::KeyboardMouse) {
+            Some(0) => {
+                if shared.config.debug.camera && keycode == KeyCode::F4 {
+                    let toggled = game.toggle_split_screen(ctx);
+
+                    shared.errors.report(toggled.map_err(FerrisError::from));
+                }
+
+                game.key_press(ctx, keycode)
+            }
+            Some(1) => {
+                game.partner_key(keycode, true);
+
+                None
+            }
+            _ => None,
+        };
+
+        drop(game);
+
+        go(change).or_else(|| Self::pause(shared))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        id: GamepadId,
+    ) -> Option<SceneChange> {
+        let mut game = shared.game.lock().unwrap();
+
+        if game.is_demo() {
+            return Some(SceneChange::to(Screen::Menu));
+        }
+
+        let change = match shared.devices.player(InputDevice::Gamepad(id)) {
+            Some(0) => game.gamepad_button_press(ctx, button),
+            Some(1) => {
+                game.partner_button(button, true);
+
+                None
+            }
+            _ => None,
+        };
+
+        drop(game);
+
+        go(change).or_else(|| Self::pause(shared))
+    }
+
+    fn pointer_down(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        x: f32,
+        y: f32,
+    ) -> Option<SceneChange> {
+        if shared.devices.player(InputDevice::KeyboardMouse) != Some(0) {
+            return None;
+        }
+
+        shared.game.lock().unwrap().pointer_down(ctx, x, y);
+
+        Self::pause(shared)
+    }
+
+    fn text_input(&mut self, shared: &mut Shared, character: char) {
+        if shared.devices.player(InputDevice::KeyboardMouse) == Some(0) {
+            shared.game.lock().unwrap().text_input(character)
+        }
+    }
+}
+
+/// The pause menu, over the level frozen underneath.
+struct PauseScene(PauseMenu);
+
+impl PauseScene {
+    /// Do whatever was picked in the pause menu.
+    fn act(shared: &mut Shared, action: PauseAction) -> SceneChange {
+        match action {
+            PauseAction::Resume => SceneChange::Pop,
+            PauseAction::PhotoMode => {
+                shared.game.lock().unwrap().enter_photo_mode();
+
+                SceneChange::Pop
+            }
+            PauseAction::Restart => SceneChange::to(Screen::Play),
+            PauseAction::Settings => SceneChange::to(Screen::Settings),
+            PauseAction::QuitToMenu => SceneChange::to(Screen::Menu),
+        }
+    }
+}
+
+impl Scene<Shared> for PauseScene {
+    fn screen(&self) -> Screen {
+        Screen::Pause
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        let audio = shared.asset_manager.audio();
+
+        audio
+            .music()
+            .fade(game::Game::PAUSED_MUSIC, game::Game::MUSIC_FADE);
+        audio.stop_loops();
+
+        // The other players online carry on while this one is paused.
+        shared.sync(ctx, dt);
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        shared.game.lock().unwrap().draw_paused(ctx)?;
+        self.0.draw(ctx, &shared.asset_manager)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        MenuInput::from_key(keycode)
+            .and_then(|input| self.0.navigate(input))
+            .map(|action| Self::act(shared, action))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        MenuInput::from_button(button)
+            .and_then(|input| self.0.navigate(input))
+            .map(|action| Self::act(shared, action))
+    }
+
+    /// The pause menu can't be gone through by touch, so touching anywhere resumes.
+    fn pointer_down(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        _x: f32,
+        _y: f32,
+    ) -> Option<SceneChange> {
+        shared.config.touch_controls.then_some(SceneChange::Pop)
+    }
+}
+
+/// The loading screen, showing while the level gets built.
+struct LoadingScene {
+    screen: Loading,
+    /// Whether the loading screen made it onto the window, the level gets built once it did.
+    shown: bool,
+}
+
+impl Scene<Shared> for LoadingScene {
+    fn screen(&self) -> Screen {
+        Screen::Loading
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.screen.update(dt);
+
+        if !self.shown {
+            return Ok(None);
+        }
+
+        // A level that can't be built, or a replay that wouldn't play back the way it was
+        // played, sends the player back to the menu to pick another.
+        match shared.build_level(ctx) {
+            Ok(()) => Ok(Some(SceneChange::Switch(Screen::Play))),
+            Err(error) => {
+                shared.errors.show(&error);
+
+                Ok(Some(SceneChange::to(Screen::Menu)))
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.screen.draw(ctx, &shared.asset_manager)?;
+        self.shown = true;
+
+        Ok(None)
+    }
+
+    fn key_press(&mut self, _: &mut Context, _: &mut Shared, _: KeyCode) -> Option<SceneChange> {
+        None
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _: &mut Context,
+        _: &mut Shared,
+        _: Button,
+        _: GamepadId,
+    ) -> Option<SceneChange> {
+        None
+    }
+}
+
+struct DeadScene(Death);
+
+impl DeadScene {
+    /// Do whatever was picked on the death screen.
+    fn act(shared: &mut Shared, action: DeathAction) -> SceneChange {
+        match action {
+            // Going back to the level as it was, rather than starting a fresh one.
+            DeathAction::Retry => {
+                shared.game.lock().unwrap().retry();
+
+                SceneChange::Switch(Screen::Play)
+            }
+            DeathAction::Restart => SceneChange::to(Screen::Play),
+            DeathAction::MainMenu => SceneChange::to(Screen::Menu),
+        }
+    }
+}
+
+impl Scene<Shared> for DeadScene {
+    fn screen(&self) -> Screen {
+        Screen::Dead
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        self.0
+            .key_press(ctx, keycode)
+            .map(|action| Self::act(shared, action))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        self.0
+            .gamepad_button_press(ctx, button)
+            .map(|action| Self::act(shared, action))
+    }
+
+    fn text_input(&mut self, _shared: &mut Shared, character: char) {
+        self.0.text_input(character)
+    }
+}
+
+struct SettingsScene(Settings);
+
+impl Scene<Shared> for SettingsScene {
+    fn screen(&self) -> Screen {
+        Screen::Settings
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx, &shared.config)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        let config = shared.config.clone();
+        let change = self.0.key_press(keycode, &mut shared.config);
+
+        if config != shared.config {
+            shared.apply_config(ctx, true);
+        }
+
+        go(change)
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        let config = shared.config.clone();
+        let change = self.0.gamepad_button_press(button, &mut shared.config);
+
+        if config != shared.config {
+            shared.apply_config(ctx, true);
+        }
+
+        go(change)
+    }
+}
+
+struct DevicesScene(DeviceScreen);
+
+impl Scene<Shared> for DevicesScene {
+    fn screen(&self) -> Screen {
+        Screen::Devices
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx, &shared.devices)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(self.0.key_press(keycode, &mut shared.devices))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(self.0.gamepad_button_press(button, id, &mut shared.devices))
+    }
+}
+
+struct SlotsScene(SaveSlots);
+
+impl Scene<Shared> for SlotsScene {
+    fn screen(&self) -> Screen {
+        Screen::Slots
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx, &shared.config)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        let config = shared.config.clone();
+        let change = self.0.key_press(ctx, keycode, &mut shared.config);
+
+        if config != shared.config {
+            shared.apply_config(ctx, false);
+        }
+
+        go(change)
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        ctx: &mut Context,
+        shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        let config = shared.config.clone();
+        let change = self.0.gamepad_button_press(ctx, button, &mut shared.config);
+
+        if config != shared.config {
+            shared.apply_config(ctx, false);
+        }
+
+        go(change)
+    }
+
+    fn text_input(&mut self, _shared: &mut Shared, character: char) {
+        self.0.text_input(character)
+    }
+}
+
+struct StatsScene(StatsScreen);
+
+impl Scene<Shared> for StatsScene {
+    fn screen(&self) -> Screen {
+        Screen::Stats
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _: &mut Context,
+        _: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(self.0.key_press(keycode))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(self.0.gamepad_button_press(button))
+    }
+}
+
+struct TrophiesScene(TrophiesScreen);
+
+impl Scene<Shared> for TrophiesScene {
+    fn screen(&self) -> Screen {
+        Screen::Trophies
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _: &mut Context,
+        _: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(self.0.key_press(keycode))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(self.0.gamepad_button_press(button))
+    }
+}
+
+struct CutsceneScene(CutsceneScreen);
+
+impl Scene<Shared> for CutsceneScene {
+    fn screen(&self) -> Screen {
+        Screen::Cutscene
+    }
+
+    fn update(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut Shared,
+        dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        Ok(go(self.0.update(dt)))
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _: &mut Context,
+        _: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(self.0.key_press(keycode))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(self.0.gamepad_button_press(button))
+    }
+}
+
+struct ResultsScene(ResultsScreen);
+
+impl Scene<Shared> for ResultsScene {
+    fn screen(&self) -> Screen {
+        Screen::Results
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        _shared: &mut Shared,
+        _dt: f32,
+    ) -> GameResult<Option<SceneChange>> {
+        self.0.update(ctx)?;
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Option<SceneChange>> {
+        self.0.draw(ctx)?;
+
+        Ok(None)
+    }
+
+    fn key_press(
+        &mut self,
+        _: &mut Context,
+        _: &mut Shared,
+        keycode: KeyCode,
+    ) -> Option<SceneChange> {
+        go(self.0.key_press(keycode))
+    }
+
+    fn gamepad_button_press(
+        &mut self,
+        _ctx: &mut Context,
+        _shared: &mut Shared,
+        button: Button,
+        _id: GamepadId,
+    ) -> Option<SceneChange> {
+        go(self.0.gamepad_button_press(button))
+    }
+}
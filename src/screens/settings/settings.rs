@@ -0,0 +1,572 @@
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{
+    bindings::{button_name, key_name, Action, Bindings},
+    config::{Config, MIN_GAME_SPEED, RESOLUTIONS},
+    display,
+    display::DisplayMode,
+    frame_rate::FPS_LIMITS,
+    i18n::Localization,
+    menu::MenuInput,
+    palette::ColorBlindMode,
+    utils::AssetManager,
+    Screen,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Setting {
+    Language,
+    Volume,
+    MusicVolume,
+    SfxVolume,
+    Resolution,
+    Display,
+    Letterbox,
+    Vsync,
+    FpsLimit,
+    ShowFps,
+    Speedrun,
+    CrtFilter,
+    TouchControls,
+    #[cfg(feature = "discord")]
+    DiscordPresence,
+    Accessibility,
+    KeyBindings,
+    ScreenShake,
+    Flashing,
+    ScreenEffects,
+    GameSpeed,
+    HighContrast,
+    ColorBlind,
+    ToggleFire,
+    ToggleJetpack,
+    Back,
+}
+
+impl Setting {
+    /// The settings on the first page.
+    const ALL: &'static [Setting] = &[
+        Setting::Language,
+        Setting::Volume,
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::Resolution,
+        Setting::Display,
+        Setting::Letterbox,
+        Setting::Vsync,
+        Setting::FpsLimit,
+        Setting::ShowFps,
+        Setting::Speedrun,
+        Setting::CrtFilter,
+        Setting::TouchControls,
+        #[cfg(feature = "discord")]
+        Setting::DiscordPresence,
+        Setting::Accessibility,
+        Setting::KeyBindings,
+        Setting::Back,
+    ];
+
+    /// The settings on the accessibility page.
+    const ACCESSIBILITY: [Setting; 9] = [
+        Setting::ScreenShake,
+        Setting::Flashing,
+        Setting::ScreenEffects,
+        Setting::GameSpeed,
+        Setting::HighContrast,
+        Setting::ColorBlind,
+        Setting::ToggleFire,
+        Setting::ToggleJetpack,
+        Setting::Back,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Setting::Language => "LANGUAGE",
+            Setting::Volume => "MASTER VOLUME",
+            Setting::MusicVolume => "MUSIC VOLUME",
+            Setting::SfxVolume => "SFX VOLUME",
+            Setting::Resolution => "RESOLUTION",
+            Setting::Display => "WINDOW MODE",
+            Setting::Letterbox => "SCALING",
+            // Vsync can only be picked as the window gets made.
+            Setting::Vsync => "VSYNC (ON RESTART)",
+            Setting::FpsLimit => "FPS LIMIT",
+            Setting::ShowFps => "SHOW FPS",
+            Setting::Speedrun => "SPEEDRUN MODE",
+            Setting::CrtFilter => "CRT FILTER",
+            Setting::TouchControls => "TOUCH CONTROLS",
+            #[cfg(feature = "discord")]
+            Setting::DiscordPresence => "DISCORD PRESENCE",
+            Setting::Accessibility => "ACCESSIBILITY",
+            Setting::KeyBindings => "KEY BINDINGS",
+            Setting::ScreenShake => "SCREEN SHAKE",
+            Setting::Flashing => "FLASHING EFFECTS",
+            Setting::ScreenEffects => "SCREEN EFFECTS",
+            Setting::GameSpeed => "GAME SPEED",
+            Setting::HighContrast => "HIGH CONTRAST",
+            Setting::ColorBlind => "COLOR BLIND MODE",
+            Setting::ToggleFire => "AUTO FIRE",
+            Setting::ToggleJetpack => "JETPACK ASSIST",
+            Setting::Back => "BACK",
+        }
+    }
+
+    /// The current value of the setting, as shown next to its label.
+    fn value(&self, config: &Config, localization: &Localization) -> String {
+        match self {
+            Setting::Language => format!("< {} >", localization.language().name),
+            Setting::Volume => format!("< {:.0}% >", config.volume * 100.0),
+            Setting::MusicVolume => format!("< {:.0}% >", config.music_volume * 100.0),
+            Setting::SfxVolume => format!("< {:.0}% >", config.sfx_volume * 100.0),
+            Setting::Resolution => {
+                format!("< {}x{} >", config.resolution.0, config.resolution.1)
+            }
+            Setting::Display => format!("< {:?} >", config.display).to_uppercase(),
+            Setting::Letterbox => String::from(if config.letterbox {
+                "< LETTERBOX >"
+            } else {
+                "< FIT >"
+            }),
+            Setting::Vsync => on_off(config.vsync),
+            Setting::FpsLimit => match config.fps_limit {
+                0 => String::from("< OFF >"),
+                limit => format!("< {} >", limit),
+            },
+            Setting::ShowFps => on_off(config.show_fps),
+            Setting::Speedrun => on_off(config.speedrun),
+            Setting::CrtFilter => on_off(config.crt_filter),
+            Setting::TouchControls => on_off(config.touch_controls),
+            #[cfg(feature = "discord")]
+            Setting::DiscordPresence => on_off(config.discord_presence),
+            Setting::ScreenShake => format!("< {:.0}% >", config.screen_shake * 100.0),
+            Setting::Flashing => on_off(config.flashing),
+            Setting::ScreenEffects => on_off(config.screen_effects),
+            Setting::GameSpeed => format!("< {:.0}% >", config.game_speed * 100.0),
+            Setting::HighContrast => on_off(config.high_contrast),
+            Setting::ColorBlind => format!("< {:?} >", config.color_blind).to_uppercase(),
+            Setting::ToggleFire => on_off(config.toggle_fire),
+            Setting::ToggleJetpack => on_off(config.toggle_jetpack),
+            Setting::Accessibility | Setting::KeyBindings | Setting::Back => String::new(),
+        }
+    }
+
+    /// Change the setting by one step to the left (-1) or right (1).
+    fn adjust(&self, config: &mut Config, step: i32, localization: &Localization) {
+        match self {
+            Setting::Language => {
+                let languages = localization.languages();
+                let current = languages
+                    .iter()
+                    .position(|language| language.code == config.language)
+                    .unwrap_or(0);
+
+                config.language = languages[cycle(current, step, languages.len())]
+                    .code
+                    .clone();
+            }
+            Setting::Volume => config.volume = volume_step(config.volume, step),
+            Setting::MusicVolume => config.music_volume = volume_step(config.music_volume, step),
+            Setting::SfxVolume => config.sfx_volume = volume_step(config.sfx_volume, step),
+            Setting::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|resolution| *resolution == config.resolution)
+                    .unwrap_or(0);
+
+                config.resolution = RESOLUTIONS[cycle(current, step, RESOLUTIONS.len())];
+            }
+            Setting::Display => {
+                let current = DisplayMode::ALL
+                    .iter()
+                    .position(|display| *display == config.display)
+                    .unwrap_or(0);
+
+                config.display = DisplayMode::ALL[cycle(current, step, DisplayMode::ALL.len())];
+            }
+            Setting::Letterbox => config.letterbox = !config.letterbox,
+            Setting::Vsync => config.vsync = !config.vsync,
+            Setting::FpsLimit => {
+                let current = FPS_LIMITS
+                    .iter()
+                    .position(|limit| *limit == config.fps_limit)
+                    .unwrap_or(0);
+
+                config.fps_limit = FPS_LIMITS[cycle(current, step, FPS_LIMITS.len())];
+            }
+            Setting::ShowFps => config.show_fps = !config.show_fps,
+            Setting::Speedrun => config.speedrun = !config.speedrun,
+            Setting::TouchControls => config.touch_controls = !config.touch_controls,
+            #[cfg(feature = "discord")]
+            Setting::DiscordPresence => config.discord_presence = !config.discord_presence,
+            Setting::ScreenEffects => config.screen_effects = !config.screen_effects,
+            Setting::Flashing => config.flashing = !config.flashing,
+            Setting::HighContrast => config.high_contrast = !config.high_contrast,
+            Setting::CrtFilter => config.crt_filter = !config.crt_filter,
+            Setting::ToggleFire => config.toggle_fire = !config.toggle_fire,
+            Setting::ToggleJetpack => config.toggle_jetpack = !config.toggle_jetpack,
+            Setting::ScreenShake => {
+                config.screen_shake = (config.screen_shake + step as f32 * 0.25).clamp(0.0, 2.0);
+            }
+            Setting::GameSpeed => {
+                config.game_speed =
+                    (config.game_speed + step as f32 * 0.1).clamp(MIN_GAME_SPEED, 1.0);
+            }
+            Setting::ColorBlind => {
+                let current = ColorBlindMode::ALL
+                    .iter()
+                    .position(|mode| *mode == config.color_blind)
+                    .unwrap_or(0);
+
+                config.color_blind =
+                    ColorBlindMode::ALL[cycle(current, step, ColorBlindMode::ALL.len())];
+            }
+            Setting::Accessibility | Setting::KeyBindings | Setting::Back => {}
+        }
+    }
+}
+
+/// A page of the settings screen.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Page {
+    Settings,
+    Accessibility,
+    Bindings,
+}
+
+impl Page {
+    /// The settings on the page, none for the key bindings.
+    fn settings(&self) -> &'static [Setting] {
+        match self {
+            Page::Settings => Setting::ALL,
+            Page::Accessibility => &Setting::ACCESSIBILITY,
+            Page::Bindings => &[],
+        }
+    }
+}
+
+fn on_off(on: bool) -> String {
+    if on {
+        String::from("< ON >")
+    } else {
+        String::from("< OFF >")
+    }
+}
+
+/// Turn a volume up or down by a tenth.
+fn volume_step(volume: f32, step: i32) -> f32 {
+    (volume + step as f32 * 0.1).clamp(0.0, 1.0)
+}
+
+/// Move `index` by `step`, wrapping around to stay within `0..count`.
+fn cycle(index: usize, step: i32, count: usize) -> usize {
+    (index as i32 + step).rem_euclid(count as i32) as usize
+}
+
+/// A row on the key bindings page.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum BindingRow {
+    Action(Action),
+    Reset,
+    Back,
+}
+
+impl BindingRow {
+    fn all() -> Vec<BindingRow> {
+        Action::ALL
+            .iter()
+            .map(|action| BindingRow::Action(*action))
+            .chain([BindingRow::Reset, BindingRow::Back])
+            .collect()
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            BindingRow::Action(action) => action.label(),
+            BindingRow::Reset => "RESET TO DEFAULTS",
+            BindingRow::Back => "BACK",
+        }
+    }
+
+    fn value(&self, bindings: &Bindings) -> String {
+        match self {
+            BindingRow::Action(action) => {
+                let binding = bindings.get(*action);
+
+                format!(
+                    "{} / {}",
+                    key_name::label(binding.key),
+                    button_name::label(binding.button)
+                )
+            }
+            BindingRow::Reset | BindingRow::Back => String::new(),
+        }
+    }
+}
+
+/// The settings screen. Every change is written to the config right away.
+pub struct Settings {
+    asset_manager: Rc<AssetManager>,
+
+    /// The highlighted setting.
+    selected: usize,
+    /// The screen to go back to once done.
+    return_to: Screen,
+
+    /// The page that's open.
+    page: Page,
+    /// The highlighted row on the key bindings page.
+    selected_binding: usize,
+    /// The action waiting for a key or a button to be pressed, to get bound to it.
+    rebinding: Option<Action>,
+}
+
+impl Settings {
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self {
+            asset_manager,
+
+            selected: 0,
+            return_to: Screen::Menu,
+
+            page: Page::Settings,
+            selected_binding: 0,
+            rebinding: None,
+        }
+    }
+
+    /// Get the settings ready to be shown, coming from the provided screen.
+    pub fn open(&mut self, from: Screen) {
+        self.selected = 0;
+        self.return_to = from;
+
+        self.page = Page::Settings;
+        self.rebinding = None;
+    }
+
+    /// The label and value of every row on the open page, in the language picked, and the
+    /// highlighted one.
+    fn rows(&self, config: &Config) -> (Vec<(String, String)>, usize) {
+        let localization = self.asset_manager.localization();
+        let text = |english: &str| localization.text(english).to_string();
+
+        if self.page == Page::Bindings {
+            let rows = BindingRow::all()
+                .iter()
+                .map(|row| match row {
+                    BindingRow::Action(action) if self.rebinding == Some(*action) => {
+                        (text(row.label()), text("PRESS A KEY OR BUTTON"))
+                    }
+                    _ => (text(row.label()), row.value(&config.bindings)),
+                })
+                .collect();
+
+            (rows, self.selected_binding)
+        } else {
+            let rows = self
+                .page
+                .settings()
+                .iter()
+                .map(|setting| {
+                    (
+                        text(setting.label()),
+                        text(&setting.value(config, &localization)),
+                    )
+                })
+                .collect();
+
+            (rows, self.selected)
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, config: &Config) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+
+        let consolas = self.asset_manager.text_font();
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let (rows, selected) = self.rows(config);
+
+        let title = Text::new(
+            TextFragment::new(self.asset_manager.text(match self.page {
+                Page::Settings => "SETTINGS",
+                Page::Accessibility => "ACCESSIBILITY",
+                Page::Bindings => "KEY BINDINGS",
+            }))
+            .font(consolas)
+            .scale(Scale::uniform(50.0)),
+        );
+
+        // Only as many rows as fit under the title get shown, scrolling along with the selection.
+        let visible = (((height - 120.0) / 50.0) as usize).clamp(1, rows.len());
+        let first = (selected + 1).saturating_sub(visible);
+
+        let title_width = title.width(ctx) as f32;
+        let top = (height - visible as f32 * 50.0) / 2.0 + 40.0;
+
+        graphics::draw(
+            ctx,
+            &title,
+            DrawParam::default().dest(Point2::new((width - title_width) / 2.0, top - 80.0)),
+        )?;
+
+        for (id, (label, value)) in rows.into_iter().enumerate().skip(first).take(visible) {
+            let position = Point2::new(width / 2.0 - 220.0, top + (id - first) as f32 * 50.0);
+            let selected = id == selected;
+
+            let row = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(position.x, position.y, 440.0, 40.0),
+                if selected {
+                    Color::from_rgba(36, 36, 36, 230)
+                } else {
+                    Color::from_rgba(36, 36, 36, 128)
+                },
+            )?;
+
+            let color = if selected {
+                Color::from_rgb(247, 76, 0)
+            } else {
+                graphics::WHITE
+            };
+
+            let label = Text::new(
+                TextFragment::new(label)
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(color),
+            );
+
+            let value = Text::new(
+                TextFragment::new(value)
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(color),
+            );
+
+            let value_width = value.width(ctx) as f32;
+
+            graphics::draw(ctx, &row, DrawParam::default())?;
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(Point2::new(position.x + 20.0, position.y + 10.0)),
+            )?;
+            graphics::draw(
+                ctx,
+                &value,
+                DrawParam::default().dest(Point2::new(
+                    position.x + 420.0 - value_width,
+                    position.y + 10.0,
+                )),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(&mut self, keycode: KeyCode, config: &mut Config) -> Option<Screen> {
+        if let Some(action) = self.rebinding {
+            // Escape gives up on rebinding, anything else that can't be bound gets ignored.
+            if keycode == KeyCode::Escape || config.bindings.bind_key(action, keycode) {
+                self.rebinding = None;
+            }
+
+            return None;
+        }
+
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input, config))
+    }
+
+    pub fn gamepad_button_press(&mut self, button: Button, config: &mut Config) -> Option<Screen> {
+        if let Some(action) = self.rebinding {
+            if button == Button::Start || config.bindings.bind_button(action, button) {
+                self.rebinding = None;
+            }
+
+            return None;
+        }
+
+        MenuInput::from_button(button).and_then(|input| self.navigate(input, config))
+    }
+
+    fn navigate(&mut self, input: MenuInput, config: &mut Config) -> Option<Screen> {
+        if self.page == Page::Bindings {
+            self.navigate_bindings(input, config);
+
+            return None;
+        }
+
+        let settings = self.page.settings();
+        let count = settings.len();
+        let setting = settings[self.selected];
+        let asset_manager = Rc::clone(&self.asset_manager);
+        let localization = asset_manager.localization();
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Left => setting.adjust(config, -1, &localization),
+            MenuInput::Right => setting.adjust(config, 1, &localization),
+            MenuInput::Select => match setting {
+                Setting::Back => return self.back(),
+                Setting::Accessibility => {
+                    self.page = Page::Accessibility;
+                    self.selected = 0;
+                }
+                Setting::KeyBindings => {
+                    self.page = Page::Bindings;
+                    self.selected_binding = 0;
+                }
+                _ => setting.adjust(config, 1, &localization),
+            },
+            MenuInput::Back => return self.back(),
+        }
+
+        None
+    }
+
+    /// Go back a page, or leave the settings from the first one.
+    fn back(&mut self) -> Option<Screen> {
+        if self.page == Page::Settings {
+            return Some(self.return_to);
+        }
+
+        self.selected = Setting::ALL
+            .iter()
+            .position(|setting| *setting == Setting::Accessibility)
+            .unwrap_or(0);
+        self.page = Page::Settings;
+
+        None
+    }
+
+    fn navigate_bindings(&mut self, input: MenuInput, config: &mut Config) {
+        let rows = BindingRow::all();
+        let count = rows.len();
+
+        match input {
+            MenuInput::Up => self.selected_binding = (self.selected_binding + count - 1) % count,
+            MenuInput::Down => self.selected_binding = (self.selected_binding + 1) % count,
+            MenuInput::Left | MenuInput::Right => {}
+            MenuInput::Select => match rows[self.selected_binding] {
+                BindingRow::Action(action) => self.rebinding = Some(action),
+                BindingRow::Reset => config.bindings = Bindings::default(),
+                BindingRow::Back => self.page = Page::Settings,
+            },
+            MenuInput::Back => self.page = Page::Settings,
+        }
+    }
+}
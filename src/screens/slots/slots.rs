@@ -0,0 +1,322 @@
+//! Picking the save to play with.
+//!
+//! Every slot shows how far its save got. ENTER (A) plays with the highlighted slot, DELETE (X)
+//! empties it, C (Y) copies it over to the first empty slot and N names it.
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{
+    config::Config, display, error::FerrisResult, menu::MenuInput, save::Save, utils::AssetManager,
+    Screen,
+};
+
+pub struct SaveSlots {
+    asset_manager: Rc<AssetManager>,
+    /// The ids of all of the maps that can be played.
+    levels: Vec<String>,
+
+    /// What's in every slot.
+    saves: Vec<Option<Save>>,
+    /// The highlighted row, the slots followed by BACK.
+    selected: usize,
+    /// The name being typed in for the highlighted slot.
+    renaming: Option<String>,
+}
+
+impl SaveSlots {
+    /// The most characters a name can have.
+    const NAME_LENGTH: usize = 12;
+
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>, levels: &[String]) -> Self {
+        Self {
+            asset_manager,
+            levels: levels.to_vec(),
+
+            saves: vec![],
+            selected: 0,
+            renaming: None,
+        }
+    }
+
+    /// Show the slots as they are on disk right now.
+    pub fn open(&mut self, ctx: &Context, config: &Config) {
+        self.reload(ctx);
+        self.selected = config.save_slot;
+        self.renaming = None;
+    }
+
+    fn reload(&mut self, ctx: &Context) {
+        self.saves = (0..Save::SLOTS).map(|slot| Save::find(ctx, slot)).collect();
+    }
+
+    /// The name and the progress of the slot, as shown on its row.
+    fn describe(&self, slot: usize) -> (String, String) {
+        let name = match (&self.renaming, &self.saves[slot]) {
+            (Some(name), _) if slot == self.selected => format!("{}_", name),
+            (_, Some(save)) if !save.name.is_empty() => save.name.clone(),
+            _ => self
+                .asset_manager
+                .text_with("SLOT {slot}", &[("slot", &(slot + 1))]),
+        };
+
+        let progress = match &self.saves[slot] {
+            Some(save) => {
+                let playtime = save.playtime as u32;
+                let reached = match save.level_reached(&self.levels) {
+                    Some(level) => self
+                        .asset_manager
+                        .text_with("LEVEL {level}", &[("level", &level)]),
+                    None => self.asset_manager.text("ALL DONE"),
+                };
+
+                let difficulty = save.difficulty.unwrap_or_default();
+
+                format!(
+                    "{}  {}:{:02}:{:02}  {:.0}%  {}",
+                    reached,
+                    playtime / 3600,
+                    playtime / 60 % 60,
+                    playtime % 60,
+                    save.completion(&self.levels) * 100.0,
+                    self.asset_manager
+                        .text(&format!("{:?}", difficulty).to_uppercase())
+                )
+            }
+            None => self.asset_manager.text("EMPTY"),
+        };
+
+        (name, progress)
+    }
+
+    pub fn draw(&self, ctx: &mut Context, config: &Config) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+        let consolas = self.asset_manager.text_font();
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let title = Text::new(
+            TextFragment::new(self.asset_manager.text("SAVE SLOTS"))
+                .font(consolas)
+                .scale(Scale::uniform(50.0)),
+        );
+        let title_width = title.width(ctx) as f32;
+        let top = height / 2.0 - ((Save::SLOTS + 1) as f32 * 50.0) / 2.0;
+
+        graphics::draw(
+            ctx,
+            &title,
+            DrawParam::default().dest(Point2::new((width - title_width) / 2.0, top - 80.0)),
+        )?;
+
+        for row in 0..=Save::SLOTS {
+            let (label, value) = if row < Save::SLOTS {
+                let (name, progress) = self.describe(row);
+                let current = if row == config.save_slot { "* " } else { "" };
+
+                (format!("{}{}", current, name), progress)
+            } else {
+                (self.asset_manager.text("BACK"), String::new())
+            };
+
+            let position = Point2::new(width / 2.0 - 300.0, top + row as f32 * 50.0);
+            let selected = row == self.selected;
+
+            let background = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(position.x, position.y, 600.0, 40.0),
+                if selected {
+                    Color::from_rgba(36, 36, 36, 230)
+                } else {
+                    Color::from_rgba(36, 36, 36, 128)
+                },
+            )?;
+
+            let color = if selected {
+                Color::from_rgb(247, 76, 0)
+            } else {
+                graphics::WHITE
+            };
+
+            let label = Text::new(
+                TextFragment::new(label)
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(color),
+            );
+            let value = Text::new(
+                TextFragment::new(value)
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(color),
+            );
+            let value_width = value.width(ctx) as f32;
+
+            graphics::draw(ctx, &background, DrawParam::default())?;
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(Point2::new(position.x + 20.0, position.y + 10.0)),
+            )?;
+            graphics::draw(
+                ctx,
+                &value,
+                DrawParam::default().dest(Point2::new(
+                    position.x + 580.0 - value_width,
+                    position.y + 10.0,
+                )),
+            )?;
+        }
+
+        let hint = Text::new(
+            TextFragment::new(
+                self.asset_manager
+                    .text("ENTER PLAY   DELETE EMPTY   C COPY   N NAME"),
+            )
+            .font(consolas)
+            .scale(Scale::uniform(16.0))
+            .color(Color::from_rgb(160, 160, 160)),
+        );
+        let hint_width = hint.width(ctx) as f32;
+
+        graphics::draw(
+            ctx,
+            &hint,
+            DrawParam::default().dest(Point2::new(
+                (width - hint_width) / 2.0,
+                top + (Save::SLOTS + 1) as f32 * 50.0 + 20.0,
+            )),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(
+        &mut self,
+        ctx: &Context,
+        keycode: KeyCode,
+        config: &mut Config,
+    ) -> Option<Screen> {
+        if let Some(name) = &mut self.renaming {
+            match keycode {
+                KeyCode::Return => self.rename(ctx),
+                KeyCode::Escape => self.renaming = None,
+                KeyCode::Back => {
+                    name.pop();
+                }
+                _ => {}
+            }
+
+            return None;
+        }
+
+        match keycode {
+            KeyCode::Delete => self.delete(ctx),
+            KeyCode::C => self.copy(ctx),
+            KeyCode::N if self.selected < Save::SLOTS => {
+                self.renaming = Some(String::new());
+            }
+            _ => {
+                return MenuInput::from_key(keycode)
+                    .and_then(|input| self.navigate(ctx, input, config));
+            }
+        }
+
+        None
+    }
+
+    pub fn gamepad_button_press(
+        &mut self,
+        ctx: &Context,
+        button: Button,
+        config: &mut Config,
+    ) -> Option<Screen> {
+        match button {
+            Button::West => self.delete(ctx),
+            Button::North => self.copy(ctx),
+            _ => {
+                return MenuInput::from_button(button)
+                    .and_then(|input| self.navigate(ctx, input, config));
+            }
+        }
+
+        None
+    }
+
+    pub fn text_input(&mut self, character: char) {
+        if let Some(name) = &mut self.renaming {
+            if (character.is_alphanumeric() || character == ' ')
+                && name.chars().count() < Self::NAME_LENGTH
+            {
+                name.extend(character.to_uppercase());
+            }
+        }
+    }
+
+    fn navigate(&mut self, ctx: &Context, input: MenuInput, config: &mut Config) -> Option<Screen> {
+        let count = Save::SLOTS + 1;
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Select if self.selected < Save::SLOTS => {
+                config.save_slot = self.selected;
+
+                // Playing with an empty slot starts it off right away, so it shows up as used.
+                if self.saves[self.selected].is_none() {
+                    report(Save::load(ctx, self.selected).save(ctx));
+                }
+
+                return Some(Screen::Menu);
+            }
+            MenuInput::Select | MenuInput::Back => return Some(Screen::Menu),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
+    }
+
+    fn delete(&mut self, ctx: &Context) {
+        if self.selected < Save::SLOTS {
+            report(Save::delete(ctx, self.selected));
+            self.reload(ctx);
+        }
+    }
+
+    /// Copy the highlighted slot over to the first empty one.
+    fn copy(&mut self, ctx: &Context) {
+        let empty = self.saves.iter().position(Option::is_none);
+
+        if let (Some(Some(save)), Some(empty)) = (self.saves.get(self.selected), empty) {
+            report(save.save_to(ctx, empty));
+            self.reload(ctx);
+        }
+    }
+
+    fn rename(&mut self, ctx: &Context) {
+        if let Some(name) = self.renaming.take() {
+            let mut save = Save::load(ctx, self.selected);
+
+            save.name = name.trim().to_string();
+            report(save.save(ctx));
+            self.reload(ctx);
+        }
+    }
+}
+
+fn report(result: FerrisResult<()>) {
+    if let Err(error) = result {
+        log::error!("Cannot change the save slot: {}", error);
+    }
+}
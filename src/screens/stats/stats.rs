@@ -0,0 +1,158 @@
+//! The lifetime statistics of the save being played.
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{display, menu::MenuInput, save::Stats, utils::AssetManager, Screen};
+
+pub struct StatsScreen {
+    asset_manager: Rc<AssetManager>,
+
+    /// The statistic at the top of the list, the list scrolls once it doesn't fit.
+    scroll: usize,
+    /// The statistics as they're shown, with their labels.
+    rows: Vec<(String, String)>,
+}
+
+impl StatsScreen {
+    /// How many statistics are shown at once.
+    const VISIBLE: usize = 8;
+
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self {
+            asset_manager,
+
+            scroll: 0,
+            rows: vec![],
+        }
+    }
+
+    /// Show the statistics of a save.
+    pub fn open(&mut self, stats: &Stats, playtime: f32) {
+        let text = |english: &str| self.asset_manager.text(english);
+        let playtime = playtime as u32;
+        let mut rows = vec![
+            (
+                text("TIME PLAYED"),
+                format!(
+                    "{}:{:02}:{:02}",
+                    playtime / 3600,
+                    playtime / 60 % 60,
+                    playtime % 60
+                ),
+            ),
+            (text("SHOTS FIRED"), stats.shots_fired.to_string()),
+            (
+                text("ACCURACY"),
+                format!("{:.0}%", stats.accuracy() * 100.0),
+            ),
+            (text("DEATHS"), stats.deaths.to_string()),
+            (
+                text("DISTANCE TRAVELED"),
+                // Ferris is about a meter wide, give or take.
+                format!("{:.0} M", stats.distance / 100.0),
+            ),
+            (text("BARRELS EXPLODED"), stats.barrels_exploded.to_string()),
+        ];
+
+        rows.extend(stats.kills.iter().map(|(kind, kills)| {
+            let kind = kind.to_uppercase();
+
+            (
+                self.asset_manager
+                    .text_with("{kind} KILLS", &[("kind", &text(&kind))]),
+                kills.to_string(),
+            )
+        }));
+
+        self.rows = rows;
+        self.scroll = 0;
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+        let consolas = self.asset_manager.text_font();
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let title = Text::new(
+            TextFragment::new(self.asset_manager.text("STATISTICS"))
+                .font(consolas)
+                .scale(Scale::uniform(50.0)),
+        );
+        let title_width = title.width(ctx) as f32;
+        let top = height / 2.0 - (Self::VISIBLE as f32 * 40.0) / 2.0;
+
+        graphics::draw(
+            ctx,
+            &title,
+            DrawParam::default().dest(Point2::new((width - title_width) / 2.0, top - 80.0)),
+        )?;
+
+        for (id, (label, value)) in self
+            .rows
+            .iter()
+            .skip(self.scroll)
+            .take(Self::VISIBLE)
+            .enumerate()
+        {
+            let y = top + id as f32 * 40.0;
+            let label = Text::new(
+                TextFragment::new(label.as_str())
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0)),
+            );
+            let value = Text::new(
+                TextFragment::new(value.as_str())
+                    .font(consolas)
+                    .scale(Scale::uniform(20.0))
+                    .color(Color::from_rgb(247, 76, 0)),
+            );
+            let value_width = value.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &label,
+                DrawParam::default().dest(Point2::new(width / 2.0 - 250.0, y)),
+            )?;
+            graphics::draw(
+                ctx,
+                &value,
+                DrawParam::default().dest(Point2::new(width / 2.0 + 250.0 - value_width, y)),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(&mut self, keycode: KeyCode) -> Option<Screen> {
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input))
+    }
+
+    pub fn gamepad_button_press(&mut self, button: Button) -> Option<Screen> {
+        MenuInput::from_button(button).and_then(|input| self.navigate(input))
+    }
+
+    fn navigate(&mut self, input: MenuInput) -> Option<Screen> {
+        let last = self.rows.len().saturating_sub(Self::VISIBLE);
+
+        match input {
+            MenuInput::Up => self.scroll = self.scroll.saturating_sub(1),
+            MenuInput::Down => self.scroll = (self.scroll + 1).min(last),
+            MenuInput::Select | MenuInput::Back => return Some(Screen::Menu),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
+    }
+}
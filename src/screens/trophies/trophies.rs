@@ -0,0 +1,186 @@
+//! The achievements of the save being played, unlocked or not.
+
+use std::rc::Rc;
+
+use ggez::{
+    event::{Button, KeyCode},
+    graphics::{self, Color, DrawParam, Scale, Text, TextFragment},
+    nalgebra::Point2,
+    Context, GameResult,
+};
+
+use crate::{
+    achievements::{Achievements, Progress},
+    display,
+    menu::MenuInput,
+    utils::AssetManager,
+    Screen,
+};
+
+/// An achievement as it's shown.
+struct Trophy {
+    name: String,
+    description: String,
+    /// How far along it is, or that it's unlocked.
+    progress: String,
+    unlocked: bool,
+}
+
+pub struct TrophiesScreen {
+    asset_manager: Rc<AssetManager>,
+
+    /// The achievement at the top of the list, the list scrolls once it doesn't fit.
+    scroll: usize,
+    trophies: Vec<Trophy>,
+}
+
+impl TrophiesScreen {
+    /// How many achievements are shown at once.
+    const VISIBLE: usize = 6;
+    /// How far apart the achievements are.
+    const ROW: f32 = 56.0;
+
+    pub fn create(_ctx: &mut Context, asset_manager: Rc<AssetManager>) -> Self {
+        Self {
+            asset_manager,
+
+            scroll: 0,
+            trophies: vec![],
+        }
+    }
+
+    /// Show the achievements with how far a save got with them.
+    pub fn open(&mut self, progress: &Progress) {
+        let achievements = Achievements::load(&self.asset_manager);
+
+        self.trophies = achievements
+            .list()
+            .iter()
+            .map(|achievement| {
+                let unlocked = progress.is_unlocked(achievement);
+                let progress = if unlocked {
+                    self.asset_manager.text("UNLOCKED")
+                } else {
+                    format!(
+                        "{}/{}",
+                        progress.count(achievement.counter).min(achievement.goal),
+                        achievement.goal
+                    )
+                };
+
+                Trophy {
+                    name: self.asset_manager.text(&achievement.name).to_uppercase(),
+                    description: self.asset_manager.text(&achievement.description),
+                    progress,
+                    unlocked,
+                }
+            })
+            .collect();
+        self.scroll = 0;
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let (width, height) = display::screen_size(ctx);
+        let consolas = self.asset_manager.text_font();
+        let text = |text: &str, size: f32, color: Color| {
+            Text::new(
+                TextFragment::new(text)
+                    .font(consolas)
+                    .scale(Scale::uniform(size))
+                    .color(color),
+            )
+        };
+
+        graphics::clear(ctx, graphics::BLACK);
+
+        let unlocked = self
+            .trophies
+            .iter()
+            .filter(|trophy| trophy.unlocked)
+            .count();
+        let title = text(&self.asset_manager.text("TROPHIES"), 50.0, graphics::WHITE);
+        let title_width = title.width(ctx) as f32;
+        let count = text(
+            &self.asset_manager.text_with(
+                "{unlocked} OF {count} UNLOCKED",
+                &[("unlocked", &unlocked), ("count", &self.trophies.len())],
+            ),
+            20.0,
+            Color::new(1.0, 1.0, 1.0, 0.7),
+        );
+        let count_width = count.width(ctx) as f32;
+        let top = height / 2.0 - (Self::VISIBLE as f32 * Self::ROW) / 2.0;
+
+        graphics::draw(
+            ctx,
+            &title,
+            DrawParam::default().dest(Point2::new((width - title_width) / 2.0, top - 110.0)),
+        )?;
+        graphics::draw(
+            ctx,
+            &count,
+            DrawParam::default().dest(Point2::new((width - count_width) / 2.0, top - 50.0)),
+        )?;
+
+        for (id, trophy) in self
+            .trophies
+            .iter()
+            .skip(self.scroll)
+            .take(Self::VISIBLE)
+            .enumerate()
+        {
+            let y = top + id as f32 * Self::ROW;
+            // The ones still to get are dimmed.
+            let color = if trophy.unlocked {
+                Color::from_rgb(247, 76, 0)
+            } else {
+                Color::new(1.0, 1.0, 1.0, 0.5)
+            };
+            let progress = text(&trophy.progress, 20.0, color);
+            let progress_width = progress.width(ctx) as f32;
+
+            graphics::draw(
+                ctx,
+                &text(&trophy.name, 20.0, graphics::WHITE),
+                DrawParam::default().dest(Point2::new(width / 2.0 - 250.0, y)),
+            )?;
+            graphics::draw(
+                ctx,
+                &text(&trophy.description, 15.0, Color::new(1.0, 1.0, 1.0, 0.7)),
+                DrawParam::default().dest(Point2::new(width / 2.0 - 250.0, y + 24.0)),
+            )?;
+            graphics::draw(
+                ctx,
+                &progress,
+                DrawParam::default().dest(Point2::new(width / 2.0 + 250.0 - progress_width, y)),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    pub fn key_press(&mut self, keycode: KeyCode) -> Option<Screen> {
+        MenuInput::from_key(keycode).and_then(|input| self.navigate(input))
+    }
+
+    pub fn gamepad_button_press(&mut self, button: Button) -> Option<Screen> {
+        MenuInput::from_button(button).and_then(|input| self.navigate(input))
+    }
+
+    fn navigate(&mut self, input: MenuInput) -> Option<Screen> {
+        let last = self.trophies.len().saturating_sub(Self::VISIBLE);
+
+        match input {
+            MenuInput::Up => self.scroll = self.scroll.saturating_sub(1),
+            MenuInput::Down => self.scroll = (self.scroll + 1).min(last),
+            MenuInput::Select | MenuInput::Back => return Some(Screen::Menu),
+            MenuInput::Left | MenuInput::Right => {}
+        }
+
+        None
+    }
+}
@@ -0,0 +1,153 @@
+//! Pictures of the game, kept in the `screenshots` folder of the user data directory.
+//!
+//! F12 takes a screenshot from any screen. Only grabbing the frame happens during it, turning
+//! that into a PNG and writing it out happen on another thread so the game doesn't wait on it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::SystemTime,
+};
+
+use ggez::{filesystem, graphics, Context, GameResult};
+
+use crate::error::FerrisResult;
+
+pub struct Screenshot {
+    width: u32,
+    height: u32,
+    /// RGBA, a row at a time from the bottom, the way the window gets read.
+    pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Grab what has been drawn onto the window so far this frame.
+    pub fn capture(ctx: &mut Context) -> GameResult<Self> {
+        let image = graphics::screenshot(ctx)?;
+
+        Ok(Self {
+            width: u32::from(image.width()),
+            height: u32::from(image.height()),
+            pixels: image.to_rgba8(ctx)?,
+        })
+    }
+
+    /// Save the screenshot as a PNG named after `name` and when it's taken.
+    pub fn save(&self, ctx: &Context, name: &str) -> FerrisResult<PathBuf> {
+        let path = Self::path(ctx, name);
+
+        self.write(&path)?;
+
+        Ok(path)
+    }
+
+    fn path(ctx: &Context, name: &str) -> PathBuf {
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+
+        screenshots_dir(ctx).join(format!("{}-{}.png", name, time))
+    }
+
+    fn write(&self, path: &Path) -> FerrisResult<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        image::save_buffer(
+            path,
+            &flip(&self.pixels, self.width as usize),
+            self.width,
+            self.height,
+            image::ColorType::RGBA(8),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Takes the screenshots asked for with F12.
+pub struct Screenshots {
+    /// Whether a screenshot is to be taken at the end of the frame.
+    requested: bool,
+    /// Where the screenshots being written tell how it went.
+    sender: Sender<Result<PathBuf, String>>,
+    receiver: Receiver<Result<PathBuf, String>>,
+}
+
+impl Screenshots {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            requested: false,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Take a screenshot of the frame being drawn, once it's done.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Take the screenshot asked for, if there is one, and start writing it out.
+    pub fn capture(&mut self, ctx: &mut Context) -> GameResult {
+        if !std::mem::take(&mut self.requested) {
+            return Ok(());
+        }
+
+        let screenshot = Screenshot::capture(ctx)?;
+        let path = Screenshot::path(ctx, "screenshot");
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            let written = screenshot
+                .write(&path)
+                .map(|_| path)
+                .map_err(|error| error.to_string());
+
+            // The game might be gone by the time this is written.
+            let _ = sender.send(written);
+        });
+
+        Ok(())
+    }
+
+    /// The screenshots that got written since the last time, or why they couldn't be.
+    pub fn finished(&self) -> Vec<Result<PathBuf, String>> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Default for Screenshots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn screenshots_dir(ctx: &Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join("screenshots")
+}
+
+/// Turn RGBA pixels `width` wide upside down.
+fn flip(pixels: &[u8], width: usize) -> Vec<u8> {
+    pixels.chunks(width * 4).rev().flatten().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flip() {
+        let pixels = [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+
+        assert_eq!(
+            flip(&pixels, 2),
+            vec![3, 3, 3, 3, 4, 4, 4, 4, 1, 1, 1, 1, 2, 2, 2, 2]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Covering up the switch from one screen to another.
+//!
+//! Instead of popping over right away, the screen gets covered up first: it fades to black, gets
+//! wiped over or breaks up into bigger and bigger blocks. Once it's fully covered the game switches
+//! over to the next screen, which gets revealed the same way the last one got covered.
+
+use ggez::{
+    conf::NumSamples,
+    graphics::{self, Canvas, Color, DrawParam, FilterMode, Rect},
+    nalgebra::{Point2, Vector2},
+    Context, GameResult,
+};
+
+use crate::{scene::SceneChange, utils::smoothstep};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TransitionKind {
+    /// Fade to black.
+    Fade,
+    /// Sweep a black curtain over from the left.
+    Wipe,
+    /// Break the screen up into blocks growing until nothing can be made out.
+    Pixelate,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum State {
+    Idle,
+    /// Covering up the screen before making the `change`.
+    Covering {
+        change: SceneChange,
+        time: f32,
+    },
+    Revealing {
+        time: f32,
+    },
+}
+
+pub struct Transition {
+    kind: TransitionKind,
+    state: State,
+}
+
+impl Transition {
+    /// How long it takes to cover up the screen, and again to reveal the next one.
+    const DURATION: f32 = 0.25;
+    /// How big the blocks of a fully pixelated screen are.
+    const MAX_BLOCK: f32 = 48.0;
+
+    pub fn new() -> Self {
+        Self {
+            kind: TransitionKind::Fade,
+            state: State::Idle,
+        }
+    }
+
+    /// Start covering up the screen to make the `change`. Ignored while another screen is already
+    /// on its way.
+    pub fn start(&mut self, kind: TransitionKind, change: SceneChange) {
+        if let State::Covering { .. } = self.state {
+            return;
+        }
+
+        self.kind = kind;
+        self.state = State::Covering { change, time: 0.0 };
+    }
+
+    /// Whether the current screen is being covered up, it shouldn't carry on in the meantime.
+    pub fn is_covering(&self) -> bool {
+        matches!(self.state, State::Covering { .. })
+    }
+
+    /// Move the transition along. Returns the change to make once the current screen is fully
+    /// covered.
+    pub fn update(&mut self, dt: f32) -> Option<SceneChange> {
+        match &mut self.state {
+            State::Idle => None,
+            State::Covering { change, time } => {
+                *time += dt;
+
+                if *time < Self::DURATION {
+                    return None;
+                }
+
+                let change = *change;
+
+                self.state = State::Revealing { time: 0.0 };
+
+                Some(change)
+            }
+            State::Revealing { time } => {
+                *time += dt;
+
+                if *time >= Self::DURATION {
+                    self.state = State::Idle;
+                }
+
+                None
+            }
+        }
+    }
+
+    /// How much of the screen is covered, from 0.0 to 1.0.
+    fn coverage(&self) -> f32 {
+        smoothstep(match self.state {
+            State::Idle => 0.0,
+            State::Covering { time, .. } => time / Self::DURATION,
+            State::Revealing { time } => 1.0 - time / Self::DURATION,
+        })
+    }
+
+    /// Draw the transition over whatever has been drawn this frame.
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        let coverage = self.coverage();
+
+        if coverage <= 0.0 {
+            return Ok(());
+        }
+
+        let coordinates = graphics::screen_coordinates(ctx);
+
+        match self.kind {
+            TransitionKind::Fade => {
+                let curtain = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    coordinates,
+                    Color::new(0.0, 0.0, 0.0, coverage),
+                )?;
+
+                graphics::draw(ctx, &curtain, DrawParam::default())
+            }
+            TransitionKind::Wipe => {
+                // The curtain comes in from the left and leaves to the right.
+                let width = coordinates.w * coverage;
+                let x = match self.state {
+                    State::Revealing { .. } => coordinates.x + coordinates.w - width,
+                    _ => coordinates.x,
+                };
+                let curtain = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    Rect::new(x, coordinates.y, width, coordinates.h),
+                    graphics::BLACK,
+                )?;
+
+                graphics::draw(ctx, &curtain, DrawParam::default())
+            }
+            TransitionKind::Pixelate => pixelate(ctx, 1.0 + coverage * (Self::MAX_BLOCK - 1.0)),
+        }
+    }
+}
+
+/// Redraw the frame so far out of blocks `block` pixels wide.
+fn pixelate(ctx: &mut Context, block: f32) -> GameResult {
+    let frame = graphics::screenshot(ctx)?;
+    let coordinates = graphics::screen_coordinates(ctx);
+    let (width, height) = (frame.width() as f32, frame.height() as f32);
+    let small_width = (width / block).max(1.0).floor();
+    let small_height = (height / block).max(1.0).floor();
+
+    let mut small = Canvas::new(
+        ctx,
+        small_width as u16,
+        small_height as u16,
+        NumSamples::One,
+    )?;
+
+    small.set_filter(FilterMode::Nearest);
+
+    graphics::set_canvas(ctx, Some(&small));
+    graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, small_width, small_height))?;
+    graphics::draw(
+        ctx,
+        &frame,
+        DrawParam::default().scale(Vector2::new(small_width / width, small_height / height)),
+    )?;
+
+    graphics::set_canvas(ctx, None);
+    graphics::set_screen_coordinates(ctx, coordinates)?;
+
+    graphics::draw(
+        ctx,
+        &small,
+        DrawParam::default()
+            .dest(Point2::new(coordinates.x, coordinates.y))
+            .scale(Vector2::new(
+                coordinates.w / small_width,
+                coordinates.h / small_height,
+            )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Screen;
+
+    #[test]
+    fn test_transition() {
+        let mut transition = Transition::new();
+
+        transition.start(TransitionKind::Fade, SceneChange::Switch(Screen::Loading));
+        assert!(transition.is_covering());
+
+        // Starting another one on the way doesn't change where it's going.
+        transition.start(TransitionKind::Fade, SceneChange::Switch(Screen::Menu));
+        assert_eq!(transition.update(0.1), None);
+        assert_eq!(
+            transition.update(Transition::DURATION),
+            Some(SceneChange::Switch(Screen::Loading))
+        );
+        assert_eq!(transition.coverage(), 1.0);
+
+        transition.update(Transition::DURATION);
+        assert_eq!(transition.coverage(), 0.0);
+        assert!(!transition.is_covering());
+    }
+}
@@ -1,13 +1,25 @@
-use std::{borrow::Cow, collections::HashMap, error::Error, io::Read, sync::Mutex};
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell},
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    fs,
+    io::{Read, Write},
+    marker::PhantomData,
+    path::Path,
+};
 
 use ggez::{
-    audio::Source,
-    graphics::{self, Color, DrawMode, Font, Image, Mesh},
+    graphics::{self, Color, DrawMode, Font, Image, Mesh, Rect},
     nalgebra::Point2,
-    timer, Context, GameResult,
+    Context, GameResult,
 };
 
 use ggez_goodies::{camera::Camera, nalgebra_glm::Vec2};
+
+use thiserror::Error;
+
+use crate::error::FerrisResult;
 use graphics::DrawParam;
 use nphysics2d::{
     algebra::Velocity2,
@@ -16,118 +28,494 @@ use nphysics2d::{
     object::{BodyPartHandle, ColliderDesc, DefaultBodyHandle, RigidBodyDesc},
 };
 use rand::Rng;
+use serde::Deserialize;
 
-use crate::game::physics::{isometry_to_point, point_to_isometry, ObjectData, Physics};
+use crate::{
+    audio::Audio,
+    game::objects::GameObject,
+    game::physics::{point_to_isometry, ObjectData, Physics},
+    i18n::Localization,
+};
+
+/// Write the file through a temporary one that then takes its place, so a crash halfway through
+/// leaves the old file as it was instead of half a new one. The temporary file is synced to the
+/// disk before it takes the old one's place, or losing power could leave an empty file behind.
+/// Makes the directory if it's missing.
+pub fn write_atomic(path: &Path, contents: &str) -> FerrisResult<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
 
-pub type FerrisResult<T> = Result<T, Box<dyn Error>>;
+    let mut temporary = path.as_os_str().to_owned();
+
+    temporary.push(".tmp");
+
+    let mut file = fs::File::create(&temporary)?;
+
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
 
 pub fn lerp(from: f32, to: f32, dt: f32) -> f32 {
     from + dt * (to - from)
 }
 
-pub fn remap(n: f32, start1: f32, stop1: f32, start2: f32, stop2: f32) -> f32 {
-    ((n - start1) / (stop1 - start1)) * (stop2 - start2) + start2
+/// Frame rate independent exponential smoothing from `from` towards `to`.
+/// The higher the `rate`, the faster the value catches up.
+pub fn damp(from: f32, to: f32, rate: f32, dt: f32) -> f32 {
+    lerp(from, to, 1.0 - (-rate * dt).exp())
 }
 
-#[macro_export]
-macro_rules! play {
-    ($exp:expr) => {
-        let mut sound = $exp
-            .lock()
-            .expect(format!("Cannot load {}.mp3", stringify!($exp)).as_str());
+/// Eases `t` in the range 0.0 to 1.0 with a smooth start and end.
+pub fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
 
-        sound
-            .play()
-            .expect(format!("Cannot play {}.mp3", stringify!($exp)).as_str());
-    };
+    t * t * (3.0 - 2.0 * t)
+}
+
+pub fn remap(n: f32, start1: f32, stop1: f32, start2: f32, stop2: f32) -> f32 {
+    ((n - start1) / (stop1 - start1)) * (stop2 - start2) + start2
 }
 
-enum Asset {
+/// Anything the asset manager holds on to.
+pub enum Asset {
     Image(Image),
     Font(Font),
-    Audio(Mutex<Source>),
     File(String),
+    Sprite(Sprite),
+}
+
+/// The kinds of assets that can be handed out through a handle.
+pub trait AssetKind: Sized {
+    /// What the kind is called in errors.
+    const KIND: &'static str;
+    /// Where the stand-in for a missing asset of the kind is, the asset manager starts out with
+    /// them.
+    const PLACEHOLDER: usize;
+
+    /// The asset as this kind, if it is one. `name` is what the asset was loaded as.
+    fn from_asset(name: &str, asset: &Asset) -> Option<Self>;
+}
+
+impl AssetKind for Image {
+    const KIND: &'static str = "image";
+    const PLACEHOLDER: usize = 0;
+
+    fn from_asset(_name: &str, asset: &Asset) -> Option<Self> {
+        match asset {
+            Asset::Image(image) => Some(image.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl AssetKind for Font {
+    const KIND: &'static str = "font";
+    const PLACEHOLDER: usize = 1;
+
+    fn from_asset(_name: &str, asset: &Asset) -> Option<Self> {
+        match asset {
+            Asset::Font(font) => Some(*font),
+            _ => None,
+        }
+    }
+}
+
+/// The contents of a text file.
+impl AssetKind for String {
+    const KIND: &'static str = "file";
+    const PLACEHOLDER: usize = 2;
+
+    fn from_asset(_name: &str, asset: &Asset) -> Option<Self> {
+        match asset {
+            Asset::File(file) => Some(file.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// A region of an atlas, or a whole image.
+impl AssetKind for Sprite {
+    const KIND: &'static str = "sprite";
+    const PLACEHOLDER: usize = Image::PLACEHOLDER;
+
+    fn from_asset(name: &str, asset: &Asset) -> Option<Self> {
+        match asset {
+            Asset::Sprite(sprite) => Some(sprite.clone()),
+            Asset::Image(image) => Some(Sprite {
+                image: image.clone(),
+                texture: name.to_string(),
+                src: Rect::one(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Points at an asset of a known kind. Handles are only handed out for assets that are there
+/// and of the right kind, so getting the asset through one can't fail, and it skips looking the
+/// name up every time the asset gets used.
+pub struct Handle<T> {
+    id: usize,
+    kind: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({})", self.id)
+    }
+}
+
+/// Why an asset couldn't be handed out.
+#[derive(PartialEq, Debug, Clone, Error)]
+pub enum AssetError {
+    /// Nothing got loaded under the name.
+    #[error("There's no {kind} called {name} in the resources")]
+    Missing { name: String, kind: &'static str },
+    /// Something else than what was asked for got loaded under the name.
+    #[error("{name} isn't a {kind}")]
+    WrongKind { name: String, kind: &'static str },
+}
+
+/// A part of an image to draw. Every image is a sprite of its own, while an atlas packs lots of
+/// sprites into a single image so they can be drawn without switching textures.
+#[derive(Clone)]
+pub struct Sprite {
+    pub image: Image,
+    /// The name of the image the sprite is on. Sprites on the same image can share a batch.
+    pub texture: String,
+    /// Where the sprite is on the image, as a part of the whole image.
+    pub src: Rect,
+}
+
+impl Sprite {
+    /// The size of the sprite in pixels.
+    pub fn size(&self) -> (f32, f32) {
+        (
+            self.image.width() as f32 * self.src.w,
+            self.image.height() as f32 * self.src.h,
+        )
+    }
+}
+
+/// An atlas as described in the resources. Every region is x, y, width and height in pixels.
+#[derive(Deserialize)]
+struct AtlasFile {
+    image: String,
+    regions: BTreeMap<String, [f32; 4]>,
+}
+
+impl AtlasFile {
+    /// Where every region is on an image of the provided size, as a part of the whole image.
+    fn sources(&self, width: f32, height: f32) -> Vec<(String, Rect)> {
+        self.regions
+            .iter()
+            .map(|(name, [x, y, w, h])| {
+                (
+                    name.clone(),
+                    Rect::new(x / width, y / height, w / width, h / height),
+                )
+            })
+            .collect()
+    }
 }
 
 pub struct AssetManager {
-    assets: HashMap<String, Asset>,
+    /// Every asset, with the name it was loaded as. Behind a cell so changed assets can be
+    /// reloaded while the game is running.
+    assets: RefCell<Vec<(String, Asset)>>,
+    /// Where every asset is in `assets`, by its name.
+    names: RefCell<HashMap<String, usize>>,
+    audio: Audio,
+    /// The language the text is shown in, behind a cell so it can be changed in the settings.
+    localization: RefCell<Localization>,
 }
 
 impl AssetManager {
-    pub fn new() -> Self {
+    /// Missing images show up in this color, so they stand out.
+    const MISSING_COLOR: Color = Color {
+        r: 1.0,
+        g: 0.0,
+        b: 1.0,
+        a: 1.0,
+    };
+
+    /// An asset manager with nothing but the stand-ins for missing assets, in the order of their
+    /// `AssetKind::PLACEHOLDER`.
+    pub fn new(ctx: &mut Context) -> FerrisResult<Self> {
+        let asset_manager = Self::empty();
+
+        asset_manager.insert(
+            String::from("<missing image>"),
+            Asset::Image(Image::solid(ctx, 16, Self::MISSING_COLOR)?),
+        );
+        asset_manager.insert(String::from("<missing font>"), Asset::Font(Font::default()));
+        asset_manager.insert(String::from("<missing file>"), Asset::File(String::new()));
+
+        Ok(asset_manager)
+    }
+
+    fn empty() -> Self {
         Self {
-            assets: HashMap::new(),
+            assets: RefCell::new(vec![]),
+            names: RefCell::new(HashMap::new()),
+            audio: Audio::new(),
+            localization: RefCell::new(Localization::default()),
         }
     }
 
-    pub fn load_image(&mut self, ctx: &mut Context, filename: Cow<'_, str>) {
-        self.assets.insert(
-            filename.to_string(),
-            Asset::Image(
-                Image::new(ctx, format!("/images/{}", filename))
-                    .unwrap_or_else(|_| panic!("Cannot load {}", filename)),
-            ),
-        );
+    /// Keep an asset around under a name, taking the place of whatever was there before. Handles
+    /// to the old asset get the new one.
+    fn insert(&self, name: String, asset: Asset) {
+        let mut assets = self.assets.borrow_mut();
+        let mut names = self.names.borrow_mut();
+
+        log::debug!("Loaded {}", name);
+
+        match names.get(&name) {
+            Some(&id) => assets[id].1 = asset,
+            None => {
+                names.insert(name.clone(), assets.len());
+                assets.push((name, asset));
+            }
+        }
     }
 
-    pub fn load_font(&mut self, ctx: &mut Context, filename: Cow<'_, str>) {
-        self.assets.insert(
-            filename.to_string(),
-            Asset::Font(
-                Font::new(ctx, format!("/fonts/{}", filename))
-                    .unwrap_or_else(|_| panic!("Cannot load {}", filename)),
-            ),
-        );
+    pub fn load_image(&self, ctx: &mut Context, filename: Cow<'_, str>) -> FerrisResult<()> {
+        let image = Image::new(ctx, format!("/images/{}", filename))?;
+
+        self.insert(filename.to_string(), Asset::Image(image));
+
+        Ok(())
     }
 
-    pub fn load_sound(&mut self, ctx: &mut Context, filename: Cow<'_, str>) {
-        self.assets.insert(
-            filename.to_string(),
-            Asset::Audio(Mutex::new(
-                Source::new(ctx, format!("/audio/{}", filename))
-                    .unwrap_or_else(|_| panic!("Cannot load {}", filename)),
-            )),
-        );
+    /// Load an atlas from its description, making every region in it available as a sprite.
+    pub fn load_atlas(&self, ctx: &mut Context, filename: Cow<'_, str>) -> FerrisResult<()> {
+        let mut file = ggez::filesystem::open(ctx, format!("/atlases/{}", filename))?;
+        let mut buffer = String::new();
+
+        file.read_to_string(&mut buffer)?;
+
+        let atlas: AtlasFile = toml::from_str(&buffer)?;
+        let image = Image::new(ctx, format!("/atlases/{}", atlas.image))?;
+        let (width, height) = (image.width() as f32, image.height() as f32);
+
+        for (name, src) in atlas.sources(width, height) {
+            let sprite = Sprite {
+                image: image.clone(),
+                texture: atlas.image.clone(),
+                src,
+            };
+
+            self.insert(name, Asset::Sprite(sprite));
+        }
+
+        Ok(())
     }
 
-    pub fn load_file(&mut self, ctx: &mut Context, folder: &str, filename: Cow<'_, str>) {
+    pub fn load_font(&mut self, ctx: &mut Context, filename: Cow<'_, str>) -> FerrisResult<()> {
+        let font = Font::new(ctx, format!("/fonts/{}", filename))?;
+
+        self.insert(filename.to_string(), Asset::Font(font));
+
+        Ok(())
+    }
+
+    pub fn load_sound(&mut self, ctx: &mut Context, filename: Cow<'_, str>) -> FerrisResult<()> {
+        self.audio.load(ctx, &filename)
+    }
+
+    pub fn load_music(&mut self, ctx: &mut Context, filename: Cow<'_, str>) -> FerrisResult<()> {
+        self.audio.load_music(ctx, &filename)
+    }
+
+    pub fn load_file(
+        &self,
+        ctx: &mut Context,
+        folder: &str,
+        filename: Cow<'_, str>,
+    ) -> FerrisResult<()> {
         let path = format!("/{}/{}", folder, filename);
 
-        let mut file = ggez::filesystem::open(ctx, &path).unwrap();
+        let mut file = ggez::filesystem::open(ctx, &path)?;
         let mut buffer = String::new();
 
-        file.read_to_string(&mut buffer).unwrap();
+        file.read_to_string(&mut buffer)?;
+        self.insert(path, Asset::File(buffer));
 
-        self.assets.insert(path, Asset::File(buffer));
+        Ok(())
     }
 
-    pub fn get_image(&self, filename: &str) -> Image {
-        match self.assets.get(&filename.to_string()).unwrap() {
-            Asset::Image(image) => image.to_owned(),
-            _ => panic!(),
+    /// Load a file from the resources again after it changed. Handles to the old version get the
+    /// new one, and sounds and music aren't reloaded.
+    #[cfg(feature = "debug")]
+    pub fn reload(&self, ctx: &mut Context, folder: &str, filename: &str) -> FerrisResult<()> {
+        match folder {
+            "images" => self.load_image(ctx, filename.into()),
+            "atlases" => self.load_atlas(ctx, filename.into()),
+            "maps" | "arenas" | "dialogues" | "scripts" | "achievements" | "lang" | "cutscenes"
+            | "remixes" | "demos" => self.load_file(ctx, folder, filename.into()),
+            _ => Ok(()),
         }
     }
 
+    /// A handle to the asset loaded as `name`, if there's one of the right kind.
+    pub fn handle<T: AssetKind>(&self, name: &str) -> Result<Handle<T>, AssetError> {
+        let id = *self
+            .names
+            .borrow()
+            .get(name)
+            .ok_or_else(|| AssetError::Missing {
+                name: name.to_string(),
+                kind: T::KIND,
+            })?;
+
+        match T::from_asset(name, &self.assets.borrow()[id].1) {
+            Some(_) => Ok(Handle {
+                id,
+                kind: PhantomData,
+            }),
+            None => Err(AssetError::WrongKind {
+                name: name.to_string(),
+                kind: T::KIND,
+            }),
+        }
+    }
+
+    pub fn get<T: AssetKind>(&self, handle: Handle<T>) -> T {
+        let assets = self.assets.borrow();
+        let (name, asset) = &assets[handle.id];
+
+        T::from_asset(name, asset).expect("Handles only point at assets of their kind")
+    }
+
+    /// A handle to an asset that has to be there. Everything gets loaded up front, so a missing
+    /// asset is a bug. It gets logged, and the stand-in of the kind takes the asset's place.
+    pub fn expect_handle<T: AssetKind>(&self, name: &str) -> Handle<T> {
+        self.handle(name).unwrap_or_else(|error| {
+            log::error!("{}", error);
+
+            Handle {
+                id: T::PLACEHOLDER,
+                kind: PhantomData,
+            }
+        })
+    }
+
+    fn expect<T: AssetKind>(&self, name: &str) -> T {
+        self.get(self.expect_handle(name))
+    }
+
+    pub fn get_image(&self, filename: &str) -> Image {
+        self.expect(filename)
+    }
+
+    /// A region of an atlas, or a whole image.
+    pub fn get_sprite(&self, name: &str) -> Sprite {
+        self.expect(name)
+    }
+
     pub fn get_font(&self, filename: &str) -> Font {
-        match self.assets.get(&filename.to_string()).unwrap() {
-            Asset::Font(font) => font.to_owned(),
-            _ => panic!(),
+        self.expect(filename)
+    }
+
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    /// Read the languages again, and show the text in the one with `code`.
+    pub fn set_language(&self, code: &str) {
+        let mut localization = Localization::load(self);
+
+        localization.set_language(code);
+
+        if let Some(font) = &localization.language().font {
+            if let Err(error) = self.handle::<Font>(font) {
+                log::warn!("Cannot draw in the font of the language: {}", error);
+            }
         }
+
+        *self.localization.borrow_mut() = localization;
+    }
+
+    pub fn localization(&self) -> Ref<'_, Localization> {
+        self.localization.borrow()
+    }
+
+    /// The `english` text in the language picked in the settings.
+    pub fn text(&self, english: &str) -> String {
+        self.localization.borrow().text(english).to_string()
+    }
+
+    /// The `english` text in the language picked in the settings, with the values filled in.
+    pub fn text_with(&self, english: &str, values: &[(&str, &dyn Display)]) -> String {
+        self.localization.borrow().text_with(english, values)
     }
 
-    pub fn get_sound(&self, filename: &str) -> &Mutex<Source> {
-        match self.assets.get(&filename.to_string()).unwrap() {
-            Asset::Audio(audio) => audio,
-            _ => panic!(),
+    /// The font to draw text in, which is the one of the language if it brings one along.
+    pub fn text_font(&self) -> Font {
+        let font = self.localization.borrow().language().font.clone();
+
+        match font.and_then(|font| self.handle(&font).ok()) {
+            Some(font) => self.get(font),
+            None => self.get_font("Consolas.ttf"),
         }
     }
 
     pub fn get_file(&self, filename: &str) -> String {
-        match self.assets.get(&filename.to_string()).unwrap() {
-            Asset::File(file) => file.to_owned(),
-            _ => panic!(),
+        self.expect(filename)
+    }
+
+    /// The file in the language picked in the settings, which is the one with the code of the
+    /// language before its extension, like `01_intro.de.dlg`. Languages that don't have the file
+    /// get the one in English.
+    pub fn get_translated_file(&self, filename: &str) -> String {
+        let code = self.localization.borrow().language().code.clone();
+        let translated = match filename.rsplit_once('.') {
+            Some((name, extension)) => format!("{}.{}.{}", name, code, extension),
+            None => format!("{}.{}", filename, code),
+        };
+
+        match self.handle(&translated) {
+            Ok(file) => self.get(file),
+            Err(_) => self.get_file(filename),
         }
     }
+
+    /// The names of all of the files loaded from the provided folder, in alphabetical order.
+    pub fn get_files(&self, folder: &str) -> Vec<String> {
+        let prefix = format!("/{}/", folder);
+
+        let mut files = self
+            .assets
+            .borrow()
+            .iter()
+            .filter_map(|(path, asset)| match asset {
+                Asset::File(_) => path.strip_prefix(&prefix).map(String::from),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        files.sort();
+        files
+    }
 }
 
 pub struct ParticleSystem {
@@ -143,8 +531,8 @@ impl ParticleSystem {
         amount: usize,
         min: na::Point2<f32>,
         max: na::Point2<f32>,
+        rng: &mut impl Rng,
     ) -> Self {
-        let rng = &mut rand::thread_rng();
         let mut particles = vec![];
 
         for _ in 0..amount {
@@ -178,17 +566,23 @@ impl ParticleSystem {
             lifetime: 2.0,
         }
     }
+}
 
-    pub fn draw(
-        &self,
-        ctx: &mut Context,
-        physics: &mut Physics,
-        camera: &mut Camera,
-    ) -> GameResult<()> {
+impl GameObject for ParticleSystem {
+    fn draw(&self, ctx: &mut Context, physics: &mut Physics, camera: &Camera) -> GameResult {
         for particle in &self.particles {
-            let body = physics.get_rigid_body(*particle);
-            let position = isometry_to_point(body.position());
-            let color = physics.get_user_data(*particle).get_particle_data();
+            // Particles that are gone from the world, or never were particles, don't get drawn.
+            let position = match physics.body_position(*particle) {
+                Ok(position) => position,
+                Err(_) => continue,
+            };
+            let color = match physics
+                .get_user_data(*particle)
+                .and_then(|data| data.particle_color())
+            {
+                Some(color) => color,
+                None => continue,
+            };
 
             let color = Color::new(color.r, color.g, color.b, self.lifetime);
 
@@ -215,18 +609,87 @@ impl ParticleSystem {
         Ok(())
     }
 
-    pub fn update(&mut self, ctx: &mut Context, physics: &mut Physics) -> bool {
-        self.lifetime -= timer::delta(ctx).as_secs_f32();
+    fn update(&mut self, _physics: &mut Physics, dt: f32) {
+        self.lifetime -= dt;
+    }
 
-        if self.lifetime <= 0.0 {
-            for id in 0..self.particles.len() {
-                // Destroy the particle from the world. The lifetime of the particle has been ended
-                physics.destroy_body(self.particles[id]);
-            }
+    fn is_dead(&self) -> bool {
+        self.lifetime <= 0.0
+    }
 
-            true
-        } else {
-            false
+    fn destroy(&mut self, physics: &mut Physics) {
+        for particle in &self.particles {
+            physics.destroy_body(*particle);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atlas_sources() {
+        let atlas: AtlasFile = toml::from_str(
+            "image = \"atlas.png\"\n[regions]\nleft = [0, 0, 50, 100]\nright = [50, 0, 150, 100]",
+        )
+        .unwrap();
+
+        assert_eq!(
+            atlas.sources(200.0, 100.0),
+            vec![
+                (String::from("left"), Rect::new(0.0, 0.0, 0.25, 1.0)),
+                (String::from("right"), Rect::new(0.25, 0.0, 0.75, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handles() {
+        let asset_manager = AssetManager::empty();
+
+        asset_manager.insert(
+            String::from("/maps/01.map"),
+            Asset::File(String::from("[-]")),
+        );
+
+        let handle = asset_manager.handle::<String>("/maps/01.map").unwrap();
+        assert_eq!(asset_manager.get(handle), "[-]");
+
+        assert_eq!(
+            asset_manager.handle::<Image>("/maps/01.map"),
+            Err(AssetError::WrongKind {
+                name: String::from("/maps/01.map"),
+                kind: "image"
+            })
+        );
+        assert_eq!(
+            asset_manager.handle::<String>("/maps/02.map"),
+            Err(AssetError::Missing {
+                name: String::from("/maps/02.map"),
+                kind: "file"
+            })
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let asset_manager = AssetManager::empty();
+
+        // Images need a context, any asset does for taking up their place.
+        asset_manager.insert(String::from("<missing image>"), Asset::File(String::new()));
+        asset_manager.insert(String::from("<missing font>"), Asset::Font(Font::default()));
+        asset_manager.insert(String::from("<missing file>"), Asset::File(String::new()));
+        asset_manager.insert(
+            String::from("/maps/01.map"),
+            Asset::File(String::from("[-]")),
+        );
+
+        assert_eq!(asset_manager.get_file("/maps/01.map"), "[-]");
+        assert_eq!(asset_manager.get_file("/maps/02.map"), "");
+        assert_eq!(
+            asset_manager.expect_handle::<Font>("Missing.ttf"),
+            asset_manager.handle::<Font>("<missing font>").unwrap()
+        );
+    }
+}